@@ -0,0 +1,305 @@
+//! Generates the Go host bindings of WebAssembly Components from the build
+//! scripts of Rust crates, so the bindings are kept fresh as the components
+//! change.
+//!
+//! The guest is built as usual, e.g. with `cargo build --target
+//! wasm32-unknown-unknown --release`, and the build script of another crate of
+//! the workspace generates its bindings by running `gravity`, which is looked
+//! up in the `GRAVITY` environment variable, then in the `PATH`:
+//!
+//! ```no_run
+//! // In the `main` function of build.rs
+//! arcjet_gravity_build::generate_in_build_rs("example-basic", "go/basic/basic.go")
+//!     .expect("failed to generate the Go bindings");
+//! ```
+//!
+//! The Wasm of the guest is found in the target directory of the workspace,
+//! and the build script runs again whenever it changes. Cargo doesn't build
+//! the guest before the build script, so it has to be built beforehand.
+
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+};
+
+/// The environment variable giving the path of the `gravity` executable.
+pub const GRAVITY: &str = "GRAVITY";
+
+/// Generates the bindings of the guest crate `name` into `output`, from its
+/// Wasm built in release mode for `wasm32-unknown-unknown`, returning the
+/// path of the bindings.
+///
+/// See [`Build`] to change any of these, or to pass more options to gravity.
+pub fn generate_in_build_rs(name: &str, output: impl Into<PathBuf>) -> Result<PathBuf, Error> {
+    Build::new(name).output(output).generate()
+}
+
+/// The generation of the bindings of a guest crate by a build script.
+#[derive(Debug, Clone)]
+pub struct Build {
+    name: String,
+    wasm: Option<PathBuf>,
+    target: String,
+    profile: String,
+    world: Option<String>,
+    output: Option<PathBuf>,
+    args: Vec<OsString>,
+    gravity: Option<PathBuf>,
+}
+
+impl Build {
+    /// Creates the generation of the bindings of the guest crate `name`, whose
+    /// Wasm is built in release mode for `wasm32-unknown-unknown`.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            wasm: None,
+            target: "wasm32-unknown-unknown".to_string(),
+            profile: "release".to_string(),
+            world: None,
+            output: None,
+            args: Vec::new(),
+            gravity: None,
+        }
+    }
+
+    /// Sets the path of the Wasm file, rather than looking it up in the target
+    /// directory of the workspace.
+    pub fn wasm(mut self, path: impl Into<PathBuf>) -> Self {
+        self.wasm = Some(path.into());
+        self
+    }
+
+    /// Sets the target the guest is built for, `wasm32-unknown-unknown` by
+    /// default.
+    pub fn target(mut self, target: &str) -> Self {
+        self.target = target.to_string();
+        self
+    }
+
+    /// Sets the profile the guest is built with, `release` by default.
+    pub fn profile(mut self, profile: &str) -> Self {
+        self.profile = profile.to_string();
+        self
+    }
+
+    /// Sets the world to generate the bindings of, rather than the primary
+    /// world of the component.
+    pub fn world(mut self, world: &str) -> Self {
+        self.world = Some(world.to_string());
+        self
+    }
+
+    /// Sets the path of the generated Go file, which defaults to the name of
+    /// the crate in `OUT_DIR`. Relative paths are relative to the crate of the
+    /// build script.
+    pub fn output(mut self, path: impl Into<PathBuf>) -> Self {
+        self.output = Some(path.into());
+        self
+    }
+
+    /// Passes another option to gravity, e.g. `--initialisms`.
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Sets the path of the `gravity` executable, rather than reading it from
+    /// the `GRAVITY` environment variable or looking it up in the `PATH`.
+    pub fn gravity(mut self, path: impl Into<PathBuf>) -> Self {
+        self.gravity = Some(path.into());
+        self
+    }
+
+    /// Generates the bindings, returning the path of the Go file.
+    ///
+    /// This tells Cargo to run the build script again when the Wasm file or
+    /// the `GRAVITY` environment variable changes.
+    pub fn generate(&self) -> Result<PathBuf, Error> {
+        let dir = match env::var_os("CARGO_MANIFEST_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => env::current_dir().map_err(Error::Io)?,
+        };
+        let wasm = match &self.wasm {
+            Some(wasm) => dir.join(wasm),
+            None => {
+                let target_dir = env::var_os("CARGO_TARGET_DIR")
+                    .map(|target_dir| dir.join(target_dir))
+                    .unwrap_or_else(|| target_dir(&dir));
+                self.wasm_path(&target_dir)
+            }
+        };
+        let output = match &self.output {
+            Some(output) => dir.join(output),
+            None => {
+                let out_dir = env::var_os("OUT_DIR").ok_or(Error::MissingOutDir)?;
+                Path::new(&out_dir).join(format!("{}.go", self.name.replace('-', "_")))
+            }
+        };
+
+        println!("cargo:rerun-if-changed={}", wasm.display());
+        println!("cargo:rerun-if-env-changed={GRAVITY}");
+        if !wasm.is_file() {
+            return Err(Error::MissingWasm(wasm));
+        }
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent).map_err(Error::Io)?;
+        }
+
+        let mut command = self.command(&wasm, &output);
+        let status = command
+            .status()
+            .map_err(|err| Error::Spawn(PathBuf::from(command.get_program()), err))?;
+        if !status.success() {
+            return Err(Error::Failed(status));
+        }
+        Ok(output)
+    }
+
+    /// Returns the path of the Wasm built for the target in `target_dir`,
+    /// named like Cargo names the `cdylib` of the crate.
+    fn wasm_path(&self, target_dir: &Path) -> PathBuf {
+        target_dir
+            .join(&self.target)
+            .join(&self.profile)
+            .join(format!("{}.wasm", self.name.replace('-', "_")))
+    }
+
+    /// Returns the command running gravity on the Wasm file.
+    fn command(&self, wasm: &Path, output: &Path) -> Command {
+        let gravity = self
+            .gravity
+            .clone()
+            .or_else(|| env::var_os(GRAVITY).map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from("gravity"));
+        let mut command = Command::new(gravity);
+        if let Some(world) = &self.world {
+            command.arg("--world").arg(world);
+        }
+        command
+            .args(&self.args)
+            .arg("--output")
+            .arg(output)
+            .arg(wasm);
+        command
+    }
+}
+
+/// Returns the target directory of the workspace of the crate in `dir`, i.e.
+/// the `target` directory next to its `Cargo.lock`, or next to its manifest if
+/// it has no lockfile yet.
+fn target_dir(dir: &Path) -> PathBuf {
+    dir.ancestors()
+        .find(|dir| dir.join("Cargo.lock").is_file())
+        .unwrap_or(dir)
+        .join("target")
+}
+
+/// An error generating the bindings.
+#[derive(Debug)]
+pub enum Error {
+    /// The Wasm file doesn't exist, e.g. as the guest isn't built yet.
+    MissingWasm(PathBuf),
+    /// No output was given, and Cargo didn't set `OUT_DIR`, as it does when
+    /// running build scripts.
+    MissingOutDir,
+    /// The `gravity` executable couldn't be run.
+    Spawn(PathBuf, io::Error),
+    /// Gravity failed to generate the bindings. Its errors are printed to
+    /// stderr, which Cargo shows when the build script fails.
+    Failed(ExitStatus),
+    /// The directory of the output couldn't be created.
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingWasm(path) => write!(
+                f,
+                "missing Wasm file `{}`, build the guest before the crate generating its bindings",
+                path.display()
+            ),
+            Error::MissingOutDir => write!(f, "no output given, and `OUT_DIR` isn't set"),
+            Error::Spawn(path, err) => write!(
+                f,
+                "failed to run `{}`, install it with `cargo install arcjet-gravity` or set `{GRAVITY}`: {err}",
+                path.display()
+            ),
+            Error::Failed(status) => write!(f, "gravity failed: {status}"),
+            Error::Io(err) => write!(f, "failed to create the output directory: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Spawn(_, err) | Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, ffi::OsStr, fs, path::Path};
+
+    use super::{Build, target_dir};
+
+    #[test]
+    fn test_target_dir() {
+        let root = env::temp_dir().join(format!("gravity-build-{}", std::process::id()));
+        let dir = root.join("crates").join("host");
+        fs::create_dir_all(&dir).unwrap();
+
+        // Without a lockfile, the target directory is next to the manifest
+        assert_eq!(target_dir(&dir), dir.join("target"));
+        // Otherwise it's the one of the workspace
+        fs::write(root.join("Cargo.lock"), "").unwrap();
+        assert_eq!(target_dir(&dir), root.join("target"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_wasm_path() {
+        let target_dir = Path::new("/workspace/target");
+        assert_eq!(
+            Build::new("example-basic").wasm_path(target_dir),
+            target_dir.join("wasm32-unknown-unknown/release/example_basic.wasm")
+        );
+        assert_eq!(
+            Build::new("example-basic")
+                .target("wasm32-wasip1")
+                .profile("debug")
+                .wasm_path(target_dir),
+            target_dir.join("wasm32-wasip1/debug/example_basic.wasm")
+        );
+    }
+
+    #[test]
+    fn test_command() {
+        let command = Build::new("example-basic")
+            .world("basic")
+            .arg("--initialisms")
+            .gravity("/bin/gravity")
+            .command(Path::new("basic.wasm"), Path::new("basic/basic.go"));
+
+        assert_eq!(command.get_program(), OsStr::new("/bin/gravity"));
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            [
+                "--world",
+                "basic",
+                "--initialisms",
+                "--output",
+                "basic/basic.go",
+                "basic.wasm"
+            ]
+        );
+    }
+}
@@ -0,0 +1,433 @@
+//! Differential tests of the canonical ABI implemented by the generated Go.
+//!
+//! Each case calls an export of one of the examples with the given
+//! arguments, both through the Go bindings generated by gravity, hosted by
+//! Wazero, and through the component API of Wasmtime, the reference
+//! implementation of the Component Model. The outcomes of the calls must be
+//! the same, which catches mistakes in how the bindings lay out values in
+//! the guest's memory, e.g. the alignment and padding of records.
+//!
+//! Values are exchanged as JSON, which both sides convert to and from their
+//! representations of the types of the export:
+//!
+//! - numbers are JSON numbers, as are `char`s, given as their code points, as
+//!   Go can't tell a `rune` from an `int32`;
+//! - `string`s are JSON strings, and `list`s are arrays, including
+//!   `list<u8>`;
+//! - `record`s are objects keyed by the names of their fields, and `tuple`s
+//!   are objects keyed by `f0`, `f1`, etc, like the fields of the Go structs
+//!   of named tuples.
+//!
+//! The keys of objects are compared case-insensitively, ignoring dashes and
+//! underscores, as the Go structs name their fields `FooBar` for `foo-bar`.
+
+use std::{fs, path::Path, process::Command};
+
+use serde::Deserialize;
+use serde_json::{Map, Number, Value};
+use wasmtime::{
+    Engine, Store,
+    component::{Component, Linker, Type, Val},
+};
+
+/// The source of the Go program calling the exports through the bindings.
+const DRIVER: &str = include_str!("driver.go.tmpl");
+
+/// A call of an export with the given arguments.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Case {
+    /// The name of the export, qualified by its interface as in
+    /// `ns:pkg/name#function` for a function of an exported interface.
+    pub export: String,
+    pub args: Vec<Value>,
+}
+
+/// The outcome of a call: either its results, or an error if it failed,
+/// e.g. because the guest trapped. Errors are only compared by their
+/// presence, as each runtime words them differently.
+pub type Outcome = Result<Vec<Value>, String>;
+
+/// Reads the cases of the file, a JSON array of cases.
+pub fn read_cases(path: &Path) -> Result<Vec<Case>, String> {
+    let json =
+        fs::read_to_string(path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    serde_json::from_str(&json).map_err(|e| format!("failed to parse {}: {e}", path.display()))
+}
+
+/// Runs the cases against the core module with Wasmtime, once encoded as a
+/// component.
+///
+/// The imports of the component are defined as functions trapping when
+/// called, so only the exports not calling their imports can be compared.
+pub fn run_wasmtime(module: &[u8], cases: &[Case]) -> Result<Vec<Outcome>, String> {
+    let component = wit_component::ComponentEncoder::default()
+        .module(module)
+        .and_then(|encoder| encoder.validate(true).encode())
+        .map_err(|e| format!("failed to encode the component: {e:?}"))?;
+    let engine = Engine::default();
+    let component = Component::new(&engine, &component)
+        .map_err(|e| format!("failed to compile the component: {e:?}"))?;
+    let mut linker = Linker::<()>::new(&engine);
+    linker
+        .define_unknown_imports_as_traps(&component)
+        .map_err(|e| format!("failed to define the imports: {e:?}"))?;
+    let mut store = Store::new(&engine, ());
+    let instance = linker
+        .instantiate(&mut store, &component)
+        .map_err(|e| format!("failed to instantiate the component: {e:?}"))?;
+
+    let mut outcomes = Vec::new();
+    for case in cases {
+        let (interface, name) = match case.export.split_once('#') {
+            Some((interface, name)) => (Some(interface), name),
+            None => (None, case.export.as_str()),
+        };
+        let interface = interface.map(|interface| {
+            instance
+                .get_export_index(&mut store, None, interface)
+                .ok_or_else(|| format!("missing exported interface {interface}"))
+        });
+        let interface = interface.transpose()?;
+        let func = instance
+            .get_export_index(&mut store, interface.as_ref(), name)
+            .and_then(|index| instance.get_func(&mut store, index))
+            .ok_or_else(|| format!("missing export {}", case.export))?;
+
+        let ty = func.ty(&store);
+        let params = ty.params().collect::<Vec<_>>();
+        if params.len() != case.args.len() {
+            return Err(format!(
+                "{} takes {} arguments, but got {}",
+                case.export,
+                params.len(),
+                case.args.len()
+            ));
+        }
+        let args = params
+            .iter()
+            .zip(&case.args)
+            .map(|((_, typ), arg)| to_val(typ, arg))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("invalid arguments of {}: {e}", case.export))?;
+        let mut results = vec![Val::Bool(false); ty.results().len()];
+        let outcome = match func.call(&mut store, &args, &mut results) {
+            Ok(()) => {
+                func.post_return(&mut store)
+                    .map_err(|e| format!("failed to clean up after {}: {e:?}", case.export))?;
+                results.iter().map(from_val).collect()
+            }
+            Err(e) => Err(format!("{e:?}")),
+        };
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
+
+/// Converts a JSON value to a value of the WIT type.
+fn to_val(typ: &Type, value: &Value) -> Result<Val, String> {
+    let unexpected = || format!("expected a value of type {typ:?}, but got {value}");
+    let int = || value.as_i64().ok_or_else(unexpected);
+    let uint = || value.as_u64().ok_or_else(unexpected);
+    let out_of_range = |_| unexpected();
+    Ok(match typ {
+        Type::Bool => Val::Bool(value.as_bool().ok_or_else(unexpected)?),
+        Type::S8 => Val::S8(int()?.try_into().map_err(out_of_range)?),
+        Type::U8 => Val::U8(uint()?.try_into().map_err(out_of_range)?),
+        Type::S16 => Val::S16(int()?.try_into().map_err(out_of_range)?),
+        Type::U16 => Val::U16(uint()?.try_into().map_err(out_of_range)?),
+        Type::S32 => Val::S32(int()?.try_into().map_err(out_of_range)?),
+        Type::U32 => Val::U32(uint()?.try_into().map_err(out_of_range)?),
+        Type::S64 => Val::S64(int()?),
+        Type::U64 => Val::U64(uint()?),
+        Type::Float32 => Val::Float32(value.as_f64().ok_or_else(unexpected)? as f32),
+        Type::Float64 => Val::Float64(value.as_f64().ok_or_else(unexpected)?),
+        Type::Char => Val::Char(
+            uint()?
+                .try_into()
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(unexpected)?,
+        ),
+        Type::String => Val::String(value.as_str().ok_or_else(unexpected)?.to_string()),
+        Type::List(list) => Val::List(
+            value
+                .as_array()
+                .ok_or_else(unexpected)?
+                .iter()
+                .map(|element| to_val(&list.ty(), element))
+                .collect::<Result<_, _>>()?,
+        ),
+        Type::Record(record) => {
+            let object = value.as_object().ok_or_else(unexpected)?;
+            Val::Record(
+                record
+                    .fields()
+                    .map(|field| {
+                        let value = field_of(object, field.name)
+                            .ok_or_else(|| format!("missing field {}", field.name))?;
+                        Ok((field.name.to_string(), to_val(&field.ty, value)?))
+                    })
+                    .collect::<Result<_, String>>()?,
+            )
+        }
+        Type::Tuple(tuple) => {
+            let object = value.as_object().ok_or_else(unexpected)?;
+            Val::Tuple(
+                tuple
+                    .types()
+                    .enumerate()
+                    .map(|(i, typ)| {
+                        let value = field_of(object, &format!("f{i}"))
+                            .ok_or_else(|| format!("missing element f{i}"))?;
+                        to_val(&typ, value)
+                    })
+                    .collect::<Result<_, _>>()?,
+            )
+        }
+        typ => return Err(format!("values of type {typ:?} aren't supported")),
+    })
+}
+
+/// Converts a WIT value to JSON.
+fn from_val(val: &Val) -> Result<Value, String> {
+    Ok(match val {
+        Val::Bool(b) => Value::Bool(*b),
+        Val::S8(n) => Value::from(*n),
+        Val::U8(n) => Value::from(*n),
+        Val::S16(n) => Value::from(*n),
+        Val::U16(n) => Value::from(*n),
+        Val::S32(n) => Value::from(*n),
+        Val::U32(n) => Value::from(*n),
+        Val::S64(n) => Value::from(*n),
+        Val::U64(n) => Value::from(*n),
+        Val::Float32(n) => float(f64::from(*n))?,
+        Val::Float64(n) => float(*n)?,
+        Val::Char(c) => Value::from(u32::from(*c)),
+        Val::String(s) => Value::String(s.clone()),
+        Val::List(values) => Value::Array(values.iter().map(from_val).collect::<Result<_, _>>()?),
+        Val::Record(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, val)| Ok((normalize(name), from_val(val)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        Val::Tuple(values) => Value::Object(
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, val)| Ok((format!("f{i}"), from_val(val)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        val => return Err(format!("values like {val:?} aren't supported")),
+    })
+}
+
+/// Converts a float to JSON, which can't represent NaN nor the infinities.
+fn float(n: f64) -> Result<Value, String> {
+    Number::from_f64(n)
+        .map(Value::Number)
+        .ok_or_else(|| format!("{n} can't be represented in JSON"))
+}
+
+/// Returns the field of the object with the given name, once normalized.
+fn field_of<'a>(object: &'a Map<String, Value>, name: &str) -> Option<&'a Value> {
+    let name = normalize(name);
+    object
+        .iter()
+        .find(|(key, _)| normalize(key) == name)
+        .map(|(_, value)| value)
+}
+
+/// Normalizes the name of a field, e.g. `foo-bar` and `FooBar` to `foobar`.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Returns true if the JSON values are the same, once the keys of objects
+/// are normalized. Numbers are compared by value, e.g. `1` and `1.0` are the
+/// same.
+pub fn same(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => match (a.as_i128(), b.as_i128()) {
+            (Some(a), Some(b)) => a == b,
+            _ => a.as_f64() == b.as_f64(),
+        },
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| field_of(b, key).is_some_and(|b| same(a, b)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+/// Returns true if the outcomes are the same: both results are the same,
+/// or both calls failed.
+pub fn same_outcome(a: &Outcome, b: &Outcome) -> bool {
+    match (a, b) {
+        (Ok(a), Ok(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b)),
+        (Err(_), Err(_)) => true,
+        _ => false,
+    }
+}
+
+/// A world whose bindings are generated by gravity, and called by a Go
+/// program.
+pub struct GoHost<'a> {
+    /// The manifest of gravity, which is run with Cargo.
+    pub gravity_manifest: &'a Path,
+    /// The core module of the component.
+    pub wasm: &'a Path,
+    pub world: &'a str,
+    /// The directory of the scratch Go module the program is built in.
+    pub dir: &'a Path,
+}
+
+impl GoHost<'_> {
+    /// Runs the cases through the Go bindings of the world, hosted by
+    /// Wazero.
+    ///
+    /// The bindings are generated with `--dynamic-exports`, so that a single
+    /// program calls the exports of every world by name. The program decodes
+    /// the arguments into the types of the parameters of the export, as
+    /// given by `Exports()`, then encodes its results back into JSON. Imports
+    /// are stubbed, as with Wasmtime.
+    pub fn run(&self, cases: &[Case]) -> Result<Vec<Outcome>, String> {
+        let package = self.world.replace('-', "_");
+        let bindings = self.dir.join(&package);
+        fs::create_dir_all(&bindings).map_err(|e| format!("failed to create the module: {e}"))?;
+        let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+        run(
+            self.dir,
+            Command::new(cargo)
+                .args(["run", "--quiet", "--manifest-path"])
+                .arg(self.gravity_manifest)
+                .args(["--bin", "gravity", "--"])
+                .args(["--dynamic-exports", "--imports-struct"])
+                .args(["--allow-missing-imports", "panic"])
+                .args(["--world", self.world, "--output"])
+                .arg(bindings.join("bindings.go"))
+                .arg(self.wasm),
+            None,
+        )?;
+
+        let world = pascal_case(self.world);
+        let driver = DRIVER
+            .replace("{{package}}", &package)
+            .replace("{{world}}", &world);
+        write(&self.dir.join("main.go"), &driver)?;
+        write(&self.dir.join("go.mod"), "module conformance\n\ngo 1.24\n")?;
+        run(self.dir, Command::new("go").args(["mod", "tidy"]), None)?;
+
+        let input = serde_json::to_string(&cases_json(cases)).expect("failed to encode the cases");
+        let output = run(
+            self.dir,
+            Command::new("go").args(["run", "."]),
+            Some(&input),
+        )?;
+        output
+            .lines()
+            .map(|line| {
+                let outcome = serde_json::from_str::<Value>(line)
+                    .map_err(|e| format!("invalid outcome {line}: {e}"))?;
+                Ok(match outcome.get("error").and_then(Value::as_str) {
+                    Some(error) => Err(error.to_string()),
+                    None => Ok(match outcome.get("results") {
+                        Some(Value::Array(results)) => results.clone(),
+                        _ => Vec::new(),
+                    }),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The cases, as read by the Go program.
+fn cases_json(cases: &[Case]) -> Value {
+    Value::Array(
+        cases
+            .iter()
+            .map(|case| {
+                serde_json::json!({
+                    "export": case.export,
+                    "args": case.args,
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Converts a WIT name to the Go identifier used by the bindings, e.g.
+/// `iface-method` to `IfaceMethod`.
+fn pascal_case(name: &str) -> String {
+    name.split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| format!("failed to write {}: {e}", path.display()))
+}
+
+/// Runs the command in `dir` with the given input, returning its output, or
+/// its errors on failure.
+fn run(dir: &Path, command: &mut Command, input: Option<&str>) -> Result<String, String> {
+    use std::{io::Write, process::Stdio};
+
+    let mut child = command
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run {command:?}: {e}"))?;
+    if let Some(input) = input {
+        let mut stdin = child.stdin.take().expect("missing stdin");
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| format!("failed to write to {command:?}: {e}"))?;
+    }
+    drop(child.stdin.take());
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("failed to run {command:?}: {e}"))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{command:?} failed:\n{stdout}{stderr}"));
+    }
+    Ok(stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{pascal_case, same};
+
+    #[test]
+    fn test_same() {
+        assert!(same(&json!([1, 2.0]), &json!([1.0, 2])));
+        assert!(same(&json!({"foo-bar": 1}), &json!({"FooBar": 1})));
+        assert!(!same(&json!({"a": 1}), &json!({"a": 1, "b": 2})));
+        assert!(!same(&json!(u64::MAX), &json!(u64::MAX - 1)));
+    }
+
+    #[test]
+    fn test_pascal_case() {
+        assert_eq!(pascal_case("iface-method"), "IfaceMethod");
+        assert_eq!(pascal_case("records"), "Records");
+    }
+}
@@ -0,0 +1,81 @@
+//! Compares the outcomes of the cases of each example, run through the Go
+//! bindings and through Wasmtime.
+//!
+//! The examples must be built first, with
+//! `cargo build -p example-<name> --target wasm32-unknown-unknown --release`.
+//! The Go side is skipped when there is no Go toolchain, in which case the
+//! cases are only run through Wasmtime.
+
+use std::{path::PathBuf, process::Command};
+
+use arcjet_gravity_conformance::{GoHost, read_cases, run_wasmtime, same_outcome};
+
+/// The examples compared, and their worlds. Only the exports which don't
+/// call their imports can be compared, as imports are stubbed.
+const EXAMPLES: &[(&str, &str)] = &[("instructions", "instructions"), ("records", "records")];
+
+/// Returns true if the `go` command is available.
+fn has_go() -> bool {
+    Command::new("go")
+        .arg("version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+#[test]
+fn test_conformance() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let root = manifest_dir.join("../../..");
+    let go = has_go();
+    if !go {
+        eprintln!("skipping the Go bindings, as there is no Go toolchain");
+    }
+
+    let mut failures = Vec::new();
+    for (example, world) in EXAMPLES {
+        let wasm = root.join(format!(
+            "target/wasm32-unknown-unknown/release/example_{}.wasm",
+            example.replace('-', "_")
+        ));
+        let module = std::fs::read(&wasm).unwrap_or_else(|e| {
+            panic!(
+                "failed to read {}, build it with `cargo build -p example-{example} --target wasm32-unknown-unknown --release`: {e}",
+                wasm.display()
+            )
+        });
+        let cases = read_cases(&manifest_dir.join(format!("cases/{example}.json"))).unwrap();
+        let reference = run_wasmtime(&module, &cases).unwrap();
+        for (case, outcome) in cases.iter().zip(&reference) {
+            if let Err(e) = outcome {
+                failures.push(format!(
+                    "{example}: {} failed with Wasmtime: {e}",
+                    case.export
+                ));
+            }
+        }
+        if !go {
+            continue;
+        }
+
+        let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("conformance-{example}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let host = GoHost {
+            gravity_manifest: &manifest_dir.join("../Cargo.toml"),
+            wasm: &wasm,
+            world,
+            dir: &dir,
+        };
+        let outcomes = host.run(&cases).unwrap();
+        assert_eq!(outcomes.len(), cases.len(), "missing outcomes of {example}");
+        for ((case, expected), actual) in cases.iter().zip(&reference).zip(&outcomes) {
+            if !same_outcome(expected, actual) {
+                failures.push(format!(
+                    "{example}: {}({:?}) returned {actual:?} through Go, but {expected:?} through Wasmtime",
+                    case.export, case.args
+                ));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
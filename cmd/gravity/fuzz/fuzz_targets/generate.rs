@@ -0,0 +1,237 @@
+//! Generates the bindings of arbitrary worlds, which must never panic.
+//!
+//! The fuzzer builds a WIT document out of the types and functions below, so
+//! nearly every input is valid WIT, and generates the bindings of its world.
+//! Documents which are rejected by the resolver are skipped, as these would
+//! be reported as errors by gravity, but any panic, e.g. a remaining `todo!`,
+//! is a crash.
+//!
+//! Run it from this directory with `cargo +nightly fuzz run generate`.
+
+#![no_main]
+
+use std::fmt::Write;
+
+use arbitrary::Arbitrary;
+use arcjet_gravity::{
+    codegen::{Bindings, disambiguate},
+    compat::wit_parser::{Resolve, SizeAlign},
+};
+use libfuzzer_sys::fuzz_target;
+
+/// The most types, fields, cases, functions or parameters of each kind, so
+/// that the documents stay small enough to be generated quickly.
+const MAX_ITEMS: usize = 6;
+
+/// The deepest nesting of anonymous types.
+const MAX_DEPTH: usize = 4;
+
+#[derive(Arbitrary, Debug)]
+struct Document {
+    /// The named types of the `types` interface, used by every function.
+    types: Vec<TypeDef>,
+    /// The functions of the imported `host` interface.
+    imports: Vec<Function>,
+    /// The functions of the exported `api` interface.
+    interface_exports: Vec<Function>,
+    /// The functions exported by the world itself.
+    exports: Vec<Function>,
+}
+
+#[derive(Arbitrary, Debug)]
+enum TypeDef {
+    Alias(Ty),
+    Record(Ty, Vec<Ty>),
+    Variant(Option<Ty>, Vec<Option<Ty>>),
+    Enum(u8),
+    Flags(u8),
+    Resource,
+}
+
+#[derive(Arbitrary, Debug)]
+enum Ty {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    S8,
+    S16,
+    S32,
+    S64,
+    F32,
+    F64,
+    Char,
+    String,
+    Option(Box<Ty>),
+    List(Box<Ty>),
+    Result(Option<Box<Ty>>, Option<Box<Ty>>),
+    Tuple(Box<Ty>, Vec<Ty>),
+    /// One of the named types defined before, by its index.
+    Named(u8),
+}
+
+#[derive(Arbitrary, Debug)]
+struct Function {
+    params: Vec<Ty>,
+    result: Option<Ty>,
+}
+
+impl Ty {
+    /// Renders the type, where `named` are the names of the types which can
+    /// be referred to.
+    fn wit(&self, named: &[String], depth: usize) -> String {
+        if depth > MAX_DEPTH {
+            return "u32".to_string();
+        }
+        let inner = |ty: &Ty| ty.wit(named, depth + 1);
+        match self {
+            Ty::Bool => "bool".to_string(),
+            Ty::U8 => "u8".to_string(),
+            Ty::U16 => "u16".to_string(),
+            Ty::U32 => "u32".to_string(),
+            Ty::U64 => "u64".to_string(),
+            Ty::S8 => "s8".to_string(),
+            Ty::S16 => "s16".to_string(),
+            Ty::S32 => "s32".to_string(),
+            Ty::S64 => "s64".to_string(),
+            Ty::F32 => "f32".to_string(),
+            Ty::F64 => "f64".to_string(),
+            Ty::Char => "char".to_string(),
+            Ty::String => "string".to_string(),
+            Ty::Option(ty) => format!("option<{}>", inner(ty)),
+            Ty::List(ty) => format!("list<{}>", inner(ty)),
+            Ty::Result(None, None) => "result".to_string(),
+            Ty::Result(Some(ok), None) => format!("result<{}>", inner(ok)),
+            Ty::Result(None, Some(err)) => format!("result<_, {}>", inner(err)),
+            Ty::Result(Some(ok), Some(err)) => format!("result<{}, {}>", inner(ok), inner(err)),
+            Ty::Tuple(first, rest) => {
+                let types = std::iter::once(&**first)
+                    .chain(rest.iter().take(MAX_ITEMS))
+                    .map(inner)
+                    .collect::<Vec<_>>();
+                format!("tuple<{}>", types.join(", "))
+            }
+            Ty::Named(_) if named.is_empty() => "u32".to_string(),
+            Ty::Named(index) => named[usize::from(*index) % named.len()].clone(),
+        }
+    }
+}
+
+impl TypeDef {
+    /// Renders the definition of the type `name`.
+    fn wit(&self, name: &str, named: &[String]) -> String {
+        let ty = |ty: &Ty| ty.wit(named, 0);
+        let items = |count: u8| 0..usize::from(count) % MAX_ITEMS + 1;
+        match self {
+            TypeDef::Alias(alias) => format!("type {name} = {};", ty(alias)),
+            TypeDef::Record(first, rest) => {
+                let fields = std::iter::once(first)
+                    .chain(rest.iter().take(MAX_ITEMS))
+                    .enumerate()
+                    .map(|(i, field)| format!("f{i}: {}", ty(field)))
+                    .collect::<Vec<_>>();
+                format!("record {name} {{ {} }}", fields.join(", "))
+            }
+            TypeDef::Variant(first, rest) => {
+                let cases = std::iter::once(first)
+                    .chain(rest.iter().take(MAX_ITEMS))
+                    .enumerate()
+                    .map(|(i, case)| match case {
+                        Some(payload) => format!("c{i}({})", ty(payload)),
+                        None => format!("c{i}"),
+                    })
+                    .collect::<Vec<_>>();
+                format!("variant {name} {{ {} }}", cases.join(", "))
+            }
+            TypeDef::Enum(count) => {
+                let cases = items(*count).map(|i| format!("c{i}")).collect::<Vec<_>>();
+                format!("enum {name} {{ {} }}", cases.join(", "))
+            }
+            TypeDef::Flags(count) => {
+                let flags = items(*count).map(|i| format!("f{i}")).collect::<Vec<_>>();
+                format!("flags {name} {{ {} }}", flags.join(", "))
+            }
+            TypeDef::Resource => format!("resource {name};"),
+        }
+    }
+}
+
+impl Function {
+    /// Renders the function `name`.
+    fn wit(&self, name: &str, named: &[String]) -> String {
+        let params = self
+            .params
+            .iter()
+            .take(MAX_ITEMS)
+            .enumerate()
+            .map(|(i, param)| format!("p{i}: {}", param.wit(named, 0)))
+            .collect::<Vec<_>>();
+        match &self.result {
+            Some(result) => format!(
+                "{name}: func({}) -> {};",
+                params.join(", "),
+                result.wit(named, 0)
+            ),
+            None => format!("{name}: func({});", params.join(", ")),
+        }
+    }
+}
+
+impl Document {
+    /// Renders the package of the document, with its world `fuzz`.
+    fn wit(&self) -> String {
+        let mut named = Vec::new();
+        let mut types = String::new();
+        for (i, typedef) in self.types.iter().take(MAX_ITEMS).enumerate() {
+            let name = format!("t{i}");
+            writeln!(types, "  {}", typedef.wit(&name, &named)).unwrap();
+            named.push(name);
+        }
+        let uses = if named.is_empty() {
+            String::new()
+        } else {
+            format!("use types.{{{}}};", named.join(", "))
+        };
+        let functions = |functions: &[Function], prefix: &str| {
+            functions
+                .iter()
+                .take(MAX_ITEMS)
+                .enumerate()
+                .map(|(i, func)| format!("  {}\n", func.wit(&format!("{prefix}{i}"), &named)))
+                .collect::<String>()
+        };
+        let exports = self
+            .exports
+            .iter()
+            .take(MAX_ITEMS)
+            .enumerate()
+            .map(|(i, func)| format!("  export {}\n", func.wit(&format!("run{i}"), &named)))
+            .collect::<String>();
+        format!(
+            "package fuzz:test;\n\n\
+             interface types {{\n{types}}}\n\n\
+             interface host {{\n  {uses}\n{}}}\n\n\
+             interface api {{\n  {uses}\n{}}}\n\n\
+             world fuzz {{\n  {uses}\n  import host;\n  export api;\n{exports}}}\n",
+            functions(&self.imports, "call"),
+            functions(&self.interface_exports, "handle"),
+        )
+    }
+}
+
+fuzz_target!(|document: Document| {
+    let wit = document.wit();
+    let mut resolve = Resolve::default();
+    let Ok(package) = resolve.push_str("fuzz.wit", &wit) else {
+        return;
+    };
+    let Ok(world) = resolve.select_world(&[package], Some("fuzz")) else {
+        return;
+    };
+    disambiguate(&mut resolve, &[world]);
+    let mut sizes = SizeAlign::default();
+    sizes.fill(&resolve);
+    let mut bindings = Bindings::new(&resolve, &resolve.worlds[world], &sizes);
+    bindings.generate();
+});
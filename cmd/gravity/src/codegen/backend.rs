@@ -0,0 +1,293 @@
+use crate::compat::abi::WasmType;
+use genco::prelude::*;
+
+use crate::go::{
+    GoType,
+    imports::{
+        WAZERO_API_DECODE_F32, WAZERO_API_DECODE_F64, WAZERO_API_ENCODE_F32, WAZERO_API_ENCODE_F64,
+        WAZERO_API_ENCODE_I32, WAZERO_API_ENCODE_I64, WAZERO_API_ENCODE_U32, WAZERO_API_FUNCTION,
+        WAZERO_API_MEMORY, WAZERO_API_MODULE, WAZERO_COMPILED_MODULE, WAZERO_NEW_MODULE_CONFIG,
+        WAZERO_NEW_RUNTIME, WAZERO_RUNTIME,
+    },
+};
+
+/// The width of an integer loaded from or stored into the guest memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Width {
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl Width {
+    /// The Go type of the integers of the width.
+    pub fn go_type(self) -> GoType {
+        match self {
+            Width::U8 => GoType::Uint8,
+            Width::U16 => GoType::Uint16,
+            Width::U32 => GoType::Uint32,
+            Width::U64 => GoType::Uint64,
+        }
+    }
+}
+
+/// The Go code specific to the runtime the bindings host the component
+/// with, i.e. how modules are compiled, how the memory of the guest is
+/// accessed and how its functions are called.
+///
+/// The instruction handlers lifting and lowering values only go through
+/// the backend for these, so another runtime can be supported by
+/// implementing it. [`Wazero`] is the default backend.
+///
+/// Expressions are given and returned as Go tokens. Core Wasm values are
+/// whatever the backend passes to and returns from calls, e.g. `uint64` for
+/// Wazero, converted from and to Go values with the `encode_*` and
+/// `decode_*` methods.
+///
+/// The host modules implementing the imports, the `error-context` intrinsics,
+/// the options limiting the resources of the instances and the `Module()`
+/// escape hatch of the instance are still specific to Wazero.
+///
+/// Backends are shared by the threads generating the functions of each
+/// interface, so they must be `Sync`.
+pub trait RuntimeBackend: Sync {
+    /// The type of the runtime compiling and instantiating modules.
+    fn runtime_type(&self) -> Tokens<Go>;
+
+    /// The type of a compiled module.
+    fn compiled_module_type(&self) -> Tokens<Go>;
+
+    /// The type of an instantiated module.
+    fn module_type(&self) -> Tokens<Go>;
+
+    /// The type of the memory of an instantiated module.
+    fn memory_type(&self) -> Tokens<Go>;
+
+    /// The type of a function exported by an instantiated module.
+    fn function_type(&self) -> Tokens<Go>;
+
+    /// Creates a runtime.
+    fn new_runtime(&self) -> Tokens<Go>;
+
+    /// Compiles the Wasm bytes with the runtime, returning the compiled
+    /// module and an error.
+    fn compile_module(&self, runtime: &Tokens<Go>, wasm: &Tokens<Go>) -> Tokens<Go>;
+
+    /// Instantiates the compiled module with the runtime, returning the
+    /// module and an error.
+    fn instantiate_module(&self, runtime: &Tokens<Go>, module: &Tokens<Go>) -> Tokens<Go>;
+
+    /// The memory of an instantiated module.
+    fn memory(&self, module: &Tokens<Go>) -> Tokens<Go>;
+
+    /// The function exported by an instantiated module with the given name,
+    /// a Go string.
+    fn function(&self, module: &Tokens<Go>, name: Tokens<Go>) -> Tokens<Go>;
+
+    /// Calls the function with the given core Wasm values, returning the
+    /// results and an error.
+    fn call(&self, function: &Tokens<Go>, args: Tokens<Go>) -> Tokens<Go>;
+
+    /// Loads an integer from the memory at the `uint32` address, returning
+    /// the integer and whether it's in bounds.
+    fn load(&self, memory: &Tokens<Go>, width: Width, addr: Tokens<Go>) -> Tokens<Go>;
+
+    /// Stores an integer into the memory at the `uint32` address, returning
+    /// whether it's in bounds.
+    fn store(
+        &self,
+        memory: &Tokens<Go>,
+        width: Width,
+        addr: Tokens<Go>,
+        value: Tokens<Go>,
+    ) -> Tokens<Go>;
+
+    /// Reads `len` bytes from the memory at `ptr`, returning the bytes and
+    /// whether they're in bounds.
+    fn read(&self, memory: &Tokens<Go>, ptr: Tokens<Go>, len: Tokens<Go>) -> Tokens<Go>;
+
+    /// Writes the bytes into the memory at `ptr`, returning whether they're
+    /// in bounds.
+    fn write(&self, memory: &Tokens<Go>, ptr: Tokens<Go>, data: Tokens<Go>) -> Tokens<Go>;
+
+    /// The Go type of the core Wasm values, such as those returned by the
+    /// `encode_*` methods.
+    fn value_type(&self) -> GoType;
+
+    /// The Go type of the values of the core Wasm type given to
+    /// [`arg`](Self::arg) and returned by [`result`](Self::result).
+    fn go_type(&self, typ: &WasmType) -> GoType;
+
+    /// Converts a Go value of the core Wasm type, of its
+    /// [`go_type`](Self::go_type), into an argument of [`call`](Self::call).
+    fn arg(&self, typ: &WasmType, value: Tokens<Go>) -> Tokens<Go>;
+
+    /// Converts a result of [`call`](Self::call) into a Go value of the core
+    /// Wasm type, of its [`go_type`](Self::go_type).
+    fn result(&self, typ: &WasmType, value: Tokens<Go>) -> Tokens<Go>;
+
+    /// Converts an `int32` into a core Wasm value.
+    fn encode_i32(&self, value: Tokens<Go>) -> Tokens<Go>;
+
+    /// Converts a `uint32` into a core Wasm value.
+    fn encode_u32(&self, value: Tokens<Go>) -> Tokens<Go>;
+
+    /// Converts an `int64` into a core Wasm value.
+    fn encode_i64(&self, value: Tokens<Go>) -> Tokens<Go>;
+
+    /// Converts a `float32` into a core Wasm value.
+    fn encode_f32(&self, value: Tokens<Go>) -> Tokens<Go>;
+
+    /// Converts a `float64` into a core Wasm value.
+    fn encode_f64(&self, value: Tokens<Go>) -> Tokens<Go>;
+
+    /// Converts a core Wasm value into a `float32`.
+    fn decode_f32(&self, value: Tokens<Go>) -> Tokens<Go>;
+
+    /// Converts a core Wasm value into a `float64`.
+    fn decode_f64(&self, value: Tokens<Go>) -> Tokens<Go>;
+}
+
+/// The [Wazero] backend, a zero dependency WebAssembly runtime for Go,
+/// whose core Wasm values are all `uint64`.
+///
+/// [Wazero]: https://wazero.io
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Wazero;
+
+impl RuntimeBackend for Wazero {
+    fn runtime_type(&self) -> Tokens<Go> {
+        quote!($WAZERO_RUNTIME)
+    }
+
+    fn compiled_module_type(&self) -> Tokens<Go> {
+        quote!($WAZERO_COMPILED_MODULE)
+    }
+
+    fn module_type(&self) -> Tokens<Go> {
+        quote!($WAZERO_API_MODULE)
+    }
+
+    fn memory_type(&self) -> Tokens<Go> {
+        quote!($WAZERO_API_MEMORY)
+    }
+
+    fn function_type(&self) -> Tokens<Go> {
+        quote!($WAZERO_API_FUNCTION)
+    }
+
+    fn new_runtime(&self) -> Tokens<Go> {
+        quote!($WAZERO_NEW_RUNTIME(ctx))
+    }
+
+    fn compile_module(&self, runtime: &Tokens<Go>, wasm: &Tokens<Go>) -> Tokens<Go> {
+        quote!($runtime.CompileModule(ctx, $wasm))
+    }
+
+    fn instantiate_module(&self, runtime: &Tokens<Go>, module: &Tokens<Go>) -> Tokens<Go> {
+        quote!($runtime.InstantiateModule(ctx, $module, $WAZERO_NEW_MODULE_CONFIG()))
+    }
+
+    fn memory(&self, module: &Tokens<Go>) -> Tokens<Go> {
+        quote!($module.Memory())
+    }
+
+    fn function(&self, module: &Tokens<Go>, name: Tokens<Go>) -> Tokens<Go> {
+        quote!($module.ExportedFunction($name))
+    }
+
+    fn call(&self, function: &Tokens<Go>, args: Tokens<Go>) -> Tokens<Go> {
+        quote!($function.Call(ctx, $args))
+    }
+
+    fn load(&self, memory: &Tokens<Go>, width: Width, addr: Tokens<Go>) -> Tokens<Go> {
+        match width {
+            Width::U8 => quote!($memory.ReadByte($addr)),
+            Width::U16 => quote!($memory.ReadUint16Le($addr)),
+            Width::U32 => quote!($memory.ReadUint32Le($addr)),
+            Width::U64 => quote!($memory.ReadUint64Le($addr)),
+        }
+    }
+
+    fn store(
+        &self,
+        memory: &Tokens<Go>,
+        width: Width,
+        addr: Tokens<Go>,
+        value: Tokens<Go>,
+    ) -> Tokens<Go> {
+        match width {
+            Width::U8 => quote!($memory.WriteByte($addr, $value)),
+            Width::U16 => quote!($memory.WriteUint16Le($addr, $value)),
+            Width::U32 => quote!($memory.WriteUint32Le($addr, $value)),
+            Width::U64 => quote!($memory.WriteUint64Le($addr, $value)),
+        }
+    }
+
+    fn read(&self, memory: &Tokens<Go>, ptr: Tokens<Go>, len: Tokens<Go>) -> Tokens<Go> {
+        quote!($memory.Read($ptr, $len))
+    }
+
+    fn write(&self, memory: &Tokens<Go>, ptr: Tokens<Go>, data: Tokens<Go>) -> Tokens<Go> {
+        quote!($memory.Write($ptr, $data))
+    }
+
+    fn value_type(&self) -> GoType {
+        GoType::Uint64
+    }
+
+    fn go_type(&self, typ: &WasmType) -> GoType {
+        match typ {
+            WasmType::F32 => GoType::Float32,
+            WasmType::F64 => GoType::Float64,
+            _ => GoType::Uint64,
+        }
+    }
+
+    fn arg(&self, typ: &WasmType, value: Tokens<Go>) -> Tokens<Go> {
+        // Every argument is passed as a `uint64`, so floats are encoded
+        // rather than converted
+        match typ {
+            WasmType::F32 => self.encode_f32(value),
+            WasmType::F64 => self.encode_f64(value),
+            _ => value,
+        }
+    }
+
+    fn result(&self, typ: &WasmType, value: Tokens<Go>) -> Tokens<Go> {
+        match typ {
+            WasmType::F32 => self.decode_f32(value),
+            WasmType::F64 => self.decode_f64(value),
+            _ => value,
+        }
+    }
+
+    fn encode_i32(&self, value: Tokens<Go>) -> Tokens<Go> {
+        quote!($WAZERO_API_ENCODE_I32($value))
+    }
+
+    fn encode_u32(&self, value: Tokens<Go>) -> Tokens<Go> {
+        quote!($WAZERO_API_ENCODE_U32($value))
+    }
+
+    fn encode_i64(&self, value: Tokens<Go>) -> Tokens<Go> {
+        quote!($WAZERO_API_ENCODE_I64($value))
+    }
+
+    fn encode_f32(&self, value: Tokens<Go>) -> Tokens<Go> {
+        quote!($WAZERO_API_ENCODE_F32($value))
+    }
+
+    fn encode_f64(&self, value: Tokens<Go>) -> Tokens<Go> {
+        quote!($WAZERO_API_ENCODE_F64($value))
+    }
+
+    fn decode_f32(&self, value: Tokens<Go>) -> Tokens<Go> {
+        quote!($WAZERO_API_DECODE_F32($value))
+    }
+
+    fn decode_f64(&self, value: Tokens<Go>) -> Tokens<Go> {
+        quote!($WAZERO_API_DECODE_F64($value))
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeSet;
+
+use crate::compat::wit_parser::{Resolve, World, WorldItem};
+use genco::prelude::*;
+
+use crate::{
+    codegen::helpers::{BATCH_IMPORTS, CALL_IMPORT, IMPORT_BATCH, IMPORT_BATCH_KEY},
+    go::{
+        comment,
+        imports::{CONTEXT_CONTEXT, CONTEXT_WITH_VALUE},
+    },
+};
+
+/// Returns true if the world imports any of the batched interfaces, given by
+/// their WIT names without their version, e.g. `wasi:logging/logging`.
+pub fn uses_batched_imports(resolve: &Resolve, world: &World, batched: &BTreeSet<String>) -> bool {
+    world.imports.values().any(|item| {
+        let WorldItem::Interface { id, .. } = item else {
+            return false;
+        };
+        let interface = &resolve.interfaces[*id];
+        let name = match (interface.package, &interface.name) {
+            (Some(package), Some(name)) => {
+                let package = &resolve.packages[package].name;
+                format!("{}:{}/{name}", package.namespace, package.name)
+            }
+            (None, Some(name)) => name.clone(),
+            (_, None) => return false,
+        };
+        batched.contains(&name)
+    })
+}
+
+/// Generator for the helpers batching the calls of imported functions
+/// without results, which are queued by the host functions during a call of
+/// an export and made once it returns, rather than as the guest makes them.
+///
+/// This must only be generated once per file, as it is shared by all
+/// factories in the file.
+pub struct ImportBatchGenerator;
+
+impl FormatInto<Go> for ImportBatchGenerator {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let batch = &IMPORT_BATCH.ident();
+        let key = &IMPORT_BATCH_KEY.ident();
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!("{} holds the calls of the batched imports queued during", String::from(batch)),
+                "the call of an export.".into(),
+            ]))
+            type $batch struct {
+                calls []func()
+            }
+            $['\n']
+            type $key struct{}
+            $['\n']
+            $(comment(&[
+                format!("{} returns a context queueing the calls of the batched", String::from(BATCH_IMPORTS.ident())),
+                "imports made with it, along with the function making them, which the".into(),
+                "export calls once it returns. A context already batching the calls is".into(),
+                "returned as is, so they're made by the outermost export.".into(),
+            ]))
+            func $(BATCH_IMPORTS.ident())(ctx $CONTEXT_CONTEXT) ($CONTEXT_CONTEXT, func()) {
+                if _, ok := ctx.Value($key{}).(*$batch); ok {
+                    return ctx, func() {}
+                }
+                b := &$batch{}
+                return $CONTEXT_WITH_VALUE(ctx, $key{}, b), func() {
+                    for _, call := range b.calls {
+                        call()
+                    }
+                    b.calls = nil
+                }
+            }
+            $['\n']
+            $(comment(&[
+                format!("{} makes the call of a batched import, or queues it if the", String::from(CALL_IMPORT.ident())),
+                "context is batching the calls of an export.".into(),
+            ]))
+            func $(CALL_IMPORT.ident())(ctx $CONTEXT_CONTEXT, call func()) {
+                if b, ok := ctx.Value($key{}).(*$batch); ok {
+                    b.calls = append(b.calls, call)
+                    return
+                }
+                call()
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::compat::wit_parser::Resolve;
+    use genco::prelude::*;
+
+    use super::{ImportBatchGenerator, uses_batched_imports};
+
+    #[test]
+    fn test_import_batch_generator() {
+        let mut tokens = Tokens::<Go>::new();
+        ImportBatchGenerator.format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("type importBatch struct {\n    calls []func()\n}"));
+        assert!(
+            output.contains("func batchImports(ctx context.Context) (context.Context, func()) {")
+        );
+        assert!(output.contains("return context.WithValue(ctx, importBatchKey{}, b), func() {"));
+        assert!(output.contains("func callImport(ctx context.Context, call func()) {"));
+        assert!(output.contains("b.calls = append(b.calls, call)"));
+    }
+
+    #[test]
+    fn test_uses_batched_imports() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg@1.0.0;
+
+                interface logger {
+                    log: func(msg: string);
+                }
+
+                world logged {
+                    import logger;
+                }
+
+                world unlogged {
+                    import log: func(msg: string);
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let world = |name: &str| {
+            let (_, world) = resolve
+                .worlds
+                .iter()
+                .find(|(_, world)| world.name == name)
+                .expect("missing world");
+            world
+        };
+        // The interfaces are named without their version
+        let batched = BTreeSet::from(["test:pkg/logger".to_string()]);
+        assert!(uses_batched_imports(&resolve, world("logged"), &batched));
+        assert!(!uses_batched_imports(&resolve, world("unlogged"), &batched));
+        assert!(!uses_batched_imports(
+            &resolve,
+            world("logged"),
+            &BTreeSet::new()
+        ));
+    }
+}
@@ -1,15 +1,34 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
+use crate::compat::{
+    wit_component::metadata::ModuleMetadata,
+    wit_parser::{Resolve, SizeAlign, World, WorldItem},
+};
 use genco::{prelude::*, tokens::Tokens};
-use wit_bindgen_core::wit_parser::{Resolve, SizeAlign, World};
 
 use crate::{
     codegen::{
-        ExportGenerator, FactoryGenerator,
-        exports::ExportConfig,
-        factory::FactoryConfig,
+        CallTimeoutGenerator, Derives, ErrorMode, ExportGenerator, FactoryGenerator, FuzzGenerator,
+        GuestErrorGenerator, GuestTrapGenerator, HarnessGenerator, HooksGenerator,
+        ImportBatchGenerator, InstanceOptionsGenerator, InstrumentGenerator, Instrumentation,
+        LimitsGenerator, MockGenerator, NestedResultGenerator, Providers, RuntimeBackend, Stats,
+        StringEncodings, StubBehavior, StubGenerator, TranscodingGenerator, TupleResults,
+        WasiAdapterGenerator, Wazero,
+        conversions::{ConversionGenerator, record_conversions},
+        error_context::{ErrorContextGenerator, uses_error_context},
+        exports::{ExportConfig, exported_functions},
+        factory::{
+            FactoryConfig, LinkedComponent, LinkedParameter, imports_field, uses_write_string,
+        },
+        helpers,
         imports::{ImportAnalyzer, ImportCodeGenerator},
         ir::AnalyzedImports,
+        resources::{
+            GuestResourcesGenerator, ResourceTableGenerator, exported_resource_intrinsics,
+            exported_resources, guest_resource_names, host_resource_names, uses_resources,
+        },
+        symbols::{SymbolTable, export_methods, imported_interface_name},
+        uses_batched_imports, uses_guest_errors, uses_guest_traps, uses_nested_results,
         wasm::{Wasm, WasmData},
     },
     go::GoIdentifier,
@@ -28,6 +47,99 @@ pub struct Bindings<'a> {
 
     /// The sizes of the architecture.
     sizes: &'a SizeAlign,
+
+    /// The worlds of other components linked into the factory.
+    linked: Vec<Linked<'a>>,
+
+    /// The names of the functions exported by the core Wasm module, if known.
+    module_exports: Option<BTreeSet<String>>,
+
+    /// The encodings of the strings exchanged with the core Wasm module.
+    string_encodings: StringEncodings,
+
+    /// Whether the embed package is imported, which must only happen once.
+    embed_imported: bool,
+
+    /// Options controlling the generated code.
+    options: BindingsOptions,
+
+    /// The runtime the generated code hosts the components with.
+    backend: &'a dyn RuntimeBackend,
+}
+
+/// Options controlling the generated code.
+#[derive(Debug, Clone, Default)]
+pub struct BindingsOptions {
+    /// Generate a `Module()` accessor and memory helpers on the instance.
+    pub expose_module: bool,
+    /// Take the imports as a single struct in the factory constructor,
+    /// rather than as positional parameters.
+    pub imports_struct: bool,
+    /// Generate a constructor compiling Wasm given by the host, rather than
+    /// the embedded Wasm.
+    pub from_bytes: bool,
+    /// Generate constructors taking a runtime shared with the host, and
+    /// optionally a module compiled with it.
+    pub shared_runtime: bool,
+    /// Generate options limiting the resources of the instances, taken by
+    /// the constructors creating a runtime.
+    pub limits: bool,
+    /// Generate the options configuring the directories, environment,
+    /// arguments and standard streams of each instance, taken by the
+    /// `Instantiate` methods.
+    pub instance_options: bool,
+    /// Generate a provider of the factory for the dependency injection
+    /// framework.
+    pub providers: Option<Providers>,
+    /// Replace imports that aren't provided (i.e. are `nil`) with stubs
+    /// behaving as given.
+    pub stub_missing_imports: Option<StubBehavior>,
+    /// Replace imports of well-known WASI interfaces that aren't provided
+    /// with adapters backed by the Go standard library.
+    pub wasi_adapters: bool,
+    /// The WIT names of the imports that may be left out, i.e. be `nil`,
+    /// such as `wasi:logging/logging`. Other imports are checked to be
+    /// provided by the factory constructor.
+    pub optional_imports: BTreeSet<String>,
+    /// The WIT names of the imported interfaces whose functions without
+    /// results are batched: their calls are queued during the call of an
+    /// export, and made once it returns.
+    pub batched_imports: BTreeSet<String>,
+    /// Instrumentation wrapped around every call of an export or import.
+    pub instrumentation: Instrumentation,
+    /// How errors encountered by the exported functions are surfaced.
+    pub error_mode: ErrorMode,
+    /// How tuples returned by the exported functions are mapped to Go.
+    pub tuple_results: TupleResults,
+    /// Generate methods listing and calling the exported functions by name.
+    pub dynamic_exports: bool,
+    /// Generate a variant of each exported function taking a timeout, which
+    /// closes the instance if the call doesn't return in time.
+    pub call_timeouts: bool,
+    /// Avoid constructs TinyGo doesn't support, such as reflection, for hosts
+    /// built with TinyGo.
+    pub tinygo_compat: bool,
+    /// Check that the core Wasm results of the exported functions fit the
+    /// types they're lifted into, returning an error if they don't.
+    pub checked_casts: bool,
+    /// Pass the UTF-8 strings of the guest to the host functions as views of
+    /// its memory, which are only valid until they return, rather than
+    /// copying them.
+    pub string_views: bool,
+    /// Methods generated for the enums and records of the imports.
+    pub derives: Derives,
+}
+
+/// Another component linked into the bindings, whose exports satisfy some of
+/// the imports of the selected world.
+struct Linked<'a> {
+    world: &'a World,
+    /// The identifier of the Go variable containing the WebAssembly bytes.
+    raw_wasm_var: GoIdentifier,
+    /// The names of the functions exported by the core Wasm module.
+    module_exports: BTreeSet<String>,
+    /// The encodings of the strings exchanged with the core Wasm module.
+    string_encodings: StringEncodings,
 }
 
 impl<'a> Bindings<'a> {
@@ -41,22 +153,268 @@ impl<'a> Bindings<'a> {
             out: Tokens::new(),
             raw_wasm_var: wasm_var,
             sizes,
+            linked: Vec::new(),
+            module_exports: None,
+            string_encodings: StringEncodings::default(),
+            embed_imported: false,
+            options: BindingsOptions::default(),
+            backend: &Wazero,
         }
     }
 
+    /// Sets the options controlling the generated code.
+    pub fn set_options(&mut self, options: BindingsOptions) {
+        self.options = options;
+    }
+
+    /// Sets the runtime the generated code hosts the components with, which
+    /// is Wazero by default.
+    pub fn set_backend(&mut self, backend: &'a dyn RuntimeBackend) {
+        self.backend = backend;
+    }
+
     /// Adds the given Wasm to the bindings.
     pub fn include_wasm(&mut self, wasm: WasmData) {
+        if matches!(wasm, WasmData::Embedded(_)) {
+            self.embed_imported = true;
+        }
         Wasm::new(&self.raw_wasm_var, wasm).format_into(&mut self.out)
     }
 
+    /// Sets the names of the functions exported by the core Wasm module, as
+    /// returned by [`module_exports`](crate::codegen::module_exports).
+    ///
+    /// The exports only call the `cabi_post_*` functions the module exports,
+    /// rather than those it's expected to export.
+    pub fn set_module_exports(&mut self, exports: BTreeSet<String>) {
+        self.module_exports = Some(exports);
+    }
+
+    /// Sets the encodings of the strings exchanged with the core Wasm module,
+    /// from the metadata decoded along with its WIT.
+    ///
+    /// Strings are otherwise assumed to be encoded with UTF-8.
+    pub fn set_module_metadata(&mut self, metadata: &ModuleMetadata) {
+        self.string_encodings = StringEncodings::new(self.resolve, self.world, metadata);
+    }
+
+    /// Generates the variable holding the given Wasm, in place of adding it
+    /// to the bindings.
+    ///
+    /// The variable is returned separately from the bindings, as it's
+    /// intended to be written to a file of its own, e.g. a file per Go build
+    /// tag choosing between builds of the component for different platforms.
+    pub fn generate_wasm(&self, wasm: WasmData) -> Tokens<Go> {
+        let mut tokens = Tokens::new();
+        Wasm::new(&self.raw_wasm_var, wasm).format_into(&mut tokens);
+        tokens
+    }
+
+    /// Links another component into the bindings.
+    ///
+    /// Any interface imported by the selected world and exported by the
+    /// linked world is implemented by an instance of the linked component,
+    /// rather than being a parameter of the factory constructor. The imports
+    /// of the linked component become parameters of the factory constructor
+    /// instead.
+    ///
+    /// Both worlds must be part of the same `Resolve`, and `module_exports`
+    /// and `metadata` are those of the core Wasm module of the linked
+    /// component, as for [`set_module_exports`](Self::set_module_exports) and
+    /// [`set_module_metadata`](Self::set_module_metadata).
+    pub fn link(
+        &mut self,
+        world: &'a World,
+        wasm: WasmData,
+        module_exports: BTreeSet<String>,
+        metadata: &ModuleMetadata,
+    ) {
+        let raw_wasm_var = GoIdentifier::private(format!("wasm-file-{}", world.name));
+        quote_in!(self.out => $['\n']);
+        let embedded = matches!(wasm, WasmData::Embedded(_));
+        let wasm = Wasm::new(&raw_wasm_var, wasm);
+        if self.embed_imported {
+            wasm.without_import().format_into(&mut self.out);
+        } else {
+            wasm.format_into(&mut self.out);
+        }
+        self.embed_imported |= embedded;
+        self.linked.push(Linked {
+            world,
+            raw_wasm_var,
+            module_exports,
+            string_encodings: StringEncodings::new(self.resolve, world, metadata),
+        });
+    }
+
+    /// Generates mock implementations of the import interfaces.
+    ///
+    /// The mocks are returned separately from the bindings, as they are
+    /// intended to be written to a `_test.go` file in the same package.
+    pub fn generate_mocks(&self) -> Tokens<Go> {
+        let analyzed = ImportAnalyzer::new(self.resolve, self.world).analyze();
+        let mut tokens = Tokens::new();
+        MockGenerator::new(&analyzed).format_into(&mut tokens);
+        tokens
+    }
+
+    /// Generate a Go program smoke-testing the bindings, which are imported
+    /// from the package with the given name and import path.
+    pub fn generate_harness(&self, package: &str, import_path: &str) -> Tokens<Go> {
+        let analyzed = ImportAnalyzer::new(self.resolve, self.world).analyze();
+        let mut tokens = Tokens::new();
+        HarnessGenerator::new(self.resolve, self.world, &analyzed, package, import_path)
+            .format_into(&mut tokens);
+        tokens
+    }
+
+    /// Generates Go fuzz targets calling the exports of the selected world
+    /// that take strings or bytes.
+    ///
+    /// Like the mocks, the targets are returned separately from the bindings,
+    /// as they are intended to be written to a `_test.go` file in the same
+    /// package.
+    pub fn generate_fuzz(&self) -> Tokens<Go> {
+        let analyzed = ImportAnalyzer::new(self.resolve, self.world).analyze();
+        let mut tokens = Tokens::new();
+        FuzzGenerator::new(self.resolve, self.world, &analyzed).format_into(&mut tokens);
+        tokens
+    }
+
+    /// Measures the code generated for each function of the selected world,
+    /// given the whole generated file, to find out what makes the bindings
+    /// large.
+    ///
+    /// The functions are generated again, one at a time, so this should be
+    /// called after [`generate`](Self::generate). The functions of linked
+    /// components aren't included.
+    pub fn stats(&self, file: &str) -> Stats {
+        let analyzed = ImportAnalyzer::new(self.resolve, self.world).analyze();
+        let imports = self.import_generator(&analyzed).stats();
+        let config = self.export_config(
+            self.world,
+            &analyzed.instance_name,
+            self.options.tuple_results,
+            true,
+            self.module_exports.as_ref(),
+            &self.string_encodings,
+        );
+        let exports = ExportGenerator::new(config)
+            .with_backend(self.backend)
+            .stats();
+        Stats {
+            lines: file.lines().count(),
+            imports,
+            exports,
+        }
+    }
+
     /// Generate the bindings.
     ///
     /// This generates the imports (interfaces, types, functions), the factory and instance
     /// type, and the exports (functions).
     pub fn generate(&mut self) {
-        let (imports, chains) = self.generate_imports();
-        self.generate_factory(&imports, chains);
-        self.generate_exports(&imports.instance_name);
+        // The helpers imported from a runtime package aren't generated
+        let helpers = !helpers::imported();
+        let error_context = uses_error_context(self.resolve, self.world)
+            || self
+                .linked
+                .iter()
+                .any(|linked| uses_error_context(self.resolve, linked.world));
+        if helpers && error_context {
+            ErrorContextGenerator.format_into(&mut self.out);
+        }
+        let resources = uses_resources(self.resolve, self.world)
+            || self
+                .linked
+                .iter()
+                .any(|linked| uses_resources(self.resolve, linked.world));
+        if helpers && resources {
+            ResourceTableGenerator.format_into(&mut self.out);
+        }
+        if helpers && !exported_resources(self.resolve, self.world).is_empty() {
+            GuestResourcesGenerator.format_into(&mut self.out);
+        }
+        let traps = uses_guest_traps(self.resolve, self.world)
+            || self
+                .linked
+                .iter()
+                .any(|linked| uses_guest_traps(self.resolve, linked.world));
+        if helpers && traps {
+            GuestTrapGenerator.format_into(&mut self.out);
+        }
+        let guest_errors = uses_guest_errors(self.resolve, self.world)
+            || self
+                .linked
+                .iter()
+                .any(|linked| uses_guest_errors(self.resolve, linked.world));
+        if helpers && guest_errors {
+            GuestErrorGenerator.format_into(&mut self.out);
+        }
+        if helpers && uses_nested_results(self.resolve) {
+            NestedResultGenerator.format_into(&mut self.out);
+        }
+        if helpers && uses_batched_imports(self.resolve, self.world, &self.options.batched_imports)
+        {
+            ImportBatchGenerator.format_into(&mut self.out);
+        }
+        HooksGenerator::new(&self.options.instrumentation).format_into(&mut self.out);
+        if self.options.limits {
+            LimitsGenerator.format_into(&mut self.out);
+        }
+        if self.options.instance_options {
+            InstanceOptionsGenerator.format_into(&mut self.out);
+        }
+        if self.options.call_timeouts {
+            CallTimeoutGenerator.format_into(&mut self.out);
+        }
+        if helpers {
+            let encodings = std::iter::once(&self.string_encodings)
+                .chain(self.linked.iter().map(|linked| &linked.string_encodings))
+                .flat_map(StringEncodings::transcoded);
+            let read_string = std::iter::once((self.world, &self.string_encodings))
+                .chain(
+                    self.linked
+                        .iter()
+                        .map(|linked| (linked.world, &linked.string_encodings)),
+                )
+                .any(|(world, encodings)| encodings.reads_utf8(self.resolve, world));
+            let view_string = self.options.string_views
+                && std::iter::once((self.world, &self.string_encodings))
+                    .chain(
+                        self.linked
+                            .iter()
+                            .map(|linked| (linked.world, &linked.string_encodings)),
+                    )
+                    .any(|(world, encodings)| encodings.passes_utf8(self.resolve, world));
+            TranscodingGenerator::new(encodings)
+                .with_utf8(read_string)
+                .with_view(view_string)
+                .with_backend(self.backend)
+                .format_into(&mut self.out);
+        }
+
+        // The helpers lifting and lowering the values stored in the memory
+        // of the guest are shared by every function, so follow them
+        let ((), conversions) = record_conversions(|| {
+            let (imports, chains) = self.generate_imports();
+            let linked = self.generate_linked(&imports);
+            self.generate_factory(&imports, chains, &linked);
+            let module_exports = self.module_exports.take();
+            let string_encodings = std::mem::take(&mut self.string_encodings);
+            self.generate_exports(
+                self.world,
+                &imports.instance_name,
+                self.options.tuple_results,
+                true,
+                module_exports.as_ref(),
+                &string_encodings,
+            );
+            self.module_exports = module_exports;
+            self.string_encodings = string_encodings;
+        });
+        ConversionGenerator::new(self.resolve, self.sizes, self.backend, conversions)
+            .format_into(&mut self.out);
     }
 
     /// Generates the imports for the bindings.
@@ -64,38 +422,302 @@ impl<'a> Bindings<'a> {
         let analyzer = ImportAnalyzer::new(self.resolve, self.world);
         let analyzed = analyzer.analyze();
 
-        let generator = ImportCodeGenerator::new(self.resolve, &analyzed, self.sizes);
-        let import_chains = generator.import_chains();
+        let generator = self.import_generator(&analyzed);
+        let mut import_chains = generator.import_chains();
         generator.format_into(&mut self.out);
+        self.generate_stubs(self.world, &analyzed);
+        import_chains.extend(exported_resource_intrinsics(self.resolve, self.world));
         (analyzed, import_chains)
     }
 
+    /// Returns the generator of the imports of the selected world, shared by
+    /// [`generate`](Self::generate) and [`stats`](Self::stats).
+    fn import_generator<'b>(&self, analyzed: &'b AnalyzedImports) -> ImportCodeGenerator<'b>
+    where
+        'a: 'b,
+    {
+        ImportCodeGenerator::new(self.resolve, analyzed, self.sizes)
+            .with_backend(self.backend)
+            .with_derives(self.options.derives)
+            .with_string_encodings(self.string_encodings.clone())
+            .with_error_context(uses_error_context(self.resolve, self.world))
+            .with_batched_imports(self.options.batched_imports.clone())
+            .with_string_views(self.options.string_views)
+    }
+
+    /// Generates the stubs and WASI adapters used in place of imports that
+    /// aren't provided, and the instrumentation of the imports and hooks, if
+    /// enabled.
+    fn generate_stubs(&mut self, world: &World, analyzed: &AnalyzedImports) {
+        if self.options.wasi_adapters {
+            WasiAdapterGenerator::new(analyzed).format_into(&mut self.out);
+        }
+        if let Some(behavior) = self.options.stub_missing_imports {
+            StubGenerator::new(analyzed, behavior).format_into(&mut self.out);
+        }
+        if self.options.instrumentation.is_enabled() {
+            InstrumentGenerator::new(&self.options.instrumentation, &world.name, analyzed)
+                .format_into(&mut self.out);
+        }
+    }
+
+    /// Generates the imports, factory, instance and exports of each linked
+    /// component, returning how they are wired into the selected world's
+    /// factory.
+    fn generate_linked(&mut self, primary: &AnalyzedImports) -> Vec<LinkedComponent> {
+        // Types of interfaces imported by multiple worlds must only be defined once.
+        let mut defined = primary
+            .interfaces
+            .iter()
+            .map(|interface| interface.wazero_module_name.clone())
+            .collect::<BTreeSet<_>>();
+        // Linked components export interfaces imported by the world, whose
+        // types are already defined among its imports. Types are renamed so
+        // that distinct types never share a Go name
+        let mut defined_types = primary
+            .interfaces
+            .iter()
+            .flat_map(|interface| &interface.types)
+            .chain(&primary.standalone_types)
+            .chain(&primary.exported_types)
+            .map(|typ| String::from(&typ.go_type_name))
+            .collect::<BTreeSet<_>>();
+
+        let linked = std::mem::take(&mut self.linked);
+        let components = linked
+            .iter()
+            .map(|linked| {
+                let mut analyzed = ImportAnalyzer::new(self.resolve, linked.world).analyze();
+
+                let generator = ImportCodeGenerator::new(self.resolve, &analyzed, self.sizes)
+                    .with_backend(self.backend)
+                    .with_string_encodings(linked.string_encodings.clone())
+                    .with_error_context(uses_error_context(self.resolve, linked.world))
+                    .with_string_views(self.options.string_views);
+                let import_chains = generator.import_chains();
+                for interface in analyzed.interfaces.iter_mut() {
+                    if !defined.insert(interface.wazero_module_name.clone()) {
+                        interface.types.clear();
+                    }
+                    defined_types.extend(
+                        interface
+                            .types
+                            .iter()
+                            .map(|typ| String::from(&typ.go_type_name)),
+                    );
+                }
+                analyzed
+                    .exported_types
+                    .retain(|typ| defined_types.insert(String::from(&typ.go_type_name)));
+                ImportCodeGenerator::new(self.resolve, &analyzed, self.sizes)
+                    .with_backend(self.backend)
+                    .with_derives(self.options.derives)
+                    .format_into(&mut self.out);
+                self.generate_stubs(linked.world, &analyzed);
+
+                let config = FactoryConfig {
+                    analyzed_imports: &analyzed,
+                    import_chains,
+                    wasm_var_name: &linked.raw_wasm_var,
+                    linked: &[],
+                    expose_module: self.options.expose_module,
+                    imports_struct: self.options.imports_struct,
+                    stub_missing_imports: self.options.stub_missing_imports.is_some(),
+                    wasi_adapters: self.options.wasi_adapters,
+                    optional_imports: self.options.optional_imports.clone(),
+                    instrumentation: &self.options.instrumentation,
+                    exports: exported_functions(
+                        self.resolve,
+                        linked.world,
+                        TupleResults::Struct,
+                        Some(&linked.module_exports),
+                    ),
+                    guest_resources: guest_resource_names(self.resolve, linked.world),
+                    host_resources: host_resource_names(self.resolve, linked.world),
+                    write_string: false,
+                    from_bytes: false,
+                    shared_runtime: false,
+                    limits: self.options.limits,
+                    instance_options: false,
+                    providers: None,
+                };
+                FactoryGenerator::new(config)
+                    .with_backend(self.backend)
+                    .format_factory_into(&mut self.out);
+                // The exports of linked components implement the imports of
+                // the world, whose methods return tuples as structs, and are
+                // only called through them
+                self.generate_exports(
+                    linked.world,
+                    &analyzed.instance_name,
+                    TupleResults::Struct,
+                    false,
+                    Some(&linked.module_exports),
+                    &linked.string_encodings,
+                );
+
+                self.linked_component(linked.world, &analyzed)
+            })
+            .collect();
+        self.linked = linked;
+        components
+    }
+
+    /// Describes how a linked world is wired into the selected world's factory.
+    fn linked_component(&self, world: &World, analyzed: &AnalyzedImports) -> LinkedComponent {
+        let exported = export_methods(self.resolve, world, &mut SymbolTable::default())
+            .interfaces
+            .into_iter()
+            .filter_map(|interface| match &world.exports[interface.key] {
+                WorldItem::Interface { id, .. } => {
+                    Some((*id, GoIdentifier::public(interface.name)))
+                }
+                _ => None,
+            })
+            .collect::<BTreeMap<_, _>>();
+        let provides = self
+            .world
+            .imports
+            .values()
+            .filter_map(|item| match item {
+                WorldItem::Interface { id, .. } => exported.get(id).map(|accessor| {
+                    let name = imported_interface_name(self.resolve, self.world, *id);
+                    (GoIdentifier::private(name), accessor.clone())
+                }),
+                _ => None,
+            })
+            .collect();
+        let parameters = analyzed
+            .interfaces
+            .iter()
+            .map(|interface| LinkedParameter {
+                name: GoIdentifier::private(format!("{}-{}", world.name, interface.name)),
+                field: imports_field(&interface.constructor_param_name),
+                go_type: interface.go_interface_name.clone(),
+            })
+            .collect();
+
+        LinkedComponent {
+            factory_name: analyzed.factory_name.clone(),
+            constructor_name: analyzed.constructor_name.clone(),
+            imports_name: analyzed.imports_name.clone(),
+            factory_var: GoIdentifier::private(format!("{}-factory", world.name)),
+            instance_var: GoIdentifier::private(format!("{}-instance", world.name)),
+            parameters,
+            provides,
+        }
+    }
+
     /// Generates the factory and instantiate functions, including any
     /// required interfaces.
     fn generate_factory(
         &mut self,
         analyzed_imports: &AnalyzedImports,
         import_chains: BTreeMap<String, Tokens<Go>>,
+        linked: &[LinkedComponent],
     ) {
         let config = FactoryConfig {
             analyzed_imports,
             import_chains,
             wasm_var_name: &self.raw_wasm_var,
+            linked,
+            expose_module: self.options.expose_module,
+            imports_struct: self.options.imports_struct,
+            stub_missing_imports: self.options.stub_missing_imports.is_some(),
+            wasi_adapters: self.options.wasi_adapters,
+            optional_imports: self.options.optional_imports.clone(),
+            instrumentation: &self.options.instrumentation,
+            exports: exported_functions(
+                self.resolve,
+                self.world,
+                self.options.tuple_results,
+                self.module_exports.as_ref(),
+            ),
+            guest_resources: guest_resource_names(self.resolve, self.world),
+            host_resources: host_resource_names(self.resolve, self.world),
+            write_string: self.uses_write_string() && !helpers::imported(),
+            from_bytes: self.options.from_bytes,
+            shared_runtime: self.options.shared_runtime,
+            limits: self.options.limits,
+            instance_options: self.options.instance_options,
+            providers: self.options.providers,
         };
-        FactoryGenerator::new(config).format_into(&mut self.out)
+        FactoryGenerator::new(config)
+            .with_backend(self.backend)
+            .format_into(&mut self.out)
+    }
+
+    /// Returns true if the selected world or any linked world writes strings
+    /// into the guest memory, including the `error-context` intrinsics.
+    fn uses_write_string(&self) -> bool {
+        std::iter::once(self.world)
+            .chain(self.linked.iter().map(|linked| linked.world))
+            .any(|world| {
+                uses_write_string(self.resolve, world) || uses_error_context(self.resolve, world)
+            })
     }
 
     /// Generates all exports for the world.
     ///
-    /// Note: for now this only generates functions; types and interfaces are
-    /// still TODO
-    fn generate_exports(&mut self, instance: &GoIdentifier) {
-        let config = ExportConfig {
+    /// This generates functions, including those of exported interfaces; the
+    /// types of exported interfaces are generated along with the imports.
+    ///
+    /// The exports of linked components, rather than of the selected world
+    /// (i.e. its `own` exports), are only called through the imports they
+    /// implement, so they aren't called by name nor with a timeout.
+    fn generate_exports(
+        &mut self,
+        world: &World,
+        instance: &GoIdentifier,
+        tuple_results: TupleResults,
+        own: bool,
+        module_exports: Option<&BTreeSet<String>>,
+        string_encodings: &StringEncodings,
+    ) {
+        let mut tokens = Tokens::new();
+        let config = self.export_config(
+            world,
             instance,
-            world: self.world,
+            tuple_results,
+            own,
+            module_exports,
+            string_encodings,
+        );
+        ExportGenerator::new(config)
+            .with_backend(self.backend)
+            .format_into(&mut tokens);
+        self.out.append(tokens);
+    }
+
+    /// Returns the configuration of the generator of the exports of the
+    /// world, shared by [`generate`](Self::generate) and
+    /// [`stats`](Self::stats).
+    fn export_config<'b>(
+        &'b self,
+        world: &'b World,
+        instance: &'b GoIdentifier,
+        tuple_results: TupleResults,
+        own: bool,
+        module_exports: Option<&'b BTreeSet<String>>,
+        string_encodings: &'b StringEncodings,
+    ) -> ExportConfig<'b> {
+        ExportConfig {
+            instance,
+            world,
             resolve: self.resolve,
             sizes: self.sizes,
-        };
-        ExportGenerator::new(config).format_into(&mut self.out)
+            instrumentation: &self.options.instrumentation,
+            error_mode: self.options.error_mode,
+            tuple_results,
+            dynamic: own && self.options.dynamic_exports,
+            call_timeouts: own && self.options.call_timeouts,
+            tinygo_compat: self.options.tinygo_compat,
+            checked_casts: self.options.checked_casts,
+            module_exports,
+            // Only the imports of the selected world are batched
+            batch_imports: own
+                && uses_batched_imports(self.resolve, world, &self.options.batched_imports),
+            string_encodings,
+        }
     }
 }
@@ -0,0 +1,471 @@
+use std::{cell::RefCell, collections::BTreeMap};
+
+use crate::compat::{
+    abi,
+    wit_component::StringEncoding,
+    wit_parser::{Resolve, SizeAlign, Type, TypeDefKind},
+};
+use genco::prelude::*;
+
+use crate::{
+    codegen::{backend::RuntimeBackend, diff::type_wit, func::Func, helpers},
+    go::{GoIdentifier, GoResult, GoType, Operand, comment, imports::CONTEXT_CONTEXT},
+    resolve_field_type, resolve_type,
+};
+
+thread_local! {
+    static USED: RefCell<Option<BTreeMap<String, Conversion>>> = const { RefCell::new(None) };
+}
+
+/// The name of the buffer the helpers lowering a value store it into.
+pub(crate) const LOWER_BUFFER: &str = "buf";
+
+/// Whether a value is lifted from the memory of the guest, or lowered into
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConversionKind {
+    Lift,
+    Lower,
+}
+
+/// A helper lifting or lowering the values of a record, option or variant
+/// stored in the memory of the guest, e.g. `liftPoint`, which is generated
+/// once per file and called by every function storing such values, rather
+/// than inlining their conversion.
+#[derive(Debug, Clone)]
+pub(crate) struct Conversion {
+    pub(crate) kind: ConversionKind,
+    /// The type of the values, with its aliases followed.
+    typ: Type,
+    /// The encoding of the strings in the values.
+    encoding: StringEncoding,
+    /// The name of the helper.
+    pub(crate) name: GoIdentifier,
+    /// The Go type of the lifted values, i.e. a value and whether it's set
+    /// for options.
+    pub(crate) go_type: GoType,
+    /// Whether the values contain strings or lists, which are allocated in
+    /// the guest when lowered, in which case the helper lowering them takes
+    /// the context, the memory and the realloc function of the guest.
+    pub(crate) allocates: bool,
+    /// Whether the helper returns an error, which those lifting values always
+    /// do, as the memory may be out of bounds.
+    pub(crate) fallible: bool,
+}
+
+/// Returns the helper lifting or lowering the values of the type, if it's a
+/// record, an option or a variant which can be converted without the state
+/// of an instance, i.e. nesting no resources, recording that it's used.
+pub(crate) fn conversion(
+    resolve: &Resolve,
+    kind: ConversionKind,
+    typ: &Type,
+    encoding: StringEncoding,
+) -> Option<Conversion> {
+    let typ = dealias(resolve, *typ);
+    let Type::Id(id) = typ else {
+        return None;
+    };
+    if !matches!(
+        resolve.types[id].kind,
+        TypeDefKind::Record(_) | TypeDefKind::Option(_) | TypeDefKind::Variant(_)
+    ) || any_type(resolve, &typ, &|typ| {
+        matches!(typ, Type::ErrorContext)
+            || matches!(
+                kind_of(resolve, typ),
+                Some(
+                    TypeDefKind::Resource
+                        | TypeDefKind::Handle(_)
+                        | TypeDefKind::Flags(_)
+                        | TypeDefKind::FixedSizeList(..)
+                        | TypeDefKind::Future(_)
+                        | TypeDefKind::Stream(_)
+                        | TypeDefKind::Unknown
+                )
+            )
+    }) {
+        return None;
+    }
+
+    let strings = any_type(resolve, &typ, &|typ| matches!(typ, Type::String));
+    let allocates = strings
+        || any_type(resolve, &typ, &|typ| {
+            matches!(kind_of(resolve, typ), Some(TypeDefKind::List(_)))
+        });
+    // Lowering variants and enums fails on values of unknown cases
+    let fallible = kind == ConversionKind::Lift
+        || allocates
+        || any_type(resolve, &typ, &|typ| {
+            matches!(
+                kind_of(resolve, typ),
+                Some(TypeDefKind::Variant(_) | TypeDefKind::Enum(_))
+            )
+        });
+
+    // Strings of other encodings are converted by other helpers
+    let encoding_suffix = match encoding {
+        _ if !strings => "",
+        StringEncoding::UTF8 => "",
+        StringEncoding::UTF16 => "-UTF16",
+        StringEncoding::CompactUTF16 => "-latin1-UTF16",
+    };
+    let prefix = match kind {
+        ConversionKind::Lift => "lift",
+        ConversionKind::Lower => "lower",
+    };
+    let wit = type_wit(resolve, &typ)
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let name = GoIdentifier::prefixed_private(&format!("{prefix}-{wit}{encoding_suffix}"));
+
+    let go_type = match &resolve.types[id].kind {
+        TypeDefKind::Option(payload) => {
+            GoType::ValueOrOk(Box::new(resolve_field_type(payload, resolve)))
+        }
+        _ => resolve_type(&typ, resolve),
+    };
+    let conversion = Conversion {
+        kind,
+        typ,
+        encoding,
+        name,
+        go_type,
+        allocates,
+        fallible,
+    };
+    let name = String::from(&conversion.name);
+    helpers::record_helper(name.clone());
+    USED.with_borrow_mut(|used| {
+        if let Some(used) = used {
+            used.entry(name).or_insert_with(|| conversion.clone());
+        }
+    });
+    Some(conversion)
+}
+
+/// Runs `f`, returning the helpers lifting and lowering values it generated
+/// code calling, by name, along with its result.
+pub(crate) fn record_conversions<T>(f: impl FnOnce() -> T) -> (T, BTreeMap<String, Conversion>) {
+    let previous = USED.replace(Some(BTreeMap::new()));
+    let result = f();
+    let used = USED.replace(previous).unwrap_or_default();
+    (result, used)
+}
+
+/// Generates the code of `f` followed by the helpers it calls to convert
+/// values, as [`Bindings::generate`](crate::codegen::Bindings::generate)
+/// does for the whole file.
+#[cfg(test)]
+pub(crate) fn with_conversions(
+    resolve: &Resolve,
+    sizes: &SizeAlign,
+    f: impl FnOnce(&mut Tokens<Go>),
+) -> Tokens<Go> {
+    let mut tokens = Tokens::new();
+    let ((), used) = record_conversions(|| f(&mut tokens));
+    ConversionGenerator::new(resolve, sizes, &crate::codegen::Wazero, used)
+        .format_into(&mut tokens);
+    tokens
+}
+
+/// Records the helpers used by another thread, as returned by
+/// [`record_conversions`], if they're recorded by this one.
+pub(crate) fn extend_recorded(conversions: BTreeMap<String, Conversion>) {
+    USED.with_borrow_mut(|used| {
+        if let Some(used) = used {
+            for (name, conversion) in conversions {
+                used.entry(name).or_insert(conversion);
+            }
+        }
+    });
+}
+
+/// Follows the aliases of a type.
+fn dealias(resolve: &Resolve, typ: Type) -> Type {
+    match kind_of(resolve, &typ) {
+        Some(TypeDefKind::Type(alias)) => dealias(resolve, *alias),
+        _ => typ,
+    }
+}
+
+/// Returns the kind of the definition of a type, unless it's a primitive.
+fn kind_of<'r>(resolve: &'r Resolve, typ: &Type) -> Option<&'r TypeDefKind> {
+    match typ {
+        Type::Id(id) => Some(&resolve.types[*id].kind),
+        _ => None,
+    }
+}
+
+/// Returns true if `f` holds for the type, or for a type nested in it.
+fn any_type(resolve: &Resolve, typ: &Type, f: &impl Fn(&Type) -> bool) -> bool {
+    if f(typ) {
+        return true;
+    }
+    let any = |typ: &Type| any_type(resolve, typ, f);
+    match kind_of(resolve, typ) {
+        Some(TypeDefKind::Record(record)) => record.fields.iter().any(|field| any(&field.ty)),
+        Some(TypeDefKind::Tuple(tuple)) => tuple.types.iter().any(any),
+        Some(TypeDefKind::Variant(variant)) => {
+            variant.cases.iter().flat_map(|case| &case.ty).any(any)
+        }
+        Some(
+            TypeDefKind::Option(typ)
+            | TypeDefKind::List(typ)
+            | TypeDefKind::FixedSizeList(typ, _)
+            | TypeDefKind::Type(typ),
+        ) => any(typ),
+        Some(TypeDefKind::Result(result)) => result.ok.iter().chain(&result.err).any(any),
+        Some(TypeDefKind::Future(typ) | TypeDefKind::Stream(typ)) => typ.iter().any(any),
+        Some(
+            TypeDefKind::Resource
+            | TypeDefKind::Handle(_)
+            | TypeDefKind::Flags(_)
+            | TypeDefKind::Enum(_)
+            | TypeDefKind::Unknown,
+        )
+        | None => false,
+    }
+}
+
+/// Generator for the helpers lifting and lowering the values used by the
+/// functions of the file, along with those the helpers themselves use.
+///
+/// This must be generated once every function of the file is, with the
+/// helpers recorded by [`record_conversions`].
+pub(crate) struct ConversionGenerator<'a> {
+    resolve: &'a Resolve,
+    sizes: &'a SizeAlign,
+    backend: &'a dyn RuntimeBackend,
+    used: BTreeMap<String, Conversion>,
+}
+
+impl<'a> ConversionGenerator<'a> {
+    pub(crate) fn new(
+        resolve: &'a Resolve,
+        sizes: &'a SizeAlign,
+        backend: &'a dyn RuntimeBackend,
+        used: BTreeMap<String, Conversion>,
+    ) -> Self {
+        Self {
+            resolve,
+            sizes,
+            backend,
+            used,
+        }
+    }
+
+    /// Generates the helper lifting the values at `ptr` in the memory.
+    fn generate_lift(&self, conversion: &Conversion, tokens: &mut Tokens<Go>) {
+        let typ = &conversion.go_type;
+        let result = GoResult::Anon(GoType::ValueOrError(Box::new(typ.clone())));
+        let mut func = Func::helper(result, self.sizes)
+            .with_backend(self.backend)
+            .with_string_encoding(conversion.encoding);
+        let ptr = Operand::SingleValue("ptr".into(), GoType::Uint32);
+        let value = abi::lift_from_memory(self.resolve, &mut func, ptr, &conversion.typ);
+        let name = &conversion.name;
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[format!(
+                "{} lifts the `{}` at ptr in the memory of the guest.",
+                String::from(name),
+                type_wit(self.resolve, &conversion.typ),
+            )]))
+            func $name(memory $(self.backend.memory_type()), ptr uint32) ($typ, error) {
+                $(func.body())
+                return $(&value), nil
+            }
+        };
+    }
+
+    /// Generates the helper lowering a value into the buffer written to the
+    /// memory.
+    fn generate_lower(&self, conversion: &Conversion, tokens: &mut Tokens<Go>) {
+        let result = match conversion.fallible {
+            true => GoResult::Anon(GoType::Error),
+            false => GoResult::Empty,
+        };
+        let mut func = Func::helper(result.clone(), self.sizes)
+            .with_backend(self.backend)
+            .with_string_encoding(conversion.encoding);
+        let buf = Operand::SingleValue(LOWER_BUFFER.into(), GoType::Slice(Box::new(GoType::Uint8)));
+        let typ = resolve_field_type(&conversion.typ, self.resolve);
+        let value = Operand::SingleValue("value".into(), typ.clone());
+        abi::lower_to_memory(self.resolve, &mut func, buf, value, &conversion.typ);
+        let name = &conversion.name;
+        let buf = LOWER_BUFFER;
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} lowers the `{}` into {buf}, which is then written to",
+                    String::from(name),
+                    type_wit(self.resolve, &conversion.typ),
+                ),
+                "the memory of the guest.".into(),
+            ]))
+            func $name(
+                $(if conversion.allocates {
+                    ctx $CONTEXT_CONTEXT,
+                    memory $(self.backend.memory_type()),
+                    realloc $(self.backend.function_type()),
+                    $['\r']
+                })
+                $buf []byte,
+                value $typ,
+            ) $(&result) {
+                $(func.body())
+                $(if conversion.fallible => return nil)
+            }
+        };
+    }
+}
+
+impl FormatInto<Go> for ConversionGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        // The helpers may call other helpers, e.g. to lift the elements of
+        // lists, which are generated too
+        let mut pending = self.used.clone();
+        let mut generated = BTreeMap::new();
+        while let Some((name, conversion)) = pending.pop_first() {
+            let (helper, used) = record_conversions(|| {
+                let mut helper = Tokens::new();
+                match conversion.kind {
+                    ConversionKind::Lift => self.generate_lift(&conversion, &mut helper),
+                    ConversionKind::Lower => self.generate_lower(&conversion, &mut helper),
+                }
+                helper
+            });
+            generated.insert(name, helper);
+            for (name, conversion) in used {
+                if !generated.contains_key(&name) {
+                    pending.entry(name).or_insert(conversion);
+                }
+            }
+        }
+        for helper in generated.into_values() {
+            tokens.append(helper);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::{
+        wit_component::{StringEncoding, metadata::ModuleMetadata},
+        wit_parser::{Resolve, SizeAlign},
+    };
+
+    use crate::codegen::Bindings;
+
+    const WIT: &str = r#"
+        package test:pkg;
+
+        interface shapes {
+            record point {
+                x: s32,
+                y: s32,
+            }
+
+            variant shape {
+                circle(u32),
+                polygon(list<point>),
+                label(string),
+                empty,
+            }
+
+            record drawing {
+                name: string,
+                shapes: list<shape>,
+                origin: option<point>,
+            }
+
+            load: func(name: string) -> drawing;
+            store: func(drawing: drawing);
+            origins: func(drawings: list<drawing>) -> list<option<point>>;
+        }
+
+        world drawings {
+            import shapes;
+            use shapes.{drawing, point, shape};
+
+            export render: func(drawing: drawing) -> drawing;
+            export outline: func(shapes: list<shape>) -> list<shape>;
+            export origins: func(drawings: list<drawing>) -> option<point>;
+        }
+    "#;
+
+    fn generate(encoding: StringEncoding) -> String {
+        let mut resolve = Resolve::default();
+        let package = resolve.push_str("test.wit", WIT).unwrap();
+        let world = resolve.select_world(&[package], None).unwrap();
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+        let metadata = ModuleMetadata::new(&resolve, world, encoding);
+
+        let mut bindings = Bindings::new(&resolve, &resolve.worlds[world], &sizes);
+        bindings.set_module_metadata(&metadata);
+        bindings.generate();
+        bindings.out.to_string().unwrap()
+    }
+
+    #[test]
+    fn test_conversions() {
+        let generated = generate(StringEncoding::UTF8);
+
+        // Each helper is generated once, including those only called by
+        // other helpers
+        for helper in [
+            "liftDrawing",
+            "liftOptionPoint",
+            "liftPoint",
+            "liftShape",
+            "lowerDrawing",
+            "lowerOptionPoint",
+            "lowerPoint",
+            "lowerShape",
+        ] {
+            assert_eq!(generated.matches(&format!("func {helper}(")).count(), 1);
+        }
+        // And called by every function converting such values in memory,
+        // whether imported or exported
+        assert_eq!(
+            generated
+                .matches("lowerShape(ctx, i.memory, i.fnCabiRealloc, ")
+                .count(),
+            2
+        );
+        assert!(generated.contains("lowerShape(ctx, memory, realloc, buf2[base2:], e)"));
+        assert!(generated.contains(":= liftShape(memory, base1)"));
+        assert!(generated.contains(":= liftShape(i.memory, base14)"));
+        assert!(generated.contains(":= lowerDrawing(ctx, memory, realloc, buf19, value1)"));
+        assert!(generated.contains(":= liftDrawing(i.memory, uint32(results17))"));
+        assert!(generated.contains(":= liftOptionPoint(i.memory, uint32(results20))"));
+        assert!(generated.contains("lowerPoint(buf1[base1:], e)"));
+        // Values without strings or lists are lowered without allocating,
+        // nor failing unless they hold variants
+        assert!(generated.contains("func lowerPoint(\n    buf []byte,\n    value Point,\n) {"));
+        assert!(
+            generated.contains("func lowerOptionPoint(\n    buf []byte,\n    value *Point,\n) {")
+        );
+    }
+
+    #[test]
+    fn test_transcoded_conversions() {
+        let generated = generate(StringEncoding::UTF16);
+
+        // The helpers of values holding strings are specific to their
+        // encoding
+        assert!(
+            generated.contains(
+                "func liftDrawingUTF16(memory api.Memory, ptr uint32) (Drawing, error) {"
+            )
+        );
+        assert!(generated.contains("func lowerShapeUTF16("));
+        assert!(generated.contains(":= readStringUTF16(memory, "));
+        assert!(generated.contains("func liftPoint("));
+        assert!(!generated.contains("func liftShape("));
+    }
+}
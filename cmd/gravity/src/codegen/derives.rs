@@ -0,0 +1,606 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use genco::prelude::*;
+
+use crate::{
+    codegen::variants::{case_name, unmarshal_name},
+    go::{
+        GoIdentifier, GoType, comment,
+        imports::{
+            ENCODING_JSON_MARSHAL, ENCODING_JSON_RAW_MESSAGE, ENCODING_JSON_UNMARSHAL, FMT_ERRORF,
+            FMT_SPRINTF, REFLECT_DEEP_EQUAL, SLICES_EQUAL, SLICES_EQUAL_FUNC,
+        },
+    },
+};
+
+/// Methods generated for the enums, records and variants of the imported
+/// interfaces.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Derives {
+    /// Generate a `String()` method, implementing `fmt.Stringer`.
+    pub string: bool,
+    /// Generate `MarshalJSON` and `UnmarshalJSON` methods, encoding enums as
+    /// the WIT names of their cases, and the cases of variants as objects
+    /// tagged with them, e.g. `{"case":"text","value":"hi"}`.
+    pub json: bool,
+    /// Generate `Equal` methods for the records and variants, comparing
+    /// their slices and pointers by the values they hold.
+    pub equal: bool,
+}
+
+/// The user-defined types of the bindings, by WIT name, deciding how the
+/// generated methods handle the fields of these types.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DefinedTypes {
+    pub enums: BTreeSet<String>,
+    pub records: BTreeSet<String>,
+    pub variants: BTreeSet<String>,
+    /// The Go types the aliases refer to.
+    pub aliases: BTreeMap<String, GoType>,
+}
+
+impl Derives {
+    /// Generates the methods of an enum, whose cases are constants of the
+    /// private type named after the enum.
+    pub(crate) fn enum_methods(&self, name: &str, cases: &[String]) -> Tokens<Go> {
+        let enum_type = &GoIdentifier::private(name);
+        let enum_interface = &GoIdentifier::public(name);
+        let parse = &GoIdentifier::public(format!("parse-{name}"));
+        let names = cases
+            .iter()
+            .map(|case| (GoIdentifier::prefixed_public(case), case))
+            .collect::<Vec<_>>();
+
+        let mut tokens = Tokens::new();
+        if self.string {
+            quote_in! { tokens =>
+                $['\n']
+                func (e $enum_type) String() string {
+                    switch e {
+                    $(for (ident, case) in &names join ($['\r']) =>
+                        case $ident:
+                            return $(quoted(*case))
+                    )
+                    default:
+                        return $FMT_SPRINTF($(quoted(format!("{name}(%d)"))), int(e))
+                    }
+                }
+            };
+        }
+        if self.json {
+            quote_in! { tokens =>
+                $['\n']
+                $(comment(&[
+                    format!("{} returns the case of `{name}` with the given WIT name.", String::from(parse)),
+                ]))
+                func $parse(name string) ($enum_interface, error) {
+                    switch name {
+                    $(for (ident, case) in &names join ($['\r']) =>
+                        case $(quoted(*case)):
+                            return $ident, nil
+                    )
+                    default:
+                        return nil, $FMT_ERRORF($(quoted(format!("invalid {name}: %q"))), name)
+                    }
+                }
+                $['\n']
+                func (e $enum_type) MarshalJSON() ([]byte, error) {
+                    switch e {
+                    $(for (ident, case) in &names join ($['\r']) =>
+                        case $ident:
+                            return $ENCODING_JSON_MARSHAL($(quoted(*case)))
+                    )
+                    default:
+                        return nil, $FMT_ERRORF($(quoted(format!("invalid {name}: %d"))), int(e))
+                    }
+                }
+                $['\n']
+                func (e *$enum_type) UnmarshalJSON(data []byte) error {
+                    var name string
+                    if err := $ENCODING_JSON_UNMARSHAL(data, &name); err != nil {
+                        return err
+                    }
+                    value, err := $parse(name)
+                    if err != nil {
+                        return err
+                    }
+                    *e = value.($enum_type)
+                    return nil
+                }
+            };
+        }
+        tokens
+    }
+
+    /// Generates the methods of a record.
+    ///
+    /// Records are marshalled by `encoding/json` as usual, so `UnmarshalJSON`
+    /// is only generated for records with enum fields, which can't be
+    /// unmarshalled into the enum interfaces by default.
+    pub(crate) fn record_methods(
+        &self,
+        name: &GoIdentifier,
+        fields: &[(GoIdentifier, GoType)],
+        types: &DefinedTypes,
+    ) -> Tokens<Go> {
+        let mut tokens = Tokens::new();
+        if self.string {
+            let format = format!(
+                "{}{{{}}}",
+                String::from(name),
+                fields
+                    .iter()
+                    .map(|(field, _)| format!("{}: %v", String::from(field)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            quote_in! { tokens =>
+                $['\n']
+                func (r $name) String() string {
+                    return $FMT_SPRINTF($(quoted(format)), $(for (field, _) in fields join (, ) => r.$field))
+                }
+            };
+        }
+
+        let enum_fields = fields
+            .iter()
+            .filter_map(|(field, typ)| match typ {
+                GoType::UserDefined(typ) if types.enums.contains(typ) => {
+                    Some((field, GoIdentifier::private(typ)))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let variant_fields = fields
+            .iter()
+            .filter_map(|(field, typ)| match typ {
+                GoType::UserDefined(typ) if types.variants.contains(typ) => {
+                    Some((field, unmarshal_name(typ)))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if self.json && !(enum_fields.is_empty() && variant_fields.is_empty()) {
+            quote_in! { tokens =>
+                $['\n']
+                func (r *$name) UnmarshalJSON(data []byte) error {
+                    $(comment(&[
+                        "The enum and variant fields are unmarshalled into their concrete types,",
+                        "shadowing the interfaces of the embedded record.",
+                    ]))
+                    type fields $name
+                    raw := struct {
+                        *fields
+                        $(for (field, enum_type) in &enum_fields join ($['\r']) => $(*field) $enum_type)
+                        $(for (field, _) in &variant_fields join ($['\r']) => $(*field) $ENCODING_JSON_RAW_MESSAGE)
+                    }{fields: (*fields)(r)}
+                    if err := $ENCODING_JSON_UNMARSHAL(data, &raw); err != nil {
+                        return err
+                    }
+                    $(for (field, _) in &enum_fields join ($['\r']) => r.$(*field) = raw.$(*field))
+                    $(for (field, unmarshal) in &variant_fields join ($['\r']) =>
+                        if raw.$(*field) != nil {
+                            value, err := $unmarshal(raw.$(*field))
+                            if err != nil {
+                                return err
+                            }
+                            r.$(*field) = value
+                        }
+                    )
+                    return nil
+                }
+            };
+        }
+
+        if self.equal {
+            let mut checks = fields
+                .iter()
+                .map(|(field, typ)| equal(&quote!(r.$field), &quote!(other.$field), typ, types));
+            // The checks of the fields after the first are on lines of their
+            // own, indented below the `return`
+            let mut expr = checks.next().unwrap_or_else(|| quote!(true));
+            for (i, check) in checks.enumerate() {
+                quote_in!(expr => $[' ']&&);
+                if i == 0 {
+                    expr.indent();
+                } else {
+                    expr.push();
+                }
+                expr.append(check);
+            }
+            expr.unindent();
+            quote_in! { tokens =>
+                $['\n']
+                $(comment(&[
+                    format!("Equal returns true if the fields of the {} are equal, comparing", String::from(name)),
+                    "their slices and pointers by the values they hold.".to_string(),
+                ]))
+                func (r $name) Equal(other $name) bool {
+                    return $expr
+                }
+            };
+        }
+        tokens
+    }
+
+    /// Generates the methods of the cases of a variant, along with the
+    /// function unmarshalling any of its cases, as the variant is an
+    /// interface.
+    pub(crate) fn variant_methods(
+        &self,
+        name: &str,
+        variant: &GoIdentifier,
+        cases: &[(String, Option<GoType>)],
+        types: &DefinedTypes,
+    ) -> Tokens<Go> {
+        let mut tokens = Tokens::new();
+        for (case, payload) in cases {
+            let case_type = &case_name(name, case);
+            if self.string {
+                quote_in! { tokens =>
+                    $['\n']
+                    $(match payload {
+                        Some(_) => {
+                            func (c $case_type) String() string {
+                                return $FMT_SPRINTF($(quoted(format!("{case}(%v)"))), c.Value)
+                            }
+                        }
+                        None => {
+                            func ($case_type) String() string {
+                                return $(quoted(case))
+                            }
+                        }
+                    })
+                };
+            }
+            if self.json {
+                // Enums and variants can't be unmarshalled into their
+                // interfaces, so the payload is unmarshalled as the concrete
+                // enum type, or by the function of the variant
+                let raw_type = match payload {
+                    Some(GoType::UserDefined(typ)) if types.enums.contains(typ) => {
+                        Some(quote!($(GoIdentifier::private(typ))))
+                    }
+                    Some(GoType::UserDefined(typ)) if types.variants.contains(typ) => {
+                        Some(quote!($ENCODING_JSON_RAW_MESSAGE))
+                    }
+                    Some(payload) => Some(quote!($payload)),
+                    None => None,
+                };
+                let assign = match payload {
+                    Some(GoType::UserDefined(typ)) if types.variants.contains(typ) => quote! {
+                        value, err := $(unmarshal_name(typ))(raw.Value)
+                        if err != nil {
+                            return err
+                        }
+                        c.Value = value
+                    },
+                    Some(_) => quote!(c.Value = raw.Value),
+                    None => quote!(),
+                };
+                let receiver = match payload {
+                    Some(_) => quote!(c *$case_type),
+                    None => quote!(*$case_type),
+                };
+                let invalid = format!("invalid case of {name}: %q");
+                quote_in! { tokens =>
+                    $['\n']
+                    $(match payload {
+                        Some(_) => {
+                            func (c $case_type) MarshalJSON() ([]byte, error) {
+                                return $ENCODING_JSON_MARSHAL(map[string]any{"case": $(quoted(case)), "value": c.Value})
+                            }
+                        }
+                        None => {
+                            func ($case_type) MarshalJSON() ([]byte, error) {
+                                return $ENCODING_JSON_MARSHAL(map[string]any{"case": $(quoted(case))})
+                            }
+                        }
+                    })
+                    $['\n']
+                    func ($receiver) UnmarshalJSON(data []byte) error {
+                        var raw struct {
+                            Case string
+                            $(if let Some(raw_type) = &raw_type => Value $raw_type)
+                        }
+                        if err := $ENCODING_JSON_UNMARSHAL(data, &raw); err != nil {
+                            return err
+                        }
+                        if raw.Case != $(quoted(case)) {
+                            return $FMT_ERRORF($(quoted(&invalid)), raw.Case)
+                        }
+                        $assign
+                        return nil
+                    }
+                };
+            }
+        }
+        if self.json {
+            let unmarshal = &unmarshal_name(name);
+            quote_in! { tokens =>
+                $['\n']
+                $(comment(&[
+                    format!(
+                        "{} unmarshals the case of `{name}` encoded by its",
+                        String::from(unmarshal),
+                    ),
+                    "MarshalJSON method, and `null` as nil.".to_string(),
+                ]))
+                func $unmarshal(data []byte) ($variant, error) {
+                    if string(data) == "null" {
+                        return nil, nil
+                    }
+                    var raw struct {
+                        Case string
+                    }
+                    if err := $ENCODING_JSON_UNMARSHAL(data, &raw); err != nil {
+                        return nil, err
+                    }
+                    switch raw.Case {
+                    $(for (case, _) in cases join ($['\r']) =>
+                        case $(quoted(case)):
+                            var c $(case_name(name, case))
+                            if err := $ENCODING_JSON_UNMARSHAL(data, &c); err != nil {
+                                return nil, err
+                            }
+                            return c, nil
+                    )
+                    default:
+                        return nil, $FMT_ERRORF($(quoted(format!("invalid case of {name}: %q"))), raw.Case)
+                    }
+                }
+            };
+        }
+        tokens
+    }
+}
+
+/// Returns the Go expression comparing the values of the given type, which
+/// are equal if their slices have equal elements, their pointers point to
+/// equal values and their errors have the same message.
+///
+/// Records and variants are compared with their `Equal` methods, so these
+/// must be generated for every user-defined type.
+pub(crate) fn equal(
+    a: &Tokens<Go>,
+    b: &Tokens<Go>,
+    typ: &GoType,
+    types: &DefinedTypes,
+) -> Tokens<Go> {
+    match typ {
+        typ if comparable(typ, types) => quote!($a == $b),
+        GoType::Error => {
+            quote!(($a == nil) == ($b == nil) && ($a == nil || $a.Error() == $b.Error()))
+        }
+        GoType::Pointer(inner) => {
+            let values = equal(&quote!((*$a)), &quote!((*$b)), inner, types);
+            quote!(($a == nil) == ($b == nil) && ($a == nil || $values))
+        }
+        GoType::Result(inner) => {
+            let values = equal(&quote!($a.Value), &quote!($b.Value), inner, types);
+            let errors = equal(&quote!($a.Err), &quote!($b.Err), &GoType::Error, types);
+            quote!($values && $errors)
+        }
+        GoType::Slice(elem) if comparable(elem, types) => quote!($SLICES_EQUAL($a, $b)),
+        GoType::Slice(elem) => {
+            let elems = equal(&quote!(x), &quote!(y), elem, types);
+            quote!($SLICES_EQUAL_FUNC($a, $b, func(x, y $(elem.as_ref())) bool { return $elems }))
+        }
+        GoType::UserDefined(name) if types.records.contains(name) => quote!($a.Equal($b)),
+        GoType::UserDefined(name) if types.variants.contains(name) => {
+            quote!(($a == nil) == ($b == nil) && ($a == nil || $a.Equal($b)))
+        }
+        GoType::UserDefined(name) if types.aliases.contains_key(name) => {
+            equal(a, b, &types.aliases[name], types)
+        }
+        _ => quote!($REFLECT_DEEP_EQUAL($a, $b)),
+    }
+}
+
+/// Returns true if the values of the type can be compared with `==`.
+fn comparable(typ: &GoType, types: &DefinedTypes) -> bool {
+    match typ {
+        GoType::Bool
+        | GoType::Uint8
+        | GoType::Uint16
+        | GoType::Uint32
+        | GoType::Uint64
+        | GoType::Int8
+        | GoType::Int16
+        | GoType::Int32
+        | GoType::Int64
+        | GoType::Float32
+        | GoType::Float64
+        | GoType::Rune
+        | GoType::String
+        | GoType::ErrorContext
+        | GoType::OwnHandle(_)
+        | GoType::BorrowHandle(_) => true,
+        GoType::UserDefined(name) => match types.aliases.get(name) {
+            Some(target) => comparable(target, types),
+            None => types.enums.contains(name),
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use genco::prelude::*;
+
+    use crate::{
+        codegen::derives::{DefinedTypes, Derives},
+        go::{GoIdentifier, GoType},
+    };
+
+    #[test]
+    fn test_enum_methods() {
+        let derives = Derives {
+            string: true,
+            json: true,
+            equal: false,
+        };
+        let cases = ["red".to_string(), "dark-green".to_string()];
+        let tokens: Tokens<Go> = derives.enum_methods("color", &cases);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("func (e color) String() string {"));
+        assert!(output.contains("case DarkGreen:\n        return \"dark-green\""));
+        assert!(output.contains("func ParseColor(name string) (Color, error) {"));
+        assert!(output.contains("case \"dark-green\":\n        return DarkGreen, nil"));
+        assert!(output.contains("func (e color) MarshalJSON() ([]byte, error) {"));
+        assert!(output.contains("func (e *color) UnmarshalJSON(data []byte) error {"));
+        assert!(output.contains("*e = value.(color)"));
+    }
+
+    #[test]
+    fn test_enum_methods_disabled() {
+        let cases = ["red".to_string()];
+        let tokens: Tokens<Go> = Derives::default().enum_methods("color", &cases);
+        assert!(tokens.is_empty());
+    }
+
+    #[test]
+    fn test_record_methods() {
+        let derives = Derives {
+            string: true,
+            json: true,
+            equal: false,
+        };
+        let fields = [
+            (GoIdentifier::public("name"), GoType::String),
+            (
+                GoIdentifier::public("color"),
+                GoType::UserDefined("color".to_string()),
+            ),
+        ];
+        let types = DefinedTypes {
+            enums: BTreeSet::from(["color".to_string()]),
+            ..DefinedTypes::default()
+        };
+        let tokens: Tokens<Go> =
+            derives.record_methods(&GoIdentifier::public("pixel"), &fields, &types);
+        let output = tokens.to_string().unwrap();
+
+        assert!(
+            output.contains("return fmt.Sprintf(\"Pixel{Name: %v, Color: %v}\", r.Name, r.Color)")
+        );
+        assert!(output.contains("func (r *Pixel) UnmarshalJSON(data []byte) error {"));
+        assert!(output.contains("Color color"));
+        assert!(output.contains("r.Color = raw.Color"));
+    }
+
+    #[test]
+    fn test_record_methods_without_enums() {
+        let derives = Derives {
+            string: false,
+            json: true,
+            equal: false,
+        };
+        let fields = [(GoIdentifier::public("name"), GoType::String)];
+        let tokens: Tokens<Go> =
+            derives.record_methods(&GoIdentifier::public("pixel"), &fields, &Default::default());
+        assert!(tokens.is_empty());
+    }
+
+    #[test]
+    fn test_record_equal() {
+        let derives = Derives {
+            equal: true,
+            ..Derives::default()
+        };
+        let fields = [
+            (GoIdentifier::public("name"), GoType::String),
+            (
+                GoIdentifier::public("tags"),
+                GoType::Slice(Box::new(GoType::String)),
+            ),
+            (
+                GoIdentifier::public("age"),
+                GoType::Pointer(Box::new(GoType::Uint32)),
+            ),
+            (
+                GoIdentifier::public("children"),
+                GoType::Slice(Box::new(GoType::UserDefined("person".to_string()))),
+            ),
+            (
+                GoIdentifier::public("count"),
+                GoType::Result(Box::new(GoType::Uint32)),
+            ),
+            (
+                GoIdentifier::public("shape"),
+                GoType::UserDefined("shape".to_string()),
+            ),
+        ];
+        let types = DefinedTypes {
+            records: BTreeSet::from(["person".to_string()]),
+            variants: BTreeSet::from(["shape".to_string()]),
+            ..DefinedTypes::default()
+        };
+        let tokens: Tokens<Go> =
+            derives.record_methods(&GoIdentifier::public("person"), &fields, &types);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("func (r Person) Equal(other Person) bool {"));
+        assert!(output.contains("return r.Name == other.Name &&\n"));
+        assert!(output.contains("slices.Equal(r.Tags, other.Tags) &&\n"));
+        assert!(output.contains(
+            "(r.Age == nil) == (other.Age == nil) && (r.Age == nil || (*r.Age) == (*other.Age)) &&\n"
+        ));
+        assert!(output.contains(
+            "slices.EqualFunc(r.Children, other.Children, func(x, y Person) bool { return x.Equal(y) }) &&\n"
+        ));
+        assert!(output.contains(
+            "r.Count.Value == other.Count.Value && (r.Count.Err == nil) == (other.Count.Err == nil)"
+        ));
+        assert!(output.contains("(r.Shape == nil || r.Shape.Equal(other.Shape))\n}"));
+    }
+
+    #[test]
+    fn test_variant_methods() {
+        let derives = Derives {
+            string: true,
+            json: true,
+            equal: false,
+        };
+        let cases = [
+            ("text".to_string(), Some(GoType::String)),
+            (
+                "color".to_string(),
+                Some(GoType::UserDefined("color".to_string())),
+            ),
+            (
+                "nested".to_string(),
+                Some(GoType::UserDefined("message".to_string())),
+            ),
+            ("none".to_string(), None),
+        ];
+        let types = DefinedTypes {
+            enums: BTreeSet::from(["color".to_string()]),
+            variants: BTreeSet::from(["message".to_string()]),
+            ..DefinedTypes::default()
+        };
+        let tokens: Tokens<Go> =
+            derives.variant_methods("message", &GoIdentifier::public("message"), &cases, &types);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("func (c MessageText) String() string {\n    return fmt.Sprintf(\"text(%v)\", c.Value)\n}"));
+        assert!(output.contains("func (MessageNone) String() string {\n    return \"none\"\n}"));
+        // The cases are tagged with their WIT names
+        assert!(output.contains(
+            "return json.Marshal(map[string]any{\"case\": \"text\", \"value\": c.Value})"
+        ));
+        assert!(output.contains("return json.Marshal(map[string]any{\"case\": \"none\"})"));
+        assert!(output.contains("func (c *MessageText) UnmarshalJSON(data []byte) error {"));
+        assert!(output.contains("func (*MessageNone) UnmarshalJSON(data []byte) error {"));
+        assert!(output.contains("if raw.Case != \"text\" {"));
+        // Enum and variant payloads are unmarshalled as their concrete types
+        assert!(output.contains("Case string\n        Value color\n    }"));
+        assert!(output.contains("Value json.RawMessage"));
+        assert!(output.contains("value, err := UnmarshalMessage(raw.Value)"));
+        assert!(output.contains("func UnmarshalMessage(data []byte) (Message, error) {"));
+        assert!(output.contains("case \"nested\":\n        var c MessageNested"));
+    }
+}
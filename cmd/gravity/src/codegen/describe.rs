@@ -0,0 +1,168 @@
+use std::fmt::Write;
+
+use crate::compat::wit_component::WitPrinter;
+use crate::compat::wit_parser::{Resolve, WorldId};
+
+use crate::{
+    codegen::{
+        imports::ImportAnalyzer,
+        symbols::{SymbolTable, export_methods},
+    },
+    go::GoIdentifier,
+};
+
+/// Describes a world as seen by gravity, for debugging the generated
+/// bindings.
+///
+/// This prints the WIT of every package in the `Resolve`, followed by the Go
+/// identifiers gravity generates for the items of the world. The Go names are
+/// printed as comments, so the output remains valid WIT.
+pub fn describe_world(resolve: &Resolve, world_id: WorldId) -> String {
+    let mut out = String::new();
+    for (id, _) in resolve.packages.iter() {
+        let mut printer = WitPrinter::default();
+        printer
+            .print(resolve, id, &[])
+            .expect("package should be printable");
+        writeln!(out, "{}", printer.output).unwrap();
+    }
+    out.push_str(&go_names(resolve, world_id));
+    out
+}
+
+/// Lists the Go identifiers generated for the items of a world.
+fn go_names(resolve: &Resolve, world_id: WorldId) -> String {
+    let world = &resolve.worlds[world_id];
+    let analyzed = ImportAnalyzer::new(resolve, world).analyze();
+    let name = |ident: &GoIdentifier| String::from(ident);
+
+    let mut out = String::new();
+    writeln!(out, "// Go names for world `{}`:", world.name).unwrap();
+    writeln!(out, "//").unwrap();
+    writeln!(
+        out,
+        "// factory `{}`, created by `{}`",
+        name(&analyzed.factory_name),
+        name(&analyzed.constructor_name)
+    )
+    .unwrap();
+    writeln!(out, "// instance `{}`", name(&analyzed.instance_name)).unwrap();
+    for interface in &analyzed.interfaces {
+        writeln!(
+            out,
+            "// import `{}` -> interface `{}`, parameter `{}`",
+            interface.wazero_module_name,
+            name(&interface.go_interface_name),
+            name(&interface.constructor_param_name)
+        )
+        .unwrap();
+        for typ in &interface.types {
+            writeln!(
+                out,
+                "//   type `{}` -> `{}`",
+                typ.name,
+                name(&typ.go_type_name)
+            )
+            .unwrap();
+        }
+        for method in &interface.methods {
+            writeln!(
+                out,
+                "//   function `{}` -> method `{}`",
+                method.name,
+                name(&method.go_method_name)
+            )
+            .unwrap();
+        }
+    }
+    for typ in &analyzed.standalone_types {
+        writeln!(
+            out,
+            "// import type `{}` -> `{}`",
+            typ.name,
+            name(&typ.go_type_name)
+        )
+        .unwrap();
+    }
+
+    let instance = name(&analyzed.instance_name);
+    let methods = export_methods(resolve, world, &mut SymbolTable::default());
+    for method in &methods.functions {
+        writeln!(
+            out,
+            "// export function `{}` -> method `(*{instance}).{}`",
+            method.func.name,
+            name(&GoIdentifier::public(&method.name))
+        )
+        .unwrap();
+    }
+    for interface in &methods.interfaces {
+        let go_type = name(&interface.go_type);
+        writeln!(
+            out,
+            "// export `{}` -> type `{go_type}`, returned by `(*{instance}).{}`",
+            resolve.name_world_key(interface.key),
+            name(&GoIdentifier::public(&interface.name))
+        )
+        .unwrap();
+        for method in &interface.methods {
+            writeln!(
+                out,
+                "//   export function `{}` -> method `(*{go_type}).{}`",
+                method.func.name,
+                name(&GoIdentifier::public(&method.name))
+            )
+            .unwrap();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::wit_parser::Resolve;
+
+    use crate::codegen::describe::describe_world;
+
+    #[test]
+    fn test_describe_world() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface logger {
+                    enum level { debug, info }
+                    log: func(level: level, message: string);
+                }
+
+                world with-logger {
+                    import logger;
+                    export say-hello: func() -> string;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (world_id, _) = resolve.worlds.iter().next().expect("missing world");
+
+        let output = describe_world(&resolve, world_id);
+        println!("{output}");
+
+        assert!(output.contains("package test:pkg;"));
+        assert!(output.contains("log: func(level: level, message: string);"));
+        assert!(output.contains("// Go names for world `with-logger`:"));
+        assert!(
+            output.contains("// factory `WithLoggerFactory`, created by `NewWithLoggerFactory`")
+        );
+        assert!(output.contains("// import `test:pkg/logger` -> interface `IWithLoggerLogger`"));
+        assert!(output.contains("//   type `level` -> `Level`"));
+        assert!(output.contains("//   function `log` -> method `Log`"));
+        assert!(
+            output.contains(
+                "// export function `say-hello` -> method `(*WithLoggerInstance).SayHello`"
+            )
+        );
+    }
+}
@@ -0,0 +1,489 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::compat::wit_parser::{
+    Function, Handle, InterfaceId, Resolve, Type, TypeDefKind, TypeId, WorldId, WorldItem, WorldKey,
+};
+
+/// How an item of a world changed between two versions of a component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A change to an item of a world, such as a function or a type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// The item, e.g. "export function `greet`".
+    pub item: String,
+    /// The WIT of the item before the change, if it existed.
+    pub old: Option<String>,
+    /// The WIT of the item after the change, if it still exists.
+    pub new: Option<String>,
+    /// Whether regenerating the bindings breaks Go code using them.
+    pub breaking: bool,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.kind, &self.old, &self.new) {
+            (ChangeKind::Added, _, Some(new)) => write!(f, "+ {}: {new}", self.item)?,
+            (ChangeKind::Removed, Some(old), _) => write!(f, "- {}: {old}", self.item)?,
+            (_, old, new) => write!(
+                f,
+                "~ {}: {} -> {}",
+                self.item,
+                old.as_deref().unwrap_or_default(),
+                new.as_deref().unwrap_or_default()
+            )?,
+        }
+        if self.breaking {
+            write!(f, " (breaking)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether an item is imported or exported by the world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Import,
+    Export,
+}
+
+/// What an item of the world is, which decides whether changing it breaks
+/// the Go API of the bindings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemKind {
+    Interface,
+    Function,
+    Type,
+}
+
+/// An item of a world, with its WIT with and without the names of the
+/// parameters, which don't change the Go API.
+struct Item {
+    direction: Direction,
+    kind: ItemKind,
+    wit: String,
+    shape: String,
+}
+
+/// Compares the items of two worlds, e.g. of the old and new versions of
+/// a component, returning the functions, interfaces and types added,
+/// removed or changed, ordered by item.
+///
+/// Interfaces are identified by their WIT name without their version, so
+/// upgrading the version of a package only reports the items that changed.
+///
+/// A change is breaking if Go code using the bindings of the old world may
+/// no longer compile with those of the new one: removing or changing an
+/// export, adding a function to an import, which the host must then
+/// implement, or adding or removing an imported interface, which changes the
+/// parameters of the factory constructor.
+pub fn diff_worlds(
+    old: &Resolve,
+    old_world: WorldId,
+    new: &Resolve,
+    new_world: WorldId,
+) -> Vec<Change> {
+    let old = world_items(old, old_world);
+    let mut new = world_items(new, new_world);
+    let mut changes = Vec::new();
+    for (item, old) in old {
+        match new.remove(&item) {
+            None => changes.push(Change {
+                kind: ChangeKind::Removed,
+                // Hosts don't have to implement the functions of the imports
+                // that are removed, so they keep compiling
+                breaking: !(old.direction == Direction::Import && old.kind != ItemKind::Interface),
+                item,
+                old: Some(old.wit),
+                new: None,
+            }),
+            Some(new) if new.wit != old.wit => changes.push(Change {
+                kind: ChangeKind::Changed,
+                breaking: new.shape != old.shape,
+                item,
+                old: Some(old.wit),
+                new: Some(new.wit),
+            }),
+            Some(_) => (),
+        }
+    }
+    for (item, new) in new {
+        changes.push(Change {
+            kind: ChangeKind::Added,
+            breaking: new.direction == Direction::Import && new.kind != ItemKind::Type,
+            item,
+            old: None,
+            new: Some(new.wit),
+        });
+    }
+    changes.sort_by(|a, b| a.item.cmp(&b.item));
+    changes
+}
+
+/// Lists the items of the world by a description of them, e.g. "import
+/// function `wasi:logging/logging#log`".
+fn world_items(resolve: &Resolve, world_id: WorldId) -> BTreeMap<String, Item> {
+    let world = &resolve.worlds[world_id];
+    let mut items = BTreeMap::new();
+    let mut insert = |direction: Direction, kind: ItemKind, name: String, wit: Wit| {
+        let prefix = match direction {
+            Direction::Import => "import",
+            Direction::Export => "export",
+        };
+        let kind_name = match kind {
+            ItemKind::Interface => "interface",
+            ItemKind::Function => "function",
+            ItemKind::Type => "type",
+        };
+        items.insert(
+            format!("{prefix} {kind_name} `{name}`"),
+            Item {
+                direction,
+                kind,
+                wit: wit.named,
+                shape: wit.unnamed,
+            },
+        );
+    };
+    for (direction, world_items) in [
+        (Direction::Import, &world.imports),
+        (Direction::Export, &world.exports),
+    ] {
+        for (key, item) in world_items {
+            match item {
+                WorldItem::Function(func) => {
+                    insert(
+                        direction,
+                        ItemKind::Function,
+                        func.name.clone(),
+                        function_wit(resolve, func),
+                    );
+                }
+                WorldItem::Type(id) => {
+                    let name = resolve.types[*id].name.clone().unwrap_or_default();
+                    insert(direction, ItemKind::Type, name, type_def_wit(resolve, *id));
+                }
+                WorldItem::Interface { id, .. } => {
+                    let name = interface_name(resolve, key, *id);
+                    let interface = &resolve.interfaces[*id];
+                    insert(
+                        direction,
+                        ItemKind::Interface,
+                        name.clone(),
+                        Wit::same("interface".to_string()),
+                    );
+                    for (type_name, id) in &interface.types {
+                        insert(
+                            direction,
+                            ItemKind::Type,
+                            format!("{name}.{type_name}"),
+                            type_def_wit(resolve, *id),
+                        );
+                    }
+                    for func in interface.functions.values() {
+                        insert(
+                            direction,
+                            ItemKind::Function,
+                            format!("{name}#{}", func.name),
+                            function_wit(resolve, func),
+                        );
+                    }
+                }
+            }
+        }
+    }
+    items
+}
+
+/// The name of an interface of a world, e.g. `wasi:logging/logging`,
+/// without the version of its package.
+fn interface_name(resolve: &Resolve, key: &WorldKey, id: InterfaceId) -> String {
+    let interface = &resolve.interfaces[id];
+    match (key, interface.package, &interface.name) {
+        (WorldKey::Interface(_), Some(package), Some(name)) => {
+            let package = &resolve.packages[package].name;
+            format!("{}:{}/{name}", package.namespace, package.name)
+        }
+        _ => resolve.name_world_key(key),
+    }
+}
+
+/// The WIT of an item, with and without the names of the parameters.
+struct Wit {
+    named: String,
+    unnamed: String,
+}
+
+impl Wit {
+    fn same(wit: String) -> Self {
+        Self {
+            unnamed: wit.clone(),
+            named: wit,
+        }
+    }
+}
+
+fn function_wit(resolve: &Resolve, func: &Function) -> Wit {
+    let result = match &func.result {
+        Some(typ) => format!(" -> {}", type_wit(resolve, typ)),
+        None => String::new(),
+    };
+    let params = func
+        .params
+        .iter()
+        .map(|(name, typ)| (name, type_wit(resolve, typ)))
+        .collect::<Vec<_>>();
+    let named = params
+        .iter()
+        .map(|(name, typ)| format!("{name}: {typ}"))
+        .collect::<Vec<_>>();
+    let unnamed = params
+        .iter()
+        .map(|(_, typ)| typ.as_str())
+        .collect::<Vec<_>>();
+    Wit {
+        named: format!("func({}){result}", named.join(", ")),
+        unnamed: format!("func({}){result}", unnamed.join(", ")),
+    }
+}
+
+/// The WIT of a type definition, e.g. `record { x: u32 }`.
+fn type_def_wit(resolve: &Resolve, id: TypeId) -> Wit {
+    let typ = |typ: &Type| type_wit(resolve, typ);
+    let list = |items: Vec<String>| items.join(", ");
+    let wit = match &resolve.types[id].kind {
+        TypeDefKind::Record(record) => format!(
+            "record {{ {} }}",
+            list(
+                record
+                    .fields
+                    .iter()
+                    .map(|field| format!("{}: {}", field.name, typ(&field.ty)))
+                    .collect()
+            )
+        ),
+        TypeDefKind::Variant(variant) => format!(
+            "variant {{ {} }}",
+            list(
+                variant
+                    .cases
+                    .iter()
+                    .map(|case| match &case.ty {
+                        Some(ty) => format!("{}({})", case.name, typ(ty)),
+                        None => case.name.clone(),
+                    })
+                    .collect()
+            )
+        ),
+        TypeDefKind::Enum(enum_) => format!(
+            "enum {{ {} }}",
+            list(enum_.cases.iter().map(|case| case.name.clone()).collect())
+        ),
+        TypeDefKind::Flags(flags) => format!(
+            "flags {{ {} }}",
+            list(flags.flags.iter().map(|flag| flag.name.clone()).collect())
+        ),
+        TypeDefKind::Resource => "resource".to_string(),
+        _ => format!("type {}", anonymous_type_wit(resolve, id)),
+    };
+    Wit::same(wit)
+}
+
+/// The WIT of a type as used by a function or another type, i.e. the name of
+/// a named type.
+pub(crate) fn type_wit(resolve: &Resolve, typ: &Type) -> String {
+    match typ {
+        Type::Bool => "bool".to_string(),
+        Type::U8 => "u8".to_string(),
+        Type::U16 => "u16".to_string(),
+        Type::U32 => "u32".to_string(),
+        Type::U64 => "u64".to_string(),
+        Type::S8 => "s8".to_string(),
+        Type::S16 => "s16".to_string(),
+        Type::S32 => "s32".to_string(),
+        Type::S64 => "s64".to_string(),
+        Type::F32 => "f32".to_string(),
+        Type::F64 => "f64".to_string(),
+        Type::Char => "char".to_string(),
+        Type::String => "string".to_string(),
+        Type::ErrorContext => "error-context".to_string(),
+        Type::Id(id) => match &resolve.types[*id].name {
+            Some(name) => name.clone(),
+            None => anonymous_type_wit(resolve, *id),
+        },
+    }
+}
+
+/// The WIT of the definition of a type, without its name.
+fn anonymous_type_wit(resolve: &Resolve, id: TypeId) -> String {
+    let typ = |typ: &Type| type_wit(resolve, typ);
+    let optional = |ty: &Option<Type>| ty.as_ref().map_or("_".to_string(), typ);
+    match &resolve.types[id].kind {
+        TypeDefKind::List(ty) => format!("list<{}>", typ(ty)),
+        TypeDefKind::FixedSizeList(ty, size) => format!("list<{}, {size}>", typ(ty)),
+        TypeDefKind::Option(ty) => format!("option<{}>", typ(ty)),
+        TypeDefKind::Result(result) => {
+            format!(
+                "result<{}, {}>",
+                optional(&result.ok),
+                optional(&result.err)
+            )
+        }
+        TypeDefKind::Tuple(tuple) => format!(
+            "tuple<{}>",
+            tuple.types.iter().map(typ).collect::<Vec<_>>().join(", ")
+        ),
+        TypeDefKind::Handle(Handle::Own(id)) => format!("own<{}>", typ(&Type::Id(*id))),
+        TypeDefKind::Handle(Handle::Borrow(id)) => format!("borrow<{}>", typ(&Type::Id(*id))),
+        TypeDefKind::Future(ty) => format!("future<{}>", optional(ty)),
+        TypeDefKind::Stream(ty) => format!("stream<{}>", optional(ty)),
+        TypeDefKind::Type(ty) => typ(ty),
+        TypeDefKind::Unknown => "unknown".to_string(),
+        TypeDefKind::Record(_)
+        | TypeDefKind::Variant(_)
+        | TypeDefKind::Enum(_)
+        | TypeDefKind::Flags(_)
+        | TypeDefKind::Resource => type_def_wit(resolve, id).named,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::wit_parser::Resolve;
+
+    use super::{ChangeKind, diff_worlds};
+
+    fn world(wit: &str) -> (Resolve, crate::compat::wit_parser::WorldId) {
+        let mut resolve = Resolve::default();
+        let package = resolve
+            .push_str("test.wit", wit)
+            .expect("failed to parse WIT");
+        let world = resolve
+            .select_world(&[package], None)
+            .expect("missing world");
+        (resolve, world)
+    }
+
+    #[test]
+    fn test_diff_worlds() {
+        let (old, old_world) = world(
+            r#"
+            package test:pkg@1.0.0;
+
+            interface logger {
+                log: func(msg: string);
+            }
+
+            interface types {
+                record point { x: u32 }
+                enum level { debug }
+            }
+
+            world example {
+                use types.{point};
+                import logger;
+                import types;
+                export greet: func(name: string) -> string;
+                export origin: func() -> point;
+                export version: func() -> u32;
+            }
+            "#,
+        );
+        let (new, new_world) = world(
+            r#"
+            package test:pkg@1.1.0;
+
+            interface logger {
+                log: func(msg: string);
+                flush: func();
+            }
+
+            interface types {
+                record point { x: u32, y: u32 }
+                enum level { debug }
+            }
+
+            world example {
+                use types.{point};
+                import logger;
+                import types;
+                export greet: func(who: string) -> string;
+                export origin: func() -> point;
+                export origins: func() -> list<point>;
+            }
+            "#,
+        );
+
+        let changes = diff_worlds(&old, old_world, &new, new_world)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                "~ export function `greet`: func(name: string) -> string -> func(who: string) -> string",
+                "+ export function `origins`: func() -> list<point>",
+                "- export function `version`: func() -> u32 (breaking)",
+                "+ import function `test:pkg/logger#flush`: func() (breaking)",
+                "~ import type `test:pkg/types.point`: record { x: u32 } -> record { x: u32, y: u32 } (breaking)",
+            ]
+        );
+
+        // Removing an import's function doesn't break the hosts implementing
+        // it, unlike removing the whole interface
+        let changes = diff_worlds(&new, new_world, &old, old_world);
+        let flush = changes
+            .iter()
+            .find(|change| change.item == "import function `test:pkg/logger#flush`")
+            .expect("missing change");
+        assert_eq!(flush.kind, ChangeKind::Removed);
+        assert!(!flush.breaking);
+    }
+
+    #[test]
+    fn test_diff_worlds_interfaces() {
+        let (old, old_world) = world(
+            r#"
+            package test:pkg;
+
+            interface logger {
+                log: func(msg: string);
+            }
+
+            world example {
+                import logger;
+            }
+            "#,
+        );
+        let (new, new_world) = world(
+            r#"
+            package test:pkg;
+
+            world example {
+                export run: func();
+            }
+            "#,
+        );
+
+        let changes = diff_worlds(&old, old_world, &new, new_world);
+        let breaking = changes
+            .iter()
+            .map(|change| (change.item.as_str(), change.breaking))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            breaking,
+            [
+                ("export function `run`", false),
+                ("import function `test:pkg/logger#log`", false),
+                ("import interface `test:pkg/logger`", true),
+            ]
+        );
+        assert!(diff_worlds(&old, old_world, &old, old_world).is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+use crate::compat::wit_parser::{Function, Stability};
+
+/// The markers of the paragraph of WIT docs deprecating an item.
+const MARKERS: [&str; 2] = ["Deprecated:", "@deprecated"];
+
+/// Returns the lines of the Go doc comment paragraph deprecating the
+/// function, if its WIT deprecates it, e.g. `Deprecated: use greet-v2.`,
+/// which Go tooling like staticcheck and gopls warns about.
+///
+/// Functions are deprecated by a `@deprecated` gate, or by a paragraph of
+/// their docs starting with `Deprecated:` or `@deprecated`, whose text is
+/// kept as the reason.
+pub fn deprecation(func: &Function) -> Option<Vec<String>> {
+    let version = match &func.stability {
+        Stability::Stable { deprecated, .. } | Stability::Unstable { deprecated, .. } => {
+            deprecated.as_ref()
+        }
+        Stability::Unknown => None,
+    };
+    let contents = func.docs.contents.as_deref().unwrap_or_default();
+    let mut lines = contents.lines().map(str::trim);
+    let reason = lines.by_ref().find_map(|line| {
+        MARKERS
+            .iter()
+            .find_map(|marker| line.strip_prefix(marker))
+            .map(|rest| rest.trim_start_matches(':').trim())
+    });
+    let mut reason = match reason {
+        // The reason is the rest of the paragraph
+        Some(first) => std::iter::once(first)
+            .chain(lines.take_while(|line| !line.is_empty()))
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>(),
+        None if version.is_some() => Vec::new(),
+        None => return None,
+    };
+    if reason.is_empty() {
+        reason.push(match version {
+            Some(version) => format!("since version {version}."),
+            None => "this function shouldn't be used anymore.".to_string(),
+        });
+    }
+    reason[0] = format!("Deprecated: {}", reason[0]);
+    Some(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::wit_parser::Resolve;
+
+    use super::deprecation;
+
+    #[test]
+    fn test_deprecation() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg@0.2.0;
+
+                interface greeter {
+                    /// Greets the person.
+                    ///
+                    /// Deprecated: use greet-v2, which
+                    /// greets them by name.
+                    greet: func();
+
+                    @since(version = 0.1.0)
+                    @deprecated(version = 0.2.0)
+                    hello: func();
+
+                    /// Says hi.
+                    ///
+                    /// @deprecated
+                    hi: func();
+
+                    greet-v2: func(name: string);
+                }
+                "#,
+            )
+            .unwrap();
+        let (_, interface) = resolve.interfaces.iter().next().unwrap();
+        let deprecation = |name: &str| deprecation(&interface.functions[name]);
+
+        assert_eq!(
+            deprecation("greet").unwrap(),
+            ["Deprecated: use greet-v2, which", "greets them by name."]
+        );
+        assert_eq!(
+            deprecation("hello").unwrap(),
+            ["Deprecated: since version 0.2.0."]
+        );
+        assert_eq!(
+            deprecation("hi").unwrap(),
+            ["Deprecated: this function shouldn't be used anymore."]
+        );
+        assert_eq!(deprecation("greet-v2"), None);
+    }
+}
@@ -0,0 +1,276 @@
+use genco::prelude::*;
+
+use crate::go::{
+    GoIdentifier, GoResult, GoType, comment,
+    imports::{CONTEXT_CONTEXT, FMT_ERRORF, REFLECT_TYPE, REFLECT_TYPE_FOR},
+};
+
+/// The name of the Go struct describing an exported function.
+const EXPORT_INFO: &str = "export-info";
+
+/// An exported function, as listed and called by name on the instance.
+pub(crate) struct DynamicExport {
+    /// The WIT name of the function, qualified by its interface, if any.
+    pub name: String,
+    /// The accessor of the instance returning the exported interface, if the
+    /// function is exported by one.
+    pub accessor: Option<GoIdentifier>,
+    /// The Go method implementing the function, on the instance or on the
+    /// exported interface.
+    pub method: GoIdentifier,
+    /// The types of the parameters of the method, after the context.
+    pub params: Vec<GoType>,
+    /// The result of the method.
+    pub result: GoResult,
+}
+
+/// Generator for the `Exports` and `CallByName` methods of an instance,
+/// which list and call its exported functions by name, for hosts routing
+/// calls without knowing the world at compile time.
+pub(crate) struct DynamicExportsGenerator<'a> {
+    instance: &'a GoIdentifier,
+    exports: &'a [DynamicExport],
+    tinygo_compat: bool,
+}
+
+impl<'a> DynamicExportsGenerator<'a> {
+    pub(crate) fn new(instance: &'a GoIdentifier, exports: &'a [DynamicExport]) -> Self {
+        Self {
+            instance,
+            exports,
+            tinygo_compat: false,
+        }
+    }
+
+    /// Avoids reflection, describing the types of the exported functions by
+    /// their names in Go rather than by their `reflect.Type`.
+    pub(crate) fn with_tinygo_compat(mut self, tinygo_compat: bool) -> Self {
+        self.tinygo_compat = tinygo_compat;
+        self
+    }
+
+    /// The description of a type in `ExportInfo`.
+    fn type_info(&self, typ: &GoType) -> Tokens<Go> {
+        if self.tinygo_compat {
+            quote!($(quoted(type_name(typ))))
+        } else {
+            quote!($REFLECT_TYPE_FOR[$typ]())
+        }
+    }
+
+    /// Generates the `case` of `CallByName` calling the export.
+    fn generate_case(&self, export: &DynamicExport, tokens: &mut Tokens<Go>) {
+        let method = &export.method;
+        let args = (0..export.params.len())
+            .map(|i| format!("arg{i}"))
+            .collect::<Vec<_>>();
+        let (values, returns_err) = export.result.values();
+        let names = values
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .chain(returns_err.then_some("err"))
+            .collect::<Vec<_>>();
+        let receiver = match &export.accessor {
+            Some(accessor) => quote!(i.$accessor()),
+            None => quote!(i),
+        };
+        let call = quote!($receiver.$method(ctx$(for arg in &args => , $arg)));
+        quote_in! { *tokens =>
+            $['\r']
+            case $(quoted(&export.name)):
+                if len(args) != $(export.params.len()) {
+                    return nil, $FMT_ERRORF("%s takes %d arguments, but got %d", name, $(export.params.len()), len(args))
+                }
+                $(for (i, (arg, typ)) in args.iter().zip(&export.params).enumerate() join ($['\r']) =>
+                    $arg, ok := args[$i].($typ)
+                    if !ok {
+                        $(if self.tinygo_compat {
+                            return nil, $FMT_ERRORF("argument %d of %s must be a %s", $i, name, $(quoted(type_name(typ))))
+                        } else {
+                            return nil, $FMT_ERRORF("argument %d of %s must be a %s, but got %T", $i, name, $(quoted(type_name(typ))), args[$i])
+                        })
+                    }
+                )
+                $(if names.is_empty() {
+                    $call
+                } else {
+                    $(for name in &names join (, ) => $(*name)) := $call
+                })
+                $(if returns_err {
+                    if err != nil {
+                        return nil, err
+                    }
+                })
+                return []any{$(for (name, _) in &values join (, ) => $name)}, nil
+        };
+    }
+}
+
+/// The Go syntax of the type, as shown in errors.
+pub(crate) fn type_name(typ: &GoType) -> String {
+    quote!($typ).to_string().expect("failed to format Go type")
+}
+
+impl FormatInto<Go> for DynamicExportsGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let instance = self.instance;
+        let info = &GoIdentifier::prefixed_public(EXPORT_INFO);
+        let mut cases = Tokens::new();
+        for export in self.exports {
+            self.generate_case(export, &mut cases);
+        }
+        let info_type = if self.tinygo_compat {
+            quote!(string)
+        } else {
+            quote!($REFLECT_TYPE)
+        };
+        let (params_doc, results_doc) = if self.tinygo_compat {
+            (
+                "The Go types of the arguments of the function, after the context.",
+                "The Go types of the values returned by the function, except for any error.",
+            )
+        } else {
+            (
+                "The types of the arguments of the function, after the context.",
+                "The types of the values returned by the function, except for any error.",
+            )
+        };
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!("{} describes a function exported by a component.", String::from(info)),
+            ]))
+            type $info struct {
+                $(comment(&["The WIT name of the function, e.g. `ns:pkg/name#function` for a function of an exported interface."]))
+                Name string
+                $(comment(&[params_doc]))
+                Params []$(&info_type)
+                $(comment(&[results_doc]))
+                Results []$(&info_type)
+            }
+            $['\n']
+            $(comment(&[
+                "Exports returns the functions exported by the component, which can be",
+                "called with CallByName.",
+            ]))
+            func (i *$instance) Exports() []$info {
+                return []$info{
+                    $(for export in self.exports join ($['\r']) =>
+                        {
+                            Name: $(quoted(&export.name)),
+                            Params: []$(&info_type){$(for typ in &export.params join (, ) => $(self.type_info(typ)))},
+                            Results: []$(&info_type){$(for (_, typ) in export.result.values().0 join (, ) => $(self.type_info(&typ)))},
+                        },
+                    )
+                }
+            }
+            $['\n']
+            $(comment(&[
+                "CallByName calls the exported function with the given WIT name, as listed",
+                "by Exports, returning the values it returns except for any error.",
+                "",
+                "The arguments must have the exact types of the parameters of the function.",
+            ]))
+            func (i *$instance) CallByName(ctx $CONTEXT_CONTEXT, name string, args []any) ([]any, error) {
+                switch name {
+                $cases
+                $['\r']
+                default:
+                    return nil, $FMT_ERRORF("unknown export: %s", name)
+                }
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use genco::prelude::*;
+
+    use crate::{
+        codegen::dynamic::{DynamicExport, DynamicExportsGenerator},
+        go::{GoIdentifier, GoResult, GoType},
+    };
+
+    #[test]
+    fn test_dynamic_exports_generator() {
+        let instance = GoIdentifier::public("test-instance");
+        let exports = [
+            DynamicExport {
+                name: "add".to_string(),
+                accessor: None,
+                method: GoIdentifier::public("add"),
+                params: vec![GoType::Uint32, GoType::Uint32],
+                result: GoResult::Anon(GoType::Uint32),
+            },
+            DynamicExport {
+                name: "ns:pkg/store#get".to_string(),
+                accessor: Some(GoIdentifier::public("store")),
+                method: GoIdentifier::public("get"),
+                params: vec![GoType::String],
+                result: GoResult::Anon(GoType::ValueOrError(Box::new(GoType::ValueOrOk(
+                    Box::new(GoType::String),
+                )))),
+            },
+            DynamicExport {
+                name: "reset".to_string(),
+                accessor: None,
+                method: GoIdentifier::public("reset"),
+                params: vec![],
+                result: GoResult::Empty,
+            },
+        ];
+        let mut tokens = Tokens::<Go>::new();
+        DynamicExportsGenerator::new(&instance, &exports).format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+        println!("{output}");
+
+        assert!(output.contains("type ExportInfo struct {"));
+        assert!(output.contains(
+            "Params: []reflect.Type{reflect.TypeFor[uint32](), reflect.TypeFor[uint32]()},"
+        ));
+        assert!(output.contains(
+            "Results: []reflect.Type{reflect.TypeFor[string](), reflect.TypeFor[bool]()},"
+        ));
+        assert!(
+            output.contains("Params: []reflect.Type{},\n            Results: []reflect.Type{},")
+        );
+        assert!(output.contains("    case \"add\":\n        if len(args) != 2 {"));
+        assert!(output.contains("        arg1, ok := args[1].(uint32)\n        if !ok {"));
+        assert!(output.contains(
+            "        result := i.Add(ctx, arg0, arg1)\n        return []any{result}, nil"
+        ));
+        assert!(output.contains(
+            "        result, ok, err := i.Store().Get(ctx, arg0)\n        if err != nil {\n            return nil, err\n        }\n        return []any{result, ok}, nil"
+        ));
+        assert!(output.contains("        i.Reset(ctx)\n        return []any{}, nil"));
+        assert!(output.contains(
+            "    default:\n        return nil, fmt.Errorf(\"unknown export: %s\", name)"
+        ));
+    }
+
+    #[test]
+    fn test_dynamic_exports_generator_tinygo_compat() {
+        let instance = GoIdentifier::public("test-instance");
+        let exports = [DynamicExport {
+            name: "add".to_string(),
+            accessor: None,
+            method: GoIdentifier::public("add"),
+            params: vec![GoType::Uint32, GoType::Slice(Box::new(GoType::String))],
+            result: GoResult::Anon(GoType::Uint32),
+        }];
+        let mut tokens = Tokens::<Go>::new();
+        DynamicExportsGenerator::new(&instance, &exports)
+            .with_tinygo_compat(true)
+            .format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(!output.contains("reflect"));
+        assert!(output.contains("    Params []string\n"));
+        assert!(output.contains("Params: []string{\"uint32\", \"[]string\"},"));
+        assert!(output.contains("Results: []string{\"uint32\"},"));
+        assert!(output.contains(
+            "return nil, fmt.Errorf(\"argument %d of %s must be a %s\", 1, name, \"[]string\")"
+        ));
+    }
+}
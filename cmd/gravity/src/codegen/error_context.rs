@@ -0,0 +1,196 @@
+use crate::compat::wit_parser::{Resolve, Type, World, WorldItem};
+use genco::prelude::*;
+
+use crate::{
+    codegen::helpers::{ERROR_CONTEXT, ERROR_CONTEXTS, WRITE_STRING, member},
+    contains_type,
+    go::{
+        GoIdentifier, comment,
+        imports::{CONTEXT_CONTEXT, ERRORS_NEW, SYNC_MUTEX, WAZERO_API_MODULE},
+    },
+};
+
+/// The name of the host module providing the `error-context` intrinsics, and
+/// the functions imported by the world itself.
+pub const INTRINSICS_MODULE: &str = "$root";
+
+/// Returns true if any function imported or exported by the world uses an
+/// `error-context`, directly or nested in another type.
+pub fn uses_error_context(resolve: &Resolve, world: &World) -> bool {
+    world
+        .imports
+        .values()
+        .chain(world.exports.values())
+        .flat_map(|item| match item {
+            WorldItem::Function(func) => vec![func],
+            WorldItem::Interface { id, .. } => resolve.interfaces[*id].functions.values().collect(),
+            WorldItem::Type(_) => vec![],
+        })
+        .flat_map(|func| func.params.iter().map(|(_, typ)| typ).chain(&func.result))
+        .any(|typ| contains_type(resolve, typ, Type::ErrorContext))
+}
+
+/// Generator for the `ErrorContext` type and the table of handles shared with
+/// the guests.
+///
+/// This must only be generated once per file, as it is shared by all
+/// factories in the file.
+pub struct ErrorContextGenerator;
+
+impl FormatInto<Go> for ErrorContextGenerator {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let error_context = &ERROR_CONTEXT.ident();
+        let table = &GoIdentifier::prefixed_private("error-context-table");
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} is a WIT `error-context`, carrying a debug message describing",
+                    String::from(error_context),
+                ),
+                "an error.".into(),
+            ]))
+            type $error_context struct {
+                DebugMessage string
+            }
+            $['\n']
+            func (e *$error_context) Error() string {
+                return e.DebugMessage
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} holds the error contexts shared with guests, indexed by the",
+                    String::from(table),
+                ),
+                "handles used to pass them across the Component Model boundary.".into(),
+            ]))
+            type $table struct {
+                mu      $SYNC_MUTEX
+                next    uint32
+                entries map[uint32]*$error_context
+            }
+            $['\n']
+            var $(ERROR_CONTEXTS.ident()) = &$table{entries: map[uint32]*$error_context{}}
+            $['\n']
+            func (t *$table) insert(e *$error_context) uint32 {
+                t.mu.Lock()
+                defer t.mu.Unlock()
+                $(comment(&["Handles start at 1, so the zero value is never a valid handle"]))
+                t.next++
+                t.entries[t.next] = e
+                return t.next
+            }
+            $['\n']
+            func (t *$table) get(handle uint32) *$error_context {
+                t.mu.Lock()
+                defer t.mu.Unlock()
+                e, ok := t.entries[handle]
+                if !ok {
+                    panic($ERRORS_NEW("invalid error context handle"))
+                }
+                return e
+            }
+            $['\n']
+            func (t *$table) drop(handle uint32) {
+                t.mu.Lock()
+                defer t.mu.Unlock()
+                delete(t.entries, handle)
+            }
+        };
+    }
+}
+
+/// Generates the host functions of the [`INTRINSICS_MODULE`] builder providing
+/// the canonical built-ins used by guests to create, inspect and drop error
+/// contexts.
+pub fn error_context_intrinsics() -> Tokens<Go> {
+    let (insert, get, drop) = (member("insert"), member("get"), member("drop"));
+    quote! {
+        NewFunctionBuilder().
+        WithFunc(func(
+            ctx $CONTEXT_CONTEXT,
+            mod $WAZERO_API_MODULE,
+            ptr uint32,
+            length uint32,
+        ) uint32 {
+            buf, ok := mod.Memory().Read(ptr, length)
+            if !ok {
+                panic($ERRORS_NEW("failed to read bytes from memory"))
+            }
+            return $ERROR_CONTEXTS.$insert(&$ERROR_CONTEXT{DebugMessage: string(buf)})
+        }).
+        Export("[error-context-new-utf8]").
+        NewFunctionBuilder().
+        WithFunc(func(
+            ctx $CONTEXT_CONTEXT,
+            mod $WAZERO_API_MODULE,
+            handle uint32,
+            retptr uint32,
+        ) {
+            e := $ERROR_CONTEXTS.$get(handle)
+            ptr, length, err := $WRITE_STRING(ctx, e.DebugMessage, mod.Memory(), mod.ExportedFunction("cabi_realloc"))
+            if err != nil {
+                panic(err)
+            }
+            mod.Memory().WriteUint32Le(retptr, uint32(ptr))
+            mod.Memory().WriteUint32Le(retptr+4, uint32(length))
+        }).
+        Export("[error-context-debug-message-utf8]").
+        NewFunctionBuilder().
+        WithFunc(func(
+            ctx $CONTEXT_CONTEXT,
+            handle uint32,
+        ) {
+            $ERROR_CONTEXTS.$drop(handle)
+        }).
+        Export("[error-context-drop]").
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::wit_parser::Resolve;
+
+    use crate::codegen::error_context::uses_error_context;
+
+    #[test]
+    fn test_uses_error_context() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface errors {
+                    record failure {
+                        context: error-context,
+                    }
+
+                    fail: func() -> result<_, failure>;
+                }
+
+                world with-context {
+                    import errors;
+                }
+
+                world without-context {
+                    export hello: func(name: string) -> string;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+
+        let world = |name: &str| {
+            resolve
+                .worlds
+                .iter()
+                .find(|(_, world)| world.name == name)
+                .map(|(_, world)| world)
+                .expect("missing world")
+        };
+        assert!(uses_error_context(&resolve, world("with-context")));
+        assert!(!uses_error_context(&resolve, world("without-context")));
+    }
+}
@@ -1,22 +1,138 @@
+use std::collections::BTreeSet;
+
+use crate::compat::wit_parser::{
+    Function, LiftLowerAbi, ManglingAndAbi, Resolve, SizeAlign, Type, TypeDefKind, TypeId,
+    WasmExport, WasmExportKind, World, WorldItem, WorldKey,
+};
 use genco::prelude::*;
-use wit_bindgen_core::wit_parser::{Function, Resolve, SizeAlign, World, WorldItem};
 
-use crate::go::{GoIdentifier, GoResult, GoType, imports::CONTEXT_CONTEXT};
+use crate::{
+    codegen::{
+        ErrorMode,
+        backend::{RuntimeBackend, Wazero},
+        docs::deprecation,
+        dynamic::{DynamicExport, DynamicExportsGenerator},
+        helpers::BATCH_IMPORTS,
+        instrument::{CallSite, Instrumentation},
+        parallel::par_map,
+        resources::exported_resources,
+        stats::{FunctionStats, InterfaceStats},
+        strings::StringEncodings,
+        support::Support,
+        symbols::{ExportInterface, SymbolTable, export_methods},
+        timeouts::timeout_wrapper,
+    },
+    go::{GoIdentifier, GoResult, GoType, comment, imports::CONTEXT_CONTEXT},
+};
+
+/// The ways items are exported by a world, and whether their bindings can be
+/// generated, as listed by `gravity features`.
+pub(crate) const DIRECTIONS: &[(&str, Support)] = &[
+    ("exported interface", Support::Supported),
+    ("exported function", Support::Supported),
+    ("exported type", Support::Supported),
+    ("exported resource", Support::Supported),
+    ("async function", Support::Unsupported),
+];
 
 pub struct ExportConfig<'a> {
     pub instance: &'a GoIdentifier,
     pub world: &'a World,
     pub resolve: &'a Resolve,
     pub sizes: &'a SizeAlign,
+    /// The instrumentation wrapped around every exported function.
+    pub instrumentation: &'a Instrumentation,
+    /// How errors encountered by the exported functions are surfaced.
+    pub error_mode: ErrorMode,
+    /// How tuples returned by the exported functions are mapped to Go.
+    pub tuple_results: TupleResults,
+    /// Generate the `Exports` and `CallByName` methods, listing and calling
+    /// the exported functions by name.
+    pub dynamic: bool,
+    /// Generate a variant of each exported function taking a timeout.
+    pub call_timeouts: bool,
+    /// Avoid constructs TinyGo doesn't support, such as reflection.
+    pub tinygo_compat: bool,
+    /// Check that the core Wasm results fit the types they're lifted into,
+    /// rather than truncating them.
+    pub checked_casts: bool,
+    /// The names of the functions exported by the core Wasm module, if known,
+    /// which tell the `cabi_post_*` functions to call.
+    pub module_exports: Option<&'a BTreeSet<String>>,
+    /// Batch the calls of the imported functions without results made
+    /// during a call of an export, making them once it returns.
+    pub batch_imports: bool,
+    /// The encodings of the strings exchanged with the exported functions.
+    pub string_encodings: &'a StringEncodings,
+}
+
+/// How an exported function returning a tuple returns it in Go.
+///
+/// Only the tuple returned by the function is affected, while tuples nested
+/// in its result are always lifted into the struct of the named tuple.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TupleResults {
+    /// Return named tuples as their struct, e.g. `Version`, and anonymous
+    /// tuples, which have no struct, as multiple values.
+    #[default]
+    Struct,
+    /// Return every tuple as multiple values, e.g. `(uint32, uint32)`.
+    Multi,
+}
+
+/// Returns the tuple returned by the function, if it's returned as multiple
+/// Go values, along with their types.
+fn multi_return(
+    resolve: &Resolve,
+    func: &Function,
+    mode: TupleResults,
+) -> Option<(TypeId, Vec<GoType>)> {
+    let Some(Type::Id(id)) = func.result else {
+        return None;
+    };
+    let typ = &resolve.types[id];
+    match &typ.kind {
+        TypeDefKind::Tuple(tuple) if typ.name.is_none() || mode == TupleResults::Multi => Some((
+            id,
+            tuple
+                .types
+                .iter()
+                .map(|typ| crate::resolve_type(typ, resolve))
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
+/// Returns the Go type returned by the exported function, if any.
+fn result_type(resolve: &Resolve, func: &Function, mode: TupleResults) -> Option<GoType> {
+    match multi_return(resolve, func, mode) {
+        Some((_, typs)) => Some(GoType::MultiReturn(typs)),
+        None => func
+            .result
+            .as_ref()
+            .map(|typ| crate::resolve_type(typ, resolve)),
+    }
 }
 
 pub struct ExportGenerator<'a> {
     config: ExportConfig<'a>,
+    backend: &'a dyn RuntimeBackend,
 }
 
 impl<'a> ExportGenerator<'a> {
     pub fn new(config: ExportConfig<'a>) -> Self {
-        Self { config }
+        Self {
+            config,
+            backend: &Wazero,
+        }
+    }
+
+    /// Sets the runtime the exported functions call the guest with, which is
+    /// Wazero by default.
+    pub fn with_backend(mut self, backend: &'a dyn RuntimeBackend) -> Self {
+        self.backend = backend;
+        self
     }
 
     /// Generate the Go function code for the given function.
@@ -28,10 +144,26 @@ impl<'a> ExportGenerator<'a> {
     ///
     /// To implement the body, we:
     /// - creating a `Func` struct which implements `Bindgen` and passing it to the
-    ///   `wit_bindgen_core::abi::call` function. This will call `Func::emit` lots of
+    ///   `crate::compat::abi::call` function. This will call `Func::emit` lots of
     ///   times, one for each instruction in the function, and `Func::emit` will generate
     ///   Go code for each instruction
-    fn generate_function(&self, func: &Function, tokens: &mut Tokens<Go>) {
+    ///
+    /// Functions exported from an interface pass the `interface` key, which is
+    /// used to find the name of the core Wasm export, and the accessor of the
+    /// instance returning the `receiver` type. The Go method is named after
+    /// `name`, which only differs from the function's name to avoid a
+    /// collision.
+    ///
+    /// Returns the function as called by name, for `CallByName`.
+    fn generate_function(
+        &self,
+        interface: Option<(&WorldKey, &GoIdentifier)>,
+        receiver: &GoIdentifier,
+        func: &Function,
+        name: &str,
+        tokens: &mut Tokens<Go>,
+    ) -> DynamicExport {
+        let (interface, accessor) = interface.unzip();
         let params = func
             .params
             .iter()
@@ -43,17 +175,42 @@ impl<'a> ExportGenerator<'a> {
             )
             .collect::<Vec<_>>();
 
-        let result = if let Some(wit_type) = &func.result {
-            GoResult::Anon(crate::resolve_type(wit_type, self.config.resolve))
-        } else {
-            GoResult::Empty
+        let tuple_results = self.config.tuple_results;
+        let result = match result_type(self.config.resolve, func, tuple_results) {
+            Some(typ) => GoResult::Anon(typ),
+            None => GoResult::Empty,
         };
 
-        let mut f = crate::Func::export(result, self.config.sizes);
-        wit_bindgen_core::abi::call(
+        let export_name = export_name(self.config.resolve, interface, func);
+        let post_return = post_return(
             self.config.resolve,
-            wit_bindgen_core::abi::AbiVariant::GuestExport,
-            wit_bindgen_core::abi::LiftLower::LowerArgsLiftResults,
+            interface,
+            func,
+            tuple_results,
+            self.config.module_exports,
+        );
+
+        let guest_resources = exported_resources(self.config.resolve, self.config.world)
+            .into_iter()
+            .map(|resource| resource.id)
+            .collect();
+        let param_types = params.iter().map(|(_, typ)| typ.clone()).collect();
+        let mut f = crate::Func::export(&export_name, param_types, result, self.config.sizes)
+            .with_backend(self.backend)
+            .with_error_mode(self.config.error_mode)
+            .with_checked_casts(self.config.checked_casts)
+            .with_guest_resources(guest_resources)
+            .with_string_encoding(self.config.string_encodings.export(&export_name));
+        if let Some((id, _)) = multi_return(self.config.resolve, func, tuple_results) {
+            f = f.with_multi_return(id);
+        }
+        if let Some(post_return) = &post_return {
+            f = f.with_post_return(post_return);
+        }
+        crate::compat::abi::call(
+            self.config.resolve,
+            crate::compat::abi::AbiVariant::GuestExport,
+            crate::compat::abi::LiftLower::LowerArgsLiftResults,
             func,
             &mut f,
             // async is not currently supported
@@ -66,43 +223,299 @@ impl<'a> ExportGenerator<'a> {
             .zip(&params)
             .map(|(arg, (param, _))| (arg, param))
             .collect::<Vec<_>>();
-        let fn_name = &GoIdentifier::public(&func.name);
+        let instrumentation = self.config.instrumentation;
+        let fn_name = &GoIdentifier::public(name);
+        let deprecation = deprecation(func);
+        if instrumentation.is_enabled() {
+            // The instrumented function wraps the private implementation
+            let inner_name = &GoIdentifier::private(name);
+            let interface = interface.map(|key| self.config.resolve.name_world_key(key));
+            let site = CallSite {
+                world: &self.config.world.name,
+                interface: interface.as_deref(),
+                function: &func.name,
+                params: params.iter().map(|(name, _)| name).collect(),
+                result: f.result(),
+                host: false,
+                hooks: Some(quote!(i.hooks)),
+            };
+            let call = quote! {
+                i.$inner_name(ctx$(for (name, _) in &params => , $name))
+            };
+            quote_in! { *tokens =>
+                $['\n']
+                $(if let Some(lines) = &deprecation => $(comment(lines)))
+                func (i *$receiver) $fn_name(
+                    $['\r']
+                    ctx $CONTEXT_CONTEXT,
+                    $(for (name, typ) in &params join ($['\r']) => $name $typ,)
+                ) $(f.result()) {
+                    $(instrumentation.wrap_call(&site, call))
+                }
+            };
+        }
+        let fn_name = if instrumentation.is_enabled() {
+            &GoIdentifier::private(name)
+        } else {
+            fn_name
+        };
+        // The deprecation is documented on the exported method
+        let deprecation = deprecation.filter(|_| !instrumentation.is_enabled());
         quote_in! { *tokens =>
             $['\n']
-            func (i *$(self.config.instance)) $fn_name(
+            $(if let Some(lines) = &deprecation => $(comment(lines)))
+            func (i *$receiver) $fn_name(
                 $['\r']
                 ctx $CONTEXT_CONTEXT,
                 $(for (name, typ) in &params join ($['\r']) => $name $typ,)
-            ) $(f.result()) {
+            ) $(f.signature()) {
+                $(if self.config.batch_imports {
+                    ctx, flushImports := $BATCH_IMPORTS(ctx)
+                    defer flushImports()
+                })
                 $(for (arg, param) in arg_assignments join ($['\r']) => $arg := $param)
                 $(f.body())
             }
         }
+        if self.config.call_timeouts {
+            tokens.append(timeout_wrapper(
+                self.config.instance,
+                receiver,
+                &GoIdentifier::public(name),
+                &params,
+                f.result(),
+            ));
+        }
+
+        let interface = interface.map(|key| self.config.resolve.name_world_key(key));
+        DynamicExport {
+            name: match interface {
+                Some(interface) => format!("{interface}#{}", func.name),
+                None => func.name.clone(),
+            },
+            accessor: accessor.cloned(),
+            method: GoIdentifier::public(name),
+            params: params.into_iter().map(|(_, typ)| typ).collect(),
+            result: f.result().clone(),
+        }
+    }
+
+    /// Generate the type of the exported interface, its accessor on the
+    /// instance and its functions.
+    ///
+    /// Returns the tokens, along with the functions as called by name.
+    fn generate_interface(&self, interface: &ExportInterface) -> (Tokens<Go>, Vec<DynamicExport>) {
+        let mut tokens = Tokens::new();
+        let instance = self.config.instance;
+        let go_type = &interface.go_type;
+        let accessor = &GoIdentifier::public(&interface.name);
+        let wit_name = self.config.resolve.name_world_key(interface.key);
+        quote_in! { tokens =>
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} implements the functions of the exported `{wit_name}`",
+                    String::from(go_type),
+                ),
+                format!(
+                    "interface, and is returned by the {} method of {}.",
+                    String::from(accessor),
+                    String::from(instance),
+                ),
+            ]))
+            type $go_type $instance
+            $['\n']
+            $(comment(&[format!(
+                "{} returns the functions exported by the `{wit_name}` interface.",
+                String::from(accessor),
+            )]))
+            func (i *$instance) $accessor() *$go_type {
+                return (*$go_type)(i)
+            }
+        };
+        let exports = interface
+            .methods
+            .iter()
+            .map(|method| {
+                self.generate_function(
+                    Some((interface.key, accessor)),
+                    go_type,
+                    method.func,
+                    &method.name,
+                    &mut tokens,
+                )
+            })
+            .collect();
+        (tokens, exports)
+    }
+
+    /// Measures the code generated for each exported function, grouped by
+    /// their interface, with the functions of the world itself first.
+    pub(crate) fn stats(&self) -> Vec<InterfaceStats> {
+        let instance = self.config.instance;
+        let methods = export_methods(
+            self.config.resolve,
+            self.config.world,
+            &mut SymbolTable::default(),
+        );
+        let functions = InterfaceStats {
+            name: self.config.world.name.clone(),
+            functions: methods
+                .functions
+                .iter()
+                .map(|method| {
+                    FunctionStats::measure(&method.func.name, |tokens| {
+                        self.generate_function(None, instance, method.func, &method.name, tokens);
+                    })
+                })
+                .collect(),
+        };
+        let interfaces = methods.interfaces.iter().map(|interface| {
+            let accessor = &GoIdentifier::public(&interface.name);
+            InterfaceStats {
+                name: self.config.resolve.name_world_key(interface.key),
+                functions: interface
+                    .methods
+                    .iter()
+                    .map(|method| {
+                        FunctionStats::measure(&method.func.name, |tokens| {
+                            self.generate_function(
+                                Some((interface.key, accessor)),
+                                &interface.go_type,
+                                method.func,
+                                &method.name,
+                                tokens,
+                            );
+                        })
+                    })
+                    .collect(),
+            }
+        });
+        std::iter::once(functions)
+            .chain(interfaces)
+            .filter(|interface| !interface.functions.is_empty())
+            .collect()
+    }
+}
+
+/// Returns the name of the core Wasm export implementing a function.
+pub(crate) fn export_name(
+    resolve: &Resolve,
+    interface: Option<&WorldKey>,
+    func: &Function,
+) -> String {
+    resolve.wasm_export_name(
+        ManglingAndAbi::Legacy(LiftLowerAbi::Sync),
+        WasmExport::Func {
+            interface,
+            func,
+            kind: WasmExportKind::Normal,
+        },
+    )
+}
+
+/// Returns the name of the `cabi_post_*` function cleaning up the results of
+/// an export, if it's called.
+///
+/// Guests only export these for the functions whose results need cleaning
+/// up, so the function is called if the module exports it. When the exports
+/// of the module aren't known, it's assumed to be exported if the result of
+/// the function may need cleaning up.
+fn post_return(
+    resolve: &Resolve,
+    interface: Option<&WorldKey>,
+    func: &Function,
+    tuple_results: TupleResults,
+    module_exports: Option<&BTreeSet<String>>,
+) -> Option<String> {
+    let name = format!("cabi_post_{}", export_name(resolve, interface, func));
+    let exported = match module_exports {
+        Some(exports) => exports.contains(&name),
+        None => result_type(resolve, func, tuple_results).is_some_and(|typ| typ.needs_cleanup()),
+    };
+    exported.then_some(name)
+}
+
+/// Returns the names of the core Wasm functions called by the exports of the
+/// world, including the `cabi_post_*` functions cleaning up their results.
+///
+/// The exports of the module, when known, tell which of these are exported.
+pub fn exported_functions(
+    resolve: &Resolve,
+    world: &World,
+    tuple_results: TupleResults,
+    module_exports: Option<&BTreeSet<String>>,
+) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut push = |interface: Option<&WorldKey>, func: &Function| {
+        names.push(export_name(resolve, interface, func));
+        names.extend(post_return(
+            resolve,
+            interface,
+            func,
+            tuple_results,
+            module_exports,
+        ));
+    };
+    for (key, item) in world.exports.iter() {
+        match item {
+            WorldItem::Function(func) => push(None, func),
+            WorldItem::Interface { id, .. } => {
+                for func in resolve.interfaces[*id].functions.values() {
+                    push(Some(key), func)
+                }
+            }
+            WorldItem::Type(_) => (),
+        }
     }
+    names
 }
 
 impl FormatInto<Go> for ExportGenerator<'_> {
     fn format_into(self, tokens: &mut Tokens<Go>) {
-        for item in self.config.world.exports.values() {
-            match item {
-                WorldItem::Function(func) => self.generate_function(func, tokens),
-                WorldItem::Interface { .. } => todo!("generate interface exports"),
-                WorldItem::Type(_) => todo!("generate type exports"),
-            }
+        let instance = self.config.instance;
+        let methods = export_methods(
+            self.config.resolve,
+            self.config.world,
+            &mut SymbolTable::default(),
+        );
+        let mut exports = methods
+            .functions
+            .iter()
+            .map(|method| self.generate_function(None, instance, method.func, &method.name, tokens))
+            .collect::<Vec<_>>();
+        // The interfaces are independent, so they're generated in parallel,
+        // and appended in the order of the world
+        let interfaces = par_map(&methods.interfaces, |_, interface| {
+            self.generate_interface(interface)
+        });
+        for (interface, interface_exports) in interfaces {
+            tokens.append(interface);
+            exports.extend(interface_exports);
+        }
+        if self.config.dynamic {
+            DynamicExportsGenerator::new(instance, &exports)
+                .with_tinygo_compat(self.config.tinygo_compat)
+                .format_into(tokens);
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use genco::prelude::*;
-    use wit_bindgen_core::wit_parser::{
+    use std::collections::BTreeSet;
+
+    use crate::compat::wit_parser::{
         Function, FunctionKind, Resolve, SizeAlign, Type, World, WorldItem, WorldKey,
     };
+    use genco::prelude::*;
 
-    use crate::go::GoIdentifier;
+    use crate::{
+        codegen::{ErrorMode, conversions::with_conversions},
+        go::GoIdentifier,
+    };
 
-    use super::{ExportConfig, ExportGenerator};
+    use super::{ExportConfig, ExportGenerator, TupleResults, exported_functions};
 
     #[test]
     fn test_generate_function_simple_u32_param() {
@@ -140,13 +553,23 @@ mod tests {
             world: &world,
             resolve: &resolve,
             sizes: &sizes,
+            instrumentation: &Default::default(),
+            error_mode: Default::default(),
+            tuple_results: Default::default(),
+            dynamic: false,
+            call_timeouts: false,
+            tinygo_compat: false,
+            checked_casts: false,
+            module_exports: None,
+            batch_imports: false,
+            string_encodings: &Default::default(),
         };
 
         let generator = ExportGenerator::new(config);
         let mut tokens = Tokens::new();
 
         // Call the actual generate_function method
-        generator.generate_function(&func, &mut tokens);
+        generator.generate_function(None, &instance, &func, &func.name, &mut tokens);
 
         let generated = tokens.to_string().unwrap();
         println!("Generated: {}", generated);
@@ -159,14 +582,671 @@ mod tests {
 
         // Verify function body
         assert!(generated.contains("arg0 := value"));
-        assert!(
-            generated
-                .contains("i.module.ExportedFunction(\"add_number\").Call(ctx, uint64(result0))")
-        );
+        assert!(generated.contains("raw1, err1 := i.fnAddNumber.Call(ctx, result0)"));
         assert!(generated.contains("if err1 != nil {"));
-        assert!(generated.contains("panic(err1)"));
+        assert!(generated.contains("panic(guestTrap(\"add_number\", err1))"));
         assert!(generated.contains("results1 := raw1[0]"));
-        assert!(generated.contains("result2 := api.DecodeU32(results1)"));
+        assert!(generated.contains("result2 := uint32(results1)"));
         assert!(generated.contains("return result2"));
     }
+
+    #[test]
+    fn test_generate_function_wrapped_errors() {
+        let func = Function {
+            name: "reset".to_string(),
+            kind: FunctionKind::Freestanding,
+            params: vec![],
+            result: None,
+            docs: Default::default(),
+            stability: Default::default(),
+        };
+
+        let world = World {
+            name: "test-world".to_string(),
+            imports: [].into(),
+            exports: [(
+                WorldKey::Name("reset".to_string()),
+                WorldItem::Function(func.clone()),
+            )]
+            .into(),
+            docs: Default::default(),
+            stability: Default::default(),
+            includes: Default::default(),
+            include_names: Default::default(),
+            package: None,
+        };
+
+        let resolve = Resolve::new();
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+        let instance = GoIdentifier::public("TestInstance");
+
+        let config = ExportConfig {
+            instance: &instance,
+            world: &world,
+            resolve: &resolve,
+            sizes: &sizes,
+            instrumentation: &Default::default(),
+            error_mode: ErrorMode::Wrap,
+            tuple_results: Default::default(),
+            dynamic: false,
+            call_timeouts: false,
+            tinygo_compat: false,
+            checked_casts: false,
+            module_exports: None,
+            batch_imports: false,
+            string_encodings: &Default::default(),
+        };
+
+        let generator = ExportGenerator::new(config);
+        let mut tokens = Tokens::new();
+        generator.generate_function(None, &instance, &func, &func.name, &mut tokens);
+
+        let generated = tokens.to_string().unwrap();
+        println!("Generated: {}", generated);
+
+        assert!(generated.contains(") error {"));
+        assert!(generated.contains("return guestTrap(\"reset\", err0)"));
+        assert!(generated.contains("return nil"));
+        assert!(!generated.contains("panic"));
+    }
+
+    #[test]
+    fn test_generate_function_keyword_param() {
+        let func = Function {
+            name: "set-type".to_string(),
+            kind: FunctionKind::Freestanding,
+            params: vec![("type".to_string(), Type::U32)],
+            result: None,
+            docs: Default::default(),
+            stability: Default::default(),
+        };
+
+        let world = World {
+            name: "test-world".to_string(),
+            imports: [].into(),
+            exports: [(
+                WorldKey::Name("set-type".to_string()),
+                WorldItem::Function(func.clone()),
+            )]
+            .into(),
+            docs: Default::default(),
+            stability: Default::default(),
+            includes: Default::default(),
+            include_names: Default::default(),
+            package: None,
+        };
+
+        let resolve = Resolve::new();
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+        let instance = GoIdentifier::public("TestInstance");
+
+        let config = ExportConfig {
+            instance: &instance,
+            world: &world,
+            resolve: &resolve,
+            sizes: &sizes,
+            instrumentation: &Default::default(),
+            error_mode: Default::default(),
+            tuple_results: Default::default(),
+            dynamic: false,
+            call_timeouts: false,
+            tinygo_compat: false,
+            checked_casts: false,
+            module_exports: None,
+            batch_imports: false,
+            string_encodings: &Default::default(),
+        };
+
+        let mut tokens = Tokens::new();
+        ExportGenerator::new(config).generate_function(
+            None,
+            &instance,
+            &func,
+            &func.name,
+            &mut tokens,
+        );
+        let generated = tokens.to_string().unwrap();
+
+        assert!(generated.contains("type_ uint32,"));
+        assert!(generated.contains("arg0 := type_"));
+    }
+
+    #[test]
+    fn test_generate_function_variant() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                world variants {
+                    variant message { text(string), code(u32), none }
+
+                    export echo: func(message: message) -> message;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let WorldItem::Function(func) = &world.exports[&WorldKey::Name("echo".to_string())] else {
+            panic!("expected a function");
+        };
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+        let instance = GoIdentifier::public("TestInstance");
+
+        let config = ExportConfig {
+            instance: &instance,
+            world,
+            resolve: &resolve,
+            sizes: &sizes,
+            instrumentation: &Default::default(),
+            error_mode: Default::default(),
+            tuple_results: Default::default(),
+            dynamic: false,
+            call_timeouts: false,
+            tinygo_compat: false,
+            checked_casts: false,
+            module_exports: None,
+            batch_imports: false,
+            string_encodings: &Default::default(),
+        };
+
+        let tokens = with_conversions(&resolve, &sizes, |tokens| {
+            ExportGenerator::new(config)
+                .generate_function(None, &instance, func, &func.name, tokens);
+        });
+        let generated = tokens.to_string().unwrap();
+        println!("{generated}");
+
+        assert!(generated.contains("message Message,"));
+        assert!(generated.contains("case MessageText:"));
+        assert!(generated.contains("switch variantCase := arg0.(type) {"));
+        assert!(generated.contains("variantPayload := variantCase.Value"));
+        assert!(generated.contains("writeString(ctx, variantPayload,"));
+        assert!(generated.contains("cast2 := result1"));
+        assert!(generated.contains("= NewMessageCode("));
+        assert!(generated.contains("= NewMessageNone()"));
+    }
+
+    #[test]
+    fn test_generate_function_tuple() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                world tuples {
+                    type pair = tuple<u32, u32>;
+
+                    export swap: func(pair: pair) -> pair;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let WorldItem::Function(func) = &world.exports[&WorldKey::Name("swap".to_string())] else {
+            panic!("expected a function");
+        };
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+        let instance = GoIdentifier::public("TestInstance");
+
+        let config = ExportConfig {
+            instance: &instance,
+            world,
+            resolve: &resolve,
+            sizes: &sizes,
+            instrumentation: &Default::default(),
+            error_mode: Default::default(),
+            tuple_results: Default::default(),
+            dynamic: false,
+            call_timeouts: false,
+            tinygo_compat: false,
+            checked_casts: false,
+            module_exports: None,
+            batch_imports: false,
+            string_encodings: &Default::default(),
+        };
+
+        let mut tokens = Tokens::new();
+        ExportGenerator::new(config).generate_function(
+            None,
+            &instance,
+            func,
+            &func.name,
+            &mut tokens,
+        );
+        let generated = tokens.to_string().unwrap();
+        println!("{generated}");
+
+        assert!(generated.contains("pair Pair,"));
+        assert!(generated.contains(") Pair {"));
+        assert!(generated.contains("f0_0 := arg0.F0"));
+        assert!(generated.contains("f1_0 := arg0.F1"));
+        assert!(generated.contains(":= Pair{"));
+    }
+
+    #[test]
+    fn test_generate_function_float_params() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                world floats {
+                    variant shape { circle(f32), square(u32) }
+
+                    export draw: func(scale: f64, shape: shape);
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let WorldItem::Function(func) = &world.exports[&WorldKey::Name("draw".to_string())] else {
+            panic!("expected a function");
+        };
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+        let instance = GoIdentifier::public("TestInstance");
+
+        let config = ExportConfig {
+            instance: &instance,
+            world,
+            resolve: &resolve,
+            sizes: &sizes,
+            instrumentation: &Default::default(),
+            error_mode: Default::default(),
+            tuple_results: Default::default(),
+            dynamic: false,
+            call_timeouts: false,
+            tinygo_compat: false,
+            checked_casts: false,
+            module_exports: None,
+            batch_imports: false,
+            string_encodings: &Default::default(),
+        };
+
+        let mut tokens = Tokens::new();
+        ExportGenerator::new(config).generate_function(
+            None,
+            &instance,
+            func,
+            &func.name,
+            &mut tokens,
+        );
+        let generated = tokens.to_string().unwrap();
+        println!("{generated}");
+
+        // The f32 payload shares an i32 with the u32 payload, so it's encoded
+        // in the variant, while the f64 is encoded when calling the guest
+        assert!(generated.contains(":= uint32(api.EncodeF32(variantPayload))"));
+        assert!(generated.contains("i.fnDraw.Call(ctx, api.EncodeF64(arg0), uint64(variant"));
+    }
+
+    #[test]
+    fn test_generate_function_tuple_results() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                world tuples {
+                    type version = tuple<u32, u32, u32>;
+
+                    export get-version: func() -> version;
+                    export get-pair: func() -> tuple<u32, string>;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+        let instance = GoIdentifier::public("TestInstance");
+        let generate = |name: &str, error_mode, tuple_results| {
+            let WorldItem::Function(func) = &world.exports[&WorldKey::Name(name.to_string())]
+            else {
+                panic!("expected a function");
+            };
+            let config = ExportConfig {
+                instance: &instance,
+                world,
+                resolve: &resolve,
+                sizes: &sizes,
+                instrumentation: &Default::default(),
+                error_mode,
+                tuple_results,
+                dynamic: false,
+                call_timeouts: false,
+                tinygo_compat: false,
+                checked_casts: false,
+                module_exports: None,
+                batch_imports: false,
+                string_encodings: &Default::default(),
+            };
+            let mut tokens = Tokens::new();
+            ExportGenerator::new(config).generate_function(
+                None,
+                &instance,
+                func,
+                &func.name,
+                &mut tokens,
+            );
+            let generated = tokens.to_string().unwrap();
+            println!("{generated}");
+            generated
+        };
+
+        // Named tuples are returned as their struct by default
+        let generated = generate("get-version", ErrorMode::Panic, TupleResults::Struct);
+        assert!(generated.contains(") Version {"));
+        assert!(generated.contains(":= Version{"));
+
+        let generated = generate("get-version", ErrorMode::Panic, TupleResults::Multi);
+        assert!(generated.contains(") (uint32, uint32, uint32) {"));
+        assert!(generated.contains("return result2, result4, result6"));
+        assert!(!generated.contains("Version{"));
+        assert!(!generated.contains("fnCabiPostGetVersion"));
+
+        let generated = generate("get-version", ErrorMode::Wrap, TupleResults::Multi);
+        assert!(generated.contains(") (uint32, uint32, uint32, error) {"));
+        assert!(generated.contains("return result2, result4, result6, nil"));
+
+        // Anonymous tuples have no struct, so they're always multiple values,
+        // and their strings are cleaned up after the call
+        let generated = generate("get-pair", ErrorMode::Panic, TupleResults::Struct);
+        assert!(generated.contains(") (uint32, string) {"));
+        assert!(generated.contains("i.fnCabiPostGetPair.Call("));
+    }
+
+    #[test]
+    fn test_generate_function_signed_integers() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                world signed {
+                    record pair { a: s8, b: s64 }
+
+                    export swap: func(val: s64) -> pair;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let WorldItem::Function(func) = &world.exports[&WorldKey::Name("swap".to_string())] else {
+            panic!("expected a function");
+        };
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+        let instance = GoIdentifier::public("TestInstance");
+
+        let config = ExportConfig {
+            instance: &instance,
+            world,
+            resolve: &resolve,
+            sizes: &sizes,
+            instrumentation: &Default::default(),
+            error_mode: Default::default(),
+            tuple_results: Default::default(),
+            dynamic: false,
+            call_timeouts: false,
+            tinygo_compat: false,
+            checked_casts: false,
+            module_exports: None,
+            batch_imports: false,
+            string_encodings: &Default::default(),
+        };
+
+        let tokens = with_conversions(&resolve, &sizes, |tokens| {
+            ExportGenerator::new(config)
+                .generate_function(None, &instance, func, &func.name, tokens);
+        });
+        let generated = tokens.to_string().unwrap();
+        println!("{generated}");
+
+        assert!(generated.contains("value0 := api.EncodeI64(arg0)"));
+        // The pair is lifted by its helper, where the s8 is sign-extended when
+        // loaded, and both fields are converted back to their Go types
+        assert!(generated.contains(":= liftPair(i.memory, uint32(results1))"));
+        assert!(generated.contains(":= uint32(int32(int8(raw"));
+        assert!(generated.contains(":= int8(value"));
+        assert!(generated.contains(", ok2 := memory.ReadUint64Le("));
+        assert!(generated.contains(":= int64(value2)"));
+    }
+
+    #[test]
+    fn test_generate_function_checked_casts() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                world checked {
+                    export level: func() -> s16;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let WorldItem::Function(func) = &world.exports[&WorldKey::Name("level".to_string())] else {
+            panic!("expected a function");
+        };
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+        let instance = GoIdentifier::public("TestInstance");
+
+        let config = ExportConfig {
+            instance: &instance,
+            world,
+            resolve: &resolve,
+            sizes: &sizes,
+            instrumentation: &Default::default(),
+            error_mode: ErrorMode::Wrap,
+            tuple_results: Default::default(),
+            dynamic: false,
+            call_timeouts: false,
+            tinygo_compat: false,
+            checked_casts: true,
+            module_exports: None,
+            batch_imports: false,
+            string_encodings: &Default::default(),
+        };
+
+        let mut tokens = Tokens::new();
+        ExportGenerator::new(config).generate_function(
+            None,
+            &instance,
+            func,
+            &func.name,
+            &mut tokens,
+        );
+        let generated = tokens.to_string().unwrap();
+        println!("{generated}");
+
+        // The i32 result is checked to fit, then the s16 lifted from it
+        assert!(generated.contains("if raw0[0] > math.MaxUint32 {"));
+        assert!(generated.contains(
+            "return default0, fmt.Errorf(\"result 0 out of range for i32: %d\", raw0[0])"
+        ));
+        assert!(generated.contains(
+            "if results0 > math.MaxUint32 || int32(results0) < math.MinInt16 || int32(results0) > math.MaxInt16 {"
+        ));
+        assert!(generated.contains("fmt.Errorf(\"value out of range for int16: %d\", results0)"));
+        assert!(generated.contains("result1 := int16(results0)"));
+    }
+
+    #[test]
+    fn test_exported_functions() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface greeter {
+                    greet: func(name: string) -> string;
+                }
+
+                world exports {
+                    export count: func() -> u32;
+                    export greeter;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+
+        assert_eq!(
+            exported_functions(&resolve, world, Default::default(), None),
+            [
+                "count",
+                "test:pkg/greeter#greet",
+                "cabi_post_test:pkg/greeter#greet"
+            ]
+        );
+        // Only the `cabi_post_*` functions exported by the module are called
+        let module_exports = BTreeSet::from([
+            "count".to_string(),
+            "cabi_post_count".to_string(),
+            "test:pkg/greeter#greet".to_string(),
+        ]);
+        assert_eq!(
+            exported_functions(&resolve, world, Default::default(), Some(&module_exports)),
+            ["count", "cabi_post_count", "test:pkg/greeter#greet"]
+        );
+        assert_eq!(
+            String::from(crate::codegen::func::export_field(
+                "cabi_post_test:pkg/greeter#greet"
+            )),
+            "fnCabiPostTestPkgGreeterGreet"
+        );
+    }
+
+    #[test]
+    fn test_generate_exported_interfaces() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface types-a {
+                    get: func() -> u32;
+                }
+
+                interface collections {
+                    get: func() -> u32;
+                }
+
+                world exports {
+                    export get: func() -> u32;
+                    export types-a;
+                    export collections;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+        let instance = GoIdentifier::public("exports-instance");
+
+        let config = ExportConfig {
+            instance: &instance,
+            world,
+            resolve: &resolve,
+            sizes: &sizes,
+            instrumentation: &Default::default(),
+            error_mode: Default::default(),
+            tuple_results: Default::default(),
+            dynamic: true,
+            call_timeouts: false,
+            tinygo_compat: false,
+            checked_casts: false,
+            module_exports: None,
+            batch_imports: false,
+            string_encodings: &Default::default(),
+        };
+
+        let mut tokens = Tokens::new();
+        ExportGenerator::new(config).format_into(&mut tokens);
+        let generated = tokens.to_string().unwrap();
+        println!("{generated}");
+
+        // The functions of each interface are namespaced by its own type, so
+        // none of them is renamed
+        assert!(generated.contains("func (i *ExportsInstance) Get(\n"));
+        assert!(generated.contains("type ExportsTypesA ExportsInstance\n"));
+        assert!(generated.contains(
+            "func (i *ExportsInstance) TypesA() *ExportsTypesA {\n    return (*ExportsTypesA)(i)\n}"
+        ));
+        assert!(generated.contains("func (i *ExportsTypesA) Get(\n"));
+        assert!(generated.contains("i.fnTestPkgTypesAGet.Call(ctx, )"));
+        assert!(
+            generated.contains("func (i *ExportsInstance) Collections() *ExportsCollections {")
+        );
+        assert!(generated.contains("func (i *ExportsCollections) Get(\n"));
+        assert!(generated.contains("result := i.Collections().Get(ctx)"));
+    }
+
+    #[test]
+    fn test_call_wasm_multiple_results() {
+        use crate::{
+            compat::abi::{Bindgen, Instruction, WasmSignature, WasmType},
+            go::{GoResult, GoType, Operand},
+        };
+
+        let resolve = Resolve::new();
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+        let sig = WasmSignature {
+            params: vec![],
+            results: vec![WasmType::I32, WasmType::F64],
+            indirect_params: false,
+            retptr: false,
+        };
+        let mut func = crate::Func::export("pair", vec![], GoResult::Anon(GoType::Uint32), &sizes);
+        let mut results = Vec::new();
+        func.emit(
+            &resolve,
+            &Instruction::CallWasm {
+                name: "pair",
+                sig: &sig,
+            },
+            &mut Vec::new(),
+            &mut results,
+        );
+        let generated = func.body().to_string().unwrap();
+        println!("Generated: {}", generated);
+
+        // Guests using multi-value returns have a result per core value
+        assert!(generated.contains("raw0, err0 := i.fnPair.Call(ctx"));
+        assert!(generated.contains("results0_0 := raw0[0]"));
+        assert!(generated.contains("results0_1 := api.DecodeF64(raw0[1])"));
+        assert_eq!(
+            results,
+            [
+                Operand::SingleValue("results0_0".into(), GoType::Uint64),
+                Operand::SingleValue("results0_1".into(), GoType::Float64),
+            ]
+        );
+    }
 }
@@ -1,14 +1,28 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
+use crate::compat::wit_parser::{Function, Resolve, Type, World, WorldItem};
 use genco::prelude::*;
 
 use crate::{
-    codegen::ir::AnalyzedImports,
+    codegen::{
+        backend::{RuntimeBackend, Wazero},
+        func::{REALLOC, export_field},
+        helpers::{NEW_RESOURCE_TABLE, RESOURCE_TABLE, WRITE_STRING},
+        instance_options::{instance_option_type, module_config},
+        instrument::{Instrumentation, instrumented_name},
+        ir::AnalyzedImports,
+        limits::{option_type, runtime_config},
+        resources::{GuestResourceRegistryGenerator, release_guest_resources, table_name},
+        stubs::stub_name,
+        wasi::{adapter_name, has_adapter},
+    },
+    contains_type,
     go::{
         GoIdentifier, comment,
         imports::{
-            CONTEXT_CONTEXT, ERRORS_NEW, WAZERO_API_MEMORY, WAZERO_API_MODULE,
-            WAZERO_COMPILED_MODULE, WAZERO_NEW_MODULE_CONFIG, WAZERO_NEW_RUNTIME, WAZERO_RUNTIME,
+            CONTEXT_BACKGROUND, CONTEXT_CONTEXT, ERRORS_NEW, FX_HOOK, FX_LIFECYCLE, FX_MODULE,
+            FX_PROVIDE, STRINGS_JOIN, SYNC_MUTEX, WAZERO_API_MODULE,
+            WAZERO_NEW_RUNTIME_WITH_CONFIG, WIRE_NEW_SET,
         },
     },
 };
@@ -18,17 +32,149 @@ pub struct FactoryConfig<'a> {
     pub analyzed_imports: &'a AnalyzedImports,
     pub import_chains: BTreeMap<String, Tokens<Go>>,
     pub wasm_var_name: &'a GoIdentifier,
+    /// Other components whose exports satisfy some of the imports.
+    pub linked: &'a [LinkedComponent],
+    /// Whether to expose the underlying module and memory helpers on the
+    /// instance.
+    pub expose_module: bool,
+    /// Whether the constructor takes the imports as a single struct, rather
+    /// than as positional parameters.
+    pub imports_struct: bool,
+    /// Whether imports that aren't provided are replaced with stubs.
+    pub stub_missing_imports: bool,
+    /// Whether imports of well-known WASI interfaces that aren't provided are
+    /// replaced with adapters backed by the Go standard library.
+    pub wasi_adapters: bool,
+    /// The WIT names of the imports that may be left out, i.e. be `nil`, in
+    /// which case their host modules aren't registered.
+    pub optional_imports: BTreeSet<String>,
+    /// The instrumentation of the imports, and the hooks set on the factory.
+    pub instrumentation: &'a Instrumentation,
+    /// The names of the core Wasm functions called by the exports, which are
+    /// looked up when instantiating the module.
+    pub exports: Vec<String>,
+    /// The WIT names of the resources implemented by the guest, whose handles
+    /// are tracked per instance.
+    pub guest_resources: Vec<String>,
+    /// The WIT names of the resources implemented by the host, whose handles
+    /// given to the guests are held by a table per factory.
+    pub host_resources: Vec<String>,
+    /// Whether to generate the `writeString` helper, see [`uses_write_string`].
+    pub write_string: bool,
+    /// Whether to generate a constructor compiling Wasm bytes given by the
+    /// host, rather than the embedded ones.
+    pub from_bytes: bool,
+    /// Whether to generate constructors taking a runtime, which is shared
+    /// with the host rather than owned by the factory.
+    pub shared_runtime: bool,
+    /// Whether the constructors creating a runtime take the options limiting
+    /// the resources of the instances, see [`LimitsGenerator`].
+    ///
+    /// [`LimitsGenerator`]: crate::codegen::LimitsGenerator
+    pub limits: bool,
+    /// Whether `Instantiate` takes the options configuring the sandbox of
+    /// the instance, see [`InstanceOptionsGenerator`].
+    ///
+    /// [`InstanceOptionsGenerator`]: crate::codegen::InstanceOptionsGenerator
+    pub instance_options: bool,
+    /// The dependency injection framework to generate a provider of the
+    /// factory for, if any.
+    pub providers: Option<Providers>,
+}
+
+/// A dependency injection framework, for which a provider of the factory is
+/// generated along with the constructors.
+///
+/// The provider takes the imports, but not a context, so that the framework
+/// can construct the factory from the implementations of the imports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Providers {
+    /// A provider returning a cleanup function, in a `wire` provider set.
+    Wire,
+    /// A provider closing the factory when the application stops, in an `fx`
+    /// module.
+    Fx,
+}
+
+/// Returns true if the world writes strings into the guest memory, which is
+/// done by the `writeString` helper.
+///
+/// Strings are written when passed to an export, or returned by an import.
+pub fn uses_write_string(resolve: &Resolve, world: &World) -> bool {
+    let params = functions(resolve, world.exports.values())
+        .flat_map(|func| func.params.iter().map(|(_, typ)| typ));
+    let results = functions(resolve, world.imports.values()).flat_map(|func| &func.result);
+    params
+        .chain(results)
+        .any(|typ| contains_type(resolve, typ, Type::String))
+}
+
+/// The functions of the given world items, including those of interfaces.
+fn functions<'a>(
+    resolve: &'a Resolve,
+    items: impl Iterator<Item = &'a WorldItem>,
+) -> impl Iterator<Item = &'a Function> {
+    items.flat_map(|item| match item {
+        WorldItem::Function(func) => vec![func],
+        WorldItem::Interface { id, .. } => resolve.interfaces[*id].functions.values().collect(),
+        WorldItem::Type(_) => vec![],
+    })
+}
+
+/// A component linked into a factory, whose instance implements some of
+/// the factory's import interfaces using the component's exports.
+#[derive(Debug, Clone)]
+pub struct LinkedComponent {
+    /// The name of the component's own factory type.
+    pub factory_name: GoIdentifier,
+    /// The name of the constructor for the component's own factory.
+    pub constructor_name: GoIdentifier,
+    /// The name of the struct holding the component's own imports.
+    pub imports_name: GoIdentifier,
+    /// The variable (and field) holding the component's factory.
+    pub factory_var: GoIdentifier,
+    /// The variable holding the component's instance.
+    pub instance_var: GoIdentifier,
+    /// The imports of the component, forwarded from the factory constructor.
+    pub parameters: Vec<LinkedParameter>,
+    /// The constructor parameters of the factory implemented by the
+    /// component's instance, rather than provided by the host, along with
+    /// the accessors of the instance returning the exported interfaces
+    /// implementing them.
+    pub provides: Vec<(GoIdentifier, GoIdentifier)>,
+}
+
+/// An import of a linked component, forwarded from the factory constructor.
+#[derive(Debug, Clone)]
+pub struct LinkedParameter {
+    /// The name of the parameter in the factory constructor.
+    pub name: GoIdentifier,
+    /// The name of the field in the linked component's imports struct.
+    pub field: GoIdentifier,
+    /// The Go interface type of the import.
+    pub go_type: GoIdentifier,
 }
 
 /// Generator for factory and instance types
 pub struct FactoryGenerator<'a> {
     config: FactoryConfig<'a>,
+    backend: &'a dyn RuntimeBackend,
 }
 
 impl<'a> FactoryGenerator<'a> {
     /// Create a new factory generator with the given config.
     pub fn new(config: FactoryConfig<'a>) -> Self {
-        Self { config }
+        Self {
+            config,
+            backend: &Wazero,
+        }
+    }
+
+    /// Sets the runtime compiling and instantiating the module, which is
+    /// Wazero by default.
+    pub fn with_backend(mut self, backend: &'a dyn RuntimeBackend) -> Self {
+        self.backend = backend;
+        self
     }
 
     /// Get the instance name from the analyzed imports.
@@ -38,28 +184,35 @@ impl<'a> FactoryGenerator<'a> {
 
     /// Generate the `writeString` helper function.
     fn generate_write_string(&self, tokens: &mut Tokens<Go>) {
+        let backend = self.backend;
+        let call = backend.call(&quote!(realloc), quote!(0, 0, 1, uint64(len(s))));
+        let write = backend.write(&quote!(memory), quote!(uint32(ptr)), quote!([]byte(s)));
+        let write_string = &WRITE_STRING.ident();
         // Add writeString helper function for interface string returns
         quote_in! { *tokens =>
             $(comment(&[
-                "writeString will put a Go string into the Wasm memory following the Component",
-                "Model calling conventions, such as allocating memory with the realloc function",
+                format!(
+                    "{} will put a Go string into the Wasm memory following the Component",
+                    String::from(write_string),
+                ),
+                "Model calling conventions, such as allocating memory with the realloc function".into(),
             ]))
-            func writeString(
+            func $write_string(
                 ctx $CONTEXT_CONTEXT,
                 s string,
-                memory $WAZERO_API_MEMORY,
-                realloc api.Function,
+                memory $(backend.memory_type()),
+                realloc $(backend.function_type()),
             ) (uint64, uint64, error) {
                 if len(s) == 0 {
                     return 1, 0, nil
                 }
 
-                results, err := realloc.Call(ctx, 0, 0, 1, uint64(len(s)))
+                results, err := $call
                 if err != nil {
                     return 1, 0, err
                 }
                 ptr := results[0]
-                ok := memory.Write(uint32(ptr), []byte(s))
+                ok := $write
                 if !ok {
                     return 1, 0, $ERRORS_NEW("failed to write string to wasm memory")
                 }
@@ -69,74 +222,520 @@ impl<'a> FactoryGenerator<'a> {
         };
     }
 
-    /// Generate the Factory struct, constructor, and methods.
+    /// The instance fields caching the functions of the module, with the
+    /// names of the functions.
+    fn functions(&self) -> Vec<(GoIdentifier, &str)> {
+        std::iter::once(REALLOC)
+            .chain(self.config.exports.iter().map(String::as_str))
+            .map(|name| (export_field(name), name))
+            .collect()
+    }
+
+    /// Generate the Factory struct, constructors, and methods.
     fn generate_factory(&self, tokens: &mut Tokens<Go>) {
         let AnalyzedImports {
             factory_name,
             instance_name,
-            constructor_name,
             ..
         } = &self.config.analyzed_imports;
-        let wasm_var_name = self.config.wasm_var_name;
-        // Build the parameter list
-        let params = self.build_parameters();
+        let linked = self.config.linked;
+        let hooks = !self.config.instrumentation.hooks().is_empty();
+        let hooks_name = &self.config.analyzed_imports.hooks_name;
+        let shared = self.config.shared_runtime;
+        let instance_options = self.config.instance_options;
+        let functions = self.functions();
+        let backend = self.backend;
+        let module = quote!(module);
+        let (factory_runtime, factory_module) = (quote!(f.runtime), quote!(f.module));
         quote_in! { *tokens =>
             $['\n']
             type $factory_name struct {
-                runtime $WAZERO_RUNTIME
-                module  $WAZERO_COMPILED_MODULE
+                runtime $(backend.runtime_type())
+                module  $(backend.compiled_module_type())
+                $(if shared {
+                    $(comment(&[
+                        "Whether Close closes the runtime, or only the module, rather than leaving",
+                        "them to the host.",
+                    ]))
+                    closeRuntime bool
+                    closeModule  bool
+                })
+                $(comment(&[
+                    "The instances that haven't been closed yet, which are closed along with",
+                    "the factory, and the function reporting them as leaked.",
+                ]))
+                mu        $SYNC_MUTEX
+                instances map[*$instance_name]struct{}
+                closed    bool
+                leaks     func(leaked []*$instance_name)
+                $(if hooks => hooks *$hooks_name)
+                $(for component in linked join ($['\r']) => $(&component.factory_var) *$(&component.factory_name))
+                $(if !self.config.host_resources.is_empty() {
+                    $(comment(&[
+                        "The handles to the resources implemented by the host that were given to",
+                        "the guests.",
+                    ]))
+                    $(for name in &self.config.host_resources join ($['\r']) => $(table_name(name)) *$RESOURCE_TABLE)
+                })
             }
             $['\n']
-            func $constructor_name(
-                $['\r']
-                $params
-                $['\r']
-            ) (*$factory_name, error) {
-                wazeroRuntime := $WAZERO_NEW_RUNTIME(ctx)
+        };
+        self.generate_constructors(tokens);
+        if let Some(providers) = self.config.providers {
+            self.generate_provider(providers, tokens);
+        }
+        quote_in! { *tokens =>
+            func (f *$factory_name) Instantiate(ctx $CONTEXT_CONTEXT$(if instance_options => , opts ...$(instance_option_type()))) (*$instance_name, error) {
+                module, err := $(if instance_options {
+                    $(&factory_runtime).InstantiateModule(ctx, $(&factory_module), $(module_config())(opts))
+                } else {
+                    $(backend.instantiate_module(&factory_runtime, &factory_module))
+                })
+                if err != nil {
+                    return nil, err
+                }
+                instance := &$instance_name{
+                    factory: f,
+                    module:  module,
+                    memory:  $(backend.memory(&module)),
+                    $(for (field, name) in &functions join ($['\r']) => $field: $(backend.function(&module, quote!($(quoted(*name))))),)
+                    $(if hooks => hooks: f.hooks,)
+                }
 
-                $(for chain in self.config.import_chains.values() =>
-                    $chain
-                    $['\r']
-                )
+                f.mu.Lock()
+                defer f.mu.Unlock()
+                if f.closed {
+                    module.Close(ctx)
+                    return nil, $ERRORS_NEW("factory is closed")
+                }
+                if f.instances == nil {
+                    f.instances = map[*$instance_name]struct{}{}
+                }
+                f.instances[instance] = struct{}{}
+                return instance, nil
+            }
+            $['\n']
+            $(comment(&[
+                "SetLeakReporter sets the function called by Close with the instances that",
+                "haven't been closed, before closing them along with the factory.",
+            ]))
+            func (f *$factory_name) SetLeakReporter(report func(leaked []*$instance_name)) {
+                f.mu.Lock()
+                defer f.mu.Unlock()
+                f.leaks = report
+            }
+            $['\n']
+            $(comment(&[
+                "release stops tracking the instance, returning false if it was already",
+                "closed, either by itself or along with the factory.",
+            ]))
+            func (f *$factory_name) release(instance *$instance_name) bool {
+                f.mu.Lock()
+                defer f.mu.Unlock()
+                _, ok := f.instances[instance]
+                delete(f.instances, instance)
+                return ok
+            }
+            $['\n']
+            $(comment(&[
+                "Close closes the factory, along with any instance that hasn't been closed.",
+                "Closing the factory again does nothing.",
+            ]))
+            func (f *$factory_name) Close(ctx $CONTEXT_CONTEXT) {
+                f.mu.Lock()
+                if f.closed {
+                    f.mu.Unlock()
+                    return
+                }
+                f.closed = true
+                leaked := make([]*$instance_name, 0, len(f.instances))
+                for instance := range f.instances {
+                    leaked = append(leaked, instance)
+                }
+                f.instances = nil
+                report := f.leaks
+                f.mu.Unlock()
+
+                if report != nil && len(leaked) > 0 {
+                    report(leaked)
+                }
+                for _, instance := range leaked {
+                    instance.module.Close(ctx)
+                    $(release_guest_resources(&self.config.guest_resources, quote!(instance.module)))
+                }
+                $(if shared {
+                    if f.closeRuntime {
+                        f.runtime.Close(ctx)
+                    } else if f.closeModule {
+                        f.module.Close(ctx)
+                    }
+                } else {
+                    f.runtime.Close(ctx)
+                })
+                $(for component in linked join ($['\r']) => f.$(&component.factory_var).Close(ctx))
+            }
+            $['\n']
+        };
+        self.generate_hook_setters(tokens);
+    }
 
+    /// Generate the constructors of the factory.
+    ///
+    /// With a shared runtime, the constructors creating the runtime or
+    /// compiling the module forward to the one taking both.
+    fn generate_constructors(&self, tokens: &mut Tokens<Go>) {
+        let AnalyzedImports {
+            factory_name,
+            constructor_name,
+            ..
+        } = &self.config.analyzed_imports;
+        let wasm_var_name = self.config.wasm_var_name;
+        let backend = self.backend;
+        let runtime = &quote!(wazeroRuntime);
+        let args = &self.build_arguments();
+        let limits = self.config.limits;
+        // The runtime created by the constructor is configured by the options,
+        // which follow the imports
+        let options = &quote!($(if limits => $['\r']opts ...$(option_type()),));
+        let new_runtime = &if limits {
+            quote!($WAZERO_NEW_RUNTIME_WITH_CONFIG(ctx, $(runtime_config())(opts)))
+        } else {
+            backend.new_runtime()
+        };
+        // The runtime or module created by a constructor is closed if it fails
+        let compile = |wasm: &Tokens<Go>, cleanup: Tokens<Go>| {
+            quote! {
                 $(comment(&[
                     "Compiling the module takes a LONG time, so we want to do it once and hold",
                        "onto it with the Runtime",
                 ]))
-                module, err := wazeroRuntime.CompileModule(ctx, $wasm_var_name)
+                module, err := $(backend.compile_module(runtime, wasm))
                 if err != nil {
+                    $cleanup
                     return nil, err
                 }
-                return &$factory_name{
-                    runtime: wazeroRuntime,
-                    module:  module,
-                }, nil
+            }
+        };
+
+        // With Wasm given by the host, the constructor of the embedded Wasm
+        // forwards to the one taking the Wasm
+        let (constructor, wasm_param, wasm) = if self.config.from_bytes {
+            let from_bytes =
+                GoIdentifier::public(format!("{}-from-bytes", String::from(constructor_name)));
+            quote_in! { *tokens =>
+                func $constructor_name(
+                    $['\r']
+                    $(self.build_parameters(Tokens::new()))$options
+                    $['\r']
+                ) (*$factory_name, error) {
+                    return $(&from_bytes)(ctx, $wasm_var_name$args$(if limits => , opts...))
+                }
+                $['\n']
+                $(comment(&[
+                    format!("{} is like {}, but compiles the given Wasm", String::from(&from_bytes), String::from(constructor_name)),
+                    "rather than the embedded one, e.g. a build for another platform. The Wasm".into(),
+                    "must be built for the same world.".into(),
+                ]))
+            };
+            (from_bytes, quote!(wasm []byte,), quote!(wasm))
+        } else {
+            (
+                constructor_name.clone(),
+                Tokens::new(),
+                quote!($wasm_var_name),
+            )
+        };
+
+        if !self.config.shared_runtime {
+            quote_in! { *tokens =>
+                func $constructor(
+                    $['\r']
+                    $(self.build_parameters(wasm_param))$options
+                    $['\r']
+                ) (*$factory_name, error) {
+                    $(self.generate_setup(limits))
+                    wazeroRuntime := $new_runtime
+                    $(comment(&[
+                        "The runtime is closed if instantiating the imports or compiling the module",
+                        "fails, rather than leaked",
+                    ]))
+                    created := false
+                    defer func() {
+                        if !created {
+                            wazeroRuntime.Close(ctx)
+                        }
+                    }()
+
+                    $(self.generate_import_chains())
+
+                    $(compile(&wasm, Tokens::new()))
+                    created = true
+                    $(self.generate_new_factory())
+                }
+                $['\n']
+            };
+            return;
+        }
+
+        let with_runtime =
+            GoIdentifier::public(format!("{}-with-runtime", String::from(constructor_name)));
+        let from_compiled = GoIdentifier::public(format!(
+            "{}-from-compiled-module",
+            String::from(constructor_name)
+        ));
+        let runtime_param = quote!(wazeroRuntime $(backend.runtime_type()),);
+        let module_param = quote!(module $(backend.compiled_module_type()),);
+        quote_in! { *tokens =>
+            func $constructor(
+                $['\r']
+                $(self.build_parameters(wasm_param))$options
+                $['\r']
+            ) (*$factory_name, error) {
+                wazeroRuntime := $new_runtime
+
+                $(compile(&wasm, quote!(wazeroRuntime.Close(ctx))))
+                f, err := $(&from_compiled)(ctx, wazeroRuntime, module$args)
+                if err != nil {
+                    wazeroRuntime.Close(ctx)
+                    return nil, err
+                }
+                f.closeRuntime = true
+                return f, nil
             }
             $['\n']
-            func (f *$factory_name) Instantiate(ctx $CONTEXT_CONTEXT) (*$instance_name, error) {
-                if module, err := f.runtime.InstantiateModule(ctx, f.module, $WAZERO_NEW_MODULE_CONFIG()); err != nil {
+            $(comment(&[
+                format!("{} is like {}, but compiles the", String::from(&with_runtime), String::from(constructor_name)),
+                "embedded Wasm with the given runtime, e.g. one shared by many components,".into(),
+                "which must stay open until the factory is closed. Closing the factory only".into(),
+                "closes the module.".into(),
+                "".into(),
+                "The imports are instantiated as host modules of the runtime, named after".into(),
+                "their interfaces, so the runtime can't host another factory importing the".into(),
+                "same interfaces. They stay instantiated until the runtime is closed.".into(),
+            ]))
+            func $(&with_runtime)(
+                $['\r']
+                $(self.build_parameters(runtime_param.clone()))
+                $['\r']
+            ) (*$factory_name, error) {
+                $(compile(&quote!($wasm_var_name), Tokens::new()))
+                f, err := $(&from_compiled)(ctx, wazeroRuntime, module$args)
+                if err != nil {
+                    module.Close(ctx)
                     return nil, err
-                } else {
-                    return &$instance_name{module}, nil
                 }
+                f.closeModule = true
+                return f, nil
             }
             $['\n']
-            func (f *$factory_name) Close(ctx $CONTEXT_CONTEXT) {
-                f.runtime.Close(ctx)
+            $(comment(&[
+                format!("{} is like {}, but", String::from(&from_compiled), String::from(&with_runtime)),
+                "uses the given module compiled with the runtime, which must be built for the".into(),
+                "same world. Closing the factory closes neither.".into(),
+            ]))
+            func $(&from_compiled)(
+                $['\r']
+                $(self.build_parameters(quote!($runtime_param$['\r']$module_param)))
+                $['\r']
+            ) (*$factory_name, error) {
+                $(self.generate_setup(false))
+                $(self.generate_import_chains())
+
+                $(self.generate_new_factory())
             }
             $['\n']
         };
     }
 
+    /// Generate the provider of the factory for the dependency injection
+    /// framework, and the provider set or module holding it.
+    ///
+    /// The provider creates the factory with a background context, so its
+    /// constructor's options, if any, are left to their defaults.
+    fn generate_provider(&self, providers: Providers, tokens: &mut Tokens<Go>) {
+        let AnalyzedImports {
+            factory_name,
+            constructor_name,
+            ..
+        } = &self.config.analyzed_imports;
+        let name = String::from(factory_name);
+        let provider = &GoIdentifier::public(format!("provide-{name}"));
+        let args = &self.build_arguments();
+        match providers {
+            Providers::Wire => {
+                let set = &GoIdentifier::public(format!("{name}-provider-set"));
+                quote_in! { *tokens =>
+                    $(comment(&[
+                        format!("{} creates a {name} with a background context, for", String::from(provider)),
+                        "dependency injection frameworks such as wire. The returned function closes".into(),
+                        "the factory.".into(),
+                    ]))
+                    func $provider(
+                        $['\r']
+                        $(self.build_import_parameters())
+                        $['\r']
+                    ) (*$factory_name, func(), error) {
+                        ctx := $CONTEXT_BACKGROUND()
+                        f, err := $constructor_name(ctx$args)
+                        if err != nil {
+                            return nil, nil, err
+                        }
+                        return f, func() { f.Close(ctx) }, nil
+                    }
+                    $['\n']
+                    $(comment(&[format!(
+                        "{} is the wire provider set of a {name}.",
+                        String::from(set),
+                    )]))
+                    var $set = $WIRE_NEW_SET($provider)
+                    $['\n']
+                };
+            }
+            Providers::Fx => {
+                let module = &GoIdentifier::public(format!("{name}-module"));
+                quote_in! { *tokens =>
+                    $(comment(&[
+                        format!("{} creates a {name} with a background context, for", String::from(provider)),
+                        "dependency injection frameworks such as fx, closing it when the application".into(),
+                        "stops.".into(),
+                    ]))
+                    func $provider(
+                        $['\r']
+                        lc $FX_LIFECYCLE,
+                        $(self.build_import_parameters())
+                        $['\r']
+                    ) (*$factory_name, error) {
+                        f, err := $constructor_name($CONTEXT_BACKGROUND()$args)
+                        if err != nil {
+                            return nil, err
+                        }
+                        lc.Append($FX_HOOK{
+                            OnStop: func(ctx $CONTEXT_CONTEXT) error {
+                                f.Close(ctx)
+                                return nil
+                            },
+                        })
+                        return f, nil
+                    }
+                    $['\n']
+                    $(comment(&[format!(
+                        "{} is the fx module providing a {name}.",
+                        String::from(module),
+                    )]))
+                    var $module = $FX_MODULE($(quoted(&name)), $FX_PROVIDE($provider))
+                    $['\n']
+                };
+            }
+        }
+    }
+
+    /// Generate the start of the constructor setting up the imports, before
+    /// they are instantiated.
+    ///
+    /// The options of the constructor, if it takes any, are forwarded to the
+    /// factories of linked components.
+    fn generate_setup(&self, options: bool) -> Tokens<Go> {
+        let linked = self.config.linked;
+        let hooks = !self.config.instrumentation.hooks().is_empty();
+        let hooks_name = &self.config.analyzed_imports.hooks_name;
+        let checked = !self.config.imports_struct && self.required_imports().next().is_some();
+        quote! {
+            $(if self.config.imports_struct {
+                $(self.generate_imports_locals())
+                $['\n']
+            })
+            $(if checked {
+                $(self.generate_nil_checks())
+                $['\n']
+            })
+            $(if self.config.wasi_adapters {
+                $(self.generate_adapter_defaults())
+                $['\n']
+            })
+            $(if self.config.stub_missing_imports {
+                $(self.generate_stub_defaults())
+                $['\n']
+            })
+            $(if hooks {
+                hooks := &$hooks_name{}
+                $['\n']
+            })
+            $(if self.config.instrumentation.instruments_imports() {
+                $(self.generate_instrumented_imports())
+                $['\n']
+            })
+            $(if !linked.is_empty() {
+                $(self.generate_linked_instances(options))
+                $['\n']
+            })
+        }
+    }
+
+    /// Generate the end of the constructor returning the factory.
+    fn generate_new_factory(&self) -> Tokens<Go> {
+        let factory_name = &self.config.analyzed_imports.factory_name;
+        let linked = self.config.linked;
+        let hooks = !self.config.instrumentation.hooks().is_empty();
+        quote! {
+            $(if !linked.is_empty() => linked = true)
+            return &$factory_name{
+                runtime: wazeroRuntime,
+                module:  module,
+                $(if hooks => hooks: hooks,)
+                $(for component in linked join ($['\r']) => $(&component.factory_var): $(&component.factory_var),)
+                $(for name in &self.config.host_resources join ($['\r']) => $(table_name(name)): $(table_name(name)),)
+            }, nil
+        }
+    }
+
+    /// Generate the methods of the factory setting each hook, which are
+    /// forwarded to the factories of linked components.
+    fn generate_hook_setters(&self, tokens: &mut Tokens<Go>) {
+        let factory_name = &self.config.analyzed_imports.factory_name;
+        for hook in self.config.instrumentation.hooks() {
+            let (setter, field) = (&hook.setter(), &hook.field());
+            quote_in! { *tokens =>
+                $(comment(hook.setter_docs()))
+                func (f *$factory_name) $setter($field $(hook.go_type())) {
+                    f.hooks.$field = $field
+                    $(for component in self.config.linked join ($['\r']) => f.$(&component.factory_var).$setter($field))
+                }
+                $['\n']
+            };
+        }
+    }
+
     /// Generate the Instance struct, and methods.
     fn generate_instance(&self, tokens: &mut Tokens<Go>) {
         let instance_name = &self.config.analyzed_imports.instance_name;
+        let hooks = !self.config.instrumentation.hooks().is_empty();
+        let functions = self.functions();
+        let backend = self.backend;
         quote_in! { *tokens =>
             type $instance_name struct {
-                module $WAZERO_API_MODULE
+                factory *$(&self.config.analyzed_imports.factory_name)
+                module  $(backend.module_type())
+                $(comment(&[
+                    "The memory and functions of the module are looked up once, rather than",
+                    "on every call.",
+                ]))
+                memory  $(backend.memory_type())
+                $(for (field, _) in &functions join ($['\r']) => $field $(backend.function_type()))
+                $(if hooks => hooks *$(&self.config.analyzed_imports.hooks_name))
             }
             $['\n']
+            $(comment(&[
+                "Close closes the instance. Closing the instance again, or after closing the",
+                "factory, does nothing.",
+            ]))
             func (i *$instance_name) Close(ctx $CONTEXT_CONTEXT) error {
+                if !i.factory.release(i) {
+                    return nil
+                }
+                $(if !self.config.guest_resources.is_empty() {
+                    $(comment(&["The handles to the resources of the instance are stale once it's closed"]))
+                    $(release_guest_resources(&self.config.guest_resources, quote!(i.module)))
+                })
                 if err := i.module.Close(ctx); err != nil {
                     return err
                 }
@@ -145,54 +744,541 @@ impl<'a> FactoryGenerator<'a> {
             }
             $['\n']
         };
+        if !self.config.guest_resources.is_empty() {
+            GuestResourceRegistryGenerator::new(instance_name, &self.config.guest_resources)
+                .format_into(tokens);
+            tokens.line();
+        }
+        if self.config.expose_module {
+            self.generate_module_accessors(tokens);
+        }
     }
 
-    /// Build parameter list for factory constructor
-    fn build_parameters(&self) -> Tokens<Go> {
-        let interfaces = &self.config.analyzed_imports.interfaces;
+    /// Generate the `Module` accessor and memory helpers on the instance.
+    ///
+    /// These are an escape hatch for hosts that need to call exports or access
+    /// memory outside of the WIT world, such as debug hooks.
+    fn generate_module_accessors(&self, tokens: &mut Tokens<Go>) {
+        let instance_name = &self.config.analyzed_imports.instance_name;
+        quote_in! { *tokens =>
+            $(comment(&[
+                "Module returns the underlying module, for calling exports or accessing",
+                "memory that isn't part of the WIT world.",
+            ]))
+            func (i *$instance_name) Module() $WAZERO_API_MODULE {
+                return i.module
+            }
+            $['\n']
+            $(comment(&[
+                "CallExport calls an exported function by name with raw Core Wasm values.",
+            ]))
+            func (i *$instance_name) CallExport(ctx $CONTEXT_CONTEXT, name string, params ...uint64) ([]uint64, error) {
+                fn := i.module.ExportedFunction(name)
+                if fn == nil {
+                    return nil, $ERRORS_NEW("export not found: " + name)
+                }
+                return fn.Call(ctx, params...)
+            }
+            $['\n']
+            $(comment(&[
+                "ReadMemory copies length bytes from the guest memory at the given offset.",
+            ]))
+            func (i *$instance_name) ReadMemory(offset uint32, length uint32) ([]byte, error) {
+                buf, ok := i.memory.Read(offset, length)
+                if !ok {
+                    return nil, $ERRORS_NEW("failed to read bytes from memory")
+                }
+                $(comment(&["The view is invalidated if the memory grows, so return a copy"]))
+                return append([]byte(nil), buf...), nil
+            }
+            $['\n']
+            $(comment(&["WriteMemory writes the bytes to the guest memory at the given offset."]))
+            func (i *$instance_name) WriteMemory(offset uint32, data []byte) error {
+                if !i.memory.Write(offset, data) {
+                    return $ERRORS_NEW("failed to write bytes to memory")
+                }
+                return nil
+            }
+            $['\n']
+            $(comment(&["ReadUint32 reads a little-endian uint32 from the guest memory."]))
+            func (i *$instance_name) ReadUint32(offset uint32) (uint32, error) {
+                value, ok := i.memory.ReadUint32Le(offset)
+                if !ok {
+                    return 0, $ERRORS_NEW("failed to read uint32 from memory")
+                }
+                return value, nil
+            }
+            $['\n']
+            $(comment(&["WriteUint32 writes a little-endian uint32 to the guest memory."]))
+            func (i *$instance_name) WriteUint32(offset uint32, value uint32) error {
+                if !i.memory.WriteUint32Le(offset, value) {
+                    return $ERRORS_NEW("failed to write uint32 to memory")
+                }
+                return nil
+            }
+            $['\n']
+            $(comment(&["ReadUint64 reads a little-endian uint64 from the guest memory."]))
+            func (i *$instance_name) ReadUint64(offset uint32) (uint64, error) {
+                value, ok := i.memory.ReadUint64Le(offset)
+                if !ok {
+                    return 0, $ERRORS_NEW("failed to read uint64 from memory")
+                }
+                return value, nil
+            }
+            $['\n']
+            $(comment(&["WriteUint64 writes a little-endian uint64 to the guest memory."]))
+            func (i *$instance_name) WriteUint64(offset uint32, value uint64) error {
+                if !i.memory.WriteUint64Le(offset, value) {
+                    return $ERRORS_NEW("failed to write uint64 to memory")
+                }
+                return nil
+            }
+            $['\n']
+        };
+    }
 
+    /// Generate the instantiation of the linked components, whose instances
+    /// stand in for the import interfaces they provide.
+    ///
+    /// A single instance of each linked component is shared by all instances
+    /// created by the factory.
+    fn generate_linked_instances(&self, options: bool) -> Tokens<Go> {
+        let mut tokens = Tokens::new();
+        for component in self.config.linked {
+            let factory_var = &component.factory_var;
+            let instance_var = &component.instance_var;
+            quote_in! { tokens =>
+                $['\r']
+                $factory_var, err := $(&component.constructor_name)(
+                    ctx,
+                    $(if self.config.imports_struct {
+                        $(&component.imports_name){
+                            $(for param in &component.parameters join ($['\r']) => $(&param.field): $(&param.name),)
+                        },
+                    } else {
+                        $(for param in &component.parameters join ($['\r']) => $(&param.name),)
+                    })
+                    $(if options => opts...,)
+                )
+                if err != nil {
+                    return nil, err
+                }
+                $instance_var, err := $factory_var.Instantiate(ctx)
+                if err != nil {
+                    $factory_var.Close(ctx)
+                    return nil, err
+                }
+                $(for (provided, accessor) in &component.provides join ($['\r']) => $provided := $instance_var.$accessor())
+            };
+        }
+
+        let factory_vars = self
+            .config
+            .linked
+            .iter()
+            .map(|component| &component.factory_var);
+        quote_in! { tokens =>
+            $['\n']
+            $(comment(&["Close the linked components if the factory fails to be created"]))
+            linked := false
+            defer func() {
+                if !linked {
+                    $(for var in factory_vars join ($['\r']) => $var.Close(ctx))
+                }
+            }()
+        };
+        tokens
+    }
+
+    /// The imports passed to the factory constructor.
+    ///
+    /// This excludes the imports provided by linked components, and includes
+    /// the imports of those linked components instead.
+    fn constructor_imports(&self) -> Vec<ConstructorImport<'_>> {
+        let provided = self
+            .config
+            .linked
+            .iter()
+            .flat_map(|component| &component.provides)
+            .map(|(provided, _)| String::from(provided))
+            .collect::<Vec<_>>();
+        let interfaces = self
+            .config
+            .analyzed_imports
+            .interfaces
+            .iter()
+            .filter(|interface| {
+                !provided.contains(&String::from(&interface.constructor_param_name))
+            })
+            .map(|interface| ConstructorImport {
+                name: &interface.constructor_param_name,
+                go_type: &interface.go_interface_name,
+                used: !interface.methods.is_empty(),
+                own: true,
+                adapter: self.config.wasi_adapters && has_adapter(interface),
+                optional: self
+                    .config
+                    .optional_imports
+                    .contains(&interface.wazero_module_name),
+            });
+        let linked_params = self
+            .config
+            .linked
+            .iter()
+            .flat_map(|component| &component.parameters)
+            .map(|param| ConstructorImport {
+                name: &param.name,
+                go_type: &param.go_type,
+                used: true,
+                own: false,
+                adapter: false,
+                optional: false,
+            });
+        interfaces.chain(linked_params).collect()
+    }
+
+    /// Build parameter list for factory constructor, with the given
+    /// parameters following the context.
+    fn build_parameters(&self, leading: Tokens<Go>) -> Tokens<Go> {
         quote! {
             ctx $CONTEXT_CONTEXT,
-            $(for interface in interfaces.iter() =>
-            $(&interface.constructor_param_name) $(&interface.go_interface_name),)
+            $leading
+            $(self.build_import_parameters())
+        }
+    }
+
+    /// Build the parameters of the factory constructor taking the imports.
+    fn build_import_parameters(&self) -> Tokens<Go> {
+        if self.config.imports_struct {
+            return quote!(imports $(&self.config.analyzed_imports.imports_name),);
+        }
+
+        quote! {
+            $(for import in self.constructor_imports() join ($['\r']) =>
+            $(import.name) $(import.go_type),)
+        }
+    }
+
+    /// The arguments forwarding the parameters of the constructor after the
+    /// context, each preceded by a comma.
+    fn build_arguments(&self) -> Tokens<Go> {
+        if self.config.imports_struct {
+            return quote!(, imports);
+        }
+        quote!($(for import in self.constructor_imports() => , $(import.name)))
+    }
+
+    /// The imports the host must provide to the positional constructor.
+    ///
+    /// The imports of linked components are checked by their own factories.
+    fn required_imports(&self) -> impl Iterator<Item = ConstructorImport<'_>> {
+        self.constructor_imports().into_iter().filter(|import| {
+            import.own
+                && import.used
+                && !import.optional
+                && !import.adapter
+                && !self.config.stub_missing_imports
+        })
+    }
+
+    /// Generate the check that none of the required imports passed to the
+    /// positional constructor are `nil`, which would otherwise only fail
+    /// when the guest calls them.
+    fn generate_nil_checks(&self) -> Tokens<Go> {
+        let required = self
+            .required_imports()
+            .map(|import| (quote!($(import.name)), String::from(import.name)))
+            .collect::<Vec<_>>();
+        quote! {
+            $(missing_imports(&required))
+            if len(missing) > 0 {
+                return nil, $ERRORS_NEW("missing imports: " + $STRINGS_JOIN(missing, ", "))
+            }
+        }
+    }
+
+    /// Generate the registration of the host modules implementing the
+    /// imports, skipping those of optional imports that are left out, after
+    /// the tables of the handles to the host's resources they give guests.
+    fn generate_import_chains(&self) -> Tokens<Go> {
+        let mut tokens = Tokens::new();
+        for name in &self.config.host_resources {
+            quote_in! { tokens =>
+                $(table_name(name)) := $NEW_RESOURCE_TABLE()
+                $['\r']
+            };
+        }
+        for (module, chain) in &self.config.import_chains {
+            let optional = self
+                .config
+                .analyzed_imports
+                .interfaces
+                .iter()
+                .find(|interface| &interface.wazero_module_name == module)
+                .filter(|interface| {
+                    !interface.methods.is_empty() && self.config.optional_imports.contains(module)
+                });
+            match optional {
+                Some(interface) => quote_in! { tokens =>
+                    if $(&interface.constructor_param_name) != nil {
+                        $chain
+                    }
+                    $['\r']
+                },
+                None => quote_in! { tokens =>
+                    $chain
+                    $['\r']
+                },
+            }
+        }
+        tokens
+    }
+
+    /// Generate the struct holding the imports, and the method validating
+    /// that all of them are provided.
+    fn generate_imports_struct(&self, tokens: &mut Tokens<Go>) {
+        let imports_name = &self.config.analyzed_imports.imports_name;
+        let imports = self.constructor_imports();
+        let fields = imports
+            .iter()
+            .map(|import| (imports_field(import.name), import.go_type))
+            .collect::<Vec<_>>();
+        // Missing imports are replaced by stubs, so none of them are required,
+        // and neither are those replaced by WASI adapters or left out
+        let required = imports
+            .iter()
+            .filter(|import| {
+                !self.config.stub_missing_imports && !import.adapter && !import.optional
+            })
+            .map(|import| {
+                let field = imports_field(import.name);
+                (quote!(i.$(&field)), String::from(&field))
+            })
+            .collect::<Vec<_>>();
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!("{} holds the implementations of the imports passed to", String::from(imports_name)),
+                format!("{}.", String::from(&self.config.analyzed_imports.constructor_name)),
+            ]))
+            type $imports_name struct {
+                $(for (field, typ) in &fields join ($['\r']) => $field $(*typ))
+            }
+            $['\n']
+            $(comment(&["Validate returns an error if any of the imports are missing."]))
+            func (i $imports_name) Validate() error {
+                $(if !required.is_empty() {
+                    $(missing_imports(&required))
+                    if len(missing) > 0 {
+                        return $ERRORS_NEW("missing imports: " + $STRINGS_JOIN(missing, ", "))
+                    }
+                })
+                return nil
+            }
+        };
+    }
+
+    /// Generate the validation of the imports struct, and the local variables
+    /// used by the constructor in place of positional parameters.
+    fn generate_imports_locals(&self) -> Tokens<Go> {
+        let imports = self.constructor_imports();
+        quote! {
+            if err := imports.Validate(); err != nil {
+                return nil, err
+            }
+            $(for import in imports =>
+                $(if import.used {
+                    $['\r']
+                    $(import.name) := imports.$(imports_field(import.name))
+                })
+            )
+        }
+    }
+
+    /// Generate the replacement of imports of well-known WASI interfaces that
+    /// aren't provided with adapters.
+    fn generate_adapter_defaults(&self) -> Tokens<Go> {
+        let imports = self
+            .constructor_imports()
+            .into_iter()
+            .filter(|import| import.adapter && import.used && !import.optional);
+        quote! {
+            $(for import in imports join ($['\r']) =>
+                if $(import.name) == nil {
+                    $(import.name) = $(adapter_name(import.go_type)){}
+                }
+            )
+        }
+    }
+
+    /// Generate the replacement of imports that aren't provided with stubs.
+    ///
+    /// The imports of linked components are stubbed by their own factories.
+    fn generate_stub_defaults(&self) -> Tokens<Go> {
+        let imports = self
+            .constructor_imports()
+            .into_iter()
+            .filter(|import| import.own && import.used && !import.optional);
+        quote! {
+            $(for import in imports join ($['\r']) =>
+                if $(import.name) == nil {
+                    $(import.name) = $(stub_name(import.go_type)){}
+                }
+            )
         }
     }
 }
 
-impl<'a> FormatInto<Go> for &FactoryGenerator<'a> {
-    fn format_into(self, tokens: &mut Tokens<Go>) {
+impl FactoryGenerator<'_> {
+    /// Generate the wrapping of imports with instrumentation.
+    ///
+    /// The imports of linked components are wrapped by their own factories.
+    fn generate_instrumented_imports(&self) -> Tokens<Go> {
+        let imports = self
+            .constructor_imports()
+            .into_iter()
+            .filter(|import| import.own && import.used);
+        let hooks = !self.config.instrumentation.hooks().is_empty();
+        quote! {
+            $(for import in imports join ($['\r']) =>
+                $(if import.optional {
+                    if $(import.name) != nil {
+                        $(import.name) = $(instrumented_name(import.go_type)){$(import.name)$(if hooks => , hooks)}
+                    }
+                } else {
+                    $(import.name) = $(instrumented_name(import.go_type)){$(import.name)$(if hooks => , hooks)}
+                })
+            )
+        }
+    }
+}
+
+/// An import passed to the factory constructor.
+struct ConstructorImport<'a> {
+    /// The name of the parameter, or local variable when the imports are
+    /// passed as a struct.
+    name: &'a GoIdentifier,
+    /// The Go interface type of the import.
+    go_type: &'a GoIdentifier,
+    /// Whether the import is used by the constructor, as Go rejects unused
+    /// local variables.
+    used: bool,
+    /// Whether the import belongs to this factory's world, rather than to a
+    /// linked component.
+    own: bool,
+    /// Whether the import is replaced by a WASI adapter when not provided.
+    adapter: bool,
+    /// Whether the import may be left out, in which case its host module
+    /// isn't registered.
+    optional: bool,
+}
+
+/// Generate the collection of the names of the given imports that are
+/// `nil` into `missing`, from the Go expressions of the imports.
+fn missing_imports(imports: &[(Tokens<Go>, String)]) -> Tokens<Go> {
+    quote! {
+        var missing []string
+        $(for (import, name) in imports join ($['\r']) =>
+            if $import == nil {
+                missing = append(missing, $(quoted(name)))
+            }
+        )
+    }
+}
+
+/// The name of the field in an imports struct for the given parameter.
+pub fn imports_field(param: &GoIdentifier) -> GoIdentifier {
+    GoIdentifier::public(String::from(param))
+}
+
+impl<'a> FactoryGenerator<'a> {
+    /// Generate the factory and instance types, without the helper functions
+    /// shared by all factories in the file.
+    pub fn format_factory_into(&self, tokens: &mut Tokens<Go>) {
+        if self.config.imports_struct {
+            self.generate_imports_struct(tokens);
+        }
         self.generate_factory(tokens);
         tokens.push();
         self.generate_instance(tokens);
         tokens.push();
-        self.generate_write_string(tokens);
+    }
+}
+
+impl<'a> FormatInto<Go> for &FactoryGenerator<'a> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        self.format_factory_into(tokens);
+        if self.config.write_string {
+            self.generate_write_string(tokens);
+        }
         tokens.push();
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use genco::lang::go::Tokens;
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        sync::LazyLock,
+    };
+
+    use crate::compat::{
+        abi::WasmType,
+        wit_parser::{Resolve, Type},
+    };
+    use genco::{lang::go::Tokens, quote};
 
     use crate::{
-        codegen::{FactoryGenerator, factory::FactoryConfig, ir::AnalyzedImports},
-        go::GoIdentifier,
+        codegen::{
+            FactoryGenerator, Instrumentation, RuntimeBackend, Wazero, Width,
+            factory::{
+                FactoryConfig, LinkedComponent, LinkedParameter, Providers, uses_write_string,
+            },
+            fixtures::{analyzed, interface, method, returning},
+            ir::AnalyzedImports,
+        },
+        go::{GoIdentifier, GoType},
     };
 
+    static WASM_VAR_NAME: LazyLock<GoIdentifier> =
+        LazyLock::new(|| GoIdentifier::private("test-wasm"));
+
+    /// The configuration of a factory of the given imports with every option
+    /// disabled, which tests override as needed.
+    fn factory_config(analyzed_imports: &AnalyzedImports) -> FactoryConfig<'_> {
+        FactoryConfig {
+            analyzed_imports,
+            import_chains: Default::default(),
+            wasm_var_name: &WASM_VAR_NAME,
+            linked: &[],
+            expose_module: false,
+            imports_struct: false,
+            stub_missing_imports: false,
+            wasi_adapters: false,
+            optional_imports: Default::default(),
+            instrumentation: &Instrumentation {
+                otel: false,
+                metrics: false,
+                log: false,
+            },
+            exports: vec![],
+            guest_resources: vec![],
+            host_resources: vec![],
+            write_string: false,
+            from_bytes: false,
+            shared_runtime: false,
+            limits: false,
+            instance_options: false,
+            providers: None,
+        }
+    }
+
     #[test]
     fn test_generate_write_string() {
-        let analyzed_imports = &AnalyzedImports {
-            interfaces: vec![],
-            standalone_types: vec![],
-            standalone_functions: vec![],
-            factory_name: GoIdentifier::public("test-factory"),
-            instance_name: GoIdentifier::public("test-instance"),
-            constructor_name: GoIdentifier::public("test-constructor"),
-        };
+        let analyzed_imports = &analyzed(vec![]);
         let config = FactoryConfig {
-            analyzed_imports,
-            import_chains: Default::default(),
-            wasm_var_name: &GoIdentifier::public("test-wasm"),
+            write_string: true,
+            ..factory_config(analyzed_imports)
         };
         let generator = FactoryGenerator::new(config);
         let mut tokens = Tokens::new();
@@ -200,4 +1286,548 @@ mod tests {
 
         assert!(tokens.to_string().unwrap().contains("func writeString"));
     }
+
+    /// A backend hosting the component with another runtime, which only
+    /// differs from Wazero in how modules are compiled and instantiated.
+    struct TestBackend;
+
+    impl RuntimeBackend for TestBackend {
+        fn runtime_type(&self) -> Tokens {
+            quote!(*testrt.Engine)
+        }
+        fn compiled_module_type(&self) -> Tokens {
+            quote!(*testrt.Module)
+        }
+        fn module_type(&self) -> Tokens {
+            quote!(*testrt.Instance)
+        }
+        fn memory_type(&self) -> Tokens {
+            Wazero.memory_type()
+        }
+        fn function_type(&self) -> Tokens {
+            Wazero.function_type()
+        }
+        fn new_runtime(&self) -> Tokens {
+            quote!(testrt.NewEngine())
+        }
+        fn compile_module(&self, runtime: &Tokens, wasm: &Tokens) -> Tokens {
+            quote!(testrt.Compile($runtime, $wasm))
+        }
+        fn instantiate_module(&self, runtime: &Tokens, module: &Tokens) -> Tokens {
+            quote!(testrt.Instantiate($runtime, $module))
+        }
+        fn memory(&self, module: &Tokens) -> Tokens {
+            Wazero.memory(module)
+        }
+        fn function(&self, module: &Tokens, name: Tokens) -> Tokens {
+            Wazero.function(module, name)
+        }
+        fn call(&self, function: &Tokens, args: Tokens) -> Tokens {
+            Wazero.call(function, args)
+        }
+        fn load(&self, memory: &Tokens, width: Width, addr: Tokens) -> Tokens {
+            Wazero.load(memory, width, addr)
+        }
+        fn store(&self, memory: &Tokens, width: Width, addr: Tokens, value: Tokens) -> Tokens {
+            Wazero.store(memory, width, addr, value)
+        }
+        fn read(&self, memory: &Tokens, ptr: Tokens, len: Tokens) -> Tokens {
+            Wazero.read(memory, ptr, len)
+        }
+        fn write(&self, memory: &Tokens, ptr: Tokens, data: Tokens) -> Tokens {
+            Wazero.write(memory, ptr, data)
+        }
+        fn value_type(&self) -> GoType {
+            Wazero.value_type()
+        }
+        fn go_type(&self, typ: &WasmType) -> GoType {
+            Wazero.go_type(typ)
+        }
+        fn arg(&self, typ: &WasmType, value: Tokens) -> Tokens {
+            Wazero.arg(typ, value)
+        }
+        fn result(&self, typ: &WasmType, value: Tokens) -> Tokens {
+            Wazero.result(typ, value)
+        }
+        fn encode_i32(&self, value: Tokens) -> Tokens {
+            Wazero.encode_i32(value)
+        }
+        fn encode_u32(&self, value: Tokens) -> Tokens {
+            Wazero.encode_u32(value)
+        }
+        fn encode_i64(&self, value: Tokens) -> Tokens {
+            Wazero.encode_i64(value)
+        }
+        fn encode_f32(&self, value: Tokens) -> Tokens {
+            Wazero.encode_f32(value)
+        }
+        fn encode_f64(&self, value: Tokens) -> Tokens {
+            Wazero.encode_f64(value)
+        }
+        fn decode_f32(&self, value: Tokens) -> Tokens {
+            Wazero.decode_f32(value)
+        }
+        fn decode_f64(&self, value: Tokens) -> Tokens {
+            Wazero.decode_f64(value)
+        }
+    }
+
+    #[test]
+    fn test_generate_factory_with_backend() {
+        let analyzed_imports = &analyzed(vec![]);
+        let config = factory_config(analyzed_imports);
+        let mut tokens = Tokens::new();
+        FactoryGenerator::new(config)
+            .with_backend(&TestBackend)
+            .format_factory_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("    runtime *testrt.Engine\n    module *testrt.Module\n"));
+        assert!(output.contains("wazeroRuntime := testrt.NewEngine()"));
+        assert!(output.contains("module, err := testrt.Compile(wazeroRuntime, testWasm)"));
+        assert!(output.contains("module, err := testrt.Instantiate(f.runtime, f.module)\n"));
+        assert!(output.contains("    module *testrt.Instance\n"));
+        assert!(!output.contains("wazero."));
+    }
+
+    #[test]
+    fn test_generate_from_bytes_constructor() {
+        let analyzed_imports = &analyzed(vec![]);
+        let config = FactoryConfig {
+            imports_struct: true,
+            from_bytes: true,
+            ..factory_config(analyzed_imports)
+        };
+        let mut tokens = Tokens::new();
+        FactoryGenerator::new(config).format_factory_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains(
+            "    imports TestImports,\n) (*TestFactory, error) {\n    return NewTestFactoryFromBytes(ctx, testWasm, imports)\n}"
+        ));
+        assert!(output.contains(
+            "func NewTestFactoryFromBytes(\n    ctx context.Context,\n    wasm []byte,\n    imports TestImports,\n)"
+        ));
+        assert!(output.contains("module, err := wazeroRuntime.CompileModule(ctx, wasm)"));
+        // The runtime is closed on any error of the constructor creating it
+        assert!(output.contains(
+            "    defer func() {\n        if !created {\n            wazeroRuntime.Close(ctx)\n        }\n    }()"
+        ));
+        assert!(output.contains("    created = true\n    return &TestFactory{"));
+    }
+
+    #[test]
+    fn test_generate_limited_constructors() {
+        let analyzed_imports = &analyzed(vec![]);
+        let config = FactoryConfig {
+            imports_struct: true,
+            from_bytes: true,
+            limits: true,
+            ..factory_config(analyzed_imports)
+        };
+        let mut tokens = Tokens::new();
+        FactoryGenerator::new(config).format_factory_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains(
+            "    imports TestImports,\n    opts ...FactoryOption,\n) (*TestFactory, error) {\n    return NewTestFactoryFromBytes(ctx, testWasm, imports, opts...)\n}"
+        ));
+        assert!(output.contains(
+            "func NewTestFactoryFromBytes(\n    ctx context.Context,\n    wasm []byte,\n    imports TestImports,\n    opts ...FactoryOption,\n)"
+        ));
+        assert!(
+            output
+                .contains("wazeroRuntime := wazero.NewRuntimeWithConfig(ctx, runtimeConfig(opts))")
+        );
+    }
+
+    #[test]
+    fn test_generate_instantiate_options() {
+        let analyzed_imports = &analyzed(vec![]);
+        let config = FactoryConfig {
+            imports_struct: true,
+            instance_options: true,
+            ..factory_config(analyzed_imports)
+        };
+        let mut tokens = Tokens::new();
+        FactoryGenerator::new(config).format_factory_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains(
+            "func (f *TestFactory) Instantiate(ctx context.Context, opts ...InstanceOption) (*TestInstance, error) {"
+        ));
+        assert!(output.contains(
+            "module, err := f.runtime.InstantiateModule(ctx, f.module, moduleConfig(opts))"
+        ));
+    }
+
+    #[test]
+    fn test_generate_providers() {
+        let analyzed_imports = &analyzed(vec![]);
+        let generate = |providers| {
+            let config = FactoryConfig {
+                imports_struct: true,
+                limits: true,
+                providers: Some(providers),
+                ..factory_config(analyzed_imports)
+            };
+            let mut tokens = Tokens::new();
+            FactoryGenerator::new(config).format_factory_into(&mut tokens);
+            tokens.to_string().unwrap()
+        };
+
+        let output = generate(Providers::Wire);
+        assert!(output.contains(
+            "func ProvideTestFactory(\n    imports TestImports,\n) (*TestFactory, func(), error) {\n    ctx := context.Background()\n    f, err := NewTestFactory(ctx, imports)"
+        ));
+        assert!(output.contains("return f, func() { f.Close(ctx) }, nil"));
+        assert!(output.contains("var TestFactoryProviderSet = wire.NewSet(ProvideTestFactory)"));
+
+        let output = generate(Providers::Fx);
+        assert!(output.contains(
+            "func ProvideTestFactory(\n    lc fx.Lifecycle,\n    imports TestImports,\n) (*TestFactory, error) {\n    f, err := NewTestFactory(context.Background(), imports)"
+        ));
+        assert!(output.contains("lc.Append(fx.Hook{"));
+        assert!(output.contains(
+            "var TestFactoryModule = fx.Module(\"TestFactory\", fx.Provide(ProvideTestFactory))"
+        ));
+    }
+
+    #[test]
+    fn test_generate_shared_runtime_constructors() {
+        let analyzed_imports = &analyzed(vec![]);
+        let config = FactoryConfig {
+            imports_struct: true,
+            shared_runtime: true,
+            ..factory_config(analyzed_imports)
+        };
+        let mut tokens = Tokens::new();
+        FactoryGenerator::new(config).format_factory_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains(
+            "    module, err := wazeroRuntime.CompileModule(ctx, testWasm)\n    if err != nil {\n        wazeroRuntime.Close(ctx)\n        return nil, err\n    }"
+        ));
+        assert!(output.contains(
+            "    f, err := NewTestFactoryFromCompiledModule(ctx, wazeroRuntime, module, imports)\n    if err != nil {\n        wazeroRuntime.Close(ctx)\n        return nil, err\n    }\n    f.closeRuntime = true"
+        ));
+        assert!(output.contains(
+            "func NewTestFactoryWithRuntime(\n    ctx context.Context,\n    wazeroRuntime wazero.Runtime,\n    imports TestImports,\n)"
+        ));
+        assert!(output.contains("module, err := wazeroRuntime.CompileModule(ctx, testWasm)"));
+        assert!(output.contains(
+            "    if err != nil {\n        module.Close(ctx)\n        return nil, err\n    }\n    f.closeModule = true"
+        ));
+        assert!(output.contains(
+            "func NewTestFactoryFromCompiledModule(\n    ctx context.Context,\n    wazeroRuntime wazero.Runtime,\n    module wazero.CompiledModule,\n    imports TestImports,\n)"
+        ));
+        assert!(output.contains(
+            "    if f.closeRuntime {\n        f.runtime.Close(ctx)\n    } else if f.closeModule {\n        f.module.Close(ctx)\n    }"
+        ));
+    }
+
+    #[test]
+    fn test_generate_host_resource_tables() {
+        let analyzed_imports = &analyzed(vec![]);
+        let config = FactoryConfig {
+            host_resources: vec!["fooer".to_string(), "barer".to_string()],
+            ..factory_config(analyzed_imports)
+        };
+        let mut tokens = Tokens::new();
+        FactoryGenerator::new(config).format_factory_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        for expected in [
+            "    fooerTable *resourceTable
+    barerTable *resourceTable
+}",
+            "    fooerTable := newResourceTable()
+    barerTable := newResourceTable()
+",
+            "        fooerTable: fooerTable,
+        barerTable: barerTable,
+    }, nil",
+        ] {
+            assert!(
+                output.contains(expected),
+                "missing {expected:?} in:\n{output}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_instance_tracking() {
+        let analyzed_imports = &analyzed(vec![]);
+        let config = factory_config(analyzed_imports);
+        let mut tokens = Tokens::new();
+        FactoryGenerator::new(config).format_factory_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains(
+            "    if f.closed {\n        module.Close(ctx)\n        return nil, errors.New(\"factory is closed\")\n    }"
+        ));
+        assert!(
+            output.contains("    f.instances[instance] = struct{}{}\n    return instance, nil")
+        );
+        assert!(output.contains(
+            "func (f *TestFactory) Close(ctx context.Context) {\n    f.mu.Lock()\n    if f.closed {\n        f.mu.Unlock()\n        return\n    }"
+        ));
+        assert!(
+            output.contains(
+                "    if report != nil && len(leaked) > 0 {\n        report(leaked)\n    }"
+            )
+        );
+        assert!(output.contains(
+            "func (i *TestInstance) Close(ctx context.Context) error {\n    if !i.factory.release(i) {\n        return nil\n    }"
+        ));
+    }
+
+    #[test]
+    fn test_generate_linked_factory() {
+        let analyzed_imports = &analyzed(vec![]);
+        let linked = &[LinkedComponent {
+            factory_name: GoIdentifier::public("provider-factory"),
+            constructor_name: GoIdentifier::public("new-provider-factory"),
+            imports_name: GoIdentifier::public("provider-imports"),
+            factory_var: GoIdentifier::private("provider-factory"),
+            instance_var: GoIdentifier::private("provider-instance"),
+            parameters: vec![LinkedParameter {
+                name: GoIdentifier::private("provider-logger"),
+                field: GoIdentifier::public("logger"),
+                go_type: GoIdentifier::public("i-provider-logger"),
+            }],
+            provides: vec![(
+                GoIdentifier::private("greeter"),
+                GoIdentifier::public("greeter"),
+            )],
+        }];
+        let config = FactoryConfig {
+            linked,
+            write_string: true,
+            ..factory_config(analyzed_imports)
+        };
+        let mut tokens = Tokens::new();
+        FactoryGenerator::new(config).format_factory_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("providerLogger IProviderLogger,"));
+        assert!(output.contains("providerFactory *ProviderFactory"));
+        assert!(output.contains("providerInstance, err := providerFactory.Instantiate(ctx)"));
+        assert!(output.contains("greeter := providerInstance"));
+        assert!(output.contains("f.providerFactory.Close(ctx)"));
+    }
+
+    #[test]
+    fn test_generate_module_accessors() {
+        let analyzed_imports = &analyzed(vec![]);
+        let config = FactoryConfig {
+            expose_module: true,
+            write_string: true,
+            ..factory_config(analyzed_imports)
+        };
+        let mut tokens = Tokens::new();
+        FactoryGenerator::new(config).format_factory_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("func (i *TestInstance) Module() api.Module {"));
+        assert!(output.contains("func (i *TestInstance) CallExport("));
+        assert!(output.contains("func (i *TestInstance) ReadMemory(offset uint32, length uint32)"));
+        assert!(output.contains("func (i *TestInstance) WriteUint64(offset uint32, value uint64)"));
+    }
+
+    #[test]
+    fn test_generate_imports_struct() {
+        let analyzed_imports = &analyzed(vec![interface("logger", "test:pkg/logger", vec![])]);
+        let config = FactoryConfig {
+            imports_struct: true,
+            write_string: true,
+            ..factory_config(analyzed_imports)
+        };
+        let mut tokens = Tokens::new();
+        FactoryGenerator::new(config).format_factory_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("type TestImports struct {"));
+        assert!(output.contains("Logger ITestLogger"));
+        assert!(output.contains("func (i TestImports) Validate() error {"));
+        assert!(output.contains("if i.Logger == nil {"));
+        assert!(output.contains("missing = append(missing, \"Logger\")"));
+        assert!(
+            output.contains(
+                "return errors.New(\"missing imports: \" + strings.Join(missing, \", \"))"
+            )
+        );
+        assert!(output.contains("imports TestImports,"));
+        assert!(output.contains("if err := imports.Validate(); err != nil {"));
+        // The interface has no methods, so the import is never used
+        assert!(!output.contains("logger := imports.Logger"));
+    }
+
+    #[test]
+    fn test_generate_stub_defaults() {
+        let analyzed_imports = &analyzed(vec![interface(
+            "logger",
+            "test:pkg/logger",
+            vec![method("log", vec![], None)],
+        )]);
+        let config = FactoryConfig {
+            imports_struct: true,
+            stub_missing_imports: true,
+            write_string: true,
+            ..factory_config(analyzed_imports)
+        };
+        let generator = FactoryGenerator::new(config);
+        let mut tokens = Tokens::new();
+        generator.format_factory_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        // None of the imports are required
+        assert!(!output.contains("missing = append"));
+        assert!(output.contains("logger := imports.Logger"));
+        assert!(output.contains("if logger == nil {"));
+        assert!(output.contains("logger = unimplementedITestLogger{}"));
+    }
+
+    #[test]
+    fn test_generate_nil_checks() {
+        let interface = |name: &str| {
+            interface(
+                name,
+                &format!("test:pkg/{name}"),
+                vec![method("log", vec![], None)],
+            )
+        };
+        let analyzed_imports = &analyzed(vec![interface("logger"), interface("tracer")]);
+        let config = FactoryConfig {
+            import_chains: BTreeMap::from([
+                ("test:pkg/logger".to_string(), quote!(registerLogger())),
+                ("test:pkg/tracer".to_string(), quote!(registerTracer())),
+            ]),
+            optional_imports: BTreeSet::from(["test:pkg/tracer".to_string()]),
+            write_string: true,
+            ..factory_config(analyzed_imports)
+        };
+        let mut tokens = Tokens::new();
+        FactoryGenerator::new(config).format_factory_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(
+            output.contains("if logger == nil {\n        missing = append(missing, \"logger\")")
+        );
+        assert!(output.contains(
+            "return nil, errors.New(\"missing imports: \" + strings.Join(missing, \", \"))"
+        ));
+        // The optional import is only registered when provided
+        assert!(!output.contains("\"tracer\""));
+        assert!(output.contains("    registerLogger()\n"));
+        assert!(output.contains("if tracer != nil {\n        registerTracer()\n    }"));
+    }
+
+    #[test]
+    fn test_generate_wasi_adapter_defaults() {
+        let method = |name: &str| method(name, vec![], returning(GoType::Uint64, Type::U64));
+        let analyzed_imports = &analyzed(vec![
+            interface(
+                "insecure",
+                "wasi:random/insecure@0.2.0",
+                vec![method("get-insecure-random-u64")],
+            ),
+            interface("logger", "test:pkg/logger", vec![method("level")]),
+        ]);
+        let config = FactoryConfig {
+            imports_struct: true,
+            wasi_adapters: true,
+            write_string: true,
+            ..factory_config(analyzed_imports)
+        };
+        let generator = FactoryGenerator::new(config);
+        let mut tokens = Tokens::new();
+        generator.format_factory_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        // Only the imports without an adapter are required
+        assert!(!output.contains("append(missing, \"Insecure\")"));
+        assert!(output.contains("append(missing, \"Logger\")"));
+        assert!(
+            output.contains("if insecure == nil {\n        insecure = wasiITestInsecure{}\n    }")
+        );
+        assert!(!output.contains("wasiITestLogger"));
+    }
+
+    #[test]
+    fn test_generate_hooks() {
+        let analyzed_imports = &analyzed(vec![]);
+        let config = FactoryConfig {
+            instrumentation: &Instrumentation {
+                metrics: true,
+                ..Default::default()
+            },
+            exports: vec!["hello".to_string()],
+            ..factory_config(analyzed_imports)
+        };
+        let mut tokens = Tokens::new();
+        FactoryGenerator::new(config).format_factory_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("hooks *testHooks"));
+        assert!(output.contains("hooks := &testHooks{}"));
+        assert!(output.contains("hooks: hooks,"));
+        assert!(output.contains("func (f *TestFactory) SetMetrics(metrics Metrics) {"));
+        assert!(output.contains("f.hooks.metrics = metrics"));
+        assert!(output.contains("instance := &TestInstance{\n"));
+        assert!(output.contains("hooks: f.hooks,\n"));
+        assert!(output.contains("fnCabiRealloc api.Function\n"));
+        assert!(output.contains("fnCabiRealloc: module.ExportedFunction(\"cabi_realloc\"),\n"));
+        assert!(output.contains("fnHello: module.ExportedFunction(\"hello\"),\n"));
+    }
+
+    #[test]
+    fn test_uses_write_string() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface logger {
+                    log: func(msg: string);
+                    name: func() -> option<string>;
+                }
+
+                world reads-strings {
+                    import log: func(msg: string);
+                    export hello: func() -> string;
+                }
+
+                world writes-imported-strings {
+                    import logger;
+                }
+
+                world writes-exported-strings {
+                    export greet: func(names: list<string>);
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let world = |name: &str| {
+            let (_, world) = resolve
+                .worlds
+                .iter()
+                .find(|(_, world)| world.name == name)
+                .expect("missing world");
+            world
+        };
+
+        assert!(!uses_write_string(&resolve, world("reads-strings")));
+
+        assert!(uses_write_string(
+            &resolve,
+            world("writes-imported-strings")
+        ));
+        assert!(uses_write_string(
+            &resolve,
+            world("writes-exported-strings")
+        ));
+    }
 }
@@ -0,0 +1,657 @@
+use std::collections::BTreeSet;
+
+use crate::compat::wit_parser::{
+    Function, Handle, InterfaceId, Resolve, Stability, Type, TypeDefKind, TypeId, TypeOwner, World,
+    WorldId, WorldItem, WorldKey,
+};
+
+use crate::contains_type;
+
+/// Filters restricting the interfaces of a world that bindings are generated
+/// for, by their names like `ns:pkg/name`.
+///
+/// Functions and types of the world itself are always generated.
+#[derive(Debug, Clone, Default)]
+pub struct InterfaceFilter {
+    /// Only generate the interfaces with these names, if any are given.
+    pub include: Vec<String>,
+    /// Don't generate the interfaces with these names.
+    pub exclude: Vec<String>,
+}
+
+impl InterfaceFilter {
+    /// Returns true if the filter doesn't remove any interfaces.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    fn keeps(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|include| include == name))
+            && !self.exclude.iter().any(|exclude| exclude == name)
+    }
+}
+
+/// Removes the interfaces rejected by the filter from the imports and
+/// exports of the world.
+///
+/// An import can only be removed if the core Wasm module doesn't import it,
+/// given the module and field names of its imports, as it would otherwise
+/// fail to instantiate. Nor can it be removed if the remaining items of the
+/// world use its types, as they wouldn't be defined.
+pub fn filter_world(
+    resolve: &mut Resolve,
+    world_id: WorldId,
+    filter: &InterfaceFilter,
+    module_imports: &BTreeSet<(String, String)>,
+) -> Result<(), String> {
+    let world = &resolve.worlds[world_id];
+    let names = interfaces(resolve, world)
+        .map(|(name, _)| name)
+        .collect::<BTreeSet<_>>();
+    if let Some(unknown) = filter
+        .include
+        .iter()
+        .chain(&filter.exclude)
+        .find(|name| !names.contains(*name))
+    {
+        return Err(format!(
+            "unknown interface: {unknown}, the world `{}` has {}",
+            world.name,
+            names.into_iter().collect::<Vec<_>>().join(", ")
+        ));
+    }
+
+    let removed = interfaces(resolve, world)
+        .filter(|(name, _)| !filter.keeps(name))
+        .collect::<Vec<_>>();
+    for (name, _) in &removed {
+        let imported = world
+            .imports
+            .keys()
+            .any(|key| resolve.name_world_key(key) == *name);
+        if imported && module_imports.iter().any(|(module, _)| module == name) {
+            return Err(format!(
+                "unable to exclude interface: {name}, which is imported by the module"
+            ));
+        }
+    }
+    let removed_ids = removed.iter().map(|(_, id)| *id).collect::<BTreeSet<_>>();
+    let is_removed = |item: &WorldItem| match item {
+        WorldItem::Interface { id, .. } => removed_ids.contains(id),
+        WorldItem::Function(_) | WorldItem::Type(_) => false,
+    };
+
+    let mut filtered = world.clone();
+    filtered.imports.retain(|_, item| !is_removed(item));
+    filtered.exports.retain(|_, item| !is_removed(item));
+
+    // Types used by the remaining items must still be defined
+    let used = filtered
+        .imports
+        .values()
+        .chain(filtered.exports.values())
+        .flat_map(|item| match item {
+            WorldItem::Interface { id, .. } => {
+                resolve.interfaces[*id].types.values().copied().collect()
+            }
+            WorldItem::Type(id) => vec![*id],
+            WorldItem::Function(_) => vec![],
+        });
+    for id in used {
+        if let TypeDefKind::Type(Type::Id(target)) = resolve.types[id].kind
+            && let TypeOwner::Interface(owner) = resolve.types[target].owner
+            && let Some((name, _)) = removed.iter().find(|(_, id)| *id == owner)
+        {
+            return Err(format!(
+                "unable to exclude interface: {name}, whose type `{}` is used by the world",
+                resolve.types[target].name.as_deref().unwrap_or_default()
+            ));
+        }
+    }
+
+    resolve.worlds[world_id] = filtered;
+    Ok(())
+}
+
+/// Removes the items of the world gated by `@unstable` features that aren't
+/// enabled in the `Resolve`, as wit-bindgen does when parsing WIT.
+///
+/// Items that are stable `@since` a version are always kept. A gated item
+/// can't be removed if the core Wasm module imports it, given the module and
+/// field names of its imports, or if a remaining item uses its types, so its
+/// feature must be enabled instead.
+pub fn filter_features(
+    resolve: &mut Resolve,
+    world_id: WorldId,
+    module_imports: &BTreeSet<(String, String)>,
+) -> Result<(), String> {
+    let gate = |stability: &Stability| match stability {
+        Stability::Unstable { feature, .. }
+            if !resolve.all_features && !resolve.features.contains(feature) =>
+        {
+            Some(feature.clone())
+        }
+        _ => None,
+    };
+    let function_gate = |func: &Function| {
+        gate(&func.stability).or_else(|| {
+            func.kind
+                .resource()
+                .and_then(|id| gate(&resolve.types[id].stability))
+        })
+    };
+    let imported = |module: &str, name: Option<&str>| {
+        module_imports
+            .iter()
+            .any(|(m, n)| m == module && name.is_none_or(|name| n == name))
+    };
+    let gated = |item: String, feature: String| {
+        format!(
+            "unable to skip {item}, which is gated by feature `{feature}` but imported by the module"
+        )
+    };
+
+    let world = &resolve.worlds[world_id];
+    let mut removed_keys = Vec::new();
+    let mut removed_functions = Vec::new();
+    let mut removed_types = Vec::new();
+    let items = world
+        .imports
+        .iter()
+        .map(|item| (true, item))
+        .chain(world.exports.iter().map(|item| (false, item)));
+    for (import, (key, item)) in items {
+        let name = resolve.name_world_key(key);
+        let feature = gate(item.stability(resolve)).or_else(|| match item {
+            WorldItem::Interface { id, .. } => gate(&resolve.interfaces[*id].stability),
+            WorldItem::Function(func) => function_gate(func),
+            WorldItem::Type(_) => None,
+        });
+        if let Some(feature) = feature {
+            match item {
+                WorldItem::Interface { .. } if import && imported(&name, None) => {
+                    return Err(gated(format!("interface `{name}`"), feature));
+                }
+                WorldItem::Function(func) if import && imported("$root", Some(&func.name)) => {
+                    return Err(gated(format!("function `{name}`"), feature));
+                }
+                WorldItem::Type(id) => removed_types.push(*id),
+                _ => (),
+            }
+            removed_keys.push(key.clone());
+            continue;
+        }
+
+        let WorldItem::Interface { id, .. } = item else {
+            continue;
+        };
+        let interface = &resolve.interfaces[*id];
+        for func in interface.functions.values() {
+            if let Some(feature) = function_gate(func) {
+                if import && imported(&name, Some(&func.name)) {
+                    return Err(gated(
+                        format!("function `{}` of `{name}`", func.name),
+                        feature,
+                    ));
+                }
+                removed_functions.push((*id, func.name.clone()));
+            }
+        }
+        for typ in interface.types.values() {
+            if gate(&resolve.types[*typ].stability).is_some() {
+                removed_types.push(*typ);
+            }
+        }
+    }
+
+    // The remaining items can't use the types that are removed
+    for (key, item) in world.imports.iter().chain(&world.exports) {
+        if removed_keys.contains(key) {
+            continue;
+        }
+        let (functions, types): (Vec<&Function>, Vec<TypeId>) = match item {
+            WorldItem::Interface { id, .. } => {
+                let interface = &resolve.interfaces[*id];
+                (
+                    interface
+                        .functions
+                        .values()
+                        .filter(|func| !removed_functions.contains(&(*id, func.name.clone())))
+                        .collect(),
+                    interface
+                        .types
+                        .values()
+                        .filter(|typ| !removed_types.contains(typ))
+                        .copied()
+                        .collect(),
+                )
+            }
+            WorldItem::Function(func) => (vec![func], vec![]),
+            WorldItem::Type(id) => (vec![], vec![*id]),
+        };
+        let used = functions
+            .iter()
+            .flat_map(|func| func.params.iter().map(|(_, typ)| typ).chain(&func.result))
+            .copied()
+            .chain(types.into_iter().map(Type::Id));
+        for typ in used {
+            for removed in &removed_types {
+                let target = Type::Id(*removed);
+                if typ == target || contains_type(resolve, &typ, target) {
+                    let removed = &resolve.types[*removed];
+                    return Err(format!(
+                        "unable to skip type `{}`, which is gated by feature `{}` but used by `{}`",
+                        removed.name.as_deref().unwrap_or_default(),
+                        gate(&removed.stability).unwrap_or_default(),
+                        resolve.name_world_key(key)
+                    ));
+                }
+            }
+        }
+    }
+
+    let world = &mut resolve.worlds[world_id];
+    world.imports.retain(|key, _| !removed_keys.contains(key));
+    world.exports.retain(|key, _| !removed_keys.contains(key));
+    for (id, name) in removed_functions {
+        resolve.interfaces[id].functions.shift_remove(&name);
+    }
+    for interface in resolve
+        .interfaces
+        .iter_mut()
+        .map(|(_, interface)| interface)
+    {
+        interface
+            .types
+            .retain(|_, typ| !removed_types.contains(typ));
+    }
+    Ok(())
+}
+
+/// Removes the types of the interfaces and worlds that no function of the
+/// worlds uses, directly or through other types, so no bindings are generated
+/// for them.
+///
+/// Resources are always kept, as the module may import their intrinsics.
+/// Returns the names of the interfaces that types were removed from, or of
+/// the world for its own types, along with the names of the removed types.
+pub fn prune_types(resolve: &mut Resolve, worlds: &[WorldId]) -> Vec<(String, Vec<String>)> {
+    let mut used = BTreeSet::new();
+    for world_id in worlds {
+        let world = &resolve.worlds[*world_id];
+        for item in world.imports.values().chain(world.exports.values()) {
+            let functions: Vec<&Function> = match item {
+                WorldItem::Interface { id, .. } => {
+                    resolve.interfaces[*id].functions.values().collect()
+                }
+                WorldItem::Function(func) => vec![func],
+                WorldItem::Type(_) => vec![],
+            };
+            for func in functions {
+                for typ in func.params.iter().map(|(_, typ)| typ).chain(&func.result) {
+                    use_type(resolve, typ, &mut used);
+                }
+            }
+        }
+    }
+    let keeps =
+        |id: &TypeId| used.contains(id) || matches!(resolve.types[*id].kind, TypeDefKind::Resource);
+
+    let mut removed = Vec::new();
+    let mut seen = BTreeSet::new();
+    for world_id in worlds {
+        let world = &resolve.worlds[*world_id];
+        for (name, id) in interfaces(resolve, world) {
+            if !seen.insert(id) {
+                continue;
+            }
+            let types: Vec<String> = resolve.interfaces[id]
+                .types
+                .iter()
+                .filter(|(_, typ)| !keeps(typ))
+                .map(|(name, _)| name.clone())
+                .collect();
+            if !types.is_empty() {
+                removed.push((name, id, types));
+            }
+        }
+    }
+    let mut world_types = Vec::new();
+    for world_id in worlds {
+        let world = &resolve.worlds[*world_id];
+        let types: Vec<String> = world
+            .imports
+            .iter()
+            .chain(&world.exports)
+            .filter_map(|(key, item)| match item {
+                WorldItem::Type(id) if !keeps(id) => Some(resolve.name_world_key(key)),
+                _ => None,
+            })
+            .collect();
+        if !types.is_empty() {
+            world_types.push((*world_id, types));
+        }
+    }
+
+    let mut report = Vec::new();
+    for (name, id, types) in removed {
+        resolve.interfaces[id]
+            .types
+            .retain(|name, _| !types.contains(name));
+        report.push((name, types));
+    }
+    for (world_id, types) in world_types {
+        let world = &mut resolve.worlds[world_id];
+        let retain = |key: &WorldKey, item: &mut WorldItem| !matches!((key, item), (WorldKey::Name(name), WorldItem::Type(_)) if types.contains(name));
+        world.imports.retain(retain);
+        world.exports.retain(retain);
+        report.push((world.name.clone(), types));
+    }
+    report
+}
+
+/// Adds the type definitions used by the type to `used`, following aliases
+/// and handles to their resources.
+fn use_type(resolve: &Resolve, typ: &Type, used: &mut BTreeSet<TypeId>) {
+    let Type::Id(id) = typ else {
+        return;
+    };
+    if !used.insert(*id) {
+        return;
+    }
+    let mut uses = |typ: &Type| use_type(resolve, typ, used);
+    match &resolve.types[*id].kind {
+        TypeDefKind::Record(record) => record.fields.iter().for_each(|field| uses(&field.ty)),
+        TypeDefKind::Tuple(tuple) => tuple.types.iter().for_each(uses),
+        TypeDefKind::Variant(variant) => variant
+            .cases
+            .iter()
+            .flat_map(|case| &case.ty)
+            .for_each(uses),
+        TypeDefKind::Option(typ)
+        | TypeDefKind::List(typ)
+        | TypeDefKind::FixedSizeList(typ, _)
+        | TypeDefKind::Type(typ) => uses(typ),
+        TypeDefKind::Result(result) => result.ok.iter().chain(&result.err).for_each(uses),
+        TypeDefKind::Future(typ) | TypeDefKind::Stream(typ) => typ.iter().for_each(uses),
+        TypeDefKind::Handle(Handle::Own(id) | Handle::Borrow(id)) => uses(&Type::Id(*id)),
+        TypeDefKind::Resource
+        | TypeDefKind::Flags(_)
+        | TypeDefKind::Enum(_)
+        | TypeDefKind::Unknown => {}
+    }
+}
+
+/// The names and IDs of the interfaces imported and exported by the world.
+fn interfaces<'a>(
+    resolve: &'a Resolve,
+    world: &'a World,
+) -> impl Iterator<Item = (String, InterfaceId)> + 'a {
+    world
+        .imports
+        .iter()
+        .chain(&world.exports)
+        .filter_map(|(key, item)| match item {
+            WorldItem::Interface { id, .. } => Some((resolve.name_world_key(key), *id)),
+            WorldItem::Function(_) | WorldItem::Type(_) => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::compat::wit_parser::{Resolve, WorldId};
+
+    use crate::codegen::filter::{InterfaceFilter, filter_features, filter_world, prune_types};
+
+    fn world() -> (Resolve, WorldId) {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface types {
+                    record point { x: u32 }
+                }
+
+                interface logger {
+                    log: func(msg: string);
+                }
+
+                interface greeter {
+                    greet: func(name: string) -> string;
+                }
+
+                world filtered {
+                    use types.{point};
+
+                    import types;
+                    import logger;
+                    export greeter;
+                    export origin: func() -> point;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (world_id, _) = resolve.worlds.iter().next().expect("missing world");
+        (resolve, world_id)
+    }
+
+    fn names(resolve: &Resolve, world_id: WorldId) -> Vec<String> {
+        let world = &resolve.worlds[world_id];
+        world
+            .imports
+            .keys()
+            .chain(world.exports.keys())
+            .map(|key| resolve.name_world_key(key))
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_world() {
+        let (mut resolve, world_id) = world();
+        let filter = InterfaceFilter {
+            include: vec![],
+            exclude: vec!["test:pkg/greeter".to_string()],
+        };
+        filter_world(&mut resolve, world_id, &filter, &BTreeSet::new()).unwrap();
+        assert_eq!(
+            names(&resolve, world_id),
+            ["test:pkg/types", "test:pkg/logger", "point", "origin"]
+        );
+
+        let (mut resolve, world_id) = world();
+        let filter = InterfaceFilter {
+            include: vec!["test:pkg/types".to_string()],
+            exclude: vec![],
+        };
+        filter_world(&mut resolve, world_id, &filter, &BTreeSet::new()).unwrap();
+        assert_eq!(
+            names(&resolve, world_id),
+            ["test:pkg/types", "point", "origin"]
+        );
+    }
+
+    #[test]
+    fn test_filter_world_errors() {
+        let (mut resolve, world_id) = world();
+        let exclude = |name: &str| InterfaceFilter {
+            include: vec![],
+            exclude: vec![name.to_string()],
+        };
+
+        let err = filter_world(
+            &mut resolve,
+            world_id,
+            &exclude("test:pkg/missing"),
+            &BTreeSet::new(),
+        )
+        .unwrap_err();
+        assert!(err.starts_with("unknown interface: test:pkg/missing"));
+
+        let module_imports = BTreeSet::from([("test:pkg/logger".to_string(), "log".to_string())]);
+        let err = filter_world(
+            &mut resolve,
+            world_id,
+            &exclude("test:pkg/logger"),
+            &module_imports,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            "unable to exclude interface: test:pkg/logger, which is imported by the module"
+        );
+
+        let err = filter_world(
+            &mut resolve,
+            world_id,
+            &exclude("test:pkg/types"),
+            &BTreeSet::new(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            "unable to exclude interface: test:pkg/types, whose type `point` is used by the world"
+        );
+    }
+
+    fn gated_world() -> (Resolve, WorldId) {
+        let mut resolve = Resolve {
+            all_features: true,
+            ..Resolve::default()
+        };
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface logger {
+                    log: func(msg: string);
+
+                    @unstable(feature = fancy)
+                    record style { bold: bool }
+
+                    @unstable(feature = fancy)
+                    log-styled: func(msg: string, style: style);
+                }
+
+                world gated {
+                    import logger;
+
+                    export run: func();
+
+                    @unstable(feature = fancy)
+                    export run-fancy: func();
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        resolve.all_features = false;
+        let (world_id, _) = resolve.worlds.iter().next().expect("missing world");
+        (resolve, world_id)
+    }
+
+    fn logger(resolve: &Resolve) -> (Vec<&str>, Vec<&str>) {
+        let (_, interface) = resolve.interfaces.iter().next().expect("missing interface");
+        (
+            interface.functions.keys().map(String::as_str).collect(),
+            interface.types.keys().map(String::as_str).collect(),
+        )
+    }
+
+    #[test]
+    fn test_filter_features() {
+        let (mut resolve, world_id) = gated_world();
+        filter_features(&mut resolve, world_id, &BTreeSet::new()).unwrap();
+        assert_eq!(names(&resolve, world_id), ["test:pkg/logger", "run"]);
+        assert_eq!(logger(&resolve), (vec!["log"], vec![]));
+
+        let (mut resolve, world_id) = gated_world();
+        resolve.features.insert("fancy".to_string());
+        filter_features(&mut resolve, world_id, &BTreeSet::new()).unwrap();
+        assert_eq!(
+            names(&resolve, world_id),
+            ["test:pkg/logger", "run", "run-fancy"]
+        );
+        assert_eq!(logger(&resolve), (vec!["log", "log-styled"], vec!["style"]));
+    }
+
+    #[test]
+    fn test_filter_features_imported() {
+        let (mut resolve, world_id) = gated_world();
+        let module_imports =
+            BTreeSet::from([("test:pkg/logger".to_string(), "log-styled".to_string())]);
+        let err = filter_features(&mut resolve, world_id, &module_imports).unwrap_err();
+        assert_eq!(
+            err,
+            "unable to skip function `log-styled` of `test:pkg/logger`, which is gated by feature `fancy` but imported by the module"
+        );
+    }
+
+    #[test]
+    fn test_prune_types() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface types {
+                    record point { x: u32 }
+                    record line { start: point, end: point }
+                    record unused { x: u32 }
+                    resource canvas {}
+                    type shape = line;
+                }
+
+                interface drawing {
+                    use types.{shape, point};
+
+                    draw: func(shape: shape);
+                }
+
+                world pruned {
+                    use types.{point};
+
+                    import drawing;
+                    export origin: func() -> u32;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (world_id, _) = resolve.worlds.iter().next().expect("missing world");
+
+        let removed = prune_types(&mut resolve, &[world_id]);
+        assert_eq!(
+            removed,
+            [
+                ("test:pkg/types".to_string(), vec!["unused".to_string()]),
+                ("test:pkg/drawing".to_string(), vec!["point".to_string()]),
+                ("pruned".to_string(), vec!["point".to_string()]),
+            ]
+        );
+        let types = |name: &str| {
+            let (_, interface) = resolve
+                .interfaces
+                .iter()
+                .find(|(_, interface)| interface.name.as_deref() == Some(name))
+                .expect("missing interface");
+            interface
+                .types
+                .keys()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(types("types"), ["point", "line", "canvas", "shape"]);
+        assert_eq!(types("drawing"), ["shape"]);
+        assert_eq!(
+            names(&resolve, world_id),
+            ["test:pkg/types", "test:pkg/drawing", "origin"]
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//! Analyzed imports shared by the tests of the generators working on them,
+//! e.g. the mocks, stubs and WASI adapters.
+
+use crate::{
+    codegen::ir::{AnalyzedImports, AnalyzedInterface, InterfaceMethod, Parameter, WitReturn},
+    compat::wit_parser::{Function, FunctionKind, Type},
+    go::{GoIdentifier, GoType},
+};
+
+/// A parameter of an interface method.
+pub(crate) fn param(name: &str, go_type: GoType, wit_type: Type) -> Parameter {
+    Parameter {
+        name: GoIdentifier::private(name),
+        go_type,
+        wit_type,
+    }
+}
+
+/// The return type of an interface method.
+pub(crate) fn returning(go_type: GoType, wit_type: Type) -> Option<WitReturn> {
+    Some(WitReturn { go_type, wit_type })
+}
+
+/// A method of a freestanding imported function.
+pub(crate) fn method(
+    name: &str,
+    parameters: Vec<Parameter>,
+    return_type: Option<WitReturn>,
+) -> InterfaceMethod {
+    InterfaceMethod {
+        name: name.to_string(),
+        go_method_name: GoIdentifier::public(name),
+        parameters,
+        return_type,
+        wit_function: Function {
+            name: name.to_string(),
+            kind: FunctionKind::Freestanding,
+            params: vec![],
+            result: None,
+            docs: Default::default(),
+            stability: Default::default(),
+        },
+    }
+}
+
+/// An imported interface named `ITest<Name>`, registered as the given Wazero
+/// module.
+pub(crate) fn interface(
+    name: &str,
+    module: &str,
+    methods: Vec<InterfaceMethod>,
+) -> AnalyzedInterface {
+    AnalyzedInterface {
+        name: name.to_string(),
+        methods,
+        types: vec![],
+        go_interface_name: GoIdentifier::public(format!("i-test-{name}")),
+        resources_name: GoIdentifier::public(format!("test-{name}-resources")),
+        constructor_param_name: GoIdentifier::private(name),
+        wazero_module_name: module.to_string(),
+    }
+}
+
+/// The imports of a `test` world, with the given interfaces.
+pub(crate) fn analyzed(interfaces: Vec<AnalyzedInterface>) -> AnalyzedImports {
+    AnalyzedImports {
+        interfaces,
+        standalone_types: vec![],
+        exported_types: vec![],
+        factory_name: GoIdentifier::public("test-factory"),
+        instance_name: GoIdentifier::public("test-instance"),
+        constructor_name: GoIdentifier::public("new-test-factory"),
+        imports_name: GoIdentifier::public("test-imports"),
+        hooks_name: GoIdentifier::private("test-hooks"),
+    }
+}
@@ -1,23 +1,158 @@
-use std::mem;
+use std::{collections::BTreeSet, mem};
 
-use genco::prelude::*;
-use wit_bindgen_core::{
-    abi::{Bindgen, Instruction},
-    wit_parser::{Alignment, ArchitectureSize, Resolve, Result_, SizeAlign, Type},
+use crate::compat::{
+    abi::{Bindgen, Bitcast, Instruction, WasmType},
+    wit_component::StringEncoding,
+    wit_parser::{
+        Alignment, ArchitectureSize, Function, Handle, Resolve, Result_, SizeAlign, Type,
+        TypeDefKind, TypeId,
+    },
 };
+use genco::prelude::*;
 
 use crate::{
+    codegen::{
+        backend::{RuntimeBackend, Wazero, Width},
+        conversions::{Conversion, ConversionKind, LOWER_BUFFER, conversion},
+        dynamic::type_name,
+        helpers::{
+            CALL_IMPORT, ERROR_CONTEXTS, GUEST_ERROR, GUEST_TRAP, RESULT, VIEW_STRING, member,
+        },
+        panics::record_panic,
+        resources::{
+            borrow_name, guest_resource_rep, host_resource, host_resource_table, method_name,
+            own_name, resource_id, resource_name,
+        },
+        strings::{read_string_helper, write_string_helper},
+        support::Support,
+        variants,
+    },
     go::{
         GoIdentifier, GoResult, GoType, Operand, comment,
         imports::{
-            ERRORS_NEW, WAZERO_API_DECODE_F32, WAZERO_API_DECODE_F64, WAZERO_API_DECODE_I32,
-            WAZERO_API_DECODE_U32, WAZERO_API_ENCODE_F32, WAZERO_API_ENCODE_F64,
-            WAZERO_API_ENCODE_I32, WAZERO_API_ENCODE_U32,
+            ENCODING_BINARY_APPEND, ENCODING_BINARY_DECODE, ENCODING_BINARY_LITTLE_ENDIAN,
+            ERRORS_NEW, FMT_ERRORF, MATH_MAX_INT8, MATH_MAX_INT16, MATH_MAX_UINT8, MATH_MAX_UINT16,
+            MATH_MAX_UINT32, MATH_MIN_INT8, MATH_MIN_INT16, UNICODE_UTF8_VALID_RUNE,
         },
     },
-    resolve_type, resolve_wasm_type,
+    resolve_field_type, resolve_type, resolve_wasm_type, tuple_field,
 };
 
+/// The name of the function exported by guests to allocate memory.
+pub(crate) const REALLOC: &str = "cabi_realloc";
+
+/// The name of the instance field caching the core Wasm function exported
+/// with the given name, e.g. `fnCabiRealloc` for `cabi_realloc`.
+pub(crate) fn export_field(name: &str) -> GoIdentifier {
+    let words = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    GoIdentifier::private(format!("fn-{}", words.join("-")))
+}
+
+/// The instructions of the canonical ABI, and whether [`Func`] can emit them,
+/// as listed by `gravity features`.
+pub(crate) const INSTRUCTIONS: &[(&str, Support)] = &[
+    ("AsyncTaskReturn", Support::Unsupported),
+    ("Bitcasts", Support::Supported),
+    ("BoolFromI32", Support::Supported),
+    ("CallInterface", Support::Supported),
+    ("CallWasm", Support::Supported),
+    ("CharFromI32", Support::Supported),
+    ("ConstZero", Support::Supported),
+    ("CoreF32FromF32", Support::Supported),
+    ("CoreF64FromF64", Support::Supported),
+    ("DropHandle", Support::Supported),
+    ("EnumLift", Support::Unsupported),
+    ("EnumLower", Support::Supported),
+    ("ErrorContextLift", Support::Supported),
+    ("ErrorContextLower", Support::Supported),
+    ("F32FromCoreF32", Support::Supported),
+    ("F32Load", Support::Unsupported),
+    ("F32Store", Support::Unsupported),
+    ("F64FromCoreF64", Support::Supported),
+    ("F64Load", Support::Unsupported),
+    ("F64Store", Support::Unsupported),
+    ("FlagsLift", Support::Unsupported),
+    ("FlagsLower", Support::Unsupported),
+    ("Flush", Support::Supported),
+    ("FutureLift", Support::Unsupported),
+    ("FutureLower", Support::Unsupported),
+    ("GetArg", Support::Supported),
+    ("GuestDeallocate", Support::Unsupported),
+    ("GuestDeallocateList", Support::Unsupported),
+    ("GuestDeallocateString", Support::Unsupported),
+    ("GuestDeallocateVariant", Support::Unsupported),
+    ("HandleLift", Support::Supported),
+    ("HandleLower", Support::Supported),
+    ("I32Const", Support::Supported),
+    ("I32FromBool", Support::Supported),
+    ("I32FromChar", Support::Supported),
+    ("I32FromS16", Support::Supported),
+    ("I32FromS32", Support::Supported),
+    ("I32FromS8", Support::Supported),
+    ("I32FromU16", Support::Supported),
+    ("I32FromU32", Support::Supported),
+    ("I32FromU8", Support::Supported),
+    ("I32Load", Support::Supported),
+    ("I32Load16S", Support::Supported),
+    ("I32Load16U", Support::Supported),
+    ("I32Load8S", Support::Supported),
+    ("I32Load8U", Support::Supported),
+    ("I32Store", Support::Supported),
+    ("I32Store16", Support::Supported),
+    ("I32Store8", Support::Supported),
+    ("I64FromS64", Support::Supported),
+    ("I64FromU64", Support::Supported),
+    ("I64Load", Support::Supported),
+    ("I64Store", Support::Supported),
+    ("IterBasePointer", Support::Supported),
+    ("IterElem", Support::Supported),
+    ("LengthLoad", Support::Supported),
+    ("LengthStore", Support::Supported),
+    ("ListCanonLift", Support::Supported),
+    (
+        "ListCanonLower",
+        Support::Partial("only with a realloc function"),
+    ),
+    ("ListLift", Support::Supported),
+    (
+        "ListLower",
+        Support::Partial("only with a realloc function"),
+    ),
+    ("Malloc", Support::Unsupported),
+    ("OptionLift", Support::Supported),
+    ("OptionLower", Support::Partial("only of strings")),
+    ("PointerLoad", Support::Supported),
+    ("PointerStore", Support::Supported),
+    ("RecordLift", Support::Supported),
+    ("RecordLower", Support::Supported),
+    ("ResultLift", Support::Partial("only with string errors")),
+    ("ResultLower", Support::Partial("only with string errors")),
+    ("Return", Support::Supported),
+    ("S16FromI32", Support::Supported),
+    ("S32FromI32", Support::Supported),
+    ("S64FromI64", Support::Supported),
+    ("S8FromI32", Support::Supported),
+    ("StreamLift", Support::Unsupported),
+    ("StreamLower", Support::Unsupported),
+    ("StringLift", Support::Supported),
+    (
+        "StringLower",
+        Support::Partial("only with a realloc function"),
+    ),
+    ("TupleLift", Support::Supported),
+    ("TupleLower", Support::Supported),
+    ("U16FromI32", Support::Supported),
+    ("U32FromI32", Support::Supported),
+    ("U64FromI64", Support::Supported),
+    ("U8FromI32", Support::Supported),
+    ("VariantLift", Support::Supported),
+    ("VariantLower", Support::Supported),
+    ("VariantPayloadName", Support::Supported),
+];
+
 /// The direction of a function.
 ///
 /// Functions in the Component Model can be imported into a world or
@@ -30,67 +165,356 @@ enum Direction<'a> {
         param_name: &'a GoIdentifier,
     },
     /// The function is exported from the world.
-    #[allow(dead_code, reason = "halfway through refactor of func bindings")]
-    Export,
+    Export {
+        /// The name of the core Wasm export implementing the function.
+        ///
+        /// E.g. `hello` for a function exported by the world, or
+        /// `arcjet:example/greeter#hello` for one exported by an interface.
+        export_name: &'a str,
+    },
+}
+
+/// How errors encountered by the generated code are surfaced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorMode {
+    /// Return errors from functions whose result can carry one, and panic
+    /// otherwise.
+    #[default]
+    Panic,
+    /// Add a trailing `error` to the result of every exported function, so
+    /// errors are always returned rather than panicking.
+    Wrap,
 }
 
 pub struct Func<'a> {
     direction: Direction<'a>,
     args: Vec<String>,
+    /// The Go types of the arguments: the parameters of the Go function when
+    /// exported, or the core Wasm parameters of the host function when
+    /// imported.
+    params: Vec<GoType>,
+    /// The result in the signature of the Go function, which includes a
+    /// trailing `error` not in `wit_result` when errors are wrapped.
     result: GoResult,
+    /// The result of the WIT function.
+    wit_result: GoResult,
     tmp: usize,
     body: Tokens<Go>,
     block_storage: Vec<Tokens<Go>>,
     blocks: Vec<(Tokens<Go>, Vec<Operand>)>,
+    /// The ids of the lists whose elements are being lifted or lowered,
+    /// innermost last.
+    lists: Vec<usize>,
+    /// Whether the body of a host function uses the memory of the guest.
+    uses_memory: bool,
+    /// Whether the body of a host function uses the realloc function of
+    /// the guest.
+    uses_realloc: bool,
+    /// The name of the Go method called by an imported function.
+    method_name: Option<&'a GoIdentifier>,
+    /// The tuple returned by an exported function as multiple Go values.
+    multi_return: Option<TypeId>,
+    /// The resources implemented by the guest, whose methods are called with
+    /// the guest's representation of the resource rather than its handle.
+    guest_resources: BTreeSet<TypeId>,
+    /// The `cabi_post_*` function called once an exported function is done
+    /// with its results, if the guest exports one.
+    post_return: Option<&'a str>,
+    /// The encoding of the strings in the guest's memory.
+    string_encoding: StringEncoding,
+    /// Whether to check that core Wasm integers fit the types they're lifted
+    /// into, rather than truncating them.
+    checked_casts: bool,
+    /// Whether the calls of an imported function without results are
+    /// batched, i.e. queued until the export being called returns.
+    batched: bool,
+    /// Whether the UTF-8 strings passed to an imported function share the
+    /// guest's memory rather than being copied out of it.
+    string_views: bool,
+    /// The runtime the generated code accesses the guest with.
+    backend: &'a dyn RuntimeBackend,
+    /// Whether this is the body of a helper lifting or lowering a value,
+    /// which takes the memory and the realloc function of the guest.
+    helper: bool,
+    /// The code generated until the call of the function, and the return
+    /// area its result is stored in, if it's returned through memory.
+    return_area: Option<ReturnArea>,
     sizes: &'a SizeAlign,
 }
 
+/// The result of a function returned through memory, which is lifted or
+/// lowered by the helper of its type, if it has one, rather than by the code
+/// generated once the function is called.
+struct ReturnArea {
+    /// The body of the function until the call.
+    body: Tokens<Go>,
+    /// The pointer to the return area.
+    ptr: Option<Operand>,
+    /// The result of a host function, which is lowered into the return area.
+    value: Option<Operand>,
+}
+
 impl<'a> Func<'a> {
     /// Create a new exported function.
-    #[allow(dead_code, reason = "halfway through refactor of func bindings")]
-    pub fn export(result: GoResult, sizes: &'a SizeAlign) -> Self {
+    pub fn export(
+        export_name: &'a str,
+        params: Vec<GoType>,
+        result: GoResult,
+        sizes: &'a SizeAlign,
+    ) -> Self {
         Self {
-            direction: Direction::Export,
+            direction: Direction::Export { export_name },
             args: Vec::new(),
+            params,
+            wit_result: result.clone(),
             result,
             tmp: 0,
             body: Tokens::new(),
             block_storage: Vec::new(),
             blocks: Vec::new(),
+            lists: Vec::new(),
+            uses_memory: false,
+            uses_realloc: false,
+            method_name: None,
+            multi_return: None,
+            guest_resources: BTreeSet::new(),
+            post_return: None,
+            string_encoding: StringEncoding::UTF8,
+            checked_casts: false,
+            batched: false,
+            string_views: false,
+            backend: &Wazero,
+            helper: false,
+            return_area: None,
             sizes,
         }
     }
 
     /// Create a new exported function.
-    pub fn import(param_name: &'a GoIdentifier, result: GoResult, sizes: &'a SizeAlign) -> Self {
+    pub fn import(
+        param_name: &'a GoIdentifier,
+        params: Vec<GoType>,
+        result: GoResult,
+        sizes: &'a SizeAlign,
+    ) -> Self {
         Self {
             direction: Direction::Import { param_name },
             args: Vec::new(),
+            params,
+            wit_result: result.clone(),
             result,
             tmp: 0,
             body: Tokens::new(),
             block_storage: Vec::new(),
             blocks: Vec::new(),
+            lists: Vec::new(),
+            uses_memory: false,
+            uses_realloc: false,
+            method_name: None,
+            multi_return: None,
+            guest_resources: BTreeSet::new(),
+            post_return: None,
+            string_encoding: StringEncoding::UTF8,
+            checked_casts: false,
+            batched: false,
+            string_views: false,
+            backend: &Wazero,
+            helper: false,
+            return_area: None,
             sizes,
         }
     }
 
+    /// Create the body of a helper lifting or lowering a value, which returns
+    /// its errors as the given result.
+    pub(crate) fn helper(result: GoResult, sizes: &'a SizeAlign) -> Self {
+        Self {
+            helper: true,
+            ..Self::export("", Vec::new(), result, sizes)
+        }
+    }
+
+    /// Sets the name of the Go method called by an imported function, when it
+    /// differs from the one derived from the WIT function.
+    pub fn with_method_name(mut self, name: &'a GoIdentifier) -> Self {
+        self.method_name = Some(name);
+        self
+    }
+
+    /// Returns the tuple returned by an exported function as multiple Go
+    /// values, rather than as the struct of the named tuple.
+    pub fn with_multi_return(mut self, tuple: TypeId) -> Self {
+        self.multi_return = Some(tuple);
+        self
+    }
+
+    /// Sets the resources implemented by the guest, i.e. those of the
+    /// interfaces exported by the world.
+    pub fn with_guest_resources(mut self, resources: BTreeSet<TypeId>) -> Self {
+        self.guest_resources = resources;
+        self
+    }
+
+    /// Sets the `cabi_post_*` function cleaning up the results of an exported
+    /// function, which is called once these are lifted.
+    pub fn with_post_return(mut self, name: &'a str) -> Self {
+        self.post_return = Some(name);
+        self
+    }
+
+    /// Sets the encoding of the strings in the guest's memory, which is UTF-8
+    /// by default.
+    pub fn with_string_encoding(mut self, encoding: StringEncoding) -> Self {
+        self.string_encoding = encoding;
+        self
+    }
+
+    /// Checks that the core Wasm integers fit the types they're lifted into,
+    /// failing with an error rather than truncating them.
+    pub fn with_checked_casts(mut self, checked_casts: bool) -> Self {
+        self.checked_casts = checked_casts;
+        self
+    }
+
+    /// Sets whether the calls of an imported function without results are
+    /// queued until the export being called returns, when it batches them.
+    pub fn with_batched(mut self, batched: bool) -> Self {
+        self.batched = batched;
+        self
+    }
+
+    /// Sets whether the UTF-8 strings passed to an imported function are
+    /// views of the guest's memory, which are only valid until it returns.
+    ///
+    /// Batched calls always copy their strings, as they're made once the
+    /// guest may have reused the memory.
+    pub fn with_string_views(mut self, string_views: bool) -> Self {
+        self.string_views = string_views;
+        self
+    }
+
+    /// Sets the runtime the generated code accesses the guest with, which is
+    /// Wazero by default.
+    pub fn with_backend(mut self, backend: &'a dyn RuntimeBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Sets how errors are surfaced by an exported function.
+    pub fn with_error_mode(mut self, mode: ErrorMode) -> Self {
+        self.result = match (mode, &self.wit_result) {
+            (ErrorMode::Panic, _) => self.wit_result.clone(),
+            (ErrorMode::Wrap, GoResult::Empty) => GoResult::Anon(GoType::Error),
+            (ErrorMode::Wrap, GoResult::Anon(typ @ (GoType::Error | GoType::ValueOrError(_)))) => {
+                GoResult::Anon(typ.clone())
+            }
+            (ErrorMode::Wrap, GoResult::Anon(typ)) => {
+                GoResult::Anon(GoType::ValueOrError(Box::new(typ.clone())))
+            }
+        };
+        self
+    }
+
+    /// Returns true if the resource a type refers to is implemented by the
+    /// guest.
+    fn is_guest_resource(&self, resolve: &Resolve, id: TypeId) -> bool {
+        self.guest_resources.contains(&resource_id(resolve, id))
+    }
+
+    /// The table of the handles the factory gave guests to the resource
+    /// implemented by the host, which instances reach through their factory.
+    fn host_resource_table(&self, name: &str) -> Tokens<Go> {
+        match self.direction {
+            Direction::Export { .. } => host_resource_table(name, Some(quote!(i.factory))),
+            Direction::Import { .. } => host_resource_table(name, None),
+        }
+    }
+
+    /// Returns true if the signature has a trailing `error` which isn't part
+    /// of the WIT result.
+    fn wraps_error(&self) -> bool {
+        self.result != self.wit_result
+    }
+
     fn tmp(&mut self) -> usize {
         let ret = self.tmp;
         self.tmp += 1;
         ret
     }
 
+    /// Converts a lifted option or result, i.e. its value and whether it's
+    /// set or its error, to the pointer or `Result` it's represented by when
+    /// nested in another value, see [`resolve_field_type`], returning the
+    /// code converting it.
+    ///
+    /// Other operands are returned as is.
+    fn nested(&mut self, op: &Operand) -> (Tokens<Go>, Operand) {
+        match op {
+            Operand::MultiValue((value, ok), GoType::ValueOrOk(typ)) => {
+                let ptr = &format!("ptr{}", self.tmp());
+                let code = quote! {
+                    var $ptr *$(typ.as_ref())
+                    if $ok {
+                        $ptr = &$value
+                    }
+                };
+                (
+                    code,
+                    Operand::SingleValue(ptr.into(), GoType::Pointer(typ.clone())),
+                )
+            }
+            Operand::MultiValue((value, err), GoType::ValueOrError(typ)) => {
+                let result = &format!("nested{}", self.tmp());
+                let code = quote! {
+                    $result := $RESULT[$(typ.as_ref())]{Value: $value, Err: $err}
+                };
+                (
+                    code,
+                    Operand::SingleValue(result.into(), GoType::Result(typ.clone())),
+                )
+            }
+            _ => (Tokens::new(), op.clone()),
+        }
+    }
+
     pub fn args(&self) -> &[String] {
         &self.args
     }
 
+    /// The result in the signature of the Go function.
     pub fn result(&self) -> &GoResult {
         &self.result
     }
 
-    pub fn body(&self) -> &Tokens<Go> {
-        &self.body
+    /// Returns true if the results are cleaned up after a call, with the
+    /// error of the cleanup returned by the function.
+    fn returns_cleanup_error(&self) -> bool {
+        self.post_return.is_some() && self.result.values().1
+    }
+
+    /// The result as declared by the Go function, which names the trailing
+    /// `error` when the cleanup of the results sets it once the function
+    /// returns.
+    pub fn signature(&self) -> Tokens<Go> {
+        if !self.returns_cleanup_error() {
+            return quote!($(&self.result));
+        }
+        let (values, _) = self.result.values();
+        let results = values
+            .iter()
+            .map(|(_, typ)| quote!(_ $typ))
+            .chain([quote!(err error)]);
+        quote!(($(for result in results join (, ) => $result)))
+    }
+
+    /// The body of the Go function, preceded by the lookups it uses.
+    pub fn body(&self) -> Tokens<Go> {
+        let module = &quote!(mod);
+        quote! {
+            $(if self.uses_memory => memory := $(self.backend.memory(module))$['\r'])
+            $(if self.uses_realloc => realloc := $(self.backend.function(module, quote!($(quoted(REALLOC)))))$['\r'])
+            $(&self.body)
+        }
     }
 
     fn push_arg(&mut self, value: &str) {
@@ -100,6 +524,408 @@ impl<'a> Func<'a> {
     fn pop_block(&mut self) -> (Tokens<Go>, Vec<Operand>) {
         self.blocks.pop().expect("should have block to pop")
     }
+
+    /// The memory of the guest.
+    ///
+    /// Exported functions use the memory looked up by the instance, while
+    /// host functions look it up once at the start of the function.
+    fn memory(&mut self) -> Tokens<Go> {
+        match self.direction {
+            _ if self.helper => quote!(memory),
+            Direction::Export { .. } => quote!(i.memory),
+            Direction::Import { .. } => {
+                self.uses_memory = true;
+                quote!(memory)
+            }
+        }
+    }
+
+    /// The realloc function of the guest with the given name, which is
+    /// looked up once like the memory.
+    fn realloc(&mut self, name: &str) -> Tokens<Go> {
+        match self.direction {
+            _ if self.helper => quote!(realloc),
+            Direction::Export { .. } if name == REALLOC => quote!(i.$(export_field(name))),
+            Direction::Export { .. } => self
+                .backend
+                .function(&quote!(i.module), quote!($(quoted(name)))),
+            Direction::Import { .. } if name == REALLOC => {
+                self.uses_realloc = true;
+                quote!(realloc)
+            }
+            Direction::Import { .. } => {
+                self.backend.function(&quote!(mod), quote!($(quoted(name))))
+            }
+        }
+    }
+
+    /// Generates a store of `value` at `offset` from `ptr`.
+    ///
+    /// Stores into the elements of a list being lowered are written to the
+    /// buffer of the list, which is copied into the memory with a single
+    /// write once every element is lowered, as are the stores of the helpers
+    /// lowering a value into the buffer they're given. Other stores are
+    /// written to the memory directly.
+    fn store(
+        &mut self,
+        store: Width,
+        ptr: &Operand,
+        offset: usize,
+        value: Tokens<Go>,
+    ) -> Tokens<Go> {
+        let buffer = self
+            .lists
+            .iter()
+            .find(|id| ptr.as_string() == format!("base{id}"))
+            .map(|id| (format!("buf{id}"), quote!($ptr+$offset)))
+            .or_else(|| {
+                (self.helper && ptr.as_string() == LOWER_BUFFER)
+                    .then(|| (LOWER_BUFFER.to_string(), quote!($offset)))
+            });
+        match (store, buffer) {
+            (Width::U8, Some((buf, index))) => quote!($buf[$index] = $value),
+            (Width::U16, Some((buf, index))) => {
+                quote!($ENCODING_BINARY_LITTLE_ENDIAN.PutUint16($buf[$index:], $value))
+            }
+            (Width::U32, Some((buf, index))) => {
+                quote!($ENCODING_BINARY_LITTLE_ENDIAN.PutUint32($buf[$index:], $value))
+            }
+            (Width::U64, Some((buf, index))) => {
+                quote!($ENCODING_BINARY_LITTLE_ENDIAN.PutUint64($buf[$index:], $value))
+            }
+            (width, None) => {
+                let memory = &self.memory();
+                let addr = quote!($(ptr.convert(&GoType::Uint32))+$offset);
+                let store = self.backend.store(memory, width, addr, value);
+                match self.direction {
+                    Direction::Export { .. } => store,
+                    // The results of host functions are written to the
+                    // return area given by the guest, which traps if it's
+                    // out of bounds rather than leaving the results unset
+                    Direction::Import { .. } => quote! {
+                        if !$store {
+                            panic($ERRORS_NEW("failed to write result to memory"))
+                        }
+                    },
+                }
+            }
+        }
+    }
+
+    /// Generates a load of the integer at `offset` from `ptr`, returning the
+    /// integer and whether it's in bounds.
+    fn load(&mut self, width: Width, ptr: &Operand, offset: usize) -> Tokens<Go> {
+        let memory = &self.memory();
+        self.backend.load(
+            memory,
+            width,
+            quote!($(ptr.convert(&GoType::Uint32)) + $offset),
+        )
+    }
+
+    /// Returns the helper lifting or lowering the values of the type stored
+    /// in memory, if it has one.
+    ///
+    /// The strings viewed by host functions, rather than copied, are lifted
+    /// inline.
+    fn conversion(
+        &self,
+        resolve: &Resolve,
+        kind: ConversionKind,
+        typ: &Type,
+    ) -> Option<Conversion> {
+        let views = matches!(self.direction, Direction::Import { .. })
+            && self.string_views
+            && !self.batched;
+        if kind == ConversionKind::Lift && views {
+            return None;
+        }
+        conversion(resolve, kind, typ, self.string_encoding)
+    }
+
+    /// Generates the call of the helper lifting the value at `ptr`, returning
+    /// the code along with the lifted value.
+    fn call_lift(&mut self, conversion: &Conversion, ptr: Tokens<Go>) -> (Tokens<Go>, Operand) {
+        let tmp = self.tmp();
+        let value = format!("value{tmp}");
+        let err = &format!("err{tmp}");
+        let default = &format!("default{tmp}");
+        let memory = &self.memory();
+        let lifted = match &conversion.go_type {
+            typ @ GoType::ValueOrOk(_) => {
+                Operand::MultiValue((value, format!("ok{tmp}")), typ.clone())
+            }
+            typ => Operand::SingleValue(value, typ.clone()),
+        };
+        let code = quote! {
+            $(&lifted), $err := $(&conversion.name)($memory, $ptr)
+            $(check_error(&self.direction, &self.result, quote!($err != nil), quote!($err), default))
+        };
+        (code, lifted)
+    }
+
+    /// Generates the call of the helper lowering `value` into `buf`, with the
+    /// realloc function of the guest with the given name.
+    fn call_lower(
+        &mut self,
+        conversion: &Conversion,
+        buf: Tokens<Go>,
+        value: &Operand,
+        realloc: &str,
+    ) -> Tokens<Go> {
+        let name = &conversion.name;
+        let args = if conversion.allocates {
+            let memory = &self.memory();
+            let realloc = &self.realloc(realloc);
+            quote!(ctx, $memory, $realloc, $buf, $value)
+        } else {
+            quote!($buf, $value)
+        };
+        if !conversion.fallible {
+            return quote!($name($args));
+        }
+        let tmp = self.tmp();
+        let err = &format!("err{tmp}");
+        let default = &format!("default{tmp}");
+        quote! {
+            $err := $name($args)
+            $(check_error(&self.direction, &self.result, quote!($err != nil), quote!($err), default))
+        }
+    }
+
+    /// Lifts the result of an exported function from its return area, or
+    /// lowers the result of a host function into it, with the helper of its
+    /// type, replacing the code generated since the call, if the function
+    /// returns its result through memory and the type has a helper.
+    fn convert_return_area(
+        &mut self,
+        resolve: &Resolve,
+        func: &Function,
+        operands: &mut [Operand],
+    ) {
+        let Some(ReturnArea {
+            body,
+            ptr: Some(ptr),
+            value,
+        }) = self.return_area.take()
+        else {
+            return;
+        };
+        let Some(typ) = &func.result else {
+            return;
+        };
+        let ptr = ptr.convert(&GoType::Uint32);
+        match (&self.direction, value) {
+            (Direction::Export { .. }, _) => {
+                let Some(conversion) = self.conversion(resolve, ConversionKind::Lift, typ) else {
+                    return;
+                };
+                self.body = body;
+                let (code, lifted) = self.call_lift(&conversion, ptr);
+                quote_in! { self.body =>
+                    $['\r']
+                    $code
+                };
+                operands[0] = lifted;
+            }
+            (Direction::Import { .. }, Some(value)) => {
+                let Some(conversion) = self.conversion(resolve, ConversionKind::Lower, typ) else {
+                    return;
+                };
+                self.body = body;
+                // Options are lowered from the pointers they're nested as
+                let (convert, value) = self.nested(&value);
+                let tmp = self.tmp();
+                let buf = &format!("buf{tmp}");
+                let size = self.sizes.size(typ).size_wasm32();
+                let lower = self.call_lower(&conversion, quote!($buf), &value, REALLOC);
+                let memory = &self.memory();
+                quote_in! { self.body =>
+                    $['\r']
+                    $convert
+                    $buf := make([]byte, $size)
+                    $lower
+                    if !$(self.backend.write(memory, ptr, quote!($buf))) {
+                        panic($ERRORS_NEW("failed to write result to memory"))
+                    }
+                };
+            }
+            (Direction::Import { .. }, None) => (),
+        }
+    }
+}
+
+/// Returns the handle a type refers to, following any aliases, or `None` if
+/// it isn't a handle, e.g. a future or a stream.
+fn handle_of(resolve: &Resolve, typ: &Type) -> Option<Handle> {
+    let Type::Id(id) = typ else {
+        return None;
+    };
+    match &resolve.types[*id].kind {
+        TypeDefKind::Handle(handle) => Some(*handle),
+        TypeDefKind::Type(typ) => handle_of(resolve, typ),
+        _ => None,
+    }
+}
+
+/// Generates the statements returning `err` from a function with the given
+/// result, or panicking if the result can't carry an error.
+fn return_error(result: &GoResult, err: Tokens<Go>, default: &str) -> Tokens<Go> {
+    match result {
+        GoResult::Anon(GoType::ValueOrError(typ)) => match typ.as_ref() {
+            GoType::ValueOrOk(typ) => quote! {
+                var $default $(typ.as_ref())
+                return $default, false, $err
+            },
+            typ => quote! {
+                var $default $typ
+                return $default, $err
+            },
+        },
+        GoResult::Anon(GoType::Error) => quote!(return $err),
+        GoResult::Anon(_) | GoResult::Empty => {
+            record_panic("an error is encountered, as its result can't carry it");
+            quote! {
+                $(comment(&["The return type doesn't contain an error so we panic if one is encountered"]))
+                panic($err)
+            }
+        }
+    }
+}
+
+/// Converts a Go value of the given type between the types of core Wasm
+/// values, whose integers are unsigned and whose pointers and lengths are
+/// `uint64`, returning the converted value and its type.
+fn bitcast(
+    backend: &dyn RuntimeBackend,
+    cast: &Bitcast,
+    value: Tokens<Go>,
+    typ: Option<GoType>,
+) -> (Tokens<Go>, Option<GoType>) {
+    // Values are only converted when their types differ
+    let convert = |value: Tokens<Go>, to: GoType| match &typ {
+        Some(typ) if *typ == to => (value, Some(to)),
+        _ => (quote!($(&to)($value)), Some(to)),
+    };
+    match cast {
+        Bitcast::None
+        | Bitcast::P64ToI64
+        | Bitcast::I64ToP64
+        | Bitcast::P64ToP
+        | Bitcast::PToP64
+        | Bitcast::PToL
+        | Bitcast::LToP
+        | Bitcast::I64ToL
+        | Bitcast::LToI64 => (value, typ),
+        Bitcast::I32ToI64 | Bitcast::I32ToP | Bitcast::I32ToL => convert(value, GoType::Uint64),
+        Bitcast::I64ToI32 | Bitcast::PToI32 | Bitcast::LToI32 => convert(value, GoType::Uint32),
+        Bitcast::F32ToI32 => bitcast(
+            backend,
+            &Bitcast::I64ToI32,
+            backend.encode_f32(value),
+            Some(backend.value_type()),
+        ),
+        Bitcast::F32ToI64 => (backend.encode_f32(value), Some(backend.value_type())),
+        Bitcast::F64ToI64 => (backend.encode_f64(value), Some(backend.value_type())),
+        Bitcast::I32ToF32 | Bitcast::I64ToF32 => {
+            let (value, _) = convert(value, backend.value_type());
+            (backend.decode_f32(value), Some(GoType::Float32))
+        }
+        Bitcast::I64ToF64 => {
+            let (value, _) = convert(value, backend.value_type());
+            (backend.decode_f64(value), Some(GoType::Float64))
+        }
+        Bitcast::Sequence(casts) => {
+            let [first, second] = casts.as_ref();
+            let (value, typ) = bitcast(backend, first, value, typ);
+            bitcast(backend, second, value, typ)
+        }
+    }
+}
+
+/// Generates the check returning `err` from a function with the given result
+/// if `cond` holds, or panicking if the result can't carry an error.
+fn handle_error(result: &GoResult, cond: Tokens<Go>, err: Tokens<Go>, default: &str) -> Tokens<Go> {
+    match result {
+        GoResult::Anon(GoType::ValueOrError(_) | GoType::Error) => quote! {
+            if $cond {
+                $(return_error(result, err, default))
+            }
+        },
+        GoResult::Anon(_) | GoResult::Empty => {
+            record_panic("an error is encountered, as its result can't carry it");
+            quote! {
+                $(comment(&["The return type doesn't contain an error so we panic if one is encountered"]))
+                if $cond {
+                    panic($err)
+                }
+            }
+        }
+    }
+}
+
+/// Returns true for the core Wasm types of 32-bit integers, which Wazero
+/// passes around as `uint64`.
+fn is_i32(typ: &WasmType) -> bool {
+    matches!(typ, WasmType::I32 | WasmType::Pointer | WasmType::Length)
+}
+
+/// The condition under which the integer doesn't fit the type it's lifted
+/// into, if it can be out of its range.
+///
+/// The core Wasm `i32`s held in a `uint64` have their upper bits cleared, so
+/// the signed integers narrower than 32 bits are checked on their `int32`.
+fn out_of_range(operand: &Operand, typ: &GoType) -> Option<Tokens<Go>> {
+    let wide = matches!(operand.typ(), Some(GoType::Uint64));
+    let as_int32 = match operand.typ() {
+        Some(GoType::Int32) => quote!($operand),
+        _ => quote!(int32($operand)),
+    };
+    let signed = |min, max| {
+        let range = quote!($(&as_int32) < $min || $(&as_int32) > $max);
+        if wide {
+            quote!($operand > $MATH_MAX_UINT32 || $range)
+        } else {
+            range
+        }
+    };
+    match (operand.typ()?, typ) {
+        (GoType::Uint64 | GoType::Uint32 | GoType::Int32, GoType::Uint8) => {
+            Some(quote!($(operand.convert(&GoType::Uint64)) > $MATH_MAX_UINT8))
+        }
+        (GoType::Uint64 | GoType::Uint32 | GoType::Int32, GoType::Uint16) => {
+            Some(quote!($(operand.convert(&GoType::Uint64)) > $MATH_MAX_UINT16))
+        }
+        (GoType::Uint64, GoType::Uint32 | GoType::Int32) => {
+            Some(quote!($operand > $MATH_MAX_UINT32))
+        }
+        (GoType::Uint64 | GoType::Uint32 | GoType::Int32, GoType::Int8) => {
+            Some(signed(MATH_MIN_INT8, MATH_MAX_INT8))
+        }
+        (GoType::Uint64 | GoType::Uint32 | GoType::Int32, GoType::Int16) => {
+            Some(signed(MATH_MIN_INT16, MATH_MAX_INT16))
+        }
+        _ => None,
+    }
+}
+
+/// Like [`handle_error`], but host functions always panic, which Wazero turns
+/// into a trap of the guest, as their results can't carry the error.
+fn check_error(
+    direction: &Direction,
+    result: &GoResult,
+    cond: Tokens<Go>,
+    err: Tokens<Go>,
+    default: &str,
+) -> Tokens<Go> {
+    match direction {
+        Direction::Export { .. } => handle_error(result, cond, err, default),
+        Direction::Import { .. } => quote! {
+            if $cond {
+                panic($err)
+            }
+        },
+    }
 }
 
 impl Bindgen for Func<'_> {
@@ -113,13 +939,19 @@ impl Bindgen for Func<'_> {
         results: &mut Vec<Self::Operand>,
     ) {
         let iter_element = "e";
-        let iter_base = "base";
 
         match inst {
             Instruction::GetArg { nth } => {
                 let arg = &format!("arg{nth}");
                 self.push_arg(arg);
-                results.push(Operand::SingleValue(arg.into()));
+                let typ = self.params[*nth].clone();
+                let arg = Operand::SingleValue(arg.into(), typ);
+                // The only argument of a host function got once it's called
+                // is the pointer to its return area
+                if let Some(area) = &mut self.return_area {
+                    area.ptr.get_or_insert_with(|| arg.clone());
+                }
+                results.push(arg);
             }
             Instruction::ConstZero { tys } => {
                 for _ in tys.iter() {
@@ -135,118 +967,146 @@ impl Bindgen for Func<'_> {
                 let len = &format!("len{tmp}");
                 let err = &format!("err{tmp}");
                 let default = &format!("default{tmp}");
-                let memory = &format!("memory{tmp}");
-                let realloc = &format!("realloc{tmp}");
+                let memory = &self.memory();
+                let realloc = &self.realloc(realloc_name);
                 let operand = &operands[0];
+                let write = write_string_helper(self.string_encoding);
                 match self.direction {
-                    Direction::Export => {
+                    Direction::Export { .. } => {
                         quote_in! { self.body =>
                             $['\r']
-                            $memory := i.module.Memory()
-                            $realloc := i.module.ExportedFunction($(quoted(*realloc_name)))
-                            $ptr, $len, $err := writeString(ctx, $operand, $memory, $realloc)
-                            $(match &self.result {
-                                GoResult::Anon(GoType::ValueOrError(typ)) => {
-                                    if $err != nil {
-                                        var $default $(typ.as_ref())
-                                        return $default, $err
-                                    }
-                                }
-                                GoResult::Anon(GoType::Error) => {
-                                    if $err != nil {
-                                        return $err
-                                    }
-                                }
-                                GoResult::Anon(_) | GoResult::Empty => {
-                                    $(comment(&["The return type doesn't contain an error so we panic if one is encountered"]))
-                                    if $err != nil {
-                                        panic($err)
-                                    }
-                                }
-                            })
+                            $ptr, $len, $err := $write(ctx, $operand, $memory, $realloc)
+                            $(handle_error(&self.result, quote!($err != nil), quote!($err), default))
                         }
                     }
                     Direction::Import { .. } => {
                         quote_in! { self.body =>
                             $['\r']
-                            $memory := mod.Memory()
-                            $realloc := mod.ExportedFunction($(quoted(*realloc_name)))
-                            $ptr, $len, $err := writeString(ctx, $operand, $memory, $realloc)
+                            $ptr, $len, $err := $write(ctx, $operand, $memory, $realloc)
                             if $err != nil {
                                 panic($err)
                             }
                         };
                     }
                 }
-                results.push(Operand::SingleValue(ptr.into()));
-                results.push(Operand::SingleValue(len.into()));
+                results.push(Operand::SingleValue(ptr.into(), GoType::Uint64));
+                results.push(Operand::SingleValue(len.into(), GoType::Uint64));
             }
-            Instruction::CallWasm { name, .. } => {
+            Instruction::CallWasm { sig, .. } => {
+                let Direction::Export { export_name: name } = self.direction else {
+                    unimplemented!("CallWasm is only used for exported functions")
+                };
                 let tmp = self.tmp();
                 let raw = &format!("raw{tmp}");
-                let ret = &format!("results{tmp}");
+                // Guests returning multiple core values, rather than through a
+                // return pointer, have a result per value
+                let rets = match sig.results.len() {
+                    0 | 1 => vec![format!("results{tmp}")],
+                    n => (0..n).map(|i| format!("results{tmp}_{i}")).collect(),
+                };
                 let err = &format!("err{tmp}");
                 let default = &format!("default{tmp}");
-                // TODO(#17): Wrapping every argument in `uint64` is bad and we should instead be looking
-                // at the types and converting with proper guards in place
-                quote_in! { self.body =>
-                    $['\r']
-                    $(match &self.result {
-                        GoResult::Anon(GoType::ValueOrError(typ)) => {
-                            $raw, $err := i.module.ExportedFunction($(quoted(*name))).Call(ctx, $(for op in operands.iter() join (, ) => uint64($op)))
-                            if $err != nil {
-                                var $default $(typ.as_ref())
-                                return $default, $err
+                let args = operands.iter().zip(&sig.params).map(|(op, typ)| {
+                    self.backend
+                        .arg(typ, op.convert(&self.backend.go_type(typ)))
+                });
+                let call = self.backend.call(
+                    &quote!(i.$(export_field(name))),
+                    quote!($(for arg in args join (, ) => $arg)),
+                );
+                let cleanup = self.post_return.map(|post_return| {
+                    let args = match &self.wit_result {
+                        GoResult::Anon(_) => quote!($raw...),
+                        GoResult::Empty => quote!(),
+                    };
+                    let call = self
+                        .backend
+                        .call(&quote!(i.$(export_field(post_return))), args);
+                    if self.returns_cleanup_error() {
+                        quote! {
+                            if _, cleanupErr := $call; cleanupErr != nil && err == nil {
+                                $(comment(&[
+                                    "The error of the cleanup is returned through the named",
+                                    "result, unless the function already returns one",
+                                ]))
+                                err = $GUEST_TRAP($(quoted(post_return)), cleanupErr)
                             }
                         }
-                        GoResult::Anon(GoType::Error) => {
-                            $raw, $err := i.module.ExportedFunction($(quoted(*name))).Call(ctx, $(for op in operands.iter() join (, ) => uint64($op)))
-                            if $err != nil {
-                                return $err
+                    } else {
+                        record_panic(
+                            "the cleanup of its results fails, as its result can't carry the error",
+                        );
+                        quote! {
+                            if _, err := $call; err != nil {
+                                $(comment(&[
+                                    "If we get an error during cleanup, something really bad is",
+                                    "going on, so we panic. Also, you can't return the error from",
+                                    "the `defer`"
+                                ]))
+                                panic($ERRORS_NEW("failed to cleanup"))
                             }
                         }
+                    }
+                });
+                quote_in! { self.body =>
+                    $['\r']
+                    $(match &self.wit_result {
                         GoResult::Anon(_) => {
-                            $raw, $err := i.module.ExportedFunction($(quoted(*name))).Call(ctx, $(for op in operands.iter() join (, ) => uint64($op)))
-                            $(comment(&["The return type doesn't contain an error so we panic if one is encountered"]))
-                            if $err != nil {
-                                panic($err)
-                            }
+                            $raw, $err := $call
                         }
                         GoResult::Empty => {
-                            _, $err := i.module.ExportedFunction($(quoted(*name))).Call(ctx, $(for op in operands.iter() join (, ) => uint64($op)))
-                            $(comment(&["The return type doesn't contain an error so we panic if one is encountered"]))
-                            if $err != nil {
-                                panic($err)
-                            }
+                            _, $err := $call
                         }
                     })
+                    $(handle_error(&self.result, quote!($err != nil), quote!($GUEST_TRAP($(quoted(name)), $err)), default))
 
-                    $(if self.result.needs_cleanup() {
+                    $(if let Some(cleanup) = cleanup {
                         $(comment(&[
                             "The cleanup via `cabi_post_*` cleans up the memory in the guest. By",
                             "deferring this, we ensure that no memory is corrupted before the function",
                             "is done accessing it."
                         ]))
                         defer func() {
-                            if _, err := i.module.ExportedFunction($(quoted(format!("cabi_post_{name}")))).Call(ctx, $raw...); err != nil {
-                                $(comment(&[
-                                    "If we get an error during cleanup, something really bad is",
-                                    "going on, so we panic. Also, you can't return the error from",
-                                    "the `defer`"
-                                ]))
-                                panic($ERRORS_NEW("failed to cleanup"))
-                            }
+                            $cleanup
                         }()
                     })
 
-                    $(match &self.result {
-                        GoResult::Anon(_) => $ret := $raw[0],
+                    $(if self.checked_casts && matches!(self.wit_result, GoResult::Anon(_)) {
+                        $(for (i, _) in sig.results.iter().enumerate().filter(|(_, typ)| is_i32(typ)) join ($['\r']) =>
+                            $(handle_error(
+                                &self.result,
+                                quote!($raw[$i] > $MATH_MAX_UINT32),
+                                quote!($FMT_ERRORF($(quoted(format!("result {i} out of range for i32: %d"))), $raw[$i])),
+                                default,
+                            ))
+                        )
+                    })
+
+                    $(match &self.wit_result {
+                        GoResult::Anon(_) if sig.results.is_empty() => $(&rets[0]) := $raw[0],
+                        GoResult::Anon(_) => $(for (i, (ret, typ)) in rets.iter().zip(&sig.results).enumerate() join ($['\r']) =>
+                            $ret := $(self.backend.result(typ, quote!($raw[$i])))
+                        ),
                         GoResult::Empty => (),
                     })
                 };
-                match self.result {
+                match self.wit_result {
                     GoResult::Empty => (),
-                    GoResult::Anon(_) => results.push(Operand::SingleValue(ret.into())),
+                    GoResult::Anon(_) if sig.results.is_empty() => results.push(
+                        Operand::SingleValue(rets[0].clone(), self.backend.value_type()),
+                    ),
+                    GoResult::Anon(_) => {
+                        for (ret, typ) in rets.into_iter().zip(&sig.results) {
+                            results.push(Operand::SingleValue(ret, self.backend.go_type(typ)));
+                        }
+                    }
+                }
+                if sig.retptr {
+                    self.return_area = Some(ReturnArea {
+                        body: self.body.clone(),
+                        ptr: results.last().cloned(),
+                        value: None,
+                    });
                 }
             }
             Instruction::I32Load8U { offset } => {
@@ -256,31 +1116,13 @@ impl Bindgen for Func<'_> {
                 let value = &format!("value{tmp}");
                 let ok = &format!("ok{tmp}");
                 let default = &format!("default{tmp}");
-                let operand = &operands[0];
+                let load = self.load(Width::U8, &operands[0], offset);
                 quote_in! { self.body =>
                     $['\r']
-                    $value, $ok := i.module.Memory().ReadByte(uint32($operand + $offset))
-                    $(match &self.result {
-                        GoResult::Anon(GoType::ValueOrError(typ)) => {
-                            if !$ok {
-                                var $default $(typ.as_ref())
-                                return $default, $ERRORS_NEW("failed to read byte from memory")
-                            }
-                        }
-                        GoResult::Anon(GoType::Error) => {
-                            if !$ok {
-                                return $ERRORS_NEW("failed to read byte from memory")
-                            }
-                        }
-                        GoResult::Anon(_) | GoResult::Empty => {
-                            $(comment(&["The return type doesn't contain an error so we panic if one is encountered"]))
-                            if !$ok {
-                                panic($ERRORS_NEW("failed to read byte from memory"))
-                            }
-                        }
-                    })
+                    $value, $ok := $load
+                    $(handle_error(&self.result, quote!(!$ok), quote!($ERRORS_NEW("failed to read byte from memory")), default))
                 };
-                results.push(Operand::SingleValue(value.into()));
+                results.push(Operand::SingleValue(value.into(), Width::U8.go_type()));
             }
             Instruction::I32FromBool => {
                 let tmp = self.tmp();
@@ -295,7 +1137,7 @@ impl Bindgen for Func<'_> {
                         $(&value) = 0
                     }
                 }
-                results.push(Operand::SingleValue(value))
+                results.push(Operand::SingleValue(value, GoType::Uint32))
             }
             Instruction::BoolFromI32 => {
                 let tmp = self.tmp();
@@ -305,7 +1147,7 @@ impl Bindgen for Func<'_> {
                     $['\r']
                     $(&value) := $operand != 0
                 }
-                results.push(Operand::SingleValue(value))
+                results.push(Operand::SingleValue(value, GoType::Bool))
             }
             Instruction::I32FromU32 => {
                 let tmp = self.tmp();
@@ -313,19 +1155,12 @@ impl Bindgen for Func<'_> {
                 let operand = &operands[0];
                 quote_in! { self.body =>
                     $['\r']
-                    $result := $WAZERO_API_ENCODE_U32($operand)
+                    $result := $(self.backend.encode_u32(quote!($operand)))
                 };
-                results.push(Operand::SingleValue(result.into()));
-            }
-            Instruction::U32FromI32 => {
-                let tmp = self.tmp();
-                let result = &format!("result{tmp}");
-                let operand = &operands[0];
-                quote_in! { self.body =>
-                    $['\r']
-                    $result := $WAZERO_API_DECODE_U32($operand)
-                };
-                results.push(Operand::SingleValue(result.into()));
+                results.push(Operand::SingleValue(
+                    result.into(),
+                    self.backend.value_type(),
+                ));
             }
             Instruction::PointerLoad { offset } => {
                 // TODO(#58): Support additional ArchitectureSize
@@ -334,31 +1169,13 @@ impl Bindgen for Func<'_> {
                 let ptr = &format!("ptr{tmp}");
                 let ok = &format!("ok{tmp}");
                 let default = &format!("default{tmp}");
-                let operand = &operands[0];
+                let load = self.load(Width::U32, &operands[0], offset);
                 quote_in! { self.body =>
                     $['\r']
-                    $ptr, $ok := i.module.Memory().ReadUint32Le(uint32($operand + $offset))
-                    $(match &self.result {
-                        GoResult::Anon(GoType::ValueOrError(typ)) => {
-                            if !$ok {
-                                var $default $(typ.as_ref())
-                                return $default, $ERRORS_NEW("failed to read pointer from memory")
-                            }
-                        }
-                        GoResult::Anon(GoType::Error) => {
-                            if !$ok {
-                                return $ERRORS_NEW("failed to read pointer from memory")
-                            }
-                        }
-                        GoResult::Anon(_) | GoResult::Empty => {
-                            $(comment(&["The return type doesn't contain an error so we panic if one is encountered"]))
-                            if !$ok {
-                                panic($ERRORS_NEW("failed to read pointer from memory"))
-                            }
-                        }
-                    })
+                    $ptr, $ok := $load
+                    $(handle_error(&self.result, quote!(!$ok), quote!($ERRORS_NEW("failed to read pointer from memory")), default))
                 };
-                results.push(Operand::SingleValue(ptr.into()));
+                results.push(Operand::SingleValue(ptr.into(), Width::U32.go_type()));
             }
             Instruction::LengthLoad { offset } => {
                 // TODO(#58): Support additional ArchitectureSize
@@ -367,31 +1184,13 @@ impl Bindgen for Func<'_> {
                 let len = &format!("len{tmp}");
                 let ok = &format!("ok{tmp}");
                 let default = &format!("default{tmp}");
-                let operand = &operands[0];
+                let load = self.load(Width::U32, &operands[0], offset);
                 quote_in! { self.body =>
                     $['\r']
-                    $len, $ok := i.module.Memory().ReadUint32Le(uint32($operand + $offset))
-                    $(match &self.result {
-                        GoResult::Anon(GoType::ValueOrError(typ)) => {
-                            if !$ok {
-                                var $default $(typ.as_ref())
-                                return $default, $ERRORS_NEW("failed to read length from memory")
-                            }
-                        }
-                        GoResult::Anon(GoType::Error) => {
-                            if !$ok {
-                                return $ERRORS_NEW("failed to read length from memory")
-                            }
-                        }
-                        GoResult::Anon(_) | GoResult::Empty => {
-                            $(comment(&["The return type doesn't contain an error so we panic if one is encountered"]))
-                            if !$ok {
-                                panic($ERRORS_NEW("failed to read length from memory"))
-                            }
-                        }
-                    })
+                    $len, $ok := $load
+                    $(handle_error(&self.result, quote!(!$ok), quote!($ERRORS_NEW("failed to read length from memory")), default))
                 };
-                results.push(Operand::SingleValue(len.into()));
+                results.push(Operand::SingleValue(len.into(), Width::U32.go_type()));
             }
             Instruction::I32Load { offset } => {
                 // TODO(#58): Support additional ArchitectureSize
@@ -400,79 +1199,52 @@ impl Bindgen for Func<'_> {
                 let value = &format!("value{tmp}");
                 let ok = &format!("ok{tmp}");
                 let default = &format!("default{tmp}");
-                let operand = &operands[0];
+                let load = self.load(Width::U32, &operands[0], offset);
                 quote_in! { self.body =>
                     $['\r']
-                    $value, $ok := i.module.Memory().ReadUint32Le(uint32($operand + $offset))
-                    $(match &self.result {
-                        GoResult::Anon(GoType::ValueOrError(typ)) => {
-                            if !$ok {
-                                var $default $(typ.as_ref())
-                                return $default, $ERRORS_NEW("failed to read i32 from memory")
-                            }
-                        }
-                        GoResult::Anon(GoType::Error) => {
-                            if !$ok {
-                                return $ERRORS_NEW("failed to read i32 from memory")
-                            }
-                        }
-                        GoResult::Anon(_) | GoResult::Empty => {
-                            $(comment(&["The return type doesn't contain an error so we panic if one is encountered"]))
-                            if !$ok {
-                                panic($ERRORS_NEW("failed to read i32 from memory"))
-                            }
-                        }
-                    })
+                    $value, $ok := $load
+                    $(handle_error(&self.result, quote!(!$ok), quote!($ERRORS_NEW("failed to read i32 from memory")), default))
                 };
-                results.push(Operand::SingleValue(value.into()));
+                results.push(Operand::SingleValue(value.into(), Width::U32.go_type()));
             }
             Instruction::StringLift => {
                 let tmp = self.tmp();
-                let buf = &format!("buf{tmp}");
                 let ok = &format!("ok{tmp}");
                 let default = &format!("default{tmp}");
                 let str = &format!("str{tmp}");
                 let ptr = &operands[0];
                 let len = &operands[1];
+                let memory = &self.memory();
+                let helper = match self.direction {
+                    Direction::Import { .. }
+                        if self.string_views
+                            && !self.batched
+                            && self.string_encoding == StringEncoding::UTF8 =>
+                    {
+                        VIEW_STRING
+                    }
+                    _ => read_string_helper(self.string_encoding),
+                };
+                let read = quote!($str, $ok := $helper($memory, $(ptr.convert(&GoType::Uint32)), $(len.convert(&GoType::Uint32))));
                 match self.direction {
                     Direction::Export { .. } => {
                         quote_in! { self.body =>
                             $['\r']
-                            $buf, $ok := i.module.Memory().Read($ptr, $len)
-                            $(match &self.result {
-                                GoResult::Anon(GoType::ValueOrError(typ)) => {
-                                    if !$ok {
-                                        var $default $(typ.as_ref())
-                                        return $default, $ERRORS_NEW("failed to read bytes from memory")
-                                    }
-                                }
-                                GoResult::Anon(GoType::Error) => {
-                                    if !$ok {
-                                        return $ERRORS_NEW("failed to read bytes from memory")
-                                    }
-                                }
-                                GoResult::Anon(_) | GoResult::Empty => {
-                                    $(comment(&["The return type doesn't contain an error so we panic if one is encountered"]))
-                                    if !$ok {
-                                        panic($ERRORS_NEW("failed to read bytes from memory"))
-                                    }
-                                }
-                            })
-                            $str := string($buf)
+                            $read
+                            $(handle_error(&self.result, quote!(!$ok), quote!($ERRORS_NEW("failed to read bytes from memory")), default))
                         };
                     }
                     Direction::Import { .. } => {
                         quote_in! { self.body =>
                             $['\r']
-                            $buf, $ok := mod.Memory().Read($ptr, $len)
+                            $read
                             if !$ok {
                                 panic($ERRORS_NEW("failed to read bytes from memory"))
                             }
-                            $str := string($buf)
                         };
                     }
                 }
-                results.push(Operand::SingleValue(str.into()));
+                results.push(Operand::SingleValue(str.into(), GoType::String));
             }
             Instruction::ResultLift {
                 result:
@@ -488,30 +1260,35 @@ impl Bindgen for Func<'_> {
 
                 let (ok_block, ok_results) = self.pop_block();
                 assert_eq!(ok_results.len(), 1);
-                let ok_op = &ok_results[0];
+                let (convert, ok_op) = self.nested(&ok_results[0]);
+                let ok_op = &ok_op;
 
                 let tmp = self.tmp();
                 let value = &format!("value{tmp}");
                 let err = &format!("err{tmp}");
-                let typ = resolve_type(typ, resolve);
+                let typ = resolve_field_type(typ, resolve);
                 let tag = &operands[0];
                 quote_in! { self.body =>
                     $['\r']
-                    var $value $typ
+                    var $value $(&typ)
                     var $err error
                     switch $tag {
                     case 0:
                         $ok_block
+                        $convert
                         $value = $ok_op
                     case 1:
                         $err_block
-                        $err = $ERRORS_NEW($err_op)
+                        $err = &$GUEST_ERROR{Message: $err_op}
                     default:
                         $err = $ERRORS_NEW("invalid variant discriminant for expected")
                     }
                 };
 
-                results.push(Operand::MultiValue((value.into(), err.into())));
+                results.push(Operand::MultiValue(
+                    (value.into(), err.into()),
+                    GoType::ValueOrError(Box::new(typ)),
+                ));
             }
             Instruction::ResultLift {
                 result:
@@ -539,18 +1316,31 @@ impl Bindgen for Func<'_> {
                         $ok_block
                     case 1:
                         $err_block
-                        $err = $ERRORS_NEW($err_op)
+                        $err = &$GUEST_ERROR{Message: $err_op}
                     default:
                         $err = $ERRORS_NEW("invalid variant discriminant for expected")
                     }
                 };
 
-                results.push(Operand::SingleValue(err.into()));
+                results.push(Operand::SingleValue(err.into(), GoType::Error));
             }
             Instruction::ResultLift { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::Return { amt, .. } => {
-                if *amt != 0 {
+            Instruction::Return { amt, func } => {
+                self.convert_return_area(resolve, func, operands);
+                if self.wraps_error() {
+                    quote_in! { self.body =>
+                        $['\r']
+                        return $(for operand in operands.iter().take(*amt) join (, ) => $operand)$(if *amt != 0 => ,) nil
+                    };
+                } else if *amt != 0 {
                     let operand = &operands[0];
+                    // Integers are lowered to the `uint64`s Wazero passes to
+                    // guests, so host functions convert them to the Go type
+                    // of their core Wasm result
+                    let operand = match (&self.direction, &self.result) {
+                        (Direction::Import { .. }, GoResult::Anon(typ)) => operand.convert(typ),
+                        _ => quote!($operand),
+                    };
                     quote_in! { self.body =>
                         $['\r']
                         return $operand
@@ -558,7 +1348,10 @@ impl Bindgen for Func<'_> {
                 }
             }
             Instruction::CallInterface { func, .. } => {
-                let ident = GoIdentifier::public(&func.name);
+                let ident = self
+                    .method_name
+                    .cloned()
+                    .unwrap_or_else(|| method_name(resolve, func));
                 let tmp = self.tmp();
                 let args = quote!($(for op in operands.iter() join (, ) => $op));
                 let returns = match &func.result {
@@ -568,14 +1361,24 @@ impl Bindgen for Func<'_> {
                 let value = &format!("value{tmp}");
                 let err = &format!("err{tmp}");
                 let ok = &format!("ok{tmp}");
+                // Borrowed handles are only valid during the call, so the
+                // calls they're passed to aren't queued
+                let batched = self.batched
+                    && !operands
+                        .iter()
+                        .any(|op| matches!(op.typ(), Some(GoType::BorrowHandle(_))));
                 match self.direction {
                     Direction::Export { .. } => todo!("TODO(#10): handle export direction"),
                     Direction::Import { param_name, .. } => {
                         quote_in! { self.body =>
                             $['\r']
                             $(match returns {
+                                GoType::Nothing if batched => {
+                                    $CALL_IMPORT(ctx, func() {
+                                        $param_name.$ident(ctx, $args)
+                                    })
+                                }
                                 GoType::Nothing => $param_name.$ident(ctx, $args),
-                                GoType::Bool | GoType::Uint32 | GoType::Interface | GoType::String | GoType::UserDefined(_) => $value := $param_name.$ident(ctx, $args),
                                 GoType::Error => $err := $param_name.$ident(ctx, $args),
                                 GoType::ValueOrError(_) => {
                                     $value, $err := $param_name.$ident(ctx, $args)
@@ -583,112 +1386,84 @@ impl Bindgen for Func<'_> {
                                 GoType::ValueOrOk(_) => {
                                     $value, $ok := $param_name.$ident(ctx, $args)
                                 }
-                                _ => $(comment(&["TODO(#9): handle return type"]))
+                                GoType::MultiReturn(_) => $(comment(&["TODO(#9): handle return type"])),
+                                _ => $value := $param_name.$ident(ctx, $args),
                             })
                         }
                     }
                 }
                 match returns {
                     GoType::Nothing => (),
-                    GoType::Bool
-                    | GoType::Uint32
-                    | GoType::Interface
-                    | GoType::UserDefined(_)
-                    | GoType::String => {
-                        results.push(Operand::SingleValue(value.into()));
-                    }
                     GoType::Error => {
-                        results.push(Operand::SingleValue(err.into()));
+                        results.push(Operand::SingleValue(err.into(), returns));
                     }
                     GoType::ValueOrError(_) => {
-                        results.push(Operand::MultiValue((value.into(), err.into())));
+                        results.push(Operand::MultiValue((value.into(), err.into()), returns));
                     }
                     GoType::ValueOrOk(_) => {
-                        results.push(Operand::MultiValue((value.into(), ok.into())))
+                        results.push(Operand::MultiValue((value.into(), ok.into()), returns))
+                    }
+                    GoType::MultiReturn(_) => todo!("TODO(#9): handle return type - {returns:?}"),
+                    // Any other result, such as a `list<string>`, is a single
+                    // Go value
+                    _ => {
+                        results.push(Operand::SingleValue(value.into(), returns));
                     }
-                    _ => todo!("TODO(#9): handle return type - {returns:?}"),
                 }
+                self.return_area = Some(ReturnArea {
+                    body: self.body.clone(),
+                    ptr: None,
+                    value: results.first().cloned(),
+                });
             }
             Instruction::VariantPayloadName => {
-                results.push(Operand::SingleValue("variantPayload".into()));
+                results.push(Operand::Untyped(variants::PAYLOAD.into()));
             }
             Instruction::I32Const { val } => results.push(Operand::Literal(val.to_string())),
             Instruction::I32Store8 { offset } => {
                 // TODO(#58): Support additional ArchitectureSize
                 let offset = offset.size_wasm32();
-                let tag = &operands[0];
+                let value = &operands[0];
                 let ptr = &operands[1];
-                if let Operand::Literal(byte) = tag {
-                    match &self.direction {
-                        Direction::Export => {
-                            quote_in! { self.body =>
-                                $['\r']
-                                i.module.Memory().WriteByte($ptr+$offset, $byte)
-                            }
-                        }
-                        Direction::Import { .. } => {
-                            quote_in! { self.body =>
-                                $['\r']
-                                mod.Memory().WriteByte($ptr+$offset, $byte)
-                            }
-                        }
-                    }
-                } else {
-                    let tmp = self.tmp();
-                    let byte = format!("byte{tmp}");
-                    match &self.direction {
-                        Direction::Export => {
-                            quote_in! { self.body =>
-                                $['\r']
-                                var $(&byte) uint8
-                                switch $tag {
-                                case 0:
-                                    $(&byte) = 0
-                                case 1:
-                                    $(&byte) = 1
-                                default:
-                                    $(comment(["TODO(#8): Return an error if the return type allows it"]))
-                                    panic($ERRORS_NEW("invalid int8 value encountered"))
-                                }
-                                i.module.Memory().WriteByte($ptr+$offset, $byte)
-                            }
-                        }
-                        Direction::Import { .. } => {
-                            quote_in! { self.body =>
-                                $['\r']
-                                var $(&byte) uint8
-                                switch $tag {
-                                case 0:
-                                    $(&byte) = 0
-                                case 1:
-                                    $(&byte) = 1
-                                default:
-                                    panic($ERRORS_NEW("invalid int8 value encountered"))
-                                }
-                                mod.Memory().WriteByte($ptr+$offset, $byte)
-                            }
-                        }
-                    }
+                // Like `i32.store8`, only the low byte is stored, which keeps
+                // the bits of negative `s8`s
+                let store = self.store(Width::U8, ptr, offset, value.convert(&GoType::Uint8));
+                quote_in! { self.body =>
+                    $['\r']
+                    $store
                 }
             }
-            Instruction::I32Store { offset } => {
+            Instruction::I32Store16 { offset } => {
                 // TODO(#58): Support additional ArchitectureSize
                 let offset = offset.size_wasm32();
-                let tag = &operands[0];
+                let value = &operands[0];
                 let ptr = &operands[1];
-                match &self.direction {
-                    Direction::Export => {
-                        quote_in! { self.body =>
-                            $['\r']
-                            i.module.Memory().WriteUint32Le($ptr+$offset, $tag)
-                        }
-                    }
-                    Direction::Import { .. } => {
-                        quote_in! { self.body =>
-                            $['\r']
-                            mod.Memory().WriteUint32Le($ptr+$offset, $tag)
-                        }
-                    }
+                let store = self.store(Width::U16, ptr, offset, value.convert(&GoType::Uint16));
+                quote_in! { self.body =>
+                    $['\r']
+                    $store
+                }
+            }
+            Instruction::I64Store { offset } => {
+                // TODO(#58): Support additional ArchitectureSize
+                let offset = offset.size_wasm32();
+                let value = &operands[0];
+                let ptr = &operands[1];
+                let store = self.store(Width::U64, ptr, offset, value.convert(&GoType::Uint64));
+                quote_in! { self.body =>
+                    $['\r']
+                    $store
+                }
+            }
+            Instruction::I32Store { offset } => {
+                // TODO(#58): Support additional ArchitectureSize
+                let offset = offset.size_wasm32();
+                let tag = &operands[0];
+                let ptr = &operands[1];
+                let store = self.store(Width::U32, ptr, offset, tag.convert(&GoType::Uint32));
+                quote_in! { self.body =>
+                    $['\r']
+                    $store
                 }
             }
             Instruction::LengthStore { offset } => {
@@ -696,19 +1471,10 @@ impl Bindgen for Func<'_> {
                 let offset = offset.size_wasm32();
                 let len = &operands[0];
                 let ptr = &operands[1];
-                match &self.direction {
-                    Direction::Export => {
-                        quote_in! { self.body =>
-                            $['\r']
-                            i.module.Memory().WriteUint32Le($ptr+$offset, uint32($len))
-                        }
-                    }
-                    Direction::Import { .. } => {
-                        quote_in! { self.body =>
-                            $['\r']
-                            mod.Memory().WriteUint32Le($ptr+$offset, uint32($len))
-                        }
-                    }
+                let store = self.store(Width::U32, ptr, offset, len.convert(&GoType::Uint32));
+                quote_in! { self.body =>
+                    $['\r']
+                    $store
                 }
             }
             Instruction::PointerStore { offset } => {
@@ -716,19 +1482,10 @@ impl Bindgen for Func<'_> {
                 let offset = offset.size_wasm32();
                 let value = &operands[0];
                 let ptr = &operands[1];
-                match &self.direction {
-                    Direction::Export => {
-                        quote_in! { self.body =>
-                            $['\r']
-                            i.module.Memory().WriteUint32Le($ptr+$offset, uint32($value))
-                        }
-                    }
-                    Direction::Import { .. } => {
-                        quote_in! { self.body =>
-                            $['\r']
-                            mod.Memory().WriteUint32Le($ptr+$offset, uint32($value))
-                        }
-                    }
+                let store = self.store(Width::U32, ptr, offset, value.convert(&GoType::Uint32));
+                quote_in! { self.body =>
+                    $['\r']
+                    $store
                 }
             }
             Instruction::ResultLower {
@@ -746,10 +1503,15 @@ impl Bindgen for Func<'_> {
                     Operand::Literal(_) => {
                         panic!("impossible: expected Operand::MultiValue but got Operand::Literal")
                     }
-                    Operand::SingleValue(_) => panic!(
-                        "impossible: expected Operand::MultiValue but got Operand::SingleValue"
-                    ),
-                    Operand::MultiValue(bindings) => bindings,
+                    // Results nested in other values are a `Result`, as is
+                    // the payload of a nested option or result
+                    Operand::SingleValue(value, GoType::Result(_)) | Operand::Untyped(value) => {
+                        &(format!("{value}.Value"), format!("{value}.Err"))
+                    }
+                    Operand::SingleValue(..) => {
+                        panic!("impossible: expected Operand::MultiValue but got a single value")
+                    }
+                    Operand::MultiValue(bindings, _) => bindings,
                 };
                 quote_in! { self.body =>
                     $['\r']
@@ -787,32 +1549,38 @@ impl Bindgen for Func<'_> {
             Instruction::OptionLift { payload, .. } => {
                 let (some, some_results) = self.blocks.pop().unwrap();
                 let (none, _) = self.blocks.pop().unwrap();
-                let some_result = &some_results[0];
+                // Nested options are pointers
+                let (convert, some_result) = self.nested(&some_results[0]);
+                let some_result = &some_result;
 
                 let tmp = self.tmp();
                 let result = &format!("result{tmp}");
                 let ok = &format!("ok{tmp}");
-                let typ = resolve_type(payload, resolve);
+                let typ = resolve_field_type(payload, resolve);
                 let op = &operands[0];
 
                 quote_in! { self.body =>
                     $['\r']
-                    var $result $typ
+                    var $result $(&typ)
                     var $ok bool
                     if $op == 0 {
                         $none
                         $ok = false
                     } else {
                         $some
+                        $convert
                         $ok = true
                         $result = $some_result
                     }
                 };
 
-                results.push(Operand::MultiValue((result.into(), ok.into())));
+                results.push(Operand::MultiValue(
+                    (result.into(), ok.into()),
+                    GoType::ValueOrOk(Box::new(typ)),
+                ));
             }
             Instruction::OptionLower {
-                payload: Type::String,
+                payload,
                 results: result_types,
                 ..
             } => {
@@ -825,23 +1593,23 @@ impl Bindgen for Func<'_> {
                 for i in 0..result_types.len() {
                     let variant = &format!("variant{tmp}_{i}");
                     let typ = resolve_wasm_type(&result_types[i]);
-                    results.push(Operand::SingleValue(variant.into()));
 
                     quote_in! { vars =>
                         $['\r']
-                        var $variant $typ
+                        var $variant $(&typ)
                     }
 
                     let some_result = &some_results[i];
                     let none_result = &none_results[i];
                     quote_in! { some_block =>
                         $['\r']
-                        $variant = $some_result
+                        $variant = $(some_result.convert(&typ))
                     };
                     quote_in! { none_block =>
                         $['\r']
-                        $variant = $none_result
+                        $variant = $(none_result.convert(&typ))
                     };
+                    results.push(Operand::SingleValue(variant.into(), typ));
                 }
 
                 let operand = &operands[0];
@@ -849,11 +1617,25 @@ impl Bindgen for Func<'_> {
                     Operand::Literal(_) => {
                         panic!("impossible: expected Operand::MultiValue but got Operand::Literal")
                     }
+                    // Options nested in other values are pointers, as is the
+                    // payload of a nested option
+                    Operand::SingleValue(value, GoType::Pointer(_)) | Operand::Untyped(value) => {
+                        quote_in! { self.body =>
+                            $['\r']
+                            $vars
+                            if $value == nil {
+                                $none_block
+                            } else {
+                                variantPayload := *$value
+                                $some_block
+                            }
+                        };
+                    }
                     // TODO(#7): This is a weird hack to implement `option<string>`
                     // as arguments that currently only works for strings
                     // because it checks the empty string as the zero value to
                     // consider it None
-                    Operand::SingleValue(value) => {
+                    Operand::SingleValue(value, _) if matches!(payload, Type::String) => {
                         quote_in! { self.body =>
                             $['\r']
                             $vars
@@ -865,7 +1647,7 @@ impl Bindgen for Func<'_> {
                             }
                         };
                     }
-                    Operand::MultiValue((value, ok)) => {
+                    Operand::MultiValue((value, ok), _) => {
                         quote_in! { self.body =>
                             $['\r']
                             if $ok {
@@ -876,9 +1658,9 @@ impl Bindgen for Func<'_> {
                             }
                         };
                     }
+                    Operand::SingleValue(..) => todo!("implement instruction: {inst:?}"),
                 };
             }
-            Instruction::OptionLower { .. } => todo!("implement instruction: {inst:?}"),
             Instruction::RecordLower { record, .. } => {
                 let tmp = self.tmp();
                 let operand = &operands[0];
@@ -889,28 +1671,40 @@ impl Bindgen for Func<'_> {
                         $['\r']
                         $var := $operand.$struct_field
                     }
-                    results.push(Operand::SingleValue(var.into()))
+                    let typ = resolve_field_type(&field.ty, resolve);
+                    results.push(Operand::SingleValue(var.into(), typ))
                 }
             }
             Instruction::RecordLift { record, name, .. } => {
+                let (converts, operands): (Vec<_>, Vec<_>) =
+                    operands.iter().map(|op| self.nested(op)).unzip();
                 let tmp = self.tmp();
                 let value = &format!("value{tmp}");
                 let fields = record
                     .fields
                     .iter()
-                    .zip(operands)
+                    .zip(&operands)
                     .map(|(field, op)| (GoIdentifier::public(&field.name), op));
 
                 quote_in! {self.body =>
                     $['\r']
+                    $(for convert in converts join ($['\r']) => $convert)
                     $value := $(GoIdentifier::public(*name)){
                         $(for (name, op) in fields join ($['\r']) => $name: $op,)
                     }
                 };
-                results.push(Operand::SingleValue(value.into()))
+                let typ = GoType::UserDefined(name.to_string());
+                results.push(Operand::SingleValue(value.into(), typ))
+            }
+            Instruction::IterElem { element } => {
+                let typ = resolve_field_type(element, resolve);
+                results.push(Operand::SingleValue(iter_element.into(), typ))
+            }
+            Instruction::IterBasePointer => {
+                let tmp = self.tmp();
+                self.lists.push(tmp);
+                results.push(Operand::SingleValue(format!("base{tmp}"), GoType::Uint32));
             }
-            Instruction::IterElem { .. } => results.push(Operand::SingleValue(iter_element.into())),
-            Instruction::IterBasePointer => results.push(Operand::SingleValue(iter_base.into())),
             Instruction::ListLower { realloc: None, .. } => {
                 todo!("implement instruction: {inst:?}")
             }
@@ -919,6 +1713,19 @@ impl Bindgen for Func<'_> {
                 realloc: Some(realloc_name),
             } => {
                 let (body, _) = self.pop_block();
+                let list = self.lists.pop().expect("should have list being lowered");
+                let base = &format!("base{list}");
+                let buf = &format!("buf{list}");
+                // Elements with a helper are lowered by calling it rather
+                // than by the code of the block
+                let body = match self.conversion(resolve, ConversionKind::Lower, element) {
+                    Some(conversion) => {
+                        let typ = resolve_field_type(element, resolve);
+                        let element = Operand::SingleValue(iter_element.into(), typ);
+                        self.call_lower(&conversion, quote!($buf[$base:]), &element, realloc_name)
+                    }
+                    None => body,
+                };
                 let tmp = self.tmp();
                 let vec = &format!("vec{tmp}");
                 let result = &format!("result{tmp}");
@@ -929,43 +1736,31 @@ impl Bindgen for Func<'_> {
                 let operand = &operands[0];
                 let size = self.sizes.size(element).size_wasm32();
                 let align = self.sizes.align(element).align_wasm32();
+                let memory = &self.memory();
+                let realloc = &self.realloc(realloc_name);
 
                 quote_in! { self.body =>
                     $['\r']
                     $vec := $operand
                     $len := uint64(len($vec))
-                    $result, $err := i.module.ExportedFunction($(quoted(*realloc_name))).Call(ctx, 0, 0, $align, $len * $size)
-                    $(match &self.result {
-                        GoResult::Anon(GoType::ValueOrError(typ)) => {
-                            if $err != nil {
-                                var $default $(typ.as_ref())
-                                return $default, $err
-                            }
-                        }
-                        GoResult::Anon(GoType::Error) => {
-                            if $err != nil {
-                                return $err
-                            }
-                        }
-                        GoResult::Anon(_) | GoResult::Empty => {
-                            $(comment(&["The return type doesn't contain an error so we panic if one is encountered"]))
-                            if $err != nil {
-                                panic($err)
-                            }
-                        }
-                    })
+                    $result, $err := $(self.backend.call(realloc, quote!(0, 0, $align, $len * $size)))
+                    $(check_error(&self.direction, &self.result, quote!($err != nil), quote!($err), default))
                     $ptr := $result[0]
+                    $buf := make([]byte, $len * $size)
                     for idx := uint64(0); idx < $len; idx++ {
                         $iter_element := $vec[idx]
-                        $iter_base := uint32($ptr + uint64(idx) * uint64($size))
+                        $base := uint32(idx * $size)
                         $body
                     }
+                    $(check_error(&self.direction, &self.result, quote!(!$(self.backend.write(memory, quote!(uint32($ptr)), quote!($buf)))), quote!($ERRORS_NEW("failed to write list to memory")), default))
                 };
-                results.push(Operand::SingleValue(ptr.into()));
-                results.push(Operand::SingleValue(len.into()));
+                results.push(Operand::SingleValue(ptr.into(), self.backend.value_type()));
+                results.push(Operand::SingleValue(len.into(), GoType::Uint64));
             }
             Instruction::ListLift { element, .. } => {
                 let (body, body_results) = self.pop_block();
+                let list = self.lists.pop().expect("should have list being lifted");
+                let iter_base = &format!("base{list}");
                 let tmp = self.tmp();
                 let size = self.sizes.size(element).size_wasm32();
                 let len = &format!("len{tmp}");
@@ -975,25 +1770,36 @@ impl Bindgen for Func<'_> {
 
                 let base_operand = &operands[0];
                 let len_operand = &operands[1];
-                let body_result = &body_results[0];
+                // Elements with a helper are lifted by calling it rather than
+                // by the code of the block
+                let (body, body_result) =
+                    match self.conversion(resolve, ConversionKind::Lift, element) {
+                        Some(conversion) => self.call_lift(&conversion, quote!($iter_base)),
+                        None => (body, body_results[0].clone()),
+                    };
+                let (convert, body_result) = self.nested(&body_result);
+                let body_result = &body_result;
 
-                let typ = resolve_type(element, resolve);
+                let typ = resolve_field_type(element, resolve);
 
                 quote_in! { self.body =>
                     $['\r']
-                    $base := $base_operand
-                    $len := $len_operand
-                    $result := make([]$typ, $len)
+                    $base := $(base_operand.convert(&GoType::Uint32))
+                    $len := $(len_operand.convert(&GoType::Uint32))
+                    $result := make([]$(&typ), $len)
                     for $idx := uint32(0); $idx < $len; $idx++ {
-                        base := $base + $idx * $size
+                        $iter_base := $base + $idx * $size
                         $body
+                        $convert
                         $result[$idx] = $body_result
                     }
                 }
-                results.push(Operand::SingleValue(result.into()));
+                let typ = GoType::Slice(Box::new(typ));
+                results.push(Operand::SingleValue(result.into(), typ));
             }
             Instruction::VariantLower {
                 variant,
+                name: variant_name,
                 results: result_types,
                 ..
             } => {
@@ -1010,49 +1816,50 @@ impl Bindgen for Func<'_> {
                     let typ = resolve_wasm_type(typ);
                     quote_in! { self.body =>
                         $['\r']
-                        var $variant_item $typ
+                        var $variant_item $(&typ)
                     }
-                    results.push(Operand::SingleValue(variant_item.into()));
+                    results.push(Operand::SingleValue(variant_item.into(), typ));
                 }
 
                 let mut cases: Tokens<Go> = Tokens::new();
                 for (case, (block, block_results)) in variant.cases.iter().zip(blocks) {
                     let mut assignments: Tokens<Go> = Tokens::new();
-                    for (i, result) in block_results.iter().enumerate() {
+                    // The cases may produce differently sized integers for
+                    // the same core Wasm value, so they're converted when
+                    // their types differ
+                    for (i, (result, typ)) in
+                        block_results.iter().zip(result_types.iter()).enumerate()
+                    {
                         let variant_item = &format!("variant{tmp}_{i}");
+                        let typ = resolve_wasm_type(typ);
                         quote_in! { assignments =>
                             $['\r']
-                            $variant_item = $result
+                            $variant_item = $(result.convert(&typ))
                         };
                     }
 
-                    let name = GoIdentifier::public(case.name.clone());
+                    let name = variants::case_name(variant_name, &case.name);
                     quote_in! { cases =>
                         $['\r']
                         case $name:
+                            $(if case.ty.is_some() => $(variants::PAYLOAD) := variantCase.Value)
                             $block
                             $assignments
                     }
                 }
 
+                // Go rejects the variable of the switch if no case uses it
+                let switch = if variant.cases.iter().any(|case| case.ty.is_some()) {
+                    quote!(variantCase := $value.(type))
+                } else {
+                    quote!($value.(type))
+                };
                 quote_in! { self.body =>
                     $['\r']
-                    switch variantPayload := $value.(type) {
+                    switch $switch {
                         $cases
                         default:
-                            $(match &self.result {
-                                GoResult::Anon(GoType::ValueOrError(typ)) => {
-                                    var $default $(typ.as_ref())
-                                    return $default, $ERRORS_NEW("invalid variant type provided")
-                                }
-                                GoResult::Anon(GoType::Error) => {
-                                    return $ERRORS_NEW("invalid variant type provided")
-                                }
-                                GoResult::Anon(_) | GoResult::Empty => {
-                                    $(comment(&["The return type doesn't contain an error so we panic if one is encountered"]))
-                                    panic($ERRORS_NEW("invalid variant type provided"))
-                                }
-                            })
+                            $(return_error(&self.result, quote!($ERRORS_NEW("invalid variant type provided")), default))
                     }
                 }
             }
@@ -1063,7 +1870,7 @@ impl Bindgen for Func<'_> {
 
                 let mut cases: Tokens<Go> = Tokens::new();
                 for (i, case) in enum_.cases.iter().enumerate() {
-                    let case_name = GoIdentifier::public(case.name.clone());
+                    let case_name = GoIdentifier::prefixed_public(&case.name);
                     quote_in! { cases =>
                         $['\r']
                         case $case_name:
@@ -1071,41 +1878,116 @@ impl Bindgen for Func<'_> {
                     };
                 }
 
+                let err = quote!($ERRORS_NEW("invalid enum type provided"));
+                let invalid = match self.direction {
+                    Direction::Export { .. } => {
+                        return_error(&self.result, err, &format!("default{tmp}"))
+                    }
+                    Direction::Import { .. } => quote!(panic($err)),
+                };
                 quote_in! { self.body =>
                     $['\r']
                     var $enum_tmp uint32
                     switch $value {
                     $cases
                     default:
-                        panic($ERRORS_NEW("invalid enum type provided"))
+                        $invalid
                     }
                 };
 
-                results.push(Operand::SingleValue(enum_tmp.to_string()));
+                results.push(Operand::SingleValue(enum_tmp.to_string(), GoType::Uint32));
+            }
+            Instruction::Bitcasts { casts } => {
+                for (cast, op) in casts.iter().zip(operands) {
+                    if *cast == Bitcast::None {
+                        results.push(op.clone());
+                        continue;
+                    }
+                    let tmp = self.tmp();
+                    let value = &format!("cast{tmp}");
+                    let (expr, typ) =
+                        bitcast(self.backend, cast, quote!($(&*op)), op.typ().cloned());
+                    quote_in! { self.body =>
+                        $['\r']
+                        $value := $expr
+                    };
+                    results.push(match typ {
+                        Some(typ) => Operand::SingleValue(value.into(), typ),
+                        None => Operand::Untyped(value.into()),
+                    });
+                }
+            }
+            Instruction::I32Load8S { offset }
+            | Instruction::I32Load16U { offset }
+            | Instruction::I32Load16S { offset }
+            | Instruction::I64Load { offset } => {
+                // TODO(#58): Support additional ArchitectureSize
+                let offset = offset.size_wasm32();
+                let tmp = self.tmp();
+                let raw = &format!("raw{tmp}");
+                let value = &format!("value{tmp}");
+                let ok = &format!("ok{tmp}");
+                let default = &format!("default{tmp}");
+                // Signed loads sign-extend the value to an i32, like
+                // `i32.load8_s` and `i32.load16_s`
+                let (width, what, extend): (_, _, Option<Tokens<Go>>) = match inst {
+                    Instruction::I32Load8S { .. } => {
+                        (Width::U8, "byte", Some(quote!(uint32(int32(int8($raw))))))
+                    }
+                    Instruction::I32Load16U { .. } => {
+                        (Width::U16, "i16", Some(quote!(uint32($raw))))
+                    }
+                    Instruction::I32Load16S { .. } => {
+                        (Width::U16, "i16", Some(quote!(uint32(int32(int16($raw))))))
+                    }
+                    _ => (Width::U64, "i64", None),
+                };
+                let err = &format!("failed to read {what} from memory");
+                let load = self.load(width, &operands[0], offset);
+                let loaded = if extend.is_some() { raw } else { value };
+                quote_in! { self.body =>
+                    $['\r']
+                    $loaded, $ok := $load
+                    $(handle_error(&self.result, quote!(!$ok), quote!($ERRORS_NEW($(quoted(err)))), default))
+                    $(if let Some(extend) = &extend => $value := $extend)
+                };
+                let typ = match extend {
+                    Some(_) => GoType::Uint32,
+                    None => width.go_type(),
+                };
+                results.push(Operand::SingleValue(value.into(), typ));
             }
-            Instruction::Bitcasts { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::I32Load8S { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::I32Load16U { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::I32Load16S { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::I64Load { .. } => todo!("implement instruction: {inst:?}"),
             Instruction::F32Load { .. } => todo!("implement instruction: {inst:?}"),
             Instruction::F64Load { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::I32Store16 { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::I64Store { .. } => todo!("implement instruction: {inst:?}"),
             Instruction::F32Store { .. } => todo!("implement instruction: {inst:?}"),
             Instruction::F64Store { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::I32FromChar => todo!("implement instruction: {inst:?}"),
-            Instruction::I64FromU64 => todo!("implement instruction: {inst:?}"),
-            Instruction::I64FromS64 => todo!("implement instruction: {inst:?}"),
-            Instruction::I32FromS32 => {
+            Instruction::I64FromU64 => results.push(operands[0].clone()),
+            Instruction::I64FromS64 => {
+                let tmp = self.tmp();
+                let value = format!("value{tmp}");
+                let operand = &operands[0];
+                quote_in! { self.body =>
+                    $['\r']
+                    $(&value) := $(self.backend.encode_i64(quote!($operand)))
+                }
+                results.push(Operand::SingleValue(value, self.backend.value_type()))
+            }
+            // Core Wasm floats are Go floats, which are only encoded as the
+            // `uint64`s Wazero passes when calling into the guest
+            Instruction::CoreF32FromF32
+            | Instruction::CoreF64FromF64
+            | Instruction::F32FromCoreF32
+            | Instruction::F64FromCoreF64 => results.push(operands[0].clone()),
+            // Runes are `int32`s, whose values are always valid when lowered
+            Instruction::I32FromS32 | Instruction::I32FromChar => {
                 let tmp = self.tmp();
                 let value = format!("value{tmp}");
                 let operand = &operands[0];
                 quote_in! { self.body =>
                     $['\r']
-                    $(&value) := $WAZERO_API_ENCODE_I32($operand)
+                    $(&value) := $(self.backend.encode_i32(quote!($operand)))
                 }
-                results.push(Operand::SingleValue(value))
+                results.push(Operand::SingleValue(value, self.backend.value_type()))
             }
             // All of these values should fit in Go's `int32` type which allows a safe cast
             Instruction::I32FromU16
@@ -1117,121 +1999,356 @@ impl Bindgen for Func<'_> {
                 let operand = &operands[0];
                 quote_in! { self.body =>
                     $['\r']
-                    $(&value) := $WAZERO_API_ENCODE_I32(int32($operand))
+                    $(&value) := $(self.backend.encode_i32(operand.convert(&GoType::Int32)))
                 }
-                results.push(Operand::SingleValue(value))
+                results.push(Operand::SingleValue(value, self.backend.value_type()))
             }
-            Instruction::CoreF32FromF32 => {
+            // Core Wasm integers are lifted with Go conversions, which
+            // truncate and reinterpret their bits like `api.DecodeI32` and
+            // `api.DecodeU32` do, but also accept the narrower integers read
+            // from memory
+            Instruction::S8FromI32
+            | Instruction::U8FromI32
+            | Instruction::S16FromI32
+            | Instruction::U16FromI32
+            | Instruction::S32FromI32
+            | Instruction::U32FromI32
+            | Instruction::S64FromI64
+            | Instruction::U64FromI64 => {
+                let typ = match inst {
+                    Instruction::S8FromI32 => GoType::Int8,
+                    Instruction::U8FromI32 => GoType::Uint8,
+                    Instruction::S16FromI32 => GoType::Int16,
+                    Instruction::U16FromI32 => GoType::Uint16,
+                    Instruction::S32FromI32 => GoType::Int32,
+                    Instruction::U32FromI32 => GoType::Uint32,
+                    Instruction::S64FromI64 => GoType::Int64,
+                    _ => GoType::Uint64,
+                };
                 let tmp = self.tmp();
                 let result = &format!("result{tmp}");
+                let default = &format!("default{tmp}");
                 let operand = &operands[0];
+                if self.checked_casts
+                    && let Some(cond) = out_of_range(operand, &typ)
+                {
+                    let err = quote!($FMT_ERRORF($(quoted(format!("value out of range for {}: %d", type_name(&typ)))), $operand));
+                    quote_in! { self.body =>
+                        $['\r']
+                        $(check_error(&self.direction, &self.result, cond, err, default))
+                    };
+                }
                 quote_in! { self.body =>
                     $['\r']
-                    $result := $WAZERO_API_ENCODE_F32($operand)
+                    $result := $(operand.convert(&typ))
                 };
-                results.push(Operand::SingleValue(result.into()));
+                results.push(Operand::SingleValue(result.into(), typ));
             }
-            Instruction::CoreF64FromF64 => {
+            Instruction::CharFromI32 => {
                 let tmp = self.tmp();
                 let result = &format!("result{tmp}");
+                let default = &format!("default{tmp}");
                 let operand = &operands[0];
+                // Like the canonical ABI, values that aren't Unicode scalar
+                // values, i.e. surrogates or beyond U+10FFFF, are rejected
+                let err = quote!($ERRORS_NEW("invalid char: not a Unicode scalar value"));
                 quote_in! { self.body =>
                     $['\r']
-                    $result := $WAZERO_API_ENCODE_F64($operand)
+                    $result := $(operand.convert(&GoType::Rune))
+                    $(handle_error(&self.result, quote!(!$UNICODE_UTF8_VALID_RUNE($result)), err, default))
                 };
-                results.push(Operand::SingleValue(result.into()));
+                results.push(Operand::SingleValue(result.into(), GoType::Rune));
             }
-            // TODO: Validate the Go cast truncates the upper bits in the I32
-            Instruction::S8FromI32 => {
+            Instruction::TupleLower { tuple, .. } => {
                 let tmp = self.tmp();
-                let result = &format!("result{tmp}");
                 let operand = &operands[0];
+                for i in 0..tuple.types.len() {
+                    let var = &format!("f{i}_{tmp}");
+                    quote_in! { self.body =>
+                        $['\r']
+                        $var := $operand.$(tuple_field(i))
+                    }
+                    let typ = resolve_field_type(&tuple.types[i], resolve);
+                    results.push(Operand::SingleValue(var.into(), typ))
+                }
+            }
+            Instruction::TupleLift { ty, .. } if self.multi_return == Some(*ty) => {
+                // The elements are returned as is, so the operand is the list
+                // of values in the `return` statement
+                let (converts, operands): (Vec<_>, Vec<_>) =
+                    operands.iter().map(|op| self.nested(op)).unzip();
                 quote_in! { self.body =>
-                    $['\r']
-                    $result := int8($WAZERO_API_DECODE_I32($operand))
+                    $(for convert in converts join ($['\r']) => $convert)
                 };
-                results.push(Operand::SingleValue(result.into()));
+                let values = operands.iter().map(Operand::as_string).collect::<Vec<_>>();
+                let TypeDefKind::Tuple(tuple) = &resolve.types[*ty].kind else {
+                    unreachable!("expected a tuple")
+                };
+                let types = tuple
+                    .types
+                    .iter()
+                    .map(|typ| resolve_field_type(typ, resolve))
+                    .collect();
+                results.push(Operand::SingleValue(
+                    values.join(", "),
+                    GoType::MultiReturn(types),
+                ))
             }
-            // TODO: Validate the Go cast truncates the upper bits in the I32
-            Instruction::U8FromI32 => {
+            Instruction::TupleLift { ty, .. } => {
+                let (converts, operands): (Vec<_>, Vec<_>) =
+                    operands.iter().map(|op| self.nested(op)).unzip();
                 let tmp = self.tmp();
-                let result = &format!("result{tmp}");
-                let operand = &operands[0];
-                quote_in! { self.body =>
+                let value = &format!("value{tmp}");
+                let name = resolve.types[*ty]
+                    .name
+                    .as_deref()
+                    .expect("expected tuple to have a name");
+                let fields = operands
+                    .iter()
+                    .enumerate()
+                    .map(|(i, op)| (tuple_field(i), op));
+
+                quote_in! {self.body =>
                     $['\r']
-                    $result := uint8($WAZERO_API_DECODE_U32($operand))
+                    $(for convert in converts join ($['\r']) => $convert)
+                    $value := $(GoIdentifier::public(name)){
+                        $(for (name, op) in fields join ($['\r']) => $name: $op,)
+                    }
                 };
-                results.push(Operand::SingleValue(result.into()));
+                results.push(Operand::SingleValue(
+                    value.into(),
+                    GoType::UserDefined(name.to_string()),
+                ))
             }
-            // TODO: Validate the Go cast truncates the upper bits in the I32
-            Instruction::S16FromI32 => {
+            Instruction::FlagsLower { .. } => todo!("implement instruction: {inst:?}"),
+            Instruction::FlagsLift { .. } => todo!("implement instruction: {inst:?}"),
+            Instruction::VariantLift {
+                variant,
+                name: variant_name,
+                ..
+            } => {
+                let blocks = self
+                    .blocks
+                    .drain(self.blocks.len() - variant.cases.len()..)
+                    .collect::<Vec<_>>();
                 let tmp = self.tmp();
-                let result = &format!("result{tmp}");
-                let operand = &operands[0];
+                let value = &format!("variant{tmp}");
+                let default = &format!("default{tmp}");
+                let typ = GoIdentifier::public(*variant_name);
+                let op = &operands[0];
+
+                let mut cases: Tokens<Go> = Tokens::new();
+                for (i, (case, (block, block_results))) in
+                    variant.cases.iter().zip(blocks).enumerate()
+                {
+                    let constructor = variants::constructor_name(variant_name, &case.name);
+                    let payload = block_results.first();
+                    quote_in! { cases =>
+                        $['\r']
+                        case $i:
+                            $block
+                            $value = $constructor($payload)
+                    }
+                }
+
                 quote_in! { self.body =>
                     $['\r']
-                    $result := int16($WAZERO_API_DECODE_I32($operand))
+                    var $value $typ
+                    switch $op {
+                        $cases
+                        default:
+                            $(return_error(&self.result, quote!($ERRORS_NEW("invalid variant discriminant")), default))
+                    }
                 };
-                results.push(Operand::SingleValue(result.into()));
+                let typ = GoType::UserDefined(variant_name.to_string());
+                results.push(Operand::SingleValue(value.into(), typ));
             }
-            // TODO: Validate the Go cast truncates the upper bits in the I32
-            Instruction::U16FromI32 => {
+            Instruction::EnumLift { .. } => todo!("implement instruction: {inst:?}"),
+            Instruction::Malloc { .. } => todo!("implement instruction: {inst:?}"),
+            Instruction::HandleLower { handle, .. } => {
                 let tmp = self.tmp();
-                let result = &format!("result{tmp}");
-                let operand = &operands[0];
-                quote_in! { self.body =>
-                    $['\r']
-                    $result := uint16($WAZERO_API_DECODE_U32($operand))
+                let op = &operands[0];
+                let value = &format!("handle{tmp}");
+                match handle {
+                    // Guest resources must have been created by the instance
+                    // they are passed to. Borrowed ones are passed as the
+                    // guest's representation, which is what their methods
+                    // receive
+                    Handle::Own(id) | Handle::Borrow(id)
+                        if self.is_guest_resource(resolve, *id) =>
+                    {
+                        let module = match self.direction {
+                            Direction::Export { .. } => quote!(i.module),
+                            Direction::Import { .. } => quote!(mod),
+                        };
+                        let err = &format!("err{tmp}");
+                        let rep = match handle {
+                            Handle::Borrow(_) => value.as_str(),
+                            Handle::Own(_) => "_",
+                        };
+                        let lookup = guest_resource_rep(
+                            resource_name(resolve, *id),
+                            module,
+                            quote!($op.handle),
+                        );
+                        let check = check_error(
+                            &self.direction,
+                            &self.result,
+                            quote!($err != nil),
+                            quote!($err),
+                            &format!("default{tmp}"),
+                        );
+                        quote_in! { self.body =>
+                            $['\r']
+                            $rep, $err := $lookup
+                            $check
+                        };
+                        if let Handle::Own(_) = handle {
+                            quote_in! { self.body =>
+                                $['\r']
+                                $value := $op.handle
+                            }
+                        }
+                    }
+                    // Host resources are given a handle in the table of the
+                    // factory, which a borrowed one is only valid for during
+                    // the call
+                    Handle::Own(id) | Handle::Borrow(id) => {
+                        let table = self.host_resource_table(resource_name(resolve, *id));
+                        quote_in! { self.body =>
+                            $['\r']
+                            $value := $(&table).$(member("insert"))($op.resource)
+                        };
+                        if let (Handle::Borrow(_), Direction::Export { .. }) =
+                            (handle, &self.direction)
+                        {
+                            quote_in! { self.body =>
+                                $['\r']
+                                defer $table.$(member("remove"))($value)
+                            };
+                        }
+                    }
                 };
-                results.push(Operand::SingleValue(result.into()));
+                results.push(Operand::SingleValue(value.into(), GoType::Uint32));
             }
-            Instruction::S32FromI32 => {
-                let tmp = self.tmp();
-                let result = &format!("result{tmp}");
-                let operand = &operands[0];
-                quote_in! { self.body =>
-                    $['\r']
-                    $result := $WAZERO_API_DECODE_I32($operand)
+            Instruction::HandleLift { handle, .. } => {
+                let op = &operands[0];
+                let (typ, go_type) = match handle {
+                    Handle::Own(id) => {
+                        let name = resource_name(resolve, *id);
+                        (own_name(name), GoType::OwnHandle(name.to_string()))
+                    }
+                    Handle::Borrow(id) => {
+                        let name = resource_name(resolve, *id);
+                        (borrow_name(name), GoType::BorrowHandle(name.to_string()))
+                    }
                 };
-                results.push(Operand::SingleValue(result.into()));
+                let tmp = self.tmp();
+                let value = &format!("handle{tmp}");
+                let (Handle::Own(id) | Handle::Borrow(id)) = handle;
+                if self.is_guest_resource(resolve, *id) {
+                    quote_in! { self.body =>
+                        $['\r']
+                        $value := $typ{handle: $(op.convert(&GoType::Uint32))}
+                    };
+                } else {
+                    // Guests may only pass the handles the factory gave them
+                    // to resources implemented by the host
+                    let name = resource_name(resolve, *id);
+                    let resource = &format!("resource{tmp}");
+                    let err = &format!("err{tmp}");
+                    let lookup = host_resource(
+                        name,
+                        self.host_resource_table(name),
+                        op.convert(&GoType::Uint32),
+                        matches!(handle, Handle::Own(_)),
+                    );
+                    let check = check_error(
+                        &self.direction,
+                        &self.result,
+                        quote!($err != nil),
+                        quote!($err),
+                        &format!("default{tmp}"),
+                    );
+                    quote_in! { self.body =>
+                        $['\r']
+                        $resource, $err := $lookup
+                        $check
+                        $value := $typ{resource: $resource}
+                    };
+                }
+                results.push(Operand::SingleValue(value.into(), go_type));
+            }
+            Instruction::ListCanonLower { realloc: None, .. } => {
+                todo!("implement instruction: {inst:?}")
             }
-            Instruction::S64FromI64 => todo!("implement instruction: {inst:?}"),
-            Instruction::U64FromI64 => todo!("implement instruction: {inst:?}"),
-            Instruction::CharFromI32 => todo!("implement instruction: {inst:?}"),
-            Instruction::F32FromCoreF32 => {
+            Instruction::ListCanonLower {
+                element,
+                realloc: Some(realloc_name),
+            } => {
                 let tmp = self.tmp();
+                let vec = &format!("vec{tmp}");
+                let data = &format!("data{tmp}");
                 let result = &format!("result{tmp}");
+                let err = &format!("err{tmp}");
+                let default = &format!("default{tmp}");
+                let ptr = &format!("ptr{tmp}");
+                let len = &format!("len{tmp}");
                 let operand = &operands[0];
+                let size = self.sizes.size(element).size_wasm32();
+                let align = self.sizes.align(element).align_wasm32();
+                let memory = &self.memory();
+                let realloc = &self.realloc(realloc_name);
+
+                // Byte slices are written as is, while other numbers are encoded into a
+                // single buffer first, so the memory is only written once
                 quote_in! { self.body =>
                     $['\r']
-                    $result := $WAZERO_API_DECODE_F32($operand)
+                    $vec := $operand
+                    $len := uint64(len($vec))
+                    $result, $err := $(self.backend.call(realloc, quote!(0, 0, $align, $len * $size)))
+                    $(check_error(&self.direction, &self.result, quote!($err != nil), quote!($err), default))
+                    $ptr := $result[0]
+                    $(if **element == Type::U8 {
+                        $data := $vec
+                    } else {
+                        $data, $err := $ENCODING_BINARY_APPEND(nil, $ENCODING_BINARY_LITTLE_ENDIAN, $vec)
+                        $(check_error(&self.direction, &self.result, quote!($err != nil), quote!($err), default))
+                    })
+                    $(check_error(&self.direction, &self.result, quote!(!$(self.backend.write(memory, quote!(uint32($ptr)), quote!($data)))), quote!($ERRORS_NEW("failed to write list to memory")), default))
                 };
-                results.push(Operand::SingleValue(result.into()));
+                results.push(Operand::SingleValue(ptr.into(), self.backend.value_type()));
+                results.push(Operand::SingleValue(len.into(), GoType::Uint64));
             }
-            Instruction::F64FromCoreF64 => {
+            Instruction::ListCanonLift { element, .. } => {
                 let tmp = self.tmp();
+                let buf = &format!("buf{tmp}");
+                let ok = &format!("ok{tmp}");
+                let err = &format!("err{tmp}");
+                let default = &format!("default{tmp}");
                 let result = &format!("result{tmp}");
-                let operand = &operands[0];
+                let ptr = &operands[0];
+                let len = &operands[1];
+                let size = self.sizes.size(element).size_wasm32();
+                let slice = GoType::Slice(Box::new(resolve_type(element, resolve)));
+                let memory = &self.memory();
+
+                // The memory is copied out of the guest, as it may be reused once the
+                // call returns
                 quote_in! { self.body =>
                     $['\r']
-                    $result := $WAZERO_API_DECODE_F64($operand)
+                    $buf, $ok := $(self.backend.read(memory, ptr.convert(&GoType::Uint32), quote!($(len.convert(&GoType::Uint32)) * $size)))
+                    $(handle_error(&self.result, quote!(!$ok), quote!($ERRORS_NEW("failed to read list from memory")), default))
+                    $result := make($(&slice), $len)
+                    $(if **element == Type::U8 {
+                        copy($result, $buf)
+                    } else {
+                        if _, $err := $ENCODING_BINARY_DECODE($buf, $ENCODING_BINARY_LITTLE_ENDIAN, $result); $err != nil {
+                            $(return_error(&self.result, quote!($err), default))
+                        }
+                    })
                 };
-                results.push(Operand::SingleValue(result.into()));
-            }
-            Instruction::TupleLower { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::TupleLift { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::FlagsLower { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::FlagsLift { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::VariantLift { .. } => {
-                todo!("implement instruction: {inst:?}")
-            }
-            Instruction::EnumLift { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::Malloc { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::HandleLower { .. } | Instruction::HandleLift { .. } => {
-                todo!("implement resources: {inst:?}")
-            }
-            Instruction::ListCanonLower { .. } | Instruction::ListCanonLift { .. } => {
-                unimplemented!("gravity doesn't represent lists as Canonical")
+                results.push(Operand::SingleValue(result.into(), slice));
             }
             Instruction::GuestDeallocateString
             | Instruction::GuestDeallocate { .. }
@@ -1243,10 +2360,66 @@ impl Bindgen for Func<'_> {
             Instruction::FutureLift { .. } => todo!("implement instruction: {inst:?}"),
             Instruction::StreamLower { .. } => todo!("implement instruction: {inst:?}"),
             Instruction::StreamLift { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::ErrorContextLower => todo!("implement instruction: {inst:?}"),
-            Instruction::ErrorContextLift => todo!("implement instruction: {inst:?}"),
+            Instruction::ErrorContextLower => {
+                let tmp = self.tmp();
+                let handle = &format!("handle{tmp}");
+                let operand = &operands[0];
+                quote_in! { self.body =>
+                    $['\r']
+                    $handle := $ERROR_CONTEXTS.$(member("insert"))($operand)
+                }
+                results.push(Operand::SingleValue(handle.into(), GoType::Uint32));
+            }
+            Instruction::ErrorContextLift => {
+                let tmp = self.tmp();
+                let value = &format!("value{tmp}");
+                let operand = &operands[0];
+                quote_in! { self.body =>
+                    $['\r']
+                    $value := $ERROR_CONTEXTS.$(member("get"))($(operand.convert(&GoType::Uint32)))
+                }
+                results.push(Operand::SingleValue(value.into(), GoType::ErrorContext));
+            }
             Instruction::AsyncTaskReturn { .. } => todo!("implement instruction: {inst:?}"),
-            Instruction::DropHandle { .. } => todo!("implement instruction: {inst:?}"),
+            Instruction::DropHandle { ty } => {
+                let op = &operands[0];
+                let Some(handle) = handle_of(resolve, ty) else {
+                    todo!("implement instruction: {inst:?}")
+                };
+                match handle {
+                    Handle::Own(id) if self.is_guest_resource(resolve, id) => {
+                        let tmp = self.tmp();
+                        let err = &format!("err{tmp}");
+                        let default = &format!("default{tmp}");
+                        let check = match self.direction {
+                            Direction::Export { .. } => handle_error(
+                                &self.result,
+                                quote!($err != nil),
+                                quote!($err),
+                                default,
+                            ),
+                            Direction::Import { .. } => quote! {
+                                if $err != nil {
+                                    panic($err)
+                                }
+                            },
+                        };
+                        quote_in! { self.body =>
+                            $['\r']
+                            $err := $op.Drop(ctx)
+                            $check
+                        }
+                    }
+                    Handle::Own(_) => {
+                        quote_in! { self.body =>
+                            $['\r']
+                            $op.Close()
+                        }
+                    }
+                    // Borrowed handles don't own the resource
+                    Handle::Borrow(_) => {}
+                }
+            }
             Instruction::Flush { amt } => {
                 for op in operands.iter().take(*amt) {
                     results.push(op.clone());
@@ -1274,8 +2447,21 @@ impl Bindgen for Func<'_> {
         self.sizes
     }
 
-    fn is_list_canonical(&self, _resolve: &Resolve, _element: &Type) -> bool {
-        // Go slices are never directly in the Wasm Memory, so they are never "canonical"
-        false
+    fn is_list_canonical(&self, _resolve: &Resolve, element: &Type) -> bool {
+        // Go slices are never directly in the Wasm Memory, but slices of numbers have
+        // the same layout once encoded, so they can be copied in bulk
+        matches!(
+            element,
+            Type::U8
+                | Type::S8
+                | Type::U16
+                | Type::S16
+                | Type::U32
+                | Type::S32
+                | Type::U64
+                | Type::S64
+                | Type::F32
+                | Type::F64
+        )
     }
 }
@@ -0,0 +1,278 @@
+use crate::compat::wit_parser::{Function, Resolve, World};
+use genco::{prelude::*, tokens::static_literal};
+
+use crate::{
+    codegen::{
+        ir::AnalyzedImports,
+        symbols::{SymbolTable, export_methods},
+    },
+    go::{
+        GoIdentifier, GoType, comment,
+        imports::{
+            CONTEXT_BACKGROUND, REFLECT_VALUE, REFLECT_VALUE_OF, REFLECT_ZERO, STRINGS_REPEAT,
+            TESTING_F, TESTING_T,
+        },
+    },
+    resolve_type,
+};
+
+/// Generator for Go fuzz targets calling the exports taking strings or bytes
+/// with random inputs, to find bugs in passing them through the guest's
+/// memory.
+///
+/// The targets are in the package of the bindings, so they're meant to be
+/// written to a `_test.go` file next to them. The factory is created with the
+/// imports left to the stubs.
+pub struct FuzzGenerator<'a> {
+    resolve: &'a Resolve,
+    world: &'a World,
+    analyzed: &'a AnalyzedImports,
+}
+
+/// An export called by a fuzz target.
+struct FuzzTarget<'a> {
+    func: &'a Function,
+    /// The name of the fuzz target, e.g. `FuzzGreeterGreet`.
+    name: GoIdentifier,
+    /// The Go expression of the method implementing the export.
+    method: Tokens<Go>,
+    /// The names and Go types of the parameters of the export.
+    params: Vec<(GoIdentifier, GoType)>,
+}
+
+impl<'a> FuzzGenerator<'a> {
+    /// Create a new fuzz generator for the bindings of the world.
+    pub fn new(resolve: &'a Resolve, world: &'a World, analyzed: &'a AnalyzedImports) -> Self {
+        Self {
+            resolve,
+            world,
+            analyzed,
+        }
+    }
+
+    /// The exports the fuzzer can generate all the parameters of, as long as
+    /// one of them is a string or bytes.
+    ///
+    /// The functions of resources aren't fuzzed, as they need a resource
+    /// created by the guest.
+    fn targets(&self) -> Vec<FuzzTarget<'a>> {
+        let methods = export_methods(self.resolve, self.world, &mut SymbolTable::default());
+        let functions = methods.functions.iter().map(|method| {
+            let name = GoIdentifier::public(&method.name);
+            (
+                method.func,
+                GoIdentifier::public(format!("fuzz-{}", method.name)),
+                quote!(instance.$name),
+            )
+        });
+        let interfaces = methods.interfaces.iter().flat_map(|interface| {
+            let accessor = GoIdentifier::public(&interface.name);
+            interface.methods.iter().map(move |method| {
+                let name = GoIdentifier::public(&method.name);
+                (
+                    method.func,
+                    GoIdentifier::public(format!("fuzz-{}-{}", interface.name, method.name)),
+                    quote!(instance.$(&accessor)().$name),
+                )
+            })
+        });
+        functions
+            .chain(interfaces)
+            .filter(|(func, _, _)| func.kind.resource().is_none())
+            .filter_map(|(func, name, method)| {
+                let params = func
+                    .params
+                    .iter()
+                    .map(|(param, typ)| {
+                        let typ = resolve_type(typ, self.resolve);
+                        fuzzable(&typ).then(|| (param_name(param), typ))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                params
+                    .iter()
+                    .any(|(_, typ)| matches!(typ, GoType::String | GoType::Slice(_)))
+                    .then_some(FuzzTarget {
+                        func,
+                        name,
+                        method,
+                        params,
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Returns true if Go's fuzzer can generate values of the type.
+fn fuzzable(typ: &GoType) -> bool {
+    match typ {
+        GoType::Bool
+        | GoType::Uint8
+        | GoType::Uint16
+        | GoType::Uint32
+        | GoType::Uint64
+        | GoType::Int8
+        | GoType::Int16
+        | GoType::Int32
+        | GoType::Int64
+        | GoType::Float32
+        | GoType::Float64
+        | GoType::Rune
+        | GoType::String => true,
+        GoType::Slice(elem) => **elem == GoType::Uint8,
+        _ => false,
+    }
+}
+
+/// The name of the parameter of the fuzz function, which mustn't be the
+/// name of its `*testing.T`.
+fn param_name(name: &str) -> GoIdentifier {
+    if name == "t" {
+        GoIdentifier::local("t-arg")
+    } else {
+        GoIdentifier::local(name)
+    }
+}
+
+/// The values of the seed corpus of a parameter, i.e. its zero value, then
+/// invalid UTF-8 and a large input for strings and bytes.
+fn seeds(typ: &GoType) -> [Tokens<Go>; 3] {
+    match typ {
+        GoType::String => [
+            quote!(""),
+            quote!($(static_literal(r#""\xff""#))),
+            quote!($STRINGS_REPEAT("x", 1<<16)),
+        ],
+        GoType::Slice(_) => [
+            quote!([]byte{}),
+            quote!([]byte{0xff}),
+            quote!(make([]byte, 1<<16)),
+        ],
+        GoType::Bool => [quote!(false), quote!(false), quote!(false)],
+        typ => [quote!($typ(0)), quote!($typ(0)), quote!($typ(0))],
+    }
+}
+
+impl FormatInto<Go> for FuzzGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let factory = &self.analyzed.factory_name;
+        let constructor = &self.analyzed.constructor_name;
+        let new_factory = &GoIdentifier::private(format!("new-fuzz-{}", String::from(factory)));
+        let targets = self.targets();
+
+        quote_in! { *tokens =>
+            $(comment(&[
+                format!("{} creates the factory with the imports left to the", String::from(new_factory)),
+                "stubs, which is closed once the fuzz target is done.".to_string(),
+            ]))
+            func $new_factory(f *$TESTING_F) *$factory {
+                constructor := $REFLECT_VALUE_OF($constructor)
+                typ := constructor.Type()
+                args := []$REFLECT_VALUE{$REFLECT_VALUE_OF(f.Context())}
+                for i := 1; i < typ.NumIn(); i++ {
+                    args = append(args, $REFLECT_ZERO(typ.In(i)))
+                }
+                var results []$REFLECT_VALUE
+                if typ.IsVariadic() {
+                    results = constructor.CallSlice(args)
+                } else {
+                    results = constructor.Call(args)
+                }
+                if err, _ := results[1].Interface().(error); err != nil {
+                    f.Fatal(err)
+                }
+                factory := results[0].Interface().(*$factory)
+                f.Cleanup(func() { factory.Close($CONTEXT_BACKGROUND()) })
+                return factory
+            }
+        };
+        for target in targets {
+            let FuzzTarget {
+                func,
+                name,
+                method,
+                params,
+            } = &target;
+            let seeds = params.iter().map(|(_, typ)| seeds(typ)).collect::<Vec<_>>();
+            quote_in! { *tokens =>
+                $['\n']
+                $(comment(&[
+                    format!("{} calls the `{}` export", String::from(name), func.name),
+                    "with random inputs, failing if the bindings or the guest panic.".to_string(),
+                ]))
+                func $name(f *$TESTING_F) {
+                    factory := $new_factory(f)
+                    $(for i in 0..3 join ($['\r']) =>
+                        f.Add($(for seed in &seeds join (, ) => $(&seed[i])))
+                    )
+                    f.Fuzz(func(t *$TESTING_T, $(for (param, typ) in params join (, ) => $param $typ)) {
+                        instance, err := factory.Instantiate(t.Context())
+                        if err != nil {
+                            t.Fatal(err)
+                        }
+                        defer instance.Close(t.Context())
+
+                        $method(t.Context(), $(for (param, _) in params join (, ) => $param))
+                    })
+                }
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::wit_parser::Resolve;
+    use genco::prelude::*;
+
+    use crate::codegen::{fuzz::FuzzGenerator, imports::ImportAnalyzer};
+
+    #[test]
+    fn test_generate_fuzz_targets() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface greeter {
+                    resource counter {
+                        constructor(name: string);
+                    }
+
+                    greet: func(name: string, times: u32) -> string;
+                    count: func(n: u32) -> u32;
+                }
+
+                world fuzzed {
+                    export greeter;
+                    export parse: func(data: list<u8>, t: bool) -> result<u32, string>;
+                    export points: func(points: list<u32>, name: string);
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let analyzed = ImportAnalyzer::new(&resolve, world).analyze();
+
+        let mut tokens = Tokens::<Go>::new();
+        FuzzGenerator::new(&resolve, world, &analyzed).format_into(&mut tokens);
+        let generated = tokens.to_file_string().unwrap();
+
+        assert!(generated.contains("func newFuzzFuzzedFactory(f *testing.F) *FuzzedFactory {"));
+        assert!(generated.contains("func FuzzParse(f *testing.F) {"));
+        assert!(generated.contains("f.Add([]byte{}, false)"));
+        assert!(generated.contains("f.Add([]byte{0xff}, false)"));
+        assert!(generated.contains("f.Add(make([]byte, 1<<16), false)"));
+        assert!(generated.contains("f.Fuzz(func(t *testing.T, data []byte, tArg bool) {"));
+        assert!(generated.contains("instance.Parse(t.Context(), data, tArg)"));
+        assert!(generated.contains("func FuzzGreeterGreet(f *testing.F) {"));
+        assert!(generated.contains("f.Add(strings.Repeat(\"x\", 1<<16), uint32(0))"));
+        assert!(generated.contains("instance.Greeter().Greet(t.Context(), name, times)"));
+        // Exports without strings or bytes, or taking other lists, aren't fuzzed
+        assert!(!generated.contains("FuzzGreeterCount"));
+        assert!(!generated.contains("FuzzPoints"));
+        // The functions of resources need a resource created by the guest
+        assert!(!generated.contains("Counter"));
+    }
+}
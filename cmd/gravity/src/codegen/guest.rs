@@ -0,0 +1,383 @@
+use crate::compat::wit_parser::{
+    FunctionKind, Resolve, Type, TypeDefKind, WorldId, WorldItem, WorldKey,
+};
+use genco::prelude::*;
+
+use crate::go::{GoIdentifier, GoType, comment};
+
+/// The module of the core Wasm imports of functions imported by the world
+/// itself, rather than by an interface.
+const ROOT_MODULE: &str = "$root";
+
+/// The name of the helper lowering a `bool` to a core Wasm `i32`.
+const LOWER_BOOL: &str = "lower-bool";
+
+/// A function of the world, as called or implemented by the guest.
+struct GuestFunction {
+    /// The module and field of the core Wasm import, or the name of the core
+    /// Wasm export.
+    wasm_name: String,
+    /// The name of the function, or method of the exports, in Go.
+    go_name: GoIdentifier,
+    /// The name of the core Wasm function in Go.
+    core_name: GoIdentifier,
+    /// The names and types of the parameters, with aliases resolved.
+    params: Vec<(GoIdentifier, Type)>,
+    /// The type of the result, with aliases resolved.
+    result: Option<Type>,
+}
+
+/// Generates the guest bindings of a world, for components written in Go
+/// and compiled with TinyGo (or Go 1.24 and later).
+///
+/// Every import becomes a Go function calling the core Wasm import through
+/// `//go:wasmimport`, and the exports become methods of an interface that
+/// the guest implements and registers with `SetExports`, called through
+/// `//go:wasmexport`. The core module is then turned into a component with
+/// `wasm-tools component embed` and `wasm-tools component new`.
+///
+/// Only functions whose parameters and results are `bool`, integers or
+/// floats are supported, as they're passed directly as core Wasm values.
+/// Anything else, e.g. strings, lists or resources, is reported as an error.
+pub fn guest_bindings(resolve: &Resolve, world_id: WorldId) -> Result<Tokens<Go>, String> {
+    let world = &resolve.worlds[world_id];
+    let imports = guest_functions(resolve, world.imports.iter(), false)?;
+    let exports = guest_functions(resolve, world.exports.iter(), true)?;
+
+    let mut tokens = Tokens::new();
+    for import in &imports {
+        generate_import(import, &mut tokens);
+    }
+    if !exports.is_empty() {
+        generate_exports(
+            &GoIdentifier::public(format!("i-{}-exports", world.name)),
+            &exports,
+            &mut tokens,
+        );
+    }
+    let lowers_bool = imports
+        .iter()
+        .flat_map(|func| func.params.iter().map(|(_, typ)| typ))
+        .chain(exports.iter().flat_map(|func| &func.result))
+        .any(|typ| *typ == Type::Bool);
+    if lowers_bool {
+        quote_in! { tokens =>
+            $['\n']
+            func $(GoIdentifier::private(LOWER_BOOL))(value bool) uint32 {
+                if value {
+                    return 1
+                }
+                return 0
+            }
+        };
+    }
+    Ok(tokens)
+}
+
+/// The functions of the given world items, including those of interfaces.
+fn guest_functions<'a>(
+    resolve: &Resolve,
+    items: impl Iterator<Item = (&'a WorldKey, &'a WorldItem)>,
+    export: bool,
+) -> Result<Vec<GuestFunction>, String> {
+    let direction = if export { "wasmexport" } else { "wasmimport" };
+    let mut functions = Vec::new();
+    for (key, item) in items {
+        let (interface, funcs) = match item {
+            WorldItem::Function(func) => (None, vec![func]),
+            WorldItem::Interface { id, .. } => {
+                let interface = &resolve.interfaces[*id];
+                let name = match key {
+                    WorldKey::Name(name) => name.clone(),
+                    WorldKey::Interface(_) => {
+                        interface.name.clone().expect("interface missing name")
+                    }
+                };
+                let module = resolve.name_world_key(key);
+                (Some((name, module)), interface.functions.values().collect())
+            }
+            WorldItem::Type(_) => continue,
+        };
+        for func in funcs {
+            let qualified = match &interface {
+                Some((_, module)) => format!("{module}#{}", func.name),
+                None => func.name.clone(),
+            };
+            let go_name = match &interface {
+                Some((name, _)) => format!("{name}-{}", func.name),
+                None => func.name.clone(),
+            };
+            let wasm_name = match (&interface, export) {
+                (_, true) => qualified.clone(),
+                (Some((_, module)), false) => format!("{module} {}", func.name),
+                (None, false) => format!("{ROOT_MODULE} {}", func.name),
+            };
+            let unsupported = || {
+                format!(
+                    "unsupported function in guest bindings: {qualified}: only bool, integer and float parameters and results are supported"
+                )
+            };
+            if func.kind != FunctionKind::Freestanding {
+                return Err(unsupported());
+            }
+            let params = func
+                .params
+                .iter()
+                .map(|(name, typ)| Some((GoIdentifier::private(name), scalar(resolve, typ)?)))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(unsupported)?;
+            let result = match &func.result {
+                Some(typ) => Some(scalar(resolve, typ).ok_or_else(unsupported)?),
+                None => None,
+            };
+            functions.push(GuestFunction {
+                wasm_name,
+                core_name: GoIdentifier::private(format!("{direction}-{go_name}")),
+                go_name: GoIdentifier::public(go_name),
+                params,
+                result,
+            });
+        }
+    }
+    Ok(functions)
+}
+
+/// The type passed directly as a core Wasm value, following aliases, if
+/// supported.
+fn scalar(resolve: &Resolve, typ: &Type) -> Option<Type> {
+    match typ {
+        Type::Bool
+        | Type::U8
+        | Type::U16
+        | Type::U32
+        | Type::U64
+        | Type::S8
+        | Type::S16
+        | Type::S32
+        | Type::S64
+        | Type::F32
+        | Type::F64 => Some(*typ),
+        Type::Id(id) => match &resolve.types[*id].kind {
+            TypeDefKind::Type(typ) => scalar(resolve, typ),
+            _ => None,
+        },
+        Type::Char | Type::String | Type::ErrorContext => None,
+    }
+}
+
+/// The Go type of a supported WIT type.
+fn go_type(typ: &Type) -> GoType {
+    match typ {
+        Type::Bool => GoType::Bool,
+        Type::U8 => GoType::Uint8,
+        Type::U16 => GoType::Uint16,
+        Type::U32 => GoType::Uint32,
+        Type::U64 => GoType::Uint64,
+        Type::S8 => GoType::Int8,
+        Type::S16 => GoType::Int16,
+        Type::S32 => GoType::Int32,
+        Type::S64 => GoType::Int64,
+        Type::F32 => GoType::Float32,
+        Type::F64 => GoType::Float64,
+        _ => unreachable!("unsupported type in guest bindings"),
+    }
+}
+
+/// The Go type of the core Wasm value of a supported WIT type.
+fn core_type(typ: &Type) -> GoType {
+    match typ {
+        Type::U64 | Type::S64 => GoType::Uint64,
+        Type::F32 => GoType::Float32,
+        Type::F64 => GoType::Float64,
+        _ => GoType::Uint32,
+    }
+}
+
+/// Converts a core Wasm value to the Go value of the WIT type.
+fn lift(typ: &Type, value: Tokens<Go>) -> Tokens<Go> {
+    match typ {
+        Type::Bool => quote!($value != 0),
+        Type::U32 | Type::U64 | Type::F32 | Type::F64 => value,
+        typ => quote!($(go_type(typ))($value)),
+    }
+}
+
+/// Converts the Go value of the WIT type to a core Wasm value.
+fn lower(typ: &Type, value: Tokens<Go>) -> Tokens<Go> {
+    match typ {
+        Type::Bool => quote!($(GoIdentifier::private(LOWER_BOOL))($value)),
+        Type::U32 | Type::U64 | Type::F32 | Type::F64 => value,
+        typ => quote!($(core_type(typ))($value)),
+    }
+}
+
+/// The parameters and result of the core Wasm function.
+fn core_signature(func: &GuestFunction) -> Tokens<Go> {
+    quote! {
+        ($(for (i, (_, typ)) in func.params.iter().enumerate() join (, ) => arg$i $(core_type(typ))))$(if let Some(typ) = &func.result => $[' ']$(core_type(typ)))
+    }
+}
+
+/// Generates the Go function calling an imported function.
+fn generate_import(func: &GuestFunction, tokens: &mut Tokens<Go>) {
+    let core_name = &func.core_name;
+    let args = func
+        .params
+        .iter()
+        .map(|(name, typ)| lower(typ, quote!($name)))
+        .collect::<Vec<_>>();
+    let call = quote!($core_name($(for arg in args join (, ) => $arg)));
+    let (module, field) = func
+        .wasm_name
+        .split_once(' ')
+        .expect("import should have a module");
+    let description = match module {
+        ROOT_MODULE => format!("the `{field}` function imported by the component."),
+        module => format!("the `{field}` function of the imported `{module}` interface."),
+    };
+
+    quote_in! { *tokens =>
+        $['\n']
+        $(format!("//go:wasmimport {}", func.wasm_name))
+        func $core_name$(core_signature(func))
+        $['\n']
+        $(comment(&[format!("{} calls {description}", String::from(&func.go_name))]))
+        func $(&func.go_name)($(for (name, typ) in &func.params join (, ) => $name $(go_type(typ))))$(if let Some(typ) = &func.result => $[' ']$(go_type(typ))) {
+            $(match &func.result {
+                Some(typ) => return $(lift(typ, call)),
+                None => $call,
+            })
+        }
+    };
+}
+
+/// Generates the interface implemented by the guest for the exports, and
+/// the core Wasm exports calling it.
+fn generate_exports(
+    interface_name: &GoIdentifier,
+    exports: &[GuestFunction],
+    tokens: &mut Tokens<Go>,
+) {
+    quote_in! { *tokens =>
+        $['\n']
+        $(comment(&[
+            format!("{} is implemented by the guest, providing the functions", String::from(interface_name)),
+            "exported by the component.".to_string(),
+        ]))
+        type $interface_name interface {
+            $(for func in exports join ($['\r']) =>
+                $(&func.go_name)($(for (name, typ) in &func.params join (, ) => $name $(go_type(typ))))$(if let Some(typ) = &func.result => $[' ']$(go_type(typ)))
+            )
+        }
+        $['\n']
+        var exports $interface_name
+        $['\n']
+        $(comment(&[
+            "SetExports sets the implementation of the functions exported by the",
+            "component. It must be called before any of them are, e.g. from `init`.",
+        ]))
+        func SetExports(impl $interface_name) {
+            exports = impl
+        }
+    };
+
+    for func in exports {
+        let core_name = &func.core_name;
+        let args = func
+            .params
+            .iter()
+            .enumerate()
+            .map(|(i, (_, typ))| lift(typ, quote!(arg$i)))
+            .collect::<Vec<_>>();
+        let call = quote!(exports.$(&func.go_name)($(for arg in args join (, ) => $arg)));
+        quote_in! { *tokens =>
+            $['\n']
+            $(format!("//go:wasmexport {}", func.wasm_name))
+            func $core_name$(core_signature(func)) {
+                $(match &func.result {
+                    Some(typ) => return $(lower(typ, call)),
+                    None => $call,
+                })
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::wit_parser::Resolve;
+
+    use crate::codegen::guest::guest_bindings;
+
+    fn resolve(wit: &str) -> Resolve {
+        let mut resolve = Resolve::new();
+        resolve.push_str("test.wit", wit).unwrap();
+        resolve
+    }
+
+    #[test]
+    fn test_guest_bindings() {
+        let resolve = resolve(
+            r#"
+            package test:guest@0.1.0;
+
+            interface counter {
+                type count = u64;
+                add: func(delta: s32) -> count;
+            }
+
+            world example {
+                import counter;
+                import log-level: func(level: u8, verbose: bool);
+                export double: func(value: s8) -> s8;
+                export counter;
+            }
+            "#,
+        );
+        let (world_id, _) = resolve.worlds.iter().next().unwrap();
+        let output = guest_bindings(&resolve, world_id)
+            .unwrap()
+            .to_string()
+            .unwrap();
+        println!("{output}");
+
+        assert!(output.contains(
+            "//go:wasmimport test:guest/counter@0.1.0 add\nfunc wasmimportCounterAdd(arg0 uint32) uint64"
+        ));
+        assert!(output.contains(
+            "func CounterAdd(delta int32) uint64 {\n    return wasmimportCounterAdd(uint32(delta))\n}"
+        ));
+        assert!(output.contains(
+            "//go:wasmimport $root log-level\nfunc wasmimportLogLevel(arg0 uint32, arg1 uint32)"
+        ));
+        assert!(output.contains("    wasmimportLogLevel(uint32(level), lowerBool(verbose))"));
+        assert!(output.contains("type IExampleExports interface {\n    Double(value int8) int8\n    CounterAdd(delta int32) uint64\n}"));
+        assert!(output.contains(
+            "//go:wasmexport double\nfunc wasmexportDouble(arg0 uint32) uint32 {\n    return uint32(exports.Double(int8(arg0)))\n}"
+        ));
+        assert!(output.contains("//go:wasmexport test:guest/counter@0.1.0#add\n"));
+        assert!(output.contains("func lowerBool(value bool) uint32 {"));
+    }
+
+    #[test]
+    fn test_guest_bindings_unsupported() {
+        let resolve = resolve(
+            r#"
+            package test:guest;
+
+            world example {
+                export greet: func(name: string);
+            }
+            "#,
+        );
+        let (world_id, _) = resolve.worlds.iter().next().unwrap();
+        let Err(err) = guest_bindings(&resolve, world_id) else {
+            panic!("strings should be unsupported");
+        };
+
+        assert_eq!(
+            err,
+            "unsupported function in guest bindings: greet: only bool, integer and float parameters and results are supported"
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use crate::compat::wit_parser::{Function, Resolve, World};
+use genco::prelude::*;
+
+use crate::{
+    codegen::{
+        ir::AnalyzedImports,
+        symbols::{SymbolTable, export_methods},
+    },
+    go::{
+        GoIdentifier, comment,
+        imports::{
+            CONTEXT_BACKGROUND, CONTEXT_CONTEXT, FMT_FPRINTLN, FMT_PRINTLN, OS_EXIT, OS_STDERR,
+            REFLECT_TYPE_FOR, REFLECT_VALUE, REFLECT_VALUE_OF, REFLECT_ZERO,
+        },
+    },
+};
+
+/// Generator for a Go program smoke-testing the bindings, by instantiating
+/// the factory with the imports left to the stubs and calling each export
+/// with zero values.
+///
+/// The program is in package `main`, so it imports the bindings from the
+/// given import path. The arguments are filled in with reflection, so its
+/// code doesn't depend on the signatures of the exports.
+pub struct HarnessGenerator<'a> {
+    resolve: &'a Resolve,
+    world: &'a World,
+    analyzed: &'a AnalyzedImports,
+    /// The name of the Go package of the bindings.
+    package: &'a str,
+    /// The import path of the Go package of the bindings.
+    import_path: &'a str,
+}
+
+impl<'a> HarnessGenerator<'a> {
+    /// Create a new harness generator for the bindings of the world.
+    pub fn new(
+        resolve: &'a Resolve,
+        world: &'a World,
+        analyzed: &'a AnalyzedImports,
+        package: &'a str,
+        import_path: &'a str,
+    ) -> Self {
+        Self {
+            resolve,
+            world,
+            analyzed,
+            package,
+            import_path,
+        }
+    }
+
+    /// The WIT names of the exported functions, along with the Go
+    /// expressions of the methods implementing them.
+    ///
+    /// The functions of resources aren't called, as they need a resource
+    /// created by the guest.
+    fn exports(&self) -> Vec<(String, Tokens<Go>)> {
+        let methods = export_methods(self.resolve, self.world, &mut SymbolTable::default());
+        let callable = |func: &Function| func.kind.resource().is_none();
+        let mut exports = methods
+            .functions
+            .iter()
+            .filter(|method| callable(method.func))
+            .map(|method| {
+                let name = GoIdentifier::public(&method.name);
+                (method.func.name.clone(), quote!(instance.$name))
+            })
+            .collect::<Vec<_>>();
+        for interface in &methods.interfaces {
+            let accessor = &GoIdentifier::public(&interface.name);
+            let wit_name = self.resolve.name_world_key(interface.key);
+            exports.extend(
+                interface
+                    .methods
+                    .iter()
+                    .filter(|method| callable(method.func))
+                    .map(|method| {
+                        let name = GoIdentifier::public(&method.name);
+                        (
+                            format!("{wit_name}#{}", method.func.name),
+                            quote!(instance.$accessor().$name),
+                        )
+                    }),
+            );
+        }
+        exports
+    }
+}
+
+impl FormatInto<Go> for HarnessGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let package = self.package;
+        let factory = &self.analyzed.factory_name;
+        let constructor = &self.analyzed.constructor_name;
+        let exports = self.exports();
+
+        quote_in! { *tokens =>
+            import $package $(quoted(self.import_path))
+            $['\n']
+            $(comment(&[
+                "main instantiates the bindings, with the imports left to the stubs, and",
+                "calls each export with the zero values of its parameters, printing its",
+                "results. It exits with an error if any export fails.",
+            ]))
+            func main() {
+                $OS_EXIT(run($CONTEXT_BACKGROUND()))
+            }
+            $['\n']
+            $(comment(&["run smoke-tests the bindings, returning the exit code."]))
+            func run(ctx $CONTEXT_CONTEXT) int {
+                results := call($REFLECT_VALUE_OF($package.$constructor), ctx)
+                if err, _ := results[1].Interface().(error); err != nil {
+                    $FMT_FPRINTLN($OS_STDERR, "unable to create the factory:", err)
+                    return 1
+                }
+                factory := results[0].Interface().(*$package.$factory)
+                defer factory.Close(ctx)
+
+                instance, err := factory.Instantiate(ctx)
+                if err != nil {
+                    $FMT_FPRINTLN($OS_STDERR, "unable to instantiate the module:", err)
+                    return 1
+                }
+                defer instance.Close(ctx)
+
+                code := 0
+                $(for (name, method) in &exports join ($['\r']) =>
+                    if !invoke(ctx, $(quoted(name)), $REFLECT_VALUE_OF($method)) {
+                        code = 1
+                    }
+                )
+                return code
+            }
+            $['\n']
+            $(comment(&[
+                "invoke calls the export, printing its results, and returns false if it",
+                "returns an error or panics.",
+            ]))
+            func invoke(ctx $CONTEXT_CONTEXT, name string, export $REFLECT_VALUE) (ok bool) {
+                defer func() {
+                    if r := recover(); r != nil {
+                        $FMT_PRINTLN(name+":", "panic:", r)
+                        ok = false
+                    }
+                }()
+                values := []any{name + ":"}
+                for _, result := range call(export, ctx) {
+                    if result.Type() == $REFLECT_TYPE_FOR[error]() {
+                        if !result.IsNil() {
+                            $FMT_PRINTLN(name+":", "error:", result.Interface())
+                            return false
+                        }
+                        continue
+                    }
+                    values = append(values, result.Interface())
+                }
+                $FMT_PRINTLN(values...)
+                return true
+            }
+            $['\n']
+            $(comment(&[
+                "call calls the function with the context, followed by the zero values of",
+                "its other parameters.",
+            ]))
+            func call(fn $REFLECT_VALUE, ctx $CONTEXT_CONTEXT) []$REFLECT_VALUE {
+                typ := fn.Type()
+                args := []$REFLECT_VALUE{$REFLECT_VALUE_OF(ctx)}
+                for i := 1; i < typ.NumIn(); i++ {
+                    args = append(args, $REFLECT_ZERO(typ.In(i)))
+                }
+                if typ.IsVariadic() {
+                    return fn.CallSlice(args)
+                }
+                return fn.Call(args)
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::wit_parser::Resolve;
+    use genco::prelude::*;
+
+    use crate::codegen::{harness::HarnessGenerator, imports::ImportAnalyzer};
+
+    #[test]
+    fn test_generate_harness() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface greeter {
+                    resource counter {
+                        constructor();
+                        next: func() -> u32;
+                    }
+
+                    greet: func(name: string) -> string;
+                }
+
+                world smoke {
+                    export greeter;
+                    export run: func(ticks: u32) -> result<u32, string>;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let analyzed = ImportAnalyzer::new(&resolve, world).analyze();
+
+        let mut tokens = Tokens::<Go>::new();
+        HarnessGenerator::new(&resolve, world, &analyzed, "smoke", "example.com/app/smoke")
+            .format_into(&mut tokens);
+        let generated = tokens.to_file_string().unwrap();
+
+        assert!(generated.contains("import smoke \"example.com/app/smoke\""));
+        assert!(generated.contains("call(reflect.ValueOf(smoke.NewSmokeFactory), ctx)"));
+        assert!(generated.contains(".(*smoke.SmokeFactory)"));
+        assert!(generated.contains("invoke(ctx, \"run\", reflect.ValueOf(instance.Run))"));
+        assert!(generated.contains(
+            "invoke(ctx, \"test:pkg/greeter#greet\", reflect.ValueOf(instance.Greeter().Greet))"
+        ));
+        // The functions of resources need a resource created by the guest
+        assert!(!generated.contains("counter"));
+        assert!(!generated.contains("Next"));
+    }
+}
@@ -0,0 +1,181 @@
+use std::{cell::RefCell, collections::BTreeSet};
+
+use genco::{lang::go, prelude::*};
+
+use crate::go::GoIdentifier;
+
+/// The import path of the Go package of the helpers published along with
+/// gravity, which is versioned with the `github.com/arcjet/gravity` module.
+pub const RUNTIME_PACKAGE: &str = "github.com/arcjet/gravity/gravityruntime";
+
+thread_local! {
+    static PACKAGE: RefCell<Option<String>> = const { RefCell::new(None) };
+    static USED: RefCell<Option<BTreeSet<String>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, importing the helpers shared by the bindings of every world,
+/// such as `writeString` and the tables of resources, from the Go package
+/// with the given import path rather than generating them into the file.
+///
+/// The package must export the helpers of the version of gravity generating
+/// the bindings, under their names capitalized, e.g. [`RUNTIME_PACKAGE`].
+/// The helpers access the guest's memory with Wazero, so the package can't
+/// be used with another backend.
+pub fn with_runtime_package<T>(package: Option<String>, f: impl FnOnce() -> T) -> T {
+    let previous = PACKAGE.replace(package);
+    let result = f();
+    PACKAGE.set(previous);
+    result
+}
+
+/// Returns the import path of the runtime package the helpers are currently
+/// imported from, if any.
+pub(crate) fn runtime_package() -> Option<String> {
+    PACKAGE.with_borrow(Clone::clone)
+}
+
+/// Returns true if the helpers are imported from a runtime package, in which
+/// case they must not be generated.
+pub(crate) fn imported() -> bool {
+    PACKAGE.with_borrow(Option::is_some)
+}
+
+/// Runs `f`, returning the names of the helpers it generated code using,
+/// along with its result.
+pub(crate) fn record_helpers<T>(f: impl FnOnce() -> T) -> (T, BTreeSet<String>) {
+    let previous = USED.replace(Some(BTreeSet::new()));
+    let result = f();
+    let used = USED.replace(previous).unwrap_or_default();
+    (result, used)
+}
+
+/// A function, type or variable shared by the bindings of every world, which
+/// is either generated once per file or imported from the runtime package.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Helper {
+    /// The kebab-case name of the helper.
+    name: &'static str,
+    /// Whether the helper is exported when generated into the file, as it
+    /// appears in the bindings' API.
+    public: bool,
+}
+
+impl Helper {
+    const fn private(name: &'static str) -> Self {
+        Self {
+            name,
+            public: false,
+        }
+    }
+
+    const fn public(name: &'static str) -> Self {
+        Self { name, public: true }
+    }
+
+    /// The identifier declaring the helper when it's generated into the
+    /// file, prefixed by the symbol prefix, if any.
+    pub(crate) fn ident(&self) -> GoIdentifier {
+        if self.public {
+            GoIdentifier::prefixed_public(self.name)
+        } else {
+            GoIdentifier::prefixed_private(self.name)
+        }
+    }
+}
+
+/// Records that the code being generated uses the helper with the given Go
+/// name, which is done when formatting the shared helpers, but must be done
+/// explicitly for those generated for the types of a world.
+pub(crate) fn record_helper(name: String) {
+    USED.with_borrow_mut(|used| {
+        if let Some(used) = used {
+            used.insert(name);
+        }
+    });
+}
+
+impl FormatInto<Go> for Helper {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        record_helper(String::from(self.ident()));
+        PACKAGE.with_borrow(|package| match package {
+            Some(package) => tokens.append(go::import(
+                package.clone(),
+                String::from(GoIdentifier::public(self.name)),
+            )),
+            None => self.ident().format_into(tokens),
+        });
+    }
+}
+
+impl FormatInto<Go> for &Helper {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        (*self).format_into(tokens)
+    }
+}
+
+/// A method or field of a helper type, which is exported by the runtime
+/// package.
+pub(crate) fn member(name: &str) -> GoIdentifier {
+    if imported() {
+        GoIdentifier::public(name)
+    } else {
+        GoIdentifier::private(name)
+    }
+}
+
+pub(crate) const WRITE_STRING: Helper = Helper::private("write-string");
+pub(crate) const READ_STRING: Helper = Helper::private("read-string");
+pub(crate) const VIEW_STRING: Helper = Helper::private("view-string");
+pub(crate) const WRITE_STRING_UTF16: Helper = Helper::private("write-string-UTF16");
+pub(crate) const READ_STRING_UTF16: Helper = Helper::private("read-string-UTF16");
+pub(crate) const WRITE_STRING_LATIN1_UTF16: Helper = Helper::private("write-string-latin1-UTF16");
+pub(crate) const READ_STRING_LATIN1_UTF16: Helper = Helper::private("read-string-latin1-UTF16");
+pub(crate) const RESOURCE_TABLE: Helper = Helper::private("resource-table");
+pub(crate) const NEW_RESOURCE_TABLE: Helper = Helper::private("new-resource-table");
+pub(crate) const HOST_RESOURCE: Helper = Helper::private("host-resource");
+pub(crate) const NEW_HOST_RESOURCE: Helper = Helper::private("new-host-resource");
+pub(crate) const BORROW_HOST_RESOURCE: Helper = Helper::private("borrow-host-resource");
+pub(crate) const TAKE_HOST_RESOURCE: Helper = Helper::private("take-host-resource");
+pub(crate) const GUEST_RESOURCE: Helper = Helper::private("guest-resource");
+pub(crate) const DROP_GUEST_RESOURCE: Helper = Helper::private("drop-guest-resource");
+pub(crate) const GUEST_RESOURCE_REP: Helper = Helper::private("guest-resource-rep");
+pub(crate) const GUEST_RESOURCE_HANDLES: Helper = Helper::private("guest-resource-handles");
+pub(crate) const RELEASE_GUEST_RESOURCES: Helper = Helper::private("release-guest-resources");
+pub(crate) const STALE_HANDLE_ERROR: Helper = Helper::public("stale-handle-error");
+pub(crate) const GUEST_TRAP_ERROR: Helper = Helper::public("guest-trap-error");
+pub(crate) const GUEST_TRAP: Helper = Helper::private("guest-trap");
+pub(crate) const GUEST_ERROR: Helper = Helper::public("guest-error");
+pub(crate) const RESULT: Helper = Helper::public("result");
+pub(crate) const ERROR_CONTEXT: Helper = Helper::public("error-context");
+pub(crate) const ERROR_CONTEXTS: Helper = Helper::private("error-contexts");
+pub(crate) const IMPORT_BATCH: Helper = Helper::private("import-batch");
+pub(crate) const IMPORT_BATCH_KEY: Helper = Helper::private("import-batch-key");
+pub(crate) const BATCH_IMPORTS: Helper = Helper::private("batch-imports");
+pub(crate) const CALL_IMPORT: Helper = Helper::private("call-import");
+
+#[cfg(test)]
+mod tests {
+    use genco::prelude::*;
+
+    use super::{RUNTIME_PACKAGE, WRITE_STRING, member, with_runtime_package};
+
+    #[test]
+    fn test_runtime_package() {
+        let generated = quote!($WRITE_STRING(ctx, s, memory, realloc));
+        assert_eq!(
+            generated.to_string().unwrap(),
+            "writeString(ctx, s, memory, realloc)"
+        );
+
+        with_runtime_package(Some(RUNTIME_PACKAGE.to_string()), || {
+            let generated: Tokens<Go> = quote! {
+                $WRITE_STRING(ctx, s, memory, realloc)
+                table.$(member("insert"))(rep)
+            };
+            let file = generated.to_file_string().unwrap();
+            assert!(file.contains("import \"github.com/arcjet/gravity/gravityruntime\""));
+            assert!(file.contains("gravityruntime.WriteString(ctx, s, memory, realloc)"));
+            assert!(file.contains("table.Insert(rep)"));
+        });
+    }
+}
@@ -1,28 +1,50 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
-use genco::prelude::*;
-use wit_bindgen_core::{
-    abi::{AbiVariant, LiftLower},
+use crate::compat::{
+    abi::{AbiVariant, LiftLower, WasmType},
+    wit_component::StringEncoding,
     wit_parser::{
         Function, InterfaceId, Resolve, SizeAlign, Type, TypeDefKind, TypeId, World, WorldItem,
+        WorldKey,
     },
 };
+use genco::prelude::*;
 
 use crate::{
     codegen::{
+        backend::{RuntimeBackend, Wazero},
+        derives::{DefinedTypes, Derives},
+        docs::deprecation,
+        error_context::{INTRINSICS_MODULE, error_context_intrinsics},
         func::Func,
         ir::{
-            AnalyzedFunction, AnalyzedImports, AnalyzedInterface, AnalyzedType, InterfaceMethod,
-            Parameter, TypeDefinition, WitReturn,
+            AnalyzedImports, AnalyzedInterface, AnalyzedType, InterfaceMethod, Parameter,
+            TypeDefinition, WitReturn,
         },
+        parallel::par_map,
+        resources::{
+            GuestResourceGenerator, ResourceGenerator, ResourceInterfacesGenerator,
+            destructor_name, resource_drop,
+        },
+        stats::{FunctionStats, InterfaceStats},
+        strings::StringEncodings,
+        support::Support,
+        symbols::{SymbolTable, imported_interface_name, interface_methods},
+        variants::VariantGenerator,
     },
-    go::{
-        GoIdentifier, GoResult, GoType,
-        imports::{CONTEXT_CONTEXT, WAZERO_API_MODULE},
-    },
-    resolve_type,
+    go::{GoIdentifier, GoResult, GoType, comment, imports::CONTEXT_CONTEXT, renamed},
+    resolve_field_type, resolve_type, resolve_wasm_type, tuple_field,
 };
 
+/// The ways items are imported by a world, and whether their bindings can be
+/// generated, as listed by `gravity features`.
+pub(crate) const DIRECTIONS: &[(&str, Support)] = &[
+    ("imported interface", Support::Supported),
+    ("imported function", Support::Supported),
+    ("imported type", Support::Supported),
+    ("imported resource", Support::Supported),
+];
+
 /// Analyzer for imports - only does analysis, no code generation
 pub struct ImportAnalyzer<'a> {
     resolve: &'a Resolve,
@@ -38,7 +60,7 @@ impl<'a> ImportAnalyzer<'a> {
         let world_imports = &self.world.imports;
         let mut interfaces = Vec::new();
         let mut standalone_types = Vec::new();
-        let mut standalone_functions = Vec::new();
+        let mut functions = Vec::new();
 
         for (_import_name, world_item) in world_imports.iter() {
             match world_item {
@@ -50,36 +72,71 @@ impl<'a> ImportAnalyzer<'a> {
                         standalone_types.push(t);
                     }
                 }
-                WorldItem::Function(func) => {
-                    standalone_functions.push(self.analyze_function(func));
-                }
+                WorldItem::Function(func) => functions.push(func),
             }
         }
+        if !functions.is_empty() {
+            interfaces.push(self.analyze_functions(&functions));
+        }
+
+        let imported = world_imports
+            .values()
+            .filter_map(|item| match item {
+                WorldItem::Interface { id, .. } => Some(*id),
+                _ => None,
+            })
+            .collect::<BTreeSet<_>>();
+        let exported_types = self
+            .world
+            .exports
+            .iter()
+            .flat_map(|(key, item)| match item {
+                WorldItem::Interface { id, .. } if !imported.contains(id) => {
+                    self.resolve.interfaces[*id]
+                        .types
+                        .values()
+                        .filter_map(|id| self.analyze_exported_type(key, *id))
+                        .collect()
+                }
+                WorldItem::Type(id) => self.analyze_type(*id).into_iter().collect(),
+                _ => vec![],
+            })
+            .collect();
 
         // Generate factory-related identifiers
         let factory_name = GoIdentifier::public(format!("{}-factory", self.world.name));
         let instance_name = GoIdentifier::public(format!("{}-instance", self.world.name));
         let constructor_name = GoIdentifier::public(format!("new-{}-factory", self.world.name));
+        let imports_name = GoIdentifier::public(format!("{}-imports", self.world.name));
+        let hooks_name = GoIdentifier::private(format!("{}-hooks", self.world.name));
 
         AnalyzedImports {
             interfaces,
             standalone_types,
-            standalone_functions,
+            exported_types,
             factory_name,
             instance_name,
             constructor_name,
+            imports_name,
+            hooks_name,
         }
     }
 
     fn analyze_interface(&self, interface_id: InterfaceId) -> AnalyzedInterface {
         let interface = &self.resolve.interfaces[interface_id];
-        let interface_name = interface.name.as_ref().expect("interface missing name");
+        let interface_name = &imported_interface_name(self.resolve, self.world, interface_id);
 
-        // Analyze methods
+        // Analyze methods, naming them so that they don't collide
+        let names = interface_methods(
+            self.resolve,
+            interface.functions.values(),
+            &mut SymbolTable::default(),
+        );
         let methods = interface
             .functions
             .values()
-            .map(|func| self.analyze_interface_method(func, interface_name))
+            .zip(names)
+            .map(|(func, name)| self.analyze_interface_method(func, name))
             .collect();
 
         // Analyze interface types
@@ -91,16 +148,24 @@ impl<'a> ImportAnalyzer<'a> {
 
         // Generate names
         let go_interface_name =
-            GoIdentifier::public(format!("i-{}-{}", self.world.name, interface_name));
-
+            GoIdentifier::public(format!("i-{}-{}", self.world.name, renamed(interface_name)));
+        let resources_name = GoIdentifier::public(format!(
+            "{}-{}-resources",
+            self.world.name,
+            renamed(interface_name)
+        ));
+
+        // The module is named after the interface itself, even if its Go
+        // identifiers are qualified by its package
+        let wit_name = interface.name.as_ref().expect("interface missing name");
         let wazero_module_name = if let Some(package_id) = interface.package {
             let package = &self.resolve.packages[package_id];
             format!(
                 "{}:{}/{}",
-                package.name.namespace, package.name.name, interface_name
+                package.name.namespace, package.name.name, wit_name
             )
         } else {
-            interface_name.to_string()
+            wit_name.to_string()
         };
 
         AnalyzedInterface {
@@ -109,11 +174,56 @@ impl<'a> ImportAnalyzer<'a> {
             types,
             constructor_param_name: GoIdentifier::private(interface_name),
             go_interface_name,
+            resources_name,
             wazero_module_name,
         }
     }
 
-    fn analyze_interface_method(&self, func: &Function, _interface_name: &str) -> InterfaceMethod {
+    /// Analyzes the functions imported by the world itself, rather than by
+    /// one of its interfaces, which the host implements as the methods of a
+    /// single Go interface, as if they were imported by an interface named
+    /// `functions` from the `$root` module.
+    fn analyze_functions(&self, functions: &[&Function]) -> AnalyzedInterface {
+        // The functions are named after the world if an imported interface is
+        // already named `functions`
+        let taken = self.world.imports.values().any(|item| match item {
+            WorldItem::Interface { id, .. } => {
+                imported_interface_name(self.resolve, self.world, *id) == "functions"
+            }
+            _ => false,
+        });
+        let name = if taken {
+            format!("{}-functions", self.world.name)
+        } else {
+            "functions".to_string()
+        };
+        let names = interface_methods(
+            self.resolve,
+            functions.iter().copied(),
+            &mut SymbolTable::default(),
+        );
+        let methods = functions
+            .iter()
+            .zip(names)
+            .map(|(func, name)| self.analyze_interface_method(func, name))
+            .collect();
+
+        AnalyzedInterface {
+            methods,
+            types: vec![],
+            constructor_param_name: GoIdentifier::private(&name),
+            go_interface_name: GoIdentifier::public(format!("i-{}-{name}", self.world.name)),
+            resources_name: GoIdentifier::public(format!("{}-{name}-resources", self.world.name)),
+            wazero_module_name: INTRINSICS_MODULE.to_string(),
+            name,
+        }
+    }
+
+    fn analyze_interface_method(
+        &self,
+        func: &Function,
+        go_method_name: GoIdentifier,
+    ) -> InterfaceMethod {
         let parameters = func
             .params
             .iter()
@@ -131,7 +241,7 @@ impl<'a> ImportAnalyzer<'a> {
 
         InterfaceMethod {
             name: func.name.clone(),
-            go_method_name: GoIdentifier::public(&func.name),
+            go_method_name,
             parameters,
             return_type,
             wit_function: func.clone(),
@@ -152,6 +262,18 @@ impl<'a> ImportAnalyzer<'a> {
         })
     }
 
+    /// Analyzes a type of an exported interface, whose resources are
+    /// implemented by the guest rather than the host.
+    fn analyze_exported_type(&self, key: &WorldKey, type_id: TypeId) -> Option<AnalyzedType> {
+        let mut typ = self.analyze_type(type_id)?;
+        if let TypeDefinition::Resource = typ.definition {
+            typ.definition = TypeDefinition::GuestResource {
+                destructor: destructor_name(self.resolve, key, type_id),
+            };
+        }
+        Some(typ)
+    }
+
     /// Analyze a type definition and return an intermediate representation ready for
     /// codegen.
     ///
@@ -168,7 +290,7 @@ impl<'a> ImportAnalyzer<'a> {
                     .map(|field| {
                         (
                             GoIdentifier::public(&field.name),
-                            resolve_type(&field.ty, self.resolve),
+                            resolve_field_type(&field.ty, self.resolve),
                         )
                     })
                     .collect(),
@@ -195,21 +317,25 @@ impl<'a> ImportAnalyzer<'a> {
             TypeDefKind::Type(Type::String) => TypeDefinition::Alias {
                 target: GoType::String,
             },
-            TypeDefKind::Type(Type::Bool) => todo!("TODO(#4): generate bool type alias"),
-            TypeDefKind::Type(Type::U8) => todo!("TODO(#4): generate u8 type alias"),
-            TypeDefKind::Type(Type::U16) => todo!("TODO(#4): generate u16 type alias"),
-            TypeDefKind::Type(Type::U32) => todo!("TODO(#4): generate u32 type alias"),
-            TypeDefKind::Type(Type::U64) => todo!("TODO(#4): generate u64 type alias"),
-            TypeDefKind::Type(Type::S8) => todo!("TODO(#4): generate s8 type alias"),
-            TypeDefKind::Type(Type::S16) => todo!("TODO(#4): generate s16 type alias"),
-            TypeDefKind::Type(Type::S32) => todo!("TODO(#4): generate s32 type alias"),
-            TypeDefKind::Type(Type::S64) => todo!("TODO(#4): generate s64 type alias"),
-            TypeDefKind::Type(Type::F32) => todo!("TODO(#4): generate f32 type alias"),
-            TypeDefKind::Type(Type::F64) => todo!("TODO(#4): generate f64 type alias"),
-            TypeDefKind::Type(Type::Char) => todo!("TODO(#4): generate char type alias"),
-            TypeDefKind::Type(Type::ErrorContext) => {
-                todo!("TODO(#4): generate error context definition")
-            }
+            TypeDefKind::Type(
+                typ @ (Type::Bool
+                | Type::U8
+                | Type::U16
+                | Type::U32
+                | Type::U64
+                | Type::S8
+                | Type::S16
+                | Type::S32
+                | Type::S64
+                | Type::F32
+                | Type::F64
+                | Type::Char),
+            ) => TypeDefinition::Alias {
+                target: resolve_type(typ, self.resolve),
+            },
+            TypeDefKind::Type(Type::ErrorContext) => TypeDefinition::Alias {
+                target: GoType::ErrorContext,
+            },
             TypeDefKind::FixedSizeList(_, _) => {
                 todo!("TODO(#4): generate fixed size list definition")
             }
@@ -219,36 +345,21 @@ impl<'a> ImportAnalyzer<'a> {
             TypeDefKind::Future(_) => todo!("TODO(#4): generate future type definition"),
             TypeDefKind::Stream(_) => todo!("TODO(#4): generate stream type definition"),
             TypeDefKind::Flags(_) => todo!("TODO(#4):generate flags type definition"),
-            TypeDefKind::Tuple(_) => todo!("TODO(#4):generate tuple type definition"),
-            TypeDefKind::Resource => todo!("TODO(#5): implement resources"),
-            TypeDefKind::Handle(_) => todo!("TODO(#5): implement resources"),
+            // Named tuples are structs whose fields are named after their
+            // positions, e.g. `F0` and `F1`
+            TypeDefKind::Tuple(tuple) => TypeDefinition::Record {
+                fields: tuple
+                    .types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, typ)| (tuple_field(i), resolve_field_type(typ, self.resolve)))
+                    .collect(),
+            },
+            TypeDefKind::Resource => TypeDefinition::Resource,
+            TypeDefKind::Handle(_) => todo!("TODO(#5): generate handle type definition"),
             TypeDefKind::Unknown => panic!("cannot generate Unknown type"),
         })
     }
-
-    fn analyze_function(&self, func: &Function) -> AnalyzedFunction {
-        let parameters = func
-            .params
-            .iter()
-            .map(|(name, wit_type)| Parameter {
-                name: GoIdentifier::private(name),
-                go_type: resolve_type(wit_type, self.resolve),
-                wit_type: *wit_type,
-            })
-            .collect();
-
-        let return_type = func
-            .result
-            .as_ref()
-            .map(|wit_type| resolve_type(wit_type, self.resolve));
-
-        AnalyzedFunction {
-            name: func.name.clone(),
-            go_name: GoIdentifier::public(&func.name),
-            parameters,
-            return_type,
-        }
-    }
 }
 
 /// Code generator for imports - takes analysis results and generates Go code
@@ -256,6 +367,16 @@ pub struct ImportCodeGenerator<'a> {
     resolve: &'a Resolve,
     analyzed: &'a AnalyzedImports,
     sizes: &'a SizeAlign,
+    derives: Derives,
+    string_encodings: StringEncodings,
+    error_context: bool,
+    /// The WIT names of the interfaces whose functions without results are
+    /// batched.
+    batched_imports: BTreeSet<String>,
+    /// Whether the UTF-8 strings passed to the host functions are views of
+    /// the guest's memory.
+    string_views: bool,
+    backend: &'a dyn RuntimeBackend,
 }
 
 impl<'a> ImportCodeGenerator<'a> {
@@ -265,57 +386,210 @@ impl<'a> ImportCodeGenerator<'a> {
             resolve,
             analyzed,
             sizes,
+            derives: Derives::default(),
+            string_encodings: StringEncodings::default(),
+            error_context: false,
+            batched_imports: BTreeSet::new(),
+            string_views: false,
+            backend: &Wazero,
         }
     }
 
+    /// Sets the WIT names of the interfaces whose functions without results
+    /// are batched, i.e. queued until the export being called returns.
+    pub fn with_batched_imports(mut self, batched: BTreeSet<String>) -> Self {
+        self.batched_imports = batched;
+        self
+    }
+
+    /// Sets whether the UTF-8 strings passed to the host functions are views
+    /// of the guest's memory, which are only valid until they return, rather
+    /// than copies.
+    pub fn with_string_views(mut self, string_views: bool) -> Self {
+        self.string_views = string_views;
+        self
+    }
+
+    /// Sets the methods generated for the enums and records.
+    pub fn with_derives(mut self, derives: Derives) -> Self {
+        self.derives = derives;
+        self
+    }
+
+    /// Sets the encodings of the strings the host functions exchange with the
+    /// guest.
+    pub fn with_string_encodings(mut self, encodings: StringEncodings) -> Self {
+        self.string_encodings = encodings;
+        self
+    }
+
+    /// Sets the runtime the host functions access the guest with, which is
+    /// Wazero by default.
+    pub fn with_backend(mut self, backend: &'a dyn RuntimeBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Sets whether the guest uses the `error-context` intrinsics, which are
+    /// provided by the `$root` module along with the functions imported by
+    /// the world itself.
+    pub fn with_error_context(mut self, error_context: bool) -> Self {
+        self.error_context = error_context;
+        self
+    }
+
     /// Extract import chains for host module builders
+    ///
+    /// The chains of the interfaces are independent, so they're generated in
+    /// parallel.
     pub fn import_chains(&self) -> BTreeMap<String, Tokens<Go>> {
-        let mut chains = BTreeMap::new();
-
-        for (i, interface) in self.analyzed.interfaces.iter().enumerate() {
-            let err = &GoIdentifier::private(format!("err{i}"));
-            let mut chain = quote! {
-                _, $err := wazeroRuntime.NewHostModuleBuilder($(quoted(&interface.wazero_module_name))).
+        let mut chains = par_map(&self.analyzed.interfaces, |i, interface| {
+            let chain = self.import_chain(i, interface);
+            (interface.wazero_module_name.clone(), chain)
+        })
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+        if self.error_context && !chains.contains_key(INTRINSICS_MODULE) {
+            let chain = quote! {
+                _, errErrorContext := wazeroRuntime.NewHostModuleBuilder($(quoted(INTRINSICS_MODULE))).
+                $(error_context_intrinsics())
+                Instantiate(ctx)
+                if errErrorContext != nil {
+                    return nil, errErrorContext
+                }
             };
+            chains.insert(INTRINSICS_MODULE.to_string(), chain);
+        }
 
-            for method in &interface.methods {
-                chain.push();
-                let func_builder =
-                    self.generate_host_function_builder(method, &interface.constructor_param_name);
-                quote_in! { chain =>
-                    $func_builder
-                };
-            }
+        chains
+    }
 
+    /// Measures the code generated for each host function, grouped by the
+    /// interface they implement.
+    pub(crate) fn stats(&self) -> Vec<InterfaceStats> {
+        self.analyzed
+            .interfaces
+            .iter()
+            .map(|interface| InterfaceStats {
+                name: interface.wazero_module_name.clone(),
+                functions: interface
+                    .methods
+                    .iter()
+                    .map(|method| {
+                        let encoding = self
+                            .string_encodings
+                            .import(&interface.wazero_module_name, &method.name);
+                        FunctionStats::measure(&method.name, |tokens| {
+                            tokens.append(self.generate_host_function_builder(
+                                method,
+                                &interface.constructor_param_name,
+                                encoding,
+                                self.batched_imports.contains(&interface.wazero_module_name),
+                            ));
+                        })
+                    })
+                    .collect(),
+            })
+            .filter(|interface| !interface.functions.is_empty())
+            .collect()
+    }
+
+    /// Generates the host module builder of the `i`th imported interface.
+    fn import_chain(&self, i: usize, interface: &AnalyzedInterface) -> Tokens<Go> {
+        let err = &GoIdentifier::private(format!("err{i}"));
+        let mut chain = quote! {
+            _, $err := wazeroRuntime.NewHostModuleBuilder($(quoted(&interface.wazero_module_name))).
+        };
+
+        for method in &interface.methods {
             chain.push();
+            let encoding = self
+                .string_encodings
+                .import(&interface.wazero_module_name, &method.name);
+            let func_builder = self.generate_host_function_builder(
+                method,
+                &interface.constructor_param_name,
+                encoding,
+                self.batched_imports.contains(&interface.wazero_module_name),
+            );
             quote_in! { chain =>
-                Instantiate(ctx)
-                if $err != nil {
-                    return nil, $err
-                }
+                $func_builder
             };
+        }
 
-            chains.insert(interface.wazero_module_name.clone(), chain);
+        for typ in &interface.types {
+            if let TypeDefinition::Resource = typ.definition {
+                chain.push();
+                chain.append(resource_drop(&typ.name));
+            }
         }
 
-        chains
+        // A module can only be instantiated once, so the intrinsics are
+        // among the functions of the world
+        if self.error_context && interface.wazero_module_name == INTRINSICS_MODULE {
+            chain.push();
+            chain.append(error_context_intrinsics());
+        }
+
+        chain.push();
+        quote_in! { chain =>
+            Instantiate(ctx)
+            if $err != nil {
+                return nil, $err
+            }
+        };
+
+        chain
     }
 }
 
 impl FormatInto<Go> for ImportCodeGenerator<'_> {
     fn format_into(self, tokens: &mut Tokens<Go>) {
+        let mut types = DefinedTypes::default();
+        for typ in self
+            .analyzed
+            .interfaces
+            .iter()
+            .flat_map(|interface| &interface.types)
+            .chain(&self.analyzed.standalone_types)
+            .chain(&self.analyzed.exported_types)
+        {
+            let name = typ.name.clone();
+            match &typ.definition {
+                TypeDefinition::Enum { .. } => {
+                    types.enums.insert(name);
+                }
+                TypeDefinition::Record { .. } => {
+                    types.records.insert(name);
+                }
+                TypeDefinition::Variant { .. } => {
+                    types.variants.insert(name);
+                }
+                TypeDefinition::Alias { target } => {
+                    types.aliases.insert(name, target.clone());
+                }
+                _ => {}
+            }
+        }
+
         // Generate interface type definitions
         for interface in &self.analyzed.interfaces {
             self.generate_interface_type(interface, tokens);
 
             for typ in &interface.types {
-                self.generate_type_definition(typ, tokens);
+                self.generate_type_definition(typ, &types, tokens);
             }
+            ResourceInterfacesGenerator::new(self.resolve, interface).format_into(tokens);
         }
 
-        // Generate standalone types
-        for typ in &self.analyzed.standalone_types {
-            self.generate_type_definition(typ, tokens);
+        // Generate standalone types, and those of the exported interfaces
+        for typ in self
+            .analyzed
+            .standalone_types
+            .iter()
+            .chain(&self.analyzed.exported_types)
+        {
+            self.generate_type_definition(typ, &types, tokens);
         }
     }
 }
@@ -343,6 +617,7 @@ impl<'a> ImportCodeGenerator<'a> {
             .unwrap_or(GoResult::Empty);
 
         quote! {
+            $(if let Some(lines) = deprecation(&method.wit_function) => $(comment(lines)))
             $(&method.go_method_name)(
                 ctx $CONTEXT_CONTEXT,
                 $(for param in &method.parameters join ($['\r']) => $(&param.name) $(&param.go_type),)
@@ -350,7 +625,12 @@ impl<'a> ImportCodeGenerator<'a> {
         }
     }
 
-    fn generate_type_definition(&self, typ: &AnalyzedType, tokens: &mut Tokens<Go>) {
+    fn generate_type_definition(
+        &self,
+        typ: &AnalyzedType,
+        types: &DefinedTypes,
+        tokens: &mut Tokens<Go>,
+    ) {
         match &typ.definition {
             TypeDefinition::Record { fields } => {
                 quote_in! { *tokens =>
@@ -360,13 +640,14 @@ impl<'a> ImportCodeGenerator<'a> {
                             $field_name $field_type
                         )
                     }
+                    $(self.derives.record_methods(&typ.go_type_name, fields, types))
                 }
             }
             TypeDefinition::Enum { cases } => {
                 let enum_type = &GoIdentifier::private(&typ.name);
                 let enum_interface = &typ.go_type_name;
                 let enum_function = &GoIdentifier::private(format!("is-{}", &typ.name));
-                let variants = cases.iter().map(GoIdentifier::public);
+                let variants = cases.iter().map(|case| GoIdentifier::prefixed_public(case));
                 quote_in! { *tokens =>
                     $['\n']
                     type $(enum_interface) interface {
@@ -375,11 +656,12 @@ impl<'a> ImportCodeGenerator<'a> {
                     $['\n']
                     type $(enum_type) int
                     $['\n']
-                    func $(enum_type) $enum_function() {}
+                    func ($(enum_type)) $enum_function() {}
                     $['\n']
                     const (
                         $(for name in variants join ($['\r']) => $name $enum_type = iota)
                     )
+                    $(self.derives.enum_methods(&typ.name, cases))
                     $['\n']
                 }
             }
@@ -396,11 +678,23 @@ impl<'a> ImportCodeGenerator<'a> {
                     // Primitive type: $(typ.name)
                 }
             }
-            TypeDefinition::Variant { .. } => {
-                quote_in! { *tokens =>
-                    $['\n']
-                    // Variant type: $(typ.name) (TODO: implement)
+            TypeDefinition::Variant { cases } => {
+                let variant = VariantGenerator::new(&typ.name, &typ.go_type_name, cases);
+                if self.derives.equal {
+                    variant.with_equal(types).format_into(tokens)
+                } else {
+                    variant.format_into(tokens)
                 }
+                tokens.append(self.derives.variant_methods(
+                    &typ.name,
+                    &typ.go_type_name,
+                    cases,
+                    types,
+                ));
+            }
+            TypeDefinition::Resource => ResourceGenerator::new(&typ.name).format_into(tokens),
+            TypeDefinition::GuestResource { destructor } => {
+                GuestResourceGenerator::new(&typ.name, destructor).format_into(tokens)
             }
         }
     }
@@ -411,27 +705,37 @@ impl<'a> ImportCodeGenerator<'a> {
         // The name of the parameter representing the interface instance
         // in the generated function.
         param_name: &GoIdentifier,
+        string_encoding: StringEncoding,
+        // Whether the calls of the function, if it has no results, are
+        // queued until the export being called returns.
+        batched: bool,
     ) -> Tokens<Go> {
         let func_name = &method.name;
 
         // Generate Wasm function parameters based on WIT types.
         let wasm_params = vec![
             quote! { ctx $CONTEXT_CONTEXT },
-            quote! { mod $WAZERO_API_MODULE },
+            quote! { mod $(self.backend.module_type()) },
         ];
 
         let wasm_sig = self
             .resolve
             .wasm_signature(AbiVariant::GuestImport, &method.wit_function);
-        let result = if wasm_sig.results.is_empty() {
-            GoResult::Empty
-        } else {
-            todo!("implement handling of wasm signatures with results");
+        let result = match wasm_sig.results.as_slice() {
+            [] => GoResult::Empty,
+            [result] => GoResult::Anon(resolve_wasm_type(result)),
+            _ => todo!("implement handling of wasm signatures with multiple results"),
         };
-        let mut f = Func::import(param_name, result, self.sizes);
+        let param_types = wasm_sig.params.iter().map(host_param_type).collect();
+        let mut f = Func::import(param_name, param_types, result, self.sizes)
+            .with_backend(self.backend)
+            .with_method_name(&method.go_method_name)
+            .with_string_encoding(string_encoding)
+            .with_batched(batched)
+            .with_string_views(self.string_views);
 
         // Magic
-        wit_bindgen_core::abi::call(
+        crate::compat::abi::call(
             self.resolve,
             AbiVariant::GuestImport,
             LiftLower::LiftArgsLowerResults,
@@ -441,12 +745,17 @@ impl<'a> ImportCodeGenerator<'a> {
             false,
         );
 
+        let params = wasm_params.into_iter().chain(
+            f.args()
+                .iter()
+                .zip(&wasm_sig.params)
+                .map(|(param, typ)| quote!($param $(host_param_type(typ)))),
+        );
         quote! {
             NewFunctionBuilder().
             WithFunc(func(
-                $(for param in wasm_params join (,$['\r']) => $param),
-                $(for param in f.args() join (,$['\r']) => $param uint32),
-            ) $(f.result()){
+                $(for param in params join (,$['\r']) => $param),
+            ) $(f.result()) {
                 $(f.body())
             }).
             Export($(quoted(func_name))).
@@ -454,16 +763,33 @@ impl<'a> ImportCodeGenerator<'a> {
     }
 }
 
+/// The Go type of a core Wasm parameter of a host function.
+///
+/// Floats are passed as Go floats and `i64`s as `uint64`s, while the other
+/// integers, pointers and lengths are all read as `uint32`s.
+fn host_param_type(typ: &WasmType) -> GoType {
+    match typ {
+        WasmType::F32 => GoType::Float32,
+        WasmType::F64 => GoType::Float64,
+        WasmType::I64 => GoType::Uint64,
+        _ => GoType::Uint32,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use genco::prelude::*;
-    use wit_bindgen_core::wit_parser::{
-        Function, FunctionKind, Interface, Package, PackageName, Resolve, SizeAlign, Type, World,
-        WorldId, WorldItem, WorldKey,
+    use crate::compat::{
+        wit_component::StringEncoding,
+        wit_parser::{
+            Function, FunctionKind, Interface, Package, PackageName, Resolve, SizeAlign, Type,
+            World, WorldId, WorldItem, WorldKey,
+        },
     };
+    use genco::prelude::*;
 
     use crate::{
         codegen::{
+            conversions::with_conversions,
             imports::{ImportAnalyzer, ImportCodeGenerator},
             ir::{AnalyzedImports, InterfaceMethod, Parameter, WitReturn},
         },
@@ -489,10 +815,12 @@ mod tests {
         let analyzed = AnalyzedImports {
             instance_name: GoIdentifier::public("TestInstance"),
             interfaces: vec![],
-            standalone_functions: vec![],
+            exported_types: vec![],
             standalone_types: vec![],
             factory_name: GoIdentifier::public("TestFactory"),
             constructor_name: GoIdentifier::public("NewTestFactory"),
+            imports_name: GoIdentifier::public("test-imports"),
+            hooks_name: GoIdentifier::private("test-hooks"),
         };
 
         let generator = ImportCodeGenerator::new(&resolve, &analyzed, &sizes);
@@ -512,12 +840,18 @@ mod tests {
         };
 
         let param_name = GoIdentifier::private("handler");
-        let result = generator.generate_host_function_builder(&method, &param_name);
+        let result = generator.generate_host_function_builder(
+            &method,
+            &param_name,
+            StringEncoding::UTF8,
+            false,
+        );
 
         // The result should contain the WIT type-driven generation
         let code_str = result.to_string().unwrap();
         assert!(code_str.contains("NewFunctionBuilder"));
-        assert!(code_str.contains("mod.Memory().Read"));
+        assert!(code_str.contains("memory := mod.Memory()"));
+        assert!(code_str.contains("readString(memory, "));
         assert!(code_str.contains("writeString"));
 
         println!("Generated code:\n{}", code_str);
@@ -529,10 +863,12 @@ mod tests {
         let analyzed = AnalyzedImports {
             instance_name: GoIdentifier::public("TestInstance"),
             interfaces: vec![],
-            standalone_functions: vec![],
+            exported_types: vec![],
             standalone_types: vec![],
             factory_name: GoIdentifier::public("TestFactory"),
             constructor_name: GoIdentifier::public("NewTestFactory"),
+            imports_name: GoIdentifier::public("test-imports"),
+            hooks_name: GoIdentifier::private("test-hooks"),
         };
         let resolve = Resolve::new();
         let sizes = SizeAlign::default();
@@ -560,13 +896,18 @@ mod tests {
         };
 
         let param_name = GoIdentifier::private("handler");
-        let result = generator.generate_host_function_builder(&u32_method, &param_name);
+        let result = generator.generate_host_function_builder(
+            &u32_method,
+            &param_name,
+            StringEncoding::UTF8,
+            false,
+        );
 
         // Should have only one uint32 parameter (plus ctx and mod)
         let code_str = result.to_string().unwrap();
         assert!(code_str.contains("arg0 uint32"));
         assert!(!code_str.contains("arg1 uint32"));
-        assert!(!code_str.contains("mod.Memory().Read")); // No string reading
+        assert!(!code_str.contains("memory.Read")); // No string reading
 
         println!("U32 generated code:\n{}", code_str);
     }
@@ -636,7 +977,7 @@ mod tests {
         let (resolve, world_id) = create_test_world_with_interface();
         let world = &resolve.worlds[world_id];
 
-        let analyzer = ImportAnalyzer::new(&resolve, &world);
+        let analyzer = ImportAnalyzer::new(&resolve, world);
         let analyzed = analyzer.analyze();
 
         // Check that we got one interface
@@ -661,7 +1002,7 @@ mod tests {
         let sizes = SizeAlign::default();
 
         // Analyze
-        let analyzer = ImportAnalyzer::new(&resolve, &world);
+        let analyzer = ImportAnalyzer::new(&resolve, world);
         let analyzed = analyzer.analyze();
 
         // Generate
@@ -674,10 +1015,407 @@ mod tests {
         assert!(output.contains("Log("));
     }
 
+    #[test]
+    fn test_import_code_generator_derives() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface types {
+                    enum color { red, green }
+
+                    record pixel {
+                        x: u32,
+                        color: color,
+                    }
+
+                    variant shape {
+                        dots(list<pixel>),
+                        empty,
+                    }
+
+                    type nickname = string;
+
+                    record sprite {
+                        pixels: list<pixel>,
+                        label: option<string>,
+                        shape: shape,
+                        nicknames: list<nickname>,
+                    }
+
+                    get: func() -> sprite;
+                }
+
+                world test-world {
+                    import types;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let sizes = SizeAlign::default();
+
+        let analyzed = ImportAnalyzer::new(&resolve, world).analyze();
+        let generator = ImportCodeGenerator::new(&resolve, &analyzed, &sizes).with_derives(
+            crate::codegen::Derives {
+                string: true,
+                json: true,
+                equal: true,
+            },
+        );
+        let mut tokens = Tokens::<Go>::new();
+        generator.format_into(&mut tokens);
+
+        let output = tokens.to_string().unwrap();
+        assert!(output.contains("func (color) isColor() {}"));
+        assert!(output.contains("func (e color) String() string {"));
+        assert!(output.contains("func (e *color) UnmarshalJSON(data []byte) error {"));
+        assert!(output.contains("func (r Pixel) String() string {"));
+        assert!(output.contains("func (r *Pixel) UnmarshalJSON(data []byte) error {"));
+        assert!(output.contains("func (r Pixel) Equal(other Pixel) bool {\n    return r.X == other.X &&\n        r.Color == other.Color\n}"));
+        assert!(output.contains("    Equal(other Shape) bool\n}"));
+        assert!(output.contains("return ok && slices.EqualFunc(c.Value, o.Value, func(x, y Pixel) bool { return x.Equal(y) })"));
+        assert!(output.contains("func (ShapeEmpty) Equal(other Shape) bool {\n    _, ok := other.(ShapeEmpty)\n    return ok\n}"));
+        assert!(output.contains("(r.Shape == nil || r.Shape.Equal(other.Shape)) &&"));
+        // Variants are marshalled as their tagged cases, which records
+        // unmarshal their variant fields from
+        assert!(output.contains("func (c ShapeDots) String() string {"));
+        assert!(output.contains("func (c ShapeDots) MarshalJSON() ([]byte, error) {"));
+        assert!(output.contains("func UnmarshalShape(data []byte) (Shape, error) {"));
+        assert!(output.contains("func (r *Sprite) UnmarshalJSON(data []byte) error {"));
+        assert!(output.contains("Shape json.RawMessage"));
+        assert!(output.contains("value, err := UnmarshalShape(raw.Shape)"));
+        // Aliases are compared as the types they refer to
+        assert!(output.contains("slices.Equal(r.Nicknames, other.Nicknames)\n}"));
+    }
+
+    #[test]
+    fn test_imported_functions() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                world test-world {
+                    import log: func(msg: string);
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+
+        let analyzed = ImportAnalyzer::new(&resolve, world).analyze();
+        assert_eq!(analyzed.interfaces.len(), 1);
+        let interface = &analyzed.interfaces[0];
+        assert_eq!(interface.name, "functions");
+        assert_eq!(interface.wazero_module_name, "$root");
+        assert_eq!(
+            String::from(&interface.go_interface_name),
+            "ITestWorldFunctions"
+        );
+        assert_eq!(String::from(&interface.constructor_param_name), "functions");
+
+        let generator =
+            ImportCodeGenerator::new(&resolve, &analyzed, &sizes).with_error_context(true);
+        let chains = generator.import_chains();
+        assert_eq!(chains.len(), 1);
+        let chain = chains["$root"].to_string().unwrap();
+        assert!(chain.contains("Export(\"log\")."));
+        // The intrinsics are provided by the same module
+        assert!(chain.contains("Export(\"[error-context-drop]\")."));
+        assert_eq!(chain.matches("Instantiate(ctx)").count(), 1);
+    }
+
+    #[test]
+    fn test_import_returning_list_of_strings() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface fs {
+                    list-dir: func(path: string) -> list<string>;
+                    try-list-dir: func(path: string) -> result<list<string>, string>;
+                }
+
+                world test-world {
+                    import fs;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+
+        let analyzed = ImportAnalyzer::new(&resolve, world).analyze();
+        let chains = ImportCodeGenerator::new(&resolve, &analyzed, &sizes).import_chains();
+        let chain = chains["test:pkg/fs"].to_string().unwrap();
+
+        // The strings are written into the guest's memory, then the list of
+        // their pointers and lengths, which is written to the return area
+        assert!(chain.contains("value1 := fs.ListDir(ctx, str0)"));
+        assert!(chain.contains("result4, err4 := realloc.Call(ctx, 0, 0, 4, len4 * 8)"));
+        assert!(chain.contains(
+            "ptr3, len3, err3 := writeString(ctx, e, memory, realloc)\n        if err3 != nil {\n            panic(err3)\n        }"
+        ));
+        assert!(chain.contains(
+            "if !memory.WriteUint32Le(arg2+4, uint32(len4)) {\n        panic(errors.New(\"failed to write result to memory\"))\n    }"
+        ));
+        assert!(chain.contains(
+            "if !memory.WriteUint32Le(arg2+0, uint32(ptr4)) {\n        panic(errors.New(\"failed to write result to memory\"))\n    }"
+        ));
+
+        // Errors are written after the discriminant, as are the lists
+        assert!(chain.contains("value1, err1 := fs.TryListDir(ctx, str0)"));
+        assert!(chain.contains("writeString(ctx, variantPayload, memory, realloc)"));
+        assert!(chain.contains("vec4 := variantPayload\n"));
+        assert!(chain.contains("if !memory.WriteUint32Le(arg2+8, uint32(len4)) {"));
+        assert!(!chain.contains("Value"));
+    }
+
+    #[test]
+    fn test_optional_record_fields() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface profiles {
+                    record profile {
+                        age: option<u32>,
+                        nickname: option<option<string>>,
+                        scores: list<option<u32>>,
+                    }
+
+                    get: func() -> profile;
+                    set: func(profile: profile);
+                }
+
+                world test-world {
+                    import profiles;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+
+        let analyzed = ImportAnalyzer::new(&resolve, world).analyze();
+        let generator = ImportCodeGenerator::new(&resolve, &analyzed, &sizes);
+        let mut tokens = Tokens::<Go>::new();
+        generator.format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+        println!("{output}");
+
+        assert!(output.contains(
+            "type Profile struct {\n    Age *uint32\n\n    Nickname **string\n\n    Scores []*uint32\n}"
+        ));
+
+        let chain = with_conversions(&resolve, &sizes, |tokens| {
+            let chains = ImportCodeGenerator::new(&resolve, &analyzed, &sizes).import_chains();
+            tokens.append(chains["test:pkg/profiles"].clone());
+        })
+        .to_string()
+        .unwrap();
+        println!("{chain}");
+
+        // The options are lowered from their pointers, including the
+        // pointers they point to, by the helpers of the record and the
+        // options of its list
+        assert!(chain.contains("err12 := lowerProfile(ctx, memory, realloc, buf11, value0)"));
+        assert!(chain.contains("if age0 == nil {"));
+        assert!(chain.contains("variantPayload := *age0"));
+        assert!(chain.contains("if variantPayload == nil {"));
+        assert!(chain.contains("variantPayload := *variantPayload"));
+        assert!(chain.contains("lowerOptionU32(buf6[base6:], e)"));
+        assert!(chain.contains("if value == nil {"));
+
+        // And lifted to pointers, which are nil for `none`
+        assert!(chain.contains("var result5 *string"));
+        assert!(chain.contains("result11 := make([]*uint32, len11)"));
+        assert!(chain.contains("value12, ok12, err12 := liftOptionU32(memory, base6)"));
+        assert!(chain.contains("result11[idx11] = ptr13"));
+        assert!(
+            chain.contains("var ptr15 **string\n    if ok5 {\n        ptr15 = &result5\n    }")
+        );
+        assert!(chain.contains("Age: ptr14,"));
+    }
+
+    #[test]
+    fn test_result_record_fields() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface jobs {
+                    record job {
+                        count: result<u32, string>,
+                        done: result<_, string>,
+                        names: list<result<string, string>>,
+                    }
+
+                    get: func() -> job;
+                    set: func(job: job);
+                }
+
+                world test-world {
+                    import jobs;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+
+        let analyzed = ImportAnalyzer::new(&resolve, world).analyze();
+        let generator = ImportCodeGenerator::new(&resolve, &analyzed, &sizes);
+        let mut tokens = Tokens::<Go>::new();
+        generator.format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+        println!("{output}");
+
+        assert!(output.contains(
+            "type Job struct {\n    Count Result[uint32]\n\n    Done error\n\n    Names []Result[string]\n}"
+        ));
+
+        let chain = with_conversions(&resolve, &sizes, |tokens| {
+            let chains = ImportCodeGenerator::new(&resolve, &analyzed, &sizes).import_chains();
+            tokens.append(chains["test:pkg/jobs"].clone());
+        })
+        .to_string()
+        .unwrap();
+        println!("{chain}");
+
+        // The results are lowered from their `Result`s, and results without
+        // a value from their errors, by the helper of the record
+        assert!(chain.contains("err10 := lowerJob(ctx, memory, realloc, buf9, value0)"));
+        assert!(
+            chain.contains("if count0.Err != nil {\n        variantPayload := count0.Err.Error()")
+        );
+        assert!(chain.contains("variantPayload := count0.Value"));
+        assert!(chain.contains("if done0 != nil {"));
+        assert!(chain.contains("variantPayload := e.Value"));
+
+        // And lifted into them
+        assert!(chain.contains("result15 := make([]Result[string], len15)"));
+        assert!(chain.contains("nested16 := Result[string]{Value: value14, Err: err14}"));
+        assert!(chain.contains("result15[idx15] = nested16"));
+        assert!(chain.contains("Count: nested17,\n        Done: err5,"));
+    }
+
+    #[test]
+    fn test_tuple_type_generation() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface shapes {
+                    type point = tuple<f64, f64>;
+
+                    move-to: func(point: point);
+                }
+
+                world test-world {
+                    import shapes;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+
+        let analyzed = ImportAnalyzer::new(&resolve, world).analyze();
+        let generator = ImportCodeGenerator::new(&resolve, &analyzed, &sizes);
+        let mut tokens = Tokens::<Go>::new();
+        generator.format_into(&mut tokens);
+
+        let output = tokens.to_string().unwrap();
+        println!("{output}");
+        assert!(output.contains("type Point struct {\n    F0 float64\n\n    F1 float64\n}"));
+        assert!(output.contains("point Point,"));
+    }
+
+    #[test]
+    fn test_exported_type_generation() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface shared {
+                    record config { verbose: bool }
+                }
+
+                interface shapes {
+                    type id = u32;
+                    record point { x: u32 }
+
+                    area: func(point: point) -> id;
+                }
+
+                world test-world {
+                    type score = u32;
+
+                    import shared;
+                    export shared;
+                    export shapes;
+                    export best: func() -> score;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+
+        let analyzed = ImportAnalyzer::new(&resolve, world).analyze();
+        // The types of `shared` are generated along with the imports
+        let exported = analyzed
+            .exported_types
+            .iter()
+            .map(|typ| typ.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(exported, ["id", "point"]);
+
+        let generator = ImportCodeGenerator::new(&resolve, &analyzed, &sizes);
+        let mut tokens = Tokens::<Go>::new();
+        generator.format_into(&mut tokens);
+
+        let output = tokens.to_string().unwrap();
+        println!("{output}");
+        assert_eq!(output.matches("type Config struct {").count(), 1);
+        assert!(output.contains("type Score = uint32"));
+        assert!(output.contains("type Id = uint32"));
+        assert!(output.contains("type Point struct {\n    X uint32\n}"));
+    }
+
     #[test]
     fn test_record_type_generation() {
         use crate::codegen::ir::TypeDefinition;
-        use wit_bindgen_core::wit_parser::{Field, Record, TypeDef, TypeDefKind, TypeOwner};
+        use crate::compat::wit_parser::{Field, Record, TypeDef, TypeDefKind, TypeOwner};
 
         let mut resolve = Resolve::default();
 
@@ -774,7 +1512,7 @@ mod tests {
         let world = &resolve.worlds[world_id];
 
         // Test the analyzer first
-        let analyzer = ImportAnalyzer::new(&resolve, &world);
+        let analyzer = ImportAnalyzer::new(&resolve, world);
 
         // Test analyze_type_definition directly with the record kind
         let type_def = &resolve.types[type_id];
@@ -905,7 +1643,7 @@ mod tests {
             );
         }
 
-        if !output.contains("type Foo struct") && analyzed.interfaces[0].types.len() > 0 {
+        if !output.contains("type Foo struct") && !analyzed.interfaces[0].types.is_empty() {
             println!(
                 "❌ Generated code doesn't contain struct definition, but types were analyzed correctly"
             );
@@ -920,7 +1658,7 @@ mod tests {
     #[test]
     fn test_record_vs_alias_analysis() {
         use crate::codegen::ir::TypeDefinition;
-        use wit_bindgen_core::wit_parser::{Field, Record, TypeDef, TypeDefKind, TypeOwner};
+        use crate::compat::wit_parser::{Field, Record, TypeDef, TypeDefKind, TypeOwner};
 
         let mut resolve = Resolve::default();
 
@@ -996,7 +1734,7 @@ mod tests {
         let world_id = resolve.worlds.alloc(world);
         let world = &resolve.worlds[world_id];
 
-        let analyzer = ImportAnalyzer::new(&resolve, &world);
+        let analyzer = ImportAnalyzer::new(&resolve, world);
 
         // Test record analysis
         let record_def = &resolve.types[record_type_id];
@@ -0,0 +1,164 @@
+use genco::prelude::*;
+
+use crate::go::{
+    GoIdentifier, comment,
+    imports::{
+        IO_READER, IO_WRITER, WAZERO_FS_CONFIG, WAZERO_MODULE_CONFIG, WAZERO_NEW_FS_CONFIG,
+        WAZERO_NEW_MODULE_CONFIG,
+    },
+};
+
+/// The type of the options of the `Instantiate` methods.
+pub(crate) fn instance_option_type() -> GoIdentifier {
+    GoIdentifier::prefixed_public("instance-option")
+}
+
+/// The function returning the configuration of a module with the options.
+pub(crate) fn module_config() -> GoIdentifier {
+    GoIdentifier::prefixed_private("module-config")
+}
+
+/// Generator for the options configuring the sandbox of each instance, i.e.
+/// its directories, environment, arguments and standard streams, shared by
+/// all factories in the file.
+///
+/// The options configure the Wazero module config the instances are created
+/// with, which only guests importing `wasi_snapshot_preview1` see, once the
+/// host instantiates it in the runtime of the factory.
+pub struct InstanceOptionsGenerator;
+
+impl FormatInto<Go> for InstanceOptionsGenerator {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let option = &instance_option_type();
+        let options = &GoIdentifier::prefixed_private("instance-options");
+        let dir = &GoIdentifier::prefixed_public("with-dir");
+        let env = &GoIdentifier::prefixed_public("with-env");
+        let args = &GoIdentifier::prefixed_public("with-args");
+        let stdin = &GoIdentifier::prefixed_public("with-stdin");
+        let stdout = &GoIdentifier::prefixed_public("with-stdout");
+        let stderr = &GoIdentifier::prefixed_public("with-stderr");
+        let config = &module_config();
+        let name = |ident: &GoIdentifier| String::from(ident);
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} configures the sandbox of an instance, i.e. the directories,",
+                    name(option),
+                ),
+                "environment, arguments and standard streams of guests importing".into(),
+                "wasi_snapshot_preview1, which must be instantiated in the runtime.".into(),
+            ]))
+            type $option func(*$options)
+            $['\n']
+            type $options struct {
+                config $WAZERO_MODULE_CONFIG
+                fs     $WAZERO_FS_CONFIG
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} mounts the host directory at the guest path, e.g. \"/\". Guests",
+                    name(dir),
+                ),
+                "have no access to the host's files otherwise.".into(),
+            ]))
+            func $dir(host, guest string) $option {
+                return func(o *$options) {
+                    if o.fs == nil {
+                        o.fs = $WAZERO_NEW_FS_CONFIG()
+                    }
+                    o.fs = o.fs.WithDirMount(host, guest)
+                }
+            }
+            $['\n']
+            $(comment(&[format!(
+                "{} sets the environment variable, which guests have none of otherwise.",
+                name(env),
+            )]))
+            func $env(key, value string) $option {
+                return func(o *$options) {
+                    o.config = o.config.WithEnv(key, value)
+                }
+            }
+            $['\n']
+            $(comment(&[format!(
+                "{} sets the arguments, starting with the name of the program.",
+                name(args),
+            )]))
+            func $args(args ...string) $option {
+                return func(o *$options) {
+                    o.config = o.config.WithArgs(args...)
+                }
+            }
+            $['\n']
+            $(comment(&[format!(
+                "{} sets the standard input, which is empty otherwise.",
+                name(stdin),
+            )]))
+            func $stdin(r $IO_READER) $option {
+                return func(o *$options) {
+                    o.config = o.config.WithStdin(r)
+                }
+            }
+            $['\n']
+            $(comment(&[format!(
+                "{} sets the standard output, which is discarded otherwise.",
+                name(stdout),
+            )]))
+            func $stdout(w $IO_WRITER) $option {
+                return func(o *$options) {
+                    o.config = o.config.WithStdout(w)
+                }
+            }
+            $['\n']
+            $(comment(&[format!(
+                "{} sets the standard error, which is discarded otherwise.",
+                name(stderr),
+            )]))
+            func $stderr(w $IO_WRITER) $option {
+                return func(o *$options) {
+                    o.config = o.config.WithStderr(w)
+                }
+            }
+            $['\n']
+            $(comment(&[format!(
+                "{} returns the configuration of a module with the options.",
+                name(config),
+            )]))
+            func $config(opts []$option) $WAZERO_MODULE_CONFIG {
+                options := $options{config: $WAZERO_NEW_MODULE_CONFIG()}
+                for _, opt := range opts {
+                    opt(&options)
+                }
+                if options.fs != nil {
+                    return options.config.WithFSConfig(options.fs)
+                }
+                return options.config
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use genco::{lang::go::Tokens, tokens::FormatInto};
+
+    use super::InstanceOptionsGenerator;
+
+    #[test]
+    fn test_generate_instance_options() {
+        let mut tokens = Tokens::new();
+        InstanceOptionsGenerator.format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("type InstanceOption func(*instanceOptions)"));
+        assert!(output.contains("func WithDir(host, guest string) InstanceOption {"));
+        assert!(output.contains("            o.fs = wazero.NewFSConfig()\n"));
+        assert!(output.contains("func WithArgs(args ...string) InstanceOption {"));
+        assert!(output.contains("func WithStdout(w io.Writer) InstanceOption {"));
+        assert!(output.contains(
+            "    if options.fs != nil {\n        return options.config.WithFSConfig(options.fs)\n    }"
+        ));
+    }
+}
@@ -0,0 +1,554 @@
+use genco::prelude::*;
+
+use crate::{
+    codegen::{
+        error_context::INTRINSICS_MODULE,
+        ir::{AnalyzedImports, AnalyzedInterface, InterfaceMethod},
+        mocks::return_type,
+    },
+    go::{
+        GoIdentifier, GoResult, comment,
+        imports::{
+            CONTEXT_CONTEXT, OTEL_ATTRIBUTE_STRING, OTEL_CODES_ERROR, OTEL_TRACE_WITH_ATTRIBUTES,
+            OTEL_TRACER, TIME_DURATION, TIME_NOW, TIME_SINCE,
+        },
+    },
+};
+
+/// Instrumentation wrapped around every call of an export or import.
+#[derive(Debug, Clone, Default)]
+pub struct Instrumentation {
+    /// Wrap each call in an OpenTelemetry span, recording any error.
+    pub otel: bool,
+    /// Report the calls of exported functions to a `Metrics` hook set on the
+    /// factory.
+    pub metrics: bool,
+    /// Report the arguments and results of every call of an export or
+    /// import to a `Logger` hook set on the factory.
+    pub log: bool,
+}
+
+impl Instrumentation {
+    /// Returns true if any instrumentation is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.otel || self.metrics || self.log
+    }
+
+    /// Returns true if the calls of the guest to its imports are
+    /// instrumented, as well as the calls of exported functions.
+    pub(crate) fn instruments_imports(&self) -> bool {
+        self.otel || self.log
+    }
+
+    /// The hooks the host can set on a factory, which are shared with the
+    /// instances it creates.
+    pub(crate) fn hooks(&self) -> Vec<Hook> {
+        let mut hooks = Vec::new();
+        if self.metrics {
+            hooks.push(METRICS_HOOK);
+        }
+        if self.log {
+            hooks.push(LOGGER_HOOK);
+        }
+        hooks
+    }
+
+    /// Wraps the call in the enabled instrumentation, returning its results.
+    ///
+    /// The call must be an expression producing the results described by the
+    /// call site, and `ctx` must be in scope.
+    pub(crate) fn wrap_call(&self, site: &CallSite, call: Tokens<Go>) -> Tokens<Go> {
+        let (values, returns_err) = site.result.values();
+        let err = returns_err.then(|| quote!(err));
+        let names = values
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .chain(returns_err.then_some("err"))
+            .collect::<Vec<_>>();
+        let results =
+            (!names.is_empty()).then(|| quote!($(for name in &names join (, ) => $(*name))));
+
+        let mut tokens = Tokens::new();
+        if self.otel {
+            quote_in! { tokens =>
+                ctx, span := $(tracer_name(site.world)).Start(ctx, $(quoted(site.qualified_name())), $OTEL_TRACE_WITH_ATTRIBUTES(
+                    $OTEL_ATTRIBUTE_STRING("wasm.world", $(quoted(site.world))),
+                    $(if let Some(interface) = site.interface {
+                        $OTEL_ATTRIBUTE_STRING("wasm.interface", $(quoted(interface))),
+                    })
+                    $OTEL_ATTRIBUTE_STRING("wasm.function", $(quoted(site.function))),
+                ))
+                defer span.End()
+            };
+        }
+
+        let name = &site.qualified_name();
+        let logger = site.hooks.as_ref().filter(|_| self.log);
+        if let Some(hooks) = logger {
+            let on_call = if site.host {
+                quote!(OnHostCall)
+            } else {
+                quote!(OnCall)
+            };
+            quote_in! { tokens =>
+                $['\r']
+                if logger := $hooks.logger; logger != nil {
+                    logger.$on_call(ctx, $(quoted(name)), []any{$(for param in &site.params join (, ) => $(*param))})
+                }
+            };
+        }
+
+        // Metrics are only reported for exported functions
+        let metrics = site.hooks.as_ref().filter(|_| self.metrics && !site.host);
+        if metrics.is_some() {
+            quote_in!(tokens => $['\r']start := $TIME_NOW());
+        }
+
+        match &results {
+            Some(results) => quote_in!(tokens => $['\r']$results := $call),
+            None => quote_in!(tokens => $['\r']$call),
+        }
+
+        if let Some(hooks) = metrics {
+            quote_in! { tokens =>
+                $['\r']
+                if metrics := $hooks.metrics; metrics != nil {
+                    metrics.IncCalls($(quoted(name)))
+                    metrics.ObserveDuration($(quoted(name)), $TIME_SINCE(start))
+                    $(if let Some(err) = &err {
+                        if $err != nil {
+                            metrics.IncErrors($(quoted(name)))
+                        }
+                    })
+                }
+            };
+        }
+
+        if let Some(hooks) = logger {
+            quote_in! { tokens =>
+                $['\r']
+                if logger := $hooks.logger; logger != nil {
+                    logger.OnReturn(ctx, $(quoted(name)), []any{$(if let Some(results) = &results => $results)})
+                }
+            };
+        }
+
+        if let Some(err) = &err
+            && self.otel
+        {
+            quote_in! { tokens =>
+                $['\r']
+                if $err != nil {
+                    span.RecordError($err)
+                    span.SetStatus($OTEL_CODES_ERROR, $err.Error())
+                }
+            };
+        }
+
+        if let Some(results) = &results {
+            quote_in!(tokens => $['\r']return $results);
+        }
+        tokens
+    }
+}
+
+/// A call of an export or import wrapped by instrumentation.
+pub(crate) struct CallSite<'a> {
+    /// The name of the world the call belongs to.
+    pub world: &'a str,
+    /// The interface the function belongs to, if any (e.g. `arcjet:basic/logger`).
+    pub interface: Option<&'a str>,
+    /// The name of the WIT function.
+    pub function: &'a str,
+    /// The names of the parameters passed to the call.
+    pub params: Vec<&'a GoIdentifier>,
+    /// The result of the call.
+    pub result: &'a GoResult,
+    /// Whether the call is of an import provided by the host, rather than
+    /// of an exported function.
+    pub host: bool,
+    /// The hooks set by the host, if they are in scope of the call.
+    pub hooks: Option<Tokens<Go>>,
+}
+
+impl CallSite<'_> {
+    /// The name identifying the function in spans and metrics, e.g.
+    /// `arcjet:basic/logger#hello`.
+    fn qualified_name(&self) -> String {
+        match self.interface {
+            Some(interface) => format!("{interface}#{}", self.function),
+            None => self.function.to_string(),
+        }
+    }
+}
+
+/// A hook set by the host on a factory, reached through its `Set` method.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Hook {
+    /// The name of the hook, which is also the name of its interface type.
+    name: &'static str,
+    /// The comment on the setter of the hook.
+    docs: &'static [&'static str],
+}
+
+const METRICS_HOOK: Hook = Hook {
+    name: "metrics",
+    docs: &[
+        "receiving the measurements of every call of",
+        "an exported function by the instances of the factory.",
+    ],
+};
+
+const LOGGER_HOOK: Hook = Hook {
+    name: "logger",
+    docs: &[
+        "receiving the arguments and results of every",
+        "call of an exported function or import.",
+    ],
+};
+
+impl Hook {
+    /// The field holding the hook in a world's hooks struct.
+    pub(crate) fn field(&self) -> GoIdentifier {
+        GoIdentifier::private(self.name)
+    }
+
+    /// The interface type of the hook.
+    pub(crate) fn go_type(&self) -> GoIdentifier {
+        GoIdentifier::prefixed_public(self.name)
+    }
+
+    /// The method of the factory setting the hook.
+    pub(crate) fn setter(&self) -> GoIdentifier {
+        GoIdentifier::public(format!("set-{}", self.name))
+    }
+
+    /// The comment on the setter of the hook.
+    pub(crate) fn setter_docs(&self) -> Vec<String> {
+        let mut docs = vec![format!(
+            "{} sets the hook {}",
+            String::from(self.setter()),
+            self.docs[0]
+        )];
+        docs.extend(self.docs[1..].iter().map(|line| line.to_string()));
+        docs.push("It must be called before creating any instances.".to_string());
+        docs
+    }
+}
+
+/// Generator for the interfaces of the hooks set by the host on a factory.
+///
+/// This must only be generated once per file, as the interfaces are shared
+/// by all factories in the file.
+pub struct HooksGenerator<'a> {
+    instrumentation: &'a Instrumentation,
+}
+
+impl<'a> HooksGenerator<'a> {
+    /// Create a new generator for the hooks of the given instrumentation.
+    pub fn new(instrumentation: &'a Instrumentation) -> Self {
+        Self { instrumentation }
+    }
+}
+
+impl FormatInto<Go> for HooksGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        if self.instrumentation.metrics {
+            let metrics = &METRICS_HOOK.go_type();
+            quote_in! { *tokens =>
+                $['\n']
+                $(comment(&[
+                    format!(
+                        "{} receives the measurements of every call of an exported function,",
+                        String::from(metrics),
+                    ),
+                    "labelled by the qualified name of the function (e.g.".into(),
+                    "\"arcjet:basic/logger#hello\").".into(),
+                    "".into(),
+                    "The methods map onto the Prometheus client, e.g. a CounterVec of calls and".into(),
+                    "errors and a HistogramVec of durations, each with a function label.".into(),
+                ]))
+                type $metrics interface {
+                    $(comment(&["IncCalls counts a call of the function."]))
+                    IncCalls(function string)
+                    $(comment(&["ObserveDuration records how long a call of the function took."]))
+                    ObserveDuration(function string, duration $TIME_DURATION)
+                    $(comment(&["IncErrors counts a call of the function returning an error."]))
+                    IncErrors(function string)
+                }
+            };
+        }
+        if self.instrumentation.log {
+            let logger = &LOGGER_HOOK.go_type();
+            quote_in! { *tokens =>
+                $['\n']
+                $(comment(&[
+                    format!(
+                        "{} receives the values crossing the Wasm boundary, for debugging.",
+                        String::from(logger),
+                    ),
+                    "Functions are identified by their qualified name (e.g.".into(),
+                    "\"arcjet:basic/logger#hello\"), and the arguments and results are".into(),
+                    "passed as a []any in the order they are declared in WIT.".into(),
+                ]))
+                type $logger interface {
+                    $(comment(&["OnCall is called before an exported function is called."]))
+                    OnCall(ctx $CONTEXT_CONTEXT, function string, args any)
+                    $(comment(&["OnReturn is called after an exported function or import returns."]))
+                    OnReturn(ctx $CONTEXT_CONTEXT, function string, results any)
+                    $(comment(&["OnHostCall is called before the guest calls an import."]))
+                    OnHostCall(ctx $CONTEXT_CONTEXT, function string, args any)
+                }
+            };
+        }
+    }
+}
+
+/// The name of the package-level tracer used by a world.
+fn tracer_name(world: &str) -> GoIdentifier {
+    GoIdentifier::private(format!("{world}-tracer"))
+}
+
+/// The name of the type wrapping an import interface with instrumentation.
+pub(crate) fn instrumented_name(interface_name: &GoIdentifier) -> GoIdentifier {
+    GoIdentifier::private(format!("instrumented-{}", String::from(interface_name)))
+}
+
+/// Generator for the instrumentation shared by a world's exports and
+/// imports, including the struct holding the hooks set by the host and the
+/// types wrapping each import interface.
+///
+/// The factory constructor wraps each import provided by the host, so calls
+/// from the guest are instrumented without the host having to do anything.
+pub struct InstrumentGenerator<'a> {
+    instrumentation: &'a Instrumentation,
+    world: &'a str,
+    analyzed: &'a AnalyzedImports,
+}
+
+impl<'a> InstrumentGenerator<'a> {
+    /// Create a new generator for the given world's analyzed imports.
+    pub fn new(
+        instrumentation: &'a Instrumentation,
+        world: &'a str,
+        analyzed: &'a AnalyzedImports,
+    ) -> Self {
+        Self {
+            instrumentation,
+            world,
+            analyzed,
+        }
+    }
+
+    fn generate_wrapper(&self, interface: &AnalyzedInterface, tokens: &mut Tokens<Go>) {
+        let interface_name = &interface.go_interface_name;
+        let wrapper_name = &instrumented_name(interface_name);
+
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!("{} instruments the calls of the guest to an", String::from(wrapper_name)),
+                format!("{}.", String::from(interface_name)),
+            ]))
+            type $wrapper_name struct {
+                inner $interface_name
+                $(if !self.instrumentation.hooks().is_empty() => hooks *$(&self.analyzed.hooks_name))
+            }
+        };
+
+        for method in &interface.methods {
+            self.generate_method(interface, wrapper_name, method, tokens);
+        }
+    }
+
+    fn generate_method(
+        &self,
+        interface: &AnalyzedInterface,
+        wrapper_name: &GoIdentifier,
+        method: &InterfaceMethod,
+        tokens: &mut Tokens<Go>,
+    ) {
+        let result = return_type(method);
+        let site = CallSite {
+            world: self.world,
+            // The functions imported by the world aren't in any interface
+            interface: (interface.wazero_module_name != INTRINSICS_MODULE)
+                .then_some(interface.wazero_module_name.as_str()),
+            function: &method.name,
+            params: method.parameters.iter().map(|param| &param.name).collect(),
+            result: &result,
+            host: true,
+            hooks: (!self.instrumentation.hooks().is_empty()).then(|| quote!(w.hooks)),
+        };
+        let call = quote! {
+            w.inner.$(&method.go_method_name)(ctx$(for param in &method.parameters => , $(&param.name)))
+        };
+
+        quote_in! { *tokens =>
+            $['\n']
+            func (w $wrapper_name) $(&method.go_method_name)(
+                ctx $CONTEXT_CONTEXT,
+                $(for param in &method.parameters join ($['\r']) => $(&param.name) $(&param.go_type),)
+            ) $(&result) {
+                $(self.instrumentation.wrap_call(&site, call))
+            }
+        };
+    }
+}
+
+impl FormatInto<Go> for InstrumentGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let hooks = self.instrumentation.hooks();
+        if !hooks.is_empty() {
+            let hooks_name = &self.analyzed.hooks_name;
+            quote_in! { *tokens =>
+                $['\n']
+                $(comment(&[
+                    format!("{} holds the hooks set on a {},", String::from(hooks_name), String::from(&self.analyzed.factory_name)),
+                    "which are shared with its instances.".to_string(),
+                ]))
+                type $hooks_name struct {
+                    $(for hook in hooks join ($['\r']) => $(hook.field()) $(hook.go_type()))
+                }
+            };
+        }
+
+        if self.instrumentation.otel {
+            quote_in! { *tokens =>
+                $['\n']
+                var $(tracer_name(self.world)) = $OTEL_TRACER($(quoted(self.world)))
+            };
+        }
+
+        if self.instrumentation.instruments_imports() {
+            for interface in &self.analyzed.interfaces {
+                self.generate_wrapper(interface, tokens);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use genco::prelude::*;
+
+    use crate::{
+        codegen::instrument::{CallSite, Instrumentation},
+        go::{GoIdentifier, GoResult, GoType},
+    };
+
+    #[test]
+    fn test_wrap_call_otel() {
+        let instrumentation = Instrumentation {
+            otel: true,
+            ..Default::default()
+        };
+        let result = GoResult::Anon(GoType::ValueOrError(Box::new(GoType::String)));
+        let site = CallSite {
+            world: "basic",
+            interface: Some("arcjet:basic/logger"),
+            function: "hello",
+            params: vec![],
+            result: &result,
+            host: false,
+            hooks: None,
+        };
+        let mut tokens = Tokens::<Go>::new();
+        tokens.append(instrumentation.wrap_call(&site, quote!(i.hello(ctx))));
+        let output = tokens.to_string().unwrap();
+        println!("{output}");
+
+        assert!(output.contains(
+            "ctx, span := basicTracer.Start(ctx, \"arcjet:basic/logger#hello\", trace.WithAttributes("
+        ));
+        assert!(output.contains("attribute.String(\"wasm.interface\", \"arcjet:basic/logger\"),"));
+        assert!(output.contains("defer span.End()"));
+        assert!(output.contains("result, err := i.hello(ctx)"));
+        assert!(output.contains("span.SetStatus(codes.Error, err.Error())"));
+        assert!(output.contains("return result, err"));
+    }
+
+    #[test]
+    fn test_wrap_call_without_result() {
+        let instrumentation = Instrumentation {
+            otel: true,
+            ..Default::default()
+        };
+        let site = CallSite {
+            world: "basic",
+            interface: None,
+            function: "hello",
+            params: vec![],
+            result: &GoResult::Empty,
+            host: false,
+            hooks: None,
+        };
+        let mut tokens = Tokens::<Go>::new();
+        tokens.append(instrumentation.wrap_call(&site, quote!(i.hello(ctx))));
+        let output = tokens.to_string().unwrap();
+
+        assert!(!output.contains("wasm.interface"));
+        assert!(!output.contains("RecordError"));
+        assert!(!output.contains("return"));
+    }
+
+    #[test]
+    fn test_wrap_call_metrics() {
+        let instrumentation = Instrumentation {
+            metrics: true,
+            ..Default::default()
+        };
+        let result = GoResult::Anon(GoType::Error);
+        let site = CallSite {
+            world: "basic",
+            interface: None,
+            function: "hello",
+            params: vec![],
+            result: &result,
+            host: false,
+            hooks: Some(quote!(i.hooks)),
+        };
+        let mut tokens = Tokens::<Go>::new();
+        tokens.append(instrumentation.wrap_call(&site, quote!(i.hello(ctx))));
+        let output = tokens.to_string().unwrap();
+        println!("{output}");
+
+        assert!(output.contains("start := time.Now()"));
+        assert!(output.contains("err := i.hello(ctx)"));
+        assert!(output.contains("if metrics := i.hooks.metrics; metrics != nil {"));
+        assert!(output.contains("metrics.IncCalls(\"hello\")"));
+        assert!(output.contains("metrics.ObserveDuration(\"hello\", time.Since(start))"));
+        assert!(output.contains("metrics.IncErrors(\"hello\")"));
+        assert!(!output.contains("span"));
+    }
+
+    #[test]
+    fn test_wrap_call_log() {
+        let instrumentation = Instrumentation {
+            log: true,
+            ..Default::default()
+        };
+        let name = GoIdentifier::local("msg");
+        let site = CallSite {
+            world: "basic",
+            interface: Some("arcjet:basic/logger"),
+            function: "debug",
+            params: vec![&name],
+            result: &GoResult::Empty,
+            host: true,
+            hooks: Some(quote!(w.hooks)),
+        };
+        let mut tokens = Tokens::<Go>::new();
+        tokens.append(instrumentation.wrap_call(&site, quote!(w.inner.Debug(ctx, msg))));
+        let output = tokens.to_string().unwrap();
+        println!("{output}");
+
+        assert!(output.contains("if logger := w.hooks.logger; logger != nil {"));
+        assert!(
+            output.contains("logger.OnHostCall(ctx, \"arcjet:basic/logger#debug\", []any{msg})")
+        );
+        assert!(output.contains("w.inner.Debug(ctx, msg)"));
+        assert!(output.contains("logger.OnReturn(ctx, \"arcjet:basic/logger#debug\", []any{})"));
+        assert!(!output.contains("OnCall"));
+    }
+}
@@ -1,4 +1,4 @@
-use wit_bindgen_core::wit_parser::{Function, Type};
+use crate::compat::wit_parser::{Function, Type};
 
 use crate::go::{GoIdentifier, GoType};
 
@@ -9,8 +9,9 @@ pub struct AnalyzedImports {
     pub interfaces: Vec<AnalyzedInterface>,
     /// All standalone types found in the input world.
     pub standalone_types: Vec<AnalyzedType>,
-    /// All standalone functions found in the input world.
-    pub standalone_functions: Vec<AnalyzedFunction>,
+    /// The types defined by the interfaces exported by the world, except
+    /// for those it also imports, which are already among the interfaces.
+    pub exported_types: Vec<AnalyzedType>,
 
     /// The name of the factory type to be generated.
     pub factory_name: GoIdentifier,
@@ -18,6 +19,12 @@ pub struct AnalyzedImports {
     pub instance_name: GoIdentifier,
     /// The name of the constructor for the factory type.
     pub constructor_name: GoIdentifier,
+    /// The name of the struct holding the imports, when the factory
+    /// constructor takes a single struct rather than positional parameters.
+    pub imports_name: GoIdentifier,
+    /// The name of the struct holding the hooks shared by the factory and
+    /// its instances.
+    pub hooks_name: GoIdentifier,
 }
 
 /// An analyzed WIT interface with all its metadata.
@@ -47,6 +54,11 @@ pub struct AnalyzedInterface {
     ///
     /// E.g. the `ILogger` in `type ILogger interface { ... }`.
     pub go_interface_name: GoIdentifier,
+    /// The name of the struct implementing the functions of the interface's
+    /// resources with their Go interfaces.
+    ///
+    /// E.g. the `BasicTypesResources` in `type BasicTypesResources struct { ... }`.
+    pub resources_name: GoIdentifier,
     /// The parameter name this instance of the interface will have in the factory constructor.
     ///
     /// E.g. the `logger` in `NewLoggerFactory(ctx context.Context, logger ILogger)`
@@ -123,13 +135,9 @@ pub enum TypeDefinition {
     Alias { target: GoType },
     /// A primitive type that doesn't need special handling
     Primitive,
-}
-
-/// An analyzed WIT function.
-#[derive(Debug, Clone)]
-pub struct AnalyzedFunction {
-    pub name: String,
-    pub go_name: GoIdentifier,
-    pub parameters: Vec<Parameter>,
-    pub return_type: Option<GoType>,
+    /// A resource implemented by the host
+    Resource,
+    /// A resource implemented by the guest, whose representation in the guest
+    /// is destroyed by the core Wasm export `destructor`
+    GuestResource { destructor: String },
 }
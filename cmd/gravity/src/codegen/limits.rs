@@ -0,0 +1,138 @@
+use genco::prelude::*;
+
+use crate::go::{
+    GoIdentifier, comment,
+    imports::{
+        CONTEXT_CANCEL_FUNC, CONTEXT_CONTEXT, CONTEXT_WITH_TIMEOUT, TIME_DURATION,
+        WAZERO_NEW_RUNTIME_CONFIG, WAZERO_RUNTIME_CONFIG,
+    },
+};
+
+/// The type of the options of the factory constructors.
+pub(crate) fn option_type() -> GoIdentifier {
+    GoIdentifier::prefixed_public("factory-option")
+}
+
+/// The function returning the configuration of the runtime with the options.
+pub(crate) fn runtime_config() -> GoIdentifier {
+    GoIdentifier::prefixed_private("runtime-config")
+}
+
+/// Generator for the options limiting the resources used by the instances of
+/// the factories, shared by all factories in the file.
+///
+/// The options configure the Wazero runtime created by the constructors,
+/// which enforces them: the memory of each instance is limited to a number
+/// of pages, and calls can be interrupted when their context is done. Wazero
+/// doesn't meter fuel, so interrupting calls is how a host stops guests
+/// running for too long.
+pub struct LimitsGenerator;
+
+impl FormatInto<Go> for LimitsGenerator {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let option = &option_type();
+        let options = &GoIdentifier::prefixed_private("factory-options");
+        let memory_limit = &GoIdentifier::prefixed_public("with-memory-limit-pages");
+        let close = &GoIdentifier::prefixed_public("with-close-on-context-done");
+        let config = &runtime_config();
+        let timeout = &GoIdentifier::prefixed_public("with-timeout");
+        let name = |ident: &GoIdentifier| String::from(ident);
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} configures the runtime created by a factory constructor, e.g.",
+                    name(option),
+                ),
+                "to limit the resources used by the instances of a multi-tenant host.".into(),
+            ]))
+            type $option func(*$options)
+            $['\n']
+            type $options struct {
+                memoryLimitPages   uint32
+                closeOnContextDone bool
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} limits the memory of each instance to the given number",
+                    name(memory_limit),
+                ),
+                "of 64KiB pages, up to 65536 (4GiB). Instantiating a module whose memory".into(),
+                "needs more pages fails, and so does growing the memory beyond the limit.".into(),
+            ]))
+            func $memory_limit(pages uint32) $option {
+                return func(o *$options) {
+                    o.memoryLimitPages = pages
+                }
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} interrupts the calls whose context is done, e.g.",
+                    name(close),
+                ),
+                format!(
+                    "after the timeout of {}, closing their instance. The checks this",
+                    name(timeout),
+                ),
+                "adds to the compiled code make calls slightly slower.".into(),
+            ]))
+            func $close() $option {
+                return func(o *$options) {
+                    o.closeOnContextDone = true
+                }
+            }
+            $['\n']
+            $(comment(&[format!(
+                "{} returns the configuration of the runtime with the options.",
+                name(config),
+            )]))
+            func $config(opts []$option) $WAZERO_RUNTIME_CONFIG {
+                var options $options
+                for _, opt := range opts {
+                    opt(&options)
+                }
+                config := $WAZERO_NEW_RUNTIME_CONFIG().WithCloseOnContextDone(options.closeOnContextDone)
+                if options.memoryLimitPages > 0 {
+                    config = config.WithMemoryLimitPages(options.memoryLimitPages)
+                }
+                return config
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} returns a context for a call which is done after the duration,",
+                    name(timeout),
+                ),
+                "along with the function releasing it. The call is only interrupted if the".into(),
+                format!("factory was created {}.", name(close)),
+            ]))
+            func $timeout(ctx $CONTEXT_CONTEXT, d $TIME_DURATION) ($CONTEXT_CONTEXT, $CONTEXT_CANCEL_FUNC) {
+                return $CONTEXT_WITH_TIMEOUT(ctx, d)
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use genco::{lang::go::Tokens, tokens::FormatInto};
+
+    use super::LimitsGenerator;
+
+    #[test]
+    fn test_generate_limits() {
+        let mut tokens = Tokens::new();
+        LimitsGenerator.format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("type FactoryOption func(*factoryOptions)"));
+        assert!(output.contains(
+            "    if options.memoryLimitPages > 0 {\n        config = config.WithMemoryLimitPages(options.memoryLimitPages)\n    }"
+        ));
+        assert!(output.contains(
+            "func WithTimeout(ctx context.Context, d time.Duration) (context.Context, context.CancelFunc) {"
+        ));
+    }
+}
@@ -0,0 +1,217 @@
+use genco::prelude::*;
+
+use crate::{
+    codegen::ir::{AnalyzedImports, AnalyzedInterface, InterfaceMethod},
+    go::{
+        GoIdentifier, GoResult, GoType, comment,
+        imports::{CONTEXT_CONTEXT, SYNC_MUTEX},
+    },
+};
+
+/// Generator for mock implementations of the import interfaces.
+///
+/// For every import interface, a `Mock<Interface>` struct is generated with a
+/// function field per method (e.g. `InfoFunc`) and a slice recording the
+/// arguments of every call (e.g. `InfoCalls`). This allows Go hosts to test
+/// guest interactions without hand-writing fakes.
+pub struct MockGenerator<'a> {
+    analyzed: &'a AnalyzedImports,
+}
+
+impl<'a> MockGenerator<'a> {
+    /// Create a new mock generator for the given analyzed imports.
+    pub fn new(analyzed: &'a AnalyzedImports) -> Self {
+        Self { analyzed }
+    }
+
+    /// Generate the mock struct, call records and methods for an interface.
+    fn generate_mock(&self, interface: &AnalyzedInterface, tokens: &mut Tokens<Go>) {
+        let interface_name = &interface.go_interface_name;
+        let mock_name = &GoIdentifier::public(format!("mock-{}", String::from(interface_name)));
+
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!("{} is a mock implementation of {}.", String::from(mock_name), String::from(interface_name)),
+                "Calls are recorded and delegated to the corresponding function field, if set.".to_string(),
+            ]))
+            type $mock_name struct {
+                mu $SYNC_MUTEX
+                $(for method in &interface.methods =>
+                    $['\r']
+                    $(func_field(method)) func($(self.signature_params(method))) $(return_type(method))
+                    $(calls_field(method)) []$(call_record(mock_name, method))
+                )
+            }
+            $['\n']
+            var _ $interface_name = (*$mock_name)(nil)
+        };
+
+        for method in &interface.methods {
+            self.generate_method(mock_name, method, tokens);
+        }
+    }
+
+    /// Generate the call record struct and the method implementation.
+    fn generate_method(
+        &self,
+        mock_name: &GoIdentifier,
+        method: &InterfaceMethod,
+        tokens: &mut Tokens<Go>,
+    ) {
+        let record = &call_record(mock_name, method);
+        let func_field = &func_field(method);
+        let calls_field = &calls_field(method);
+        let fields = method
+            .parameters
+            .iter()
+            .map(|param| (GoIdentifier::public(String::from(&param.name)), param))
+            .collect::<Vec<_>>();
+        let args = quote!(ctx$(for param in &method.parameters => , $(&param.name)));
+
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[format!(
+                "{} records a call to {}.{}.",
+                String::from(record),
+                String::from(mock_name),
+                String::from(&method.go_method_name)
+            )]))
+            type $record struct {
+                $(for (field, param) in &fields join ($['\r']) => $field $(&param.go_type))
+            }
+            $['\n']
+            func (m *$mock_name) $(&method.go_method_name)($(self.signature_params(method))) $(return_type(method)) {
+                m.mu.Lock()
+                m.$calls_field = append(m.$calls_field, $record{
+                    $(for (field, param) in &fields join ($['\r']) => $field: $(&param.name),)
+                })
+                fn := m.$func_field
+                m.mu.Unlock()
+                $(match &method.return_type {
+                    None => {
+                        if fn != nil {
+                            fn($args)
+                        }
+                    }
+                    Some(ret) => {
+                        if fn == nil {
+                            $(zero_return(&ret.go_type))
+                        }
+                        return fn($args)
+                    }
+                })
+            }
+        };
+    }
+
+    /// The parameters of the method signature, including the context.
+    fn signature_params(&self, method: &InterfaceMethod) -> Tokens<Go> {
+        quote! {
+            ctx $CONTEXT_CONTEXT$(for param in &method.parameters => , $(&param.name) $(&param.go_type))
+        }
+    }
+}
+
+impl FormatInto<Go> for MockGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        for interface in &self.analyzed.interfaces {
+            self.generate_mock(interface, tokens);
+        }
+    }
+}
+
+/// The name of the function field used to configure a mocked method.
+fn func_field(method: &InterfaceMethod) -> GoIdentifier {
+    GoIdentifier::public(format!("{}-func", String::from(&method.go_method_name)))
+}
+
+/// The name of the field recording calls to a mocked method.
+fn calls_field(method: &InterfaceMethod) -> GoIdentifier {
+    GoIdentifier::public(format!("{}-calls", String::from(&method.go_method_name)))
+}
+
+/// The name of the struct recording the arguments of a call.
+fn call_record(mock_name: &GoIdentifier, method: &InterfaceMethod) -> GoIdentifier {
+    GoIdentifier::public(format!(
+        "{}-{}-call",
+        String::from(mock_name),
+        String::from(&method.go_method_name)
+    ))
+}
+
+pub(crate) fn return_type(method: &InterfaceMethod) -> GoResult {
+    method
+        .return_type
+        .as_ref()
+        .map(|ret| GoResult::Anon(ret.go_type.clone()))
+        .unwrap_or(GoResult::Empty)
+}
+
+/// A return statement producing the zero value(s) of the given type.
+pub(crate) fn zero_return(typ: &GoType) -> Tokens<Go> {
+    match typ {
+        GoType::Error => quote!(return nil),
+        GoType::ValueOrError(inner) => quote! {
+            var zero $(inner.as_ref())
+            return zero, nil
+        },
+        GoType::ValueOrOk(inner) => quote! {
+            var zero $(inner.as_ref())
+            return zero, false
+        },
+        GoType::Nothing => quote!(return),
+        typ => quote! {
+            var zero $typ
+            return zero
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::wit_parser::Type;
+    use genco::prelude::*;
+
+    use crate::{
+        codegen::{
+            fixtures::{analyzed, interface, method, param, returning},
+            mocks::MockGenerator,
+        },
+        go::GoType,
+    };
+
+    #[test]
+    fn test_generate_mock() {
+        let analyzed = analyzed(vec![interface(
+            "logger",
+            "test:pkg/logger",
+            vec![
+                method(
+                    "info",
+                    vec![param("msg", GoType::String, Type::String)],
+                    None,
+                ),
+                method("level", vec![], returning(GoType::Uint32, Type::U32)),
+            ],
+        )]);
+
+        let mut tokens = Tokens::<Go>::new();
+        MockGenerator::new(&analyzed).format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+        println!("{output}");
+
+        assert!(output.contains("type MockITestLogger struct {"));
+        assert!(output.contains("InfoFunc func(ctx context.Context, msg string)"));
+        assert!(output.contains("InfoCalls []MockITestLoggerInfoCall"));
+        assert!(output.contains("var _ ITestLogger = (*MockITestLogger)(nil)"));
+        assert!(
+            output.contains("func (m *MockITestLogger) Info(ctx context.Context, msg string) {")
+        );
+        assert!(output.contains("Msg: msg,"));
+        assert!(output.contains("func (m *MockITestLogger) Level(ctx context.Context) uint32 {"));
+        assert!(output.contains("var zero uint32"));
+        assert!(output.contains("return fn(ctx)"));
+        assert!(output.contains("fn(ctx, msg)"));
+    }
+}
@@ -1,13 +1,68 @@
+mod backend;
+mod batching;
 mod bindings;
+mod conversions;
+mod derives;
+mod describe;
+mod diff;
+mod docs;
+mod dynamic;
+mod error_context;
 mod exports;
 mod factory;
+mod filter;
+#[cfg(test)]
+mod fixtures;
 mod func;
+mod fuzz;
+mod guest;
+mod harness;
+pub(crate) mod helpers;
 mod imports;
+mod instance_options;
+mod instrument;
 mod ir;
+mod limits;
+mod mocks;
+mod panics;
+mod parallel;
+pub(crate) mod resources;
+mod results;
+mod stats;
+mod strings;
+mod stubs;
+pub mod support;
+mod symbols;
+mod timeouts;
+mod traps;
+pub(crate) mod variants;
+mod wasi;
 mod wasm;
 
+pub use backend::{RuntimeBackend, Wazero, Width};
+pub use batching::{ImportBatchGenerator, uses_batched_imports};
 pub use bindings::*;
-pub use exports::ExportGenerator;
-pub use factory::FactoryGenerator;
-pub use func::Func;
-pub use wasm::WasmData;
+pub use derives::Derives;
+pub use describe::describe_world;
+pub use diff::{Change, ChangeKind, diff_worlds};
+pub use exports::{ExportGenerator, TupleResults};
+pub use factory::{FactoryGenerator, Providers};
+pub use filter::{InterfaceFilter, filter_features, filter_world, prune_types};
+pub use func::{ErrorMode, Func};
+pub use fuzz::FuzzGenerator;
+pub use guest::guest_bindings;
+pub use harness::HarnessGenerator;
+pub use helpers::{RUNTIME_PACKAGE, with_runtime_package};
+pub use instance_options::InstanceOptionsGenerator;
+pub use instrument::{HooksGenerator, InstrumentGenerator, Instrumentation};
+pub use limits::LimitsGenerator;
+pub use mocks::MockGenerator;
+pub use results::{NestedResultGenerator, uses_nested_results};
+pub use stats::{FunctionStats, InterfaceStats, Stats};
+pub use strings::{StringEncodings, TranscodingGenerator};
+pub use stubs::{StubBehavior, StubGenerator};
+pub use symbols::{check_renames, disambiguate, prefix_types};
+pub use timeouts::CallTimeoutGenerator;
+pub use traps::{GuestErrorGenerator, GuestTrapGenerator, uses_guest_errors, uses_guest_traps};
+pub use wasi::{WasiAdapterGenerator, unadapted_interfaces};
+pub use wasm::{WasmData, component_metadata, module_exports, module_imports, uses_memory64};
@@ -0,0 +1,48 @@
+use std::{cell::RefCell, collections::BTreeSet};
+
+thread_local! {
+    static PANICS: RefCell<Option<BTreeSet<&'static str>>> = const { RefCell::new(None) };
+}
+
+/// Records that the code being generated for an exported function panics in
+/// the given case, e.g. as its result can't carry an error, for
+/// `--deny-panics`.
+///
+/// The panics of host functions aren't recorded, as Wazero recovers them into
+/// a trap of the guest, which is returned as the error of the call.
+pub(crate) fn record_panic(reason: &'static str) {
+    PANICS.with_borrow_mut(|panics| {
+        if let Some(panics) = panics {
+            panics.insert(reason);
+        }
+    });
+}
+
+/// Runs `f`, returning the cases the code it generated panics in, along with
+/// its result.
+pub(crate) fn record_panics<T>(f: impl FnOnce() -> T) -> (T, BTreeSet<&'static str>) {
+    let previous = PANICS.replace(Some(BTreeSet::new()));
+    let result = f();
+    let panics = PANICS.replace(previous).unwrap_or_default();
+    (result, panics)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{record_panic, record_panics};
+
+    #[test]
+    fn test_record_panics() {
+        // Panics aren't recorded outside of `record_panics`
+        record_panic("ignored");
+        let ((), panics) = record_panics(|| {
+            record_panic("an error is encountered");
+            let ((), inner) = record_panics(|| record_panic("the cleanup fails"));
+            assert_eq!(inner, BTreeSet::from(["the cleanup fails"]));
+            record_panic("an error is encountered");
+        });
+        assert_eq!(panics, BTreeSet::from(["an error is encountered"]));
+    }
+}
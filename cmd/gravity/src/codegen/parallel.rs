@@ -0,0 +1,80 @@
+use rayon::prelude::*;
+
+use crate::{
+    codegen::{
+        conversions::{extend_recorded, record_conversions},
+        helpers::{runtime_package, with_runtime_package},
+    },
+    go::{initialisms, renames, symbol_prefix, with_initialisms, with_renames, with_symbol_prefix},
+};
+
+/// Maps the items in parallel, returning the results in the order of the
+/// items, so the generated code doesn't depend on the scheduling.
+///
+/// Identifiers are formatted with the settings of the calling thread, such
+/// as the initialisms, the renames and the symbol prefix, which are
+/// thread-local, so they're carried over to the threads mapping the items.
+/// Likewise, the helpers converting values which the items use are recorded
+/// by the calling thread.
+pub(crate) fn par_map<T, R>(items: &[T], f: impl Fn(usize, &T) -> R + Sync) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+{
+    let initialisms = initialisms();
+    let renames = renames();
+    let prefix = symbol_prefix();
+    let package = runtime_package();
+    let results: Vec<_> = items
+        .par_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            with_initialisms(initialisms.clone(), || {
+                with_renames(renames.clone(), || {
+                    with_symbol_prefix(prefix.clone(), || {
+                        with_runtime_package(package.clone(), || record_conversions(|| f(i, item)))
+                    })
+                })
+            })
+        })
+        .collect();
+    results
+        .into_iter()
+        .map(|(result, conversions)| {
+            extend_recorded(conversions);
+            result
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use genco::prelude::*;
+
+    use crate::{
+        codegen::{helpers::with_runtime_package, parallel::par_map},
+        go::{GoIdentifier, Initialisms, with_initialisms, with_symbol_prefix},
+    };
+
+    #[test]
+    fn test_par_map_keeps_settings() {
+        let names = (0..64)
+            .map(|i| format!("http-client-{i}"))
+            .collect::<Vec<_>>();
+        let generated = with_initialisms(Some(Initialisms::standard()), || {
+            with_symbol_prefix(Some("api".to_string()), || {
+                with_runtime_package(None, || {
+                    par_map(&names, |i, name| {
+                        let public = &GoIdentifier::public(name);
+                        let helper = &GoIdentifier::prefixed_private("write-string");
+                        let tokens: Tokens<Go> = quote!($i $public $helper);
+                        tokens.to_string().unwrap()
+                    })
+                })
+            })
+        });
+        for (i, line) in generated.iter().enumerate() {
+            assert_eq!(line, &format!("{i} HTTPClient{i} apiWriteString"));
+        }
+    }
+}
@@ -0,0 +1,1248 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::compat::wit_parser::{
+    Function, FunctionKind, LiftLowerAbi, ManglingAndAbi, Resolve, ResourceIntrinsic, Type,
+    TypeDefKind, TypeId, WasmExport, WasmImport, World, WorldItem, WorldKey,
+};
+use genco::prelude::*;
+
+use crate::{
+    codegen::{
+        helpers::{
+            BORROW_HOST_RESOURCE, DROP_GUEST_RESOURCE, GUEST_RESOURCE, GUEST_RESOURCE_HANDLES,
+            GUEST_RESOURCE_REP, HOST_RESOURCE, NEW_HOST_RESOURCE, NEW_RESOURCE_TABLE,
+            RELEASE_GUEST_RESOURCES, RESOURCE_TABLE, STALE_HANDLE_ERROR, TAKE_HOST_RESOURCE,
+            member,
+        },
+        ir::{AnalyzedInterface, InterfaceMethod, TypeDefinition},
+    },
+    go::{
+        GoIdentifier, GoResult, GoType, comment,
+        imports::{
+            CONTEXT_CONTEXT, ERRORS_NEW, FMT_SPRINTF, SLICES_SORT, SYNC_MUTEX, WAZERO_API_MODULE,
+        },
+    },
+};
+
+/// Returns true if any interface imported by the world defines a resource,
+/// or if the world exports a resource.
+pub fn uses_resources(resolve: &Resolve, world: &World) -> bool {
+    !host_resource_names(resolve, world).is_empty()
+        || !exported_resources(resolve, world).is_empty()
+}
+
+/// Returns the WIT names of the resources implemented by the host, i.e.
+/// those defined by the interfaces imported by the world, whose handles are
+/// held by a table per factory.
+pub fn host_resource_names(resolve: &Resolve, world: &World) -> Vec<String> {
+    world
+        .imports
+        .values()
+        .filter_map(|item| match item {
+            WorldItem::Interface { id, .. } => Some(&resolve.interfaces[*id]),
+            WorldItem::Function(_) | WorldItem::Type(_) => None,
+        })
+        .flat_map(|interface| &interface.types)
+        .filter(|(_, id)| resolve.types[**id].kind == TypeDefKind::Resource)
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Returns the resource a type refers to, following any aliases created by
+/// `use`.
+pub fn resource_id(resolve: &Resolve, mut id: TypeId) -> TypeId {
+    loop {
+        match &resolve.types[id].kind {
+            TypeDefKind::Type(Type::Id(aliased)) => id = *aliased,
+            TypeDefKind::Resource => return id,
+            kind => panic!("expected a resource, found {kind:?}"),
+        }
+    }
+}
+
+/// Returns the name of the resource a type refers to, following any aliases
+/// created by `use`.
+pub fn resource_name(resolve: &Resolve, id: TypeId) -> &str {
+    resolve.types[resource_id(resolve, id)]
+        .name
+        .as_deref()
+        .expect("resource missing name")
+}
+
+/// A resource defined by an interface exported by the world, which is
+/// implemented by the guest.
+pub struct ExportedResource<'a> {
+    /// The key of the exported interface defining the resource.
+    pub key: &'a WorldKey,
+    pub id: TypeId,
+    pub name: &'a str,
+}
+
+/// Returns the resources defined by the interfaces exported by the world,
+/// except for those it also imports, whose resources are the host's.
+pub fn exported_resources<'a>(resolve: &'a Resolve, world: &'a World) -> Vec<ExportedResource<'a>> {
+    let imported = world
+        .imports
+        .values()
+        .filter_map(|item| match item {
+            WorldItem::Interface { id, .. } => Some(*id),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>();
+    world
+        .exports
+        .iter()
+        .filter_map(|(key, item)| match item {
+            WorldItem::Interface { id, .. } if !imported.contains(id) => Some((key, *id)),
+            _ => None,
+        })
+        .flat_map(|(key, id)| {
+            resolve.interfaces[id]
+                .types
+                .iter()
+                .filter(|(_, id)| resolve.types[**id].kind == TypeDefKind::Resource)
+                .map(move |(name, id)| ExportedResource { key, id: *id, name })
+        })
+        .collect()
+}
+
+/// Returns the WIT names of the resources implemented by the guest, see
+/// [`exported_resources`].
+pub fn guest_resource_names(resolve: &Resolve, world: &World) -> Vec<String> {
+    exported_resources(resolve, world)
+        .iter()
+        .map(|resource| resource.name.to_string())
+        .collect()
+}
+
+/// Returns the name of the core Wasm export destroying the guest's
+/// representation of an exported resource.
+pub fn destructor_name(resolve: &Resolve, key: &WorldKey, resource: TypeId) -> String {
+    resolve.wasm_export_name(
+        ManglingAndAbi::Legacy(LiftLowerAbi::Sync),
+        WasmExport::ResourceDtor {
+            interface: key,
+            resource,
+        },
+    )
+}
+
+/// Returns the name of the Go method implementing a function of an imported
+/// interface.
+///
+/// Functions of resources are prefixed by the name of the resource, e.g.
+/// `NewFooer` for the constructor of `fooer` and `FooerGetX` for its
+/// `get-x` method.
+pub fn method_name(resolve: &Resolve, func: &Function) -> GoIdentifier {
+    GoIdentifier::public(method_raw_name(resolve, func))
+}
+
+/// Returns the WIT-style name the Go method of a function is generated from,
+/// as used by [`method_name`].
+pub fn method_raw_name(resolve: &Resolve, func: &Function) -> String {
+    match func.kind {
+        FunctionKind::Freestanding | FunctionKind::AsyncFreestanding => func.name.clone(),
+        FunctionKind::Constructor(id) => format!("new-{}", resource_name(resolve, id)),
+        FunctionKind::Method(id)
+        | FunctionKind::AsyncMethod(id)
+        | FunctionKind::Static(id)
+        | FunctionKind::AsyncStatic(id) => {
+            format!("{}-{}", resource_name(resolve, id), func.item_name())
+        }
+    }
+}
+
+/// The name of the Go type holding an owned handle to the resource.
+pub fn own_name(resource: &str) -> GoIdentifier {
+    GoIdentifier::prefixed_public(&format!("{resource}-own"))
+}
+
+/// The name of the Go type holding a borrowed handle to the resource.
+pub fn borrow_name(resource: &str) -> GoIdentifier {
+    GoIdentifier::prefixed_public(&format!("{resource}-borrow"))
+}
+
+/// The name of the function wrapping the host's representation of the
+/// resource in an owned handle.
+pub fn new_own_name(resource: &str) -> GoIdentifier {
+    GoIdentifier::prefixed_public(&format!("new-{resource}-own"))
+}
+
+/// The name of the Go interface implemented by the host's representation of
+/// the resource.
+pub fn interface_name(resource: &str) -> GoIdentifier {
+    GoIdentifier::prefixed_public(resource)
+}
+
+/// The name of the Go interface creating the host's representations of the
+/// resource.
+pub fn factory_name(resource: &str) -> GoIdentifier {
+    GoIdentifier::prefixed_public(&format!("{resource}-factory"))
+}
+
+/// The name of the table holding the handles to the resources of a type,
+/// which is a package-level variable for those implemented by the guest, and
+/// a field of the factory for those implemented by the host.
+pub fn table_name(resource: &str) -> GoIdentifier {
+    GoIdentifier::prefixed_private(&format!("{resource}-table"))
+}
+
+/// Generator for the table of resources shared with the guests, and for the
+/// types and functions of the resources implemented by the host.
+///
+/// This must only be generated once per file, as it is shared by all
+/// resources in the file.
+pub struct ResourceTableGenerator;
+
+impl FormatInto<Go> for ResourceTableGenerator {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let table = &RESOURCE_TABLE.ident();
+        let new_table = &NEW_RESOURCE_TABLE.ident();
+        let stale = &STALE_HANDLE_ERROR.ident();
+        let resource = &HOST_RESOURCE.ident();
+        let new_resource = &NEW_HOST_RESOURCE.ident();
+        let borrow = &BORROW_HOST_RESOURCE.ident();
+        let take = &TAKE_HOST_RESOURCE.ident();
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} holds the representations of resources shared with guests,",
+                    String::from(table),
+                ),
+                "indexed by the handles used to pass them across the Component Model".into(),
+                "boundary.".into(),
+            ]))
+            type $table struct {
+                mu      $SYNC_MUTEX
+                next    uint32
+                entries map[uint32]any
+            }
+            $['\n']
+            func $new_table() *$table {
+                return &$table{entries: map[uint32]any{}}
+            }
+            $['\n']
+            func (t *$table) insert(rep any) uint32 {
+                t.mu.Lock()
+                defer t.mu.Unlock()
+                $(comment(&["Handles start at 1, so the zero value is never a valid handle"]))
+                t.next++
+                t.entries[t.next] = rep
+                return t.next
+            }
+            $['\n']
+            func (t *$table) get(handle uint32) any {
+                t.mu.Lock()
+                defer t.mu.Unlock()
+                return t.entries[handle]
+            }
+            $['\n']
+            func (t *$table) remove(handle uint32) {
+                t.mu.Lock()
+                defer t.mu.Unlock()
+                delete(t.entries, handle)
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} is returned for a handle that isn't valid for the instance",
+                    String::from(stale),
+                ),
+                "using it, e.g. one created by another instance or factory, or one used".into(),
+                "after the resource was dropped or its instance closed.".into(),
+            ]))
+            type $stale struct {
+                Resource string
+                Handle   uint32
+            }
+            $['\n']
+            func (e *$stale) Error() string {
+                return $FMT_SPRINTF("stale handle %d to a `%s` resource", e.Handle, e.Resource)
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} is the host's representation of a resource, shared by",
+                    String::from(resource),
+                ),
+                "the handles to it, which is cleared once the resource is dropped.".into(),
+            ]))
+            type $resource struct {
+                mu  $SYNC_MUTEX
+                rep any
+            }
+            $['\n']
+            func $new_resource(rep any) *$resource {
+                return &$resource{rep: rep}
+            }
+            $['\n']
+            $(comment(&["get returns the representation of the resource, or nil once it's dropped."]))
+            func (r *$resource) get() any {
+                if r == nil {
+                    return nil
+                }
+                r.mu.Lock()
+                defer r.mu.Unlock()
+                return r.rep
+            }
+            $['\n']
+            $(comment(&[
+                "drop clears the representation of the resource, then calls its Drop",
+                "method, if it has one. Dropping the resource again does nothing.",
+            ]))
+            func (r *$resource) drop() {
+                if r == nil {
+                    return
+                }
+                r.mu.Lock()
+                rep := r.rep
+                r.rep = nil
+                r.mu.Unlock()
+                if dropper, ok := rep.(interface{ Drop() }); ok {
+                    dropper.Drop()
+                }
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} returns the resource implemented by the host with the",
+                    String::from(borrow),
+                ),
+                format!(
+                    "handle, or a {} if it isn't in the table.",
+                    String::from(stale),
+                ),
+            ]))
+            func $borrow(table *$table, handle uint32, name string) (*$resource, error) {
+                resource, ok := table.get(handle).(*$resource)
+                if !ok {
+                    return nil, &$stale{Resource: name, Handle: handle}
+                }
+                return resource, nil
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} is like {}, but also removes the resource",
+                    String::from(take),
+                    String::from(borrow),
+                ),
+                "from the table, as its ownership is transferred to the host.".into(),
+            ]))
+            func $take(table *$table, handle uint32, name string) (*$resource, error) {
+                table.mu.Lock()
+                defer table.mu.Unlock()
+                resource, ok := table.entries[handle].(*$resource)
+                if !ok {
+                    return nil, &$stale{Resource: name, Handle: handle}
+                }
+                delete(table.entries, handle)
+                return resource, nil
+            }
+        };
+    }
+}
+
+/// Generator for the types and functions shared by the resources implemented
+/// by guests, which are stored in the table of resources along with those of
+/// the host.
+///
+/// This must only be generated once per file, after the table of resources.
+pub struct GuestResourcesGenerator;
+
+impl FormatInto<Go> for GuestResourcesGenerator {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let resource = &GUEST_RESOURCE.ident();
+        let drop = &DROP_GUEST_RESOURCE.ident();
+        let stale = &STALE_HANDLE_ERROR.ident();
+        let lookup = &GUEST_RESOURCE_REP.ident();
+        let handles = &GUEST_RESOURCE_HANDLES.ident();
+        let release = &RELEASE_GUEST_RESOURCES.ident();
+        let table = &RESOURCE_TABLE.ident();
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} is a resource implemented by a guest, i.e. the instance",
+                    String::from(resource),
+                ),
+                "implementing it and the guest's representation of it.".into(),
+            ]))
+            type $resource struct {
+                module $WAZERO_API_MODULE
+                rep    uint32
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} removes a resource implemented by a guest from the",
+                    String::from(drop),
+                ),
+                "table, then calls its destructor in the guest. Dropping a handle again".into(),
+                "from the host, i.e. without a module, does nothing, while a guest dropping".into(),
+                "a handle created by another module, or dropping it again, gets a".into(),
+                format!("{}.", String::from(stale)),
+            ]))
+            func $drop(
+                ctx $CONTEXT_CONTEXT,
+                table *$(RESOURCE_TABLE.ident()),
+                module $WAZERO_API_MODULE,
+                handle uint32,
+                name string,
+                destructor string,
+            ) error {
+                resource, ok := table.get(handle).($resource)
+                if module != nil && (!ok || resource.module != module) {
+                    return &$stale{Resource: name, Handle: handle}
+                }
+                if !ok {
+                    return nil
+                }
+                table.remove(handle)
+                $(comment(&["Resources without a destructor aren't required to export one"]))
+                dtor := resource.module.ExportedFunction(destructor)
+                if dtor == nil {
+                    return nil
+                }
+                _, err := dtor.Call(ctx, uint64(resource.rep))
+                return err
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} returns the guest's representation of the resource with",
+                    String::from(lookup),
+                ),
+                format!(
+                    "the handle, or a {} if it wasn't created by the module.",
+                    String::from(stale),
+                ),
+            ]))
+            func $lookup(
+                table *$table,
+                module $WAZERO_API_MODULE,
+                handle uint32,
+                name string,
+            ) (uint32, error) {
+                resource, ok := table.get(handle).($resource)
+                if !ok || resource.module != module {
+                    return 0, &$stale{Resource: name, Handle: handle}
+                }
+                return resource.rep, nil
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} returns the handles to the resources in the table",
+                    String::from(handles),
+                ),
+                "created by the module, in the order they were created.".into(),
+            ]))
+            func $handles(table *$table, module $WAZERO_API_MODULE) []uint32 {
+                table.mu.Lock()
+                defer table.mu.Unlock()
+                var handles []uint32
+                for handle, rep := range table.entries {
+                    if resource, ok := rep.($resource); ok && resource.module == module {
+                        handles = append(handles, handle)
+                    }
+                }
+                $SLICES_SORT(handles)
+                return handles
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} removes the resources created by the module from the",
+                    String::from(release),
+                ),
+                "table once it's closed, without calling their destructors, so that their".into(),
+                "handles are stale rather than referring to the closed module.".into(),
+            ]))
+            func $release(table *$table, module $WAZERO_API_MODULE) {
+                table.mu.Lock()
+                defer table.mu.Unlock()
+                for handle, rep := range table.entries {
+                    if resource, ok := rep.($resource); ok && resource.module == module {
+                        delete(table.entries, handle)
+                    }
+                }
+            }
+        };
+    }
+}
+
+/// Generator for the owned and borrowed handle types of a resource
+/// implemented by the host.
+pub struct ResourceGenerator<'a> {
+    name: &'a str,
+}
+
+impl<'a> ResourceGenerator<'a> {
+    /// Create a new generator for the resource with the given WIT name.
+    pub fn new(name: &'a str) -> Self {
+        Self { name }
+    }
+}
+
+impl FormatInto<Go> for ResourceGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let own = &own_name(self.name);
+        let borrow = &borrow_name(self.name);
+        let constructor = &new_own_name(self.name);
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!("{} is an owned handle to a `{}` resource. Passing it to", String::from(own), self.name),
+                "the guest transfers its ownership, otherwise it must be released with".to_string(),
+                "Close.".to_string(),
+            ]))
+            type $own struct {
+                resource *$HOST_RESOURCE
+            }
+            $['\n']
+            $(comment(&[
+                format!("{} stores the host's representation of a `{}`", String::from(constructor), self.name),
+                "resource, returning an owned handle to it.".to_string(),
+            ]))
+            func $constructor(rep any) $own {
+                return $own{resource: $NEW_HOST_RESOURCE(rep)}
+            }
+            $['\n']
+            $(comment(&[
+                "Rep returns the host's representation of the resource, or nil once it's",
+                "released.",
+            ]))
+            func (h $own) Rep() any {
+                return h.resource.$(member("get"))()
+            }
+            $['\n']
+            $(comment(&["Borrow returns a borrowed handle to the resource."]))
+            func (h $own) Borrow() $borrow {
+                return $borrow{resource: h.resource}
+            }
+            $['\n']
+            $(comment(&["Close releases the resource."]))
+            func (h $own) Close() {
+                h.resource.$(member("drop"))()
+            }
+            $['\n']
+            $(comment(&[
+                format!("{} is a borrowed handle to a `{}` resource, which is only", String::from(borrow), self.name),
+                "valid during the call it is passed to.".to_string(),
+            ]))
+            type $borrow struct {
+                resource *$HOST_RESOURCE
+            }
+            $['\n']
+            $(comment(&[
+                "Rep returns the host's representation of the resource, or nil once it's",
+                "released.",
+            ]))
+            func (h $borrow) Rep() any {
+                return h.resource.$(member("get"))()
+            }
+        };
+    }
+}
+
+/// Generator for the Go interfaces of the resources defined by an imported
+/// interface, and for the struct implementing the functions of the resources
+/// with them.
+///
+/// The functions of resources are methods of the imported interface, taking
+/// and returning handles. Hosts can instead implement a Go interface per
+/// resource, e.g. `Fooer` with a `GetX` method for the `get-x` method of
+/// `fooer`, and a factory creating them, e.g. `FooerFactory` with a
+/// `NewFooer` method. The generated struct, e.g. `ResourcesTypesResources`,
+/// implements the functions of the resources by calling those, and can be
+/// embedded in the implementation of the imported interface.
+pub struct ResourceInterfacesGenerator<'a> {
+    resolve: &'a Resolve,
+    interface: &'a AnalyzedInterface,
+}
+
+/// A function of a resource, as a method of its Go interface or factory.
+struct ResourceMethod<'a> {
+    method: &'a InterfaceMethod,
+    /// The name of the method on the resource's Go interface or factory.
+    name: GoIdentifier,
+    /// The resource the function belongs to.
+    resource: &'a str,
+    kind: ResourceMethodKind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ResourceMethodKind {
+    Constructor,
+    Method,
+    Static,
+}
+
+impl<'a> ResourceInterfacesGenerator<'a> {
+    /// Create a new generator for the resources of the imported interface.
+    pub fn new(resolve: &'a Resolve, interface: &'a AnalyzedInterface) -> Self {
+        Self { resolve, interface }
+    }
+
+    /// Returns the resources the interface defines, excluding those it
+    /// doesn't generate, e.g. those defined along with the imports of another
+    /// world.
+    fn resources(&self) -> Vec<&'a str> {
+        self.interface
+            .types
+            .iter()
+            .filter(|typ| matches!(typ.definition, TypeDefinition::Resource))
+            .map(|typ| typ.name.as_str())
+            .collect()
+    }
+
+    /// Returns the functions of the resources the interface generates.
+    fn methods(&self, resources: &[&'a str]) -> Vec<ResourceMethod<'a>> {
+        self.interface
+            .methods
+            .iter()
+            .filter_map(|method| {
+                let func = &method.wit_function;
+                let (id, kind) = match func.kind {
+                    FunctionKind::Constructor(id) => (id, ResourceMethodKind::Constructor),
+                    FunctionKind::Method(id) => (id, ResourceMethodKind::Method),
+                    FunctionKind::Static(id) => (id, ResourceMethodKind::Static),
+                    _ => return None,
+                };
+                let name = resource_name(self.resolve, id);
+                let resource = resources
+                    .iter()
+                    .find(|resource| **resource == name)
+                    .copied()?;
+                let name = match kind {
+                    ResourceMethodKind::Constructor => method_name(self.resolve, func),
+                    ResourceMethodKind::Method | ResourceMethodKind::Static => {
+                        GoIdentifier::public(func.item_name())
+                    }
+                };
+                Some(ResourceMethod {
+                    method,
+                    name,
+                    resource,
+                    kind,
+                })
+            })
+            .collect()
+    }
+}
+
+impl FormatInto<Go> for ResourceInterfacesGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let resources = self.resources();
+        if resources.is_empty() {
+            return;
+        }
+        let methods = self.methods(&resources);
+        let signature = |method: &ResourceMethod| {
+            // The receiver of a method is the resource itself
+            let params = match method.kind {
+                ResourceMethodKind::Method => &method.method.parameters[1..],
+                ResourceMethodKind::Constructor | ResourceMethodKind::Static => {
+                    &method.method.parameters[..]
+                }
+            };
+            let result = match (method.kind, &method.method.return_type) {
+                (ResourceMethodKind::Constructor, _) => GoResult::Anon(GoType::UserDefined(
+                    String::from(interface_name(method.resource)),
+                )),
+                (_, Some(result)) => GoResult::Anon(result.go_type.clone()),
+                (_, None) => GoResult::Empty,
+            };
+            quote! {
+                $(&method.name)(
+                    ctx $CONTEXT_CONTEXT,
+                    $(for param in params join ($['\r']) => $(&param.name) $(&param.go_type),)
+                ) $result
+            }
+        };
+        let mut factories = Vec::new();
+        for &resource in &resources {
+            let interface = &interface_name(resource);
+            let factory = &factory_name(resource);
+            let of = |kinds: &[ResourceMethodKind]| {
+                methods
+                    .iter()
+                    .filter(|method| method.resource == resource && kinds.contains(&method.kind))
+                    .map(signature)
+                    .collect::<Vec<_>>()
+            };
+            let members = of(&[ResourceMethodKind::Method]);
+            quote_in! { *tokens =>
+                $['\n']
+                $(comment(&[
+                    format!("{} is implemented by the host's representation of a `{resource}`", String::from(interface)),
+                    "resource. If it also has a `Drop()` method, it is called once the".to_string(),
+                    "resource is released, by the guest or by Close.".to_string(),
+                ]))
+                type $interface interface {
+                    $(for member in members join ($['\r']) => $member)
+                }
+            };
+            let constructors = of(&[ResourceMethodKind::Constructor, ResourceMethodKind::Static]);
+            if !constructors.is_empty() {
+                quote_in! { *tokens =>
+                    $['\n']
+                    $(comment(&[
+                        format!("{} creates the host's representations of `{resource}`", String::from(factory)),
+                        "resources, and implements their static functions.".to_string(),
+                    ]))
+                    type $factory interface {
+                        $(for member in constructors join ($['\r']) => $member)
+                    }
+                };
+                factories.push(resource);
+            }
+        }
+
+        let adapter = &self.interface.resources_name;
+        let go_interface = &self.interface.go_interface_name;
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!("{} implements the functions of the resources of", String::from(adapter)),
+                format!("{} by calling their Go interfaces, and can be embedded in", String::from(go_interface)),
+                "its implementation.".to_string(),
+            ]))
+            type $adapter struct {
+                $(for resource in &factories join ($['\r']) => $(interface_name(resource)) $(factory_name(resource)))
+            }
+        };
+        for method in &methods {
+            let go_method = &method.method.go_method_name;
+            let params = &method.method.parameters;
+            let args = match method.kind {
+                ResourceMethodKind::Method => &params[1..],
+                ResourceMethodKind::Constructor | ResourceMethodKind::Static => &params[..],
+            };
+            let args = std::iter::once("ctx".to_string())
+                .chain(args.iter().map(|param| String::from(&param.name)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let result = match &method.method.return_type {
+                Some(result) => GoResult::Anon(result.go_type.clone()),
+                None => GoResult::Empty,
+            };
+            // The factory of a resource is a field named after it
+            let interface = &interface_name(method.resource);
+            let name = &method.name;
+            let call = match method.kind {
+                ResourceMethodKind::Constructor => {
+                    let new_own = new_own_name(method.resource);
+                    quote!(return $new_own(r.$interface.$name($args)))
+                }
+                // Handles to resources that were dropped, or whose
+                // representation doesn't implement their Go interface, trap
+                ResourceMethodKind::Method => {
+                    let receiver = &params[0].name;
+                    let err = format!(
+                        "the `{}` resource was dropped, or is not a {}",
+                        method.resource,
+                        String::from(interface),
+                    );
+                    let call = quote!(rep.$name($args));
+                    quote! {
+                        rep, ok := $receiver.Rep().($interface)
+                        if !ok {
+                            panic($ERRORS_NEW($(quoted(err))))
+                        }
+                        $(match result {
+                            GoResult::Empty => $call,
+                            GoResult::Anon(_) => return $call,
+                        })
+                    }
+                }
+                ResourceMethodKind::Static => {
+                    let call = quote!(r.$interface.$name($args));
+                    match result {
+                        GoResult::Empty => call,
+                        GoResult::Anon(_) => quote!(return $call),
+                    }
+                }
+            };
+            quote_in! { *tokens =>
+                $['\n']
+                func (r $adapter) $go_method(
+                    ctx $CONTEXT_CONTEXT,
+                    $(for param in params join ($['\r']) => $(&param.name) $(&param.go_type),)
+                ) $result {
+                    $call
+                }
+            };
+        }
+    }
+}
+
+/// Generator for the owned and borrowed handle types of a resource
+/// implemented by the guest.
+pub struct GuestResourceGenerator<'a> {
+    name: &'a str,
+    destructor: &'a str,
+}
+
+impl<'a> GuestResourceGenerator<'a> {
+    /// Create a new generator for the resource with the given WIT name, and
+    /// the name of the core Wasm export destroying it.
+    pub fn new(name: &'a str, destructor: &'a str) -> Self {
+        Self { name, destructor }
+    }
+}
+
+impl FormatInto<Go> for GuestResourceGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let table = &table_name(self.name);
+        let own = &own_name(self.name);
+        let borrow = &borrow_name(self.name);
+        quote_in! { *tokens =>
+            $['\n']
+            var $table = $NEW_RESOURCE_TABLE()
+            $['\n']
+            $(comment(&[
+                format!("{} is an owned handle to a `{}` resource implemented by the", String::from(own), self.name),
+                "guest. Passing it to the guest transfers its ownership, otherwise it".to_string(),
+                "must be released with Drop.".to_string(),
+            ]))
+            type $own struct {
+                handle uint32
+            }
+            $['\n']
+            $(comment(&["Borrow returns a borrowed handle to the resource."]))
+            func (h $own) Borrow() $borrow {
+                return $borrow{handle: h.handle}
+            }
+            $['\n']
+            $(comment(&[
+                "Drop releases the resource, calling its destructor in the guest.",
+                "Dropping the resource again does nothing.",
+            ]))
+            func (h $own) Drop(ctx $CONTEXT_CONTEXT) error {
+                return $DROP_GUEST_RESOURCE(ctx, $table, nil, h.handle, $(quoted(self.name)), $(quoted(self.destructor)))
+            }
+            $['\n']
+            $(comment(&[
+                format!("{} is a borrowed handle to a `{}` resource implemented by", String::from(borrow), self.name),
+                "the guest, which is only valid during the call it is passed to.".to_string(),
+            ]))
+            type $borrow struct {
+                handle uint32
+            }
+            $['\n']
+            $(comment(&[
+                "rep returns the guest's representation of the resource, which is what",
+                "its methods are called with.",
+            ]))
+            func (h $borrow) rep() uint32 {
+                resource, _ := $table.$(member("get"))(h.handle).($GUEST_RESOURCE)
+                return resource.$(member("rep"))
+            }
+        };
+    }
+}
+
+/// Generator for the registry of the handles to the resources implemented by
+/// an instance, with an accessor per resource listing the handles the
+/// instance created that haven't been dropped.
+///
+/// The resources are removed from their tables when the instance is closed,
+/// see [`release_guest_resources`], and handles passed to another instance
+/// than the one that created them are rejected.
+pub struct GuestResourceRegistryGenerator<'a> {
+    instance_name: &'a GoIdentifier,
+    resources: &'a [String],
+}
+
+impl<'a> GuestResourceRegistryGenerator<'a> {
+    /// Create a new generator for the registry of the instance type, given
+    /// the WIT names of the resources the guest implements.
+    pub fn new(instance_name: &'a GoIdentifier, resources: &'a [String]) -> Self {
+        Self {
+            instance_name,
+            resources,
+        }
+    }
+}
+
+/// The name of the Go type of the registry of the resources implemented by
+/// an instance.
+pub fn registry_name(instance_name: &GoIdentifier) -> GoIdentifier {
+    GoIdentifier::public(format!("{}-resources", String::from(instance_name)))
+}
+
+impl FormatInto<Go> for GuestResourceRegistryGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let instance_name = self.instance_name;
+        let registry = &registry_name(instance_name);
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!("{} holds the handles to the resources implemented by", String::from(registry)),
+                format!("a {}, which are only valid for the instance that created", String::from(instance_name)),
+                "them.".to_string(),
+            ]))
+            type $registry struct {
+                module $WAZERO_API_MODULE
+            }
+            $['\n']
+            $(comment(&["Resources returns the handles to the resources implemented by the instance."]))
+            func (i *$instance_name) Resources() $registry {
+                return $registry{module: i.module}
+            }
+        };
+        for name in self.resources {
+            let own = &own_name(name);
+            let accessor = &GoIdentifier::public(format!("{name}-handles"));
+            quote_in! { *tokens =>
+                $['\n']
+                $(comment(&[
+                    format!("{} returns owned handles to the `{name}` resources created by", String::from(accessor)),
+                    "the instance that haven't been dropped, in the order they were created.".to_string(),
+                ]))
+                func (r $registry) $accessor() []$own {
+                    var handles []$own
+                    for _, handle := range $GUEST_RESOURCE_HANDLES($(table_name(name)), r.module) {
+                        handles = append(handles, $own{handle: handle})
+                    }
+                    return handles
+                }
+            };
+        }
+    }
+}
+
+/// Generates the removal of the resources implemented by the module from
+/// their tables, once it's closed.
+pub fn release_guest_resources(resources: &[String], module: Tokens<Go>) -> Tokens<Go> {
+    quote! {
+        $(for name in resources join ($['\r']) =>
+            $RELEASE_GUEST_RESOURCES($(table_name(name)), $(&module))
+        )
+    }
+}
+
+/// Generates the lookup of the guest's representation of the resource with the
+/// handle, checking that it was created by the module.
+pub fn guest_resource_rep(name: &str, module: Tokens<Go>, handle: Tokens<Go>) -> Tokens<Go> {
+    quote!($GUEST_RESOURCE_REP($(table_name(name)), $module, $handle, $(quoted(name))))
+}
+
+/// Generates the host modules providing the intrinsics guests call to create,
+/// look up and drop the handles of the resources they export, keyed by the
+/// name of the module.
+pub fn exported_resource_intrinsics(
+    resolve: &Resolve,
+    world: &World,
+) -> BTreeMap<String, Tokens<Go>> {
+    let mut functions = BTreeMap::<String, Tokens<Go>>::new();
+    for resource in exported_resources(resolve, world) {
+        let import_name = |intrinsic| {
+            resolve.wasm_import_name(
+                ManglingAndAbi::Legacy(LiftLowerAbi::Sync),
+                WasmImport::ResourceIntrinsic {
+                    interface: Some(resource.key),
+                    resource: resource.id,
+                    intrinsic,
+                },
+            )
+        };
+        let (module, new) = import_name(ResourceIntrinsic::ExportedNew);
+        let (_, rep) = import_name(ResourceIntrinsic::ExportedRep);
+        let (_, drop) = import_name(ResourceIntrinsic::ExportedDrop);
+        let table = &table_name(resource.name);
+        let destructor = destructor_name(resolve, resource.key, resource.id);
+        let chain = functions.entry(module).or_default();
+        quote_in! { *chain =>
+            NewFunctionBuilder().
+            WithFunc(func(
+                ctx $CONTEXT_CONTEXT,
+                mod $WAZERO_API_MODULE,
+                rep uint32,
+            ) uint32 {
+                return $table.$(member("insert"))($GUEST_RESOURCE{$(member("module")): mod, $(member("rep")): rep})
+            }).
+            Export($(quoted(new))).
+            NewFunctionBuilder().
+            WithFunc(func(
+                ctx $CONTEXT_CONTEXT,
+                mod $WAZERO_API_MODULE,
+                handle uint32,
+            ) uint32 {
+                rep, err := $(guest_resource_rep(resource.name, quote!(mod), quote!(handle)))
+                if err != nil {
+                    panic(err)
+                }
+                return rep
+            }).
+            Export($(quoted(rep))).
+            NewFunctionBuilder().
+            WithFunc(func(
+                ctx $CONTEXT_CONTEXT,
+                mod $WAZERO_API_MODULE,
+                handle uint32,
+            ) {
+                if err := $DROP_GUEST_RESOURCE(ctx, $table, mod, handle, $(quoted(resource.name)), $(quoted(destructor))); err != nil {
+                    panic(err)
+                }
+            }).
+            Export($(quoted(drop))).
+        };
+    }
+    functions
+        .into_iter()
+        .enumerate()
+        .map(|(i, (module, functions))| {
+            let err = &GoIdentifier::private(format!("err-resources{i}"));
+            let chain = quote! {
+                _, $err := wazeroRuntime.NewHostModuleBuilder($(quoted(&module))).
+                $functions
+                Instantiate(ctx)
+                if $err != nil {
+                    return nil, $err
+                }
+            };
+            (module, chain)
+        })
+        .collect()
+}
+
+/// Generates the table of the handles the factory gave guests to the
+/// resource implemented by the host, which is a variable in the constructors
+/// of the factory, and a field of the factory given its variable, e.g.
+/// `i.factory` in the methods of its instances.
+pub fn host_resource_table(name: &str, factory: Option<Tokens<Go>>) -> Tokens<Go> {
+    match factory {
+        Some(factory) => quote!($factory.$(table_name(name))),
+        None => quote!($(table_name(name))),
+    }
+}
+
+/// Generates the lookup of the host's representation of the resource with
+/// the handle given by a guest, which is removed from the table if the
+/// ownership of the resource is transferred to the host.
+pub fn host_resource(name: &str, table: Tokens<Go>, handle: Tokens<Go>, owned: bool) -> Tokens<Go> {
+    if owned {
+        quote!($TAKE_HOST_RESOURCE($table, $handle, $(quoted(name))))
+    } else {
+        quote!($BORROW_HOST_RESOURCE($table, $handle, $(quoted(name))))
+    }
+}
+
+/// Generates the host function called by guests to drop an owned handle to
+/// the resource, which traps if the handle isn't one the factory gave them.
+pub fn resource_drop(name: &str) -> Tokens<Go> {
+    quote! {
+        NewFunctionBuilder().
+        WithFunc(func(
+            ctx $CONTEXT_CONTEXT,
+            handle uint32,
+        ) {
+            resource, err := $(host_resource(name, host_resource_table(name, None), quote!(handle), true))
+            if err != nil {
+                panic(err)
+            }
+            resource.$(member("drop"))()
+        }).
+        Export($(quoted(format!("[resource-drop]{name}")))).
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::wit_parser::Resolve;
+
+    use genco::prelude::*;
+
+    use crate::{
+        codegen::{
+            imports::ImportAnalyzer,
+            resources::{
+                GuestResourceRegistryGenerator, ResourceInterfacesGenerator, destructor_name,
+                exported_resource_intrinsics, exported_resources, host_resource_names, method_name,
+                release_guest_resources, uses_resources,
+            },
+        },
+        go::GoIdentifier,
+    };
+
+    #[test]
+    fn test_method_name() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface types {
+                    resource fooer {
+                        constructor(x: u32);
+                        get-x: func() -> u32;
+                        make: static func() -> fooer;
+                    }
+
+                    hello: func();
+                }
+
+                world with-resources {
+                    import types;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+
+        let (_, interface) = resolve
+            .interfaces
+            .iter()
+            .find(|(_, interface)| interface.name.as_deref() == Some("types"))
+            .expect("missing interface");
+        let names = interface
+            .functions
+            .values()
+            .map(|func| String::from(method_name(&resolve, func)))
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["NewFooer", "FooerGetX", "FooerMake", "Hello"]);
+
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        assert!(uses_resources(&resolve, world));
+    }
+
+    #[test]
+    fn test_exported_resources() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface host {
+                    resource fooer;
+                }
+
+                interface guest {
+                    resource counter {
+                        constructor(start: u32);
+                    }
+                }
+
+                world with-resources {
+                    import host;
+                    export guest;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        assert_eq!(host_resource_names(&resolve, world), ["fooer"]);
+        let resources = exported_resources(&resolve, world);
+        let names = resources
+            .iter()
+            .map(|resource| resource.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["counter"]);
+        assert_eq!(
+            destructor_name(&resolve, resources[0].key, resources[0].id),
+            "test:pkg/guest#[dtor]counter"
+        );
+
+        let intrinsics = exported_resource_intrinsics(&resolve, world);
+        let modules = intrinsics.keys().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(modules, ["[export]test:pkg/guest"]);
+        let chain = intrinsics["[export]test:pkg/guest"].to_string().unwrap();
+        for export in [
+            "[resource-new]counter",
+            "[resource-rep]counter",
+            "[resource-drop]counter",
+        ] {
+            assert!(chain.contains(&format!("Export(\"{export}\")")), "{chain}");
+        }
+    }
+
+    #[test]
+    fn test_guest_resource_registry() {
+        let instance = &GoIdentifier::public("counters-instance");
+        let resources = ["counter".to_string(), "ticker".to_string()];
+        let mut tokens = Tokens::<Go>::new();
+        GuestResourceRegistryGenerator::new(instance, &resources).format_into(&mut tokens);
+        let code = tokens.to_string().unwrap();
+
+        for expected in [
+            "type CountersInstanceResources struct",
+            "func (i *CountersInstance) Resources() CountersInstanceResources {",
+            "func (r CountersInstanceResources) CounterHandles() []CounterOwn {",
+            "guestResourceHandles(counterTable, r.module)",
+            "func (r CountersInstanceResources) TickerHandles() []TickerOwn {",
+        ] {
+            assert!(code.contains(expected), "missing {expected:?} in:\n{code}");
+        }
+
+        let release = release_guest_resources(&resources, quote!(i.module));
+        assert_eq!(
+            release.to_string().unwrap(),
+            "releaseGuestResources(counterTable, i.module)\n\
+             releaseGuestResources(tickerTable, i.module)"
+        );
+    }
+
+    #[test]
+    fn test_resource_interfaces() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface types {
+                    resource fooer {
+                        constructor(x: u32);
+                        get-x: func() -> u32;
+                        make: static func() -> fooer;
+                    }
+                    resource barer {
+                        ping: func();
+                    }
+
+                    hello: func();
+                }
+
+                world with-resources {
+                    import types;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+
+        let (_, world) = resolve.worlds.iter().next().expect("missing world");
+        let analyzed = ImportAnalyzer::new(&resolve, world).analyze();
+        let mut tokens = Tokens::<Go>::new();
+        ResourceInterfacesGenerator::new(&resolve, &analyzed.interfaces[0])
+            .format_into(&mut tokens);
+        let code = tokens.to_string().unwrap();
+
+        for expected in [
+            "type Fooer interface",
+            "type FooerFactory interface",
+            "type Barer interface",
+            "type WithResourcesTypesResources struct",
+            "return NewFooerOwn(r.Fooer.NewFooer(ctx, x))",
+            "rep, ok := self.Rep().(Fooer)",
+            "panic(errors.New(\"the `fooer` resource was dropped, or is not a Fooer\"))",
+            "return rep.GetX(ctx)",
+            "return r.Fooer.Make(ctx)",
+            "rep.Ping(ctx)",
+        ] {
+            assert!(code.contains(expected), "missing {expected:?} in:\n{code}");
+        }
+        // Resources without a constructor or static functions have no factory
+        assert!(!code.contains("BarerFactory"), "{code}");
+        // Freestanding functions are left to the implementation it's embedded in
+        assert!(!code.contains("Hello"), "{code}");
+    }
+}
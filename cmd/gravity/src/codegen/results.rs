@@ -0,0 +1,101 @@
+use crate::compat::wit_parser::{Resolve, Result_, Type, TypeDefKind};
+use genco::prelude::*;
+
+use crate::{codegen::helpers::RESULT, go::comment};
+
+/// Returns true if any type nests a `result` with a value in another value,
+/// i.e. in a field of a record or tuple, an element of a list or the payload
+/// of an option, where it's a `Result[T]`.
+pub fn uses_nested_results(resolve: &Resolve) -> bool {
+    resolve.types.iter().any(|(_, typ)| match &typ.kind {
+        TypeDefKind::Record(record) => record
+            .fields
+            .iter()
+            .any(|field| is_value_result(resolve, &field.ty)),
+        TypeDefKind::Tuple(tuple) => tuple.types.iter().any(|typ| is_value_result(resolve, typ)),
+        TypeDefKind::List(typ) | TypeDefKind::Option(typ) => is_value_result(resolve, typ),
+        _ => false,
+    })
+}
+
+/// Returns true if the type is a `result` with a value.
+fn is_value_result(resolve: &Resolve, typ: &Type) -> bool {
+    let Type::Id(id) = typ else {
+        return false;
+    };
+    matches!(
+        resolve.types[*id].kind,
+        TypeDefKind::Result(Result_ { ok: Some(_), .. })
+    )
+}
+
+/// Generator for the generic `Result` type of the `result`s nested in other
+/// values, which can't be a pair of a value and an error like the results of
+/// functions.
+///
+/// This must only be generated once per file, as it is shared by all
+/// factories in the file.
+pub struct NestedResultGenerator;
+
+impl FormatInto<Go> for NestedResultGenerator {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let result = &RESULT.ident();
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} is a `result` nested in another value, such as a field of a",
+                    String::from(result),
+                ),
+                "record, holding either its value or, if it's an error, a non-nil Err.".into(),
+            ]))
+            type $result[T any] struct {
+                Value T
+                Err   error
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::wit_parser::Resolve;
+    use genco::{lang::go::Tokens, tokens::FormatInto};
+
+    use super::{NestedResultGenerator, uses_nested_results};
+
+    #[test]
+    fn test_generate_nested_results() {
+        let mut tokens = Tokens::new();
+        NestedResultGenerator.format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("type Result[T any] struct {\n    Value T\n    Err error\n}"));
+    }
+
+    #[test]
+    fn test_uses_nested_results() {
+        let uses = |wit: &str| {
+            let mut resolve = Resolve::default();
+            resolve
+                .push_str("test.wit", wit)
+                .expect("failed to parse WIT");
+            uses_nested_results(&resolve)
+        };
+
+        assert!(uses(
+            "package test:pkg; interface i { record r { count: result<u32, string> } }"
+        ));
+        assert!(uses(
+            "package test:pkg; interface i { f: func() -> list<result<u32, string>>; }"
+        ));
+        // Results of functions, and nested results without a value, which
+        // are errors, don't need the type
+        assert!(!uses(
+            "package test:pkg; interface i { f: func() -> result<u32, string>; }"
+        ));
+        assert!(!uses(
+            "package test:pkg; interface i { record r { done: result<_, string> } }"
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+use std::{collections::BTreeSet, fmt::Write};
+
+use genco::prelude::*;
+
+use crate::codegen::{helpers::record_helpers, panics::record_panics};
+
+/// The size of the code generated for the selected world, broken down by
+/// interface and function, as printed by `--stats`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The lines of the whole generated file.
+    pub lines: usize,
+    /// The host functions implementing the imports of the world.
+    pub imports: Vec<InterfaceStats>,
+    /// The Go functions calling the exports of the world.
+    pub exports: Vec<InterfaceStats>,
+}
+
+/// The functions generated for an interface, or for the functions of the
+/// world itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceStats {
+    /// The WIT name of the interface, or the name of the world.
+    pub name: String,
+    pub functions: Vec<FunctionStats>,
+}
+
+impl InterfaceStats {
+    /// The lines of all the functions of the interface.
+    pub fn lines(&self) -> usize {
+        self.functions.iter().map(|function| function.lines).sum()
+    }
+}
+
+/// The code generated for a function.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FunctionStats {
+    /// The WIT name of the function.
+    pub name: String,
+    /// The lines generated for the function.
+    pub lines: usize,
+    /// The Go names of the shared helpers the function uses, e.g.
+    /// `writeString`.
+    pub helpers: BTreeSet<String>,
+    /// The cases the function panics in rather than returning an error, as
+    /// checked by `--deny-panics`.
+    pub panics: BTreeSet<String>,
+}
+
+impl FunctionStats {
+    /// Counts the lines of the code `generate` writes into the tokens, and
+    /// records the helpers it uses and the cases it panics in.
+    pub(crate) fn measure(name: &str, generate: impl FnOnce(&mut Tokens<Go>)) -> Self {
+        let ((tokens, helpers), panics) = record_panics(|| {
+            record_helpers(|| {
+                let mut tokens = Tokens::new();
+                generate(&mut tokens);
+                tokens
+            })
+        });
+        let code = tokens.to_string().expect("failed to format the function");
+        Self {
+            name: name.to_string(),
+            lines: code.lines().filter(|line| !line.trim().is_empty()).count(),
+            helpers,
+            panics: panics.into_iter().map(String::from).collect(),
+        }
+    }
+}
+
+impl Stats {
+    /// Describes the exported functions which panic rather than returning an
+    /// error, e.g. ``export `greet` of `arcjet:basic/greeter` panics if an
+    /// error is encountered``, for `--deny-panics`.
+    pub fn panics(&self) -> Vec<String> {
+        self.exports
+            .iter()
+            .flat_map(|interface| {
+                interface.functions.iter().flat_map(move |function| {
+                    function.panics.iter().map(move |reason| {
+                        format!(
+                            "export `{}` of `{}` panics if {reason}",
+                            function.name, interface.name
+                        )
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Formats the breakdown to be read by humans, listing the functions of
+    /// each interface indented below it.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{} lines generated\n", self.lines);
+        for (direction, interfaces) in [("imports", &self.imports), ("exports", &self.exports)] {
+            if interfaces.is_empty() {
+                continue;
+            }
+            let _ = writeln!(text, "{direction}:");
+            for interface in interfaces {
+                let _ = writeln!(text, "  {}: {} lines", interface.name, interface.lines());
+                for function in &interface.functions {
+                    let _ = write!(text, "    {}: {} lines", function.name, function.lines);
+                    if !function.helpers.is_empty() {
+                        let helpers = function.helpers.iter().cloned().collect::<Vec<_>>();
+                        let _ = write!(text, ", using {}", helpers.join(", "));
+                    }
+                    text.push('\n');
+                }
+            }
+        }
+        text
+    }
+
+    /// Formats the breakdown as JSON, to be processed by other tools.
+    pub fn to_json(&self) -> String {
+        let interfaces = |interfaces: &[InterfaceStats]| {
+            let interfaces = interfaces
+                .iter()
+                .map(|interface| {
+                    let functions = interface
+                        .functions
+                        .iter()
+                        .map(|function| {
+                            let helpers = function
+                                .helpers
+                                .iter()
+                                .map(|helper| json_string(helper))
+                                .collect::<Vec<_>>();
+                            format!(
+                                "        {{\"name\": {}, \"lines\": {}, \"helpers\": [{}]}}",
+                                json_string(&function.name),
+                                function.lines,
+                                helpers.join(", "),
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    format!(
+                        "    {{\n      \"name\": {},\n      \"lines\": {},\n      \"functions\": [\n{}\n      ]\n    }}",
+                        json_string(&interface.name),
+                        interface.lines(),
+                        functions.join(",\n"),
+                    )
+                })
+                .collect::<Vec<_>>();
+            if interfaces.is_empty() {
+                "[]".to_string()
+            } else {
+                format!("[\n{}\n  ]", interfaces.join(",\n"))
+            }
+        };
+        format!(
+            "{{\n  \"lines\": {},\n  \"imports\": {},\n  \"exports\": {}\n}}\n",
+            self.lines,
+            interfaces(&self.imports),
+            interfaces(&self.exports),
+        )
+    }
+}
+
+/// Quotes the string as a JSON string.
+fn json_string(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use genco::prelude::*;
+
+    use crate::codegen::{helpers::WRITE_STRING, panics::record_panic};
+
+    use super::{FunctionStats, InterfaceStats, Stats};
+
+    #[test]
+    fn test_measure() {
+        let stats = FunctionStats::measure("greet", |tokens| {
+            quote_in! { *tokens =>
+                func Greet() {
+                    $WRITE_STRING(ctx, "hello")
+
+                    return
+                }
+            }
+        });
+        assert_eq!(stats.name, "greet");
+        assert_eq!(stats.lines, 4);
+        assert_eq!(stats.helpers, BTreeSet::from(["writeString".to_string()]));
+    }
+
+    #[test]
+    fn test_stats_formats() {
+        let stats = Stats {
+            lines: 120,
+            imports: vec![InterfaceStats {
+                name: "arcjet:basic/logger".to_string(),
+                functions: vec![
+                    FunctionStats {
+                        name: "debug".to_string(),
+                        lines: 20,
+                        helpers: BTreeSet::new(),
+                        panics: BTreeSet::new(),
+                    },
+                    FunctionStats {
+                        name: "info".to_string(),
+                        lines: 22,
+                        helpers: BTreeSet::from(["writeString".to_string()]),
+                        panics: BTreeSet::new(),
+                    },
+                ],
+            }],
+            exports: Vec::new(),
+        };
+        assert_eq!(
+            stats.to_text(),
+            "120 lines generated\n\
+             imports:\n  \
+             arcjet:basic/logger: 42 lines\n    \
+             debug: 20 lines\n    \
+             info: 22 lines, using writeString\n"
+        );
+        let json = stats.to_json();
+        assert!(json.starts_with("{\n  \"lines\": 120,\n"));
+        assert!(json.contains("\"name\": \"arcjet:basic/logger\",\n      \"lines\": 42,"));
+        assert!(
+            json.contains("{\"name\": \"info\", \"lines\": 22, \"helpers\": [\"writeString\"]}")
+        );
+        assert!(json.ends_with("\"exports\": []\n}\n"));
+        assert!(stats.panics().is_empty());
+    }
+
+    #[test]
+    fn test_panics() {
+        let greet = FunctionStats::measure("greet", |tokens| {
+            record_panic("an error is encountered, as its result can't carry it");
+            quote_in!(*tokens => panic(err));
+        });
+        let stats = Stats {
+            exports: vec![InterfaceStats {
+                name: "arcjet:basic/greeter".to_string(),
+                functions: vec![greet, FunctionStats::measure("reset", |_| {})],
+            }],
+            ..Stats::default()
+        };
+        assert_eq!(
+            stats.panics(),
+            [
+                "export `greet` of `arcjet:basic/greeter` panics if an error is encountered, as its result can't carry it"
+            ]
+        );
+    }
+}
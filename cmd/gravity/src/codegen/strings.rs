@@ -0,0 +1,547 @@
+use std::collections::BTreeMap;
+
+use crate::compat::{
+    wit_component::{StringEncoding, metadata::ModuleMetadata},
+    wit_parser::{
+        Function, LiftLowerAbi, ManglingAndAbi, Resolve, Type, WasmImport, World, WorldItem,
+        WorldKey,
+    },
+};
+use genco::prelude::*;
+
+use crate::{
+    codegen::{
+        backend::{RuntimeBackend, Wazero},
+        exports::export_name,
+        helpers::{
+            Helper, READ_STRING, READ_STRING_LATIN1_UTF16, READ_STRING_UTF16, VIEW_STRING,
+            WRITE_STRING, WRITE_STRING_LATIN1_UTF16, WRITE_STRING_UTF16,
+        },
+    },
+    contains_type,
+    go::{
+        GoIdentifier, comment,
+        imports::{
+            CONTEXT_CONTEXT, ENCODING_BINARY_LITTLE_ENDIAN, ERRORS_NEW, UNICODE_UTF16_DECODE,
+            UNICODE_UTF16_ENCODE, UNSAFE_SLICE_DATA, UNSAFE_STRING,
+        },
+    },
+};
+
+/// The encodings of the strings passed to and returned by the functions of a
+/// world, as chosen by the guest when it was built.
+///
+/// Most toolchains encode strings with UTF-8, which is assumed for the
+/// functions that aren't listed, but some default to UTF-16, or to Latin-1
+/// falling back to UTF-16, in which case the strings are transcoded from and
+/// to the Go strings by the helpers of [`TranscodingGenerator`].
+#[derive(Debug, Clone, Default)]
+pub struct StringEncodings {
+    /// The encodings of the imported functions, by the module and name of
+    /// their core Wasm import.
+    imports: BTreeMap<(String, String), StringEncoding>,
+    /// The encodings of the exported functions, by the name of their core
+    /// Wasm export.
+    exports: BTreeMap<String, StringEncoding>,
+}
+
+impl StringEncodings {
+    /// Reads the encodings of the functions of the world from the metadata
+    /// of the core Wasm module, as decoded by `wit-component`.
+    pub fn new(resolve: &Resolve, world: &World, metadata: &ModuleMetadata) -> Self {
+        let mut encodings = Self::default();
+        for (key, interface, func) in functions(resolve, &world.imports) {
+            if let Some(encoding) = metadata.import_encodings.get(resolve, key, &func.name)
+                && encoding != StringEncoding::UTF8
+            {
+                let name = resolve.wasm_import_name(
+                    ManglingAndAbi::Legacy(LiftLowerAbi::Sync),
+                    WasmImport::Func { interface, func },
+                );
+                encodings.imports.insert(name, encoding);
+            }
+        }
+        for (key, interface, func) in functions(resolve, &world.exports) {
+            if let Some(encoding) = metadata.export_encodings.get(resolve, key, &func.name)
+                && encoding != StringEncoding::UTF8
+            {
+                let name = export_name(resolve, interface, func);
+                encodings.exports.insert(name, encoding);
+            }
+        }
+        encodings
+    }
+
+    /// Returns the encoding of the function imported from the module.
+    pub fn import(&self, module: &str, name: &str) -> StringEncoding {
+        self.imports
+            .get(&(module.to_string(), name.to_string()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the encoding of the exported function.
+    pub fn export(&self, name: &str) -> StringEncoding {
+        self.exports.get(name).copied().unwrap_or_default()
+    }
+
+    /// Returns true if the world reads UTF-8 strings from the guest memory,
+    /// which is done by the `readString` helper.
+    ///
+    /// Strings are read when passed to an import, or returned by an export.
+    pub fn reads_utf8(&self, resolve: &Resolve, world: &World) -> bool {
+        let exports = functions(resolve, &world.exports).any(|(_, interface, func)| {
+            self.export(&export_name(resolve, interface, func)) == StringEncoding::UTF8
+                && func
+                    .result
+                    .as_ref()
+                    .is_some_and(|typ| contains_type(resolve, typ, Type::String))
+        });
+        self.passes_utf8(resolve, world) || exports
+    }
+
+    /// Returns true if any import of the world is passed UTF-8 strings, which
+    /// are viewed by the `viewString` helper with `--unsafe-strings`.
+    pub fn passes_utf8(&self, resolve: &Resolve, world: &World) -> bool {
+        functions(resolve, &world.imports).any(|(_, interface, func)| {
+            let (module, name) = resolve.wasm_import_name(
+                ManglingAndAbi::Legacy(LiftLowerAbi::Sync),
+                WasmImport::Func { interface, func },
+            );
+            self.import(&module, &name) == StringEncoding::UTF8
+                && func
+                    .params
+                    .iter()
+                    .any(|(_, typ)| contains_type(resolve, typ, Type::String))
+        })
+    }
+
+    /// Returns the encodings other than UTF-8 used by any function.
+    pub fn transcoded(&self) -> impl Iterator<Item = StringEncoding> + '_ {
+        self.imports.values().chain(self.exports.values()).copied()
+    }
+}
+
+/// The functions of the world items, with the key of their item and the key
+/// of their interface, which is `None` for the functions of the world.
+fn functions<'a>(
+    resolve: &'a Resolve,
+    items: impl IntoIterator<Item = (&'a WorldKey, &'a WorldItem)>,
+) -> impl Iterator<Item = (&'a WorldKey, Option<&'a WorldKey>, &'a Function)> {
+    items.into_iter().flat_map(|(key, item)| match item {
+        WorldItem::Function(func) => vec![(key, None, func)],
+        WorldItem::Interface { id, .. } => resolve.interfaces[*id]
+            .functions
+            .values()
+            .map(|func| (key, Some(key), func))
+            .collect(),
+        WorldItem::Type(_) => vec![],
+    })
+}
+
+/// Returns the name of the helper writing a Go string into the guest's
+/// memory with the encoding.
+pub(crate) fn write_string_helper(encoding: StringEncoding) -> Helper {
+    match encoding {
+        StringEncoding::UTF8 => WRITE_STRING,
+        StringEncoding::UTF16 => WRITE_STRING_UTF16,
+        StringEncoding::CompactUTF16 => WRITE_STRING_LATIN1_UTF16,
+    }
+}
+
+/// Returns the name of the helper reading a string of the encoding from the
+/// guest's memory.
+pub(crate) fn read_string_helper(encoding: StringEncoding) -> Helper {
+    match encoding {
+        StringEncoding::UTF8 => READ_STRING,
+        StringEncoding::UTF16 => READ_STRING_UTF16,
+        StringEncoding::CompactUTF16 => READ_STRING_LATIN1_UTF16,
+    }
+}
+
+/// Generator for the helpers reading the UTF-8 strings of guests, and
+/// transcoding the strings of guests encoding them with UTF-16, or Latin-1
+/// and UTF-16, from and to Go strings.
+///
+/// This must only be generated once per file, as it is shared by all
+/// factories in the file.
+pub struct TranscodingGenerator<'a> {
+    utf8: bool,
+    view: bool,
+    utf16: bool,
+    latin1: bool,
+    backend: &'a dyn RuntimeBackend,
+}
+
+impl<'a> TranscodingGenerator<'a> {
+    /// Creates a generator of the helpers of the given encodings, which
+    /// generates nothing if these are all UTF-8.
+    pub fn new(encodings: impl IntoIterator<Item = StringEncoding>) -> Self {
+        let (mut utf16, mut latin1) = (false, false);
+        for encoding in encodings {
+            match encoding {
+                StringEncoding::UTF8 => (),
+                StringEncoding::UTF16 => utf16 = true,
+                // Strings not fitting in Latin-1 are encoded with UTF-16
+                StringEncoding::CompactUTF16 => (utf16, latin1) = (true, true),
+            }
+        }
+        Self {
+            utf8: false,
+            view: false,
+            utf16,
+            latin1,
+            backend: &Wazero,
+        }
+    }
+
+    /// Sets whether to generate the helper reading UTF-8 strings, which is
+    /// needed by worlds whose exports return strings, or whose imports are
+    /// passed strings, see [`StringEncodings::reads_utf8`].
+    pub fn with_utf8(mut self, utf8: bool) -> Self {
+        self.utf8 = utf8;
+        self
+    }
+
+    /// Sets whether to generate the helper viewing UTF-8 strings in place,
+    /// which is needed by worlds whose imports are passed strings with
+    /// `--unsafe-strings`, see [`StringEncodings::passes_utf8`].
+    pub fn with_view(mut self, view: bool) -> Self {
+        self.view = view;
+        self
+    }
+
+    /// Sets the runtime the helpers access the guest's memory with, which is
+    /// Wazero by default.
+    pub fn with_backend(mut self, backend: &'a dyn RuntimeBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    fn generate_utf8(&self, tokens: &mut Tokens<Go>) {
+        let backend = self.backend;
+        let read = backend.read(&quote!(memory), quote!(ptr), quote!(length));
+        let read_string = &READ_STRING.ident();
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} reads a string of the given length from the Wasm memory, as",
+                    String::from(read_string),
+                ),
+                "encoded by guests using the `utf8` encoding.".into(),
+            ]))
+            func $read_string(memory $(backend.memory_type()), ptr uint32, length uint32) (string, bool) {
+                buf, ok := $read
+                if !ok {
+                    return "", false
+                }
+                return string(buf), true
+            }
+        };
+    }
+
+    fn generate_view(&self, tokens: &mut Tokens<Go>) {
+        let backend = self.backend;
+        let read = backend.read(&quote!(memory), quote!(ptr), quote!(length));
+        let view_string = &VIEW_STRING.ident();
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} is like {}, but returns a string sharing the Wasm",
+                    String::from(view_string),
+                    String::from(READ_STRING.ident()),
+                ),
+                "memory rather than a copy of it. The string is only valid until the host".into(),
+                "function it's passed to returns, and mustn't be retained past it.".into(),
+            ]))
+            func $view_string(memory $(backend.memory_type()), ptr uint32, length uint32) (string, bool) {
+                buf, ok := $read
+                if !ok {
+                    return "", false
+                }
+                return $UNSAFE_STRING($UNSAFE_SLICE_DATA(buf), len(buf)), true
+            }
+        };
+    }
+
+    fn generate_utf16(&self, tokens: &mut Tokens<Go>) {
+        let backend = self.backend;
+        let memory = &quote!(memory);
+        let read = backend.read(memory, quote!(ptr), quote!(2 * units));
+        let call = backend.call(&quote!(realloc), quote!(0, 0, 2, uint64(len(buf))));
+        let write = backend.write(memory, quote!(uint32(ptr)), quote!(buf));
+        let read_string = &READ_STRING_UTF16.ident();
+        let write_string = &WRITE_STRING_UTF16.ident();
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} reads a string of the given number of UTF-16 code units",
+                    String::from(read_string),
+                ),
+                "from the Wasm memory, as encoded by guests using the `utf16` encoding.".into(),
+            ]))
+            func $read_string(memory $(backend.memory_type()), ptr uint32, units uint32) (string, bool) {
+                buf, ok := $read
+                if !ok {
+                    return "", false
+                }
+                codes := make([]uint16, units)
+                for i := range codes {
+                    codes[i] = $ENCODING_BINARY_LITTLE_ENDIAN.Uint16(buf[2*i:])
+                }
+                return string($UNICODE_UTF16_DECODE(codes)), true
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} is like {}, but encodes the string with UTF-16 for",
+                    String::from(write_string),
+                    String::from(WRITE_STRING.ident()),
+                ),
+                "guests using the `utf16` encoding, returning its number of code units.".into(),
+            ]))
+            func $write_string(
+                ctx $CONTEXT_CONTEXT,
+                s string,
+                memory $(backend.memory_type()),
+                realloc $(backend.function_type()),
+            ) (uint64, uint64, error) {
+                codes := $UNICODE_UTF16_ENCODE([]rune(s))
+                if len(codes) == 0 {
+                    return 2, 0, nil
+                }
+                buf := make([]byte, 2*len(codes))
+                for i, code := range codes {
+                    $ENCODING_BINARY_LITTLE_ENDIAN.PutUint16(buf[2*i:], code)
+                }
+
+                results, err := $call
+                if err != nil {
+                    return 2, 0, err
+                }
+                ptr := results[0]
+                ok := $write
+                if !ok {
+                    return 2, 0, $ERRORS_NEW("failed to write string to wasm memory")
+                }
+                return uint64(ptr), uint64(len(codes)), nil
+            }
+        };
+    }
+
+    fn generate_latin1(&self, tokens: &mut Tokens<Go>) {
+        let backend = self.backend;
+        let memory = &quote!(memory);
+        let read = backend.read(memory, quote!(ptr), quote!(length));
+        let call = backend.call(&quote!(realloc), quote!(0, 0, 2, uint64(len(buf))));
+        let write = backend.write(memory, quote!(uint32(ptr)), quote!(buf));
+        let tag = &GoIdentifier::prefixed_private("latin1-UTF16-tag");
+        let read_string = &READ_STRING_LATIN1_UTF16.ident();
+        let write_string = &WRITE_STRING_LATIN1_UTF16.ident();
+        let read_utf16 = &READ_STRING_UTF16.ident();
+        let write_utf16 = &WRITE_STRING_UTF16.ident();
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                "The bit set in the length of the strings encoded with UTF-16 rather than",
+                "Latin-1, by guests using the `latin1+utf16` encoding.",
+            ]))
+            const $tag = 1 << 31
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} reads a string from the Wasm memory, as encoded by",
+                    String::from(read_string),
+                ),
+                "guests using the `latin1+utf16` encoding, where the length of the string".into(),
+                "tells whether it's encoded with Latin-1 or UTF-16.".into(),
+            ]))
+            func $read_string(memory $(backend.memory_type()), ptr uint32, length uint32) (string, bool) {
+                if length&$tag != 0 {
+                    return $read_utf16(memory, ptr, length&^$tag)
+                }
+                buf, ok := $read
+                if !ok {
+                    return "", false
+                }
+                runes := make([]rune, len(buf))
+                for i, b := range buf {
+                    runes[i] = rune(b)
+                }
+                return string(runes), true
+            }
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} is like {}, but encodes the string with",
+                    String::from(write_string),
+                    String::from(WRITE_STRING.ident()),
+                ),
+                "Latin-1 if it can, and with UTF-16 otherwise, for guests using the".into(),
+                "`latin1+utf16` encoding.".into(),
+            ]))
+            func $write_string(
+                ctx $CONTEXT_CONTEXT,
+                s string,
+                memory $(backend.memory_type()),
+                realloc $(backend.function_type()),
+            ) (uint64, uint64, error) {
+                buf := make([]byte, 0, len(s))
+                for _, r := range s {
+                    if r > 0xff {
+                        ptr, units, err := $write_utf16(ctx, s, memory, realloc)
+                        return ptr, units | $tag, err
+                    }
+                    buf = append(buf, byte(r))
+                }
+                if len(buf) == 0 {
+                    return 2, 0, nil
+                }
+
+                results, err := $call
+                if err != nil {
+                    return 2, 0, err
+                }
+                ptr := results[0]
+                ok := $write
+                if !ok {
+                    return 2, 0, $ERRORS_NEW("failed to write string to wasm memory")
+                }
+                return uint64(ptr), uint64(len(buf)), nil
+            }
+        };
+    }
+}
+
+impl FormatInto<Go> for TranscodingGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        if self.utf8 {
+            self.generate_utf8(tokens);
+        }
+        if self.view {
+            self.generate_view(tokens);
+        }
+        if self.utf16 {
+            self.generate_utf16(tokens);
+        }
+        if self.latin1 {
+            self.generate_latin1(tokens);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::{
+        wit_component::{StringEncoding, metadata::ModuleMetadata},
+        wit_parser::{Resolve, SizeAlign},
+    };
+
+    use crate::codegen::{Bindings, BindingsOptions, StringEncodings};
+
+    const WIT: &str = r#"
+        package test:pkg;
+
+        interface logger {
+            log: func(message: string);
+        }
+
+        world strings {
+            import logger;
+            export greet: func(name: string) -> string;
+        }
+    "#;
+
+    #[test]
+    fn test_string_encodings() {
+        let mut resolve = Resolve::default();
+        let package = resolve.push_str("test.wit", WIT).unwrap();
+        let world = resolve.select_world(&[package], None).unwrap();
+        let metadata = ModuleMetadata::new(&resolve, world, StringEncoding::UTF16);
+        let encodings = StringEncodings::new(&resolve, &resolve.worlds[world], &metadata);
+
+        assert_eq!(
+            encodings.import("test:pkg/logger", "log"),
+            StringEncoding::UTF16
+        );
+        assert_eq!(encodings.export("greet"), StringEncoding::UTF16);
+        assert_eq!(encodings.export("missing"), StringEncoding::UTF8);
+
+        // UTF-8 is the default, so it isn't listed
+        let metadata = ModuleMetadata::new(&resolve, world, StringEncoding::UTF8);
+        let encodings = StringEncodings::new(&resolve, &resolve.worlds[world], &metadata);
+        assert_eq!(encodings.transcoded().count(), 0);
+    }
+
+    #[test]
+    fn test_transcoded_strings() {
+        let mut resolve = Resolve::default();
+        let package = resolve.push_str("test.wit", WIT).unwrap();
+        let world = resolve.select_world(&[package], None).unwrap();
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+        let metadata = ModuleMetadata::new(&resolve, world, StringEncoding::CompactUTF16);
+
+        let mut bindings = Bindings::new(&resolve, &resolve.worlds[world], &sizes);
+        bindings.set_module_metadata(&metadata);
+        bindings.generate();
+        let generated = bindings.out.to_string().unwrap();
+
+        assert!(generated.contains("func readStringUTF16("));
+        assert!(generated.contains("func writeStringLatin1UTF16("));
+        // The imported message and the exported name and greeting
+        assert!(generated.contains(":= readStringLatin1UTF16(memory, "));
+        assert!(generated.contains(":= writeStringLatin1UTF16(ctx, arg0, i.memory, "));
+        assert!(generated.contains(":= readStringLatin1UTF16(i.memory, "));
+        assert!(!generated.contains("string(buf"));
+        assert!(!generated.contains("func readString("));
+    }
+
+    #[test]
+    fn test_read_utf8_strings() {
+        let mut resolve = Resolve::default();
+        let package = resolve.push_str("test.wit", WIT).unwrap();
+        let world = resolve.select_world(&[package], None).unwrap();
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+
+        let mut bindings = Bindings::new(&resolve, &resolve.worlds[world], &sizes);
+        bindings.generate();
+        let generated = bindings.out.to_string().unwrap();
+
+        // The helper is generated once, and read both the imported message
+        // and the exported greeting
+        assert_eq!(generated.matches("func readString(").count(), 1);
+        assert!(generated.contains(":= readString(memory, "));
+        assert!(generated.contains(":= readString(i.memory, "));
+        // The bytes are only converted by the helper
+        assert_eq!(generated.matches("string(buf").count(), 1);
+    }
+
+    #[test]
+    fn test_view_utf8_strings() {
+        let mut resolve = Resolve::default();
+        let package = resolve.push_str("test.wit", WIT).unwrap();
+        let world = resolve.select_world(&[package], None).unwrap();
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+
+        let mut bindings = Bindings::new(&resolve, &resolve.worlds[world], &sizes);
+        bindings.set_options(BindingsOptions {
+            string_views: true,
+            ..Default::default()
+        });
+        bindings.generate();
+        let generated = bindings.out.to_string().unwrap();
+
+        // The imported message is viewed, but the exported greeting is still
+        // copied, as the guest frees it once it's returned
+        assert_eq!(generated.matches("func viewString(").count(), 1);
+        assert!(generated.contains("return unsafe.String(unsafe.SliceData(buf), len(buf)), true"));
+        assert!(generated.contains(":= viewString(memory, "));
+        assert!(generated.contains(":= readString(i.memory, "));
+    }
+}
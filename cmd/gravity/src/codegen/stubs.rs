@@ -0,0 +1,154 @@
+use genco::prelude::*;
+
+use crate::{
+    codegen::{
+        ir::{AnalyzedImports, AnalyzedInterface, InterfaceMethod},
+        mocks::{return_type, zero_return},
+    },
+    go::{
+        GoIdentifier, comment,
+        imports::{CONTEXT_CONTEXT, ERRORS_NEW},
+    },
+};
+
+/// How the stub of an import interface behaves when called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StubBehavior {
+    /// Panic, reporting which import is not implemented.
+    Panic,
+    /// Do nothing, returning the zero value of the return type.
+    ZeroValue,
+}
+
+/// Generator for stub implementations of the import interfaces.
+///
+/// The factory constructor uses a stub in place of any import the host
+/// doesn't provide (i.e. is `nil`), so a component can be run before every
+/// host capability is implemented.
+pub struct StubGenerator<'a> {
+    analyzed: &'a AnalyzedImports,
+    behavior: StubBehavior,
+}
+
+impl<'a> StubGenerator<'a> {
+    /// Create a new stub generator for the given analyzed imports.
+    pub fn new(analyzed: &'a AnalyzedImports, behavior: StubBehavior) -> Self {
+        Self { analyzed, behavior }
+    }
+
+    /// Generate the stub struct and methods for an interface.
+    fn generate_stub(&self, interface: &AnalyzedInterface, tokens: &mut Tokens<Go>) {
+        let interface_name = &interface.go_interface_name;
+        let stub_name = &stub_name(interface_name);
+
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!("{} is used in place of an {}", String::from(stub_name), String::from(interface_name)),
+                "that isn't provided to the factory.".to_string(),
+            ]))
+            type $stub_name struct{}
+        };
+
+        for method in &interface.methods {
+            self.generate_method(interface_name, stub_name, method, tokens);
+        }
+    }
+
+    fn generate_method(
+        &self,
+        interface_name: &GoIdentifier,
+        stub_name: &GoIdentifier,
+        method: &InterfaceMethod,
+        tokens: &mut Tokens<Go>,
+    ) {
+        let body = match (self.behavior, &method.return_type) {
+            (StubBehavior::Panic, _) => {
+                let message = format!(
+                    "import not implemented: {}.{}",
+                    String::from(interface_name),
+                    String::from(&method.go_method_name)
+                );
+                quote!(panic($ERRORS_NEW($(quoted(message)))))
+            }
+            (StubBehavior::ZeroValue, None) => quote!(),
+            (StubBehavior::ZeroValue, Some(ret)) => zero_return(&ret.go_type),
+        };
+
+        quote_in! { *tokens =>
+            $['\n']
+            func ($stub_name) $(&method.go_method_name)(
+                ctx $CONTEXT_CONTEXT,
+                $(for param in &method.parameters join ($['\r']) => _ $(&param.go_type),)
+            ) $(return_type(method)) {
+                $body
+            }
+        };
+    }
+}
+
+impl FormatInto<Go> for StubGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        for interface in &self.analyzed.interfaces {
+            self.generate_stub(interface, tokens);
+        }
+    }
+}
+
+/// The name of the stub type implementing the given interface.
+pub(crate) fn stub_name(interface_name: &GoIdentifier) -> GoIdentifier {
+    GoIdentifier::private(format!("unimplemented-{}", String::from(interface_name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::wit_parser::Type;
+    use genco::prelude::*;
+
+    use crate::{
+        codegen::{
+            fixtures::{self, interface, method, param, returning},
+            ir::AnalyzedImports,
+            stubs::{StubBehavior, StubGenerator},
+        },
+        go::GoType,
+    };
+
+    fn analyzed() -> AnalyzedImports {
+        fixtures::analyzed(vec![interface(
+            "logger",
+            "test:pkg/logger",
+            vec![method(
+                "level",
+                vec![param("msg", GoType::String, Type::String)],
+                returning(GoType::Uint32, Type::U32),
+            )],
+        )])
+    }
+
+    #[test]
+    fn test_generate_panic_stub() {
+        let analyzed = analyzed();
+        let mut tokens = Tokens::<Go>::new();
+        StubGenerator::new(&analyzed, StubBehavior::Panic).format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("type unimplementedITestLogger struct{}"));
+        assert!(output.contains("func (unimplementedITestLogger) Level("));
+        assert!(output.contains("_ string,"));
+        assert!(
+            output.contains("panic(errors.New(\"import not implemented: ITestLogger.Level\"))")
+        );
+    }
+
+    #[test]
+    fn test_generate_zero_value_stub() {
+        let analyzed = analyzed();
+        let mut tokens = Tokens::<Go>::new();
+        StubGenerator::new(&analyzed, StubBehavior::ZeroValue).format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("var zero uint32"));
+        assert!(!output.contains("panic"));
+    }
+}
@@ -0,0 +1,291 @@
+use std::{collections::BTreeSet, fmt};
+
+use crate::compat::wit_parser::{
+    FunctionKind, Handle, Resolve, Type, TypeDefKind, WorldId, WorldItem,
+};
+
+/// The kinds of WIT features tracked by the registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    /// A WIT type, e.g. `record`.
+    Type,
+    /// An instruction of the canonical ABI, e.g. `StringLift`.
+    Instruction,
+    /// A way items are imported or exported by a world, e.g. an exported
+    /// interface.
+    Direction,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Category::Type => "type",
+            Category::Instruction => "instruction",
+            Category::Direction => "direction",
+        })
+    }
+}
+
+/// Whether the bindings of a feature can be generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Support {
+    Supported,
+    /// Supported in some cases only, which are described.
+    Partial(&'static str),
+    Unsupported,
+}
+
+impl fmt::Display for Support {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Support::Supported => f.write_str("supported"),
+            Support::Partial(note) => write!(f, "partial ({note})"),
+            Support::Unsupported => f.write_str("unsupported"),
+        }
+    }
+}
+
+/// A WIT feature, and whether gravity supports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Feature {
+    pub category: Category,
+    pub name: &'static str,
+    pub support: Support,
+}
+
+/// Returns every feature, in the order of their categories.
+///
+/// The features of each category are registered by the module generating
+/// them, next to the code they describe: the types by `resolve_type`, the
+/// instructions by `Func` and the directions by the import and export
+/// generators.
+pub fn registry() -> Vec<Feature> {
+    let categories: [(Category, &[(&'static str, Support)]); 4] = [
+        (Category::Type, crate::TYPES),
+        (Category::Instruction, super::func::INSTRUCTIONS),
+        (Category::Direction, super::imports::DIRECTIONS),
+        (Category::Direction, super::exports::DIRECTIONS),
+    ];
+    categories
+        .into_iter()
+        .flat_map(|(category, features)| {
+            features.iter().map(move |&(name, support)| Feature {
+                category,
+                name,
+                support,
+            })
+        })
+        .collect()
+}
+
+/// Returns the features used by the world, i.e. the types of its functions
+/// and the ways its items are imported and exported.
+///
+/// The instructions follow from the types, so these aren't listed.
+pub fn world_features(resolve: &Resolve, world_id: WorldId) -> Vec<Feature> {
+    let world = &resolve.worlds[world_id];
+    let mut used = BTreeSet::new();
+    for (items, direction) in [(&world.imports, "imported"), (&world.exports, "exported")] {
+        for item in items.values() {
+            let (kind, functions, types) = match item {
+                WorldItem::Function(func) => ("function", vec![func], vec![]),
+                WorldItem::Interface { id, .. } => {
+                    let interface = &resolve.interfaces[*id];
+                    (
+                        "interface",
+                        interface.functions.values().collect(),
+                        interface.types.values().copied().collect(),
+                    )
+                }
+                WorldItem::Type(id) => ("type", vec![], vec![*id]),
+            };
+            used.insert((Category::Direction, format!("{direction} {kind}")));
+            for id in types {
+                if resolve.types[id].kind == TypeDefKind::Resource {
+                    used.insert((Category::Direction, format!("{direction} resource")));
+                }
+                type_features(resolve, &Type::Id(id), &mut used);
+            }
+            for func in functions {
+                if matches!(
+                    func.kind,
+                    FunctionKind::AsyncFreestanding
+                        | FunctionKind::AsyncMethod(_)
+                        | FunctionKind::AsyncStatic(_)
+                ) {
+                    used.insert((Category::Direction, "async function".to_string()));
+                }
+                let types = func.params.iter().map(|(_, typ)| typ).chain(&func.result);
+                for typ in types {
+                    type_features(resolve, typ, &mut used);
+                }
+            }
+        }
+    }
+    registry()
+        .into_iter()
+        .filter(|feature| used.contains(&(feature.category, feature.name.to_string())))
+        .collect()
+}
+
+/// Adds the features of the type, and of the types it contains, to `used`.
+fn type_features(resolve: &Resolve, typ: &Type, used: &mut BTreeSet<(Category, String)>) {
+    let Type::Id(id) = typ else {
+        used.insert((Category::Type, primitive_name(typ).to_string()));
+        return;
+    };
+    used.insert((
+        Category::Type,
+        kind_name(&resolve.types[*id].kind).to_string(),
+    ));
+    let mut contains = |typ: &Type| type_features(resolve, typ, used);
+    match &resolve.types[*id].kind {
+        TypeDefKind::Record(record) => record.fields.iter().for_each(|f| contains(&f.ty)),
+        TypeDefKind::Tuple(tuple) => tuple.types.iter().for_each(contains),
+        TypeDefKind::Variant(variant) => variant
+            .cases
+            .iter()
+            .flat_map(|case| &case.ty)
+            .for_each(contains),
+        TypeDefKind::Option(typ)
+        | TypeDefKind::List(typ)
+        | TypeDefKind::FixedSizeList(typ, _)
+        | TypeDefKind::Type(typ) => contains(typ),
+        TypeDefKind::Result(result) => result.ok.iter().chain(&result.err).for_each(contains),
+        TypeDefKind::Future(typ) | TypeDefKind::Stream(typ) => typ.iter().for_each(contains),
+        TypeDefKind::Resource
+        | TypeDefKind::Handle(_)
+        | TypeDefKind::Flags(_)
+        | TypeDefKind::Enum(_)
+        | TypeDefKind::Unknown => {}
+    }
+}
+
+/// The name of a primitive type in the registry.
+fn primitive_name(typ: &Type) -> &'static str {
+    match typ {
+        Type::Bool => "bool",
+        Type::U8 => "u8",
+        Type::U16 => "u16",
+        Type::U32 => "u32",
+        Type::U64 => "u64",
+        Type::S8 => "s8",
+        Type::S16 => "s16",
+        Type::S32 => "s32",
+        Type::S64 => "s64",
+        Type::F32 => "f32",
+        Type::F64 => "f64",
+        Type::Char => "char",
+        Type::String => "string",
+        Type::ErrorContext => "error-context",
+        Type::Id(_) => unreachable!("not a primitive type"),
+    }
+}
+
+/// The name of a kind of type definition in the registry.
+fn kind_name(kind: &TypeDefKind) -> &'static str {
+    match kind {
+        TypeDefKind::Record(_) => "record",
+        TypeDefKind::Resource => "resource",
+        TypeDefKind::Handle(Handle::Own(_)) => "own",
+        TypeDefKind::Handle(Handle::Borrow(_)) => "borrow",
+        TypeDefKind::Flags(_) => "flags",
+        TypeDefKind::Tuple(_) => "tuple",
+        TypeDefKind::Variant(_) => "variant",
+        TypeDefKind::Enum(_) => "enum",
+        TypeDefKind::Option(_) => "option",
+        TypeDefKind::Result(_) => "result",
+        TypeDefKind::List(_) => "list",
+        TypeDefKind::FixedSizeList(..) => "fixed-size-list",
+        TypeDefKind::Future(_) => "future",
+        TypeDefKind::Stream(_) => "stream",
+        TypeDefKind::Type(_) => "alias",
+        TypeDefKind::Unknown => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::compat::wit_parser::{Resolve, WorldId};
+
+    use crate::codegen::support::{Category, Support, registry, world_features};
+
+    fn world(wit: &str) -> (Resolve, WorldId) {
+        let mut resolve = Resolve::default();
+        let package = resolve.push_str("test.wit", wit).unwrap();
+        let world = resolve.select_world(&[package], None).unwrap();
+        (resolve, world)
+    }
+
+    #[test]
+    fn test_registry_is_unique() {
+        let registry = registry();
+        let names = registry
+            .iter()
+            .map(|feature| (feature.category, feature.name))
+            .collect::<BTreeSet<_>>();
+        assert_eq!(names.len(), registry.len());
+    }
+
+    #[test]
+    fn test_world_features() {
+        let (resolve, world) = world(
+            r#"
+            package test:pkg;
+
+            interface types {
+                record point { x: u32, tags: list<string> }
+                flags permissions { read, write }
+                resource counter {
+                    get: func() -> u64;
+                }
+            }
+
+            interface host {
+                use types.{point, permissions, counter};
+
+                move: func(p: point, by: tuple<s32, s32>) -> option<point>;
+                check: func(c: borrow<counter>) -> permissions;
+            }
+
+            world features {
+                import host;
+                export run: async func() -> result<char, string>;
+            }
+            "#,
+        );
+        let features = world_features(&resolve, world);
+        let names = |category| {
+            features
+                .iter()
+                .filter(|feature| feature.category == category)
+                .map(|feature| feature.name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(Category::Type),
+            [
+                "u32", "u64", "s32", "char", "string", "record", "flags", "tuple", "option",
+                "result", "list", "resource", "borrow", "alias"
+            ]
+        );
+        assert_eq!(
+            names(Category::Direction),
+            [
+                "imported interface",
+                "imported resource",
+                "exported function",
+                "async function"
+            ]
+        );
+        let unsupported = features
+            .iter()
+            .filter(|feature| feature.support == Support::Unsupported)
+            .map(|feature| feature.name)
+            .collect::<Vec<_>>();
+        assert_eq!(unsupported, ["flags", "async function"]);
+    }
+}
@@ -0,0 +1,823 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::compat::wit_parser::{
+    Function, InterfaceId, Resolve, Type, TypeDefKind, TypeId, World, WorldId, WorldItem, WorldKey,
+};
+
+use crate::{
+    codegen::{
+        imports::ImportAnalyzer,
+        resources::{
+            borrow_name, factory_name, interface_name, method_raw_name, new_own_name, own_name,
+        },
+        variants,
+    },
+    go::{GoIdentifier, renamed, renames},
+};
+
+/// The Go identifiers declared in a scope, such as the package or the
+/// methods of a type.
+///
+/// WIT names that map to the same Go identifier, e.g. `foo-bar` and
+/// `fooBar`, are renamed deterministically: first by qualifying them with the
+/// name of their interface, then by appending a numeric suffix.
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    names: BTreeSet<String>,
+    warnings: Vec<String>,
+}
+
+impl SymbolTable {
+    /// Reserves an identifier that is always generated under its own name.
+    ///
+    /// A collision with an earlier identifier is reported, as it can't be
+    /// resolved by renaming.
+    pub fn reserve(&mut self, item: &str, ident: &GoIdentifier) {
+        let name = String::from(ident);
+        if !self.names.insert(name.clone()) {
+            self.warnings.push(format!(
+                "{item} generates `{name}`, which collides with another Go identifier"
+            ));
+        }
+    }
+
+    /// Declares the WIT name of an item, returning the name it should be
+    /// generated under.
+    ///
+    /// Each candidate name is mapped by `idents` to all of the Go
+    /// identifiers generated for it, none of which may have been declared.
+    pub fn declare(
+        &mut self,
+        item: &str,
+        name: &str,
+        qualifier: Option<&str>,
+        idents: impl Fn(&str) -> Vec<GoIdentifier>,
+    ) -> String {
+        let qualified = qualifier.map(|qualifier| format!("{qualifier}-{name}"));
+        let numbered = (2..).map(|n| format!("{name}-{n}"));
+        let renamed = std::iter::once(name.to_string())
+            .chain(qualified)
+            .chain(numbered)
+            .find(|candidate| {
+                idents(candidate)
+                    .iter()
+                    .all(|ident| !self.names.contains(&String::from(ident)))
+            })
+            .expect("should find an unused name");
+        self.names.extend(idents(&renamed).iter().map(String::from));
+        if renamed != name {
+            self.warnings.push(format!(
+                "{item} collides with another Go identifier, so it is generated as `{}`",
+                String::from(&idents(&renamed)[0])
+            ));
+        }
+        renamed
+    }
+}
+
+/// An exported function, with the name of its Go method.
+pub struct ExportMethod<'a> {
+    pub func: &'a Function,
+    /// The WIT name the Go method is generated from.
+    pub name: String,
+}
+
+/// An exported interface, whose functions are the methods of a Go type of
+/// their own, returned by an accessor on the instance.
+pub struct ExportInterface<'a> {
+    pub key: &'a WorldKey,
+    /// The WIT name the accessor is generated from.
+    pub name: String,
+    /// The Go type implementing the functions of the interface.
+    pub go_type: GoIdentifier,
+    pub methods: Vec<ExportMethod<'a>>,
+}
+
+/// The Go methods implementing the exports of a world.
+pub struct ExportMethods<'a> {
+    /// The functions exported by the world itself, which are methods of the
+    /// instance.
+    pub functions: Vec<ExportMethod<'a>>,
+    /// The exported interfaces, whose accessors are methods of the instance.
+    pub interfaces: Vec<ExportInterface<'a>>,
+}
+
+/// The methods gravity generates on every instance, besides those of the
+/// exports.
+///
+/// Some are only generated with an option, e.g. `Module` with
+/// `--expose-module`, but they're always reserved, so the names of the
+/// exports don't depend on the options.
+const INSTANCE_METHODS: &[&str] = &[
+    "close",
+    "module",
+    "call-export",
+    "read-memory",
+    "write-memory",
+    "read-uint32",
+    "write-uint32",
+    "read-uint64",
+    "write-uint64",
+    "exports",
+    "call-by-name",
+    "resources",
+];
+
+/// Names the methods of an instance implementing the exports of a world.
+///
+/// The functions of an exported interface are namespaced by the Go type
+/// returned by its accessor, so they only collide with the other functions
+/// of the interface. The accessors are qualified by the package of their
+/// interface when they collide with another method of the instance,
+/// including those gravity generates on every instance, such as `Close`.
+pub fn export_methods<'a>(
+    resolve: &'a Resolve,
+    world: &'a World,
+    table: &mut SymbolTable,
+) -> ExportMethods<'a> {
+    let instance = GoIdentifier::public(format!("{}-instance", world.name));
+    for method in INSTANCE_METHODS {
+        let method = GoIdentifier::public(*method);
+        table.reserve(
+            &format!(
+                "the `{}` method of `{}`",
+                String::from(&method),
+                String::from(&instance)
+            ),
+            &method,
+        );
+    }
+    // The functions also declare the wrappers generated with
+    // `--call-timeouts`
+    let declare = |table: &mut SymbolTable, func: &'a Function| {
+        let name = table.declare(
+            &format!("exported function `{}`", func.name),
+            &method_raw_name(resolve, func),
+            None,
+            |name| {
+                vec![
+                    GoIdentifier::public(name),
+                    GoIdentifier::public(format!("{name}-with-timeout")),
+                ]
+            },
+        );
+        ExportMethod { func, name }
+    };
+    let mut functions = Vec::new();
+    let mut interfaces = Vec::new();
+    for (key, item) in world.exports.iter() {
+        match item {
+            WorldItem::Function(func) => functions.push(declare(table, func)),
+            WorldItem::Interface { id, .. } => {
+                let interface = &resolve.interfaces[*id];
+                let (name, qualifier) = match key {
+                    WorldKey::Name(name) => (name.as_str(), None),
+                    WorldKey::Interface(_) => (
+                        interface.name.as_deref().expect("interface missing name"),
+                        interface.package.map(|package| {
+                            let package = &resolve.packages[package].name;
+                            format!("{}-{}", package.namespace, package.name)
+                        }),
+                    ),
+                };
+                let name = table.declare(
+                    &format!("exported interface `{}`", resolve.name_world_key(key)),
+                    name,
+                    qualifier.as_deref(),
+                    |name| vec![GoIdentifier::public(name)],
+                );
+                let mut methods = SymbolTable::default();
+                interfaces.push(ExportInterface {
+                    key,
+                    go_type: GoIdentifier::public(format!("{}-{}", world.name, renamed(&name))),
+                    name,
+                    methods: interface
+                        .functions
+                        .values()
+                        .map(|func| declare(&mut methods, func))
+                        .collect(),
+                });
+                table.warnings.extend(methods.warnings);
+            }
+            WorldItem::Type(_) => (),
+        }
+    }
+    ExportMethods {
+        functions,
+        interfaces,
+    }
+}
+
+/// Names the methods of the Go interface implementing an imported
+/// interface, in the order of its functions.
+pub fn interface_methods<'a>(
+    resolve: &Resolve,
+    functions: impl IntoIterator<Item = &'a Function>,
+    table: &mut SymbolTable,
+) -> Vec<GoIdentifier> {
+    functions
+        .into_iter()
+        .map(|func| {
+            let name = table.declare(
+                &format!("imported function `{}`", func.name),
+                &method_raw_name(resolve, func),
+                None,
+                |name| vec![GoIdentifier::public(name)],
+            );
+            GoIdentifier::public(name)
+        })
+        .collect()
+}
+
+/// The name the Go identifiers of an interface imported by the world are
+/// generated from.
+///
+/// This is the name of the interface, unless the world imports another
+/// interface with the same name from a different package, e.g. when it
+/// `include`s a world of another package, in which case it's qualified by
+/// the namespace and name of its package, as in `acme-geo-types`.
+pub fn imported_interface_name(resolve: &Resolve, world: &World, id: InterfaceId) -> String {
+    let interface = &resolve.interfaces[id];
+    let name = interface.name.as_deref().expect("interface missing name");
+    let ambiguous = world.imports.values().any(|item| match item {
+        WorldItem::Interface { id: other, .. } => {
+            *other != id && resolve.interfaces[*other].name.as_deref() == Some(name)
+        }
+        _ => false,
+    });
+    match interface.package {
+        Some(package) if ambiguous => {
+            let package = &resolve.packages[package].name;
+            format!("{}-{}-{name}", package.namespace, package.name)
+        }
+        _ => name.to_string(),
+    }
+}
+
+/// Renames the types imported or exported by the worlds whose Go identifiers
+/// collide.
+///
+/// Types are renamed in the `Resolve`, so every generator sees the same
+/// names. Functions and resources are never renamed, as their names are part
+/// of the core Wasm imports and exports, so collisions of their Go
+/// identifiers are only reported.
+///
+/// Returns the collisions found, including those of the exported functions
+/// and imported interface methods, which are renamed when generated.
+pub fn disambiguate(resolve: &mut Resolve, worlds: &[WorldId]) -> Vec<String> {
+    let mut table = SymbolTable::default();
+    for world in worlds {
+        let analyzed = ImportAnalyzer::new(resolve, &resolve.worlds[*world]).analyze();
+        for ident in [
+            &analyzed.factory_name,
+            &analyzed.instance_name,
+            &analyzed.constructor_name,
+            &analyzed.imports_name,
+            &analyzed.hooks_name,
+        ] {
+            table.reserve(&format!("world `{}`", resolve.worlds[*world].name), ident);
+        }
+        for interface in &analyzed.interfaces {
+            table.reserve(
+                &format!("interface `{}`", interface.name),
+                &interface.go_interface_name,
+            );
+        }
+        let world = &resolve.worlds[*world];
+        for interface in export_methods(resolve, world, &mut SymbolTable::default()).interfaces {
+            table.reserve(
+                &format!(
+                    "exported interface `{}`",
+                    resolve.name_world_key(interface.key)
+                ),
+                &interface.go_type,
+            );
+        }
+    }
+
+    let mut renames = BTreeMap::new();
+    let mut aliases = Vec::new();
+    for world in worlds {
+        // The types of interfaces both imported and exported are declared
+        // along with the imports, so they're skipped for the exports
+        let world_ref = &resolve.worlds[*world];
+        for (imported, item) in world_ref
+            .imports
+            .values()
+            .map(|item| (true, item))
+            .chain(world_ref.exports.values().map(|item| (false, item)))
+        {
+            let (qualifier, types) = match item {
+                WorldItem::Interface { id, .. } if !imported => (
+                    resolve.interfaces[*id].name.clone(),
+                    resolve.interfaces[*id].types.values().copied().collect(),
+                ),
+                WorldItem::Interface { id, .. } => (
+                    Some(imported_interface_name(
+                        resolve,
+                        &resolve.worlds[*world],
+                        *id,
+                    )),
+                    resolve.interfaces[*id].types.values().copied().collect(),
+                ),
+                WorldItem::Type(id) => (None, vec![*id]),
+                WorldItem::Function(_) => (None, vec![]),
+            };
+            for id in types {
+                if renames.contains_key(&id) {
+                    continue;
+                }
+                let typ = &resolve.types[id];
+                let name = typ.name.as_deref().expect("type missing name");
+                let item = &format!("type `{name}`");
+                let renamed = match &typ.kind {
+                    TypeDefKind::Type(Type::Id(target)) => {
+                        aliases.push((id, *target));
+                        continue;
+                    }
+                    TypeDefKind::Resource => {
+                        for ident in [
+                            own_name(name),
+                            borrow_name(name),
+                            new_own_name(name),
+                            interface_name(name),
+                            factory_name(name),
+                        ] {
+                            table.reserve(item, &ident);
+                        }
+                        name.to_string()
+                    }
+                    TypeDefKind::Enum(enum_) => {
+                        let renamed = table.declare(item, name, qualifier.as_deref(), |name| {
+                            vec![
+                                GoIdentifier::public(name),
+                                GoIdentifier::private(name),
+                                GoIdentifier::private(format!("is-{name}")),
+                                GoIdentifier::public(format!("parse-{name}")),
+                            ]
+                        });
+                        for case in &enum_.cases {
+                            table.reserve(
+                                &format!("case `{}` of `{name}`", case.name),
+                                &GoIdentifier::prefixed_public(&case.name),
+                            );
+                        }
+                        renamed
+                    }
+                    TypeDefKind::Variant(variant) => {
+                        table.declare(item, name, qualifier.as_deref(), |name| {
+                            let cases = variant.cases.iter().flat_map(|case| {
+                                [
+                                    variants::case_name(name, &case.name),
+                                    variants::constructor_name(name, &case.name),
+                                ]
+                            });
+                            std::iter::once(GoIdentifier::public(name))
+                                .chain(std::iter::once(variants::visitor_name(name)))
+                                .chain(std::iter::once(variants::unmarshal_name(name)))
+                                .chain(cases)
+                                .collect()
+                        })
+                    }
+                    _ => table.declare(item, name, qualifier.as_deref(), |name| {
+                        vec![GoIdentifier::public(name)]
+                    }),
+                };
+                renames.insert(id, renamed);
+            }
+        }
+    }
+
+    // Types brought into scope by `use` keep referring to their original
+    // type, which is what the Go code is generated for.
+    for (alias, target) in aliases {
+        let target = follow_aliases(resolve, target);
+        if resolve.types[alias].name == resolve.types[target].name
+            && let Some(renamed) = renames.get(&target)
+        {
+            resolve.types[alias].name = Some(renamed.clone());
+        }
+    }
+    for (id, renamed) in renames {
+        resolve.types[id].name = Some(renamed);
+    }
+
+    let mut warnings = table.warnings;
+    for world in worlds {
+        let world = &resolve.worlds[*world];
+        let mut methods = SymbolTable::default();
+        export_methods(resolve, world, &mut methods);
+        warnings.extend(methods.warnings);
+        for item in world.imports.values() {
+            if let WorldItem::Interface { id, .. } = item {
+                let mut methods = SymbolTable::default();
+                interface_methods(
+                    resolve,
+                    resolve.interfaces[*id].functions.values(),
+                    &mut methods,
+                );
+                warnings.extend(methods.warnings);
+            }
+        }
+    }
+    warnings
+}
+
+/// Checks that the Go identifiers the WIT names are renamed to, see
+/// [`with_renames`](crate::go::with_renames), don't collide with the
+/// identifiers generated for the other WIT names of the worlds, such as
+/// their interfaces, types, functions, fields and cases.
+///
+/// Returns an error describing the first collision found.
+pub fn check_renames(resolve: &Resolve, worlds: &[WorldId]) -> Result<(), String> {
+    let Some(renames) = renames() else {
+        return Ok(());
+    };
+    let mut names = BTreeSet::new();
+    let mut types = Vec::new();
+    let mut functions = Vec::new();
+    for world in worlds {
+        for item in resolve.worlds[*world]
+            .imports
+            .values()
+            .chain(resolve.worlds[*world].exports.values())
+        {
+            match item {
+                WorldItem::Interface { id, .. } => {
+                    let interface = &resolve.interfaces[*id];
+                    names.extend(interface.name.clone());
+                    types.extend(interface.types.values().copied());
+                    functions.extend(interface.functions.values());
+                }
+                WorldItem::Function(func) => functions.push(func),
+                WorldItem::Type(id) => types.push(*id),
+            }
+        }
+    }
+    names.extend(functions.iter().map(|func| func.item_name().to_string()));
+    for id in types {
+        let typ = &resolve.types[id];
+        names.extend(typ.name.clone());
+        match &typ.kind {
+            TypeDefKind::Record(record) => {
+                names.extend(record.fields.iter().map(|field| field.name.clone()));
+            }
+            TypeDefKind::Variant(variant) => {
+                names.extend(variant.cases.iter().map(|case| case.name.clone()));
+            }
+            TypeDefKind::Enum(enum_) => {
+                names.extend(enum_.cases.iter().map(|case| case.name.clone()));
+            }
+            TypeDefKind::Flags(flags) => {
+                names.extend(flags.flags.iter().map(|flag| flag.name.clone()));
+            }
+            _ => {}
+        }
+    }
+    let idents = names
+        .iter()
+        .filter(|name| renames.get(name).is_none())
+        .map(|name| (String::from(GoIdentifier::public(name)), name))
+        .collect::<BTreeMap<_, _>>();
+    for (name, ident) in renames.iter() {
+        if let Some(other) = idents.get(ident) {
+            return Err(format!(
+                "`{name}` is renamed to `{ident}`, which is also generated for `{other}`"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Prefixes the names of the types, so that they don't collide with the
+/// types of other worlds whose bindings are generated into the same Go
+/// package.
+///
+/// Like [`disambiguate`], this renames the types in the `Resolve`, and must
+/// be called before it. Resources keep their names, which are part of the
+/// core Wasm imports and exports, and their Go identifiers are prefixed when
+/// generated instead, see [`with_symbol_prefix`](crate::go::with_symbol_prefix).
+pub fn prefix_types(resolve: &mut Resolve, prefix: &str) {
+    let ids = resolve
+        .types
+        .iter()
+        .filter(|(id, typ)| {
+            typ.name.is_some()
+                && !matches!(
+                    resolve.types[follow_aliases(resolve, *id)].kind,
+                    TypeDefKind::Resource
+                )
+        })
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    for id in ids {
+        let typ = &mut resolve.types[id];
+        typ.name = typ.name.take().map(|name| format!("{prefix}-{name}"));
+    }
+}
+
+/// Follows a type through any aliases created by `use`.
+fn follow_aliases(resolve: &Resolve, mut id: TypeId) -> TypeId {
+    while let TypeDefKind::Type(Type::Id(target)) = resolve.types[id].kind {
+        id = target;
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::wit_parser::Resolve;
+
+    use crate::{
+        codegen::symbols::{
+            SymbolTable, check_renames, disambiguate, export_methods, imported_interface_name,
+            prefix_types,
+        },
+        go::{GoIdentifier, Renames, with_renames},
+    };
+
+    #[test]
+    fn test_check_renames() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface errors {
+                    record error-info { code: u32 }
+                    record api-error { message: string }
+                    check: func() -> result<error-info, api-error>;
+                }
+
+                world checked {
+                    import errors;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (world_id, _) = resolve.worlds.iter().next().expect("missing world");
+        let check = |text: &str| {
+            with_renames(Some(Renames::parse(text).unwrap()), || {
+                check_renames(&resolve, &[world_id])
+            })
+        };
+
+        assert_eq!(check("error-info = \"APIError\""), Ok(()));
+        assert_eq!(
+            check("error-info = \"ApiError\""),
+            Err(
+                "`error-info` is renamed to `ApiError`, which is also generated for `api-error`"
+                    .to_string()
+            )
+        );
+        // Renaming the other name as well resolves the collision
+        assert_eq!(
+            check("error-info = \"ApiError\"\napi-error = \"APIMessage\""),
+            Ok(())
+        );
+        assert_eq!(
+            check("error-info = \"Message\""),
+            Err(
+                "`error-info` is renamed to `Message`, which is also generated for `message`"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_declare() {
+        let mut table = SymbolTable::default();
+        let idents = |name: &str| vec![GoIdentifier::public(name)];
+        assert_eq!(table.declare("a", "point", Some("types"), idents), "point");
+        assert_eq!(
+            table.declare("b", "point", Some("types"), idents),
+            "types-point"
+        );
+        assert_eq!(
+            table.declare("c", "point", Some("types"), idents),
+            "point-2"
+        );
+        assert_eq!(table.declare("d", "point", None, idents), "point-3");
+        assert_eq!(table.warnings.len(), 3);
+    }
+
+    #[test]
+    fn test_disambiguate() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface a {
+                    record point { x: u32 }
+                    get: func() -> point;
+                }
+
+                interface b {
+                    record point { y: u32 }
+                    enum color { red }
+                    record red { z: u32 }
+                    get: func() -> point;
+                }
+
+                world colliding {
+                    import a;
+                    import b;
+                    export a;
+                    export b;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (world_id, _) = resolve.worlds.iter().next().expect("missing world");
+
+        let warnings = disambiguate(&mut resolve, &[world_id]);
+        println!("{warnings:#?}");
+        assert_eq!(warnings.len(), 2);
+
+        let names = resolve
+            .types
+            .iter()
+            .filter_map(|(_, typ)| typ.name.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["point", "b-point", "color", "b-red"]);
+
+        // The functions of exported interfaces are namespaced by their
+        // accessors, so they don't collide
+        let methods = export_methods(
+            &resolve,
+            &resolve.worlds[world_id],
+            &mut SymbolTable::default(),
+        );
+        let interfaces = methods
+            .interfaces
+            .iter()
+            .map(|interface| {
+                let methods = interface
+                    .methods
+                    .iter()
+                    .map(|method| String::from(GoIdentifier::public(&method.name)))
+                    .collect::<Vec<_>>();
+                (String::from(&interface.go_type), methods)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            interfaces,
+            [
+                ("CollidingA".to_string(), vec!["Get".to_string()]),
+                ("CollidingB".to_string(), vec!["Get".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_export_methods_reserved() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface resources {
+                    close: func();
+                }
+
+                world reserved {
+                    export close: func();
+                    export module: func();
+                    export greet: func();
+                    export greet-with-timeout: func();
+                    export resources;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (world_id, _) = resolve.worlds.iter().next().expect("missing world");
+
+        let mut table = SymbolTable::default();
+        let methods = export_methods(&resolve, &resolve.worlds[world_id], &mut table);
+        let functions = methods
+            .functions
+            .iter()
+            .map(|method| method.name.as_str())
+            .collect::<Vec<_>>();
+        // The methods generated on every instance, and the wrappers of
+        // `--call-timeouts`, are renamed
+        assert_eq!(
+            functions,
+            ["close-2", "module-2", "greet", "greet-with-timeout-2"]
+        );
+        // The accessor of the interface is qualified by its package, but its
+        // own functions are methods of another type
+        assert_eq!(methods.interfaces[0].name, "test-pkg-resources");
+        assert_eq!(methods.interfaces[0].methods[0].name, "close");
+        assert_eq!(table.warnings.len(), 4);
+        assert_eq!(
+            table.warnings[0],
+            "exported function `close` collides with another Go identifier, so it is generated as `Close2`"
+        );
+    }
+
+    #[test]
+    fn test_prefix_types() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface types {
+                    record point { x: u32 }
+                    resource fooer;
+                }
+
+                world prefixed {
+                    use types.{point, fooer};
+                    import types;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+
+        prefix_types(&mut resolve, "acme");
+
+        // Resources and their aliases keep their names
+        let names = resolve
+            .types
+            .iter()
+            .filter_map(|(_, typ)| typ.name.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["acme-point", "fooer", "acme-point", "fooer"]);
+    }
+
+    #[test]
+    fn test_disambiguate_packages() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:app;
+
+                package acme:geo {
+                    interface types {
+                        record point { x: u32 }
+                    }
+                    interface shapes {
+                        use types.{point};
+                        area: func(p: point) -> u32;
+                    }
+                    world base {
+                        import shapes;
+                    }
+                }
+
+                package other:geo {
+                    interface types {
+                        record point { lat: s32 }
+                    }
+                }
+
+                world app {
+                    include acme:geo/base;
+                    import other:geo/types;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let (world_id, _) = resolve
+            .worlds
+            .iter()
+            .find(|(_, world)| world.name == "app")
+            .expect("missing world");
+
+        let world = &resolve.worlds[world_id];
+        let names = resolve
+            .interfaces
+            .iter()
+            .map(|(id, _)| imported_interface_name(&resolve, world, id))
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["acme-geo-types", "shapes", "other-geo-types"]);
+
+        let warnings = disambiguate(&mut resolve, &[world_id]);
+        println!("{warnings:#?}");
+        assert_eq!(warnings.len(), 1);
+
+        let names = resolve
+            .types
+            .iter()
+            .filter_map(|(_, typ)| typ.name.as_deref())
+            .collect::<Vec<_>>();
+        // The alias used by `shapes` follows the renamed type
+        assert_eq!(
+            names,
+            ["acme-geo-types-point", "acme-geo-types-point", "point"]
+        );
+    }
+}
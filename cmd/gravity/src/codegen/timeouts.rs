@@ -0,0 +1,203 @@
+use genco::prelude::*;
+
+use crate::go::{
+    GoIdentifier, GoResult, GoType, comment,
+    imports::{
+        CONTEXT_CAUSE, CONTEXT_CONTEXT, CONTEXT_WITH_TIMEOUT_CAUSE, CONTEXT_WITHOUT_CANCEL,
+        ERRORS_NEW, TIME_DURATION, WAZERO_SYS_EXIT_CODE_DEADLINE_EXCEEDED,
+    },
+};
+
+/// The error returned by the variants of the exported functions taking a
+/// timeout on expiry.
+fn timeout_error() -> GoIdentifier {
+    GoIdentifier::prefixed_public("err-call-timeout")
+}
+
+/// Generator for the `ErrCallTimeout` error returned by the variants of the
+/// exported functions taking a timeout, shared by all instances in the file.
+pub struct CallTimeoutGenerator;
+
+impl FormatInto<Go> for CallTimeoutGenerator {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let err = &timeout_error();
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} is returned by the WithTimeout variants of the exported",
+                    String::from(err),
+                ),
+                "functions when a call doesn't return within its timeout.".into(),
+            ]))
+            var $err = $ERRORS_NEW("call timed out")
+        };
+    }
+}
+
+/// Returns the variant of the exported method taking a timeout, which calls
+/// the method in a goroutine, and closes the instance with Wazero's deadline
+/// exit code if the call doesn't return in time.
+///
+/// The variant returns the values returned by the method followed by an
+/// error, which is `ErrCallTimeout` on expiry, or the error of the context
+/// of the caller if it's done first, e.g. cancelled, in which case the
+/// instance is closed as well. Panics of the method, e.g.
+/// with `--error-mode panic`, are recovered in the goroutine and raised
+/// again in the caller's.
+pub(crate) fn timeout_wrapper(
+    instance: &GoIdentifier,
+    receiver: &GoIdentifier,
+    method: &GoIdentifier,
+    params: &[(GoIdentifier, GoType)],
+    result: &GoResult,
+) -> Tokens<Go> {
+    let wrapper = &GoIdentifier::public(format!("{}-with-timeout", String::from(method)));
+    let err = &timeout_error();
+    let (values, returns_err) = result.values();
+    let assigned = values
+        .iter()
+        .map(|(name, _)| format!("o.{name}"))
+        .chain(returns_err.then(|| "o.err".to_string()))
+        .collect::<Vec<_>>();
+    let call = quote!(i.$method(ctx$(for (name, _) in params => , $name)));
+    let results = if values.is_empty() {
+        quote!(error)
+    } else {
+        quote!(($(for (_, typ) in &values join (, ) => $typ), error))
+    };
+    let returned = |err: Tokens<Go>| {
+        let values = values.iter().map(|(name, _)| quote!(o.$(name.as_str())));
+        quote!($(for value in values.chain([err]) join (, ) => $value))
+    };
+    // The methods of exported interfaces convert their receiver back to the
+    // instance to close it
+    let interface = String::from(receiver) != String::from(instance);
+    let closed = &if interface {
+        quote!(instance)
+    } else {
+        quote!(i)
+    };
+    quote! {
+        $['\n']
+        $(comment(&[
+            format!(
+                "{} calls {} with a timeout, returning",
+                String::from(wrapper),
+                String::from(method),
+            ),
+            format!(
+                "{} and closing the instance if the call doesn't return in time.",
+                String::from(err),
+            ),
+            "If the context of the caller is done first, e.g. as it's cancelled, its error".into(),
+            "is returned instead, and the instance is closed as well.".into(),
+            "The call is only interrupted if the runtime closes modules when the context".into(),
+            "of their calls is done, e.g. with WithCloseOnContextDone; otherwise it keeps".into(),
+            "running in the background until it returns.".into(),
+        ]))
+        func (i *$receiver) $wrapper(
+            $['\r']
+            ctx $CONTEXT_CONTEXT,
+            timeout $TIME_DURATION,
+            $(for (name, typ) in params join ($['\r']) => $name $typ,)
+        ) $results {
+            ctx, cancel := $CONTEXT_WITH_TIMEOUT_CAUSE(ctx, timeout, $err)
+            defer cancel()
+
+            type outcome struct {
+                $(for (name, typ) in &values join ($['\r']) => $(name.as_str()) $typ)
+                $(if returns_err => err error)
+                recovered any
+            }
+            done := make(chan outcome, 1)
+            go func() {
+                var o outcome
+                defer func() {
+                    o.recovered = recover()
+                    done <- o
+                }()
+                $(if assigned.is_empty() {
+                    $call
+                } else {
+                    $(for name in &assigned join (, ) => $name) = $call
+                })
+            }()
+
+            select {
+            case o := <-done:
+                if o.recovered != nil {
+                    panic(o.recovered)
+                }
+                return $(returned(if returns_err { quote!(o.err) } else { quote!(nil) }))
+            case <-ctx.Done():
+                $(if interface => instance := (*$instance)(i))
+                if $closed.factory.release($closed) {
+                    $closed.module.CloseWithExitCode($CONTEXT_WITHOUT_CANCEL(ctx), $WAZERO_SYS_EXIT_CODE_DEADLINE_EXCEEDED)
+                }
+                $(if !values.is_empty() => var o outcome)
+                $(comment(&[
+                    format!("{} is only the cause if the timeout expired, rather than", String::from(err)),
+                    "the context of the caller being done.".into(),
+                ]))
+                if $CONTEXT_CAUSE(ctx) != $err {
+                    return $(returned(quote!(ctx.Err())))
+                }
+                return $(returned(quote!($err)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::go::{GoIdentifier, GoResult, GoType};
+
+    use super::timeout_wrapper;
+
+    #[test]
+    fn test_timeout_wrapper() {
+        let wrapper = timeout_wrapper(
+            &GoIdentifier::public("test-instance"),
+            &GoIdentifier::public("test-greeter"),
+            &GoIdentifier::public("greet"),
+            &[(GoIdentifier::local("name"), GoType::String)],
+            &GoResult::Anon(GoType::ValueOrError(Box::new(GoType::String))),
+        );
+        let output = wrapper.to_string().unwrap();
+
+        assert!(output.contains(
+            "func (i *TestGreeter) GreetWithTimeout(\n    ctx context.Context,\n    timeout time.Duration,\n    name string,\n) (string, error) {"
+        ));
+        assert!(output.contains("        o.result, o.err = i.Greet(ctx, name)"));
+        assert!(output.contains("        return o.result, o.err"));
+        assert!(output.contains(
+            "        instance := (*TestInstance)(i)\n        if instance.factory.release(instance) {"
+        ));
+        assert!(
+            output.contains(
+                "    ctx, cancel := context.WithTimeoutCause(ctx, timeout, ErrCallTimeout)"
+            )
+        );
+        assert!(output.contains(
+            "        if context.Cause(ctx) != ErrCallTimeout {\n            return o.result, ctx.Err()\n        }\n        return o.result, ErrCallTimeout"
+        ));
+
+        // Without any value, only the error is returned
+        let wrapper = timeout_wrapper(
+            &GoIdentifier::public("test-instance"),
+            &GoIdentifier::public("test-instance"),
+            &GoIdentifier::public("reset"),
+            &[],
+            &GoResult::Empty,
+        );
+        let output = wrapper.to_string().unwrap();
+
+        assert!(output.contains(") error {"));
+        assert!(output.contains("        i.Reset(ctx)\n"));
+        assert!(output.contains("        return nil\n"));
+        assert!(output.contains("        if i.factory.release(i) {\n"));
+        assert!(output.contains("            return ctx.Err()\n"));
+        assert!(output.contains("        return ErrCallTimeout\n"));
+    }
+}
@@ -0,0 +1,230 @@
+use crate::compat::wit_parser::{Function, Resolve, Type, TypeDefKind, World, WorldItem};
+use genco::prelude::*;
+
+use crate::{
+    codegen::helpers::{GUEST_ERROR, GUEST_TRAP, GUEST_TRAP_ERROR},
+    go::{
+        comment,
+        imports::{ERRORS_AS, FMT_SPRINTF, WAZERO_SYS_EXIT_ERROR},
+    },
+};
+
+/// Returns true if the world exports any function, whose calls may trap.
+pub fn uses_guest_traps(resolve: &Resolve, world: &World) -> bool {
+    world.exports.values().any(|item| match item {
+        WorldItem::Function(_) => true,
+        WorldItem::Interface { id, .. } => !resolve.interfaces[*id].functions.is_empty(),
+        WorldItem::Type(_) => false,
+    })
+}
+
+/// Returns true if the guest returns any `result` with a string error, by
+/// the result of an export or a parameter of an import.
+pub fn uses_guest_errors(resolve: &Resolve, world: &World) -> bool {
+    let params = world
+        .imports
+        .values()
+        .flat_map(|item| functions(resolve, item))
+        .flat_map(|func| func.params.iter().map(|(_, typ)| typ));
+    let results = world
+        .exports
+        .values()
+        .flat_map(|item| functions(resolve, item))
+        .flat_map(|func| &func.result);
+    params.chain(results).any(|typ| string_result(resolve, typ))
+}
+
+/// The functions of an item of a world.
+fn functions<'a>(resolve: &'a Resolve, item: &'a WorldItem) -> Vec<&'a Function> {
+    match item {
+        WorldItem::Function(func) => vec![func],
+        WorldItem::Interface { id, .. } => resolve.interfaces[*id].functions.values().collect(),
+        WorldItem::Type(_) => vec![],
+    }
+}
+
+/// Returns true if the type is a `result` with a string error.
+fn string_result(resolve: &Resolve, typ: &Type) -> bool {
+    let Type::Id(id) = typ else {
+        return false;
+    };
+    match &resolve.types[*id].kind {
+        TypeDefKind::Result(result) => result.err == Some(Type::String),
+        TypeDefKind::Type(typ) => string_result(resolve, typ),
+        _ => false,
+    }
+}
+
+/// Generator for the `GuestError` type of the errors returned by the guest,
+/// as the `err` case of a `result` with a string error, so they can be told
+/// apart from the errors of the calls themselves.
+///
+/// This must only be generated once per file, as it is shared by all
+/// factories in the file.
+pub struct GuestErrorGenerator;
+
+impl FormatInto<Go> for GuestErrorGenerator {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let guest_error = &GUEST_ERROR.ident();
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} is the error returned by the guest, as the `err` case of a",
+                    String::from(guest_error),
+                ),
+                "`result`, rather than an error of the call itself.".into(),
+            ]))
+            type $guest_error struct {
+                Message string
+            }
+            $['\n']
+            func (e *$guest_error) Error() string {
+                return e.Message
+            }
+        };
+    }
+}
+
+/// Generator for the `GuestTrapError` type wrapping the errors of the calls
+/// of exports, and the function wrapping them.
+///
+/// This must only be generated once per file, as it is shared by all
+/// factories in the file.
+pub struct GuestTrapGenerator;
+
+impl FormatInto<Go> for GuestTrapGenerator {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let trap_error = &GUEST_TRAP_ERROR.ident();
+        let trap = &GUEST_TRAP.ident();
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!(
+                    "{} is returned when a call of an export fails in the guest,",
+                    String::from(trap_error),
+                ),
+                "e.g. as it reached an `unreachable` instruction, accessed its memory out of".into(),
+                "bounds or exited, wrapping the error returned by Wazero.".into(),
+            ]))
+            type $trap_error struct {
+                $(comment(&["Function is the name of the core Wasm function that was called."]))
+                Function string
+                $(comment(&[
+                    "Exited is true if the guest exited, e.g. with `proc_exit`, or was closed",
+                    "during the call, rather than trapped.",
+                ]))
+                Exited bool
+                $(comment(&["ExitCode is the exit code of the guest, if it exited."]))
+                ExitCode uint32
+                Err      error
+            }
+            $['\n']
+            func (e *$trap_error) Error() string {
+                if e.Exited {
+                    return $FMT_SPRINTF("guest exited with code %d during the call of %s", e.ExitCode, e.Function)
+                }
+                return $FMT_SPRINTF("guest trapped during the call of %s: %v", e.Function, e.Err)
+            }
+            $['\n']
+            func (e *$trap_error) Unwrap() error {
+                return e.Err
+            }
+            $['\n']
+            $(comment(&[format!(
+                "{} wraps the error of the call of the function in a {}.",
+                String::from(trap),
+                String::from(trap_error),
+            )]))
+            func $trap(function string, err error) error {
+                trap := &$trap_error{Function: function, Err: err}
+                var exit *$WAZERO_SYS_EXIT_ERROR
+                if $ERRORS_AS(err, &exit) {
+                    trap.Exited = true
+                    trap.ExitCode = exit.ExitCode()
+                }
+                return trap
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use genco::{lang::go::Tokens, tokens::FormatInto};
+
+    use crate::compat::wit_parser::Resolve;
+
+    use super::{GuestErrorGenerator, GuestTrapGenerator, uses_guest_errors};
+
+    #[test]
+    fn test_generate_guest_traps() {
+        let mut tokens = Tokens::new();
+        GuestTrapGenerator.format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("type GuestTrapError struct {"));
+        assert!(output.contains("func (e *GuestTrapError) Unwrap() error {"));
+        assert!(output.contains("func guestTrap(function string, err error) error {"));
+        assert!(output.contains(
+            "    if errors.As(err, &exit) {\n        trap.Exited = true\n        trap.ExitCode = exit.ExitCode()\n    }"
+        ));
+    }
+
+    #[test]
+    fn test_generate_guest_errors() {
+        let mut tokens = Tokens::new();
+        GuestErrorGenerator.format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+
+        assert!(output.contains("type GuestError struct {\n    Message string\n}"));
+        assert!(output.contains("func (e *GuestError) Error() string {\n    return e.Message\n}"));
+    }
+
+    #[test]
+    fn test_uses_guest_errors() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:pkg;
+
+                interface host {
+                    type outcome = result<u32, string>;
+
+                    report: func(outcome: outcome);
+                    code: func() -> result<u32, string>;
+                }
+
+                world importing {
+                    import host;
+                }
+
+                world exporting {
+                    export parse: func(input: string) -> result<u32, string>;
+                }
+
+                world plain {
+                    import host-code: func() -> result<u32, string>;
+                    export check: func(input: result<u32, string>) -> u32;
+                }
+                "#,
+            )
+            .expect("failed to parse WIT");
+        let world = |name: &str| {
+            let (_, world) = resolve
+                .worlds
+                .iter()
+                .find(|(_, world)| world.name == name)
+                .expect("missing world");
+            world
+        };
+
+        assert!(uses_guest_errors(&resolve, world("importing")));
+        assert!(uses_guest_errors(&resolve, world("exporting")));
+        // The host returns the results of imports, and passes the
+        // parameters of exports
+        assert!(!uses_guest_errors(&resolve, world("plain")));
+    }
+}
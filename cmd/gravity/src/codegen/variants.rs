@@ -0,0 +1,205 @@
+use genco::prelude::*;
+
+use crate::{
+    codegen::derives::{DefinedTypes, equal},
+    go::{GoIdentifier, GoType, comment},
+};
+
+/// The name of the Go struct holding a case of the variant.
+pub fn case_name(variant: &str, case: &str) -> GoIdentifier {
+    GoIdentifier::public(format!("{variant}-{case}"))
+}
+
+/// The name of the Go function constructing a case of the variant.
+pub fn constructor_name(variant: &str, case: &str) -> GoIdentifier {
+    GoIdentifier::public(format!("new-{variant}-{case}"))
+}
+
+/// The name of the Go interface visiting the cases of the variant.
+pub fn visitor_name(variant: &str) -> GoIdentifier {
+    GoIdentifier::public(format!("{variant}-visitor"))
+}
+
+/// The name of the Go function unmarshalling any case of the variant, which
+/// is generated with `--derive json`.
+pub fn unmarshal_name(variant: &str) -> GoIdentifier {
+    GoIdentifier::public(format!("unmarshal-{variant}"))
+}
+
+/// The name of the unexported method marking the cases of the variant.
+fn marker_name(variant: &str) -> GoIdentifier {
+    GoIdentifier::private(format!("is-{variant}"))
+}
+
+/// The Go variable holding the payload of the case of a variant, `result` or
+/// `option` being lowered, which for variants is the `Value` of the struct of
+/// the case.
+pub const PAYLOAD: &str = "variantPayload";
+
+/// Generator for the Go types of a variant.
+///
+/// The variant is an interface implemented by a struct for each of its
+/// cases, which holds the payload of the case, if any. Each case has a
+/// constructor, and the variant can be visited by an interface with a method
+/// for each case, so the compiler checks that host code handles every case.
+pub struct VariantGenerator<'a> {
+    name: &'a str,
+    go_name: &'a GoIdentifier,
+    cases: &'a [(String, Option<GoType>)],
+    /// The user-defined types the payloads are compared with, if `Equal`
+    /// methods are generated.
+    equal: Option<&'a DefinedTypes>,
+}
+
+impl<'a> VariantGenerator<'a> {
+    /// Create a new generator for the variant with the given WIT name.
+    pub fn new(
+        name: &'a str,
+        go_name: &'a GoIdentifier,
+        cases: &'a [(String, Option<GoType>)],
+    ) -> Self {
+        Self {
+            name,
+            go_name,
+            cases,
+            equal: None,
+        }
+    }
+
+    /// Generates `Equal` methods for the cases, comparing their payloads as
+    /// the fields of records are.
+    pub fn with_equal(mut self, types: &'a DefinedTypes) -> Self {
+        self.equal = Some(types);
+        self
+    }
+}
+
+impl FormatInto<Go> for VariantGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        let variant = self.go_name;
+        let marker = &marker_name(self.name);
+        let visitor = &visitor_name(self.name);
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!("{} is a `{}` variant, holding one of its cases.", String::from(variant), self.name),
+            ]))
+            type $variant interface {
+                $marker()
+                $(comment(&["Visit calls the method of the visitor for the case of the variant."]))
+                Visit(visitor $visitor)
+                $(if self.equal.is_some() {
+                    $(comment(&["Equal returns true if the other variant holds the same case with an equal payload."]))
+                    Equal(other $variant) bool
+                })
+            }
+            $['\n']
+            $(comment(&[
+                format!("{} has a method for each case of {}.", String::from(visitor), String::from(variant)),
+            ]))
+            type $visitor interface {
+                $(for (case, payload) in self.cases join ($['\r']) =>
+                    $(match payload {
+                        Some(payload) => $(GoIdentifier::public(case))(value $payload),
+                        None => $(GoIdentifier::public(case))(),
+                    })
+                )
+            }
+        };
+        for (case, payload) in self.cases {
+            let name = &case_name(self.name, case);
+            let constructor = &constructor_name(self.name, case);
+            let method = &GoIdentifier::public(case);
+            match payload {
+                Some(payload) => quote_in! { *tokens =>
+                    $['\n']
+                    type $name struct {
+                        Value $payload
+                    }
+                    $['\n']
+                    func ($name) $marker() {}
+                    $['\n']
+                    func (c $name) Visit(visitor $visitor) {
+                        visitor.$method(c.Value)
+                    }
+                    $['\n']
+                    func $constructor(value $payload) $variant {
+                        return $name{Value: value}
+                    }
+                },
+                None => quote_in! { *tokens =>
+                    $['\n']
+                    type $name struct{}
+                    $['\n']
+                    func ($name) $marker() {}
+                    $['\n']
+                    func ($name) Visit(visitor $visitor) {
+                        visitor.$method()
+                    }
+                    $['\n']
+                    func $constructor() $variant {
+                        return $name{}
+                    }
+                },
+            }
+            if let Some(types) = self.equal {
+                quote_in! { *tokens =>
+                    $['\n']
+                    $(match payload {
+                        Some(payload) => {
+                            func (c $name) Equal(other $variant) bool {
+                                o, ok := other.($name)
+                                return ok && $(equal(&quote!(c.Value), &quote!(o.Value), payload, types))
+                            }
+                        }
+                        None => {
+                            func ($name) Equal(other $variant) bool {
+                                _, ok := other.($name)
+                                return ok
+                            }
+                        }
+                    })
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use genco::prelude::*;
+
+    use crate::{
+        codegen::variants::VariantGenerator,
+        go::{GoIdentifier, GoType},
+    };
+
+    #[test]
+    fn test_variant_generator() {
+        let cases = [
+            ("text".to_string(), Some(GoType::String)),
+            ("none".to_string(), None),
+        ];
+        let name = GoIdentifier::public("message");
+        let mut tokens = Tokens::<Go>::new();
+        VariantGenerator::new("message", &name, &cases).format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+        println!("{output}");
+
+        assert!(output.contains("type Message interface {\n    isMessage()\n"));
+        assert!(output.contains("    Visit(visitor MessageVisitor)\n}"));
+        assert!(
+            output
+                .contains("type MessageVisitor interface {\n    Text(value string)\n    None()\n}")
+        );
+        assert!(output.contains("type MessageText struct {\n    Value string\n}"));
+        assert!(output.contains(
+            "func (c MessageText) Visit(visitor MessageVisitor) {\n    visitor.Text(c.Value)\n}"
+        ));
+        assert!(output.contains(
+            "func NewMessageText(value string) Message {\n    return MessageText{Value: value}\n}"
+        ));
+        assert!(output.contains("type MessageNone struct{}"));
+        assert!(output.contains("func NewMessageNone() Message {\n    return MessageNone{}\n}"));
+    }
+}
@@ -0,0 +1,283 @@
+use genco::prelude::*;
+
+use crate::{
+    codegen::{
+        imports::ImportAnalyzer,
+        ir::{AnalyzedImports, AnalyzedInterface, InterfaceMethod},
+        mocks::return_type,
+    },
+    compat::wit_parser::{Resolve, WorldId},
+    go::{
+        GoIdentifier, GoType, comment,
+        imports::{CONTEXT_CONTEXT, CRYPTO_RAND_READ, ENCODING_BINARY_LITTLE_ENDIAN, TIME_NOW},
+    },
+};
+
+/// Generator for adapters implementing well-known WASI interfaces with the
+/// Go standard library.
+///
+/// The factory constructor uses an adapter in place of any such import the
+/// host doesn't provide (i.e. is `nil`), so the host only implements the
+/// interfaces it wants to override.
+///
+/// Only interfaces whose functions are all supported get an adapter:
+///
+/// - `wasi:clocks/wall-clock`, backed by `time.Now`.
+/// - `wasi:random/random`, backed by `crypto/rand`.
+/// - `wasi:random/insecure`, backed by `crypto/rand` as well.
+pub struct WasiAdapterGenerator<'a> {
+    analyzed: &'a AnalyzedImports,
+}
+
+impl<'a> WasiAdapterGenerator<'a> {
+    /// Create a new adapter generator for the given analyzed imports.
+    pub fn new(analyzed: &'a AnalyzedImports) -> Self {
+        Self { analyzed }
+    }
+
+    /// Generate the adapter struct and methods for an interface.
+    fn generate_adapter(&self, interface: &AnalyzedInterface, tokens: &mut Tokens<Go>) {
+        let interface_name = &interface.go_interface_name;
+        let adapter_name = &adapter_name(interface_name);
+        let package = package_name(&interface.wazero_module_name);
+
+        quote_in! { *tokens =>
+            $['\n']
+            $(comment(&[
+                format!("{} implements {} with the Go standard library,", String::from(adapter_name), String::from(interface_name)),
+                format!("and is used in place of the {package} import if it isn't provided."),
+            ]))
+            type $adapter_name struct{}
+        };
+
+        for method in &interface.methods {
+            let body = method_body(package, method).expect("method of WASI adapter not supported");
+            quote_in! { *tokens =>
+                $['\n']
+                func ($adapter_name) $(&method.go_method_name)(
+                    ctx $CONTEXT_CONTEXT,
+                    $(for param in &method.parameters join ($['\r']) => $(&param.name) $(&param.go_type),)
+                ) $(return_type(method)) {
+                    $body
+                }
+            };
+        }
+    }
+}
+
+impl FormatInto<Go> for WasiAdapterGenerator<'_> {
+    fn format_into(self, tokens: &mut Tokens<Go>) {
+        for interface in &self.analyzed.interfaces {
+            if has_adapter(interface) {
+                self.generate_adapter(interface, tokens);
+            }
+        }
+    }
+}
+
+/// The name of the adapter type implementing the given interface.
+pub(crate) fn adapter_name(interface_name: &GoIdentifier) -> GoIdentifier {
+    GoIdentifier::private(format!("wasi-{}", String::from(interface_name)))
+}
+
+/// Returns true if the interface is a well-known WASI interface, all of
+/// whose functions are implemented by an adapter.
+pub(crate) fn has_adapter(interface: &AnalyzedInterface) -> bool {
+    let package = package_name(&interface.wazero_module_name);
+    !interface.methods.is_empty()
+        && interface
+            .methods
+            .iter()
+            .all(|method| method_body(package, method).is_some())
+}
+
+/// Returns the WASI interfaces imported by the worlds that get no adapter,
+/// e.g. `wasi:filesystem/types@0.2.0`, so the host still has to implement
+/// them with `--wasi-adapters`.
+pub fn unadapted_interfaces(resolve: &Resolve, worlds: &[WorldId]) -> Vec<String> {
+    let mut unadapted = Vec::new();
+    for world in worlds {
+        let analyzed = ImportAnalyzer::new(resolve, &resolve.worlds[*world]).analyze();
+        for name in unadapted_imports(&analyzed) {
+            if !unadapted.iter().any(|unadapted| unadapted == name) {
+                unadapted.push(name.to_string());
+            }
+        }
+    }
+    unadapted
+}
+
+/// The Wazero module names of the imported WASI interfaces without an
+/// adapter.
+fn unadapted_imports(analyzed: &AnalyzedImports) -> impl Iterator<Item = &str> {
+    analyzed
+        .interfaces
+        .iter()
+        .filter(|interface| {
+            interface.wazero_module_name.starts_with("wasi:") && !has_adapter(interface)
+        })
+        .map(|interface| interface.wazero_module_name.as_str())
+}
+
+/// The name of the interface without its version, e.g.
+/// `wasi:clocks/wall-clock` for `wasi:clocks/wall-clock@0.2.0`.
+fn package_name(module_name: &str) -> &str {
+    module_name
+        .split_once('@')
+        .map_or(module_name, |(name, _)| name)
+}
+
+/// The body of the adapter method implementing the given function of a
+/// WASI interface, if supported.
+fn method_body(package: &str, method: &InterfaceMethod) -> Option<Tokens<Go>> {
+    let result = method.return_type.as_ref().map(|ret| &ret.go_type);
+    let param = method.parameters.first().map(|param| &param.name);
+    let body = match (package, method.name.as_str(), result, param) {
+        ("wasi:clocks/wall-clock", "now", Some(datetime @ GoType::UserDefined(_)), None) => {
+            quote! {
+                now := $TIME_NOW()
+                return $datetime{
+                    Seconds: uint64(now.Unix()),
+                    Nanoseconds: uint32(now.Nanosecond()),
+                }
+            }
+        }
+        ("wasi:clocks/wall-clock", "resolution", Some(datetime @ GoType::UserDefined(_)), None) => {
+            quote!(return $datetime{Nanoseconds: 1})
+        }
+        ("wasi:random/random", "get-random-bytes", Some(_), Some(len))
+        | ("wasi:random/insecure", "get-insecure-random-bytes", Some(_), Some(len)) => quote! {
+            buf := make([]byte, $len)
+            $CRYPTO_RAND_READ(buf)
+            return buf
+        },
+        ("wasi:random/random", "get-random-u64", Some(GoType::Uint64), None)
+        | ("wasi:random/insecure", "get-insecure-random-u64", Some(GoType::Uint64), None) => {
+            quote! {
+                var buf [8]byte
+                $CRYPTO_RAND_READ(buf[:])
+                return $ENCODING_BINARY_LITTLE_ENDIAN.Uint64(buf[:])
+            }
+        }
+        _ => return None,
+    };
+    Some(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::wit_parser::Type;
+    use genco::prelude::*;
+
+    use crate::{
+        codegen::{
+            fixtures::{analyzed, interface, method, param, returning},
+            wasi::{WasiAdapterGenerator, has_adapter, unadapted_imports},
+        },
+        go::GoType,
+    };
+
+    #[test]
+    fn test_generate_wasi_adapters() {
+        let datetime = GoType::UserDefined("datetime".to_string());
+        let len = param("len", GoType::Uint64, Type::U64);
+        let analyzed = analyzed(vec![
+            interface(
+                "wall-clock",
+                "wasi:clocks/wall-clock@0.2.0",
+                vec![
+                    method("now", vec![], returning(datetime.clone(), Type::U64)),
+                    method("resolution", vec![], returning(datetime, Type::U64)),
+                ],
+            ),
+            interface(
+                "random",
+                "wasi:random/random@0.2.0",
+                vec![
+                    method(
+                        "get-random-bytes",
+                        vec![len],
+                        returning(GoType::Slice(Box::new(GoType::Uint8)), Type::U64),
+                    ),
+                    method(
+                        "get-random-u64",
+                        vec![],
+                        returning(GoType::Uint64, Type::U64),
+                    ),
+                ],
+            ),
+            interface(
+                "logger",
+                "test:pkg/logger",
+                vec![method("now", vec![], returning(GoType::Uint64, Type::U64))],
+            ),
+        ]);
+        let mut tokens = Tokens::<Go>::new();
+        WasiAdapterGenerator::new(&analyzed).format_into(&mut tokens);
+        let output = tokens.to_string().unwrap();
+        println!("{output}");
+
+        assert!(output.contains("type wasiITestWallClock struct{}"));
+        assert!(output.contains("func (wasiITestWallClock) Now("));
+        assert!(output.contains("Seconds: uint64(now.Unix()),"));
+        assert!(output.contains("return Datetime{Nanoseconds: 1}"));
+        assert!(output.contains("func (wasiITestRandom) GetRandomBytes(\n    ctx context.Context,\n    len uint64,\n) []byte {"));
+        assert!(output.contains("buf := make([]byte, len)\n    rand.Read(buf)"));
+        assert!(output.contains("return binary.LittleEndian.Uint64(buf[:])"));
+        assert!(!output.contains("wasiITestLogger"));
+    }
+
+    #[test]
+    fn test_has_adapter_requires_all_functions() {
+        let monotonic = interface(
+            "monotonic-clock",
+            "wasi:clocks/monotonic-clock@0.2.0",
+            vec![method("now", vec![], returning(GoType::Uint64, Type::U64))],
+        );
+        assert!(!has_adapter(&monotonic));
+
+        let insecure = interface(
+            "insecure",
+            "wasi:random/insecure",
+            vec![method(
+                "get-insecure-random-u64",
+                vec![],
+                returning(GoType::Uint64, Type::U64),
+            )],
+        );
+        assert!(has_adapter(&insecure));
+    }
+
+    #[test]
+    fn test_unadapted_imports() {
+        let analyzed = analyzed(vec![
+            interface(
+                "insecure",
+                "wasi:random/insecure@0.2.0",
+                vec![method(
+                    "get-insecure-random-u64",
+                    vec![],
+                    returning(GoType::Uint64, Type::U64),
+                )],
+            ),
+            interface(
+                "types",
+                "wasi:filesystem/types@0.2.0",
+                vec![method(
+                    "filesystem-error-code",
+                    vec![],
+                    returning(GoType::Uint64, Type::U64),
+                )],
+            ),
+            interface(
+                "logger",
+                "test:pkg/logger",
+                vec![method("log", vec![], returning(GoType::Uint64, Type::U64))],
+            ),
+        ]);
+        assert_eq!(
+            unadapted_imports(&analyzed).collect::<Vec<_>>(),
+            ["wasi:filesystem/types@0.2.0"]
+        );
+    }
+}
@@ -1,23 +1,165 @@
-use genco::prelude::*;
+use std::{collections::BTreeSet, io::Write};
 
-use crate::go::{GoIdentifier, embed};
+use crate::compat::wasmparser::{ExternalKind, Payload, TypeRef};
+use flate2::{Compression, write::GzEncoder};
+use genco::{prelude::*, tokens::static_literal};
+
+use crate::go::{
+    GoIdentifier, embed,
+    imports::{
+        COMPRESS_GZIP_NEW_READER, ENCODING_BASE64_NEW_DECODER, ENCODING_BASE64_STD_ENCODING,
+        IO_READ_ALL, STRINGS_NEW_READER,
+    },
+};
 
 /// The WebAssembly data for a world, either inline or embedded using go:embed.
 pub enum WasmData<'a> {
     /// The WebAssembly file is inlined as a byte array.
     Inline(&'a [u8]),
+    /// The WebAssembly file is inlined as an escaped string constant, which
+    /// Go compiles faster than a byte array.
+    String(&'a [u8]),
+    /// The WebAssembly file is inlined as a gzip-compressed, base64-encoded
+    /// string, which is decompressed when the package is initialized.
+    Gzip(&'a [u8]),
     /// The WebAssembly file is embedded using go:embed.
     Embedded(&'a str),
 }
 
+/// Returns true if the core Wasm module defines or imports a 64-bit memory.
+///
+/// Wazero only supports 32-bit memories, so the bindings can't instantiate
+/// these modules. Modules that fail to parse are left for Wazero to reject.
+pub fn uses_memory64(module: &[u8]) -> bool {
+    crate::compat::wasmparser::Parser::new(0)
+        .parse_all(module)
+        .filter_map(Result::ok)
+        .any(|payload| match payload {
+            Payload::MemorySection(memories) => memories
+                .into_iter()
+                .any(|memory| memory.is_ok_and(|memory| memory.memory64)),
+            Payload::ImportSection(imports) => imports.into_iter().any(|import| {
+                import.is_ok_and(
+                    |import| matches!(import.ty, TypeRef::Memory(memory) if memory.memory64),
+                )
+            }),
+            _ => false,
+        })
+}
+
+/// Returns the module and field names of the imports of the core Wasm module.
+pub fn module_imports(module: &[u8]) -> BTreeSet<(String, String)> {
+    crate::compat::wasmparser::Parser::new(0)
+        .parse_all(module)
+        .filter_map(Result::ok)
+        .filter_map(|payload| match payload {
+            Payload::ImportSection(imports) => Some(imports),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|import| (import.module.to_string(), import.name.to_string()))
+        .collect()
+}
+
+/// Returns the names of the functions exported by the core Wasm module.
+pub fn module_exports(module: &[u8]) -> BTreeSet<String> {
+    crate::compat::wasmparser::Parser::new(0)
+        .parse_all(module)
+        .filter_map(Result::ok)
+        .filter_map(|payload| match payload {
+            Payload::ExportSection(exports) => Some(exports),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|export| export.kind == ExternalKind::Func)
+        .map(|export| export.name.to_string())
+        .collect()
+}
+
+/// Returns the WIT metadata of the Wasm file, i.e. the contents of its
+/// `component-type` custom sections.
+pub fn component_metadata(wasm: &[u8]) -> Vec<u8> {
+    crate::compat::wasmparser::Parser::new(0)
+        .parse_all(wasm)
+        .filter_map(Result::ok)
+        .filter_map(|payload| match payload {
+            Payload::CustomSection(section) if section.name().starts_with("component-type") => {
+                Some(section.data().to_vec())
+            }
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+/// Escapes the bytes as the contents of a Go string literal, keeping the
+/// printable ASCII characters as they are.
+fn escape(bytes: &[u8]) -> String {
+    let mut escaped = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        match b {
+            b'"' | b'\\' => {
+                escaped.push('\\');
+                escaped.push(*b as char);
+            }
+            b' '..=b'~' => escaped.push(*b as char),
+            b => escaped.push_str(&format!("\\x{b:02x}")),
+        }
+    }
+    escaped
+}
+
+/// Compresses the bytes with gzip.
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder
+        .write_all(bytes)
+        .and_then(|_| encoder.finish())
+        .expect("failed to compress the WebAssembly file")
+}
+
+/// Encodes the bytes with the standard, padded base64 alphabet.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
 pub(crate) struct Wasm<'a> {
     var: &'a GoIdentifier,
     data: WasmData<'a>,
+    /// Whether to import the embed package, which must only happen once.
+    import_embed: bool,
 }
 
 impl<'a> Wasm<'a> {
     pub(crate) fn new(var: &'a GoIdentifier, data: WasmData<'a>) -> Self {
-        Self { var, data }
+        Self {
+            var,
+            data,
+            import_embed: true,
+        }
+    }
+
+    /// Skips importing the embed package, when an earlier Wasm already has.
+    pub(crate) fn without_import(mut self) -> Self {
+        self.import_embed = false;
+        self
     }
 }
 
@@ -40,10 +182,43 @@ impl FormatInto<Go> for Wasm<'_> {
                     }
                 };
             }
-            WasmData::Embedded(name) => {
+            WasmData::String(bytes) => {
+                let string = &GoIdentifier::private(format!("{}-string", String::from(self.var)));
+
                 quote_in! { *tokens =>
-                    import _ "embed"
+                    const $string = $(static_literal("\""))$(escape(bytes))$(static_literal("\""))
+                    $['\n']
+                    var $(self.var) = []byte($string)
+                };
+            }
+            WasmData::Gzip(bytes) => {
+                let compressed = &GoIdentifier::private(format!("{}-gzip", String::from(self.var)));
+                let encoded = base64(&gzip(bytes));
 
+                quote_in! { *tokens =>
+                    const $compressed = $(quoted(encoded))
+                    $['\n']
+                    var $(self.var) = func() []byte {
+                        reader, err := $COMPRESS_GZIP_NEW_READER($ENCODING_BASE64_NEW_DECODER($ENCODING_BASE64_STD_ENCODING, $STRINGS_NEW_READER($compressed)))
+                        if err != nil {
+                            panic(err)
+                        }
+                        wasm, err := $IO_READ_ALL(reader)
+                        if err != nil {
+                            panic(err)
+                        }
+                        return wasm
+                    }()
+                };
+            }
+            WasmData::Embedded(name) => {
+                if self.import_embed {
+                    quote_in! { *tokens =>
+                        import _ "embed"
+                        $['\n']
+                    };
+                }
+                quote_in! { *tokens =>
                     $(embed(name))
                     var $(self.var) []byte
                 }
@@ -54,10 +229,16 @@ impl FormatInto<Go> for Wasm<'_> {
 
 #[cfg(test)]
 mod tests {
+    use std::{collections::BTreeSet, io::Read};
+
+    use flate2::read::GzDecoder;
     use genco::{prelude::*, tokens::Tokens};
 
     use crate::{
-        codegen::wasm::{Wasm, WasmData},
+        codegen::wasm::{
+            Wasm, WasmData, base64, component_metadata, module_exports, module_imports,
+            uses_memory64,
+        },
         go::GoIdentifier,
     };
 
@@ -75,6 +256,73 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_string_wasm() {
+        let var = GoIdentifier::private("wasm");
+        let wasm = WasmData::String(b"\0asm \x01\"\\\xff");
+        let mut tokens = Tokens::<Go>::new();
+        Wasm::new(&var, wasm).format_into(&mut tokens);
+        assert_eq!(
+            tokens.to_string().unwrap(),
+            r#"const wasmString = "\x00asm \x01\"\\\xff"
+
+var wasm = []byte(wasmString)"#
+        );
+    }
+
+    #[test]
+    fn test_gzip_wasm() {
+        let var = GoIdentifier::private("wasm");
+        let module = b"\0asm\x01\0\0\0".repeat(64);
+        let mut tokens = Tokens::<Go>::new();
+        Wasm::new(&var, WasmData::Gzip(&module)).format_into(&mut tokens);
+        let generated = tokens.to_file_string().unwrap();
+
+        let encoded = generated
+            .lines()
+            .find_map(|line| line.strip_prefix("const wasmGzip = \""))
+            .and_then(|line| line.strip_suffix('"'))
+            .expect("missing the compressed Wasm");
+        let compressed = base64_decode(encoded);
+        assert!(compressed.len() < module.len());
+        let mut decompressed = Vec::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, module);
+        assert!(generated.contains(
+            "reader, err := gzip.NewReader(base64.NewDecoder(base64.StdEncoding, strings.NewReader(wasmGzip)))"
+        ));
+        assert!(generated.contains("wasm, err := io.ReadAll(reader)"));
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foob"), "Zm9vYg==");
+        assert_eq!(base64(&[0xfb, 0xff]), "+/8=");
+    }
+
+    /// Decodes the padded base64, to check the compressed Wasm.
+    fn base64_decode(encoded: &str) -> Vec<u8> {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut decoded = Vec::new();
+        for chunk in encoded.as_bytes().chunks(4) {
+            let digits = chunk.iter().take_while(|c| **c != b'=').collect::<Vec<_>>();
+            let n = digits.iter().enumerate().fold(0u32, |n, (i, c)| {
+                let digit = ALPHABET.iter().position(|a| a == *c).unwrap() as u32;
+                n | digit << (18 - 6 * i)
+            });
+            for i in 0..digits.len() - 1 {
+                decoded.push((n >> (16 - 8 * i)) as u8);
+            }
+        }
+        decoded
+    }
+
     #[test]
     fn test_embedded_wasm() {
         let var = GoIdentifier::private("wasm");
@@ -86,4 +334,41 @@ mod tests {
             "import _ \"embed\"\n\n//go:embed hello.wasm\nvar wasm []byte"
         );
     }
+
+    #[test]
+    fn test_uses_memory64() {
+        // A module with a single memory of one page, i.e. `(memory 1)`
+        let mut module = b"\0asm\x01\0\0\0\x05\x03\x01\x00\x01".to_vec();
+        assert!(!uses_memory64(&module));
+
+        // Setting the memory64 flag, i.e. `(memory i64 1)`
+        module[11] = 0x04;
+        assert!(uses_memory64(&module));
+    }
+
+    #[test]
+    fn test_component_metadata() {
+        // A module with a `component-type:w` custom section holding `wit`,
+        // followed by a `name` section
+        let module = b"\0asm\x01\0\0\0\x00\x14\x10component-type:wwit\x00\x05\x04name";
+        assert_eq!(component_metadata(module), b"wit");
+    }
+
+    #[test]
+    fn test_module_imports() {
+        // A module importing a function, i.e. `(import "env" "f" (func))`
+        let module = b"\0asm\x01\0\0\0\x02\x09\x01\x03env\x01f\x00\x00";
+        assert_eq!(
+            module_imports(module),
+            BTreeSet::from([("env".to_string(), "f".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_module_exports() {
+        // A module exporting a function and its memory, i.e.
+        // `(func (export "f")) (memory (export "memory") 0)`
+        let module = b"\0asm\x01\0\0\0\x01\x04\x01\x60\x00\x00\x03\x02\x01\x00\x05\x03\x01\x00\x00\x07\x0e\x02\x01f\x00\x00\x06memory\x02\x00\x0a\x04\x01\x02\x00\x0b";
+        assert_eq!(module_exports(module), BTreeSet::from(["f".to_string()]));
+    }
 }
@@ -0,0 +1,37 @@
+//! The parts of the WebAssembly tooling gravity is built against.
+//!
+//! Gravity depends on the API of `wit-bindgen-core` for the instructions of
+//! the canonical ABI, the sizes and alignments of types and the `Resolve` of
+//! the WIT, as well as on `wit-component` and `wasmparser` to decode the
+//! Wasm. These crates are only used through this module, so supporting a new
+//! version only requires a feature selecting it here, and adapting anything
+//! that changed below.
+//!
+//! The `wit-bindgen-0-46` feature, the default, builds against
+//! `wit-bindgen-core` 0.46 and the matching `wit-component` 0.239, while
+//! `wit-bindgen-0-47` builds against 0.47 and 0.240.
+
+#[cfg(all(feature = "wit-bindgen-0-46", feature = "wit-bindgen-0-47"))]
+compile_error!("only one of the `wit-bindgen-0-46` and `wit-bindgen-0-47` features can be enabled");
+
+#[cfg(not(any(feature = "wit-bindgen-0-46", feature = "wit-bindgen-0-47")))]
+compile_error!("one of the `wit-bindgen-0-46` or `wit-bindgen-0-47` features must be enabled");
+
+// The canonical ABI, i.e. the instructions lifting and lowering values, and
+// the WIT of a component, i.e. the `Resolve`, its types and their sizes
+#[cfg(feature = "wit-bindgen-0-46")]
+pub use wit_bindgen_core_0_46::{abi, wit_parser};
+#[cfg(all(feature = "wit-bindgen-0-47", not(feature = "wit-bindgen-0-46")))]
+pub use wit_bindgen_core_0_47::{abi, wit_parser};
+
+// Decoding the WIT embedded in a core module, and printing it
+#[cfg(feature = "wit-bindgen-0-46")]
+pub use wit_component_0_239 as wit_component;
+#[cfg(all(feature = "wit-bindgen-0-47", not(feature = "wit-bindgen-0-46")))]
+pub use wit_component_0_240 as wit_component;
+
+// Parsing the sections of a core module
+#[cfg(feature = "wit-bindgen-0-46")]
+pub use wasmparser_0_239 as wasmparser;
+#[cfg(all(feature = "wit-bindgen-0-47", not(feature = "wit-bindgen-0-46")))]
+pub use wasmparser_0_240 as wasmparser;
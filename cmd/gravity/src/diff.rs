@@ -0,0 +1,270 @@
+//! Unified diffs between the files on disk and the generated ones, printed by
+//! `--dry-run`.
+
+use std::fmt::Write;
+
+/// The number of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+/// An edit turning the old lines into the new ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    /// The old line at the index is kept, as the new line at the index.
+    Keep(usize, usize),
+    /// The old line at the index is removed.
+    Remove(usize),
+    /// The new line at the index is inserted.
+    Insert(usize),
+}
+
+/// Returns the unified diff of the file at `path`, from its `old` contents,
+/// if it exists, to its `new` ones, or an empty string if these are the same.
+///
+/// Files that aren't UTF-8, such as Wasm files, are only reported as
+/// differing.
+pub fn unified_diff(path: &str, old: Option<&[u8]>, new: &[u8]) -> String {
+    if old == Some(new) {
+        return String::new();
+    }
+    let (old_name, new_name) = match old {
+        Some(_) => (format!("a/{path}"), format!("b/{path}")),
+        None => ("/dev/null".to_string(), format!("b/{path}")),
+    };
+    let (Ok(old), Ok(new)) = (
+        std::str::from_utf8(old.unwrap_or_default()),
+        std::str::from_utf8(new),
+    ) else {
+        return format!("Binary files {old_name} and {new_name} differ\n");
+    };
+    let old = lines(old);
+    let new = lines(new);
+    let edits = edits(&old, &new);
+
+    let mut diff = format!("--- {old_name}\n+++ {new_name}\n");
+    for hunk in hunks(&edits) {
+        let before = &edits[..hunk.start];
+        let old_start = before
+            .iter()
+            .filter(|e| !matches!(e, Edit::Insert(_)))
+            .count();
+        let new_start = before
+            .iter()
+            .filter(|e| !matches!(e, Edit::Remove(_)))
+            .count();
+        let hunk = &edits[hunk];
+        let old_len = hunk
+            .iter()
+            .filter(|e| !matches!(e, Edit::Insert(_)))
+            .count();
+        let new_len = hunk
+            .iter()
+            .filter(|e| !matches!(e, Edit::Remove(_)))
+            .count();
+        let _ = writeln!(
+            diff,
+            "@@ -{} +{} @@",
+            range(old_start, old_len),
+            range(new_start, new_len)
+        );
+        for edit in hunk {
+            let (marker, line) = match *edit {
+                Edit::Keep(i, _) => (' ', old[i]),
+                Edit::Remove(i) => ('-', old[i]),
+                Edit::Insert(j) => ('+', new[j]),
+            };
+            diff.push(marker);
+            match line.strip_suffix('\n') {
+                Some(line) => {
+                    diff.push_str(line);
+                    diff.push('\n');
+                }
+                None => {
+                    diff.push_str(line);
+                    diff.push_str("\n\\ No newline at end of file\n");
+                }
+            }
+        }
+    }
+    diff
+}
+
+/// Splits the text into lines, keeping their line endings.
+fn lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+/// Formats the range of lines of a hunk, which starts at the 0-based index,
+/// as `start,len` with 1-based lines.
+///
+/// An empty range starts at the line before it, as in GNU diff.
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{len}", start + 1),
+    }
+}
+
+/// Groups the edits into hunks, i.e. the ranges of edits around changes,
+/// along with up to [`CONTEXT`] kept lines on each side.
+fn hunks(edits: &[Edit]) -> Vec<std::ops::Range<usize>> {
+    let changes = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| !matches!(edit, Edit::Keep(..)))
+        .map(|(i, _)| i);
+    let mut hunks: Vec<std::ops::Range<usize>> = Vec::new();
+    for change in changes {
+        let start = change.saturating_sub(CONTEXT);
+        let end = (change + 1 + CONTEXT).min(edits.len());
+        match hunks.last_mut() {
+            // Hunks whose contexts touch are merged
+            Some(hunk) if start <= hunk.end => hunk.end = end,
+            _ => hunks.push(start..end),
+        }
+    }
+    hunks
+}
+
+/// Returns the shortest edits turning the old lines into the new ones,
+/// following Myers' algorithm.
+fn edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    // The common prefix and suffix are kept as is, which is most of the file
+    // when regenerating bindings
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    // The furthest `x` reached on each diagonal `k = x - y`, and its values
+    // at each step `d`, to trace the path back
+    let mut v = vec![0isize; 2 * max as usize + 2];
+    let mut trace = Vec::new();
+    'search: for d in 0..=max {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let index = (k + max) as usize;
+            let mut x = if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+                v[index + 1]
+            } else {
+                v[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut reversed = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let index = (k + max) as usize;
+        let previous = if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let previous_x = v[(previous + max) as usize];
+        let previous_y = previous_x - previous;
+        while x > previous_x && y > previous_y {
+            x -= 1;
+            y -= 1;
+            reversed.push(Edit::Keep(x as usize, y as usize));
+        }
+        if d > 0 {
+            if x == previous_x {
+                reversed.push(Edit::Insert(previous_y as usize));
+            } else {
+                reversed.push(Edit::Remove(previous_x as usize));
+            }
+        }
+        (x, y) = (previous_x, previous_y);
+    }
+
+    let shift = |edit: Edit| match edit {
+        Edit::Keep(i, j) => Edit::Keep(i + prefix, j + prefix),
+        Edit::Remove(i) => Edit::Remove(i + prefix),
+        Edit::Insert(j) => Edit::Insert(j + prefix),
+    };
+    let middle = reversed.into_iter().rev().map(shift);
+    let suffix_start = (old.len() - suffix, new.len() - suffix);
+    (0..prefix)
+        .map(|i| Edit::Keep(i, i))
+        .chain(middle)
+        .chain((0..suffix).map(|i| Edit::Keep(suffix_start.0 + i, suffix_start.1 + i)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unified_diff;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let new = "a\nb\nc\nD\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        assert_eq!(
+            unified_diff("out.go", Some(old.as_bytes()), new.as_bytes()),
+            "--- a/out.go
++++ b/out.go
+@@ -1,7 +1,7 @@
+ a
+ b
+ c
+-d
++D
+ e
+ f
+ g
+@@ -10,3 +10,4 @@
+ j
+ k
+ l
++m
+"
+        );
+
+        // Changes separated by less than twice the context share a hunk
+        let new = "a\nb\nc\nD\ne\nf\ng\nh\ni\nJ\nk\nl\n";
+        assert_eq!(
+            unified_diff("out.go", Some(old.as_bytes()), new.as_bytes())
+                .matches("@@ -")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_of_new_file() {
+        assert_eq!(
+            unified_diff("out.go", None, b"package out\n"),
+            "--- /dev/null\n+++ b/out.go\n@@ -0,0 +1 @@\n+package out\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_of_unchanged_and_binary_files() {
+        assert_eq!(unified_diff("out.go", Some(b"same\n"), b"same\n"), "");
+        assert_eq!(
+            unified_diff("out.wasm", Some(b"\0asm\xff"), b"\0asm\xfe"),
+            "Binary files a/out.wasm and b/out.wasm differ\n"
+        );
+    }
+}
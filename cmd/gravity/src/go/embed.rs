@@ -16,7 +16,15 @@ where
         tokens.push();
         tokens.append(static_literal("//go:embed"));
         tokens.space();
-        tokens.append(self.0.into());
+        let path = self.0.into();
+        // Paths with spaces must be quoted, as the patterns are separated by
+        // spaces
+        if path.contains(char::is_whitespace) || path.contains('"') {
+            let quoted = path.replace('\\', "\\\\").replace('"', "\\\"");
+            tokens.append(ItemStr::from(format!("\"{quoted}\"")));
+        } else {
+            tokens.append(path);
+        }
     }
 }
 
@@ -58,4 +66,16 @@ mod tests {
         assert!(output.contains("//go:embed app.wasm"));
         assert!(output.contains("var wasmFile []byte"));
     }
+
+    #[test]
+    fn test_embed_quoted() {
+        let mut tokens = Tokens::<Go>::new();
+
+        quote_in! { tokens =>
+            $(embed("wasm/my app.wasm"))
+        };
+
+        let output = tokens.to_string().unwrap();
+        assert!(output.contains("//go:embed \"wasm/my app.wasm\""));
+    }
 }
@@ -1,13 +1,245 @@
-use std::str::Chars;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    str::Chars,
+};
 
 use genco::{prelude::*, tokens::ItemStr};
 
+/// The reserved keywords of Go, which can't be used as identifiers.
+///
+/// See <https://go.dev/ref/spec#Keywords>.
+const GO_KEYWORDS: [&str; 25] = [
+    "break",
+    "case",
+    "chan",
+    "const",
+    "continue",
+    "default",
+    "defer",
+    "else",
+    "fallthrough",
+    "for",
+    "func",
+    "go",
+    "goto",
+    "if",
+    "import",
+    "interface",
+    "map",
+    "package",
+    "range",
+    "return",
+    "select",
+    "struct",
+    "switch",
+    "type",
+    "var",
+];
+
+/// The initialisms recommended by the Go code review comments, which are
+/// cased consistently, e.g. `HTTPClient` and `userID`.
+///
+/// See <https://go.dev/wiki/CodeReviewComments#initialisms>.
+const GO_INITIALISMS: [&str; 38] = [
+    "ACL", "API", "ASCII", "CPU", "CSS", "DNS", "EOF", "GUID", "HTML", "HTTP", "HTTPS", "ID", "IP",
+    "JSON", "LHS", "QPS", "RAM", "RHS", "RPC", "SLA", "SMTP", "SQL", "SSH", "TCP", "TLS", "TTL",
+    "UDP", "UI", "UID", "UUID", "URI", "URL", "UTF8", "VM", "XML", "XMPP", "XSRF", "XSS",
+];
+
+/// Words cased as initialisms when formatting identifiers.
+#[derive(Debug, Clone, Default)]
+pub struct Initialisms(BTreeSet<String>);
+
+impl Initialisms {
+    /// The initialisms recommended by the Go code review comments.
+    pub fn standard() -> Self {
+        Self(GO_INITIALISMS.iter().map(|word| word.to_string()).collect())
+    }
+
+    /// Adds another word to be cased as an initialism.
+    pub fn insert(&mut self, word: &str) {
+        self.0.insert(word.to_uppercase());
+    }
+
+    /// Returns the initialism of the word, if it is one.
+    fn get(&self, word: &str) -> Option<&str> {
+        self.0.get(&word.to_uppercase()).map(String::as_str)
+    }
+}
+
+thread_local! {
+    static INITIALISMS: RefCell<Option<Initialisms>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, formatting the identifiers within it with the given
+/// initialisms.
+///
+/// Identifiers are formatted when they are quoted into tokens, so the whole
+/// generation is run within this. Without initialisms, words are capitalized
+/// as usual, e.g. `HttpClient`.
+pub fn with_initialisms<T>(initialisms: Option<Initialisms>, f: impl FnOnce() -> T) -> T {
+    let previous = INITIALISMS.replace(initialisms);
+    let result = f();
+    INITIALISMS.set(previous);
+    result
+}
+
+/// Returns the initialisms identifiers are currently formatted with.
+pub(crate) fn initialisms() -> Option<Initialisms> {
+    INITIALISMS.with_borrow(Clone::clone)
+}
+
+thread_local! {
+    static SYMBOL_PREFIX: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, prefixing the package-level symbols shared by the bindings of
+/// every world, such as helpers, with the given prefix.
+///
+/// Bindings generated into the same Go package declare these symbols once
+/// per file, so each file needs a prefix of its own. Without a prefix, the
+/// symbols are named as usual, e.g. `writeString`.
+pub fn with_symbol_prefix<T>(prefix: Option<String>, f: impl FnOnce() -> T) -> T {
+    let previous = SYMBOL_PREFIX.replace(prefix);
+    let result = f();
+    SYMBOL_PREFIX.set(previous);
+    result
+}
+
+/// Returns the prefix the package-level symbols are currently prefixed
+/// with.
+pub(crate) fn symbol_prefix() -> Option<String> {
+    SYMBOL_PREFIX.with_borrow(Clone::clone)
+}
+
+/// The Go identifiers to generate for WIT names in place of the usual ones,
+/// e.g. `APIError` for `error-info`, as read from a `--rename-file`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Renames(BTreeMap<String, String>);
+
+impl Renames {
+    /// Parses the renames from TOML lines of the form `error-info =
+    /// "APIError"`, where the WIT name may also be quoted.
+    ///
+    /// Every Go identifier must be exported, and can't be the rename of more
+    /// than one WIT name.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut renames = BTreeMap::new();
+        let mut renamed = BTreeMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: &str| format!("line {}: {message}", i + 1);
+            let (name, ident) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `name = \"Identifier\"`"))?;
+            let name = name.trim();
+            let name = unquote(name).unwrap_or(name);
+            if name.is_empty() {
+                return Err(error("missing WIT name"));
+            }
+            let ident = ident.trim();
+            // The quoted identifier may be followed by a comment
+            let ident = ident
+                .strip_prefix('"')
+                .and_then(|ident| ident.split_once('"'))
+                .filter(|(_, rest)| rest.trim().is_empty() || rest.trim().starts_with('#'))
+                .map(|(ident, _)| ident)
+                .ok_or_else(|| error("expected a quoted Go identifier"))?;
+            if !ident.starts_with(|c: char| c.is_uppercase())
+                || !ident.chars().all(|c| c.is_alphanumeric() || c == '_')
+            {
+                return Err(error(&format!("`{ident}` isn't an exported Go identifier")));
+            }
+            if renames
+                .insert(name.to_string(), ident.to_string())
+                .is_some()
+            {
+                return Err(error(&format!("`{name}` is renamed more than once")));
+            }
+            if let Some(other) = renamed.insert(ident.to_string(), name.to_string()) {
+                return Err(error(&format!(
+                    "both `{other}` and `{name}` are renamed to `{ident}`"
+                )));
+            }
+        }
+        Ok(Self(renames))
+    }
+
+    /// Returns the Go identifier the WIT name is renamed to, if any.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    /// Iterates over the WIT names and the Go identifiers they're renamed
+    /// to.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(name, ident)| (name.as_str(), ident.as_str()))
+    }
+}
+
+/// Strips the quotes around a quoted TOML key.
+fn unquote(key: &str) -> Option<&str> {
+    key.strip_prefix('"')?.strip_suffix('"')
+}
+
+thread_local! {
+    static RENAMES: RefCell<Option<Renames>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, generating the public identifiers of the renamed WIT names
+/// within it as their renames.
+///
+/// Renames apply to every public identifier generated from the whole WIT
+/// name, so a type, interface, method or field named `error-info` is
+/// consistently generated as `APIError`.
+pub fn with_renames<T>(renames: Option<Renames>, f: impl FnOnce() -> T) -> T {
+    let previous = RENAMES.replace(renames);
+    let result = f();
+    RENAMES.set(previous);
+    result
+}
+
+/// Returns the Go identifier the WIT name is renamed to, or the name itself,
+/// to be joined into the name of another public identifier, such as the Go
+/// interface of an imported interface.
+pub(crate) fn renamed(name: &str) -> String {
+    RENAMES.with_borrow(|renames| {
+        renames
+            .as_ref()
+            .and_then(|renames| renames.get(name))
+            .unwrap_or(name)
+            .to_string()
+    })
+}
+
+/// Returns the renames identifiers are currently generated with.
+pub(crate) fn renames() -> Option<Renames> {
+    RENAMES.with_borrow(Clone::clone)
+}
+
+/// Prefixes the name with the symbol prefix, if any.
+fn prefixed(name: &str) -> String {
+    SYMBOL_PREFIX.with_borrow(|prefix| match prefix {
+        Some(prefix) => format!("{prefix}-{name}"),
+        None => name.to_string(),
+    })
+}
+
 /// Represents a Go identifier with appropriate casing rules.
 ///
 /// Go identifiers follow specific naming conventions:
 /// - Public identifiers start with uppercase (exported)
 /// - Private identifiers start with lowercase (unexported)
 /// - Local identifiers are used as-is without transformation
+///
+/// Private and local identifiers which are Go keywords, such as a WIT
+/// parameter named `type`, are escaped with a trailing underscore.
 #[derive(Debug, Clone)]
 pub enum GoIdentifier {
     /// Public/exported identifier (will be converted to UpperCamelCase)
@@ -43,6 +275,18 @@ impl GoIdentifier {
         Self::Local { name: name.into() }
     }
 
+    /// Creates a new public identifier of a package-level symbol shared by
+    /// the bindings of every world, see [`with_symbol_prefix`].
+    pub fn prefixed_public(name: &str) -> Self {
+        Self::public(prefixed(name))
+    }
+
+    /// Creates a new private identifier of a package-level symbol shared by
+    /// the bindings of every world, see [`with_symbol_prefix`].
+    pub fn prefixed_private(name: &str) -> Self {
+        Self::private(prefixed(name))
+    }
+
     /// Returns an iterator over the characters of the underlying name.
     ///
     /// This provides access to the raw name without case transformations.
@@ -74,14 +318,45 @@ impl From<&GoIdentifier> for String {
 
 impl FormatInto<Go> for &GoIdentifier {
     fn format_into(self, tokens: &mut Tokens<Go>) {
+        if let GoIdentifier::Public { name } = self {
+            let renamed = RENAMES.with_borrow(|renames| {
+                renames
+                    .as_ref()
+                    .and_then(|renames| renames.get(name))
+                    .map(str::to_string)
+            });
+            if let Some(renamed) = renamed {
+                tokens.append(ItemStr::from(renamed));
+                return;
+            }
+        }
+        let mut name = INITIALISMS.with_borrow(|initialisms| match initialisms {
+            Some(initialisms) => self.cased_with(initialisms),
+            None => self.cased(),
+        });
+
+        // Public identifiers start with an uppercase letter, so only private
+        // and local identifiers can be keywords
+        if GO_KEYWORDS.contains(&name.as_str()) {
+            name.push('_');
+        }
+        tokens.append(ItemStr::from(name));
+    }
+}
+
+impl GoIdentifier {
+    /// Cases the identifier by capitalizing the character after each
+    /// separator.
+    fn cased(&self) -> String {
         let mut chars = self.chars();
+        let mut name = String::new();
 
         // TODO(#12): Check for invalid first character
 
         if let GoIdentifier::Public { .. } = self {
             // https://stackoverflow.com/a/38406885
             match chars.next() {
-                Some(c) => tokens.append(ItemStr::from(c.to_uppercase().to_string())),
+                Some(c) => name.extend(c.to_uppercase()),
                 None => panic!("No function name"),
             };
         };
@@ -90,14 +365,46 @@ impl FormatInto<Go> for &GoIdentifier {
             match c {
                 ' ' | '-' | '_' => {
                     if let Some(c) = chars.next() {
-                        tokens.append(ItemStr::from(c.to_uppercase().to_string()));
+                        name.extend(c.to_uppercase());
                     }
                 }
-                _ => tokens.append(ItemStr::from(c.to_string())),
+                _ => name.push(c),
+            }
+        }
+        name
+    }
+
+    /// Cases the identifier word by word, writing initialisms in uppercase
+    /// unless they start a private or local identifier.
+    fn cased_with(&self, initialisms: &Initialisms) -> String {
+        let name = match self {
+            GoIdentifier::Public { name }
+            | GoIdentifier::Private { name }
+            | GoIdentifier::Local { name } => name,
+        };
+        let public = matches!(self, GoIdentifier::Public { .. });
+        let words = name.split([' ', '-', '_']).filter(|word| !word.is_empty());
+        let mut cased = String::new();
+        for (i, word) in words.enumerate() {
+            let first = i == 0;
+            match initialisms.get(word) {
+                Some(_) if first && !public => cased.push_str(&word.to_lowercase()),
+                Some(initialism) => cased.push_str(initialism),
+                None if first && !public => cased.push_str(word),
+                None => {
+                    let mut chars = word.chars();
+                    cased.extend(chars.next().into_iter().flat_map(char::to_uppercase));
+                    cased.extend(chars);
+                }
             }
         }
+        if cased.is_empty() {
+            panic!("No function name");
+        }
+        cased
     }
 }
+
 impl FormatInto<Go> for GoIdentifier {
     fn format_into(self, tokens: &mut Tokens<Go>) {
         (&self).format_into(tokens)
@@ -109,7 +416,10 @@ mod tests {
 
     use genco::{prelude::*, tokens::Tokens};
 
-    use crate::go::GoIdentifier;
+    use crate::go::{
+        GoIdentifier, Initialisms, Renames, identifier::GO_KEYWORDS, with_initialisms,
+        with_renames, with_symbol_prefix,
+    };
 
     #[test]
     fn test_public_identifier() {
@@ -134,4 +444,123 @@ mod tests {
         (&id).format_into(&mut tokens);
         assert_eq!(tokens.to_string().unwrap(), "helloWorld");
     }
+
+    #[test]
+    fn test_keyword_identifiers() {
+        for keyword in GO_KEYWORDS {
+            let escaped = format!("{keyword}_");
+            assert_eq!(String::from(GoIdentifier::private(keyword)), escaped);
+            assert_eq!(String::from(GoIdentifier::local(keyword)), escaped);
+            assert_ne!(String::from(GoIdentifier::public(keyword)), escaped);
+        }
+        assert_eq!(String::from(GoIdentifier::public("type")), "Type");
+        assert_eq!(String::from(GoIdentifier::local("type-name")), "typeName");
+    }
+
+    #[test]
+    fn test_symbol_prefix() {
+        assert_eq!(
+            String::from(GoIdentifier::prefixed_private("write-string")),
+            "writeString"
+        );
+        with_symbol_prefix(Some("basic".to_string()), || {
+            assert_eq!(
+                String::from(GoIdentifier::prefixed_private("write-string")),
+                "basicWriteString"
+            );
+            assert_eq!(
+                String::from(GoIdentifier::prefixed_public("error-context")),
+                "BasicErrorContext"
+            );
+            assert_eq!(
+                String::from(GoIdentifier::public("hello-world")),
+                "HelloWorld"
+            );
+        });
+    }
+
+    #[test]
+    fn test_initialisms() {
+        let mut initialisms = Initialisms::standard();
+        initialisms.insert("wit");
+        with_initialisms(Some(initialisms), || {
+            assert_eq!(
+                String::from(GoIdentifier::public("http-client")),
+                "HTTPClient"
+            );
+            assert_eq!(String::from(GoIdentifier::public("id")), "ID");
+            assert_eq!(String::from(GoIdentifier::private("id")), "id");
+            assert_eq!(String::from(GoIdentifier::private("user-id")), "userID");
+            assert_eq!(String::from(GoIdentifier::local("http-url")), "httpURL");
+            assert_eq!(String::from(GoIdentifier::public("parse-wit")), "ParseWIT");
+            assert_eq!(
+                String::from(GoIdentifier::public("hello-world")),
+                "HelloWorld"
+            );
+            assert_eq!(String::from(GoIdentifier::local("type")), "type_");
+        });
+
+        // Initialisms only apply within the scope
+        assert_eq!(
+            String::from(GoIdentifier::public("http-client")),
+            "HttpClient"
+        );
+    }
+
+    #[test]
+    fn test_renames() {
+        let renames = Renames::parse(
+            "# Names required by the style guide\n\
+             error-info = \"APIError\"\n\
+             \n\
+             \"http-client\" = \"Client\" # quoted\n",
+        )
+        .unwrap();
+        assert_eq!(
+            renames.iter().collect::<Vec<_>>(),
+            [("error-info", "APIError"), ("http-client", "Client")]
+        );
+        with_renames(Some(renames), || {
+            assert_eq!(String::from(GoIdentifier::public("error-info")), "APIError");
+            assert_eq!(String::from(GoIdentifier::public("http-client")), "Client");
+            // Only public identifiers of the whole name are renamed
+            assert_eq!(
+                String::from(GoIdentifier::private("error-info")),
+                "errorInfo"
+            );
+            assert_eq!(
+                String::from(GoIdentifier::public("parse-error-info")),
+                "ParseErrorInfo"
+            );
+        });
+        assert_eq!(
+            String::from(GoIdentifier::public("error-info")),
+            "ErrorInfo"
+        );
+    }
+
+    #[test]
+    fn test_invalid_renames() {
+        for (text, error) in [
+            ("error-info", "line 1: expected `name = \"Identifier\"`"),
+            (
+                "error-info = APIError",
+                "line 1: expected a quoted Go identifier",
+            ),
+            (
+                "error-info = \"apiError\"",
+                "line 1: `apiError` isn't an exported Go identifier",
+            ),
+            (
+                "a = \"A\"\na = \"B\"",
+                "line 2: `a` is renamed more than once",
+            ),
+            (
+                "a = \"A\"\nb = \"A\"",
+                "line 2: both `a` and `b` are renamed to `A`",
+            ),
+        ] {
+            assert_eq!(Renames::parse(text).unwrap_err(), error);
+        }
+    }
 }
@@ -9,16 +9,103 @@ impl FormatInto<Go> for GoImport {
     }
 }
 
+pub static COMPRESS_GZIP_NEW_READER: GoImport = GoImport("compress/gzip", "NewReader");
+pub static CONTEXT_BACKGROUND: GoImport = GoImport("context", "Background");
 pub static CONTEXT_CONTEXT: GoImport = GoImport("context", "Context");
+pub static CONTEXT_CANCEL_FUNC: GoImport = GoImport("context", "CancelFunc");
+pub static CONTEXT_WITH_VALUE: GoImport = GoImport("context", "WithValue");
+pub static CONTEXT_WITH_TIMEOUT: GoImport = GoImport("context", "WithTimeout");
+pub static CONTEXT_WITH_TIMEOUT_CAUSE: GoImport = GoImport("context", "WithTimeoutCause");
+pub static CONTEXT_CAUSE: GoImport = GoImport("context", "Cause");
+pub static CONTEXT_WITHOUT_CANCEL: GoImport = GoImport("context", "WithoutCancel");
+pub static CRYPTO_RAND_READ: GoImport = GoImport("crypto/rand", "Read");
+pub static ERRORS_AS: GoImport = GoImport("errors", "As");
 pub static ERRORS_NEW: GoImport = GoImport("errors", "New");
+pub static ENCODING_BASE64_NEW_DECODER: GoImport = GoImport("encoding/base64", "NewDecoder");
+pub static ENCODING_BASE64_STD_ENCODING: GoImport = GoImport("encoding/base64", "StdEncoding");
+pub static ENCODING_BINARY_APPEND: GoImport = GoImport("encoding/binary", "Append");
+pub static ENCODING_BINARY_DECODE: GoImport = GoImport("encoding/binary", "Decode");
+pub static ENCODING_BINARY_LITTLE_ENDIAN: GoImport = GoImport("encoding/binary", "LittleEndian");
+pub static ENCODING_JSON_MARSHAL: GoImport = GoImport("encoding/json", "Marshal");
+pub static ENCODING_JSON_RAW_MESSAGE: GoImport = GoImport("encoding/json", "RawMessage");
+pub static ENCODING_JSON_UNMARSHAL: GoImport = GoImport("encoding/json", "Unmarshal");
+pub static FMT_ERRORF: GoImport = GoImport("fmt", "Errorf");
+pub static FMT_FPRINTLN: GoImport = GoImport("fmt", "Fprintln");
 pub static FMT_PRINTF: GoImport = GoImport("fmt", "Printf");
+pub static FMT_PRINTLN: GoImport = GoImport("fmt", "Println");
+pub static FMT_SPRINTF: GoImport = GoImport("fmt", "Sprintf");
+pub static IO_READ_ALL: GoImport = GoImport("io", "ReadAll");
+pub static IO_READER: GoImport = GoImport("io", "Reader");
+pub static IO_WRITER: GoImport = GoImport("io", "Writer");
+pub static MATH_MAX_INT8: GoImport = GoImport("math", "MaxInt8");
+pub static MATH_MAX_INT16: GoImport = GoImport("math", "MaxInt16");
+pub static MATH_MAX_UINT8: GoImport = GoImport("math", "MaxUint8");
+pub static MATH_MAX_UINT16: GoImport = GoImport("math", "MaxUint16");
+pub static MATH_MAX_UINT32: GoImport = GoImport("math", "MaxUint32");
+pub static MATH_MIN_INT8: GoImport = GoImport("math", "MinInt8");
+pub static MATH_MIN_INT16: GoImport = GoImport("math", "MinInt16");
+pub static OS_EXIT: GoImport = GoImport("os", "Exit");
+pub static OS_STDERR: GoImport = GoImport("os", "Stderr");
+pub static REFLECT_DEEP_EQUAL: GoImport = GoImport("reflect", "DeepEqual");
+pub static REFLECT_TYPE: GoImport = GoImport("reflect", "Type");
+pub static REFLECT_TYPE_FOR: GoImport = GoImport("reflect", "TypeFor");
+pub static REFLECT_VALUE: GoImport = GoImport("reflect", "Value");
+pub static REFLECT_VALUE_OF: GoImport = GoImport("reflect", "ValueOf");
+pub static REFLECT_ZERO: GoImport = GoImport("reflect", "Zero");
+pub static SLICES_EQUAL: GoImport = GoImport("slices", "Equal");
+pub static SLICES_EQUAL_FUNC: GoImport = GoImport("slices", "EqualFunc");
+pub static SLICES_SORT: GoImport = GoImport("slices", "Sort");
+pub static STRINGS_JOIN: GoImport = GoImport("strings", "Join");
+pub static STRINGS_NEW_READER: GoImport = GoImport("strings", "NewReader");
+pub static STRINGS_REPEAT: GoImport = GoImport("strings", "Repeat");
+pub static SYNC_MUTEX: GoImport = GoImport("sync", "Mutex");
+pub static TESTING_F: GoImport = GoImport("testing", "F");
+pub static TESTING_T: GoImport = GoImport("testing", "T");
+pub static TIME_DURATION: GoImport = GoImport("time", "Duration");
+pub static TIME_NOW: GoImport = GoImport("time", "Now");
+pub static TIME_SINCE: GoImport = GoImport("time", "Since");
+pub static UNICODE_UTF8_VALID_RUNE: GoImport = GoImport("unicode/utf8", "ValidRune");
+pub static UNICODE_UTF16_DECODE: GoImport = GoImport("unicode/utf16", "Decode");
+pub static UNICODE_UTF16_ENCODE: GoImport = GoImport("unicode/utf16", "Encode");
+pub static UNSAFE_SLICE_DATA: GoImport = GoImport("unsafe", "SliceData");
+pub static UNSAFE_STRING: GoImport = GoImport("unsafe", "String");
+pub static OTEL_TRACER: GoImport = GoImport("go.opentelemetry.io/otel", "Tracer");
+pub static OTEL_ATTRIBUTE_STRING: GoImport =
+    GoImport("go.opentelemetry.io/otel/attribute", "String");
+pub static OTEL_CODES_ERROR: GoImport = GoImport("go.opentelemetry.io/otel/codes", "Error");
+pub static OTEL_TRACE_WITH_ATTRIBUTES: GoImport =
+    GoImport("go.opentelemetry.io/otel/trace", "WithAttributes");
+pub static WIRE_NEW_SET: GoImport = GoImport("github.com/google/wire", "NewSet");
+pub static FX_HOOK: GoImport = GoImport("go.uber.org/fx", "Hook");
+pub static FX_LIFECYCLE: GoImport = GoImport("go.uber.org/fx", "Lifecycle");
+pub static FX_MODULE: GoImport = GoImport("go.uber.org/fx", "Module");
+pub static FX_PROVIDE: GoImport = GoImport("go.uber.org/fx", "Provide");
 pub static WAZERO_RUNTIME: GoImport = GoImport("github.com/tetratelabs/wazero", "Runtime");
 pub static WAZERO_NEW_RUNTIME: GoImport = GoImport("github.com/tetratelabs/wazero", "NewRuntime");
+pub static WAZERO_NEW_RUNTIME_WITH_CONFIG: GoImport =
+    GoImport("github.com/tetratelabs/wazero", "NewRuntimeWithConfig");
+pub static WAZERO_RUNTIME_CONFIG: GoImport =
+    GoImport("github.com/tetratelabs/wazero", "RuntimeConfig");
+pub static WAZERO_NEW_RUNTIME_CONFIG: GoImport =
+    GoImport("github.com/tetratelabs/wazero", "NewRuntimeConfig");
+pub static WAZERO_MODULE_CONFIG: GoImport =
+    GoImport("github.com/tetratelabs/wazero", "ModuleConfig");
 pub static WAZERO_NEW_MODULE_CONFIG: GoImport =
     GoImport("github.com/tetratelabs/wazero", "NewModuleConfig");
+pub static WAZERO_FS_CONFIG: GoImport = GoImport("github.com/tetratelabs/wazero", "FSConfig");
+pub static WAZERO_NEW_FS_CONFIG: GoImport =
+    GoImport("github.com/tetratelabs/wazero", "NewFSConfig");
 pub static WAZERO_COMPILED_MODULE: GoImport =
     GoImport("github.com/tetratelabs/wazero", "CompiledModule");
+pub static WAZERO_SYS_EXIT_ERROR: GoImport =
+    GoImport("github.com/tetratelabs/wazero/sys", "ExitError");
+pub static WAZERO_SYS_EXIT_CODE_DEADLINE_EXCEEDED: GoImport = GoImport(
+    "github.com/tetratelabs/wazero/sys",
+    "ExitCodeDeadlineExceeded",
+);
 pub static WAZERO_API_MODULE: GoImport = GoImport("github.com/tetratelabs/wazero/api", "Module");
+pub static WAZERO_API_FUNCTION: GoImport =
+    GoImport("github.com/tetratelabs/wazero/api", "Function");
 pub static WAZERO_API_MEMORY: GoImport = GoImport("github.com/tetratelabs/wazero/api", "Memory");
 pub static WAZERO_API_ENCODE_U32: GoImport =
     GoImport("github.com/tetratelabs/wazero/api", "EncodeU32");
@@ -28,6 +115,8 @@ pub static WAZERO_API_ENCODE_I32: GoImport =
     GoImport("github.com/tetratelabs/wazero/api", "EncodeI32");
 pub static WAZERO_API_DECODE_I32: GoImport =
     GoImport("github.com/tetratelabs/wazero/api", "DecodeI32");
+pub static WAZERO_API_ENCODE_I64: GoImport =
+    GoImport("github.com/tetratelabs/wazero/api", "EncodeI64");
 pub static WAZERO_API_ENCODE_F32: GoImport =
     GoImport("github.com/tetratelabs/wazero/api", "EncodeF32");
 pub static WAZERO_API_DECODE_F32: GoImport =
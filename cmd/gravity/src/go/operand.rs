@@ -3,18 +3,27 @@ use genco::{
     tokens::{ItemStr, static_literal},
 };
 
+use crate::go::GoType;
+
 /// Represents an operand in Go code generation.
 ///
 /// Operands can be literals, single values (variables), or multi-value tuples
-/// (used for functions returning multiple values).
+/// (used for functions returning multiple values). Values carry the Go type
+/// of their expression, so they're only converted when used as another type,
+/// e.g. the `uint64`s Wazero passes for core Wasm values stored as `uint32`s.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Operand {
-    /// A literal value (e.g., "0", "true", "\"hello\"")
+    /// A literal value (e.g., "0", "true", "\"hello\""), which is an untyped
+    /// Go constant
     Literal(String),
-    /// A single variable or expression
-    SingleValue(String),
-    /// A tuple of two values (for multi-value returns)
-    MultiValue((String, String)),
+    /// A single variable or expression, and its Go type
+    SingleValue(String, GoType),
+    /// A tuple of two values (for multi-value returns), and the Go type of
+    /// the pair, i.e. [`GoType::ValueOrError`] or [`GoType::ValueOrOk`]
+    MultiValue((String, String), GoType),
+    /// A variable whose Go type depends on where it's declared, i.e. the
+    /// payload bound by each case of a variant being lowered
+    Untyped(String),
 }
 
 impl Operand {
@@ -27,9 +36,27 @@ impl Operand {
     /// A string representation of the primary value.
     pub fn as_string(&self) -> String {
         match self {
-            Operand::Literal(s) => s.clone(),
-            Operand::SingleValue(s) => s.clone(),
-            Operand::MultiValue((s1, _)) => s1.clone(),
+            Operand::Literal(s) | Operand::SingleValue(s, _) | Operand::Untyped(s) => s.clone(),
+            Operand::MultiValue((s1, _), _) => s1.clone(),
+        }
+    }
+
+    /// Returns the Go type of the operand, if it's known.
+    pub fn typ(&self) -> Option<&GoType> {
+        match self {
+            Operand::SingleValue(_, typ) | Operand::MultiValue(_, typ) => Some(typ),
+            Operand::Literal(_) | Operand::Untyped(_) => None,
+        }
+    }
+
+    /// Returns the operand as a value of the Go type, converting it unless
+    /// it's already of the type or an untyped constant.
+    pub fn convert(&self, typ: &GoType) -> Tokens<Go> {
+        let value = self;
+        match self {
+            Operand::Literal(_) => quote!($value),
+            _ if self.typ() == Some(typ) => quote!($value),
+            _ => quote!($typ($value)),
         }
     }
 }
@@ -38,9 +65,10 @@ impl Operand {
 impl FormatInto<Go> for &Operand {
     fn format_into(self, tokens: &mut Tokens<Go>) {
         match self {
-            Operand::Literal(val) => tokens.append(ItemStr::from(val)),
-            Operand::SingleValue(val) => tokens.append(ItemStr::from(val)),
-            Operand::MultiValue((val1, val2)) => {
+            Operand::Literal(val) | Operand::SingleValue(val, _) | Operand::Untyped(val) => {
+                tokens.append(ItemStr::from(val))
+            }
+            Operand::MultiValue((val1, val2), _) => {
                 tokens.append(ItemStr::from(val1));
                 tokens.append(static_literal(","));
                 tokens.space();
@@ -67,7 +95,7 @@ impl FormatInto<Go> for &mut Operand {
 mod tests {
     use genco::{prelude::*, tokens::Tokens};
 
-    use crate::go::Operand;
+    use crate::go::{GoType, Operand};
 
     #[test]
     fn test_operand_literal() {
@@ -79,7 +107,7 @@ mod tests {
 
     #[test]
     fn test_operand_single_value() {
-        let op = Operand::SingleValue("myVar".to_string());
+        let op = Operand::SingleValue("myVar".to_string(), GoType::Uint32);
         let mut tokens = Tokens::<Go>::new();
         op.format_into(&mut tokens);
         assert_eq!(tokens.to_string().unwrap(), "myVar");
@@ -87,9 +115,28 @@ mod tests {
 
     #[test]
     fn test_operand_multi_value() {
-        let op = Operand::MultiValue(("val1".to_string(), "val2".to_string()));
+        let op = Operand::MultiValue(
+            ("val1".to_string(), "val2".to_string()),
+            GoType::ValueOrError(Box::new(GoType::String)),
+        );
         let mut tokens = Tokens::<Go>::new();
         op.format_into(&mut tokens);
         assert_eq!(tokens.to_string().unwrap(), "val1, val2");
     }
+
+    #[test]
+    fn test_operand_convert() {
+        let value = Operand::SingleValue("value".to_string(), GoType::Uint64);
+        let convert = |op: &Operand, typ: GoType| op.convert(&typ).to_string().unwrap();
+        assert_eq!(convert(&value, GoType::Uint64), "value");
+        assert_eq!(convert(&value, GoType::Uint32), "uint32(value)");
+        assert_eq!(
+            convert(&Operand::Literal("0".to_string()), GoType::Uint32),
+            "0"
+        );
+        assert_eq!(
+            convert(&Operand::Untyped("payload".to_string()), GoType::Uint32),
+            "uint32(payload)"
+        );
+    }
 }
@@ -28,6 +28,36 @@ impl GoResult {
             GoResult::Anon(typ) => typ.needs_cleanup(),
         }
     }
+
+    /// Returns the names and types of the values returned by a function
+    /// with this result, other than a trailing `error`, and whether it
+    /// returns an `error`.
+    ///
+    /// The values are named `result`, along with `ok` for options, or
+    /// `result0`, `result1`, etc. for multiple values.
+    pub fn values(&self) -> (Vec<(String, GoType)>, bool) {
+        fn values(typ: &GoType) -> Vec<(String, GoType)> {
+            match typ {
+                GoType::Nothing => vec![],
+                GoType::ValueOrOk(typ) => vec![
+                    ("result".to_string(), *typ.clone()),
+                    ("ok".to_string(), GoType::Bool),
+                ],
+                GoType::MultiReturn(typs) => typs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, typ)| (format!("result{i}"), typ.clone()))
+                    .collect(),
+                typ => vec![("result".to_string(), typ.clone())],
+            }
+        }
+        match self {
+            GoResult::Empty => (vec![], false),
+            GoResult::Anon(GoType::Error) => (vec![], true),
+            GoResult::Anon(GoType::ValueOrError(typ)) => (values(typ), true),
+            GoResult::Anon(typ) => (values(typ), false),
+        }
+    }
 }
 
 impl FormatInto<Go> for GoResult {
@@ -39,7 +69,11 @@ impl FormatInto<Go> for GoResult {
 impl FormatInto<Go> for &GoResult {
     fn format_into(self, tokens: &mut Tokens<Go>) {
         match &self {
-            GoResult::Anon(typ @ GoType::ValueOrError(_) | typ @ GoType::ValueOrOk(_)) => {
+            GoResult::Anon(
+                typ @ GoType::ValueOrError(_)
+                | typ @ GoType::ValueOrOk(_)
+                | typ @ GoType::MultiReturn(_),
+            ) => {
                 // Be cautious here as there are `(` and `)` surrounding the type
                 tokens.append(quote!(($typ)))
             }
@@ -80,6 +114,44 @@ mod tests {
         assert_eq!(tokens.to_string().unwrap(), "(uint32, bool)");
     }
 
+    #[test]
+    fn test_go_result_multi_return() {
+        // GoResult with MultiReturn should add parentheses
+        let result = GoResult::Anon(GoType::MultiReturn(vec![GoType::Uint32, GoType::Uint32]));
+        let mut tokens = Tokens::<Go>::new();
+        (&result).format_into(&mut tokens);
+        assert_eq!(tokens.to_string().unwrap(), "(uint32, uint32)");
+    }
+
+    #[test]
+    fn test_go_result_values() {
+        assert_eq!(GoResult::Empty.values(), (vec![], false));
+        assert_eq!(GoResult::Anon(GoType::Error).values(), (vec![], true));
+        assert_eq!(
+            GoResult::Anon(GoType::ValueOrError(Box::new(GoType::ValueOrOk(Box::new(
+                GoType::String
+            )))))
+            .values(),
+            (
+                vec![
+                    ("result".to_string(), GoType::String),
+                    ("ok".to_string(), GoType::Bool)
+                ],
+                true
+            )
+        );
+        assert_eq!(
+            GoResult::Anon(GoType::MultiReturn(vec![GoType::Uint32, GoType::Int8])).values(),
+            (
+                vec![
+                    ("result0".to_string(), GoType::Uint32),
+                    ("result1".to_string(), GoType::Int8)
+                ],
+                false
+            )
+        );
+    }
+
     #[test]
     fn test_go_result_value_or_error() {
         // GoResult with ValueOrError should add parentheses
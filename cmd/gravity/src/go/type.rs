@@ -1,6 +1,12 @@
 use genco::{prelude::*, tokens::static_literal};
 
-use crate::go::GoIdentifier;
+use crate::{
+    codegen::{
+        helpers::{ERROR_CONTEXT, RESULT},
+        resources::{borrow_name, own_name},
+    },
+    go::GoIdentifier,
+};
 
 /// Represents a Go type in the code generation system.
 ///
@@ -30,14 +36,22 @@ pub enum GoType {
     Float32,
     /// 64-bit floating point
     Float64,
+    /// Unicode scalar value (for `char`)
+    Rune,
     /// String type
     String,
     /// Error type (represents Result<None, String>)
     Error,
     /// Interface type (for variants/discriminated unions)
     Interface,
-    // Pointer to another type
-    // Pointer(Box<GoType>),
+    /// Pointer to the generated `ErrorContext` type (for `error-context`)
+    ErrorContext,
+    /// Pointer to another type (for options nested in records, tuples and
+    /// lists, which are nil for `none`)
+    Pointer(Box<GoType>),
+    /// The generated `Result` of a value (for results nested in records,
+    /// tuples and lists)
+    Result(Box<GoType>),
     /// Result type with Ok value
     ValueOrOk(Box<GoType>),
     /// Result type with Error value
@@ -45,9 +59,13 @@ pub enum GoType {
     /// Slice/array of another type
     Slice(Box<GoType>),
     /// Multi-return type (for functions returning arbitrary multiple values)
-    // MultiReturn(Vec<GoType>),
+    MultiReturn(Vec<GoType>),
     /// User-defined type (records, enums, type aliases)
     UserDefined(String),
+    /// Owned handle to a resource, by the name of the resource
+    OwnHandle(String),
+    /// Borrowed handle to a resource, by the name of the resource
+    BorrowHandle(String),
     /// Represents no value/void
     Nothing,
 }
@@ -85,13 +103,15 @@ impl GoType {
             | GoType::Int32
             | GoType::Int64
             | GoType::Float32
-            | GoType::Float64 => false,
+            | GoType::Float64
+            | GoType::Rune => false,
 
             // String and slices allocate memory and need cleanup
             GoType::String | GoType::Slice(_) => true,
 
             // Complex types need cleanup if their inner types do
-            GoType::ValueOrOk(inner) => inner.needs_cleanup(),
+            GoType::ValueOrOk(inner) | GoType::Pointer(inner) => inner.needs_cleanup(),
+            GoType::MultiReturn(typs) => typs.iter().any(GoType::needs_cleanup),
 
             // The inner type of `Err` is always a String so it requires cleanup
             // TODO(#91): Store the error type to check both inner types.
@@ -100,6 +120,10 @@ impl GoType {
             // Interfaces (variants) might need cleanup (conservative approach)
             GoType::Interface => true,
 
+            // Error contexts and resources are passed as handles, so there is
+            // nothing to clean up
+            GoType::ErrorContext | GoType::OwnHandle(_) | GoType::BorrowHandle(_) => false,
+
             // User-defined types (records, enums, type aliases) need cleanup if they
             // contain strings or other allocated types. Since we don't have access to
             // the type definition here, we must be conservative and assume they might.
@@ -116,12 +140,10 @@ impl GoType {
             GoType::UserDefined(_) => true,
 
             // Error is actually Result<None, String> - strings need cleanup!
-            GoType::Error => true,
+            GoType::Error | GoType::Result(_) => true,
 
             // Nothing represents no value, so no cleanup needed
             GoType::Nothing => false,
-            // TODO - figure out if a pointer needs cleanup, once implemented.
-            // GoType::Pointer(_) => todo!()
         }
     }
 }
@@ -140,9 +162,11 @@ impl FormatInto<Go> for &GoType {
             GoType::Int64 => tokens.append(static_literal("int64")),
             GoType::Float32 => tokens.append(static_literal("float32")),
             GoType::Float64 => tokens.append(static_literal("float64")),
+            GoType::Rune => tokens.append(static_literal("rune")),
             GoType::String => tokens.append(static_literal("string")),
             GoType::Error => tokens.append(static_literal("error")),
             GoType::Interface => tokens.append(static_literal("interface{}")),
+            GoType::ErrorContext => quote_in!(*tokens => *$ERROR_CONTEXT),
             GoType::ValueOrOk(value_typ) => {
                 value_typ.as_ref().format_into(tokens);
                 tokens.append(static_literal(","));
@@ -157,19 +181,25 @@ impl FormatInto<Go> for &GoType {
             }
             GoType::Slice(typ) => {
                 tokens.append(static_literal("[]"));
+                match typ.as_ref() {
+                    GoType::Uint8 => tokens.append(static_literal("byte")),
+                    typ => typ.format_into(tokens),
+                }
+            }
+            GoType::MultiReturn(typs) => {
+                tokens.append(quote!($(for typ in typs join (, ) => $typ)))
+            }
+            GoType::Pointer(typ) => {
+                tokens.append(static_literal("*"));
                 typ.as_ref().format_into(tokens);
             }
-            // GoType::MultiReturn(typs) => {
-            //     tokens.append(quote!($(for typ in typs join (, ) => $typ)))
-            // }
-            // GoType::Pointer(typ) => {
-            //     tokens.append(static_literal("*"));
-            //     typ.as_ref().format_into(tokens);
-            // }
+            GoType::Result(typ) => quote_in!(*tokens => $RESULT[$(typ.as_ref())]),
             GoType::UserDefined(name) => {
                 let id = GoIdentifier::public(name);
                 id.format_into(tokens)
             }
+            GoType::OwnHandle(name) => own_name(name).format_into(tokens),
+            GoType::BorrowHandle(name) => borrow_name(name).format_into(tokens),
             GoType::Nothing => (),
         }
     }
@@ -201,9 +231,14 @@ mod tests {
             (GoType::Int64, "int64"),
             (GoType::Float32, "float32"),
             (GoType::Float64, "float64"),
+            (GoType::Rune, "rune"),
             (GoType::String, "string"),
             (GoType::Error, "error"),
             (GoType::Interface, "interface{}"),
+            (GoType::ErrorContext, "*ErrorContext"),
+            (GoType::Result(Box::new(GoType::Uint32)), "Result[uint32]"),
+            (GoType::OwnHandle("fooer".into()), "FooerOwn"),
+            (GoType::BorrowHandle("fooer".into()), "FooerBorrow"),
             (GoType::Nothing, ""),
         ];
 
@@ -235,6 +270,15 @@ mod tests {
         assert_eq!(tokens.to_string().unwrap(), "string, error");
     }
 
+    #[test]
+    fn test_multi_return() {
+        let typ = GoType::MultiReturn(vec![GoType::Uint32, GoType::String]);
+        let mut tokens = Tokens::<Go>::new();
+        (&typ).format_into(&mut tokens);
+        assert_eq!(tokens.to_string().unwrap(), "uint32, string");
+        assert!(typ.needs_cleanup());
+    }
+
     #[test]
     fn test_slice() {
         let typ = GoType::Slice(Box::new(GoType::Int32));
@@ -243,26 +287,42 @@ mod tests {
         assert_eq!(tokens.to_string().unwrap(), "[]int32");
     }
 
-    // #[test]
-    // fn test_pointer() {
-    //     let typ = GoType::Pointer(Box::new(GoType::String));
-    //     let mut tokens = Tokens::<Go>::new();
-    //     (&typ).format_into(&mut tokens);
-    //     assert_eq!(tokens.to_string().unwrap(), "*string");
-    // }
+    #[test]
+    fn test_byte_slice() {
+        let typ = GoType::Slice(Box::new(GoType::Uint8));
+        let mut tokens = Tokens::<Go>::new();
+        (&typ).format_into(&mut tokens);
+        assert_eq!(tokens.to_string().unwrap(), "[]byte");
+    }
+
+    #[test]
+    fn test_pointer() {
+        let typ = GoType::Pointer(Box::new(GoType::String));
+        let mut tokens = Tokens::<Go>::new();
+        (&typ).format_into(&mut tokens);
+        assert_eq!(tokens.to_string().unwrap(), "*string");
+        assert!(typ.needs_cleanup());
+        assert!(!GoType::Pointer(Box::new(GoType::Uint32)).needs_cleanup());
+    }
 
-    // #[test]
-    // fn test_nested_types() {
-    //     // Test *[]string
-    //     let typ = GoType::Pointer(Box::new(GoType::Slice(Box::new(GoType::String))));
-    //     let mut tokens = Tokens::<Go>::new();
-    //     (&typ).format_into(&mut tokens);
-    //     assert_eq!(tokens.to_string().unwrap(), "*[]string");
+    #[test]
+    fn test_nested_types() {
+        // Test *[]string
+        let typ = GoType::Pointer(Box::new(GoType::Slice(Box::new(GoType::String))));
+        let mut tokens = Tokens::<Go>::new();
+        (&typ).format_into(&mut tokens);
+        assert_eq!(tokens.to_string().unwrap(), "*[]string");
 
-    //     // Test [][]uint8
-    //     let typ = GoType::Slice(Box::new(GoType::Slice(Box::new(GoType::Uint8))));
-    //     let mut tokens = Tokens::<Go>::new();
-    //     (&typ).format_into(&mut tokens);
-    //     assert_eq!(tokens.to_string().unwrap(), "[][]uint8");
-    // }
+        // Test []*uint32
+        let typ = GoType::Slice(Box::new(GoType::Pointer(Box::new(GoType::Uint32))));
+        let mut tokens = Tokens::<Go>::new();
+        (&typ).format_into(&mut tokens);
+        assert_eq!(tokens.to_string().unwrap(), "[]*uint32");
+
+        // Test [][]uint8
+        let typ = GoType::Slice(Box::new(GoType::Slice(Box::new(GoType::Uint8))));
+        let mut tokens = Tokens::<Go>::new();
+        (&typ).format_into(&mut tokens);
+        assert_eq!(tokens.to_string().unwrap(), "[][]byte");
+    }
 }
@@ -1,10 +1,14 @@
 pub mod codegen;
+pub mod compat;
 pub mod go;
 
-use crate::go::GoType;
-use wit_bindgen_core::{
+use crate::compat::{
     abi::WasmType,
-    wit_parser::{Resolve, Result_, Type, TypeDef, TypeDefKind},
+    wit_parser::{Handle, Resolve, Result_, Type, TypeDef, TypeDefKind},
+};
+use crate::{
+    codegen::{resources::resource_name, support::Support},
+    go::{GoIdentifier, GoType},
 };
 
 // Temporary re-export while we migrate.
@@ -23,6 +27,121 @@ pub fn resolve_wasm_type(typ: &WasmType) -> GoType {
     }
 }
 
+/// Returns true if the WIT type is `target`, or contains it nested in
+/// another type.
+pub(crate) fn contains_type(resolve: &Resolve, typ: &Type, target: Type) -> bool {
+    let Type::Id(id) = typ else {
+        return *typ == target;
+    };
+    let contains = |typ: &Type| contains_type(resolve, typ, target);
+    match &resolve.types[*id].kind {
+        TypeDefKind::Record(record) => record.fields.iter().any(|field| contains(&field.ty)),
+        TypeDefKind::Tuple(tuple) => tuple.types.iter().any(contains),
+        TypeDefKind::Variant(variant) => {
+            variant.cases.iter().flat_map(|case| &case.ty).any(contains)
+        }
+        TypeDefKind::Option(typ)
+        | TypeDefKind::List(typ)
+        | TypeDefKind::FixedSizeList(typ, _)
+        | TypeDefKind::Type(typ) => contains(typ),
+        TypeDefKind::Result(result) => result.ok.iter().chain(&result.err).any(contains),
+        TypeDefKind::Future(typ) | TypeDefKind::Stream(typ) => typ.iter().any(contains),
+        TypeDefKind::Resource
+        | TypeDefKind::Handle(_)
+        | TypeDefKind::Flags(_)
+        | TypeDefKind::Enum(_)
+        | TypeDefKind::Unknown => false,
+    }
+}
+
+/// The name of the field of the Go struct for a named tuple holding the
+/// element at `index`, e.g. `F0`.
+pub(crate) fn tuple_field(index: usize) -> GoIdentifier {
+    GoIdentifier::public(format!("f{index}"))
+}
+
+/// The WIT types, and whether their bindings can be generated, as resolved
+/// by [`resolve_type`] and listed by `gravity features`.
+pub(crate) const TYPES: &[(&str, Support)] = &[
+    ("bool", Support::Supported),
+    ("u8", Support::Supported),
+    ("u16", Support::Supported),
+    ("u32", Support::Supported),
+    ("u64", Support::Supported),
+    ("s8", Support::Supported),
+    ("s16", Support::Supported),
+    ("s32", Support::Supported),
+    ("s64", Support::Supported),
+    (
+        "f32",
+        Support::Partial("not in options, results or variants returned by exports"),
+    ),
+    (
+        "f64",
+        Support::Partial("not in options, results or variants returned by exports"),
+    ),
+    ("char", Support::Supported),
+    ("string", Support::Supported),
+    ("error-context", Support::Supported),
+    ("record", Support::Supported),
+    ("variant", Support::Supported),
+    (
+        "enum",
+        Support::Partial("not returned by exports or passed to imports"),
+    ),
+    ("flags", Support::Unsupported),
+    (
+        "tuple",
+        Support::Partial("anonymous tuples only when returned by exports"),
+    ),
+    (
+        "option",
+        Support::Partial(
+            "only of strings when passed to exports, unless nested in records, tuples or lists",
+        ),
+    ),
+    (
+        "result",
+        Support::Partial(
+            "only with string errors, and not passed to exports unless nested in records, tuples or lists",
+        ),
+    ),
+    ("list", Support::Partial("not returned by imports")),
+    ("fixed-size-list", Support::Unsupported),
+    ("future", Support::Unsupported),
+    ("stream", Support::Unsupported),
+    ("resource", Support::Supported),
+    ("own", Support::Supported),
+    ("borrow", Support::Supported),
+    ("alias", Support::Supported),
+    ("unknown", Support::Unsupported),
+];
+
+/// Resolves the WIT type of a value nested in another, i.e. a field of a
+/// record or tuple, an element of a list or the payload of an option, to a
+/// Go type.
+///
+/// A Go value can't be a pair of a value and whether it's set, or of a value
+/// and an error, so options nested in other types are pointers, which are
+/// nil for `none`, e.g. `list<option<u32>>` is a `[]*uint32`, and results
+/// are the generated `Result`, e.g. `list<result<u32, string>>` is a
+/// `[]Result[uint32]`.
+pub fn resolve_field_type(typ: &Type, resolve: &Resolve) -> GoType {
+    match typ {
+        Type::Id(id) => match &resolve.types[*id].kind {
+            TypeDefKind::Option(value) => {
+                GoType::Pointer(Box::new(resolve_field_type(value, resolve)))
+            }
+            TypeDefKind::Result(Result_ {
+                ok: Some(ok),
+                err: Some(Type::String),
+            }) => GoType::Result(Box::new(resolve_field_type(ok, resolve))),
+            _ => resolve_type(typ, resolve),
+        },
+        _ => resolve_type(typ, resolve),
+    }
+}
+
 /// Resolves a WIT type to a Go type.
 ///
 /// # Panics
@@ -46,12 +165,9 @@ pub fn resolve_type(typ: &Type, resolve: &Resolve) -> GoType {
         Type::S64 => GoType::Int64,
         Type::F32 => GoType::Float32,
         Type::F64 => GoType::Float64,
-        Type::Char => {
-            // Is this a Go "rune"?
-            todo!("TODO(#6): resolve char type")
-        }
+        Type::Char => GoType::Rune,
         Type::String => GoType::String,
-        Type::ErrorContext => todo!("TODO(#4): implement error context conversion"),
+        Type::ErrorContext => GoType::ErrorContext,
 
         // Complex types.
         Type::Id(id) => {
@@ -64,17 +180,25 @@ pub fn resolve_type(typ: &Type, resolve: &Resolve) -> GoType {
                     GoType::UserDefined(name.clone().expect("expected record to have a name"))
                 }
                 TypeDefKind::Resource => todo!("TODO(#5): implement resources"),
-                TypeDefKind::Handle(_) => todo!("TODO(#5): implement resources"),
+                TypeDefKind::Handle(Handle::Own(id)) => {
+                    GoType::OwnHandle(resource_name(resolve, *id).to_string())
+                }
+                TypeDefKind::Handle(Handle::Borrow(id)) => {
+                    GoType::BorrowHandle(resource_name(resolve, *id).to_string())
+                }
                 TypeDefKind::Flags(_) => todo!("TODO(#4): implement flag conversion"),
-                TypeDefKind::Tuple(_) => todo!("TODO(#4): implement tuple conversion"),
-                // Variants are handled as an empty interfaces in type signatures; however, that
-                // means they require runtime type reflection
-                TypeDefKind::Variant(_) => GoType::Interface,
+                TypeDefKind::Tuple(_) => match name {
+                    Some(name) => GoType::UserDefined(name.clone()),
+                    None => todo!("TODO(#4): implement anonymous tuple conversion"),
+                },
+                TypeDefKind::Variant(_) => {
+                    GoType::UserDefined(name.clone().expect("expected variant to have a name"))
+                }
                 TypeDefKind::Enum(_) => {
                     GoType::UserDefined(name.clone().expect("expected enum to have a name"))
                 }
                 TypeDefKind::Option(value) => {
-                    GoType::ValueOrOk(Box::new(resolve_type(value, resolve)))
+                    GoType::ValueOrOk(Box::new(resolve_field_type(value, resolve)))
                 }
 
                 // Various results, including specialised ones.
@@ -105,7 +229,9 @@ pub fn resolve_type(typ: &Type, resolve: &Resolve) -> GoType {
                     err: None,
                 }) => GoType::Nothing,
 
-                TypeDefKind::List(inner) => GoType::Slice(Box::new(resolve_type(inner, resolve))),
+                TypeDefKind::List(inner) => {
+                    GoType::Slice(Box::new(resolve_field_type(inner, resolve)))
+                }
                 TypeDefKind::Future(_) => todo!("TODO(#4): implement future conversion"),
                 TypeDefKind::Stream(_) => todo!("TODO(#4): implement stream conversion"),
                 TypeDefKind::Type(_) => {
@@ -1,10 +1,34 @@
-use std::{fs, path::Path, process::ExitCode};
+mod diff;
 
-use clap::{Arg, ArgAction, Command};
+use std::{
+    fs,
+    io::Read,
+    path::{Component, Path, PathBuf},
+    process::ExitCode,
+    thread,
+    time::Duration,
+};
+
+use arcjet_gravity::compat::wit_component::{
+    StringEncoding,
+    metadata::{self, Bindgen},
+};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use diff::unified_diff;
 use genco::lang::{Go, go};
-use wit_bindgen_core::wit_parser::SizeAlign;
 
-use arcjet_gravity::codegen::{Bindings, WasmData};
+use arcjet_gravity::codegen::support::{Feature, Support, registry, world_features};
+use arcjet_gravity::codegen::{
+    Bindings, BindingsOptions, Derives, ErrorMode, Instrumentation, InterfaceFilter, Providers,
+    RUNTIME_PACKAGE, StubBehavior, TupleResults, WasmData, check_renames, component_metadata,
+    describe_world, diff_worlds, disambiguate, filter_features, filter_world, guest_bindings,
+    module_exports, module_imports, prefix_types, prune_types, unadapted_interfaces, uses_memory64,
+    with_runtime_package,
+};
+use arcjet_gravity::compat::wit_parser::{Resolve, SizeAlign, WorldId};
+use arcjet_gravity::go::{
+    Initialisms, Renames, with_initialisms, with_renames, with_symbol_prefix,
+};
 
 // `wit_component::decode` uses `root` as an arbitrary name for the primary
 // world name, see
@@ -12,8 +36,90 @@ use arcjet_gravity::codegen::{Bindings, WasmData};
 // 2. https://github.com/bytecodealliance/wasm-tools/issues/1315
 pub const PRIMARY_WORLD_NAME: &str = "root";
 
+/// The file argument reading the Wasm from stdin.
+const STDIN: &str = "-";
+
+/// How often the Wasm files are checked for changes with `--watch`.
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
 fn main() -> Result<ExitCode, ()> {
     let cmd = Command::new("gravity")
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
+        .subcommand(
+            Command::new("wit")
+                .about("print the WIT decoded from a WebAssembly file, with the Go names of the selected world")
+                .arg(
+                    Arg::new("world")
+                        .short('w')
+                        .long("world")
+                        .help("print the Go names for the specified world")
+                        .default_value(PRIMARY_WORLD_NAME),
+                )
+                .arg(
+                    Arg::new("file")
+                        .help("the WebAssembly file to process")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("features")
+                .about("list the WIT features and whether gravity supports them, or those used by the selected world of a file, failing if any is unsupported")
+                .arg(
+                    Arg::new("world")
+                        .short('w')
+                        .long("world")
+                        .help("list the features used by the specified world")
+                        .default_value(PRIMARY_WORLD_NAME),
+                )
+                .arg(
+                    Arg::new("file")
+                        .help("the WIT file or directory, or WebAssembly file, whose world's features to list"),
+                ),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("compare the selected world of two files, e.g. the old and new builds of a component, listing the functions, interfaces and types added, removed or changed, and failing if regenerating the bindings breaks their Go API")
+                .arg(
+                    Arg::new("world")
+                        .short('w')
+                        .long("world")
+                        .help("compare the specified world of both files")
+                        .default_value(PRIMARY_WORLD_NAME),
+                )
+                .arg(
+                    Arg::new("old")
+                        .help("the WIT file or directory, or WebAssembly file, of the old version")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("new")
+                        .help("the WIT file or directory, or WebAssembly file, of the new version")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("guest")
+                .about("generate guest bindings for Go (TinyGo) components of the selected world, supporting only bool, integer and float parameters and results")
+                .arg(
+                    Arg::new("world")
+                        .short('w')
+                        .long("world")
+                        .help("generate guest bindings for the specified world")
+                        .default_value(PRIMARY_WORLD_NAME),
+                )
+                .arg(
+                    Arg::new("output")
+                        .help("the file path where output generated code should be output")
+                        .short('o')
+                        .long("output"),
+                )
+                .arg(
+                    Arg::new("file")
+                        .help("the WIT file or directory, or WebAssembly file, to process")
+                        .required(true),
+                ),
+        )
         .arg(
             Arg::new("world")
                 .short('w')
@@ -24,48 +130,651 @@ fn main() -> Result<ExitCode, ()> {
         .arg(
             Arg::new("inline-wasm")
                 .long("inline-wasm")
-                .help("include the WebAssembly file as hex bytes in the output code")
-                .action(ArgAction::SetTrue),
+                .value_name("ENCODING")
+                .help("include the WebAssembly file in the output code, as hex bytes, as an escaped string constant, or as a gzip-compressed string decompressed when the package is initialized")
+                .value_parser(["bytes", "string", "gzip"])
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("bytes"),
         )
         .arg(
             Arg::new("file")
-                .help("the WebAssembly file to process")
-                .required(true),
+                .help("the WebAssembly file to process, or `-` to read it from stdin, followed by any components whose exports satisfy its imports")
+                .required(true)
+                .num_args(1..),
+        )
+        .arg(
+            Arg::new("wasm-out")
+                .long("wasm-out")
+                .value_name("FILE")
+                .help("write the WebAssembly file embedded by the output code to the given path, in the directory of the output or below")
+                .conflicts_with("inline-wasm"),
+        )
+        .arg(
+            Arg::new("wasm-variant")
+                .long("wasm-variant")
+                .value_name("TAG=FILE")
+                .help("embed the given WebAssembly file instead when built with the Go build tag, declaring the WebAssembly of each tag in a file of its own next to the output")
+                .requires("output")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("shared-runtime")
+                .long("shared-runtime")
+                .help("generate constructors taking a Wazero runtime shared with the host, and optionally a module compiled with it")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("limits")
+                .long("limits")
+                .help("generate options limiting the memory of the instances and interrupting calls whose context is done, taken by the constructors creating a Wazero runtime")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("instance-options")
+                .long("instance-options")
+                .help("generate options configuring the directories, environment, arguments and standard streams of each instance, taken by Instantiate and seen by guests importing wasi_snapshot_preview1")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("providers")
+                .long("providers")
+                .value_name("FRAMEWORK")
+                .help("generate a provider of the factory taking the imports without a context, in a provider set or module of the dependency injection framework")
+                .value_parser(["wire", "fx"]),
+        )
+        .arg(
+            Arg::new("wasm-from-bytes")
+                .long("wasm-from-bytes")
+                .help("generate a constructor compiling WebAssembly bytes given by the host, rather than the embedded ones")
+                .action(ArgAction::SetTrue),
         )
         .arg(
             Arg::new("output")
                 .help("the file path where output generated code should be output")
                 .short('o')
                 .long("output"),
+        )
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .help("don't write the output, but exit with an error if it would change")
+                .requires("output")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("don't write the output, but print a unified diff of the changes to it")
+                .requires("output")
+                .conflicts_with("check")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .help("regenerate the output whenever any of the WebAssembly files changes, until interrupted")
+                .requires("output")
+                .conflicts_with_all(["check", "dry-run"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("go-generate")
+                .long("go-generate")
+                .help("write a //go:generate directive running this command into the output, so `go generate` regenerates it")
+                .requires("output")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("expose-module")
+                .long("expose-module")
+                .help("generate a Module() accessor and memory helpers on the instance")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dynamic-exports")
+                .long("dynamic-exports")
+                .help("generate Exports() and CallByName() methods listing and calling the exported functions by name")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("call-timeouts")
+                .long("call-timeouts")
+                .help("generate a variant of each exported function taking a timeout, returning ErrCallTimeout and closing the instance if the call doesn't return in time")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("checked-casts")
+                .long("checked-casts")
+                .help("check that the core Wasm results of the exported functions fit the types they're lifted into, returning an error rather than truncating them")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("unsafe-strings")
+                .long("unsafe-strings")
+                .help("pass the UTF-8 strings of the guest to the host functions as views of its memory rather than copies, which mustn't be retained past the call")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("tinygo-compat")
+                .long("tinygo-compat")
+                .help("avoid constructs TinyGo doesn't support, such as reflection, for hosts built with TinyGo")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("imports-struct")
+                .long("imports-struct")
+                .help("take the imports as a single struct in the factory constructor")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("allow-missing-imports")
+                .long("allow-missing-imports")
+                .value_name("BEHAVIOR")
+                .help("replace imports that aren't provided with stubs that panic or return zero values")
+                .value_parser(["panic", "zero"])
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("panic"),
+        )
+        .arg(
+            Arg::new("wasi-adapters")
+                .long("wasi-adapters")
+                .help("replace imports of WASI clocks and random interfaces that aren't provided with adapters backed by the Go standard library")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("optional-import")
+                .long("optional-import")
+                .value_name("INTERFACE")
+                .help("allow the import of the WIT interface, e.g. `wasi:logging/logging`, not to be provided, skipping its host module rather than failing to create the factory")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("batch-imports")
+                .long("batch-imports")
+                .value_name("INTERFACE")
+                .help("queue the calls of the functions without results of the imported WIT interface, e.g. `wasi:logging/logging`, during a call of an export, making them once it returns")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("error-mode")
+                .long("error-mode")
+                .value_name("MODE")
+                .help("whether exported functions panic on errors their result can't carry, or always return a trailing error")
+                .value_parser(["panic", "wrap"])
+                .default_value("panic"),
+        )
+        .arg(
+            Arg::new("deny-panics")
+                .long("deny-panics")
+                .help("fail if an exported function panics rather than returning an error, e.g. as its result can't carry one without `--error-mode wrap`")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("tuple-results")
+                .long("tuple-results")
+                .value_name("MAPPING")
+                .help("whether exported functions return named tuples as their struct, or every tuple as multiple values")
+                .value_parser(["struct", "multi"])
+                .default_value("struct"),
+        )
+        .arg(
+            Arg::new("instrument")
+                .long("instrument")
+                .value_name("KIND")
+                .help("instrument every call of an export or import")
+                .value_parser(["otel", "metrics", "log"])
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("derive")
+                .long("derive")
+                .value_name("METHODS")
+                .help("generate String(), JSON marshalling or Equal methods for the imported enums, records and variants")
+                .value_parser(["string", "json", "equal"])
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("initialisms")
+                .long("initialisms")
+                .help("write initialisms like `id` and `http` in uppercase in Go identifiers, e.g. `ID` and `HTTPClient`")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("initialism")
+                .long("initialism")
+                .value_name("WORD")
+                .help("write the word in uppercase in Go identifiers, in addition to the standard initialisms")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("rename-file")
+                .long("rename-file")
+                .value_name("FILE")
+                .help("generate the Go identifiers of WIT names as given by the TOML file, with lines like `error-info = \"APIError\"`, for types, interfaces, methods and fields alike"),
+        )
+        .arg(
+            Arg::new("symbol-prefix")
+                .long("symbol-prefix")
+                .value_name("PREFIX")
+                .help("prefix the helpers and types shared by the bindings of every world, e.g. `basic`, to generate the bindings of several worlds into the same Go package"),
+        )
+        .arg(
+            Arg::new("runtime-package")
+                .long("runtime-package")
+                .value_name("IMPORT_PATH")
+                .help(format!("import the helpers shared by the bindings, such as writeString and the tables of resources, from the Go package rather than generating them, by default {RUNTIME_PACKAGE}"))
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value(RUNTIME_PACKAGE),
+        )
+        .arg(
+            Arg::new("features")
+                .long("features")
+                .value_name("FEATURES")
+                .help("generate bindings for the WIT items gated by the given comma-separated `@unstable` features")
+                .value_delimiter(',')
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("all-features")
+                .long("all-features")
+                .help("generate bindings for the WIT items gated by any `@unstable` feature")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("all-types")
+                .long("all-types")
+                .help("generate bindings for every type of the interfaces, including those no function uses")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("include-interface")
+                .long("include-interface")
+                .value_name("INTERFACE")
+                .help("only generate bindings for the given imported or exported interface, e.g. `ns:pkg/name`")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("exclude-interface")
+                .long("exclude-interface")
+                .value_name("INTERFACE")
+                .help("don't generate bindings for the given imported or exported interface, e.g. `ns:pkg/name`")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("mocks")
+                .long("mocks")
+                .value_name("FILE")
+                .help("write mock implementations of the import interfaces to the given file"),
+        )
+        .arg(
+            Arg::new("emit-harness")
+                .long("emit-harness")
+                .value_name("FILE")
+                .help("write a Go program calling each export with zero values to the given file, in a directory of its own in the module of the output")
+                .requires("output")
+                .conflicts_with("wasm-from-bytes"),
+        )
+        .arg(
+            Arg::new("emit-fuzz")
+                .long("emit-fuzz")
+                .value_name("FILE")
+                .help("write Go fuzz targets calling the exports taking strings or bytes with random inputs to the given _test.go file, in the package of the output"),
+        )
+        .arg(
+            Arg::new("stats")
+                .long("stats")
+                .value_name("FORMAT")
+                .help("print the lines generated for each function, and the helpers they use, to stderr, as text or json")
+                .value_parser(["text", "json"])
+                .num_args(0..=1)
+                .default_missing_value("text")
+                .require_equals(true),
         );
 
     let matches = cmd.get_matches();
+    match matches.subcommand() {
+        Some(("wit", matches)) => return Ok(print_wit(matches)),
+        Some(("features", matches)) => return Ok(print_features(matches)),
+        Some(("diff", matches)) => return Ok(print_diff(matches)),
+        Some(("guest", matches)) => return Ok(generate_guest(matches)),
+        _ => {}
+    }
+    let symbol_prefix = matches.get_one::<String>("symbol-prefix").cloned();
+    let runtime_package = matches.get_one::<String>("runtime-package").cloned();
+    let renames = match matches.get_one::<String>("rename-file") {
+        Some(path) => {
+            match fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| Renames::parse(&text))
+            {
+                Ok(renames) => Some(renames),
+                Err(err) => {
+                    eprintln!("unable to read rename file: {path}: {err}");
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+        }
+        None => None,
+    };
+    let generate = || {
+        with_runtime_package(runtime_package.clone(), || {
+            with_symbol_prefix(symbol_prefix.clone(), || {
+                with_initialisms(initialisms(&matches), || {
+                    with_renames(renames.clone(), || generate(&matches))
+                })
+            })
+        })
+    };
+    if matches.get_flag("watch") {
+        return watch(&matches, generate);
+    }
+    generate()
+}
+
+/// Regenerates the bindings whenever any of the Wasm files they're generated
+/// from changes, until interrupted.
+///
+/// The files are polled for changes to their modification times, rather than
+/// relying on notifications specific to each platform, and are only read once
+/// they stop changing, so that files that are still being written are skipped.
+fn watch(
+    matches: &ArgMatches,
+    mut generate: impl FnMut() -> Result<ExitCode, ()>,
+) -> Result<ExitCode, ()> {
+    let files = matches
+        .get_many::<String>("file")
+        .expect("should have a file")
+        .map(String::as_str)
+        .chain(
+            matches
+                .get_many::<String>("wasm-variant")
+                .unwrap_or_default()
+                .filter_map(|variant| variant.split_once('=').map(|(_, file)| file)),
+        )
+        .collect::<Vec<_>>();
+    if files.contains(&STDIN) {
+        eprintln!("unable to watch stdin: pass the path to the WebAssembly file");
+        return Ok(ExitCode::FAILURE);
+    }
+    let modified = || {
+        files
+            .iter()
+            .map(|file| {
+                fs::metadata(file)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut last = modified();
+    loop {
+        generate()?;
+        eprintln!("watching for changes: {}", files.join(" "));
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+            let current = modified();
+            if current == last {
+                continue;
+            }
+            last = current;
+            // Wait for the files to stop changing before reading them
+            thread::sleep(WATCH_INTERVAL);
+            let settled = modified();
+            if settled == last {
+                break;
+            }
+            last = settled;
+        }
+    }
+}
+
+/// The initialisms to case identifiers with, if enabled.
+fn initialisms(matches: &ArgMatches) -> Option<Initialisms> {
+    let words = matches.get_many::<String>("initialism");
+    if !matches.get_flag("initialisms") && words.is_none() {
+        return None;
+    }
+    let mut initialisms = Initialisms::standard();
+    for word in words.unwrap_or_default() {
+        initialisms.insert(word);
+    }
+    Some(initialisms)
+}
+
+/// Generates the bindings for the selected world.
+fn generate(matches: &ArgMatches) -> Result<ExitCode, ()> {
     let selected_world = matches
         .get_one::<String>("world")
         .expect("should have a world");
-    let file = matches
-        .get_one::<String>("file")
+    let mut files = matches
+        .get_many::<String>("file")
         .expect("should have a file");
-    let inline_wasm = matches.get_flag("inline-wasm");
-    let output = matches.get_one::<String>("output");
-
-    // Load the file specified as the `file` arg to clap
-    let wasm = match fs::read(file) {
-        Ok(wasm) => wasm,
-        Err(_) => {
-            eprintln!("unable to read file: {file}");
+    let file = files.next().expect("should have a file");
+    let linked_files = files.collect::<Vec<_>>();
+    let inline_encoding = matches.get_one::<String>("inline-wasm");
+    let inline_wasm = inline_encoding.is_some();
+    let inline_bytes = inline_encoding.is_none_or(|encoding| encoding == "bytes");
+    let wasm_out = matches.get_one::<String>("wasm-out");
+    if file == STDIN && !inline_wasm && wasm_out.is_none() {
+        eprintln!("unable to read stdin: pass --inline-wasm or --wasm-out to output the Wasm");
+        return Ok(ExitCode::FAILURE);
+    }
+    if matches.get_flag("go-generate") {
+        if std::iter::once(file)
+            .chain(linked_files.iter().copied())
+            .any(|file| file == STDIN)
+        {
+            eprintln!(
+                "unable to regenerate stdin with go generate: pass the path to the WebAssembly file"
+            );
+            return Ok(ExitCode::FAILURE);
+        }
+        // `go generate` runs the directive from the directory of the output
+        let output = Path::new(
+            matches
+                .get_one::<String>("output")
+                .expect("should have an output"),
+        );
+        if output
+            .parent()
+            .is_some_and(|dir| !dir.as_os_str().is_empty())
+        {
+            eprintln!(
+                "unable to write a go:generate directive: run gravity from the directory of the output, which go generate resolves the paths from"
+            );
             return Ok(ExitCode::FAILURE);
         }
+    }
+    let symbol_prefix = matches.get_one::<String>("symbol-prefix");
+    let valid_prefix = |prefix: &str| {
+        prefix.starts_with(|c: char| c.is_ascii_lowercase())
+            && prefix
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    };
+    if let Some(prefix) = symbol_prefix
+        && !valid_prefix(prefix)
+    {
+        eprintln!(
+            "invalid symbol prefix: {prefix}: expected lowercase letters, digits and dashes, starting with a letter"
+        );
+        return Ok(ExitCode::FAILURE);
+    }
+    let mut variants = Vec::new();
+    for variant in matches
+        .get_many::<String>("wasm-variant")
+        .unwrap_or_default()
+    {
+        let build_tag = |tag: &str| {
+            !tag.is_empty()
+                && tag
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        };
+        match variant.split_once('=') {
+            Some((tag, file)) if build_tag(tag) && !file.is_empty() => variants.push((tag, file)),
+            _ => {
+                eprintln!("invalid wasm variant: {variant}: expected TAG=FILE");
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+    let output = matches.get_one::<String>("output");
+    let check = matches.get_flag("check");
+    let dry_run = matches.get_flag("dry-run");
+    let mocks = matches.get_one::<String>("mocks");
+    let harness = matches.get_one::<String>("emit-harness");
+    let fuzz = matches.get_one::<String>("emit-fuzz");
+    let stats = matches.get_one::<String>("stats");
+    let deny_panics = matches.get_flag("deny-panics");
+    let instrument = matches
+        .get_many::<String>("instrument")
+        .unwrap_or_default()
+        .collect::<Vec<_>>();
+    let derive = matches
+        .get_many::<String>("derive")
+        .unwrap_or_default()
+        .collect::<Vec<_>>();
+    let filter = InterfaceFilter {
+        include: matches
+            .get_many::<String>("include-interface")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        exclude: matches
+            .get_many::<String>("exclude-interface")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+    };
+    let features = matches
+        .get_many::<String>("features")
+        .unwrap_or_default()
+        .cloned()
+        .collect::<Vec<_>>();
+    let all_features = matches.get_flag("all-features");
+    let all_types = matches.get_flag("all-types");
+    // The JSON methods use `encoding/json`, which relies on reflection
+    if matches.get_flag("tinygo-compat") && derive.contains(&&"json".to_string()) {
+        eprintln!("unable to derive json with --tinygo-compat: encoding/json relies on reflection");
+        return Ok(ExitCode::FAILURE);
+    }
+    let options = BindingsOptions {
+        expose_module: matches.get_flag("expose-module"),
+        imports_struct: matches.get_flag("imports-struct"),
+        from_bytes: matches.get_flag("wasm-from-bytes"),
+        shared_runtime: matches.get_flag("shared-runtime"),
+        limits: matches.get_flag("limits"),
+        instance_options: matches.get_flag("instance-options"),
+        providers: matches.get_one::<String>("providers").map(|framework| {
+            match framework.as_str() {
+                "fx" => Providers::Fx,
+                _ => Providers::Wire,
+            }
+        }),
+        stub_missing_imports: matches
+            .get_one::<String>("allow-missing-imports")
+            .map(|behavior| match behavior.as_str() {
+                "zero" => StubBehavior::ZeroValue,
+                _ => StubBehavior::Panic,
+            })
+            // The harness and the fuzz targets leave the imports to the stubs
+            .or(harness.or(fuzz).map(|_| StubBehavior::ZeroValue)),
+        wasi_adapters: matches.get_flag("wasi-adapters"),
+        optional_imports: matches
+            .get_many::<String>("optional-import")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        batched_imports: matches
+            .get_many::<String>("batch-imports")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        instrumentation: Instrumentation {
+            otel: instrument.contains(&&"otel".to_string()),
+            metrics: instrument.contains(&&"metrics".to_string()),
+            log: instrument.contains(&&"log".to_string()),
+        },
+        error_mode: match matches.get_one::<String>("error-mode").map(String::as_str) {
+            Some("wrap") => ErrorMode::Wrap,
+            _ => ErrorMode::Panic,
+        },
+        tuple_results: match matches
+            .get_one::<String>("tuple-results")
+            .map(String::as_str)
+        {
+            Some("multi") => TupleResults::Multi,
+            _ => TupleResults::Struct,
+        },
+        dynamic_exports: matches.get_flag("dynamic-exports"),
+        call_timeouts: matches.get_flag("call-timeouts"),
+        tinygo_compat: matches.get_flag("tinygo-compat"),
+        checked_casts: matches.get_flag("checked-casts"),
+        string_views: matches.get_flag("unsafe-strings"),
+        derives: Derives {
+            string: derive.contains(&&"string".to_string()),
+            json: derive.contains(&&"json".to_string()),
+            equal: derive.contains(&&"equal".to_string()),
+        },
     };
 
-    let (module, bindgen) = wit_component::metadata::decode(&wasm)
-        // If the Wasm doesn't have a custom section, None will be returned so we need to use the original
-        .map(|(module, bindgen)| (module.unwrap_or(wasm), bindgen))
-        .expect("file should be a valid WebAssembly module");
+    // The generated files are fingerprinted by everything they're generated
+    // from, i.e. the version of gravity and the options, along with the WIT
+    // of the files and, when inlined, the Wasm.
+    let mut inputs = vec![
+        env!("CARGO_PKG_VERSION").as_bytes().to_vec(),
+        format!(
+            "{selected_world:?} {inline_wasm:?} {options:?} {filter:?} {features:?} \
+             {all_features:?} {:?} {symbol_prefix:?} {:?}",
+            initialisms(matches),
+            matches.get_one::<String>("runtime-package"),
+        )
+        .into_bytes(),
+    ];
+
+    // Load the file specified as the `file` arg to clap
+    let Some((module, bindgen, metadata)) = read_component(file) else {
+        return Ok(ExitCode::FAILURE);
+    };
+    inputs.push(metadata);
+    if inline_wasm {
+        inputs.push(module.clone());
+    }
+    if let Some(path) = matches.get_one::<String>("rename-file")
+        && let Ok(renames) = fs::read(path)
+    {
+        inputs.push(renames);
+    }
+    if !inline_bytes {
+        inputs.push(
+            inline_encoding
+                .expect("should inline the Wasm")
+                .as_bytes()
+                .to_vec(),
+        );
+    }
 
-    let wasm_file = &format!("{}.wasm", selected_world.replace('-', "_"));
+    let wasm_file = &match (wasm_out, output) {
+        (Some(path), Some(outpath)) => match embed_path(Path::new(outpath), Path::new(path)) {
+            Ok(path) => path,
+            Err(err) => {
+                eprintln!("unable to embed the WebAssembly file: {path}: {err}");
+                return Ok(ExitCode::FAILURE);
+            }
+        },
+        (Some(path), None) => Path::new(path)
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned(),
+        (None, _) => format!("{}.wasm", selected_world.replace('-', "_")),
+    };
 
-    let Some((_, world)) = bindgen
+    let Some((world_id, _)) = bindgen
         .resolve
         .worlds
         .iter()
@@ -75,52 +784,787 @@ fn main() -> Result<ExitCode, ()> {
         return Ok(ExitCode::FAILURE);
     };
 
+    // The variants are only embedded in place of the Wasm, so they must be
+    // built for the same world, and are checked like it
+    let mut variant_modules = Vec::new();
+    for (tag, variant_file) in &variants {
+        let Some((variant_module, variant_bindgen, variant_metadata)) =
+            read_component(variant_file)
+        else {
+            return Ok(ExitCode::FAILURE);
+        };
+        if !variant_bindgen
+            .resolve
+            .worlds
+            .iter()
+            .any(|(_, world)| world.name == *selected_world)
+        {
+            eprintln!(
+                "unable to read wasm variant: {variant_file}: unable to find world: {selected_world}"
+            );
+            return Ok(ExitCode::FAILURE);
+        }
+        inputs.push(tag.as_bytes().to_vec());
+        inputs.push(variant_metadata);
+        if inline_wasm {
+            inputs.push(variant_module.clone());
+        }
+        let variant_wasm_file = format!("{}_{tag}.wasm", selected_world.replace('-', "_"));
+        variant_modules.push((*tag, variant_module, variant_wasm_file));
+    }
+
+    // Any additional files are components linked into the factory, so their
+    // WIT is merged into the same resolve as the selected world.
+    let mut resolve = bindgen.resolve;
+
+    // Like wit-bindgen, items gated by features that aren't enabled are
+    // skipped.
+    resolve.features.extend(features);
+    resolve.all_features = all_features;
+    if let Err(err) = filter_features(&mut resolve, world_id, &module_imports(&module)) {
+        eprintln!("unable to filter features: {err}");
+        return Ok(ExitCode::FAILURE);
+    }
+    if !filter.is_empty()
+        && let Err(err) = filter_world(&mut resolve, world_id, &filter, &module_imports(&module))
+    {
+        eprintln!("unable to filter interfaces: {err}");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let mut linked = Vec::new();
+    for linked_file in &linked_files {
+        let Some((linked_module, linked_bindgen, linked_metadata)) = read_component(linked_file)
+        else {
+            return Ok(ExitCode::FAILURE);
+        };
+        inputs.push(linked_metadata);
+        if inline_wasm {
+            inputs.push(linked_module.clone());
+        }
+        let linked_world = named_world(&linked_bindgen);
+        let linked_world = match resolve
+            .merge(linked_bindgen.resolve)
+            .and_then(|remap| remap.map_world(linked_world, None))
+        {
+            Ok(linked_world) => linked_world,
+            Err(err) => {
+                eprintln!("unable to link file: {linked_file}: {err}");
+                return Ok(ExitCode::FAILURE);
+            }
+        };
+        if let Err(err) =
+            filter_features(&mut resolve, linked_world, &module_imports(&linked_module))
+        {
+            eprintln!("unable to link file: {linked_file}: {err}");
+            return Ok(ExitCode::FAILURE);
+        }
+        let linked_world_name = &resolve.worlds[linked_world].name;
+        if linked_world_name == selected_world
+            || linked
+                .iter()
+                .any(|(id, _, _, _)| resolve.worlds[*id].name == *linked_world_name)
+        {
+            eprintln!("unable to link file: {linked_file}: duplicate world: {linked_world_name}");
+            return Ok(ExitCode::FAILURE);
+        }
+        let linked_wasm_file = format!("{}.wasm", linked_world_name.replace('-', "_"));
+        linked.push((
+            linked_world,
+            linked_module,
+            linked_wasm_file,
+            linked_bindgen.metadata,
+        ));
+    }
+
+    let worlds = std::iter::once(world_id)
+        .chain(linked.iter().map(|(id, _, _, _)| *id))
+        .collect::<Vec<_>>();
+    if !all_types {
+        for (name, types) in prune_types(&mut resolve, &worlds) {
+            eprintln!(
+                "note: skipping the types of {name} that no function uses: {} (pass --all-types to generate them)",
+                types.join(", ")
+            );
+        }
+    }
+    if let Some(prefix) = symbol_prefix {
+        prefix_types(&mut resolve, prefix);
+    }
+    for warning in disambiguate(&mut resolve, &worlds) {
+        eprintln!("warning: {warning}");
+    }
+    if let Err(err) = check_renames(&resolve, &worlds) {
+        eprintln!("unable to rename: {err}");
+        return Ok(ExitCode::FAILURE);
+    }
+    if options.wasi_adapters {
+        for interface in unadapted_interfaces(&resolve, &worlds) {
+            eprintln!("warning: no WASI adapter for {interface}, which the host has to implement");
+        }
+    }
+
     let mut sizes = SizeAlign::default();
-    sizes.fill(&bindgen.resolve);
-    let mut bindings = Bindings::new(&bindgen.resolve, world, &sizes);
+    sizes.fill(&resolve);
+    let mut bindings = Bindings::new(&resolve, &resolve.worlds[world_id], &sizes);
+    bindings.set_options(options);
 
-    bindings.include_wasm(if inline_wasm {
-        WasmData::Inline(&module)
-    } else {
-        WasmData::Embedded(wasm_file)
-    });
+    // The bindings are shared by the variants, so only the functions every
+    // variant exports are called
+    let mut exports = module_exports(&module);
+    for (_, variant_module, _) in &variant_modules {
+        let variant_exports = module_exports(variant_module);
+        exports.retain(|export| variant_exports.contains(export));
+    }
+    bindings.set_module_exports(exports);
+    bindings.set_module_metadata(&bindgen.metadata);
+
+    // With variants, the Wasm is declared in a file per build tag instead
+    if variants.is_empty() {
+        bindings.include_wasm(wasm_data(inline_encoding, &module, wasm_file));
+    }
+
+    for (linked_world, linked_module, linked_wasm_file, linked_metadata) in &linked {
+        bindings.link(
+            &resolve.worlds[*linked_world],
+            wasm_data(inline_encoding, linked_module, linked_wasm_file),
+            module_exports(linked_module),
+            linked_metadata,
+        );
+    }
 
     bindings.generate();
 
-    let header = "// Code generated by arcjet-gravity; DO NOT EDIT.\n\n".to_string();
-    let mut w = genco::fmt::FmtWriter::new(header);
-    let fmt = genco::fmt::Config::from_lang::<Go>().with_indentation(genco::fmt::Indentation::Tab);
-    let config = go::Config::default().with_package(selected_world.replace('-', "_"));
+    let package = selected_world.replace('-', "_");
+    let sources = std::iter::once(file)
+        .chain(linked_files)
+        .collect::<Vec<_>>();
+    // The mocks, the fuzz targets, the harness and the variants of the Wasm are
+    // regenerated along with the bindings, so they don't declare a
+    // `go:generate` directive of their own
+    let harness_header = header(selected_world, &sources, &fingerprint(&inputs), false);
+    let header = &header(
+        selected_world,
+        &sources,
+        &fingerprint(&inputs),
+        matches.get_flag("go-generate"),
+    );
+
+    let mut outputs = Vec::new();
+    if let Some(mocks_outpath) = mocks {
+        let mocks = format_go_file(&bindings.generate_mocks(), &package, &harness_header);
+        outputs.push((PathBuf::from(mocks_outpath), mocks.into_bytes()));
+    }
+
+    if let Some(fuzz_outpath) = fuzz {
+        let fuzz = format_go_file(&bindings.generate_fuzz(), &package, &harness_header);
+        outputs.push((PathBuf::from(fuzz_outpath), fuzz.into_bytes()));
+    }
+
+    if let Some((harness_outpath, outpath)) = harness.zip(output) {
+        let import_path = match go_import_path(Path::new(outpath), Path::new(harness_outpath)) {
+            Ok(import_path) => import_path,
+            Err(err) => {
+                eprintln!("unable to generate the harness: {err}");
+                return Ok(ExitCode::FAILURE);
+            }
+        };
+        let harness = bindings.generate_harness(&package, &import_path);
+        let harness = format_go_file(&harness, "main", &harness_header);
+        outputs.push((PathBuf::from(harness_outpath), harness.into_bytes()));
+    }
+
+    if let Some(outpath) = output.filter(|_| !variants.is_empty()) {
+        let outpath = Path::new(outpath);
+        let stem = outpath.file_stem().unwrap_or_default().to_string_lossy();
+        let default = variants
+            .iter()
+            .map(|(tag, _)| format!("!{tag}"))
+            .collect::<Vec<_>>()
+            .join(" && ");
+        let files = variant_modules
+            .iter()
+            .map(|(tag, module, file)| (tag.to_string(), tag.to_string(), module, file.as_str()))
+            .chain([("default".to_string(), default, &module, wasm_file.as_str())]);
+        for (suffix, constraint, module, file) in files {
+            let wasm = bindings.generate_wasm(wasm_data(inline_encoding, module, file));
+            let header = format!("{harness_header}//go:build {constraint}\n\n");
+            let contents = format_go_file(&wasm, &package, &header);
+            outputs.push((
+                outpath.with_file_name(format!("{stem}_{suffix}.go")),
+                contents.into_bytes(),
+            ));
+        }
+    }
 
     // TODO(#16): Don't use the internal bindings.out field
-    bindings
-        .out
-        .format_file(&mut w.as_formatter(&fmt), &config)
-        .unwrap();
+    let generated = format_go_file(&bindings.out, &package, header);
+    // The functions are generated again to be measured, so only once
+    let generated_stats = (stats.is_some() || deny_panics).then(|| bindings.stats(&generated));
+    match (stats.map(String::as_str), &generated_stats) {
+        (Some("json"), Some(generated_stats)) => eprint!("{}", generated_stats.to_json()),
+        (Some(_), Some(generated_stats)) => eprint!("{}", generated_stats.to_text()),
+        _ => {}
+    }
+    if let Some(generated_stats) = generated_stats.filter(|_| deny_panics) {
+        let panics = generated_stats.panics();
+        for panic in &panics {
+            eprintln!("unable to generate without panics: {panic}");
+        }
+        if !panics.is_empty() {
+            return Ok(ExitCode::FAILURE);
+        }
+    }
 
+    if !inline_wasm {
+        // The Wasm files are written next to the bindings, except for the
+        // one given by `--wasm-out`
+        let wasm_files = linked
+            .iter()
+            .map(|(_, module, file, _)| (file, module))
+            .chain(
+                variant_modules
+                    .iter()
+                    .map(|(_, module, file)| (file, module)),
+            )
+            .map(|(file, module)| {
+                (
+                    output.map(|outpath| Path::new(outpath).with_file_name(file)),
+                    module,
+                )
+            })
+            .chain([(
+                wasm_out
+                    .map(PathBuf::from)
+                    .or_else(|| output.map(|outpath| Path::new(outpath).with_file_name(wasm_file))),
+                &module,
+            )]);
+        for (wasm_outpath, module) in wasm_files {
+            if let Some(wasm_outpath) = wasm_outpath {
+                outputs.push((wasm_outpath, module.clone()));
+            }
+        }
+    }
     match output {
-        Some(outpath) => {
-            if !inline_wasm {
-                let wasm_outpath = Path::new(outpath).with_file_name(wasm_file);
-                match fs::write(&wasm_outpath, module) {
-                    Ok(_) => (),
-                    Err(_) => {
-                        eprintln!("failed to create file: {}", wasm_outpath.to_string_lossy());
-                        return Ok(ExitCode::FAILURE);
-                    }
-                }
+        Some(outpath) => outputs.push((PathBuf::from(outpath), generated.into_bytes())),
+        None => println!("{generated}"),
+    }
+
+    // Files that haven't changed aren't written, so tools watching them
+    // aren't triggered for nothing.
+    let changed = outputs
+        .iter()
+        .filter(|(path, contents)| fs::read(path).ok().as_ref() != Some(contents))
+        .collect::<Vec<_>>();
+    if check {
+        for (path, _) in &changed {
+            eprintln!("file is out of date: {}", path.to_string_lossy());
+        }
+        return Ok(if changed.is_empty() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
+    if dry_run {
+        for (path, contents) in &changed {
+            let old = fs::read(path).ok();
+            print!(
+                "{}",
+                unified_diff(&path.to_string_lossy(), old.as_deref(), contents)
+            );
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    for (path, contents) in changed {
+        if !write_file(path, contents) {
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The Wasm of the output code, inlined with the `--inline-wasm` encoding,
+/// or else embedded from the file.
+fn wasm_data<'a>(
+    inline_encoding: Option<&String>,
+    module: &'a [u8],
+    file: &'a str,
+) -> WasmData<'a> {
+    match inline_encoding.map(String::as_str) {
+        Some("string") => WasmData::String(module),
+        Some("gzip") => WasmData::Gzip(module),
+        Some(_) => WasmData::Inline(module),
+        None => WasmData::Embedded(file),
+    }
+}
+
+/// The path of the WebAssembly file embedded by the Go file at `output`,
+/// relative to the directory of the Go file and separated by slashes, as
+/// `//go:embed` requires on every platform.
+///
+/// `go:embed` can't embed files outside of the Go file's directory, so this
+/// fails if the WebAssembly file isn't in it or one of its subdirectories.
+fn embed_path(output: &Path, wasm: &Path) -> Result<String, String> {
+    let absolute = |path: &Path| {
+        std::path::absolute(path)
+            .map(|path| normalize(&path))
+            .map_err(|err| err.to_string())
+    };
+    let dir = absolute(output)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let wasm = absolute(wasm)?;
+    let Ok(relative) = wasm.strip_prefix(&dir) else {
+        return Err(format!(
+            "go:embed can only embed files in the directory of the output, {}, or below",
+            dir.to_string_lossy()
+        ));
+    };
+    let components = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>();
+    if components.is_empty() {
+        return Err("the path is a directory".to_string());
+    }
+    Ok(components.join("/"))
+}
+
+/// The import path of the Go package of the bindings at `output`, to be
+/// imported by the harness at `harness`, given by the `go.mod` file of the
+/// module they're in.
+fn go_import_path(output: &Path, harness: &Path) -> Result<String, String> {
+    let dir = |path: &Path| {
+        std::path::absolute(path)
+            .map(|path| normalize(&path))
+            .map(|path| path.parent().map(Path::to_path_buf).unwrap_or_default())
+            .map_err(|err| err.to_string())
+    };
+    let output_dir = dir(output)?;
+    if dir(harness)? == output_dir {
+        return Err(
+            "the harness is in package main, so it must be in another directory than the output"
+                .to_string(),
+        );
+    }
+    for module_dir in output_dir.ancestors() {
+        let Ok(go_mod) = fs::read_to_string(module_dir.join("go.mod")) else {
+            continue;
+        };
+        let Some(module) = go_mod.lines().find_map(|line| {
+            line.trim()
+                .strip_prefix("module ")
+                .map(|module| module.trim().trim_matches('"'))
+        }) else {
+            return Err(format!(
+                "missing module directive: {}",
+                module_dir.join("go.mod").to_string_lossy()
+            ));
+        };
+        let relative = output_dir
+            .strip_prefix(module_dir)
+            .expect("should be an ancestor")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>();
+        return Ok(std::iter::once(module.into())
+            .chain(relative)
+            .collect::<Vec<_>>()
+            .join("/"));
+    }
+    Err("the output isn't in a Go module: run `go mod init` first".to_string())
+}
+
+/// Resolves the `.` and `..` components of an absolute path, without
+/// following symlinks.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
             }
-            match fs::write(outpath, w.into_inner()) {
-                Ok(_) => Ok(ExitCode::SUCCESS),
-                Err(_) => {
-                    eprintln!("failed to create file: {outpath}");
-                    Ok(ExitCode::FAILURE)
-                }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Writes the file, returning false after reporting the error if it can't
+/// be written.
+///
+/// The contents are written to a temporary file next to it, which then
+/// replaces the file, so tools reading it never see a partial file.
+fn write_file(path: &Path, contents: &[u8]) -> bool {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    let written = fs::write(&temp, contents).is_ok() && fs::rename(&temp, path).is_ok();
+    if !written {
+        let _ = fs::remove_file(&temp);
+        eprintln!("failed to create file: {}", path.to_string_lossy());
+    }
+    written
+}
+
+/// The header of the generated files, recording how to reproduce them, and
+/// optionally followed by a `//go:generate` directive reproducing them.
+fn header(world: &str, sources: &[&String], fingerprint: &str, go_generate: bool) -> String {
+    // `--check`, `--dry-run`, `--stats` and `--watch` don't change the output,
+    // so the files they compare must have the same header as when they were
+    // written
+    let args = std::env::args()
+        .skip(1)
+        .filter(|arg| !is_output_neutral_arg(arg))
+        .map(|arg| shell_quote(&arg))
+        .collect::<Vec<_>>();
+    let mut header = format!(
+        "// Code generated by arcjet-gravity; DO NOT EDIT.\n//\n// Version: {}\n// World: {world}\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    for source in sources {
+        header.push_str(&format!("// Source: {source}\n"));
+    }
+    header.push_str(&format!(
+        "// Command: gravity {}\n// Fingerprint: {fingerprint}\n\n",
+        args.join(" ")
+    ));
+    if go_generate {
+        // `go generate` runs the command once, so it mustn't watch the files
+        let args = std::env::args()
+            .skip(1)
+            .filter(|arg| !is_output_neutral_arg(arg))
+            .map(|arg| go_generate_quote(&arg))
+            .collect::<Vec<_>>();
+        header.push_str(&format!("//go:generate gravity {}\n\n", args.join(" ")));
+    }
+    header
+}
+
+/// Returns true if the argument doesn't change the generated files, i.e. is
+/// `--check`, `--dry-run`, `--watch` or `--stats`, with or without its format.
+fn is_output_neutral_arg(arg: &str) -> bool {
+    ["--check", "--dry-run", "--watch", "--stats"].contains(&arg) || arg.starts_with("--stats=")
+}
+
+/// Quotes the argument of a `//go:generate` directive, if it needs to be,
+/// escaping the dollar signs `go generate` would otherwise expand.
+fn go_generate_quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./=:,@+%$".contains(c);
+    let arg = arg.replace('$', "$DOLLAR");
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg
+    } else {
+        format!("\"{}\"", arg.replace('\\', r"\\").replace('"', r#"\""#))
+    }
+}
+
+/// Quotes the argument for a POSIX shell, if it needs to be.
+fn shell_quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./=:,@+%".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Returns the fingerprint of the inputs of the generated files, as a hex
+/// encoded FNV-1a hash.
+///
+/// The hash is implemented here, rather than using the `Hash` trait of the
+/// standard library, so that it's stable across Rust versions.
+fn fingerprint(inputs: &[Vec<u8>]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for input in inputs {
+        // Prefixing each input with its length keeps their boundaries from
+        // being ambiguous
+        for byte in (input.len() as u64).to_le_bytes().iter().chain(input) {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{hash:016x}")
+}
+
+/// Prints the WIT decoded from a file, for the `wit` subcommand.
+fn print_wit(matches: &ArgMatches) -> ExitCode {
+    let selected_world = matches
+        .get_one::<String>("world")
+        .expect("should have a world");
+    let file = matches
+        .get_one::<String>("file")
+        .expect("should have a file");
+    let Some((_, mut bindgen, _)) = read_component(file) else {
+        return ExitCode::FAILURE;
+    };
+    let Some((world_id, _)) = bindgen
+        .resolve
+        .worlds
+        .iter()
+        .find(|(_, world)| world.name == *selected_world)
+    else {
+        eprintln!("unable to find world: {selected_world}");
+        return ExitCode::FAILURE;
+    };
+    for warning in disambiguate(&mut bindgen.resolve, &[world_id]) {
+        eprintln!("warning: {warning}");
+    }
+    print!("{}", describe_world(&bindgen.resolve, world_id));
+    ExitCode::SUCCESS
+}
+
+/// Lists the WIT features and whether they're supported, or only those used
+/// by the selected world of the file, failing if any isn't supported.
+fn print_features(matches: &ArgMatches) -> ExitCode {
+    let Some(file) = matches.get_one::<String>("file") else {
+        print!("{}", features_table(&registry()));
+        return ExitCode::SUCCESS;
+    };
+    let selected_world = matches
+        .get_one::<String>("world")
+        .expect("should have a world");
+    let Some(resolve) = read_resolve(file) else {
+        return ExitCode::FAILURE;
+    };
+    let Some((world_id, _)) = resolve
+        .worlds
+        .iter()
+        .find(|(_, world)| world.name == *selected_world)
+    else {
+        eprintln!("unable to find world: {selected_world}");
+        return ExitCode::FAILURE;
+    };
+    let features = world_features(&resolve, world_id);
+    print!("{}", features_table(&features));
+    let unsupported = features
+        .iter()
+        .filter(|feature| feature.support == Support::Unsupported)
+        .map(|feature| feature.name)
+        .collect::<Vec<_>>();
+    if !unsupported.is_empty() {
+        eprintln!(
+            "unsupported features in world {selected_world}: {}",
+            unsupported.join(", ")
+        );
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Prints the changes between the selected world of two files, failing if
+/// any of them breaks the Go API of the bindings.
+fn print_diff(matches: &ArgMatches) -> ExitCode {
+    let selected_world = matches
+        .get_one::<String>("world")
+        .expect("should have a world");
+    let mut worlds = Vec::new();
+    for file in ["old", "new"] {
+        let file = matches.get_one::<String>(file).expect("should have a file");
+        let Some(resolve) = read_resolve(file) else {
+            return ExitCode::FAILURE;
+        };
+        let Some((world_id, _)) = resolve
+            .worlds
+            .iter()
+            .find(|(_, world)| world.name == *selected_world)
+        else {
+            eprintln!("unable to find world in {file}: {selected_world}");
+            return ExitCode::FAILURE;
+        };
+        worlds.push((resolve, world_id));
+    }
+    let [(old, old_world), (new, new_world)] = &worlds[..] else {
+        unreachable!("should have read both files");
+    };
+    let changes = diff_worlds(old, *old_world, new, *new_world);
+    for change in &changes {
+        println!("{change}");
+    }
+    let breaking = changes.iter().filter(|change| change.breaking).count();
+    if changes.is_empty() {
+        println!("no changes to world {selected_world}");
+    } else {
+        println!(
+            "{} changes to world {selected_world}, {breaking} breaking",
+            changes.len()
+        );
+    }
+    if breaking > 0 {
+        eprintln!("regenerating the bindings of world {selected_world} breaks their Go API");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Formats the features as a table with a row per feature, followed by the
+/// number of features of each support.
+fn features_table(features: &[Feature]) -> String {
+    let mut out = String::new();
+    let mut counts = [0; 3];
+    for feature in features {
+        let index = match feature.support {
+            Support::Supported => 0,
+            Support::Partial(_) => 1,
+            Support::Unsupported => 2,
+        };
+        counts[index] += 1;
+        out.push_str(&format!(
+            "{:12} {:24} {}\n",
+            feature.category.to_string(),
+            feature.name,
+            feature.support
+        ));
+    }
+    let [supported, partial, unsupported] = counts;
+    out.push_str(&format!(
+        "\n{supported} supported, {partial} partially supported, {unsupported} unsupported\n"
+    ));
+    out
+}
+
+/// Generates the guest bindings for the selected world.
+fn generate_guest(matches: &ArgMatches) -> ExitCode {
+    let selected_world = matches
+        .get_one::<String>("world")
+        .expect("should have a world");
+    let file = matches
+        .get_one::<String>("file")
+        .expect("should have a file");
+    let output = matches.get_one::<String>("output");
+
+    // The guest is usually generated before there is a component, so its
+    // world is read from the WIT instead
+    let Some(resolve) = read_resolve(file) else {
+        return ExitCode::FAILURE;
+    };
+    let Some((world_id, _)) = resolve
+        .worlds
+        .iter()
+        .find(|(_, world)| world.name == *selected_world)
+    else {
+        eprintln!("unable to find world: {selected_world}");
+        return ExitCode::FAILURE;
+    };
+    let tokens = match guest_bindings(&resolve, world_id) {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            eprintln!("unable to generate guest bindings: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let metadata = metadata::encode(&resolve, world_id, StringEncoding::UTF8, None)
+        .expect("world should be encodable");
+    let inputs = [env!("CARGO_PKG_VERSION").as_bytes().to_vec(), metadata];
+    let header = header(selected_world, &[file], &fingerprint(&inputs), false);
+    let generated = format_go_file(&tokens, &selected_world.replace('-', "_"), &header);
+    match output {
+        Some(outpath) => {
+            let outpath = Path::new(outpath);
+            if fs::read(outpath).ok().as_deref() != Some(generated.as_bytes())
+                && !write_file(outpath, generated.as_bytes())
+            {
+                return ExitCode::FAILURE;
             }
         }
-        None => {
-            println!("{}", w.into_inner());
-            Ok(ExitCode::SUCCESS)
+        None => println!("{generated}"),
+    }
+    ExitCode::SUCCESS
+}
+
+/// Reads the WIT of a file, which is either WIT, or a core Wasm module like
+/// [`read_component`] if it ends with `.wasm` or is `-` for stdin.
+///
+/// Returns `None`, after reporting the error, if the file can't be read.
+fn read_resolve(file: &str) -> Option<Resolve> {
+    if file == STDIN || file.ends_with(".wasm") {
+        let (_, bindgen, _) = read_component(file)?;
+        return Some(bindgen.resolve);
+    }
+    let mut resolve = Resolve::default();
+    if let Err(err) = resolve.push_path(file) {
+        eprintln!("unable to read file: {file}: {err}");
+        return None;
+    }
+    Some(resolve)
+}
+
+/// Reads a core Wasm module like [`read_wasm`], reporting an error if
+/// Wazero doesn't support the module.
+fn read_component(file: &str) -> Option<(Vec<u8>, Bindgen, Vec<u8>)> {
+    let (module, bindgen, metadata) = read_wasm(file)?;
+    if uses_memory64(&module) {
+        eprintln!("unable to read file: {file}: 64-bit memories aren't supported by Wazero");
+        return None;
+    }
+    Some((module, bindgen, metadata))
+}
+
+/// Reads a core Wasm module and decodes its WIT metadata, which is also
+/// returned as is. The module is read from stdin if the file is `-`.
+///
+/// Returns `None`, after reporting the error, if the file can't be read.
+fn read_wasm(file: &str) -> Option<(Vec<u8>, Bindgen, Vec<u8>)> {
+    let wasm = if file == STDIN {
+        let mut wasm = Vec::new();
+        std::io::stdin().read_to_end(&mut wasm).map(|_| wasm)
+    } else {
+        fs::read(file)
+    };
+    let wasm = match wasm {
+        Ok(wasm) => wasm,
+        Err(_) => {
+            eprintln!("unable to read file: {file}");
+            return None;
         }
+    };
+
+    let metadata = component_metadata(&wasm);
+    let (module, bindgen) = match metadata::decode(&wasm) {
+        // If the Wasm doesn't have a custom section, None will be returned so we need to use the original
+        Ok((module, bindgen)) => (module.unwrap_or(wasm), bindgen),
+        Err(err) => {
+            eprintln!("unable to read file: {file}: {err:#}");
+            return None;
+        }
+    };
+    Some((module, bindgen, metadata))
+}
+
+/// The world a component was built for.
+///
+/// Decoding names the component's world `root`, so the original world is
+/// preferred when the metadata contains exactly one other world, to give the
+/// linked component meaningful Go identifiers.
+fn named_world(bindgen: &Bindgen) -> WorldId {
+    let mut named = bindgen
+        .resolve
+        .worlds
+        .iter()
+        .filter(|(id, _)| *id != bindgen.world);
+    match (named.next(), named.next()) {
+        (Some((id, _)), None)
+            if bindgen.resolve.worlds[bindgen.world].name == PRIMARY_WORLD_NAME =>
+        {
+            id
+        }
+        _ => bindgen.world,
     }
 }
+
+/// Formats the tokens as a Go file in the given package, preceded by the
+/// generated code header.
+fn format_go_file(tokens: &genco::Tokens<Go>, package: &str, header: &str) -> String {
+    let mut w = genco::fmt::FmtWriter::new(header.to_string());
+    let fmt = genco::fmt::Config::from_lang::<Go>().with_indentation(genco::fmt::Indentation::Tab);
+    let config = go::Config::default().with_package(package);
+
+    tokens
+        .format_file(&mut w.as_formatter(&fmt), &config)
+        .unwrap();
+    w.into_inner()
+}
@@ -1,4 +1,15 @@
+use std::time::Duration;
+
 #[test]
 fn cli_tests() {
     trycmd::TestCases::new().case("tests/cmd/*.toml");
 }
+
+/// `--watch` runs until interrupted, so these cases are stopped after a
+/// timeout, long enough for the bindings to be generated once.
+#[test]
+fn watch_tests() {
+    trycmd::TestCases::new()
+        .timeout(Duration::from_secs(3))
+        .case("tests/cmd/*.md");
+}
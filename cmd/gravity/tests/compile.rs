@@ -0,0 +1,134 @@
+//! Checks that the generated Go is formatted, vetted and compiles.
+//!
+//! The snapshots of the CLI tests hold the bindings generated for each
+//! fixture, so every snapshot which is a Go file is copied into a package
+//! of a scratch module, which is then checked with `gofmt -l`, `go vet` and
+//! `go build`. These catch the unused imports, redeclared variables and
+//! syntax errors the snapshots can't.
+//!
+//! The test needs a Go toolchain, so it's ignored by default. Run it with
+//! `cargo test --test compile -- --ignored`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The first line of every generated Go file.
+const GENERATED: &str = "// Code generated by arcjet-gravity; DO NOT EDIT.";
+
+/// Returns true if the `go` command is available.
+fn has_go() -> bool {
+    Command::new("go")
+        .arg("version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Runs the command in `dir`, returning its output on failure.
+fn run(dir: &Path, command: &mut Command) -> Result<String, String> {
+    let output = command
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("failed to run {command:?}: {e}"))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(format!("{command:?} failed:\n{stdout}{stderr}"));
+    }
+    Ok(stdout)
+}
+
+/// Copies each generated Go snapshot into a package of the module at
+/// `module`, returning the names of the packages.
+///
+/// The files embedded by the bindings are only needed to compile them, so
+/// these are left empty.
+fn write_packages(module: &Path, snapshots: &Path, guest: bool) -> Vec<String> {
+    let mut packages = Vec::new();
+    let mut entries = fs::read_dir(snapshots)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "stdout"))
+        .collect::<Vec<_>>();
+    entries.sort();
+    for path in entries {
+        let source = fs::read_to_string(&path).unwrap();
+        // Guest bindings only compile for Wasm, so these are checked apart
+        if !source.starts_with(GENERATED) || source.contains("//go:wasmimport") != guest {
+            continue;
+        }
+        let name = path
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .replace('-', "_");
+        let dir = module.join(&name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("bindings.go"), &source).unwrap();
+        for line in source.lines() {
+            if let Some(file) = line.strip_prefix("//go:embed ") {
+                fs::write(dir.join(file.trim()), b"").unwrap();
+            }
+        }
+        packages.push(name);
+    }
+    packages
+}
+
+/// Checks the packages of the module at `module`, built for `goos` and
+/// `goarch` if given.
+fn check_module(module: &Path, target: Option<(&str, &str)>) -> Result<(), String> {
+    let go = |args: &[&str]| {
+        let mut command = Command::new("go");
+        command.args(args);
+        if let Some((goos, goarch)) = target {
+            command.env("GOOS", goos).env("GOARCH", goarch);
+        }
+        command
+    };
+    let unformatted = run(module, Command::new("gofmt").args(["-l", "."]))?;
+    if !unformatted.trim().is_empty() {
+        return Err(format!("the generated Go isn't formatted:\n{unformatted}"));
+    }
+    // The dependencies depend on the options, e.g. `--instrument otel`, so
+    // these are resolved from the imports
+    run(module, &mut go(&["mod", "tidy"]))?;
+    run(module, &mut go(&["vet", "./..."]))?;
+    run(module, &mut go(&["build", "./..."]))?;
+    Ok(())
+}
+
+/// Creates an empty module named `name` in the scratch directory.
+fn scratch_module(name: &str) -> PathBuf {
+    let module = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&module);
+    fs::create_dir_all(&module).unwrap();
+    fs::write(
+        module.join("go.mod"),
+        format!("module example.com/{name}\n\ngo 1.24\n"),
+    )
+    .unwrap();
+    module
+}
+
+#[test]
+#[ignore = "needs a Go toolchain"]
+fn test_generated_go_compiles() {
+    assert!(has_go(), "the go command isn't available");
+    let snapshots = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/cmd");
+
+    let host = scratch_module("gravity-host");
+    assert!(!write_packages(&host, &snapshots, false).is_empty());
+    if let Err(e) = check_module(&host, None) {
+        panic!("{e}");
+    }
+
+    let guest = scratch_module("gravity-guest");
+    if !write_packages(&guest, &snapshots, true).is_empty()
+        && let Err(e) = check_module(&guest, Some(("wasip1", "wasm")))
+    {
+        panic!("{e}");
+    }
+}
@@ -0,0 +1,173 @@
+//! Coverage of the WIT types by the generated bindings.
+//!
+//! Every primitive is used bare and in each of the compound types, as the
+//! parameter or result of an exported or imported function, and a world is
+//! generated for each of these. The outcome of each is compared with
+//! `tests/matrix.txt`, so changes in coverage are tracked: run the test with
+//! `TRYCMD=overwrite` to update it, like the CLI snapshots.
+
+use std::{
+    fmt::Write,
+    panic::{self, AssertUnwindSafe},
+};
+
+use arcjet_gravity::{
+    codegen::{Bindings, disambiguate},
+    compat::wit_parser::{Resolve, SizeAlign},
+};
+
+/// The primitive WIT types.
+const PRIMITIVES: &[&str] = &[
+    "bool", "u8", "u16", "u32", "u64", "s8", "s16", "s32", "s64", "f32", "f64", "char", "string",
+];
+
+/// The types wrapping a primitive, by their column in the matrix.
+const SHAPES: &[Shape] = &[
+    Shape::Bare,
+    Shape::Option,
+    Shape::List,
+    Shape::Result,
+    Shape::RecordField,
+    Shape::VariantPayload,
+];
+
+/// Whether the function is exported or imported by the world.
+const DIRECTIONS: &[&str] = &["export", "import"];
+
+/// Whether the type is the parameter or the result of the function.
+const POSITIONS: &[&str] = &["param", "result"];
+
+#[derive(Clone, Copy)]
+enum Shape {
+    Bare,
+    Option,
+    List,
+    Result,
+    RecordField,
+    VariantPayload,
+}
+
+impl Shape {
+    fn name(self) -> &'static str {
+        match self {
+            Shape::Bare => "bare",
+            Shape::Option => "option",
+            Shape::List => "list",
+            Shape::Result => "result",
+            Shape::RecordField => "record",
+            Shape::VariantPayload => "variant",
+        }
+    }
+
+    /// The definitions of the types used by the shape, and the type of the
+    /// function.
+    fn wit(self, primitive: &str) -> (String, String) {
+        match self {
+            Shape::Bare => (String::new(), primitive.to_string()),
+            Shape::Option => (String::new(), format!("option<{primitive}>")),
+            Shape::List => (String::new(), format!("list<{primitive}>")),
+            Shape::Result => (String::new(), format!("result<{primitive}, string>")),
+            Shape::RecordField => (
+                format!("record wrapper {{ field: {primitive} }}"),
+                "wrapper".to_string(),
+            ),
+            Shape::VariantPayload => (
+                format!("variant wrapper {{ payload({primitive}), empty }}"),
+                "wrapper".to_string(),
+            ),
+        }
+    }
+}
+
+/// The WIT package of a world using the type.
+fn world(direction: &str, position: &str, typedefs: &str, typ: &str) -> String {
+    let func = match position {
+        "param" => format!("f: func(val: {typ});"),
+        _ => format!("f: func() -> {typ};"),
+    };
+    match direction {
+        "export" => {
+            format!("package test:matrix;\n\nworld matrix {{\n  {typedefs}\n  export {func}\n}}\n")
+        }
+        _ => format!(
+            "package test:matrix;\n\ninterface host {{\n  {typedefs}\n  {func}\n}}\n\nworld matrix {{\n  import host;\n}}\n"
+        ),
+    }
+}
+
+/// Returns true if the bindings are generated for the world without
+/// panicking.
+fn generates(wit: &str) -> bool {
+    let mut resolve = Resolve::default();
+    let Ok(package) = resolve.push_str("matrix.wit", wit) else {
+        return false;
+    };
+    let Ok(world) = resolve.select_world(&[package], Some("matrix")) else {
+        return false;
+    };
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        disambiguate(&mut resolve, &[world]);
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+        let mut bindings = Bindings::new(&resolve, &resolve.worlds[world], &sizes);
+        bindings.generate();
+    }))
+    .is_ok()
+}
+
+/// Renders the coverage as a table per direction and position, with a row
+/// per primitive and a column per shape.
+fn coverage() -> String {
+    let mut out = String::new();
+    let mut covered = 0;
+    let mut total = 0;
+    for direction in DIRECTIONS {
+        for position in POSITIONS {
+            writeln!(out, "{direction} {position}").unwrap();
+            write!(out, "{:8}", "").unwrap();
+            for shape in SHAPES {
+                write!(out, " {:8}", shape.name()).unwrap();
+            }
+            writeln!(out).unwrap();
+            for primitive in PRIMITIVES {
+                write!(out, "{primitive:8}").unwrap();
+                for shape in SHAPES {
+                    let (typedefs, typ) = shape.wit(primitive);
+                    let ok = generates(&world(direction, position, &typedefs, &typ));
+                    covered += usize::from(ok);
+                    total += 1;
+                    write!(out, " {:8}", if ok { "ok" } else { "-" }).unwrap();
+                }
+                writeln!(out).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+    }
+    writeln!(out, "covered: {covered}/{total}").unwrap();
+    // The columns are padded, so trailing whitespace is trimmed
+    out.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n"
+}
+
+#[test]
+fn test_matrix() {
+    // The panics of unsupported types are expected, so they aren't reported
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let actual = coverage();
+    panic::set_hook(hook);
+
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/matrix.txt");
+    if std::env::var("TRYCMD").as_deref() == Ok("overwrite") {
+        std::fs::write(path, &actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(path).unwrap_or_default();
+    assert!(
+        actual == expected,
+        "the coverage of WIT types changed, run with TRYCMD=overwrite to update tests/matrix.txt:\n{actual}"
+    );
+}
@@ -0,0 +1,182 @@
+package counters
+
+import (
+	"testing"
+)
+
+func TestGuestResource(t *testing.T) {
+	fac, err := NewCountersFactory(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	c := ins.Types().NewCounter(t.Context(), 40)
+	defer c.Drop(t.Context())
+
+	ins.Types().CounterIncrement(t.Context(), c.Borrow(), 2)
+
+	const want = 42
+	if got := ins.Types().CounterGet(t.Context(), c.Borrow()); got != want {
+		t.Errorf("wanted: %d, but got: %d", want, got)
+	}
+}
+
+func TestBorrowedGuestResources(t *testing.T) {
+	fac, err := NewCountersFactory(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	a := ins.Types().NewCounter(t.Context(), 1)
+	defer a.Drop(t.Context())
+	b := ins.Types().NewCounter(t.Context(), 2)
+	defer b.Drop(t.Context())
+
+	const want = 3
+	if got := ins.Types().Total(t.Context(), a.Borrow(), b.Borrow()); got != want {
+		t.Errorf("wanted: %d, but got: %d", want, got)
+	}
+}
+
+func TestOwnedGuestResource(t *testing.T) {
+	fac, err := NewCountersFactory(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	c := ins.Types().NewCounter(t.Context(), 5)
+	reset := ins.Types().CounterReset(t.Context(), c)
+	defer reset.Drop(t.Context())
+
+	if got := ins.Types().CounterGet(t.Context(), reset.Borrow()); got != 0 {
+		t.Errorf("wanted the counter to be reset, but got: %d", got)
+	}
+	if rep := c.Borrow().rep(); rep != 0 {
+		t.Errorf("wanted the guest to drop the consumed counter, but got: %d", rep)
+	}
+}
+
+func TestDropGuestResource(t *testing.T) {
+	fac, err := NewCountersFactory(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	c := ins.Types().NewCounter(t.Context(), 1)
+	if err := c.Drop(t.Context()); err != nil {
+		t.Fatal(err)
+	}
+	if entry := counterTable.get(c.handle); entry != nil {
+		t.Errorf("wanted the handle to be removed from the table, but got: %v", entry)
+	}
+	// Dropping the counter again does nothing
+	if err := c.Drop(t.Context()); err != nil {
+		t.Fatal(err)
+	}
+}
+
+func TestResourceHandles(t *testing.T) {
+	fac, err := NewCountersFactory(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	a, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer a.Close(t.Context())
+	b, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer b.Close(t.Context())
+
+	first := a.Types().NewCounter(t.Context(), 1)
+	second := a.Types().NewCounter(t.Context(), 2)
+	other := b.Types().NewCounter(t.Context(), 3)
+	defer other.Drop(t.Context())
+	if got := a.Resources().CounterHandles(); len(got) != 2 || got[0] != first || got[1] != second {
+		t.Errorf("wanted the handles of the instance, but got: %v", got)
+	}
+
+	if err := second.Drop(t.Context()); err != nil {
+		t.Fatal(err)
+	}
+	if got := a.Resources().CounterHandles(); len(got) != 1 || got[0] != first {
+		t.Errorf("wanted the dropped handle to be left out, but got: %v", got)
+	}
+
+	if err := a.Close(t.Context()); err != nil {
+		t.Fatal(err)
+	}
+	if got := a.Resources().CounterHandles(); len(got) != 0 {
+		t.Errorf("wanted no handles once the instance is closed, but got: %v", got)
+	}
+	if got := b.Resources().CounterHandles(); len(got) != 1 || got[0] != other {
+		t.Errorf("wanted the handles of the other instance to be kept, but got: %v", got)
+	}
+}
+
+func TestStaleResourceHandle(t *testing.T) {
+	fac, err := NewCountersFactory(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	a, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer a.Close(t.Context())
+	b, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer b.Close(t.Context())
+
+	c := a.Types().NewCounter(t.Context(), 1)
+	defer c.Drop(t.Context())
+
+	defer func() {
+		err, ok := recover().(*StaleHandleError)
+		if !ok {
+			t.Fatalf("wanted a StaleHandleError, but got: %v", err)
+		}
+		if err.Resource != "counter" || err.Handle != c.handle {
+			t.Errorf("wanted the error to name the handle, but got: %v", err)
+		}
+	}()
+	// The counter was created by another instance
+	b.Types().CounterGet(t.Context(), c.Borrow())
+}
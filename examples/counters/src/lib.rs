@@ -0,0 +1,49 @@
+// The exports of resources are named like `[dtor]counter`, which native
+// linkers can't export, so this is only built for Wasm
+#![cfg(target_family = "wasm")]
+
+use std::cell::Cell;
+
+use exports::arcjet::counters::types::{Counter, CounterBorrow, Guest, GuestCounter};
+
+wit_bindgen::generate!({
+    world: "counters",
+});
+
+struct CountersWorld;
+
+export!(CountersWorld);
+
+impl Guest for CountersWorld {
+    type Counter = GuestCounterImpl;
+
+    fn total(c: CounterBorrow<'_>, other: CounterBorrow<'_>) -> u32 {
+        c.get::<GuestCounterImpl>().get() + other.get::<GuestCounterImpl>().get()
+    }
+}
+
+struct GuestCounterImpl {
+    value: Cell<u32>,
+}
+
+impl GuestCounter for GuestCounterImpl {
+    fn new(start: u32) -> Self {
+        Self {
+            value: Cell::new(start),
+        }
+    }
+
+    fn get(&self) -> u32 {
+        self.value.get()
+    }
+
+    fn increment(&self, by: u32) {
+        self.value.set(self.value.get() + by);
+    }
+
+    fn reset(c: Counter) -> Counter {
+        // Consumes the counter, which drops it in the guest
+        let _ = c.get::<GuestCounterImpl>().get();
+        Counter::new(GuestCounterImpl::new(0))
+    }
+}
@@ -1,9 +1,22 @@
 package examples
 
 //go:generate cargo build -p example-basic --target wasm32-unknown-unknown --release
+//go:generate cargo build -p example-counters --target wasm32-unknown-unknown --release
 //go:generate cargo build -p example-iface-method-returns-string --target wasm32-unknown-unknown --release
 //go:generate cargo build -p example-instructions --target wasm32-unknown-unknown --release
+//go:generate cargo build -p example-linked-consumer --target wasm32-unknown-unknown --release
+//go:generate cargo build -p example-linked-provider --target wasm32-unknown-unknown --release
+//go:generate cargo build -p example-packages --target wasm32-unknown-unknown --release
+//go:generate cargo build -p example-records --target wasm32-unknown-unknown --release
+//go:generate cargo build -p example-resources --target wasm32-unknown-unknown --release
+//go:generate cargo build -p example-short-ints --target wasm32-unknown-unknown --release
 
 //go:generate cargo run --bin gravity -- --world basic --output ./basic/basic.go ../target/wasm32-unknown-unknown/release/example_basic.wasm
+//go:generate cargo run --bin gravity -- --world counters --output ./counters/counters.go ../target/wasm32-unknown-unknown/release/example_counters.wasm
 //go:generate cargo run --bin gravity -- --world example --output ./iface-method-returns-string/example.go ../target/wasm32-unknown-unknown/release/example_iface_method_returns_string.wasm
 //go:generate cargo run --bin gravity -- --world instructions --output ./instructions/bindings.go ../target/wasm32-unknown-unknown/release/example_instructions.wasm
+//go:generate cargo run --bin gravity -- --world consumer --output ./linked-consumer/consumer.go ../target/wasm32-unknown-unknown/release/example_linked_consumer.wasm ../target/wasm32-unknown-unknown/release/example_linked_provider.wasm
+//go:generate cargo run --bin gravity -- --world packages --output ./packages/packages.go ../target/wasm32-unknown-unknown/release/example_packages.wasm
+//go:generate cargo run --bin gravity -- --world records --output ./records/records.go ../target/wasm32-unknown-unknown/release/example_records.wasm
+//go:generate cargo run --bin gravity -- --world resources --output ./resources/resources.go ../target/wasm32-unknown-unknown/release/example_resources.wasm
+//go:generate cargo run --bin gravity -- --world short-ints --output ./short-ints/short_ints.go ../target/wasm32-unknown-unknown/release/example_short_ints.wasm
@@ -1,11 +1,14 @@
 package instructions
 
 import (
+	"bytes"
 	"fmt"
 	"iter"
 	"math"
 	"math/rand/v2"
+	"slices"
 	"testing"
+	"unicode"
 )
 
 func inclusive[Num interface {
@@ -171,6 +174,73 @@ func Test_U32Roundtrip(t *testing.T) {
 	}
 }
 
+func Test_S64Roundtrip(t *testing.T) {
+	fac, err := NewInstructionsFactory(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	for _, expected := range []int64{math.MinInt64, math.MinInt32 - 1, -1, 0, 1, math.MaxInt32 + 1, math.MaxInt64} {
+		actual := ins.S64Roundtrip(t.Context(), expected)
+		if actual != expected {
+			t.Errorf("expected: %d, but got: %d", expected, actual)
+		}
+	}
+}
+
+func Test_U64Roundtrip(t *testing.T) {
+	fac, err := NewInstructionsFactory(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	for _, expected := range []uint64{0, 1, math.MaxUint32, math.MaxUint32 + 1, math.MaxUint64} {
+		actual := ins.U64Roundtrip(t.Context(), expected)
+		if actual != expected {
+			t.Errorf("expected: %d, but got: %d", expected, actual)
+		}
+	}
+}
+
+func Test_SignedRoundtrip(t *testing.T) {
+	fac, err := NewInstructionsFactory(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	for _, expected := range []Signed{
+		{A: -1, B: -1, C: -1, D: -1},
+		{A: math.MinInt8, B: math.MinInt16, C: math.MinInt32, D: math.MinInt64},
+		{A: math.MaxInt8, B: math.MaxInt16, C: math.MaxInt32, D: math.MaxInt64},
+	} {
+		actual := ins.SignedRoundtrip(t.Context(), expected)
+		if actual != expected {
+			t.Errorf("expected: %+v, but got: %+v", expected, actual)
+		}
+	}
+}
+
 func Test_F32Roundtrip(t *testing.T) {
 	fac, err := NewInstructionsFactory(t.Context())
 	if err != nil {
@@ -222,3 +292,86 @@ func Test_F64Roundtrip(t *testing.T) {
 		})
 	}
 }
+
+func Test_CharRoundtrip(t *testing.T) {
+	fac, err := NewInstructionsFactory(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	for _, expected := range []rune{0, 'a', 'é', '\uD7FF', '\uE000', '😀', unicode.MaxRune} {
+		if actual := ins.CharRoundtrip(t.Context(), expected); actual != expected {
+			t.Errorf("expected: %q, but got: %q", expected, actual)
+		}
+	}
+}
+
+func Test_BytesRoundtrip(t *testing.T) {
+	fac, err := NewInstructionsFactory(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	for _, expected := range [][]byte{{}, {0}, bytes.Repeat([]byte{0xde, 0xad, 0xbe, 0xef}, 1<<16)} {
+		if actual := ins.BytesRoundtrip(t.Context(), expected); !bytes.Equal(actual, expected) {
+			t.Errorf("expected %d bytes to roundtrip, but got %d", len(expected), len(actual))
+		}
+	}
+}
+
+func Test_U32ListRoundtrip(t *testing.T) {
+	fac, err := NewInstructionsFactory(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	expected := slices.Collect(inclusiveStep(uint32(0), math.MaxUint32, 1<<20))
+	if actual := ins.U32ListRoundtrip(t.Context(), expected); !slices.Equal(actual, expected) {
+		t.Errorf("expected: %v, but got: %v", expected, actual)
+	}
+}
+
+func Test_F64ListRoundtrip(t *testing.T) {
+	fac, err := NewInstructionsFactory(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	seed := 123456
+	rng := rand.New(rand.NewPCG(uint64(seed), uint64(seed)))
+	expected := make([]float64, 1000)
+	for i := range expected {
+		expected[i] = rng.Float64()
+	}
+	if actual := ins.F64ListRoundtrip(t.Context(), expected); !slices.Equal(actual, expected) {
+		t.Errorf("expected: %v, but got: %v", expected, actual)
+	}
+}
@@ -31,6 +31,17 @@ impl Guest for InstructionsWorld {
         assert!((u32::MIN..=u32::MAX).contains(&val));
         val
     }
+    fn s64_roundtrip(val: i64) -> i64 {
+        assert!((i64::MIN..=i64::MAX).contains(&val));
+        val
+    }
+    fn u64_roundtrip(val: u64) -> u64 {
+        assert!((u64::MIN..=u64::MAX).contains(&val));
+        val
+    }
+    fn signed_roundtrip(val: Signed) -> Signed {
+        val
+    }
     fn f32_roundtrip(val: f32) -> f32 {
         assert!((f32::MIN..=f32::MAX).contains(&val));
         val
@@ -39,4 +50,16 @@ impl Guest for InstructionsWorld {
         assert!((f64::MIN..=f64::MAX).contains(&val));
         val
     }
+    fn char_roundtrip(val: char) -> char {
+        val
+    }
+    fn bytes_roundtrip(val: Vec<u8>) -> Vec<u8> {
+        val
+    }
+    fn u32_list_roundtrip(val: Vec<u32>) -> Vec<u32> {
+        val
+    }
+    fn f64_list_roundtrip(val: Vec<f64>) -> Vec<f64> {
+        val
+    }
 }
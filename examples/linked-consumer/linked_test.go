@@ -0,0 +1,43 @@
+package consumer
+
+import (
+	"context"
+	"testing"
+)
+
+type recordingLogger struct {
+	messages []string
+}
+
+func (l *recordingLogger) Log(ctx context.Context, msg string) {
+	l.messages = append(l.messages, msg)
+}
+
+func TestLinked(t *testing.T) {
+	consumerLogger := &recordingLogger{}
+	providerLogger := &recordingLogger{}
+	fac, err := NewConsumerFactory(t.Context(), consumerLogger, providerLogger)
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	message := ins.Hello(t.Context(), "world")
+
+	const want = "Hello, world!"
+	if message != want {
+		t.Errorf("wanted: %s, but got: %s", want, message)
+	}
+	if len(consumerLogger.messages) != 1 {
+		t.Errorf("wanted the consumer to log once, but got: %v", consumerLogger.messages)
+	}
+	if len(providerLogger.messages) != 1 {
+		t.Errorf("wanted the provider to log once, but got: %v", providerLogger.messages)
+	}
+}
@@ -0,0 +1,18 @@
+use arcjet::linked::{greeter, logger};
+
+wit_bindgen::generate!({
+    world: "consumer",
+    path: "../linked-provider/wit",
+});
+
+struct ConsumerWorld;
+
+export!(ConsumerWorld);
+
+impl Guest for ConsumerWorld {
+    fn hello(name: String) -> String {
+        logger::log("calling greeter");
+
+        greeter::greet(&name)
+    }
+}
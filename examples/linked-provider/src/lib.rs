@@ -0,0 +1,22 @@
+// Exported interface functions have names like `arcjet:linked/greeter#greet`,
+// which native linkers reject, so this component only builds for Wasm.
+#![cfg(target_family = "wasm")]
+
+use arcjet::linked::logger;
+use exports::arcjet::linked::greeter;
+
+wit_bindgen::generate!({
+    world: "provider",
+});
+
+struct ProviderWorld;
+
+export!(ProviderWorld);
+
+impl greeter::Guest for ProviderWorld {
+    fn greet(name: String) -> String {
+        logger::log(&format!("greeting {name}"));
+
+        format!("Hello, {name}!")
+    }
+}
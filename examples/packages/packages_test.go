@@ -0,0 +1,40 @@
+package packages
+
+import (
+	"context"
+	"testing"
+)
+
+type OtherGeoTypes struct{}
+
+type AcmeGeoTypes struct{}
+
+type Maps struct{}
+
+func (Maps) Locate(ctx context.Context, name string) Point {
+	return Point{Lat: int32(-len(name)), Lon: 3}
+}
+
+type Shapes struct{}
+
+func (Shapes) Area(ctx context.Context, corner AcmeGeoTypesPoint) uint32 {
+	return corner.X * corner.Y
+}
+
+func TestMeasure(t *testing.T) {
+	fac, err := NewPackagesFactory(t.Context(), OtherGeoTypes{}, Maps{}, AcmeGeoTypes{}, Shapes{})
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	if got := ins.Measure(t.Context(), "london"); got != 18 {
+		t.Errorf("wanted: 18, but got: %d", got)
+	}
+}
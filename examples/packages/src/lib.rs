@@ -0,0 +1,21 @@
+use acme::geo::{shapes, types::Point};
+use other::geo::maps;
+
+wit_bindgen::generate!({
+    world: "packages",
+    generate_all,
+});
+
+struct PackagesWorld;
+
+export!(PackagesWorld);
+
+impl Guest for PackagesWorld {
+    fn measure(name: String) -> u32 {
+        let location = maps::locate(&name);
+        shapes::area(Point {
+            x: location.lat.unsigned_abs(),
+            y: location.lon.unsigned_abs(),
+        })
+    }
+}
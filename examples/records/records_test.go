@@ -0,0 +1,83 @@
+package records
+
+import (
+	"strings"
+	"testing"
+)
+
+type Types struct{}
+
+func TestSumPoints(t *testing.T) {
+	fac, err := NewRecordsFactory(t.Context(), Types{})
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	points := make([]Point, 1000)
+	var want uint32
+	for i := range points {
+		points[i] = Point{X: uint32(i), Y: uint32(2 * i), Visible: i%2 == 0}
+		if points[i].Visible {
+			want += points[i].X + points[i].Y
+		}
+	}
+
+	if got := ins.SumPoints(t.Context(), points); got != want {
+		t.Errorf("wanted: %d, but got: %d", want, got)
+	}
+}
+
+func TestTotalLength(t *testing.T) {
+	fac, err := NewRecordsFactory(t.Context(), Types{})
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	names := []string{"", "a", "hello", strings.Repeat("x", 1000)}
+
+	const want = 1006
+	if got := ins.TotalLength(t.Context(), names); got != want {
+		t.Errorf("wanted: %d, but got: %d", want, got)
+	}
+}
+
+func TestOutline(t *testing.T) {
+	fac, err := NewRecordsFactory(t.Context(), Types{})
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	hidden := Point{X: 1, Y: 2, Visible: false}
+	dot := Point{X: 3, Y: 4, Visible: true}
+	if got := ins.Outline(t.Context(), []Point{hidden}); got != NewShapeEmpty() {
+		t.Errorf("wanted: empty, but got: %v", got)
+	}
+	if got := ins.Outline(t.Context(), []Point{hidden, dot}); got != NewShapeDot(dot) {
+		t.Errorf("wanted: a dot, but got: %v", got)
+	}
+	got, ok := ins.Outline(t.Context(), []Point{dot, hidden, dot}).(ShapePath)
+	if !ok || len(got.Value) != 2 || got.Value[0] != dot || got.Value[1] != dot {
+		t.Errorf("wanted: a path of two dots, but got: %v", got)
+	}
+}
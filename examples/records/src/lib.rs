@@ -0,0 +1,31 @@
+wit_bindgen::generate!({
+    world: "records",
+});
+
+struct RecordsWorld;
+
+export!(RecordsWorld);
+
+impl Guest for RecordsWorld {
+    fn sum_points(points: Vec<Point>) -> u32 {
+        points
+            .iter()
+            .filter(|point| point.visible)
+            .map(|point| point.x + point.y)
+            .sum()
+    }
+    fn total_length(names: Vec<String>) -> u32 {
+        names.iter().map(|name| name.len() as u32).sum()
+    }
+    fn outline(points: Vec<Point>) -> Shape {
+        let mut visible = points
+            .into_iter()
+            .filter(|point| point.visible)
+            .collect::<Vec<_>>();
+        match visible.len() {
+            0 => Shape::Empty,
+            1 => Shape::Dot(visible.remove(0)),
+            _ => Shape::Path(visible),
+        }
+    }
+}
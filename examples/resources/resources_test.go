@@ -0,0 +1,194 @@
+package resources
+
+import (
+	"context"
+	"testing"
+)
+
+type fooer struct {
+	x uint32
+}
+
+type Types struct{}
+
+func (Types) NewFooer(ctx context.Context, x uint32) FooerOwn {
+	return NewFooerOwn(&fooer{x: x})
+}
+
+func (Types) FooerGetX(ctx context.Context, self FooerBorrow) uint32 {
+	return self.Rep().(*fooer).x
+}
+
+func (Types) FooerSetX(ctx context.Context, self FooerBorrow, x uint32) {
+	self.Rep().(*fooer).x = x
+}
+
+func TestBorrowedHandle(t *testing.T) {
+	fac, err := NewResourcesFactory(t.Context(), Types{})
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	f := NewFooerOwn(&fooer{x: 41})
+	defer f.Close()
+
+	const want = 42
+	if got := ins.UseFooer(t.Context(), f.Borrow()); got != want {
+		t.Errorf("wanted: %d, but got: %d", want, got)
+	}
+	if got := f.Rep().(*fooer).x; got != want {
+		t.Errorf("wanted the host resource to be updated to %d, but got: %d", want, got)
+	}
+}
+
+func TestOwnedHandle(t *testing.T) {
+	fac, err := NewResourcesFactory(t.Context(), Types{})
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	f := NewFooerOwn(&fooer{x: 7})
+
+	const want = 7
+	if got := ins.ConsumeFooer(t.Context(), f); got != want {
+		t.Errorf("wanted: %d, but got: %d", want, got)
+	}
+	if rep := f.Rep(); rep != nil {
+		t.Errorf("wanted the guest to drop the resource, but got: %v", rep)
+	}
+}
+
+func TestReturnedHandle(t *testing.T) {
+	fac, err := NewResourcesFactory(t.Context(), Types{})
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	f := ins.MakeFooer(t.Context(), 3)
+	defer f.Close()
+
+	const want = 3
+	if got := f.Rep().(*fooer).x; got != want {
+		t.Errorf("wanted: %d, but got: %d", want, got)
+	}
+}
+
+type fooerFactory struct {
+	dropped *int
+}
+
+func (f fooerFactory) NewFooer(ctx context.Context, x uint32) Fooer {
+	return &droppedFooer{x: x, dropped: f.dropped}
+}
+
+type droppedFooer struct {
+	x       uint32
+	dropped *int
+}
+
+func (f *droppedFooer) GetX(ctx context.Context) uint32 {
+	return f.x
+}
+
+func (f *droppedFooer) SetX(ctx context.Context, x uint32) {
+	f.x = x
+}
+
+func (f *droppedFooer) Drop() {
+	*f.dropped++
+}
+
+func TestResourceInterfaces(t *testing.T) {
+	var dropped int
+	types := ResourcesTypesResources{Fooer: fooerFactory{dropped: &dropped}}
+	fac, err := NewResourcesFactory(t.Context(), types)
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer fac.Close(t.Context())
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer ins.Close(t.Context())
+
+	f := NewFooerOwn(types.Fooer.NewFooer(t.Context(), 41))
+	const want = 42
+	if got := ins.UseFooer(t.Context(), f.Borrow()); got != want {
+		t.Errorf("wanted: %d, but got: %d", want, got)
+	}
+	f.Close()
+	if dropped != 1 {
+		t.Errorf("wanted the resource to be dropped once, but got: %d", dropped)
+	}
+
+	if got := ins.ConsumeFooer(t.Context(), NewFooerOwn(types.Fooer.NewFooer(t.Context(), 7))); got != 7 {
+		t.Errorf("wanted: %d, but got: %d", 7, got)
+	}
+	if dropped != 2 {
+		t.Errorf("wanted the guest to drop the resource, but got: %d drops", dropped)
+	}
+}
+
+func TestHandleAcrossFactories(t *testing.T) {
+	var instances []*ResourcesInstance
+	for range 2 {
+		fac, err := NewResourcesFactory(t.Context(), Types{})
+		if err != nil {
+			t.Fatal(err)
+		}
+		defer fac.Close(t.Context())
+
+		ins, err := fac.Instantiate(t.Context())
+		if err != nil {
+			t.Fatal(err)
+		}
+		defer ins.Close(t.Context())
+		instances = append(instances, ins)
+	}
+
+	// The handle is given to the guest by the table of each factory
+	f := instances[0].MakeFooer(t.Context(), 41)
+	defer f.Close()
+
+	const want = 42
+	if got := instances[1].UseFooer(t.Context(), f.Borrow()); got != want {
+		t.Errorf("wanted: %d, but got: %d", want, got)
+	}
+}
+
+func TestDroppedResource(t *testing.T) {
+	var dropped int
+	types := ResourcesTypesResources{Fooer: fooerFactory{dropped: &dropped}}
+	f := NewFooerOwn(types.Fooer.NewFooer(t.Context(), 1))
+	f.Close()
+
+	defer func() {
+		if err := recover(); err == nil {
+			t.Error("wanted a dropped resource to panic")
+		}
+	}()
+	types.FooerGetX(t.Context(), f.Borrow())
+}
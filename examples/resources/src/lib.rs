@@ -0,0 +1,21 @@
+wit_bindgen::generate!({
+    world: "resources",
+});
+
+struct ResourcesWorld;
+
+export!(ResourcesWorld);
+
+impl Guest for ResourcesWorld {
+    fn use_fooer(f: &Fooer) -> u32 {
+        f.set_x(f.get_x() + 1);
+        f.get_x()
+    }
+    fn consume_fooer(f: Fooer) -> u32 {
+        // The handle is dropped, and so returned to the host, when this returns
+        f.get_x()
+    }
+    fn make_fooer(x: u32) -> Fooer {
+        Fooer::new(x)
+    }
+}
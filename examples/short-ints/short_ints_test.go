@@ -0,0 +1,124 @@
+package short_ints
+
+import (
+	"context"
+	"math"
+	"slices"
+	"testing"
+)
+
+// Samples calibrates the samples by adding one to their channel and
+// negating their level.
+type Samples struct{}
+
+func (Samples) Calibrate(_ context.Context, sample Sample) Sample {
+	sample.Channel++
+	sample.Level = -sample.Level
+	return sample
+}
+
+func ptr[T any](v T) *T {
+	return &v
+}
+
+func instantiate(t *testing.T) *ShortIntsInstance {
+	t.Helper()
+
+	fac, err := NewShortIntsFactory(t.Context(), Samples{})
+	if err != nil {
+		t.Fatal(err)
+	}
+	t.Cleanup(func() { fac.Close(context.Background()) })
+
+	ins, err := fac.Instantiate(t.Context())
+	if err != nil {
+		t.Fatal(err)
+	}
+	t.Cleanup(func() { ins.Close(context.Background()) })
+	return ins
+}
+
+func equal(a, b Sample) bool {
+	if a.Channel != b.Channel || a.Level != b.Level {
+		return false
+	}
+	if a.Offset == nil || b.Offset == nil {
+		return a.Offset == b.Offset
+	}
+	return *a.Offset == *b.Offset
+}
+
+// The samples cover the bounds of both 16-bit types, with and without
+// offsets.
+var samples = []Sample{
+	{Channel: 0, Level: 0},
+	{Channel: 1, Level: -1, Offset: ptr[int16](1)},
+	{Channel: math.MaxUint16, Level: math.MinInt16, Offset: ptr[int16](math.MaxInt16)},
+	{Channel: 0x8000, Level: math.MaxInt16, Offset: ptr[int16](math.MinInt16)},
+	{Channel: 0x7fff, Level: -0x100},
+}
+
+func TestRoundtrip(t *testing.T) {
+	ins := instantiate(t)
+
+	for _, sample := range samples {
+		if got := ins.Roundtrip(t.Context(), sample); !equal(got, sample) {
+			t.Errorf("wanted: %+v, but got: %+v", sample, got)
+		}
+	}
+}
+
+func TestCalibrated(t *testing.T) {
+	ins := instantiate(t)
+
+	for _, sample := range samples[:3] {
+		want := Samples{}.Calibrate(t.Context(), sample)
+		if got := ins.Calibrated(t.Context(), sample); !equal(got, want) {
+			t.Errorf("wanted: %+v, but got: %+v", want, got)
+		}
+	}
+}
+
+func TestLevels(t *testing.T) {
+	ins := instantiate(t)
+
+	// The offsets are added to the levels
+	want := []int16{0, 0, -1, -1, -0x100}
+	if got := ins.Levels(t.Context(), samples); !slices.Equal(got, want) {
+		t.Errorf("wanted: %v, but got: %v", want, got)
+	}
+}
+
+func TestChannels(t *testing.T) {
+	ins := instantiate(t)
+
+	want := []uint16{0, 1, math.MaxUint16, 0x8000, 0x7fff}
+	if got := ins.Channels(t.Context(), samples); !slices.Equal(got, want) {
+		t.Errorf("wanted: %v, but got: %v", want, got)
+	}
+	if got := ins.Channels(t.Context(), nil); len(got) != 0 {
+		t.Errorf("wanted no channels, but got: %v", got)
+	}
+}
+
+func TestFirstOffset(t *testing.T) {
+	ins := instantiate(t)
+
+	if got, ok := ins.FirstOffset(t.Context(), samples[2:]); !ok || got != math.MaxInt16 {
+		t.Errorf("wanted: %d, but got: %d, %t", math.MaxInt16, got, ok)
+	}
+	if got, ok := ins.FirstOffset(t.Context(), samples[:1]); ok {
+		t.Errorf("wanted no offset, but got: %d", got)
+	}
+}
+
+func TestLoudestChannel(t *testing.T) {
+	ins := instantiate(t)
+
+	if got, ok := ins.LoudestChannel(t.Context(), samples); !ok || got != 0x8000 {
+		t.Errorf("wanted: %d, but got: %d, %t", 0x8000, got, ok)
+	}
+	if got, ok := ins.LoudestChannel(t.Context(), nil); ok {
+		t.Errorf("wanted no channel, but got: %d", got)
+	}
+}
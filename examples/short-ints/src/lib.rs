@@ -0,0 +1,36 @@
+wit_bindgen::generate!({
+    world: "short-ints",
+});
+
+use arcjet::short_ints::samples::calibrate;
+
+struct ShortIntsWorld;
+
+export!(ShortIntsWorld);
+
+impl Guest for ShortIntsWorld {
+    fn roundtrip(sample: Sample) -> Sample {
+        sample
+    }
+    fn calibrated(sample: Sample) -> Sample {
+        calibrate(sample)
+    }
+    fn levels(samples: Vec<Sample>) -> Vec<i16> {
+        samples
+            .iter()
+            .map(|sample| sample.level.saturating_add(sample.offset.unwrap_or(0)))
+            .collect()
+    }
+    fn channels(samples: Vec<Sample>) -> Vec<u16> {
+        samples.iter().map(|sample| sample.channel).collect()
+    }
+    fn first_offset(samples: Vec<Sample>) -> Option<i16> {
+        samples.iter().find_map(|sample| sample.offset)
+    }
+    fn loudest_channel(samples: Vec<Sample>) -> Option<u16> {
+        samples
+            .iter()
+            .max_by_key(|sample| sample.level)
+            .map(|sample| sample.channel)
+    }
+}
@@ -0,0 +1,38 @@
+package gravityruntime
+
+import "context"
+
+// importBatch holds the calls of the batched imports queued during the call
+// of an export.
+type importBatch struct {
+	calls []func()
+}
+
+type importBatchKey struct{}
+
+// BatchImports returns a context queueing the calls of the batched imports
+// made with it, along with the function making them, which the export calls
+// once it returns. A context already batching the calls is returned as is, so
+// they're made by the outermost export.
+func BatchImports(ctx context.Context) (context.Context, func()) {
+	if _, ok := ctx.Value(importBatchKey{}).(*importBatch); ok {
+		return ctx, func() {}
+	}
+	b := &importBatch{}
+	return context.WithValue(ctx, importBatchKey{}, b), func() {
+		for _, call := range b.calls {
+			call()
+		}
+		b.calls = nil
+	}
+}
+
+// CallImport makes the call of a batched import, or queues it if the context
+// is batching the calls of an export.
+func CallImport(ctx context.Context, call func()) {
+	if b, ok := ctx.Value(importBatchKey{}).(*importBatch); ok {
+		b.calls = append(b.calls, call)
+		return
+	}
+	call()
+}
@@ -0,0 +1,7 @@
+// Package gravityruntime holds the helpers shared by the bindings generated
+// by gravity with --runtime-package, which import them from this package
+// rather than generating them into each file.
+//
+// The helpers access the guest's memory with Wazero. The version of this
+// module must match the version of gravity generating the bindings.
+package gravityruntime
@@ -0,0 +1,56 @@
+package gravityruntime
+
+import (
+	"errors"
+	"sync"
+)
+
+// ErrorContext is a WIT `error-context`, carrying a debug message describing
+// an error.
+type ErrorContext struct {
+	DebugMessage string
+}
+
+func (e *ErrorContext) Error() string {
+	return e.DebugMessage
+}
+
+// errorContextTable holds the error contexts shared with guests, indexed by
+// the handles used to pass them across the Component Model boundary.
+type errorContextTable struct {
+	mu      sync.Mutex
+	next    uint32
+	entries map[uint32]*ErrorContext
+}
+
+// ErrorContexts is the table of the error contexts shared with the guests of
+// every set of bindings importing this package.
+var ErrorContexts = &errorContextTable{entries: map[uint32]*ErrorContext{}}
+
+// Insert adds the error context to the table, returning its handle.
+func (t *errorContextTable) Insert(e *ErrorContext) uint32 {
+	t.mu.Lock()
+	defer t.mu.Unlock()
+	// Handles start at 1, so the zero value is never a valid handle
+	t.next++
+	t.entries[t.next] = e
+	return t.next
+}
+
+// Get returns the error context with the handle, panicking if there's none.
+func (t *errorContextTable) Get(handle uint32) *ErrorContext {
+	t.mu.Lock()
+	defer t.mu.Unlock()
+	e, ok := t.entries[handle]
+	if !ok {
+		panic(errors.New("invalid error context handle"))
+	}
+	return e
+}
+
+// Drop removes the error context with the handle from the table.
+func (t *errorContextTable) Drop(handle uint32) {
+	t.mu.Lock()
+	defer t.mu.Unlock()
+	delete(t.entries, handle)
+}
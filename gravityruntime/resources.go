@@ -0,0 +1,202 @@
+package gravityruntime
+
+import (
+	"context"
+	"fmt"
+	"slices"
+	"sync"
+
+	"github.com/tetratelabs/wazero/api"
+)
+
+// ResourceTable holds the representations of resources shared with guests,
+// indexed by the handles used to pass them across the Component Model
+// boundary.
+type ResourceTable struct {
+	mu      sync.Mutex
+	next    uint32
+	entries map[uint32]any
+}
+
+// NewResourceTable returns an empty table of resources.
+func NewResourceTable() *ResourceTable {
+	return &ResourceTable{entries: map[uint32]any{}}
+}
+
+// Insert adds the representation of a resource to the table, returning its
+// handle.
+func (t *ResourceTable) Insert(rep any) uint32 {
+	t.mu.Lock()
+	defer t.mu.Unlock()
+	// Handles start at 1, so the zero value is never a valid handle
+	t.next++
+	t.entries[t.next] = rep
+	return t.next
+}
+
+// Get returns the representation of the resource with the handle, or nil
+// if there's none.
+func (t *ResourceTable) Get(handle uint32) any {
+	t.mu.Lock()
+	defer t.mu.Unlock()
+	return t.entries[handle]
+}
+
+// Remove removes the resource with the handle from the table.
+func (t *ResourceTable) Remove(handle uint32) {
+	t.mu.Lock()
+	defer t.mu.Unlock()
+	delete(t.entries, handle)
+}
+
+// StaleHandleError is returned for a handle that isn't valid for the instance
+// using it, e.g. one created by another instance or factory, or one used
+// after the resource was dropped or its instance closed.
+type StaleHandleError struct {
+	Resource string
+	Handle   uint32
+}
+
+func (e *StaleHandleError) Error() string {
+	return fmt.Sprintf("stale handle %d to a `%s` resource", e.Handle, e.Resource)
+}
+
+// HostResource is the host's representation of a resource, shared by the
+// handles to it, which is cleared once the resource is dropped.
+type HostResource struct {
+	mu  sync.Mutex
+	rep any
+}
+
+// NewHostResource returns the resource with the given representation.
+func NewHostResource(rep any) *HostResource {
+	return &HostResource{rep: rep}
+}
+
+// Get returns the representation of the resource, or nil once it's dropped.
+func (r *HostResource) Get() any {
+	if r == nil {
+		return nil
+	}
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	return r.rep
+}
+
+// Drop clears the representation of the resource, then calls its Drop
+// method, if it has one. Dropping the resource again does nothing.
+func (r *HostResource) Drop() {
+	if r == nil {
+		return
+	}
+	r.mu.Lock()
+	rep := r.rep
+	r.rep = nil
+	r.mu.Unlock()
+	if dropper, ok := rep.(interface{ Drop() }); ok {
+		dropper.Drop()
+	}
+}
+
+// BorrowHostResource returns the resource implemented by the host with the
+// handle, or a StaleHandleError if it isn't in the table.
+func BorrowHostResource(table *ResourceTable, handle uint32, name string) (*HostResource, error) {
+	resource, ok := table.Get(handle).(*HostResource)
+	if !ok {
+		return nil, &StaleHandleError{Resource: name, Handle: handle}
+	}
+	return resource, nil
+}
+
+// TakeHostResource is like BorrowHostResource, but also removes the resource
+// from the table, as its ownership is transferred to the host.
+func TakeHostResource(table *ResourceTable, handle uint32, name string) (*HostResource, error) {
+	table.mu.Lock()
+	defer table.mu.Unlock()
+	resource, ok := table.entries[handle].(*HostResource)
+	if !ok {
+		return nil, &StaleHandleError{Resource: name, Handle: handle}
+	}
+	delete(table.entries, handle)
+	return resource, nil
+}
+
+// GuestResource is a resource implemented by a guest, i.e. the instance
+// implementing it and the guest's representation of it.
+type GuestResource struct {
+	Module api.Module
+	Rep    uint32
+}
+
+// DropGuestResource removes a resource implemented by a guest from the
+// table, then calls its destructor in the guest. Dropping a handle again
+// from the host, i.e. without a module, does nothing, while a guest dropping
+// a handle created by another module, or dropping it again, gets a
+// StaleHandleError.
+func DropGuestResource(
+	ctx context.Context,
+	table *ResourceTable,
+	module api.Module,
+	handle uint32,
+	name string,
+	destructor string,
+) error {
+	resource, ok := table.Get(handle).(GuestResource)
+	if module != nil && (!ok || resource.Module != module) {
+		return &StaleHandleError{Resource: name, Handle: handle}
+	}
+	if !ok {
+		return nil
+	}
+	table.Remove(handle)
+	// Resources without a destructor aren't required to export one
+	dtor := resource.Module.ExportedFunction(destructor)
+	if dtor == nil {
+		return nil
+	}
+	_, err := dtor.Call(ctx, uint64(resource.Rep))
+	return err
+}
+
+// GuestResourceRep returns the guest's representation of the resource with
+// the handle, or a StaleHandleError if it wasn't created by the module.
+func GuestResourceRep(
+	table *ResourceTable,
+	module api.Module,
+	handle uint32,
+	name string,
+) (uint32, error) {
+	resource, ok := table.Get(handle).(GuestResource)
+	if !ok || resource.Module != module {
+		return 0, &StaleHandleError{Resource: name, Handle: handle}
+	}
+	return resource.Rep, nil
+}
+
+// GuestResourceHandles returns the handles to the resources in the table
+// created by the module, in the order they were created.
+func GuestResourceHandles(table *ResourceTable, module api.Module) []uint32 {
+	table.mu.Lock()
+	defer table.mu.Unlock()
+	var handles []uint32
+	for handle, rep := range table.entries {
+		if resource, ok := rep.(GuestResource); ok && resource.Module == module {
+			handles = append(handles, handle)
+		}
+	}
+	slices.Sort(handles)
+	return handles
+}
+
+// ReleaseGuestResources removes the resources created by the module from the
+// table once it's closed, without calling their destructors, so that their
+// handles are stale rather than referring to the closed module.
+func ReleaseGuestResources(table *ResourceTable, module api.Module) {
+	table.mu.Lock()
+	defer table.mu.Unlock()
+	for handle, rep := range table.entries {
+		if resource, ok := rep.(GuestResource); ok && resource.Module == module {
+			delete(table.entries, handle)
+		}
+	}
+}
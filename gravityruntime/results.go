@@ -0,0 +1,8 @@
+package gravityruntime
+
+// Result is a `result` nested in another value, such as a field of a
+// record, holding either its value or, if it's an error, a non-nil Err.
+type Result[T any] struct {
+	Value T
+	Err   error
+}
@@ -0,0 +1,154 @@
+package gravityruntime
+
+import (
+	"context"
+	"encoding/binary"
+	"errors"
+	"unicode/utf16"
+	"unsafe"
+
+	"github.com/tetratelabs/wazero/api"
+)
+
+// WriteString allocates the string in the Wasm memory with the guest's
+// `cabi_realloc`, then writes it, returning its pointer and length.
+func WriteString(
+	ctx context.Context,
+	s string,
+	memory api.Memory,
+	realloc api.Function,
+) (uint64, uint64, error) {
+	if len(s) == 0 {
+		return 1, 0, nil
+	}
+
+	results, err := realloc.Call(ctx, 0, 0, 1, uint64(len(s)))
+	if err != nil {
+		return 1, 0, err
+	}
+	ptr := results[0]
+	ok := memory.Write(uint32(ptr), []byte(s))
+	if !ok {
+		return 1, 0, errors.New("failed to write string to wasm memory")
+	}
+	return uint64(ptr), uint64(len(s)), nil
+}
+
+// ReadString reads a string of the given length from the Wasm memory, as
+// encoded by guests using the `utf8` encoding.
+func ReadString(memory api.Memory, ptr uint32, length uint32) (string, bool) {
+	buf, ok := memory.Read(ptr, length)
+	if !ok {
+		return "", false
+	}
+	return string(buf), true
+}
+
+// ViewString is like ReadString, but returns a string sharing the Wasm
+// memory rather than a copy of it. The string is only valid until the host
+// function it's passed to returns, and mustn't be retained past it.
+func ViewString(memory api.Memory, ptr uint32, length uint32) (string, bool) {
+	buf, ok := memory.Read(ptr, length)
+	if !ok {
+		return "", false
+	}
+	return unsafe.String(unsafe.SliceData(buf), len(buf)), true
+}
+
+// ReadStringUTF16 reads a string of the given number of UTF-16 code units
+// from the Wasm memory, as encoded by guests using the `utf16` encoding.
+func ReadStringUTF16(memory api.Memory, ptr uint32, units uint32) (string, bool) {
+	buf, ok := memory.Read(ptr, 2*units)
+	if !ok {
+		return "", false
+	}
+	codes := make([]uint16, units)
+	for i := range codes {
+		codes[i] = binary.LittleEndian.Uint16(buf[2*i:])
+	}
+	return string(utf16.Decode(codes)), true
+}
+
+// WriteStringUTF16 is like WriteString, but encodes the string with UTF-16
+// for guests using the `utf16` encoding, returning its number of code units.
+func WriteStringUTF16(
+	ctx context.Context,
+	s string,
+	memory api.Memory,
+	realloc api.Function,
+) (uint64, uint64, error) {
+	codes := utf16.Encode([]rune(s))
+	if len(codes) == 0 {
+		return 2, 0, nil
+	}
+	buf := make([]byte, 2*len(codes))
+	for i, code := range codes {
+		binary.LittleEndian.PutUint16(buf[2*i:], code)
+	}
+
+	results, err := realloc.Call(ctx, 0, 0, 2, uint64(len(buf)))
+	if err != nil {
+		return 2, 0, err
+	}
+	ptr := results[0]
+	ok := memory.Write(uint32(ptr), buf)
+	if !ok {
+		return 2, 0, errors.New("failed to write string to wasm memory")
+	}
+	return uint64(ptr), uint64(len(codes)), nil
+}
+
+// The bit set in the length of the strings encoded with UTF-16 rather than
+// Latin-1, by guests using the `latin1+utf16` encoding.
+const latin1UTF16Tag = 1 << 31
+
+// ReadStringLatin1UTF16 reads a string from the Wasm memory, as encoded by
+// guests using the `latin1+utf16` encoding, where the length of the string
+// tells whether it's encoded with Latin-1 or UTF-16.
+func ReadStringLatin1UTF16(memory api.Memory, ptr uint32, length uint32) (string, bool) {
+	if length&latin1UTF16Tag != 0 {
+		return ReadStringUTF16(memory, ptr, length&^latin1UTF16Tag)
+	}
+	buf, ok := memory.Read(ptr, length)
+	if !ok {
+		return "", false
+	}
+	runes := make([]rune, len(buf))
+	for i, b := range buf {
+		runes[i] = rune(b)
+	}
+	return string(runes), true
+}
+
+// WriteStringLatin1UTF16 is like WriteString, but encodes the string with
+// Latin-1 if it can, and with UTF-16 otherwise, for guests using the
+// `latin1+utf16` encoding.
+func WriteStringLatin1UTF16(
+	ctx context.Context,
+	s string,
+	memory api.Memory,
+	realloc api.Function,
+) (uint64, uint64, error) {
+	buf := make([]byte, 0, len(s))
+	for _, r := range s {
+		if r > 0xff {
+			ptr, units, err := WriteStringUTF16(ctx, s, memory, realloc)
+			return ptr, units | latin1UTF16Tag, err
+		}
+		buf = append(buf, byte(r))
+	}
+	if len(buf) == 0 {
+		return 2, 0, nil
+	}
+
+	results, err := realloc.Call(ctx, 0, 0, 2, uint64(len(buf)))
+	if err != nil {
+		return 2, 0, err
+	}
+	ptr := results[0]
+	ok := memory.Write(uint32(ptr), buf)
+	if !ok {
+		return 2, 0, errors.New("failed to write string to wasm memory")
+	}
+	return uint64(ptr), uint64(len(buf)), nil
+}
@@ -0,0 +1,54 @@
+package gravityruntime
+
+import (
+	"errors"
+	"fmt"
+
+	"github.com/tetratelabs/wazero/sys"
+)
+
+// GuestTrapError is returned when a call of an export fails in the guest,
+// e.g. as it reached an `unreachable` instruction, accessed its memory out of
+// bounds or exited, wrapping the error returned by Wazero.
+type GuestTrapError struct {
+	// Function is the name of the core Wasm function that was called.
+	Function string
+	// Exited is true if the guest exited, e.g. with `proc_exit`, or was closed
+	// during the call, rather than trapped.
+	Exited bool
+	// ExitCode is the exit code of the guest, if it exited.
+	ExitCode uint32
+	Err      error
+}
+
+func (e *GuestTrapError) Error() string {
+	if e.Exited {
+		return fmt.Sprintf("guest exited with code %d during the call of %s", e.ExitCode, e.Function)
+	}
+	return fmt.Sprintf("guest trapped during the call of %s: %v", e.Function, e.Err)
+}
+
+func (e *GuestTrapError) Unwrap() error {
+	return e.Err
+}
+
+// GuestTrap wraps the error of the call of the function in a GuestTrapError.
+func GuestTrap(function string, err error) error {
+	trap := &GuestTrapError{Function: function, Err: err}
+	var exit *sys.ExitError
+	if errors.As(err, &exit) {
+		trap.Exited = true
+		trap.ExitCode = exit.ExitCode()
+	}
+	return trap
+}
+
+// GuestError is the error returned by the guest, as the `err` case of a
+// `result`, rather than an error of the call itself.
+type GuestError struct {
+	Message string
+}
+
+func (e *GuestError) Error() string {
+	return e.Message
+}